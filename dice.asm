@@ -0,0 +1,38 @@
+; ============================================================
+; Programm: 10 Würfelwürfe mit dem Zufallsgenerator
+; Erwartet den Zufallsgenerator an $E100 (siehe src/rng.rs);
+; jeder Lesezugriff auf DATA liefert eine neue Zahl.
+; Ergebnis: Augenzahlen 1-6 ab ROLLS, Summe in D6
+; Wiederholbar mit: mc68000 --rng-seed=42 dice.asm
+; ============================================================
+
+RNG_DATA:   EQU     $E100
+
+            ORG     $2000
+ROLLS:      DS.B    10
+
+            ORG     $1000
+
+START:      MOVEA.L #RNG_DATA, A0
+            MOVE.L  #ROLLS, D5      ; Zeiger (ADDQ geht nur auf Datenregister)
+            MOVEQ   #0, D6          ; Summe
+            MOVEQ   #9, D7          ; 10 Würfe (DBRA zählt bis -1)
+
+ROLL:       MOVEQ   #0, D0
+            MOVE.B  (A0), D0        ; Zufallsbyte 0-255
+            CMPI.W  #252, D0        ; 252-255 verwerfen, sonst wäre 1-4 häufiger
+            BGE     ROLL
+MOD6:       CMPI.W  #6, D0          ; D0 mod 6 durch Abziehen
+            BLT     STORE
+            SUBQ.W  #6, D0
+            BRA     MOD6
+STORE:      ADDQ.W  #1, D0          ; 1-6
+            MOVEA.L D5, A1
+            MOVE.B  D0, (A1)
+            ADDQ.L  #1, D5
+            ADD.W   D0, D6
+            DBRA    D7, ROLL
+
+            SIMHALT
+
+            END     START
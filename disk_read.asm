@@ -0,0 +1,47 @@
+; ============================================================
+; Programm: Block 0 von der Disk lesen und Signatur prüfen
+; Erwartet eine Disk an $E000 (siehe src/disk.rs); Block 0
+; beginnt mit "M68K". Ergebnis in D7: 1 = ok, -1 = Fehler
+; ============================================================
+
+DISK_BLOCK:   EQU     $E000
+DISK_ADDRESS: EQU     $E004
+DISK_COUNT:   EQU     $E008
+DISK_COMMAND: EQU     $E00C
+DISK_STATUS:  EQU     $E010
+
+            ORG     $2000
+BUFFER:     DS.B    512
+
+            ORG     $1000
+
+START:      MOVEQ   #0, D0
+            MOVEA.L #DISK_BLOCK, A0
+            MOVE.L  D0, (A0)        ; Block 0
+            MOVE.L  #$2000, D0
+            MOVEA.L #DISK_ADDRESS, A0
+            MOVE.L  D0, (A0)        ; nach BUFFER
+            MOVEQ   #1, D0
+            MOVEA.L #DISK_COUNT, A0
+            MOVE.L  D0, (A0)        ; ein Block
+            MOVEA.L #DISK_COMMAND, A0
+            MOVE.L  D0, (A0)        ; 1 = lesen
+
+            MOVEA.L #DISK_STATUS, A0
+WAIT:       MOVE.L  (A0), D1
+            CMP.L   #1, D1          ; BUSY
+            BEQ     WAIT
+            CMP.L   #2, D1          ; DONE ohne ERROR
+            BNE     FAIL
+
+            MOVEA.L #BUFFER, A1
+            MOVE.L  (A1), D1
+            CMP.L   #$4D36384B, D1  ; "M68K"
+            BNE     FAIL
+            MOVEQ   #1, D7
+            SIMHALT
+
+FAIL:       MOVEQ   #-1, D7
+            SIMHALT
+
+            END     START
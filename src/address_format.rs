@@ -0,0 +1,122 @@
+// Adressen relativ zu Labels darstellen, z.B. für Disassembly, Trace und Speicheransicht
+// Genaue Treffer erscheinen als Label, Adressen in einem Datenobjekt (Label plus die Bytes
+// seiner DC/DS-Zeilen) als "BUFFER+8" und Adressen zwischen zwei Code-Labels derselben
+// Section als "LOOP+$0006". Alles andere bleibt eine Hexzahl.
+
+use crate::program::{Program, SectionKind};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Label {
+    address: u32,
+    name: String,
+    /// Bytes des Datenobjekts; None bei Code-Labels und Labels ohne DC/DS
+    size: Option<u32>,
+    /// Code-Section, in der das Label steht
+    code: Option<Range<u32>>,
+}
+
+/// Symboltabelle eines Programms, nach Adresse sortiert
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressFormatter {
+    labels: Vec<Label>,
+}
+
+impl AddressFormatter {
+    pub fn new(program: &Program) -> Self {
+        let mut labels: Vec<Label> = program
+            .symbols
+            .iter()
+            .map(|(name, &address)| Label {
+                address,
+                name: name.clone(),
+                size: program.object_sizes.get(name).copied(),
+                code: program
+                    .section_at(address)
+                    .filter(|section| section.kind == SectionKind::Code)
+                    .map(|section| section.start..section.end),
+            })
+            .collect();
+        // Bei gleicher Adresse gewinnt das alphabetisch erste Label (wie `Program::symbol_at`)
+        labels.sort_by(|a, b| a.address.cmp(&b.address).then(a.name.cmp(&b.name)));
+        labels.dedup_by_key(|label| label.address);
+        AddressFormatter { labels }
+    }
+
+    /// `address` relativ zu einem Label; None, wenn kein Label passt
+    pub fn format(&self, address: u32) -> Option<String> {
+        let index = self
+            .labels
+            .partition_point(|label| label.address <= address);
+        let label = &self.labels[index.checked_sub(1)?];
+        let offset = address - label.address;
+        if offset == 0 {
+            return Some(label.name.clone());
+        }
+        match (label.size, &label.code) {
+            (Some(size), _) if offset < size => Some(format!("{}+{}", label.name, offset)),
+            (None, Some(code)) if code.contains(&address) => {
+                Some(format!("{}+${:04X}", label.name, offset))
+            }
+            _ => None,
+        }
+    }
+
+    /// Wie `format`, sonst als $-Hexzahl
+    pub fn display(&self, address: u32) -> String {
+        self.format(address)
+            .unwrap_or_else(|| format!("${:06X}", address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Assembler;
+
+    const SOURCE: &str = "        ORG     $1000
+START:  MOVEQ   #1, D0
+        MOVEQ   #2, D1
+LOOP:   SUBQ.L  #1, D0
+        BNE     LOOP
+        SIMHALT
+BUFFER: DS.B    16
+TABLE:
+        DC.W    1, 2
+        DC.L    3
+";
+
+    fn formatter() -> AddressFormatter {
+        let program = Assembler::new().assemble_source(SOURCE).unwrap();
+        assert_eq!(program.object_sizes["BUFFER"], 16);
+        assert_eq!(program.object_sizes["TABLE"], 8);
+        AddressFormatter::new(&program)
+    }
+
+    #[test]
+    fn test_exact_match_and_inside_object() {
+        let formatter = formatter();
+        assert_eq!(formatter.format(0x1000).as_deref(), Some("START"));
+        assert_eq!(formatter.format(0x1004).as_deref(), Some("LOOP"));
+        let buffer = 0x100A;
+        assert_eq!(formatter.format(buffer).as_deref(), Some("BUFFER"));
+        assert_eq!(formatter.format(buffer + 8).as_deref(), Some("BUFFER+8"));
+        assert_eq!(formatter.format(buffer + 15).as_deref(), Some("BUFFER+15"));
+        // TABLE über zwei DC-Zeilen
+        assert_eq!(formatter.format(buffer + 16).as_deref(), Some("TABLE"));
+        assert_eq!(formatter.format(buffer + 22).as_deref(), Some("TABLE+6"));
+        // Hinter dem letzten Objekt
+        assert_eq!(formatter.format(buffer + 24), None);
+        assert_eq!(formatter.display(buffer + 24), "$001022");
+    }
+
+    #[test]
+    fn test_between_labels_and_before_first() {
+        let formatter = formatter();
+        assert_eq!(formatter.format(0x1002).as_deref(), Some("START+$0002"));
+        assert_eq!(formatter.format(0x1008).as_deref(), Some("LOOP+$0004"));
+        assert_eq!(formatter.format(0x0FFE), None);
+        assert_eq!(formatter.display(0x0800), "$000800");
+        assert_eq!(AddressFormatter::default().format(0x1000), None);
+    }
+}
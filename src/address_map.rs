@@ -0,0 +1,401 @@
+// Übersicht über den 24-Bit-Adressraum
+// Sammelt die belegten Bereiche (Vektortabelle, Sections, Geräteregister, Stack) und bildet
+// Adressen auf eine Position 0.0..=1.0 entlang eines Balkens ab. Linear wären ein paar Bytes
+// Code neben 16 MB unsichtbar; deshalb bekommt jeder Abschnitt zwischen zwei Bereichsgrenzen
+// eine Breite proportional zum Logarithmus seiner Länge.
+//
+// Dieselben Bereiche gibt es als sortierte Liste (`memory_map`) für Skripte und für
+// `mc68000 --print-map`; `MapTable` formatiert sie als Tabelle.
+
+use crate::bus::SystemBus;
+use crate::program::{Program, SectionKind};
+use std::fmt;
+use std::ops::Range;
+
+/// Größe des Adressraums (24 Adressbits)
+pub const ADDRESS_SPACE: u32 = 0x0100_0000;
+
+/// Ende der Exception-Vektortabelle (256 Vektoren à 4 Bytes)
+pub const VECTOR_TABLE_END: u32 = 0x400;
+
+/// So viel Stack wird mindestens unterhalb der Stack-Obergrenze gezeigt
+const STACK_WINDOW: u32 = 0x100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+    /// Der ganze Speicher; das Modell kennt kein ROM, alles ist beschreibbar
+    Ram,
+    Vectors,
+    Code,
+    Data,
+    Device,
+    Stack,
+}
+
+/// Benannter Adressbereich für die Karte
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub name: String,
+    pub kind: RegionKind,
+    pub range: Range<u32>,
+}
+
+impl Region {
+    pub fn len(&self) -> u32 {
+        self.range.end - self.range.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Text für den Tooltip: Name, Bereich und Größe
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: ${:06X}-${:06X} ({} Bytes)",
+            self.name,
+            self.range.start,
+            self.range.end.saturating_sub(1),
+            self.len()
+        )
+    }
+}
+
+/// Alle bekannten Bereiche; `stack_top` ist der Anfangswert von A7, `sp` der aktuelle
+pub fn regions(
+    program: Option<&Program>,
+    bus: &SystemBus,
+    stack_top: Option<u32>,
+    sp: u32,
+) -> Vec<Region> {
+    let mut regions = vec![Region {
+        name: "Vektortabelle".to_string(),
+        kind: RegionKind::Vectors,
+        range: 0..VECTOR_TABLE_END,
+    }];
+
+    if let Some(program) = program {
+        regions.extend(
+            program
+                .sections
+                .iter()
+                .filter(|s| !s.is_empty())
+                .map(|s| Region {
+                    name: s.name.clone(),
+                    kind: match s.kind {
+                        SectionKind::Code => RegionKind::Code,
+                        SectionKind::Data => RegionKind::Data,
+                    },
+                    range: s.start..s.end,
+                }),
+        );
+    }
+
+    regions.extend(bus.devices().filter_map(|device| {
+        device.registers().map(|range| Region {
+            name: device.name().to_string(),
+            kind: RegionKind::Device,
+            range,
+        })
+    }));
+
+    // Stack: von der Obergrenze bis zum aktuellen SP, mindestens STACK_WINDOW Bytes
+    let top = stack_top.unwrap_or(sp).min(ADDRESS_SPACE);
+    if top > 0 {
+        let bottom = sp.min(top).min(top.saturating_sub(STACK_WINDOW));
+        regions.push(Region {
+            name: "Stack".to_string(),
+            kind: RegionKind::Stack,
+            range: bottom..top,
+        });
+    }
+
+    regions
+}
+
+impl RegionKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RegionKind::Ram => "RAM",
+            RegionKind::Vectors => "vectors",
+            RegionKind::Code => "code",
+            RegionKind::Data => "data",
+            RegionKind::Device => "MMIO",
+            RegionKind::Stack => "stack",
+        }
+    }
+}
+
+/// Eintrag der Speicherkarte; `source` ist das Modul einer Section (Module Mode)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapEntry {
+    pub name: String,
+    pub kind: RegionKind,
+    pub range: Range<u32>,
+    pub source: Option<String>,
+}
+
+impl MapEntry {
+    pub fn origin(&self) -> u32 {
+        self.range.start
+    }
+
+    pub fn len(&self) -> u32 {
+        self.range.end - self.range.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+}
+
+/// Wie `regions`, aber mit dem RAM als Hintergrund, den Modulen der Sections und dem Stack
+/// nur, wenn eine Obergrenze (`STACK` oder Vorgabe) bekannt ist. Sortiert nach Anfang,
+/// umschließende Bereiche vor den enthaltenen.
+pub fn memory_map(
+    program: Option<&Program>,
+    bus: &SystemBus,
+    stack_top: Option<u32>,
+    sp: u32,
+) -> Vec<MapEntry> {
+    let module = |region: &Region| {
+        let section = program?
+            .sections
+            .iter()
+            .find(|s| s.name == region.name && s.start == region.range.start)?;
+        section.module.clone()
+    };
+
+    let mut entries = vec![MapEntry {
+        name: "RAM".to_string(),
+        kind: RegionKind::Ram,
+        range: 0..ADDRESS_SPACE,
+        source: None,
+    }];
+    entries.extend(
+        regions(program, bus, stack_top, sp)
+            .into_iter()
+            .filter(|region| region.kind != RegionKind::Stack || stack_top.is_some())
+            .map(|region| MapEntry {
+                source: match region.kind {
+                    RegionKind::Code | RegionKind::Data => module(&region),
+                    _ => None,
+                },
+                name: region.name,
+                kind: region.kind,
+                range: region.range,
+            }),
+    );
+    entries.sort_by_key(|entry| (entry.range.start, std::cmp::Reverse(entry.range.end)));
+    entries
+}
+
+/// Spaltenweise ausgerichtete Tabelle der Speicherkarte
+pub struct MapTable<'a>(pub &'a [MapEntry]);
+
+impl fmt::Display for MapTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .0
+            .iter()
+            .map(|entry| entry.name.chars().count())
+            .chain([4])
+            .max()
+            .unwrap_or(4);
+        writeln!(
+            f,
+            "{:<7}  {:<7}  {:>8}  {:<7}  {:<width$}  SOURCE",
+            "START", "END", "LENGTH", "KIND", "NAME"
+        )?;
+        for entry in self.0 {
+            writeln!(
+                f,
+                "${:06X}  ${:06X}  {:>8}  {:<7}  {:<width$}  {}",
+                entry.range.start,
+                entry.range.end.saturating_sub(1),
+                entry.len(),
+                entry.kind.name(),
+                entry.name,
+                entry.source.as_deref().unwrap_or("-")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Stückweise logarithmische Abbildung Adresse <-> Position auf dem Balken
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapScale {
+    /// (Adresse, Position), aufsteigend; beginnt bei (0, 0.0) und endet bei (ADDRESS_SPACE, 1.0)
+    stops: Vec<(u32, f32)>,
+}
+
+impl MapScale {
+    /// Skala, in der jede Grenze der `regions` ein Stützpunkt ist
+    pub fn new(regions: &[Region]) -> Self {
+        let mut bounds: Vec<u32> = regions
+            .iter()
+            .flat_map(|region| [region.range.start, region.range.end])
+            .chain([0, ADDRESS_SPACE])
+            .map(|address| address.min(ADDRESS_SPACE))
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        // Gewicht eines Abschnitts: log2 der Länge, mindestens 1 (auch für 1 Byte)
+        let weights: Vec<f32> = bounds
+            .windows(2)
+            .map(|pair| 1.0 + ((pair[1] - pair[0]) as f32).log2())
+            .collect();
+        let total: f32 = weights.iter().sum();
+
+        let mut stops = vec![(0, 0.0)];
+        let mut position = 0.0;
+        for (pair, weight) in bounds.windows(2).zip(&weights) {
+            position += weight / total;
+            stops.push((pair[1], position));
+        }
+        if let Some(last) = stops.last_mut() {
+            last.1 = 1.0; // Rundungsfehler
+        }
+        MapScale { stops }
+    }
+
+    /// Position (0.0..=1.0) einer Adresse; innerhalb eines Abschnitts linear
+    pub fn position(&self, address: u32) -> f32 {
+        let address = address.min(ADDRESS_SPACE);
+        let index = self.stops.partition_point(|&(stop, _)| stop <= address);
+        if index >= self.stops.len() {
+            return 1.0;
+        }
+        let (start, from) = self.stops[index - 1];
+        let (end, to) = self.stops[index];
+        from + (to - from) * (address - start) as f32 / (end - start) as f32
+    }
+
+    /// Adresse an einer Position (Umkehrung von `position`, z.B. für Mausposition)
+    pub fn address(&self, position: f32) -> u32 {
+        let position = position.clamp(0.0, 1.0);
+        let index = self.stops.partition_point(|&(_, stop)| stop <= position);
+        if index >= self.stops.len() {
+            return ADDRESS_SPACE - 1;
+        }
+        let (start, from) = self.stops[index - 1];
+        let (end, to) = self.stops[index];
+        let offset = ((position - from) / (to - from) * (end - start) as f32) as u32;
+        (start + offset).min(end - 1)
+    }
+
+    /// Pixelbereich eines Adressbereichs auf einem Balken von `width` Pixeln
+    pub fn span(&self, range: &Range<u32>, width: f32) -> Range<f32> {
+        self.position(range.start) * width..self.position(range.end) * width
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disk::Disk;
+    use crate::program::Section;
+
+    fn region(kind: RegionKind, range: Range<u32>) -> Region {
+        Region {
+            name: format!("{:?}", kind),
+            kind,
+            range,
+        }
+    }
+
+    #[test]
+    fn test_scale_endpoints_and_monotonic() {
+        let scale = MapScale::new(&[
+            region(RegionKind::Code, 0x1000..0x1010),
+            region(RegionKind::Stack, 0x7F00..0x8000),
+        ]);
+        assert_eq!(scale.position(0), 0.0);
+        assert_eq!(scale.position(ADDRESS_SPACE), 1.0);
+        assert_eq!(scale.position(0xFFFF_FFFF), 1.0);
+
+        let mut previous = -1.0;
+        for address in (0..ADDRESS_SPACE).step_by(0x1234) {
+            let position = scale.position(address);
+            assert!(position > previous, "${:X}", address);
+            previous = position;
+        }
+    }
+
+    #[test]
+    fn test_small_regions_stay_visible() {
+        let scale = MapScale::new(&[
+            region(RegionKind::Code, 0x1000..0x1010),
+            region(RegionKind::Device, 0xE000..0xE004),
+        ]);
+        // Linear wären 16 Bytes von 16 MB etwa 0,0001 % des Balkens
+        let code = scale.span(&(0x1000..0x1010), 1000.0);
+        assert!(code.end - code.start > 20.0, "{:?}", code);
+        let device = scale.span(&(0xE000..0xE004), 1000.0);
+        assert!(device.end - device.start > 10.0, "{:?}", device);
+        // Der große leere Rest oberhalb bekommt trotzdem den größten Anteil
+        let rest = scale.span(&(0xE004..ADDRESS_SPACE), 1000.0);
+        assert!(rest.end - rest.start > code.end - code.start);
+    }
+
+    #[test]
+    fn test_address_inverts_position() {
+        let scale = MapScale::new(&[region(RegionKind::Code, 0x1000..0x1100)]);
+        for address in [0, 0x3FF, 0x1000, 0x1080, 0x10FF, 0x1100, 0x80_0000] {
+            let back = scale.address(scale.position(address));
+            assert!(
+                back.abs_diff(address) <= address / 1000 + 1,
+                "${:X} -> ${:X}",
+                address,
+                back
+            );
+        }
+        assert_eq!(scale.address(0.0), 0);
+        assert_eq!(scale.address(1.0), ADDRESS_SPACE - 1);
+    }
+
+    #[test]
+    fn test_regions_from_program_devices_and_stack() {
+        let program = Program {
+            sections: vec![
+                Section {
+                    name: "CODE".to_string(),
+                    kind: SectionKind::Code,
+                    start: 0x1000,
+                    end: 0x1020,
+                    module: None,
+                },
+                Section {
+                    name: "DATA".to_string(),
+                    kind: SectionKind::Data,
+                    start: 0x2000,
+                    end: 0x2000,
+                    module: None,
+                },
+            ],
+            ..Program::default()
+        };
+        let mut bus = SystemBus::new();
+        bus.attach(Box::new(Disk::new(0xE100, 4)));
+
+        let regions = regions(Some(&program), &bus, Some(0x8000), 0x7FF0);
+        let kinds: Vec<RegionKind> = regions.iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                RegionKind::Vectors,
+                RegionKind::Code,
+                RegionKind::Device,
+                RegionKind::Stack
+            ]
+        );
+        assert_eq!(regions[1].describe(), "CODE: $001000-$00101F (32 Bytes)");
+        assert_eq!(regions[3].range, 0x7F00..0x8000);
+
+        // Ohne Stack-Vorgabe und mit A7 = 0 gibt es keinen Stackbereich
+        let regions = super::regions(None, &SystemBus::new(), None, 0);
+        assert_eq!(regions.len(), 1);
+    }
+}
@@ -5,24 +5,187 @@
 #![allow(clippy::manual_strip)]
 #![allow(clippy::needless_return)]
 
-use std::collections::HashMap;
+use crate::breakpoints::{parse_register, AssertTarget, Assertion, Comparison};
+use crate::cpu::echo;
+use crate::isa::{self, OperandClass, Size};
+use crate::program::{Program, Section, SectionKind};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
 
 pub struct Assembler {
     labels: HashMap<String, u32>,
     instructions: Vec<AssemblyInstruction>,
+    sections: Vec<Section>,
+    data: Vec<(u32, u8)>,                    // DC-Bytes des letzten Laufs
+    object_sizes: HashMap<String, u32>,      // Label -> Bytes seiner DC/DS-Zeilen
+    assert_directives: Vec<AssertDirective>, // erster Pass, ausgewertet im zweiten
+    assertions: Vec<Assertion>,
+    errors: Vec<AsmError>,
+    warnings: Vec<AsmError>,
+    notes: Vec<String>,      // Hinweise ohne Fehlerwirkung (ignorierte Direktiven)
+    entry: Option<u32>,      // Startadresse aus "END label"
+    initial_sp: Option<u32>, // aus "STACK $addr" / "INITSP $addr"
+    section_name: Option<String>, // aktuelle SECTION
+    legacy_simhalt: bool,    // SIMHALT als $4E72 (veraltet)
+    optimize: bool,          // Peephole-Pass vor der Kodierung
+    strict_moveq: bool,      // MOVEQ #128..255 ablehnen statt mit Warnung kodieren
+    provisional: bool,       // erster Pass: unbekannte Symbole als Platzhalter
+    // Module Mode: XDEF/XREF des letzten Laufs und alle bisher assemblierten Module
+    xdefs: Vec<(String, usize)>,
+    xrefs: Vec<(String, usize)>,
+    modules: Vec<Module>,
+}
+
+/// Ein mit `assemble_module` assembliertes Modul
+#[derive(Debug, Clone)]
+struct Module {
+    name: String,
+    source: String,
+    exports: HashMap<String, u32>,
+    imports: Vec<(String, usize)>, // (Symbol, Zeile)
+}
+
+/// Ergebnis von `Assembler::operand_kind`: die Adressierungsart wie in der Instruktionstabelle,
+/// bei Registern mit der Registernummer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OperandKind {
+    DataRegister(u8),
+    AddressRegister(u8),
+    Indirect(u8),
+    PostIncrement,
+    PreDecrement,
+    Displacement,
+    Absolute,
+    Immediate,
+    ConditionCodes,
+}
+
+impl OperandKind {
+    fn class(self) -> OperandClass {
+        match self {
+            OperandKind::DataRegister(_) => OperandClass::DataRegister,
+            OperandKind::AddressRegister(_) => OperandClass::AddressRegister,
+            OperandKind::Indirect(_) => OperandClass::Indirect,
+            OperandKind::PostIncrement => OperandClass::PostIncrement,
+            OperandKind::PreDecrement => OperandClass::PreDecrement,
+            OperandKind::Displacement => OperandClass::Displacement,
+            OperandKind::Absolute => OperandClass::Absolute,
+            OperandKind::Immediate => OperandClass::Immediate,
+            OperandKind::ConditionCodes => OperandClass::ConditionCodes,
+        }
+    }
+}
+
+/// Kategorie eines Assembler-Fehlers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsmErrorKind {
+    UnknownInstruction,
+    OperandCount,
+    IllegalOperand, // Adressierungsart an dieser Position nicht erlaubt
+    InvalidSize,
+    InvalidOperand, // Operand passt syntaktisch, lässt sich aber nicht kodieren
+    UndefinedSymbol,
+    ValueOutOfRange,
+    MisplacedLabel, // Label auf einer Zeile, die keins tragen darf (ORG)
+    LengthChanged,  // zweiter Pass kodiert anders lang als im ersten reserviert
+    Deprecated,     // nur als Warnung
+    SignExtended,   // nur als Warnung: MOVEQ #128..255 (siehe `set_strict_moveq`)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize, // 1-basiert
+    pub kind: AsmErrorKind,
+    pub message: String,
+    /// Betroffene Zeichen in der Quellzeile (0-basiert, Ende exklusiv, Tab = 1 Zeichen),
+    /// z.B. der fehlerhafte Operand; None = ganze Zeile
+    pub columns: Option<Range<usize>>,
+}
+
+impl AsmError {
+    fn new(line: usize, kind: AsmErrorKind, message: String) -> Self {
+        AsmError {
+            line,
+            kind,
+            message,
+            columns: None,
+        }
+    }
+
+    fn at(mut self, columns: Option<Range<usize>>) -> Self {
+        self.columns = columns;
+        self
+    }
+}
+
+/// Zeichenbereich von `part` in `line`; `part` muss ein Teilstring (Slice) von `line` sein
+pub(crate) fn char_columns(line: &str, part: &str) -> Range<usize> {
+    let offset = (part.as_ptr() as usize).saturating_sub(line.as_ptr() as usize);
+    let offset = offset.min(line.len());
+    let start = line[..offset].chars().count();
+    start..start + part.chars().count()
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
 }
 
 #[derive(Debug, Clone)]
 struct AssemblyInstruction {
+    line: usize,
     address: u32,
     #[allow(dead_code)]
     label: Option<String>,
     mnemonic: String,
+    size_suffix: Option<String>, // z.B. "L" bei MOVE.L
     operands: Vec<String>,
+    // Spalten in der Quellzeile, für Fehlermarkierungen
+    mnemonic_columns: Range<usize>,
+    operand_columns: Vec<Range<usize>>,
+    // Erst nach dem zweiten Pass gesetzt
     machine_code: Option<u16>,
-    #[allow(dead_code)]
-    extension_word: Option<u16>, // Für Adressen bei MOVE.L etc.
-    size: u32, // Größe der Instruktion in Bytes (2 oder 4)
+    extensions: Vec<u16>,
+    size: u32, // im ersten Pass reservierte Bytes (Opcode plus Extension Words)
+}
+
+impl AssemblyInstruction {
+    /// Vom ersten bis zum letzten Operanden; None ohne Operanden
+    fn all_operand_columns(&self) -> Option<Range<usize>> {
+        let first = self.operand_columns.first()?;
+        let last = self.operand_columns.last()?;
+        Some(first.start..last.end)
+    }
+}
+
+/// Ergebnis der Direktiven-Behandlung im ersten Pass
+enum DirectiveAction {
+    None,    // keine Direktive: als Daten oder Instruktion weiterparsen
+    Done,    // Zeile vollständig behandelt
+    Ignored, // reine Listing-Steuerung (OPT, LIST, TTL, ...)
+    End,     // END: Rest des Quelltexts ignorieren
+}
+
+/// DC-Direktive aus dem ersten Pass; Werte werden erst im zweiten Pass ausgewertet,
+/// damit Labels auch vorwärts referenziert werden können
+#[derive(Debug, Clone)]
+struct DataDirective {
+    line: usize,
+    address: u32,
+    size: Size,
+    values: Vec<String>,
+}
+
+/// ASSERT-Direktive aus dem ersten Pass; wie bei DC werden die Ausdrücke erst im zweiten
+/// Pass ausgewertet
+#[derive(Debug, Clone)]
+struct AssertDirective {
+    line: usize,
+    address: u32,
+    size: Size, // nur für Speicher
+    text: String,
 }
 
 impl Default for Assembler {
@@ -36,233 +199,1142 @@ impl Assembler {
         Assembler {
             labels: HashMap::new(),
             instructions: Vec::new(),
+            sections: Vec::new(),
+            data: Vec::new(),
+            object_sizes: HashMap::new(),
+            assert_directives: Vec::new(),
+            assertions: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            notes: Vec::new(),
+            entry: None,
+            initial_sp: None,
+            section_name: None,
+            legacy_simhalt: false,
+            optimize: false,
+            strict_moveq: true,
+            provisional: false,
+            xdefs: Vec::new(),
+            xrefs: Vec::new(),
+            modules: Vec::new(),
         }
     }
 
+    /// Fehler des letzten `assemble`-Aufrufs
+    pub fn errors(&self) -> &[AsmError] {
+        &self.errors
+    }
+
+    /// Warnungen des letzten `assemble`-Aufrufs (erzeugen trotzdem Code)
+    pub fn warnings(&self) -> &[AsmError] {
+        &self.warnings
+    }
+
+    /// Hinweise des letzten `assemble`-Aufrufs, z.B. ignorierte OPT/LIST/TTL-Zeilen
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    /// Kompatibilität: SIMHALT wieder als $4E72 kodieren (kollidiert mit STOP, erzeugt
+    /// eine Deprecation-Warnung pro Vorkommen)
+    pub fn set_legacy_simhalt(&mut self, enabled: bool) {
+        self.legacy_simhalt = enabled;
+    }
+
+    /// Peephole-Optimierung: MOVE.L #klein → MOVEQ, ADD/SUB #1..8 → ADDQ/SUBQ,
+    /// MOVE Dx, Dx entfällt. Jede Ersetzung erzeugt einen Hinweis in [`Assembler::notes`].
+    pub fn set_optimize(&mut self, enabled: bool) {
+        self.optimize = enabled;
+    }
+
+    /// MOVEQ #128..255 bzw. #$80..$FF ablehnen (Standard). Abgeschaltet wird das Byte wie
+    /// bei vielen Assemblern übernommen; eine Warnung nennt den vorzeichenerweiterten Wert.
+    pub fn set_strict_moveq(&mut self, enabled: bool) {
+        self.strict_moveq = enabled;
+    }
+
+    pub fn strict_moveq(&self) -> bool {
+        self.strict_moveq
+    }
+
     /// Parst Assembly-Code und gibt Maschinenbefehle zurück
+    ///
+    /// Liefert `(Adresse, Wort)`-Paare; fehlerhafte Zeilen erzeugen keinen Code und landen
+    /// in [`Assembler::errors`].
+    ///
+    /// ```
+    /// use mc68000::{Assembler, Memory, CPU};
+    ///
+    /// let mut assembler = Assembler::new();
+    /// let code = assembler.assemble(&["ORG $1000", "MOVEQ #7, D0", "SIMHALT"]);
+    /// assert!(assembler.errors().is_empty());
+    ///
+    /// let mut memory = Memory::new();
+    /// for (address, word) in &code {
+    ///     memory.write_word(*address, *word);
+    /// }
+    ///
+    /// let mut cpu = CPU::new();
+    /// cpu.set_pc(code[0].0);
+    /// while !cpu.is_halted() {
+    ///     cpu.execute_instruction(&mut memory);
+    /// }
+    /// assert_eq!(cpu.get_data_register(0), 7);
+    /// ```
     pub fn assemble(&mut self, assembly_lines: &[&str]) -> Vec<(u32, u16)> {
+        self.assemble_with_symbols(assembly_lines, HashMap::new())
+    }
+
+    /// Wie `assemble`, aber mit vorbelegten (externen) Symbolen
+    fn assemble_with_symbols(
+        &mut self,
+        assembly_lines: &[&str],
+        predefined: HashMap<String, u32>,
+    ) -> Vec<(u32, u16)> {
         self.instructions.clear();
-        self.labels.clear();
+        self.labels = predefined;
+        self.sections.clear();
+        self.data.clear();
+        self.object_sizes.clear();
+        self.assert_directives.clear();
+        self.assertions.clear();
+        self.errors.clear();
+        self.warnings.clear();
+        self.xdefs.clear();
+        self.xrefs.clear();
+
+        self.entry = None;
+        self.initial_sp = None;
+        self.notes.clear();
+        self.section_name = None;
 
         let mut current_address = 0u32;
-        let mut data_values: Vec<(u32, u32)> = Vec::new(); // (address, value) für DC.L
+        let mut data_directives: Vec<DataDirective> = Vec::new();
+        let mut sections: HashMap<String, u32> = HashMap::new(); // SECTION-Name -> Stand
+        let mut ignored: Vec<String> = Vec::new(); // ignorierte Listing-Direktiven
+
+        // Datenobjekt, das folgende DC/DS-Zeilen ohne Label verlängern: (Label, Start)
+        let mut object: Option<(String, u32)> = None;
 
         // Erster Pass: Labels sammeln und Instruktionen parsen
-        for line in assembly_lines {
-            let mut line = line.trim();
-            if line.is_empty() || line.starts_with(';') {
-                continue; // Kommentare und leere Zeilen überspringen
+        for (line_index, &source_line) in assembly_lines.iter().enumerate() {
+            let mut line = source_line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() || line.starts_with('*') {
+                continue; // Kommentare (auch Easy68K-Stil "* ...") und leere Zeilen überspringen
             }
 
-            // Handle END directive
-            if line.to_uppercase().starts_with("END") {
-                break;
+            // Label abtrennen; der Rest der Zeile wird wie eine eigene Zeile behandelt
+            let mut label = match line.split_once(':') {
+                Some((label, rest)) => {
+                    line = rest.trim();
+                    Some(label.trim().to_string())
+                }
+                None => None,
+            };
+            let mut directive = line.split_whitespace().next().unwrap_or("").to_uppercase();
+
+            // "NAME EQU wert": Symbolname ohne Doppelpunkt
+            if label.is_none()
+                && line
+                    .split_whitespace()
+                    .nth(1)
+                    .is_some_and(|word| word.eq_ignore_ascii_case("EQU"))
+            {
+                let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                label = Some(name.to_string());
+                line = rest.trim();
+                directive = "EQU".to_string();
             }
 
-            // Handle ORG directive
-            if line.to_uppercase().starts_with("ORG") {
-                if let Some(addr) = self.parse_org_directive(line) {
-                    current_address = addr;
+            match self.first_pass_directive(
+                &directive,
+                line,
+                label.as_deref(),
+                line_index + 1,
+                &mut current_address,
+                &mut sections,
+            ) {
+                DirectiveAction::Done => {
+                    object = None;
+                    continue;
                 }
-                continue;
+                DirectiveAction::Ignored => {
+                    if !ignored.contains(&directive) {
+                        ignored.push(directive);
+                    }
+                    continue;
+                }
+                DirectiveAction::End => break,
+                DirectiveAction::None => {}
             }
 
-            // Handle labels (with or without colon)
-            if line.contains(':') {
-                let parts: Vec<&str> = line.splitn(2, ':').collect();
-                let label_name = parts[0].trim().to_string();
-                self.labels.insert(label_name, current_address);
-
-                // Check if there's an instruction on the same line
-                if parts.len() > 1 {
-                    line = parts[1].trim();
-                    if line.is_empty() {
-                        continue;
-                    }
-                } else {
+            if let Some(label) = &label {
+                if line.is_empty() {
+                    self.labels.insert(label.clone(), current_address);
+                    object = Some((label.clone(), current_address));
                     continue;
                 }
             }
 
             // Handle data directives
-            if line.to_uppercase().contains("DC.") || line.to_uppercase().contains("DS.") {
-                if let Some((label, size, value)) = self.parse_data_directive_with_value(line) {
-                    if !label.is_empty() {
-                        self.labels.insert(label, current_address);
+            if Self::is_data_directive(line) {
+                if let Some((data_label, reserve, size, values)) = self.parse_data_directive(line) {
+                    // .W/.L beginnen wie auf dem echten 68000 an geraden Adressen
+                    if size != Size::Byte {
+                        current_address += current_address & 1;
+                    }
+                    for label in [data_label]
+                        .into_iter()
+                        .chain(label)
+                        .filter(|l| !l.is_empty())
+                    {
+                        self.labels.insert(label.clone(), current_address);
+                        object = Some((label, current_address));
                     }
-                    // If DC.L with value, store it for memory initialization
-                    if let Some(val) = value {
-                        data_values.push((current_address, val));
+                    if reserve {
+                        // DS: nur Platz reservieren, keine Bytes erzeugen
+                        match self.reserved_length(size, &values) {
+                            Ok(length)
+                                if !self.fits_address_space(
+                                    line_index + 1,
+                                    current_address,
+                                    length,
+                                ) => {}
+                            Ok(length) => {
+                                self.record_section(current_address, length, SectionKind::Data);
+                                current_address += length;
+                                self.extend_object(&object, current_address);
+                            }
+                            Err((kind, message)) => self.errors.push(AsmError::new(
+                                line_index + 1,
+                                kind,
+                                format!("DS.{}: {}", size.suffix(), message),
+                            )),
+                        }
+                        continue;
+                    }
+                    let length = size.bytes() * values.len().max(1) as u32;
+                    if !self.fits_address_space(line_index + 1, current_address, length) {
+                        continue;
                     }
-                    current_address += size;
+                    if !values.is_empty() {
+                        data_directives.push(DataDirective {
+                            line: line_index + 1,
+                            address: current_address,
+                            size,
+                            values,
+                        });
+                    }
+                    self.record_section(current_address, length, SectionKind::Data);
+                    current_address += length;
+                    self.extend_object(&object, current_address);
                 }
                 continue;
             }
 
-            // Instruktion parsen
-            let instruction = self.parse_instruction(line, current_address);
+            // Instruktion parsen (immer an gerader Adresse)
+            object = None;
+            current_address += current_address & 1;
+            if let Some(label) = label {
+                self.labels.insert(label, current_address);
+            }
+            let mut instruction =
+                self.parse_instruction(source_line, line, line_index + 1, current_address);
+            if self.optimize {
+                // Vor der Adressvergabe ersetzen, damit alle folgenden Labels stimmen
+                match self.peephole(&instruction) {
+                    Some(Some(shorter)) => {
+                        self.notes.push(format!(
+                            "line {}: {} → {}",
+                            line_index + 1,
+                            line,
+                            Self::instruction_text(&shorter)
+                        ));
+                        instruction = shorter;
+                    }
+                    Some(None) => {
+                        self.notes
+                            .push(format!("line {}: {} → (removed)", line_index + 1, line));
+                        continue;
+                    }
+                    None => {}
+                }
+            }
+            instruction.size = self.encoded_length(&instruction);
+            if !self.fits_address_space(line_index + 1, current_address, instruction.size) {
+                continue;
+            }
+            self.record_section(current_address, instruction.size, SectionKind::Code);
             current_address += instruction.size; // Berücksichtige Extension Words
             self.instructions.push(instruction);
         }
 
+        if !ignored.is_empty() {
+            self.notes.push(format!(
+                "ignored listing directives: {} (they do not affect the program)",
+                ignored.join(", ")
+            ));
+        }
+
         // Zweiter Pass: Maschinenbefehle generieren
         let mut machine_code = Vec::new();
 
-        // Add data values first (DC.L directives)
-        for (addr, value) in data_values {
-            // Split 32-bit value into two 16-bit words (big-endian)
-            machine_code.push((addr, (value >> 16) as u16));
-            machine_code.push((addr + 2, (value & 0xFFFF) as u16));
+        // Daten zuerst (DC-Direktiven); Bytes werden zu Wörtern zusammengefasst
+        let mut errors = Vec::new();
+        let mut data_words: BTreeMap<u32, u16> = BTreeMap::new();
+        for directive in &data_directives {
+            match self.encode_data(directive) {
+                Ok(bytes) => {
+                    for (offset, byte) in bytes.into_iter().enumerate() {
+                        let address = directive.address + offset as u32;
+                        self.data.push((address, byte));
+                        let word = data_words.entry(address & !1).or_insert(0);
+                        if address & 1 == 0 {
+                            *word = (*word & 0x00FF) | ((byte as u16) << 8);
+                        } else {
+                            *word = (*word & 0xFF00) | byte as u16;
+                        }
+                    }
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        machine_code.extend(data_words);
+
+        for directive in &self.assert_directives {
+            match self.resolve_assertion(directive) {
+                Ok(assertion) => self.assertions.push(assertion),
+                Err((kind, message)) => errors.push(AsmError::new(
+                    directive.line,
+                    kind,
+                    format!("ASSERT: {}", message),
+                )),
+            }
+        }
+        self.assertions.sort_by_key(|assertion| assertion.address);
+
+        let mut warnings = Vec::new();
+        let encoded: Vec<_> = self
+            .instructions
+            .iter()
+            .map(|inst| self.encode_instruction_with_ext(inst))
+            .collect();
+        for (inst, result) in self.instructions.iter_mut().zip(encoded) {
+            match result {
+                Ok((code, extensions)) => {
+                    // Alle folgenden Adressen beruhen auf der Länge aus dem ersten Pass
+                    let length = 2 + 2 * extensions.len() as u32;
+                    if length != inst.size {
+                        errors.push(
+                            AsmError::new(
+                                inst.line,
+                                AsmErrorKind::LengthChanged,
+                                format!(
+                                    "{}: encoded as {} bytes, but the first pass reserved {}",
+                                    inst.mnemonic, length, inst.size
+                                ),
+                            )
+                            .at(inst.all_operand_columns()),
+                        );
+                        continue;
+                    }
+                    if let Some(value) = Self::sign_extended_moveq(self.strict_moveq, inst) {
+                        warnings.push(
+                            AsmError::new(
+                                inst.line,
+                                AsmErrorKind::SignExtended,
+                                format!(
+                                    "MOVEQ: {} is sign-extended to {}",
+                                    inst.operands[0], value as i8
+                                ),
+                            )
+                            .at(inst.operand_columns.first().cloned()),
+                        );
+                    }
+                    if code == isa::LEGACY_SIMHALT_OPCODE && inst.mnemonic == "SIMHALT" {
+                        warnings.push(AsmError::new(
+                            inst.line,
+                            AsmErrorKind::Deprecated,
+                            format!(
+                                "SIMHALT: legacy encoding ${:04X} is deprecated (it is STOP on a real 68000), use ${:04X}",
+                                isa::LEGACY_SIMHALT_OPCODE,
+                                isa::SIMHALT_OPCODE
+                            ),
+                        ));
+                    }
+                    machine_code.push((inst.address, code));
+
+                    // Extension Words hinzufügen, falls vorhanden
+                    for (i, ext) in extensions.iter().enumerate() {
+                        machine_code.push((inst.address + 2 + 2 * i as u32, *ext));
+                    }
+                    inst.machine_code = Some(code);
+                    inst.extensions = extensions;
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        self.errors.extend(errors);
+        self.warnings.extend(warnings);
+
+        machine_code
+    }
+
+    /// Assembliert einen kompletten Quelltext zu einem `Program` mit Metadaten
+    ///
+    /// ```
+    /// use mc68000::Assembler;
+    ///
+    /// let mut assembler = Assembler::new();
+    /// let program = assembler.assemble_source("START: MOVEQ #1, D0\n SIMHALT").unwrap();
+    /// assert_eq!(program.symbols["START"], 0);
+    /// assert_eq!(program.line_for_address(2), Some(2));
+    ///
+    /// let errors = assembler.assemble_source("MOVEQ #1").unwrap_err();
+    /// assert_eq!(errors[0].to_string(), "line 1: MOVEQ: expected 2 operands, found 1");
+    /// ```
+    pub fn assemble_source(&mut self, source: &str) -> Result<Program, Vec<AsmError>> {
+        let lines: Vec<&str> = source.lines().collect();
+        self.assemble_lines(&lines, HashMap::new())
+    }
+
+    fn assemble_lines(
+        &mut self,
+        lines: &[&str],
+        predefined: HashMap<String, u32>,
+    ) -> Result<Program, Vec<AsmError>> {
+        let image = self.assemble_with_symbols(lines, predefined);
+        if !self.errors.is_empty() {
+            return Err(self.errors.clone());
         }
 
-        for i in 0..self.instructions.len() {
-            let inst = &self.instructions[i];
-            if let Some((code, ext_word)) = self.encode_instruction_with_ext(inst) {
-                machine_code.push((inst.address, code));
+        Ok(Program {
+            image,
+            data: self.data.clone(),
+            sections: self.sections.clone(),
+            symbols: self.labels.clone(),
+            entry: self.entry,
+            initial_sp: self.initial_sp,
+            source_map: self
+                .instructions
+                .iter()
+                .map(|inst| (inst.address, inst.line))
+                .collect(),
+            source_lines: lines.iter().map(|line| line.to_string()).collect(),
+            assertions: self.assertions.clone(),
+            object_sizes: self.object_sizes.clone(),
+        })
+    }
 
-                // Extension Word hinzufügen, falls vorhanden
-                if let Some(ext) = ext_word {
-                    machine_code.push((inst.address + 2, ext));
+    /// Assembliert ein Quelltextstück ab `origin`, z.B. eine Auswahl im Editor.
+    ///
+    /// `symbols` sind vorbelegt (etwa die Labels des geladenen Programms); Labels im Stück
+    /// überdecken sie. Zeilennummern in Fehlern und Source Map beziehen sich auf `source`.
+    pub fn assemble_fragment(
+        &mut self,
+        source: &str,
+        origin: u32,
+        symbols: HashMap<String, u32>,
+    ) -> Result<Program, Vec<AsmError>> {
+        let org = format!("        ORG     ${:X}", origin);
+        let lines: Vec<&str> = std::iter::once(org.as_str())
+            .chain(source.lines())
+            .collect();
+        let result = self.assemble_lines(&lines, symbols);
+
+        // Die vorangestellte ORG-Zeile wieder herausrechnen
+        for error in self.errors.iter_mut().chain(self.warnings.iter_mut()) {
+            error.line = error.line.saturating_sub(1).max(1);
+        }
+        for instruction in &mut self.instructions {
+            instruction.line -= 1;
+        }
+        match result {
+            Ok(mut program) => {
+                program.source_lines.remove(0);
+                for line in program.source_map.values_mut() {
+                    *line -= 1;
                 }
+                Ok(program)
             }
+            Err(_) => Err(self.errors.clone()),
+        }
+    }
+
+    /// Assembliert ein Modul im Module Mode.
+    ///
+    /// Mit `XDEF name` exportierte Symbole bleiben über Aufrufe hinweg erhalten und stehen
+    /// späteren Modulen zur Verfügung. `XREF name` deklariert ein Symbol aus einem anderen
+    /// Modul; ist es noch nicht bekannt, wird es vorläufig mit 0 belegt und erst von
+    /// [`Assembler::link`] aufgelöst.
+    pub fn assemble_module(&mut self, name: &str, source: &str) -> Result<Program, Vec<AsmError>> {
+        let exported = self.exported_symbols();
+        let lines: Vec<&str> = source.lines().collect();
+
+        // Vorlauf nur für die XREF-Liste: unbekannte Externals vorläufig auf 0 setzen
+        self.assemble_with_symbols(&lines, exported.clone());
+        let mut predefined = exported;
+        for (symbol, _) in &self.xrefs {
+            predefined.entry(symbol.clone()).or_insert(0);
         }
 
-        machine_code
+        let mut program = self.assemble_lines(&lines, predefined)?;
+        program.set_module(name);
+
+        let mut errors = Vec::new();
+        let mut exports = HashMap::new();
+        for (symbol, line) in &self.xdefs {
+            let Some(&address) = self.labels.get(symbol) else {
+                errors.push(AsmError::new(
+                    *line,
+                    AsmErrorKind::UndefinedSymbol,
+                    format!("XDEF {}: symbol not defined in module {}", symbol, name),
+                ));
+                continue;
+            };
+            // Das eigene Modul zählt nicht: erneutes Assemblieren ersetzt es
+            if let Some(other) = self
+                .modules
+                .iter()
+                .find(|m| m.name != name && m.exports.contains_key(symbol))
+            {
+                errors.push(AsmError::new(
+                    *line,
+                    AsmErrorKind::InvalidOperand,
+                    format!("XDEF {}: already exported by module {}", symbol, other.name),
+                ));
+                continue;
+            }
+            exports.insert(symbol.clone(), address);
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        self.modules.retain(|module| module.name != name);
+        self.modules.push(Module {
+            name: name.to_string(),
+            source: source.to_string(),
+            exports,
+            imports: self.xrefs.clone(),
+        });
+        Ok(program)
+    }
+
+    /// Prüft, dass jedes XREF von einem Modul exportiert wird, und assembliert alle Module
+    /// mit den endgültigen Adressen zu einem gemeinsamen Programm.
+    pub fn link(&mut self) -> Result<Program, Vec<AsmError>> {
+        let exported = self.exported_symbols();
+        let errors: Vec<AsmError> = self
+            .modules
+            .iter()
+            .flat_map(|module| {
+                module
+                    .imports
+                    .iter()
+                    .filter(|(symbol, _)| !exported.contains_key(symbol))
+                    .map(|(symbol, line)| {
+                        AsmError::new(
+                            *line,
+                            AsmErrorKind::UndefinedSymbol,
+                            format!(
+                                "undefined external {} (required by module {})",
+                                symbol, module.name
+                            ),
+                        )
+                    })
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut linked = Program::default();
+        for module in self.modules.clone() {
+            let lines: Vec<&str> = module.source.lines().collect();
+            let mut program = self.assemble_lines(&lines, exported.clone())?;
+            program.set_module(&module.name);
+
+            // Zeilennummern hinter die bisherigen Module verschieben
+            let offset = linked.source_lines.len();
+            linked.image.extend(program.image);
+            linked.data.extend(program.data);
+            linked.sections.extend(program.sections);
+            linked.source_map.extend(
+                program
+                    .source_map
+                    .into_iter()
+                    .map(|(address, line)| (address, line + offset)),
+            );
+            linked.source_lines.extend(program.source_lines);
+            linked
+                .assertions
+                .extend(program.assertions.into_iter().map(|assertion| Assertion {
+                    line: assertion.line + offset,
+                    ..assertion
+                }));
+            linked.entry = linked.entry.or(program.entry);
+            linked.initial_sp = linked.initial_sp.or(program.initial_sp);
+            for (symbol, address) in program.symbols {
+                linked.symbols.entry(symbol).or_insert(address);
+            }
+            for (symbol, size) in program.object_sizes {
+                linked.object_sizes.entry(symbol).or_insert(size);
+            }
+        }
+        linked.assertions.sort_by_key(|assertion| assertion.address);
+        Ok(linked)
+    }
+
+    /// Verwirft alle Module und ihre exportierten Symbole
+    pub fn clear_modules(&mut self) {
+        self.modules.clear();
+    }
+
+    fn exported_symbols(&self) -> HashMap<String, u32> {
+        self.modules
+            .iter()
+            .flat_map(|module| module.exports.clone())
+            .collect()
+    }
+
+    /// Prüft, dass `size` Bytes ab `address` noch im 24-Bit-Adressraum liegen
+    fn fits_address_space(&mut self, line_number: usize, address: u32, size: u32) -> bool {
+        if address as u64 + size as u64 <= 0x0100_0000 {
+            return true;
+        }
+        self.errors.push(AsmError::new(
+            line_number,
+            AsmErrorKind::ValueOutOfRange,
+            format!(
+                "{} bytes at ${:06X} do not fit into the 24-bit address space",
+                size, address
+            ),
+        ));
+        false
+    }
+
+    /// Direktiven-Dispatch im ersten Pass. Bekannte Direktiven werden hier ausgewertet
+    /// (END, ORG, SECTION, STACK, EQU, EVEN, XDEF/XREF, ASSERT) oder als reine Listing-Steuerung
+    /// ignoriert; DC/DS und Instruktionen laufen weiter über den normalen Pfad.
+    fn first_pass_directive(
+        &mut self,
+        directive: &str,
+        line: &str,
+        label: Option<&str>,
+        line_number: usize,
+        current_address: &mut u32,
+        sections: &mut HashMap<String, u32>,
+    ) -> DirectiveAction {
+        let operands = line
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest.trim());
+        let error = |kind, message: String| {
+            AsmError::new(line_number, kind, format!("{}: {}", directive, message))
+        };
+
+        match directive {
+            "ORG" | "SECTION" | "STACK" | "INITSP" if label.is_some() => {
+                self.errors.push(error(
+                    AsmErrorKind::MisplacedLabel,
+                    format!(
+                        "label {} is not allowed here, put it on the next line",
+                        label.unwrap_or_default()
+                    ),
+                ));
+                DirectiveAction::Done
+            }
+            "EQU" => {
+                let value = self.evaluate_expression(operands);
+                match (label, value) {
+                    (None, _) => self.errors.push(AsmError::new(
+                        line_number,
+                        AsmErrorKind::OperandCount,
+                        "EQU: missing symbol name".to_string(),
+                    )),
+                    (Some(name), Ok(value)) => {
+                        self.labels.insert(name.to_string(), value as u32);
+                    }
+                    (Some(name), Err((kind, message))) => self.errors.push(AsmError::new(
+                        line_number,
+                        kind,
+                        format!("EQU {}: {}", name, message),
+                    )),
+                }
+                DirectiveAction::Done
+            }
+            "END" => {
+                if let Some(label) = label {
+                    self.labels.insert(label.to_string(), *current_address);
+                }
+                // "END START": Startadresse des Programms
+                if !operands.is_empty() {
+                    match self.parse_org_directive(line) {
+                        Ok(address) => self.entry = Some(address),
+                        Err((kind, message)) => self.errors.push(error(kind, message)),
+                    }
+                }
+                DirectiveAction::End
+            }
+            "STACK" | "INITSP" => {
+                // Anfangswert für A7 beim Reset (kein Code, keine Adresse belegt)
+                match self.parse_org_directive(line) {
+                    Ok(address) if address % 2 == 1 => self.errors.push(error(
+                        AsmErrorKind::ValueOutOfRange,
+                        format!("stack pointer ${:06X} must be even", address),
+                    )),
+                    Ok(address) => self.initial_sp = Some(address),
+                    Err((kind, message)) => self.errors.push(error(kind, message)),
+                }
+                DirectiveAction::Done
+            }
+            "ORG" => {
+                match self.parse_org_directive(line) {
+                    Ok(address) => *current_address = address,
+                    Err((kind, message)) => self.errors.push(error(kind, message)),
+                }
+                DirectiveAction::Done
+            }
+            "SECTION" => {
+                // Benannte ORG-Region: eine neue Section beginnt hinter allem bisher
+                // Belegten, ein erneutes SECTION setzt am gemerkten Stand fort
+                let name = operands
+                    .split(',')
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .to_uppercase();
+                if name.is_empty() {
+                    self.errors.push(error(
+                        AsmErrorKind::OperandCount,
+                        "missing section name".to_string(),
+                    ));
+                    return DirectiveAction::Done;
+                }
+                if let Some(current) = self.section_name.take() {
+                    sections.insert(current, *current_address);
+                }
+                let high_water = self
+                    .sections
+                    .iter()
+                    .map(|s| s.end)
+                    .fold(*current_address, u32::max);
+                *current_address = match sections.get(&name) {
+                    Some(&resume) if self.sections.iter().any(|s| s.contains(resume)) => {
+                        self.errors.push(error(
+                            AsmErrorKind::InvalidOperand,
+                            format!(
+                                "{} cannot continue at ${:06X}, another section was placed there; use ORG instead",
+                                name, resume
+                            ),
+                        ));
+                        resume
+                    }
+                    Some(&resume) => resume,
+                    None => high_water,
+                };
+                self.section_name = Some(name);
+                DirectiveAction::Done
+            }
+            "EVEN" => {
+                *current_address += *current_address & 1;
+                if let Some(label) = label {
+                    self.labels.insert(label.to_string(), *current_address);
+                }
+                DirectiveAction::Done
+            }
+            "XDEF" | "XREF" => {
+                // Exportierte bzw. extern erwartete Symbole (Module Mode)
+                if let Some(label) = label {
+                    self.labels.insert(label.to_string(), *current_address);
+                }
+                let names = operands
+                    .split(',')
+                    .map(|name| (name.trim().to_string(), line_number))
+                    .filter(|(name, _)| !name.is_empty());
+                if directive == "XDEF" {
+                    self.xdefs.extend(names);
+                } else {
+                    self.xrefs.extend(names);
+                }
+                DirectiveAction::Done
+            }
+            "ASSERT" | "ASSERT.B" | "ASSERT.W" | "ASSERT.L" => {
+                // Gilt vor der nächsten Instruktion, die immer an einer geraden Adresse liegt
+                let address = *current_address + (*current_address & 1);
+                if let Some(label) = label {
+                    self.labels.insert(label.to_string(), address);
+                }
+                let size = directive
+                    .strip_prefix("ASSERT.")
+                    .and_then(Size::from_suffix)
+                    .unwrap_or(Size::Word);
+                self.assert_directives.push(AssertDirective {
+                    line: line_number,
+                    address,
+                    size,
+                    text: operands.to_string(),
+                });
+                DirectiveAction::Done
+            }
+            "OPT" | "LIST" | "NOLIST" | "PAGE" | "NOPAGE" | "TTL" | "SPC" | "LLEN" | "PLEN"
+            | "FORMAT" => {
+                if let Some(label) = label {
+                    self.labels.insert(label.to_string(), *current_address);
+                }
+                DirectiveAction::Ignored
+            }
+            "INCLUDE" | "INCBIN" | "MACRO" | "ENDM" | "REPT" | "ENDR" | "IFEQ" | "IFNE" | "IFC"
+            | "IFNC" | "ENDC" | "SET" | "REG" | "DCB" | "OFFSET" | "RORG" => {
+                self.errors.push(error(
+                    AsmErrorKind::UnknownInstruction,
+                    "directive is not supported by this assembler".to_string(),
+                ));
+                DirectiveAction::Done
+            }
+            _ => DirectiveAction::None,
+        }
+    }
+
+    /// Erweitert die aktuelle Section oder beginnt eine neue (nach ORG oder Artwechsel)
+    fn record_section(&mut self, address: u32, size: u32, kind: SectionKind) {
+        if size == 0 {
+            return;
+        }
+        // Ohne SECTION-Direktive nach Art benennen
+        let name = match (&self.section_name, kind) {
+            (Some(name), _) => name.clone(),
+            (None, SectionKind::Code) => "CODE".to_string(),
+            (None, SectionKind::Data) => "DATA".to_string(),
+        };
+        if let Some(last) = self.sections.last_mut() {
+            // Füllbyte vor .W/.L/Instruktionen gehört zur laufenden Section
+            let padded = last.end + (last.end & 1);
+            if last.kind == kind && last.name == name && (last.end..=padded).contains(&address) {
+                last.end = address + size;
+                return;
+            }
+        }
+        self.sections.push(Section {
+            name,
+            kind,
+            start: address,
+            end: address + size,
+            module: None,
+        });
     }
 
     fn encode_instruction_with_ext(
         &self,
         instruction: &AssemblyInstruction,
-    ) -> Option<(u16, Option<u16>)> {
-        println!(
-            "Generiere Maschinencode für: {} {:?}",
-            instruction.mnemonic, instruction.operands
-        );
-        match instruction.mnemonic.as_str() {
-            "MOVEQ" => self.encode_moveq(instruction).map(|c| (c, None)),
+    ) -> Result<(u16, Vec<u16>), AsmError> {
+        self.validate_instruction(instruction)?;
+        self.check_immediate_range(instruction)?;
+
+        let encoded = match instruction.mnemonic.as_str() {
+            "MOVEQ" => self.encode_moveq(instruction).map(|c| (c, vec![])),
             "MOVE" => self.encode_move_with_ext(instruction),
             "MOVEA" => self.encode_movea_with_ext(instruction),
             "MULS" => self.encode_muls_with_ext(instruction),
-            "TST" => self.encode_tst(instruction).map(|c| (c, None)),
-            "SUBQ" => self.encode_subq(instruction).map(|c| (c, None)),
-            "ASL" => self.encode_asl(instruction).map(|c| (c, None)),
-            "DBRA" => self.encode_dbra(instruction).map(|c| (c, None)),
-            "BRA" => self.encode_branch(instruction, 0x0).map(|c| (c, None)), // Always
-            "BEQ" => self.encode_branch(instruction, 0x7).map(|c| (c, None)), // Equal
-            "BNE" => self.encode_branch(instruction, 0x6).map(|c| (c, None)), // Not Equal
-            "BCC" => self.encode_branch(instruction, 0x4).map(|c| (c, None)), // Carry Clear
-            "BCS" => self.encode_branch(instruction, 0x5).map(|c| (c, None)), // Carry Set
-            "BPL" => self.encode_branch(instruction, 0x8).map(|c| (c, None)), // Plus
-            "BMI" => self.encode_branch(instruction, 0x9).map(|c| (c, None)), // Minus
-            "BGE" => self.encode_branch(instruction, 0xC).map(|c| (c, None)), // Greater or Equal
-            "BLT" => self.encode_branch(instruction, 0xD).map(|c| (c, None)), // Less Than
-            "BGT" => self.encode_branch(instruction, 0xE).map(|c| (c, None)), // Greater Than
-            "BLE" => self.encode_branch(instruction, 0xF).map(|c| (c, None)), // Less or Equal
-            "NOP" => Some((0x4E71, None)),
-            "SIMHALT" => Some((0x4E72, None)), // Custom halt instruction
-            "ADD" => self.encode_add(instruction).map(|c| (c, None)),
-            "SUB" => self.encode_sub(instruction).map(|c| (c, None)),
-            "CMP" => self.encode_cmp_with_ext(instruction),
-            "JMP" | "JUMP" => self.encode_jump(instruction).map(|c| (c, None)),
-            _ => {
-                println!("Warnung: Unbekannte Instruktion: {}", instruction.mnemonic);
-                None
+            "TST" => self.encode_tst(instruction).map(|c| (c, vec![])),
+            "ADDQ" => self.encode_quick(instruction, 0x5000).map(|c| (c, vec![])),
+            "SUBQ" => self.encode_quick(instruction, 0x5100).map(|c| (c, vec![])),
+            "ASL" => self.encode_asl(instruction).map(|c| (c, vec![])),
+            "DBRA" => self.encode_dbra(instruction),
+            "BRA" => self.encode_branch(instruction, 0x0).map(|c| (c, vec![])), // Always
+            "BEQ" => self.encode_branch(instruction, 0x7).map(|c| (c, vec![])), // Equal
+            "BNE" => self.encode_branch(instruction, 0x6).map(|c| (c, vec![])), // Not Equal
+            "BCC" => self.encode_branch(instruction, 0x4).map(|c| (c, vec![])), // Carry Clear
+            "BCS" => self.encode_branch(instruction, 0x5).map(|c| (c, vec![])), // Carry Set
+            "BPL" => self.encode_branch(instruction, 0xA).map(|c| (c, vec![])), // Plus
+            "BMI" => self.encode_branch(instruction, 0xB).map(|c| (c, vec![])), // Minus
+            "BGE" => self.encode_branch(instruction, 0xC).map(|c| (c, vec![])), // Greater or Equal
+            "BLT" => self.encode_branch(instruction, 0xD).map(|c| (c, vec![])), // Less Than
+            "BGT" => self.encode_branch(instruction, 0xE).map(|c| (c, vec![])), // Greater Than
+            "BLE" => self.encode_branch(instruction, 0xF).map(|c| (c, vec![])), // Less or Equal
+            "NOP" => Some((0x4E71, vec![])),
+            "RTE" => Some((0x4E73, vec![])),
+            "SIMHALT" if self.legacy_simhalt => Some((isa::LEGACY_SIMHALT_OPCODE, vec![])),
+            "SIMHALT" => Some((isa::SIMHALT_OPCODE, vec![])), // Simulator-Halt (Line A)
+            "ADD" => self
+                .encode_add_sub(instruction, 0xD000)
+                .map(|c| (c, vec![])),
+            "SUB" => self
+                .encode_add_sub(instruction, 0x9000)
+                .map(|c| (c, vec![])),
+            "CMP" | "CMPA" | "CMPI" => self.encode_cmp_with_ext(instruction),
+            "JMP" | "JUMP" => self.encode_jump(instruction),
+            "ORI" => self.encode_ccr_immediate(instruction, 0x003C),
+            "ANDI" => self.encode_ccr_immediate(instruction, 0x023C),
+            "EORI" => self.encode_ccr_immediate(instruction, 0x0A3C),
+            _ => None,
+        };
+
+        encoded.ok_or_else(|| {
+            AsmError::new(
+                instruction.line,
+                AsmErrorKind::InvalidOperand,
+                format!(
+                    "{}: cannot encode operands {}",
+                    instruction.mnemonic,
+                    instruction.operands.join(", ")
+                ),
+            )
+            .at(self.suspect_operand_columns(instruction))
+        })
+    }
+
+    /// Spalten des Operanden, an dem die Kodierung vermutlich scheitert: ein Immediate ohne
+    /// Wert oder eine Adresse, die weder Zahl noch bekanntes Symbol ist (z.B. D9).
+    /// Sonst alle Operanden.
+    fn suspect_operand_columns(&self, instruction: &AssemblyInstruction) -> Option<Range<usize>> {
+        let suspect =
+            instruction
+                .operands
+                .iter()
+                .position(|operand| match self.classify_operand(operand) {
+                    OperandClass::Immediate => self.immediate_value(operand).is_none(),
+                    OperandClass::Absolute => self.evaluate_expression(operand).is_err(),
+                    _ => false,
+                });
+        match suspect {
+            Some(index) => instruction.operand_columns.get(index).cloned(),
+            None => instruction.all_operand_columns(),
+        }
+    }
+
+    /// Prüft Operandenanzahl, Größen-Suffix und Adressierungsarten gegen die Instruktionstabelle
+    fn validate_instruction(&self, instruction: &AssemblyInstruction) -> Result<(), AsmError> {
+        let mnemonic = instruction.mnemonic.as_str();
+        let error = |kind, message| AsmError::new(instruction.line, kind, message);
+        let mnemonic_columns = Some(instruction.mnemonic_columns.clone());
+
+        let spec = isa::lookup(mnemonic).ok_or_else(|| {
+            error(
+                AsmErrorKind::UnknownInstruction,
+                format!("unknown instruction {}", mnemonic),
+            )
+            .at(mnemonic_columns.clone())
+        })?;
+
+        if let Some(suffix) = &instruction.size_suffix {
+            let allowed = Size::from_suffix(suffix).is_some_and(|size| spec.allows_size(size));
+            if !allowed {
+                return Err(error(
+                    AsmErrorKind::InvalidSize,
+                    format!("{}: size .{} not allowed", mnemonic, suffix),
+                )
+                .at(mnemonic_columns));
             }
         }
+
+        let expected = spec.operand_count();
+        let found = instruction.operands.len();
+        if found != expected {
+            return Err(error(
+                AsmErrorKind::OperandCount,
+                format!(
+                    "{}: expected {} operand{}, found {}",
+                    mnemonic,
+                    expected,
+                    if expected == 1 { "" } else { "s" },
+                    found
+                ),
+            )
+            .at(instruction.all_operand_columns()));
+        }
+
+        let byte_sized = instruction.size_suffix.as_deref() == Some("B");
+        for (index, (operand, allowed)) in
+            instruction.operands.iter().zip(spec.operands).enumerate()
+        {
+            let class = self.classify_operand(operand);
+            if !allowed.contains(&class) {
+                return Err(error(
+                    AsmErrorKind::IllegalOperand,
+                    format!("{}: {} operand not allowed", mnemonic, class),
+                )
+                .at(instruction.operand_columns.get(index).cloned()));
+            }
+            // Adressregister haben keinen Byte-Zugriff (gilt für jede Instruktion)
+            if byte_sized && class == OperandClass::AddressRegister {
+                return Err(error(
+                    AsmErrorKind::IllegalOperand,
+                    format!(
+                        "{}: address register operand not allowed with size .B",
+                        mnemonic
+                    ),
+                )
+                .at(instruction.operand_columns.get(index).cloned()));
+            }
+        }
+
+        Ok(())
     }
 
-    fn parse_instruction(&self, line: &str, address: u32) -> AssemblyInstruction {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.is_empty() {
+    /// Zerlegt `line` (ein Ausschnitt der Quellzeile `source` ohne Label und Kommentar);
+    /// die Spalten von Mnemonic und Operanden beziehen sich auf `source`
+    fn parse_instruction(
+        &self,
+        source: &str,
+        line: &str,
+        line_number: usize,
+        address: u32,
+    ) -> AssemblyInstruction {
+        let (head, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        if head.is_empty() {
             return AssemblyInstruction {
+                line: line_number,
                 address,
                 label: None,
                 mnemonic: String::new(),
+                size_suffix: None,
                 operands: Vec::new(),
+                mnemonic_columns: 0..0,
+                operand_columns: Vec::new(),
                 machine_code: None,
-                extension_word: None,
+                extensions: Vec::new(),
                 size: 2,
             };
         }
 
         // Split mnemonic from size suffix (e.g., MOVE.L -> MOVE and .L)
-        let mnemonic_parts: Vec<&str> = parts[0].split('.').collect();
+        let mnemonic_parts: Vec<&str> = head.split('.').collect();
         let mnemonic = mnemonic_parts[0].to_uppercase();
 
-        let operands = if parts.len() > 1 {
-            // Alle Teile außer dem ersten (Mnemonic) zusammenfügen und dann nach Komma splitten
-            let operand_string = parts[1..].join(" ");
-            operand_string
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect()
-        } else {
-            Vec::new()
-        };
-
-        // Bestimme die Größe der Instruktion (prüfe auf Extension Words)
-        let size = if operands.len() >= 2 {
-            let src = &operands[0];
-            let dst = &operands[operands.len() - 1];
-
-            // Instruktionen die Extension Words brauchen:
-            // 1. MOVE.L/MOVEA.L mit #immediate oder Labels
-            // 2. CMP.L mit #immediate
-            // 3. MULS mit #immediate
-
-            if (mnemonic == "MOVE" || mnemonic == "MOVEA") && mnemonic_parts.get(1) == Some(&"L") {
-                // MOVE.L/MOVEA.L mit #immediate oder Label braucht Extension Word
-                if src.starts_with('#')
-                    || (!src.starts_with('D') && !src.starts_with('A') && !src.starts_with('('))
-                {
-                    4 // Instruktion + Extension Word
-                } else if !dst.starts_with('D') && !dst.starts_with('A') && !dst.starts_with('(') {
-                    4 // Destination ist Label
-                } else {
-                    2 // Register-zu-Register
-                }
-            } else if mnemonic == "CMP"
-                && mnemonic_parts.get(1) == Some(&"L")
-                && src.starts_with('#')
-            {
-                4 // CMP.L #imm, Dn
-            } else if mnemonic == "MULS" && src.starts_with('#') {
-                4 // MULS #imm, Dn
-            } else {
-                2 // Standardgröße
-            }
-        } else {
-            2 // Keine oder nur ein Operand
-        };
-
-        println!(
-            "Parse: '{}' -> Mnemonic: '{}', Operands: {:?}, Size: {}",
-            line, mnemonic, operands, size
-        );
+        // Rest der Zeile direkt nach Komma splitten (ohne Zwischen-String); die Teile bleiben
+        // Slices der Quellzeile, damit ihre Spalten bekannt sind
+        let pieces: Vec<&str> = rest
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+        let operand_columns = pieces
+            .iter()
+            .map(|piece| char_columns(source, piece))
+            .collect();
+        let operands: Vec<String> = pieces.iter().map(|piece| piece.to_string()).collect();
 
         AssemblyInstruction {
+            line: line_number,
             address,
             label: None,
             mnemonic,
+            size_suffix: mnemonic_parts.get(1).map(|s| s.to_uppercase()),
             operands,
+            mnemonic_columns: char_columns(source, head),
+            operand_columns,
             machine_code: None,
-            extension_word: None,
-            size,
+            extensions: Vec::new(),
+            size: 0, // legt der erste Pass mit encoded_length fest
         }
     }
 
+    /// Länge in Bytes aus einer vorläufigen Kodierung. Noch unbekannte Symbole gelten als 0,
+    /// Sprungziele als direkt hinter der Instruktion; die Länge hängt nie von ihrem Wert ab.
+    /// Scheitert auch das (meist ein Fehler, den der zweite Pass meldet), gilt die Schätzung.
+    fn encoded_length(&mut self, instruction: &AssemblyInstruction) -> u32 {
+        self.provisional = true;
+        let encoded = self.encode_instruction_with_ext(instruction);
+        self.provisional = false;
+        match encoded {
+            Ok((_, extensions)) => 2 + 2 * extensions.len() as u32,
+            Err(_) => self.estimated_length(instruction),
+        }
+    }
+
+    /// Geschätzte Länge nach Mnemonic und Operandenklassen, nur für nicht kodierbare Zeilen
+    fn estimated_length(&self, instruction: &AssemblyInstruction) -> u32 {
+        let mnemonic = instruction.mnemonic.as_str();
+        let operands = &instruction.operands;
+        let size = instruction
+            .size_suffix
+            .as_deref()
+            .and_then(Size::from_suffix);
+        let size = match mnemonic {
+            "MOVE" | "CMP" | "CMPA" | "CMPI" | "TST" => size.unwrap_or(Size::Word),
+            "MOVEA" => size.unwrap_or(Size::Long),
+            "MULS" | "JMP" | "JUMP" => Size::Word,
+            "DBRA" | "ORI" | "ANDI" | "EORI" => return 4,
+            _ => return 2, // Quick-Immediates, Registerformen, Bcc.S
+        };
+        let extension: u32 = operands
+            .iter()
+            .map(|operand| match self.classify_operand(operand) {
+                OperandClass::Immediate if size == Size::Long => 4,
+                OperandClass::Immediate | OperandClass::Absolute | OperandClass::Displacement => 2,
+                _ => 0,
+            })
+            .sum();
+        2 + extension
+    }
+
+    /// Kürzere Form einer Instruktion. `None` = unverändert, `Some(None)` = entfällt.
+    ///
+    /// Das Weglassen von `MOVE Dx, Dx` ändert die Flags nicht mehr (MOVE setzt N/Z und
+    /// löscht V/C); deshalb ist der Pass nur auf Wunsch aktiv.
+    fn peephole(&self, instruction: &AssemblyInstruction) -> Option<Option<AssemblyInstruction>> {
+        let [source, dest] = instruction.operands.as_slice() else {
+            return None;
+        };
+        self.parse_data_register(dest)?;
+        let suffix = instruction.size_suffix.as_deref();
+        let immediate = source
+            .strip_prefix('#')
+            .and_then(|value| self.evaluate_expression(value).ok());
+
+        let replacement = match (instruction.mnemonic.as_str(), immediate) {
+            ("MOVE", Some(value)) if suffix == Some("L") && (-128..=127).contains(&value) => {
+                format!("MOVEQ #{}, {}", value, dest)
+            }
+            ("ADD" | "SUB", Some(value)) if (1..=8).contains(&value) => format!(
+                "{}Q{} #{}, {}",
+                instruction.mnemonic,
+                suffix.map_or(String::new(), |s| format!(".{}", s)),
+                value,
+                dest
+            ),
+            ("MOVE", None) if source.eq_ignore_ascii_case(dest) => return Some(None),
+            _ => return None,
+        };
+        let mut shorter = self.parse_instruction(
+            &replacement,
+            &replacement,
+            instruction.line,
+            instruction.address,
+        );
+        // Spalten der ursprünglichen Zeile behalten
+        shorter.mnemonic_columns = instruction.mnemonic_columns.clone();
+        shorter.operand_columns = instruction.operand_columns.clone();
+        Some(Some(shorter))
+    }
+
+    /// Instruktion als Quelltext ("SUBQ.W #1, D0")
+    fn instruction_text(instruction: &AssemblyInstruction) -> String {
+        let mut text = instruction.mnemonic.clone();
+        if let Some(suffix) = &instruction.size_suffix {
+            text = format!("{}.{}", text, suffix);
+        }
+        if !instruction.operands.is_empty() {
+            text = format!("{} {}", text, instruction.operands.join(", "));
+        }
+        text
+    }
+
     // MOVEQ #immediate, Dn
     fn encode_moveq(&self, instruction: &AssemblyInstruction) -> Option<u16> {
         if instruction.operands.len() != 2 {
-            println!(
+            echo!(
                 "MOVEQ: Erwarte 2 Operanden, gefunden: {}",
                 instruction.operands.len()
             );
             return None;
         }
 
-        let immediate = self.parse_immediate(&instruction.operands[0])?;
+        let immediate = match Self::sign_extended_moveq(self.strict_moveq, instruction) {
+            Some(value) => value as i8,
+            None => self.parse_immediate(&instruction.operands[0])?,
+        };
         let register = self.parse_data_register(&instruction.operands[1])?;
 
         // MOVEQ: 0111 RRR0 DDDDDDDD
@@ -270,81 +1342,29 @@ impl Assembler {
         Some(opcode)
     }
 
-    // MOVE with extension word support
-    fn encode_move_with_ext(
-        &self,
-        instruction: &AssemblyInstruction,
-    ) -> Option<(u16, Option<u16>)> {
-        if instruction.operands.len() != 2 {
+    // MOVE.s <ea>, <ea>: 00SS RRR MMM MMM RRR
+    // Quelle Dn, (An), (An)+, -(An), #imm oder Label; Ziel dasselbe ohne #imm
+    fn encode_move_with_ext(&self, instruction: &AssemblyInstruction) -> Option<(u16, Vec<u16>)> {
+        let [source, dest] = instruction.operands.as_slice() else {
             return None;
+        };
+        if dest.starts_with('#') || self.parse_address_register(dest).is_some() {
+            return None; // MOVE nach An ist MOVEA
         }
 
-        let source = &instruction.operands[0];
-        let dest = &instruction.operands[1];
-
-        // MOVE.L #immediate, Dn
-        if source.starts_with('#') {
-            if let Some(dest_reg) = self.parse_data_register(dest) {
-                if let Some(imm_value) = self.parse_immediate_u16(source) {
-                    // MOVE.L #imm, Dn: 0010 DDD 111 111 100 + extension word
-                    // Binary: 0010 000 1 111 111 00 = 0x21FC for D0
-                    let opcode = 0x21FC | ((dest_reg as u16) << 9);
-                    return Some((opcode, Some(imm_value)));
-                }
-            }
-        }
-
-        // MOVE.L (An), Dn - Address Register Indirect to Data Register
-        if let Some(src_areg) = self.parse_indirect_register(source) {
-            if let Some(dest_reg) = self.parse_data_register(dest) {
-                // MOVE.L (An), Dn: 0010 DDD 010 000 AAA
-                let opcode = 0x2010 | ((dest_reg as u16) << 9) | (src_areg as u16);
-                return Some((opcode, None));
-            }
-        }
-
-        // MOVE.L Dn, (An) - Data Register to Address Register Indirect
-        if let Some(src_reg) = self.parse_data_register(source) {
-            if let Some(dest_areg) = self.parse_indirect_register(dest) {
-                // MOVE.L Dn, (An): 0010 AAA 110 000 RRR
-                let opcode = 0x2080 | ((dest_areg as u16) << 9) | (src_reg as u16);
-                return Some((opcode, None));
-            }
-        }
-
-        // Check if source is a data register
-        if let Some(source_reg) = self.parse_data_register(source) {
-            // MOVE Dx, Dy
-            if let Some(dest_reg) = self.parse_data_register(dest) {
-                // MOVE.W Dx,Dy: 0011 DDD 000 000 SSS (Word Move, Data Register to Data Register)
-                let opcode = 0x3000 | ((dest_reg as u16) << 9) | (source_reg as u16);
-                return Some((opcode, None));
-            }
-        }
-
-        // Check if source is a label or absolute address (MOVE.L label, Dn)
-        if let Some(dest_reg) = self.parse_data_register(dest) {
-            // Lookup label address
-            if let Some(&label_addr) = self.labels.get(source) {
-                // MOVE.L (xxx).W, Dn
-                // Format: 0010 DDD 111 111 000
-                let opcode = 0x2078 | ((dest_reg as u16) << 9);
-                return Some((opcode, Some(label_addr as u16)));
-            }
-        }
-
-        // MOVE.L Dn, label - store to memory
-        if let Some(source_reg) = self.parse_data_register(source) {
-            // Lookup label address
-            if let Some(&label_addr) = self.labels.get(dest) {
-                // MOVE.L Dn, (xxx).W
-                // Format: 0010 0011 110 000 RRR
-                let opcode = 0x23C0 | (source_reg as u16);
-                return Some((opcode, Some(label_addr as u16)));
-            }
-        }
+        let size = self.operation_size(instruction, Size::Word);
+        let (source_ea, mut extensions) = self.encode_source_ea(source, size)?;
+        let (dest_ea, dest_extensions) = self.encode_source_ea(dest, size)?;
+        extensions.extend(dest_extensions);
 
-        None
+        let size_bits = match size {
+            Size::Byte => 0x1000,
+            Size::Word => 0x3000,
+            Size::Long => 0x2000,
+        };
+        // Ziel-EA steht im Opcode gespiegelt: Register in 11-9, Modus in 8-6
+        let dest_field = ((dest_ea & 0x7) << 9) | ((dest_ea >> 3) << 6);
+        Some((size_bits | dest_field | source_ea, extensions))
     }
 
     // MOVE Dx, Dy or MOVE.L label, Dn (old version, now deprecated)
@@ -354,10 +1374,7 @@ impl Assembler {
     }
 
     // MOVEA - Move Address (loads address into An register)
-    fn encode_movea_with_ext(
-        &self,
-        instruction: &AssemblyInstruction,
-    ) -> Option<(u16, Option<u16>)> {
+    fn encode_movea_with_ext(&self, instruction: &AssemblyInstruction) -> Option<(u16, Vec<u16>)> {
         if instruction.operands.len() != 2 {
             return None;
         }
@@ -365,20 +1382,37 @@ impl Assembler {
         let source = &instruction.operands[0];
         let dest = &instruction.operands[1];
 
-        // MOVEA.L #label, An
-        if source.starts_with('#') {
-            if let Some(dest_areg) = self.parse_address_register(dest) {
-                // Try to parse as immediate or label
-                let label_name = &source[1..]; // Remove #
-                if let Some(&label_addr) = self.labels.get(label_name) {
-                    // MOVEA.L #imm, An: 0010 AAA 111 111 100 + extension word
-                    let opcode = 0x207C | ((dest_areg as u16) << 9);
-                    return Some((opcode, Some(label_addr as u16)));
-                }
-            }
-        }
+        // MOVEA.s <ea>, An: 00SS AAA 001 MMM RRR (SS: 11 = Word, 10 = Long);
+        // #imm hat bei .L zwei Extension Words
+        let dest_areg = self.parse_address_register(dest)?;
+        let size = self.operation_size(instruction, Size::Long);
+        let size_bits = match size {
+            Size::Word => 0x3000,
+            Size::Long => 0x2000,
+            Size::Byte => return None,
+        };
+        let (ea, extensions) = self.encode_source_ea(source, size)?;
+        Some((
+            size_bits | ((dest_areg as u16) << 9) | 0x0040 | ea,
+            extensions,
+        ))
+    }
 
-        None
+    /// Mode/Register-Feld für Adressierungsarten ohne Extension Word:
+    /// Dn, An, (An), (An)+, -(An)
+    fn encode_register_ea(&self, operand: &str) -> Option<u16> {
+        if let Some(reg) = self.parse_data_register(operand) {
+            Some(reg as u16)
+        } else if let Some(reg) = self.parse_address_register(operand) {
+            Some(0x08 | reg as u16)
+        } else if let Some(reg) = self.parse_indirect_register(operand) {
+            Some(0x10 | reg as u16)
+        } else if let Some(inner) = operand.strip_suffix('+') {
+            Some(0x18 | self.parse_indirect_register(inner)? as u16)
+        } else {
+            let inner = operand.strip_prefix('-')?;
+            Some(0x20 | self.parse_indirect_register(inner)? as u16)
+        }
     }
 
     // MULS - Signed Multiply
@@ -387,10 +1421,7 @@ impl Assembler {
         self.encode_muls_with_ext(instruction).map(|(code, _)| code)
     }
 
-    fn encode_muls_with_ext(
-        &self,
-        instruction: &AssemblyInstruction,
-    ) -> Option<(u16, Option<u16>)> {
+    fn encode_muls_with_ext(&self, instruction: &AssemblyInstruction) -> Option<(u16, Vec<u16>)> {
         if instruction.operands.len() != 2 {
             return None;
         }
@@ -402,14 +1433,12 @@ impl Assembler {
         if let Some(dest_reg) = self.parse_data_register(dest) {
             if source.starts_with('#') {
                 // MULS.W #imm, Dn: 1100 RRR 111 111 100 + extension word
-                if let Some(imm_value) = self.parse_immediate_u16(source) {
-                    let opcode = 0xC1FC | ((dest_reg as u16) << 9);
-                    return Some((opcode, Some(imm_value)));
-                }
+                let opcode = 0xC1FC | ((dest_reg as u16) << 9);
+                return Some((opcode, self.encode_immediate(source, Size::Word)?));
             } else if let Some(src_reg) = self.parse_data_register(source) {
                 // MULS Ds, Dd: 1100 RRR 111 000 SSS
                 let opcode = 0xC1C0 | ((dest_reg as u16) << 9) | (src_reg as u16);
-                return Some((opcode, None));
+                return Some((opcode, vec![]));
             }
         }
 
@@ -430,261 +1459,542 @@ impl Assembler {
         Some(opcode)
     }
 
-    // ADD Dx, Dy (vereinfacht)
-    fn encode_add(&self, instruction: &AssemblyInstruction) -> Option<u16> {
-        if instruction.operands.len() != 2 {
+    // ADD.s <ea>, Dy: 1101 DDD 0SS MMM RRR
+    // SUB.s <ea>, Dy: 1001 DDD 0SS MMM RRR
+    // Quelle Dn (Modus 0) oder An (Modus 1, nur .W/.L)
+    fn encode_add_sub(&self, instruction: &AssemblyInstruction, base: u16) -> Option<u16> {
+        let [source, dest] = instruction.operands.as_slice() else {
             return None;
-        }
-
-        let source_reg = self.parse_data_register(&instruction.operands[0])?;
-        let dest_reg = self.parse_data_register(&instruction.operands[1])?;
+        };
+        let size = self.operation_size(instruction, Size::Word);
+        let source_ea = match self.operand_kind(source) {
+            OperandKind::DataRegister(reg) => reg as u16,
+            OperandKind::AddressRegister(reg) if size != Size::Byte => 0x08 | reg as u16,
+            _ => return None,
+        };
+        let OperandKind::DataRegister(dest_reg) = self.operand_kind(dest) else {
+            return None;
+        };
+        Some(base | ((dest_reg as u16) << 9) | (Self::size_bits(size) << 6) | source_ea)
+    }
 
-        // ADD.W Dx,Dy: 1101 DDD 001 000 SSS
-        let opcode = 0xD040 | ((dest_reg as u16) << 9) | (source_reg as u16);
-        Some(opcode)
+    // CMP #immediate, Dy oder CMP Dx, Dy
+    #[allow(dead_code)]
+    fn encode_cmp(&self, instruction: &AssemblyInstruction) -> Option<u16> {
+        self.encode_cmp_with_ext(instruction).map(|(code, _)| code)
     }
 
-    // SUB Dx, Dy (vereinfacht)
-    fn encode_sub(&self, instruction: &AssemblyInstruction) -> Option<u16> {
+    fn encode_cmp_with_ext(&self, instruction: &AssemblyInstruction) -> Option<(u16, Vec<u16>)> {
         if instruction.operands.len() != 2 {
             return None;
         }
 
-        let source_reg = self.parse_data_register(&instruction.operands[0])?;
-        let dest_reg = self.parse_data_register(&instruction.operands[1])?;
+        let size = self.operation_size(instruction, Size::Word);
+        let source = &instruction.operands[0];
+        let dest = &instruction.operands[1];
+
+        // CMPA <ea>, An: 1011 AAA S11 MMM RRR (S=0 Word, S=1 Long)
+        if let Some(dest_areg) = self.parse_address_register(dest) {
+            let opmode = match size {
+                Size::Word => 0x00C0,
+                Size::Long => 0x01C0,
+                Size::Byte => return None,
+            };
+            let (ea, extensions) = self.encode_source_ea(source, size)?;
+            let opcode = 0xB000 | ((dest_areg as u16) << 9) | opmode | ea;
+            return Some((opcode, extensions));
+        }
+
+        let dest_reg = self.parse_data_register(dest)?;
 
-        // SUB.W Dx,Dy: 1001 DDD 001 000 SSS
-        let opcode = 0x9040 | ((dest_reg as u16) << 9) | (source_reg as u16);
-        Some(opcode)
+        if source.starts_with('#') {
+            // CMPI.s #imm, Dn: 0000 1100 SS 000 RRR + 1 (B/W) oder 2 (L) Extension Words
+            let opcode = 0x0C00 | (Self::size_bits(size) << 6) | (dest_reg as u16);
+            return Some((opcode, self.encode_immediate(source, size)?));
+        }
+
+        if self.parse_address_register(source).is_some() && size == Size::Byte {
+            return None; // Adressregister nicht als Byte-Quelle
+        }
+
+        // CMP.s <ea>, Dn: 1011 DDD 0SS MMM RRR
+        let (ea, extensions) = self.encode_source_ea(source, size)?;
+        let opcode = 0xB000 | ((dest_reg as u16) << 9) | (Self::size_bits(size) << 6) | ea;
+        Some((opcode, extensions))
     }
 
-    // CMP #immediate, Dy oder CMP Dx, Dy
-    #[allow(dead_code)]
-    fn encode_cmp(&self, instruction: &AssemblyInstruction) -> Option<u16> {
-        self.encode_cmp_with_ext(instruction).map(|(code, _)| code)
+    /// Größe aus dem Suffix oder `default`, wenn keins angegeben ist
+    fn operation_size(&self, instruction: &AssemblyInstruction, default: Size) -> Size {
+        instruction
+            .size_suffix
+            .as_deref()
+            .and_then(Size::from_suffix)
+            .unwrap_or(default)
     }
 
-    fn encode_cmp_with_ext(&self, instruction: &AssemblyInstruction) -> Option<(u16, Option<u16>)> {
-        if instruction.operands.len() != 2 {
+    /// Größenbits 00/01/10 wie in CMP, CMPI, SUBQ, TST usw.
+    fn size_bits(size: Size) -> u16 {
+        match size {
+            Size::Byte => 0,
+            Size::Word => 1,
+            Size::Long => 2,
+        }
+    }
+
+    /// Immediate als Extension Words: B/W ein Wort (Byte im unteren Teil), L zwei Wörter.
+    /// Negative Werte im Zweierkomplement (#-1 → $FFFF bzw. $FFFFFFFF)
+    fn encode_immediate(&self, operand: &str, size: Size) -> Option<Vec<u16>> {
+        let value = self.immediate_value(operand)?;
+        if !Self::immediate_fits(value, size) {
             return None;
         }
+        let value = value as u32;
+        match size {
+            Size::Byte => Some(vec![value as u16 & 0xFF]),
+            Size::Word => Some(vec![value as u16]),
+            Size::Long => Some(vec![(value >> 16) as u16, value as u16]),
+        }
+    }
+
+    /// Wert eines Immediate-Operanden (#Ausdruck, auch negativ oder mit Symbolen)
+    fn immediate_value(&self, operand: &str) -> Option<i64> {
+        self.evaluate_expression(operand.strip_prefix('#')?).ok()
+    }
 
-        if instruction.operands[0].starts_with('#') {
-            // CMP.L #immediate, Dn - use CMPI.L
-            let immediate = self.parse_immediate_u16(&instruction.operands[0])?;
-            let dest_reg = self.parse_data_register(&instruction.operands[1])?;
+    /// Passt vorzeichenbehaftet oder vorzeichenlos in `size` (#-1 wie #$FFFF für .W)
+    fn immediate_fits(value: i64, size: Size) -> bool {
+        let bits = size.bytes() * 8;
+        (-(1i64 << (bits - 1))..1i64 << bits).contains(&value)
+    }
 
-            // CMPI.L #imm, Dn: 0000 1100 1000 0RRR + extension word
-            let opcode = 0x0C80 | (dest_reg as u16);
-            return Some((opcode, Some(immediate)));
+    /// Immediates von MOVE, MOVEA, MULS und CMP gegen die Operationsgröße prüfen (ADDQ/SUBQ
+    /// gegen 1..8), damit ein zu großer Wert nicht nur als "cannot encode operands" gemeldet wird
+    fn check_immediate_range(&self, instruction: &AssemblyInstruction) -> Result<(), AsmError> {
+        if matches!(instruction.mnemonic.as_str(), "ADDQ" | "SUBQ") {
+            return self.check_quick_range(instruction);
+        }
+        let size = match instruction.mnemonic.as_str() {
+            "MOVE" | "CMP" | "CMPI" | "CMPA" => self.operation_size(instruction, Size::Word),
+            "MOVEA" => self.operation_size(instruction, Size::Long),
+            "MULS" => Size::Word,
+            "ORI" | "ANDI" | "EORI" => Size::Byte,
+            _ => return Ok(()),
+        };
+        let Some(index) = instruction.operands.iter().position(|o| o.starts_with('#')) else {
+            return Ok(());
+        };
+        let operand = &instruction.operands[index];
+        match self.immediate_value(operand) {
+            Some(value) if !Self::immediate_fits(value, size) => Err(AsmError::new(
+                instruction.line,
+                AsmErrorKind::ValueOutOfRange,
+                format!(
+                    "{}: {} does not fit in a {} immediate",
+                    instruction.mnemonic,
+                    operand,
+                    match size {
+                        Size::Byte => "byte",
+                        Size::Word => "word",
+                        Size::Long => "long",
+                    }
+                ),
+            )
+            .at(instruction.operand_columns.get(index).cloned())),
+            _ => Ok(()),
+        }
+    }
+
+    /// ADDQ/SUBQ kodieren nur 1..=8 (8 als 0)
+    fn check_quick_range(&self, instruction: &AssemblyInstruction) -> Result<(), AsmError> {
+        let Some(operand) = instruction.operands.first() else {
+            return Ok(());
+        };
+        match self.immediate_value(operand) {
+            Some(value) if !(1..=8).contains(&value) => Err(AsmError::new(
+                instruction.line,
+                AsmErrorKind::ValueOutOfRange,
+                format!("{}: {} is outside 1..8", instruction.mnemonic, operand),
+            )
+            .at(instruction.operand_columns.first().cloned())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Mode/Register-Feld (Bits 5-0) und Extension Words für einen Quelloperanden:
+    /// Dn, An, #imm oder Label/Adresse (absolut kurz)
+    fn encode_source_ea(&self, operand: &str, size: Size) -> Option<(u16, Vec<u16>)> {
+        if let Some(ea) = self.encode_register_ea(operand) {
+            Some((ea, vec![]))
+        } else if operand.starts_with('#') {
+            Some((0x3C, self.encode_immediate(operand, size)?))
         } else {
-            // CMP Dx, Dy: 1011 DDD 001 000 SSS
-            let source_reg = self.parse_data_register(&instruction.operands[0])?;
-            let dest_reg = self.parse_data_register(&instruction.operands[1])?;
-            let opcode = 0xB040 | ((dest_reg as u16) << 9) | (source_reg as u16);
-            return Some((opcode, None));
+            let address = self.parse_immediate_address(operand)?;
+            Some((0x38, vec![address])) // (xxx).W
         }
     }
 
-    // JMP absolute address
-    fn encode_jump(&self, instruction: &AssemblyInstruction) -> Option<u16> {
-        if instruction.operands.len() != 1 {
+    // ORI/ANDI/EORI #imm, CCR: festes Opcode-Wort, das Byte im unteren Teil des Extension Words
+    fn encode_ccr_immediate(
+        &self,
+        instruction: &AssemblyInstruction,
+        opcode: u16,
+    ) -> Option<(u16, Vec<u16>)> {
+        let [source, _ccr] = instruction.operands.as_slice() else {
+            return None;
+        };
+        Some((opcode, self.encode_immediate(source, Size::Byte)?))
+    }
+
+    // JMP (An) oder JMP label/Adresse
+    fn encode_jump(&self, instruction: &AssemblyInstruction) -> Option<(u16, Vec<u16>)> {
+        let [target] = instruction.operands.as_slice() else {
             return None;
+        };
+
+        // JMP (An): 0100 1110 1101 0AAA
+        if let Some(reg) = self.parse_indirect_register(target) {
+            return Some((0x4ED0 | reg as u16, vec![]));
         }
 
-        // JMP $address oder JMP address (absolute)
-        if self
-            .parse_immediate_address(&instruction.operands[0])
-            .is_some()
-        {
-            // JMP.W $xxxx.W: 0100 1110 1111 1000
-            Some(0x4EF8)
-        } else {
-            println!(
-                "JMP benötigt eine absolute Adresse: {}",
-                instruction.operands[0]
-            );
-            None
+        // JMP (xxx).W: 0100 1110 1111 1000 + Adresse
+        match self.parse_immediate_address(target) {
+            Some(address) => Some((0x4EF8, vec![address])),
+            None => {
+                if !self.provisional {
+                    echo!("JMP benötigt eine absolute Adresse: {}", target);
+                }
+                None
+            }
         }
     }
 
-    // TST.L Dn - Test operand
+    // TST.s Dn: 0100 1010 SS 000 RRR
     fn encode_tst(&self, instruction: &AssemblyInstruction) -> Option<u16> {
-        if instruction.operands.len() != 1 {
+        let [operand] = instruction.operands.as_slice() else {
             return None;
-        }
-
-        let reg = self.parse_data_register(&instruction.operands[0])?;
-        // TST.L Dn: 0100 1010 1000 0RRR
-        let opcode = 0x4A80 | (reg as u16);
-        Some(opcode)
+        };
+        let reg = self.parse_data_register(operand)?;
+        let size = self.operation_size(instruction, Size::Word);
+        Some(0x4A00 | (Self::size_bits(size) << 6) | reg as u16)
     }
 
-    // SUBQ.L #immediate, Dn - Subtract quick
-    fn encode_subq(&self, instruction: &AssemblyInstruction) -> Option<u16> {
+    // SUBQ.s #immediate, Dn/An - Subtract quick (1..=8, ohne Suffix .W)
+    // ADDQ/SUBQ.s #imm, Dn: 0101 DDD 0SS 000 RRR bzw. 0101 DDD 1SS 000 RRR, An mit Mode 001
+    fn encode_quick(&self, instruction: &AssemblyInstruction, base: u16) -> Option<u16> {
         if instruction.operands.len() != 2 {
             return None;
         }
 
-        let immediate = self.parse_immediate(&instruction.operands[0])? as u16;
-        let reg = self.parse_data_register(&instruction.operands[1])?;
+        let immediate = self.parse_immediate(&instruction.operands[0])?;
+        if !(1..=8).contains(&immediate) {
+            return None;
+        }
+        let destination = &instruction.operands[1];
+        let ea = match self.parse_data_register(destination) {
+            Some(reg) => reg as u16,
+            None => 0x08 | self.parse_address_register(destination)? as u16,
+        };
+        let size = self.operation_size(instruction, Size::Word);
+        if size == Size::Byte && ea & 0x08 != 0 {
+            return None; // Adressregister haben keinen Byte-Zugriff
+        }
 
-        // Convert 8 to 0 for encoding (SUBQ uses 0 to represent 8)
-        let data = if immediate == 8 { 0 } else { immediate & 0x7 };
+        // Convert 8 to 0 for encoding (ADDQ/SUBQ use 0 to represent 8)
+        let data = (immediate as u16) & 0x7;
 
-        // SUBQ.L #imm, Dn: 0101 DDD 110 000 RRR
-        let opcode = 0x5180 | (data << 9) | (reg as u16);
+        let opcode = base | (data << 9) | (Self::size_bits(size) << 6) | ea;
         Some(opcode)
     }
 
-    // ASL.L #immediate, Dn - Arithmetic shift left
+    // ASL.s #count, Dn: 1110 CCC 1SS 000 RRR (Count 1..=8, 8 als 0)
     fn encode_asl(&self, instruction: &AssemblyInstruction) -> Option<u16> {
         if instruction.operands.len() != 2 {
             return None;
         }
 
-        let shift_count = self.parse_immediate(&instruction.operands[0])? as u16;
+        let shift_count = self.parse_immediate(&instruction.operands[0])?;
+        if !(1..=8).contains(&shift_count) {
+            return None;
+        }
         let reg = self.parse_data_register(&instruction.operands[1])?;
+        let size = self.operation_size(instruction, Size::Word);
 
-        // Convert 8 to 0 for encoding
-        let count = if shift_count == 8 {
-            0
-        } else {
-            shift_count & 0x7
-        };
-
-        // ASL.L #imm, Dn: 1110 CCC 110 100 RRR
-        let opcode = 0xE180 | (count << 9) | (reg as u16);
+        let count = (shift_count as u16) & 0x7;
+        let opcode = 0xE100 | (count << 9) | (Self::size_bits(size) << 6) | (reg as u16);
         Some(opcode)
     }
 
-    // DBRA Dn, label - Decrement and branch
-    fn encode_dbra(&self, instruction: &AssemblyInstruction) -> Option<u16> {
-        if instruction.operands.len() != 2 {
+    // DBRA Dn, label: 0101 0001 1100 1RRR + 16-Bit-Displacement (relativ zu Adresse + 2)
+    fn encode_dbra(&self, instruction: &AssemblyInstruction) -> Option<(u16, Vec<u16>)> {
+        let [counter, target] = instruction.operands.as_slice() else {
             return None;
-        }
+        };
 
-        let reg = self.parse_data_register(&instruction.operands[0])?;
-        let _displacement =
-            self.parse_branch_displacement(&instruction.operands[1], instruction.address)?;
+        let reg = self.parse_data_register(counter)?;
+        let displacement = match self.branch_target(target, instruction.address) {
+            Some(address) => i16::try_from(address as i64 - instruction.address as i64 - 2).ok()?,
+            None if target.starts_with(['+', '-']) => target.parse::<i16>().ok()?,
+            None => return None,
+        };
 
-        // DBRA Dn, disp: 0101 0001 1100 1RRR
-        // Note: DBRA displacement is 16-bit, but we'll use 8-bit for simplicity
-        let opcode = 0x51C8 | (reg as u16);
-        Some(opcode)
+        Some((0x51C8 | (reg as u16), vec![displacement as u16]))
     }
 
     // Hilfsfunktionen zum Parsen
 
-    fn parse_org_directive(&self, line: &str) -> Option<u32> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 2 {
-            return None;
+    /// ORG-Adresse; erlaubt Ausdrücke mit bereits definierten Symbolen (z.B. `OS_BASE+$100`)
+    fn parse_org_directive(&self, line: &str) -> Result<u32, (AsmErrorKind, String)> {
+        let expression = line
+            .split_once(char::is_whitespace)
+            .map(|(_, rest)| rest.trim())
+            .unwrap_or("");
+        if expression.is_empty() {
+            return Err((AsmErrorKind::OperandCount, "missing address".to_string()));
         }
 
-        let addr_str = parts[1];
-        if addr_str.starts_with('$') {
-            u32::from_str_radix(&addr_str[1..], 16).ok()
-        } else if addr_str.starts_with("0x") {
-            u32::from_str_radix(&addr_str[2..], 16).ok()
-        } else {
-            addr_str.parse::<u32>().ok()
+        let address = self.evaluate_expression(expression)?;
+        if !(0..=0x00FF_FFFF).contains(&address) {
+            return Err((
+                AsmErrorKind::ValueOutOfRange,
+                format!(
+                    "{} = ${:X} is outside the 24-bit address space",
+                    expression, address
+                ),
+            ));
         }
+        Ok(address as u32)
     }
 
-    #[allow(dead_code)]
-    fn parse_data_directive(&self, line: &str) -> Option<(String, u32)> {
-        self.parse_data_directive_with_value(line)
-            .map(|(label, size, _)| (label, size))
+    /// Parst DC/DS-Direktiven: (Label, Elementgröße, Werte). DS liefert keine Werte.
+    /// DC/DS als erstes Wort oder nach einem Label ohne Doppelpunkt ("TABLE DC.L 5")
+    fn is_data_directive(line: &str) -> bool {
+        line.split_whitespace().take(2).any(|word| {
+            let name = word.split('.').next().unwrap_or("");
+            name.eq_ignore_ascii_case("DC") || name.eq_ignore_ascii_case("DS")
+        })
     }
 
-    fn parse_data_directive_with_value(&self, line: &str) -> Option<(String, u32, Option<u32>)> {
-        // Parse DC.L and DS.L directives
-        let line_upper = line.to_uppercase();
+    /// Das Datenobjekt reicht jetzt bis `end` (exklusiv)
+    fn extend_object(&mut self, object: &Option<(String, u32)>, end: u32) {
+        if let Some((label, start)) = object {
+            self.object_sizes.insert(label.clone(), end - start);
+        }
+    }
 
-        // Extract label and directive part
-        let label: String;
-        let directive_str: String;
+    /// Länge einer DS-Direktive; die Anzahl muss wie bei ORG bereits auswertbar sein
+    fn reserved_length(
+        &self,
+        size: Size,
+        operands: &[String],
+    ) -> Result<u32, (AsmErrorKind, String)> {
+        let Some(count) = operands.first() else {
+            return Ok(size.bytes()); // DS ohne Anzahl: ein Element
+        };
+        let value = self.evaluate_expression(count)?;
+        if !(0..=0x00FF_FFFF).contains(&value) {
+            return Err((
+                AsmErrorKind::ValueOutOfRange,
+                format!("count {} = {} is out of range", count, value),
+            ));
+        }
+        Ok(size.bytes() * value as u32)
+    }
 
-        if line.contains(':') {
-            let parts: Vec<&str> = line.splitn(2, ':').collect();
-            label = parts[0].trim().to_string();
-            directive_str = parts.get(1).unwrap_or(&"").trim().to_string();
-        } else {
-            // Label might be the first word before DC/DS
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let first_word = parts[0].to_uppercase();
-                // If first word is not a directive, it's a label
-                if !first_word.starts_with("DC") && !first_word.starts_with("DS") {
-                    label = parts[0].to_string();
-                    directive_str = parts[1..].join(" ");
+    /// (Label, DS?, Größe, Operanden) einer DC/DS-Zeile
+    fn parse_data_directive(&self, line: &str) -> Option<(String, bool, Size, Vec<String>)> {
+        // Label abtrennen: "LABEL: DC.W 1" oder "LABEL DC.W 1" (ohne Kopien der Zeile)
+        let (label, directive_str) = match line.split_once(':') {
+            Some((label, rest)) => (label.trim(), rest.trim()),
+            None => {
+                let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                let is_directive = ["DC", "DS"].iter().any(|prefix| {
+                    first
+                        .get(..2)
+                        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+                });
+                if rest.trim().is_empty() || is_directive {
+                    ("", line)
                 } else {
-                    label = String::new();
-                    directive_str = line.to_string();
+                    (first, rest.trim())
                 }
-            } else {
-                label = String::new();
-                directive_str = line.to_string();
+            }
+        };
+
+        let (directive, operands) = directive_str
+            .split_once(char::is_whitespace)
+            .unwrap_or((directive_str, ""));
+        let directive = directive.to_uppercase();
+
+        // Determine size based on directive (default: word)
+        let size = directive
+            .split_once('.')
+            .and_then(|(_, suffix)| Size::from_suffix(suffix))
+            .unwrap_or(Size::Word);
+
+        // DC: Werte, DS: Anzahl der Elemente
+        let values = operands
+            .split(',')
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect();
+
+        Some((label.to_string(), directive.starts_with("DS"), size, values))
+    }
+
+    /// Wertet die Werte einer DC-Direktive aus und liefert die Bytes (Big-Endian)
+    fn encode_data(&self, directive: &DataDirective) -> Result<Vec<u8>, AsmError> {
+        let mut bytes = Vec::new();
+        for expression in &directive.values {
+            let value = self
+                .evaluate_expression(expression)
+                .map_err(|(kind, message)| AsmError::new(directive.line, kind, message))?;
+
+            // Labels sind Adressen und damit vorzeichenlos
+            let has_symbol = expression
+                .split(['+', '-'])
+                .any(|term| self.labels.contains_key(term.trim()));
+            let bits = directive.size.bytes() * 8;
+            let max = (1i64 << bits) - 1;
+            let min = if has_symbol { 0 } else { -(1i64 << (bits - 1)) };
+            if value < min || value > max {
+                return Err(AsmError::new(
+                    directive.line,
+                    AsmErrorKind::ValueOutOfRange,
+                    format!(
+                        "DC.{}: {} = ${:X} does not fit in {} bytes",
+                        directive.size.suffix(),
+                        expression,
+                        value,
+                        directive.size.bytes()
+                    ),
+                ));
+            }
+
+            let value = value as u32;
+            for i in (0..directive.size.bytes()).rev() {
+                bytes.push((value >> (8 * i)) as u8);
             }
         }
+        Ok(bytes)
+    }
 
-        // Determine size based on directive
-        let size = if line_upper.contains("DC.L") || line_upper.contains("DS.L") {
-            4 // Long word = 4 bytes
-        } else if line_upper.contains("DC.W") || line_upper.contains("DS.W") {
-            2 // Word = 2 bytes
-        } else if line_upper.contains("DC.B") || line_upper.contains("DS.B") {
-            1 // Byte = 1 byte
-        } else {
-            2 // Default to word
+    /// Ausdruck aus Zahlen und Labels, verbunden mit + und - (z.B. `TABLE+4`, `END-START`)
+    /// "D0 == 5", "CCR == $04" oder "(RESULT) != -1" mit Symbolen auf beiden Seiten
+    fn resolve_assertion(
+        &self,
+        directive: &AssertDirective,
+    ) -> Result<Assertion, (AsmErrorKind, String)> {
+        let (target, comparison, value) = Comparison::split(&directive.text).ok_or_else(|| {
+            (
+                AsmErrorKind::InvalidOperand,
+                format!("expected a comparison in '{}'", directive.text),
+            )
+        })?;
+        let target = target.trim();
+        let target = match target
+            .strip_prefix('(')
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            Some(address) => AssertTarget::Memory {
+                address: self.evaluate_expression(address)? as u32 & 0x00FF_FFFF,
+                size: directive.size,
+            },
+            None => AssertTarget::Register(
+                parse_register(target)
+                    .map_err(|message| (AsmErrorKind::IllegalOperand, message))?,
+            ),
         };
+        let value = self.evaluate_expression(value.trim())?;
+        Ok(Assertion {
+            address: directive.address,
+            line: directive.line,
+            target,
+            comparison,
+            value: value as u32,
+        })
+    }
 
-        // Extract value for DC directives (DS just reserves space)
-        let value = if line_upper.contains("DC.") {
-            // Find the value after DC.L/DC.W/DC.B
-            let parts: Vec<&str> = directive_str.split_whitespace().collect();
-            if parts.len() >= 2 {
-                // parts[0] should be DC.L, parts[1] should be the value
-                let value_str = parts[1].trim();
-                if value_str.starts_with('$') {
-                    u32::from_str_radix(&value_str[1..], 16).ok()
-                } else if value_str.starts_with("0x") {
-                    u32::from_str_radix(&value_str[2..], 16).ok()
-                } else {
-                    value_str.parse::<u32>().ok()
-                }
-            } else {
-                None
+    fn evaluate_expression(&self, expression: &str) -> Result<i64, (AsmErrorKind, String)> {
+        let mut total = 0i64;
+        let mut sign = 1i64;
+        let mut term = String::new();
+
+        for c in expression.chars().chain(std::iter::once('+')) {
+            if (c == '+' || c == '-') && !term.trim().is_empty() {
+                let value = self.evaluate_term(term.trim())?;
+                total = value
+                    .checked_mul(sign)
+                    .and_then(|value| total.checked_add(value))
+                    .ok_or_else(|| {
+                        (
+                            AsmErrorKind::ValueOutOfRange,
+                            format!("{} overflows", expression.trim()),
+                        )
+                    })?;
+                term.clear();
+                sign = if c == '-' { -1 } else { 1 };
+            } else if c == '-' {
+                sign = -sign; // unäres Minus
+            } else if c != '+' {
+                term.push(c);
             }
+        }
+        Ok(total)
+    }
+
+    fn evaluate_term(&self, term: &str) -> Result<i64, (AsmErrorKind, String)> {
+        let number = if let Some(hex) = term.strip_prefix('$').or_else(|| term.strip_prefix("0x")) {
+            i64::from_str_radix(hex, 16).ok()
+        } else if let Some(binary) = term.strip_prefix('%') {
+            i64::from_str_radix(binary, 2).ok()
+        } else if term.starts_with(|c: char| c.is_ascii_digit()) {
+            term.parse::<i64>().ok()
         } else {
-            None // DS directive - no initial value
+            return match self.symbol(term) {
+                Some(address) => Ok(address as i64),
+                None => Err((
+                    AsmErrorKind::UndefinedSymbol,
+                    format!("undefined symbol {}", term),
+                )),
+            };
         };
+        number.ok_or((
+            AsmErrorKind::InvalidOperand,
+            format!("invalid value {}", term),
+        ))
+    }
 
-        Some((label, size, value))
+    /// Wert eines Labels oder EQU; im ersten Pass ist ein noch unbekanntes Symbol 0
+    fn symbol(&self, name: &str) -> Option<u32> {
+        match self.labels.get(name) {
+            Some(&value) => Some(value),
+            None => self.provisional.then_some(0),
+        }
     }
 
-    fn parse_immediate(&self, operand: &str) -> Option<i8> {
-        if !operand.starts_with('#') {
-            return None;
+    /// Wie `symbol`, aber ein unbekanntes Sprungziel liegt im ersten Pass direkt hinter der
+    /// Instruktion, damit die vorläufige Kodierung nicht an der Reichweite scheitert
+    fn branch_target(&self, name: &str, address: u32) -> Option<u32> {
+        match self.labels.get(name) {
+            Some(&target) => Some(target),
+            None => self.provisional.then_some(address + 4),
         }
+    }
 
-        let value_str = &operand[1..];
-        if value_str.starts_with("0x") || value_str.starts_with("$") {
-            // Hexadezimal
-            let hex_str = if let Some(stripped) = value_str.strip_prefix("0x") {
-                stripped
-            } else {
-                &value_str[1..]
-            };
-            i8::from_str_radix(hex_str, 16).ok()
-        } else {
-            // Dezimal
-            value_str.parse::<i8>().ok()
+    /// Byte eines MOVEQ #128..255, wenn `strict_moveq` aus ist; das Vorzeichen kippt
+    fn sign_extended_moveq(strict: bool, instruction: &AssemblyInstruction) -> Option<u8> {
+        if strict || instruction.mnemonic != "MOVEQ" {
+            return None;
         }
+        let value = instruction.operands.first()?.strip_prefix('#')?;
+        let value = match value.strip_prefix("0x").or_else(|| value.strip_prefix('$')) {
+            Some(hex) => u8::from_str_radix(hex, 16).ok()?,
+            None => value.parse::<u8>().ok()?,
+        };
+        (value >= 0x80).then_some(value)
     }
 
-    fn parse_immediate_u16(&self, operand: &str) -> Option<u16> {
+    fn parse_immediate(&self, operand: &str) -> Option<i8> {
         if !operand.starts_with('#') {
             return None;
         }
@@ -697,10 +2007,10 @@ impl Assembler {
             } else {
                 &value_str[1..]
             };
-            u16::from_str_radix(hex_str, 16).ok()
+            i8::from_str_radix(hex_str, 16).ok()
         } else {
             // Dezimal
-            value_str.parse::<u16>().ok()
+            value_str.parse::<i8>().ok()
         }
     }
 
@@ -739,6 +2049,33 @@ impl Assembler {
         None
     }
 
+    /// Ordnet einen Operanden seiner Adressierungsart zu, bei Registern samt Nummer
+    fn operand_kind(&self, operand: &str) -> OperandKind {
+        if operand.starts_with('#') {
+            OperandKind::Immediate
+        } else if operand == "CCR" {
+            OperandKind::ConditionCodes
+        } else if let Some(reg) = self.parse_data_register(operand) {
+            OperandKind::DataRegister(reg)
+        } else if let Some(reg) = self.parse_address_register(operand) {
+            OperandKind::AddressRegister(reg)
+        } else if let Some(reg) = self.parse_indirect_register(operand) {
+            OperandKind::Indirect(reg)
+        } else if operand.starts_with('(') && operand.ends_with(")+") {
+            OperandKind::PostIncrement
+        } else if operand.starts_with("-(") && operand.ends_with(')') {
+            OperandKind::PreDecrement
+        } else if operand.contains('(') && operand.ends_with(')') {
+            OperandKind::Displacement
+        } else {
+            OperandKind::Absolute
+        }
+    }
+
+    fn classify_operand(&self, operand: &str) -> OperandClass {
+        self.operand_kind(operand).class()
+    }
+
     fn parse_immediate_address(&self, operand: &str) -> Option<u16> {
         // $xxxx oder 0xxxxx Format
         if operand.starts_with('$') {
@@ -749,18 +2086,14 @@ impl Assembler {
             operand.parse::<u16>().ok()
         } else {
             // Label lookup
-            if let Some(&address) = self.labels.get(operand) {
-                Some(address as u16)
-            } else {
-                None
-            }
+            self.symbol(operand).map(|address| address as u16)
         }
     }
 
     fn parse_branch_displacement(&self, operand: &str, current_address: u32) -> Option<i8> {
         // Label-Referenz
-        if let Some(&target_address) = self.labels.get(operand) {
-            let displacement = (target_address as i32) - (current_address as i32) - 2;
+        if let Some(target_address) = self.branch_target(operand, current_address) {
+            let displacement = target_address as i64 - current_address as i64 - 2;
             if (-128..=127).contains(&displacement) {
                 return Some(displacement as i8);
             }
@@ -774,26 +2107,42 @@ impl Assembler {
         None
     }
 
+    /// Listing-Zeile "001000: 203C 0001 86A0  MOVE.L #100000, D0"; None vor dem zweiten Pass
+    fn listing_line(instruction: &AssemblyInstruction) -> Option<String> {
+        let words: Vec<String> = std::iter::once(instruction.machine_code?)
+            .chain(instruction.extensions.iter().copied())
+            .map(|word| format!("{:04X}", word))
+            .collect();
+        Some(format!(
+            "{:06X}: {:<14}  {}",
+            instruction.address,
+            words.join(" "),
+            Self::instruction_text(instruction)
+        ))
+    }
+
+    /// "BUFFER: 001010 (16 bytes)"; die Größe nur bei Datenobjekten
+    fn label_line(&self, label: &str, address: u32) -> String {
+        match self.object_sizes.get(label) {
+            Some(size) => format!("{}: {:06X} ({} bytes)", label, address, size),
+            None => format!("{}: {:06X}", label, address),
+        }
+    }
+
     /// Debug: Zeigt alle geparsten Instruktionen an
     #[allow(dead_code)]
     pub fn print_assembly(&self) {
         println!("=== Assembly Listing ===");
         for instruction in &self.instructions {
-            if let Some(machine_code) = instruction.machine_code {
-                println!(
-                    "{:06X}: {:04X}  {} {}",
-                    instruction.address,
-                    machine_code,
-                    instruction.mnemonic,
-                    instruction.operands.join(", ")
-                );
+            if let Some(line) = Self::listing_line(instruction) {
+                println!("{}", line);
             }
         }
 
         if !self.labels.is_empty() {
             println!("\n=== Labels ===");
             for (label, address) in &self.labels {
-                println!("{}: {:06X}", label, address);
+                println!("{}", self.label_line(label, *address));
             }
         }
     }
@@ -802,21 +2151,17 @@ impl Assembler {
     pub fn print_assembly_to_string(&self, output: &mut String) {
         output.push_str("=== Assembly Listing ===\n");
         for instruction in &self.instructions {
-            if let Some(machine_code) = instruction.machine_code {
-                output.push_str(&format!(
-                    "{:06X}: {:04X}  {} {}\n",
-                    instruction.address,
-                    machine_code,
-                    instruction.mnemonic,
-                    instruction.operands.join(", ")
-                ));
+            if let Some(line) = Self::listing_line(instruction) {
+                output.push_str(&line);
+                output.push('\n');
             }
         }
 
         if !self.labels.is_empty() {
             output.push_str("\n=== Labels ===\n");
             for (label, address) in &self.labels {
-                output.push_str(&format!("{}: {:06X}\n", label, address));
+                output.push_str(&self.label_line(label, *address));
+                output.push('\n');
             }
         }
         output.push('\n');
@@ -846,5 +2191,776 @@ mod tests {
         let mut assembler = Assembler::new();
         let code = assembler.assemble(&["BRA +2"]);
         assert_eq!(code[0].1, 0x6002);
+
+        // Bedingungsfeld wie in der CPU: PL = $A, MI = $B (nicht VC/VS)
+        let code = assembler.assemble(&["BPL +2", "BMI +2", "BGE +2"]);
+        let words: Vec<u16> = code.iter().map(|(_, word)| *word).collect();
+        assert_eq!(words, vec![0x6A02, 0x6B02, 0x6C02]);
+    }
+
+    #[test]
+    fn test_cmp_sizes() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&[
+            "CMP.B D1, D0",
+            "CMP.W D1, D0",
+            "CMP.L D1, D0",
+            "CMP.B #$12, D0",
+            "CMP.W #$1234, D3",
+            "CMP.L #$12345678, D0",
+            "CMP #7, D1",
+        ]);
+        let words: Vec<u16> = code.iter().map(|(_, word)| *word).collect();
+        assert_eq!(
+            words,
+            vec![
+                0xB001, 0xB041, 0xB081, // Register-Register
+                0x0C00, 0x0012, // CMPI.B
+                0x0C43, 0x1234, // CMPI.W
+                0x0C80, 0x1234, 0x5678, // CMPI.L
+                0x0C41, 0x0007, // ohne Suffix = .W
+            ]
+        );
+        assert_eq!(code[9].0, 0x0012, "CMPI.L takes six bytes");
+        assert_eq!(code[10].0, 0x0014);
+    }
+
+    #[test]
+    fn test_cmp_address_registers_and_labels() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&[
+            "LENGTH: NOP",
+            "CMPA.L A0, A1",
+            "CMPA.W D0, A2",
+            "CMP.L A3, D4",
+            "CMP.L D0, A1",
+            "CMP.W LENGTH, D0",
+        ]);
+        let words: Vec<u16> = code.iter().map(|(_, word)| *word).collect();
+        assert_eq!(
+            words,
+            vec![0x4E71, 0xB3C8, 0xB4C0, 0xB88B, 0xB3C0, 0xB078, 0x0000]
+        );
+    }
+
+    #[test]
+    fn test_cmp_immediate_out_of_range() {
+        assert_eq!(
+            first_error("CMP.B #$100, D0").kind,
+            AsmErrorKind::ValueOutOfRange
+        );
+        assert_eq!(
+            first_error("CMP.W #$10000, D0").kind,
+            AsmErrorKind::ValueOutOfRange
+        );
+        assert_eq!(first_error("CMPA.B A0, A1").kind, AsmErrorKind::InvalidSize);
+    }
+
+    #[test]
+    fn test_negative_and_wide_immediates() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&[
+            "MOVE.L #-1, D0",
+            "MOVE.W #-1, D1",
+            "MOVE.B #-1, D2",
+            "MOVE.W #-32768, D3",
+            "CMP.L #-5, D1",
+            "CMP.W #$FFFF, D1",
+            "MOVE.L #$80000000, D4",
+            "MULS #-2, D5",
+            "MOVE.W #65535, (A0)",
+        ]);
+        let words: Vec<u16> = code.iter().map(|(_, word)| *word).collect();
+        assert_eq!(
+            words,
+            vec![
+                0x203C, 0xFFFF, 0xFFFF, // MOVE.L #-1, D0
+                0x323C, 0xFFFF, // MOVE.W #-1, D1
+                0x143C, 0x00FF, // MOVE.B #-1, D2
+                0x363C, 0x8000, // MOVE.W #-32768, D3
+                0x0C81, 0xFFFF, 0xFFFB, // CMPI.L #-5, D1
+                0x0C41, 0xFFFF, // CMPI.W #$FFFF, D1
+                0x283C, 0x8000, 0x0000, // MOVE.L #$80000000, D4
+                0xCBFC, 0xFFFE, // MULS #-2, D5
+                0x30BC, 0xFFFF, // MOVE.W #65535, (A0)
+            ]
+        );
+        assert!(assembler.errors().is_empty());
+    }
+
+    #[test]
+    fn test_immediate_range_errors() {
+        let error = first_error("MOVE.W #70000, D0");
+        assert_eq!(error.kind, AsmErrorKind::ValueOutOfRange);
+        assert_eq!(
+            error.to_string(),
+            "line 1: MOVE: #70000 does not fit in a word immediate"
+        );
+        assert_eq!(
+            first_error("MOVE.W #-32769, D0").to_string(),
+            "line 1: MOVE: #-32769 does not fit in a word immediate"
+        );
+        assert_eq!(
+            first_error("CMP.B #-129, D0").to_string(),
+            "line 1: CMP: #-129 does not fit in a byte immediate"
+        );
+        assert_eq!(
+            first_error("MOVE.L #$100000000, D0").to_string(),
+            "line 1: MOVE: #$100000000 does not fit in a long immediate"
+        );
+        assert_eq!(
+            first_error("MULS #$10000, D0").kind,
+            AsmErrorKind::ValueOutOfRange
+        );
+        assert_eq!(
+            first_error("MOVEA.W #$10000, A0").to_string(),
+            "line 1: MOVEA: #$10000 does not fit in a word immediate"
+        );
+        assert_eq!(
+            first_error("MOVEA.L #$100000000, A0").to_string(),
+            "line 1: MOVEA: #$100000000 does not fit in a long immediate"
+        );
+    }
+
+    #[test]
+    fn test_movea_negative_immediates() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&[
+            "MOVEA.L #-1, A0",
+            "MOVEA.L #$80000000, A1",
+            "MOVEA.W #-1, A2",
+        ]);
+        assert!(assembler.errors().is_empty(), "{:?}", assembler.errors());
+        let words: Vec<u16> = code.iter().map(|(_, word)| *word).collect();
+        assert_eq!(
+            words,
+            vec![0x207C, 0xFFFF, 0xFFFF, 0x227C, 0x8000, 0x0000, 0x347C, 0xFFFF]
+        );
+    }
+
+    #[test]
+    fn test_subq_sizes() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&[
+            "SUBQ.B #1, D0",
+            "SUBQ.W #8, D2",
+            "SUBQ.L #3, D7",
+            "SUBQ #1, D0",
+        ]);
+        let words: Vec<u16> = code.iter().map(|(_, word)| *word).collect();
+        assert_eq!(words, vec![0x5300, 0x5142, 0x5787, 0x5340]);
+
+        // Adressregister nur als .W/.L (Mode 001)
+        let code = assembler.assemble(&["ADDQ.W #1, A0", "SUBQ.L #8, A7"]);
+        assert_eq!(code, vec![(0, 0x5248), (2, 0x518F)]);
+        assert_eq!(
+            first_error("ADDQ.B #1, A0").kind,
+            AsmErrorKind::IllegalOperand
+        );
+
+        for line in [
+            "SUBQ.L #0, D0",
+            "SUBQ.L #9, D0",
+            "SUBQ.W #-1, D0",
+            "ADDQ #9, A0",
+        ] {
+            assert_eq!(
+                first_error(line).kind,
+                AsmErrorKind::ValueOutOfRange,
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_simhalt_encoding_and_legacy_flag() {
+        let mut assembler = Assembler::new();
+        assert_eq!(assembler.assemble(&["SIMHALT"]), vec![(0, 0xA000)]);
+        assert!(assembler.warnings().is_empty());
+
+        assembler.set_legacy_simhalt(true);
+        assert_eq!(assembler.assemble(&["NOP", "SIMHALT"])[1], (2, 0x4E72));
+        assert!(assembler.errors().is_empty());
+        assert_eq!(assembler.warnings().len(), 1);
+        assert_eq!(assembler.warnings()[0].kind, AsmErrorKind::Deprecated);
+        assert_eq!(assembler.warnings()[0].line, 2);
+    }
+
+    #[test]
+    fn test_moveq_above_127_needs_lenient_mode() {
+        let mut assembler = Assembler::new();
+        assert!(assembler.assemble(&["MOVEQ #200, D0"]).is_empty());
+        assert_eq!(assembler.errors().len(), 1);
+
+        assembler.set_strict_moveq(false);
+        let code = assembler.assemble(&["MOVEQ #200, D0", "MOVEQ #$FF, D1", "MOVEQ #-1, D2"]);
+        assert_eq!(code, vec![(0, 0x70C8), (2, 0x72FF), (4, 0x74FF)]);
+        assert!(assembler.errors().is_empty());
+        let warnings: Vec<String> = assembler.warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            [
+                "line 1: MOVEQ: #200 is sign-extended to -56",
+                "line 2: MOVEQ: #$FF is sign-extended to -1",
+            ]
+        );
+        assert_eq!(assembler.warnings()[0].kind, AsmErrorKind::SignExtended);
+        // Mehr als ein Byte bleibt ein Fehler
+        assert!(assembler.assemble(&["MOVEQ #300, D0"]).is_empty());
+    }
+
+    #[test]
+    fn test_label_on_directive_lines() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&[
+            "        ORG $0800",
+            "TABLE:  DC.L 5 ; Kommentar: mit Doppelpunkt",
+            "EMPTY:",
+            "BUFFER  DS.W 1",
+            "        ORG $1000",
+            "START:  MOVEQ #1, D0 ; Start",
+        ]);
+        assert!(assembler.errors().is_empty(), "{:?}", assembler.errors());
+        assert_eq!(assembler.labels.get("TABLE"), Some(&0x0800));
+        assert_eq!(assembler.labels.get("EMPTY"), Some(&0x0804));
+        assert_eq!(assembler.labels.get("BUFFER"), Some(&0x0804));
+        assert_eq!(assembler.labels.get("START"), Some(&0x1000));
+        assert_eq!(
+            code,
+            vec![(0x0800, 0x0000), (0x0802, 0x0005), (0x1000, 0x7001)]
+        );
+
+        let error = first_error("HERE: ORG $1000");
+        assert_eq!(error.kind, AsmErrorKind::MisplacedLabel);
+        assert_eq!(
+            error.to_string(),
+            "line 1: ORG: label HERE is not allowed here, put it on the next line"
+        );
+
+        // Labels, die mit ORG/END beginnen, sind keine Direktiven
+        assembler.assemble(&["ORGIN: NOP", "ENDING: BRA ORGIN"]);
+        assert!(assembler.errors().is_empty(), "{:?}", assembler.errors());
+        assert_eq!(assembler.labels.get("ENDING"), Some(&2));
+    }
+
+    #[test]
+    fn test_org_expressions() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&["ORG $2000-$1000+4", "START: NOP", "ORG START+$10", "NOP"]);
+        assert_eq!(code, vec![(0x1004, 0x4E71), (0x1014, 0x4E71)]);
+
+        assembler.assemble(&["ORG MISSING", "NOP"]);
+        assert_eq!(
+            assembler.errors()[0].to_string(),
+            "line 1: ORG: undefined symbol MISSING"
+        );
+    }
+
+    #[test]
+    fn test_dc_label_values() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&[
+            "        ORG $0800",
+            "TABLE:  DC.L FIRST, SECOND, SECOND+4", // Vorwärtsreferenzen
+            "SHORT:  DC.W SECOND-FIRST, -1",
+            "        ORG $1000",
+            "FIRST:  NOP",
+            "SECOND: NOP",
+        ]);
+        assert!(assembler.errors().is_empty(), "{:?}", assembler.errors());
+
+        let data: Vec<_> = code.iter().filter(|(addr, _)| *addr < 0x1000).collect();
+        assert_eq!(
+            data,
+            vec![
+                &(0x0800, 0x0000),
+                &(0x0802, 0x1000),
+                &(0x0804, 0x0000),
+                &(0x0806, 0x1002),
+                &(0x0808, 0x0000),
+                &(0x080A, 0x1006),
+                &(0x080C, 0x0002),
+                &(0x080E, 0xFFFF),
+            ]
+        );
+        assert_eq!(assembler.labels.get("SHORT"), Some(&0x080C));
+    }
+
+    #[test]
+    fn test_data_layout_and_alignment() {
+        let mut assembler = Assembler::new();
+        let program = assembler
+            .assemble_source(
+                "        ORG $0800
+BYTES:  DC.B 1, 2, 3
+WORD:   DC.W $1234
+BUFFER: DS.B 3
+LONG:   DC.L $DEADBEEF
+AFTER:  DS.W 2
+ODD:    DC.B 9
+CODE:   NOP",
+            )
+            .unwrap();
+
+        assert_eq!(program.symbols["WORD"], 0x0804); // ein Füllbyte nach DC.B
+        assert_eq!(program.symbols["BUFFER"], 0x0806);
+        assert_eq!(program.symbols["LONG"], 0x080A); // DS.B 3 + Füllbyte
+        assert_eq!(program.symbols["AFTER"], 0x080E);
+        assert_eq!(program.symbols["ODD"], 0x0812);
+        assert_eq!(program.symbols["CODE"], 0x0814);
+        assert_eq!(
+            program.data,
+            vec![
+                (0x0800, 1),
+                (0x0801, 2),
+                (0x0802, 3),
+                (0x0804, 0x12),
+                (0x0805, 0x34),
+                (0x080A, 0xDE),
+                (0x080B, 0xAD),
+                (0x080C, 0xBE),
+                (0x080D, 0xEF),
+                (0x0812, 9),
+            ]
+        );
+
+        let error = first_error("DS.W COUNT");
+        assert_eq!(error.kind, AsmErrorKind::UndefinedSymbol);
+        assert_eq!(error.to_string(), "line 1: DS.W: undefined symbol COUNT");
+    }
+
+    #[test]
+    fn test_easy68k_header() {
+        // Unveränderte Vorlage aus Easy68K
+        let source = "*-----------------------------------------------------------
+* Title      :
+* Written by :
+* Date       :
+* Description:
+*-----------------------------------------------------------
+    OPT     CRE
+    ORG    $1000
+START:                  ; first instruction of program
+
+* Put program code here
+    MOVEQ   #5, D0
+
+    SIMHALT             ; halt simulator
+
+* Put variables and constants here
+COUNT   EQU     5
+        TTL     Easy68K header
+
+    END    START        ; last line of source
+";
+        let mut assembler = Assembler::new();
+        let program = assembler.assemble_source(source).unwrap();
+        assert!(
+            assembler.warnings().is_empty(),
+            "{:?}",
+            assembler.warnings()
+        );
+        assert_eq!(program.entry_point(), Some(0x1000));
+        assert_eq!(program.image, vec![(0x1000, 0x7005), (0x1002, 0xA000)]);
+        assert_eq!(program.symbols["COUNT"], 5);
+        assert_eq!(
+            assembler.notes(),
+            ["ignored listing directives: OPT, TTL (they do not affect the program)"]
+        );
+
+        // END ohne Operand: wie bisher erste Instruktion ab $1000
+        let program = assembler
+            .assemble_source("ORG $0800\nNOP\nORG $1000\nNOP\nEND")
+            .unwrap();
+        assert_eq!(program.entry, None);
+        assert_eq!(program.entry_point(), Some(0x1000));
+        assert!(assembler.notes().is_empty());
+
+        let program = assembler
+            .assemble_source("ORG $0800\nINIT: NOP\nORG $1000\nNOP\nEND INIT")
+            .unwrap();
+        assert_eq!(program.entry_point(), Some(0x0800));
+
+        let errors = assembler.assemble_source("NOP\nEND MAIN").unwrap_err();
+        assert_eq!(errors[0].to_string(), "line 2: END: undefined symbol MAIN");
+    }
+
+    #[test]
+    fn test_stack_directive() {
+        let mut assembler = Assembler::new();
+        let program = assembler
+            .assemble_source("TOP EQU $8000\nSTACK TOP\nNOP")
+            .unwrap();
+        assert_eq!(program.initial_sp, Some(0x8000));
+        assert_eq!(program.image, vec![(0, 0x4E71)], "no code, no address");
+
+        let program = assembler.assemble_source("INITSP $4000\nNOP").unwrap();
+        assert_eq!(program.initial_sp, Some(0x4000));
+        let program = assembler.assemble_source("NOP").unwrap();
+        assert_eq!(program.initial_sp, None);
+
+        let errors = assembler.assemble_source("STACK $8001").unwrap_err();
+        assert_eq!(
+            errors[0].to_string(),
+            "line 1: STACK: stack pointer $008001 must be even"
+        );
+        let errors = assembler.assemble_source("SP: STACK $8000").unwrap_err();
+        assert_eq!(errors[0].kind, AsmErrorKind::MisplacedLabel);
+    }
+
+    #[test]
+    fn test_sections() {
+        let mut assembler = Assembler::new();
+        let program = assembler
+            .assemble_source(
+                "        ORG $1000
+        SECTION CODE
+START:  MOVEQ #1, D0
+        SECTION DATA
+VALUE:  DC.W $1234
+        SECTION CODE
+        NOP
+        EVEN",
+            )
+            .unwrap_err();
+        // CODE kann nicht hinter DATA weiterlaufen, ohne DATA zu überschreiben
+        assert_eq!(
+            program[0].to_string(),
+            "line 6: SECTION: CODE cannot continue at $001002, another section was placed there; use ORG instead"
+        );
+
+        let program = assembler
+            .assemble_source(
+                "        ORG $1000
+        SECTION code
+START:  MOVEQ #1, D0
+        SIMHALT
+        SECTION DATA
+MESSAGE: DC.B 7
+ALIGNED: EVEN
+        DC.W $1234
+        END START",
+            )
+            .unwrap();
+        assert_eq!(program.symbols["MESSAGE"], 0x1004);
+        assert_eq!(program.symbols["ALIGNED"], 0x1006);
+        let names: Vec<_> = program
+            .sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.start, s.end))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("CODE", 0x1000, 0x1004), ("DATA", 0x1004, 0x1008)]
+        );
+
+        let error = first_error("HERE: SECTION CODE");
+        assert_eq!(error.kind, AsmErrorKind::MisplacedLabel);
+
+        let error = first_error("        INCLUDE macros.x68");
+        assert_eq!(
+            error.to_string(),
+            "line 1: INCLUDE: directive is not supported by this assembler"
+        );
+    }
+
+    #[test]
+    fn test_peephole_optimizer() {
+        let source = "        ORG $1000
+START:  MOVE.L #5, D0
+LOOP:   SUB #1, D0
+        MOVE.W D1, D1
+        ADD.L #8, D2
+        BNE LOOP
+        BRA DONE
+        MOVE.L #1000, D3
+DONE:   SIMHALT";
+
+        // Ohne Optimierung ist ADD/SUB #n kein gültiger Operand
+        let mut assembler = Assembler::new();
+        assert!(assembler.assemble_source(source).is_err());
+
+        assembler.set_optimize(true);
+        let program = assembler.assemble_source(source).unwrap();
+        assert_eq!(
+            program.image,
+            vec![
+                (0x1000, 0x7005), // MOVEQ #5, D0
+                (0x1002, 0x5340), // SUBQ.W #1, D0
+                (0x1004, 0x5082), // ADDQ.L #8, D2
+                (0x1006, 0x66FA), // BNE LOOP (-6)
+                (0x1008, 0x6006), // BRA DONE (+6), hinter MOVE.L #1000 mit zwei Extension Words
+                (0x100A, 0x263C),
+                (0x100C, 0x0000),
+                (0x100E, 0x03E8),
+                (0x1010, 0xA000),
+            ]
+        );
+        assert_eq!(program.symbols["LOOP"], 0x1002);
+        assert_eq!(program.symbols["DONE"], 0x1010);
+        assert_eq!(
+            assembler.notes(),
+            [
+                "line 2: MOVE.L #5, D0 → MOVEQ #5, D0",
+                "line 3: SUB #1, D0 → SUBQ #1, D0",
+                "line 4: MOVE.W D1, D1 → (removed)",
+                "line 5: ADD.L #8, D2 → ADDQ.L #8, D2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dc_value_errors() {
+        let mut assembler = Assembler::new();
+        assembler.assemble(&["DC.L MISSING"]);
+        assert_eq!(assembler.errors()[0].kind, AsmErrorKind::UndefinedSymbol);
+        assert_eq!(
+            assembler.errors()[0].to_string(),
+            "line 1: undefined symbol MISSING"
+        );
+
+        let mut assembler = Assembler::new();
+        assembler.assemble(&["ORG $12340", "FAR: NOP", "ORG $0800", "DC.W FAR"]);
+        assert_eq!(assembler.errors()[0].kind, AsmErrorKind::ValueOutOfRange);
+        assert_eq!(assembler.errors()[0].line, 4);
+    }
+
+    fn first_error(line: &str) -> AsmError {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&[line]);
+        assert!(code.is_empty(), "'{}' should not generate code", line);
+        assembler
+            .errors()
+            .first()
+            .cloned()
+            .unwrap_or_else(|| panic!("'{}' should produce an error", line))
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let error = first_error("ADD D0, D1, D2");
+        assert_eq!(error.kind, AsmErrorKind::OperandCount);
+        assert_eq!(error.message, "ADD: expected 2 operands, found 3");
+
+        let error = first_error("TST #5");
+        assert_eq!(error.kind, AsmErrorKind::IllegalOperand);
+        assert_eq!(error.message, "TST: immediate operand not allowed");
+
+        let error = first_error("MOVEQ.B #1, D0");
+        assert_eq!(error.kind, AsmErrorKind::InvalidSize);
+        assert_eq!(error.message, "MOVEQ: size .B not allowed");
+    }
+
+    #[test]
+    fn test_address_register_sources() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&[
+            "ADD.L A0, D0",
+            "SUB.W A7, D3",
+            "MOVE.L A3, D1",
+            "MOVE.W A2, (A4)",
+        ]);
+        assert!(assembler.errors().is_empty(), "{:?}", assembler.errors());
+        let words: Vec<u16> = code.into_iter().map(|(_, word)| word).collect();
+        assert_eq!(words, [0xD088, 0x964F, 0x220B, 0x388A]);
+
+        for line in [
+            "ADD.B A0, D0",
+            "SUB.B A1, D1",
+            "MOVE.B A3, D1",
+            "CMP.B A2, D0",
+        ] {
+            let error = first_error(line);
+            assert_eq!(error.kind, AsmErrorKind::IllegalOperand, "{}", line);
+            assert!(
+                error
+                    .message
+                    .ends_with("address register operand not allowed with size .B"),
+                "{}: {}",
+                line,
+                error.message
+            );
+        }
+        // Ziel bleibt Dn: ADD nach An wäre ADDA
+        assert_eq!(
+            first_error("ADD.L D0, A1").kind,
+            AsmErrorKind::IllegalOperand
+        );
+    }
+
+    #[test]
+    fn test_error_columns() {
+        // Tab zählt als ein Zeichen; D9 steht in Spalte 12..14
+        assert_eq!(first_error("\tMOVE.L\tD1, D9").columns, Some(12..14));
+        assert_eq!(first_error("    MOVE.L D1, D9").columns, Some(15..17));
+        // Label und Kommentar verschieben nichts
+        assert_eq!(
+            first_error("loop: MOVE.L D1, D9 ; Kommentar").columns,
+            Some(17..19)
+        );
+
+        assert_eq!(first_error("  TST #5").columns, Some(6..8));
+        assert_eq!(first_error("  MOVEQ.B #1, D0").columns, Some(2..9));
+        assert_eq!(first_error("  FOO D0").columns, Some(2..5));
+        assert_eq!(first_error("  ADD D0, D1, D2").columns, Some(6..16));
+        assert_eq!(first_error("  MOVE.B #$1FF, D0").columns, Some(9..14));
+        // Nicht-ASCII vor dem Operanden: Zeichen, nicht Bytes
+        assert_eq!(first_error("ä: TST #5").columns, Some(7..9));
+    }
+
+    #[test]
+    fn test_malformed_operands_per_mnemonic() {
+        use AsmErrorKind::*;
+
+        let cases = [
+            ("MOVEQ #1", OperandCount),
+            ("MOVEQ D0, D1", IllegalOperand),
+            ("MOVEQ #1000, D0", InvalidOperand),
+            ("MOVE D0", OperandCount),
+            ("MOVE D0, #5", IllegalOperand),
+            ("MOVE.X D0, D1", InvalidSize),
+            ("MOVEA #1, D0", IllegalOperand),
+            ("MOVEA.B #1, A0", InvalidSize),
+            ("MULS D0, A0", IllegalOperand),
+            ("MULS.L #2, D0", InvalidSize),
+            ("TST D0, D1", OperandCount),
+            ("TST (A0)+", IllegalOperand),
+            ("SUBQ D0, D1", IllegalOperand),
+            ("ASL #1", OperandCount),
+            ("ASL #1, -(A0)", IllegalOperand),
+            ("DBRA #1, loop", IllegalOperand),
+            ("DBRA.W D0, loop", InvalidSize),
+            ("NOP D0", OperandCount),
+            ("SIMHALT #1", OperandCount),
+            ("ADD (A0), D0", IllegalOperand),
+            ("SUB D0, 4(A0)", IllegalOperand),
+            ("CMP D0, #1", IllegalOperand),
+            ("JMP", OperandCount),
+            ("JMP #5", IllegalOperand),
+            ("JUMP.L $1000", InvalidSize),
+            ("FOO D0", UnknownInstruction),
+        ];
+
+        for (line, kind) in cases {
+            assert_eq!(
+                first_error(line).kind,
+                kind,
+                "wrong error kind for '{}'",
+                line
+            );
+        }
+
+        for branch in [
+            "BRA", "BEQ", "BNE", "BCC", "BCS", "BPL", "BMI", "BGE", "BLT", "BGT", "BLE",
+        ] {
+            assert_eq!(first_error(branch).kind, OperandCount, "{}", branch);
+            let line = format!("{} D0", branch);
+            assert_eq!(first_error(&line).kind, IllegalOperand, "{}", line);
+            let line = format!("{}.L target", branch);
+            assert_eq!(first_error(&line).kind, InvalidSize, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_extra_operand_rejected_for_every_table_entry() {
+        for spec in isa::INSTRUCTIONS {
+            let operands = vec!["D0"; spec.operand_count() + 1].join(", ");
+            let line = format!("{} {}", spec.mnemonic, operands);
+            let error = first_error(&line);
+            assert_eq!(error.kind, AsmErrorKind::OperandCount, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_error_reports_source_line() {
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&["MOVEQ #1, D0", "", "TST #5", "NOP"]);
+
+        assert_eq!(code.len(), 2, "valid lines should still be assembled");
+        assert_eq!(assembler.errors().len(), 1);
+        assert_eq!(assembler.errors()[0].line, 3);
+        assert_eq!(
+            assembler.errors()[0].to_string(),
+            "line 3: TST: immediate operand not allowed"
+        );
+    }
+
+    #[test]
+    fn test_reserved_length_matches_emitted_words() {
+        // Vorwärtsreferenzen in Immediates, Adressen und Sprüngen: jede Instruktion muss
+        // genau so viele Wörter erzeugen, wie der erste Pass reserviert hat
+        let source = [
+            "START:  MOVE.L  #100000, D0",
+            "        MOVEA.L #TABLE, A0",
+            "        cmpi.l  #LIMIT, D0",
+            "        BGE     DONE",
+            "        CMP.L   #$12345678, D1",
+            "        MOVE.W  TABLE, D2",
+            "        MOVE.L  #-1, TABLE",
+            "        DBRA    D3, START",
+            "        JMP     DONE",
+            "        ORI     #$1F, CCR",
+            "DONE:   SIMHALT",
+            "LIMIT:  EQU     $10000",
+            "TABLE:  DC.W    1",
+        ];
+        let mut assembler = Assembler::new();
+        let code = assembler.assemble(&source);
+        assert!(assembler.errors().is_empty(), "{:?}", assembler.errors());
+
+        let instructions = &assembler.instructions;
+        let lengths: Vec<u32> = instructions.iter().map(|inst| inst.size).collect();
+        assert_eq!(lengths, [6, 6, 6, 2, 6, 4, 8, 4, 4, 4, 2]);
+        for pair in instructions.windows(2) {
+            let emitted = 2 + 2 * pair[0].extensions.len() as u32;
+            assert_eq!(pair[0].size, emitted, "line {}", pair[0].line);
+            assert_eq!(pair[1].address, pair[0].address + emitted);
+        }
+        assert_eq!(assembler.labels["DONE"], 0x32);
+        assert_eq!(assembler.labels["TABLE"], 0x34);
+
+        let words: BTreeMap<u32, u16> = code.into_iter().collect();
+        // BGE DONE: Displacement von $0012 zu $0032
+        assert_eq!(words[&0x12], 0x6C1E);
+        // CMPI.L #LIMIT: beide Extension Words mit dem EQU-Wert
+        assert_eq!(
+            [words[&0x0C], words[&0x0E], words[&0x10]],
+            [0x0C80, 0x0001, 0x0000]
+        );
+        assert_eq!(words[&0x34], 0x0001);
+
+        let mut listing = String::new();
+        assembler.print_assembly_to_string(&mut listing);
+        assert!(listing.contains("000000: 203C 0001 86A0  MOVE.L #100000, D0"));
+        assert!(listing.contains("TABLE: 000034 (2 bytes)"));
+    }
+
+    #[test]
+    fn test_assert_directive() {
+        let source = "        MOVEQ   #5, D0\n\
+                      CHECK:  ASSERT  D0 == 5\n\
+                      \x20       ASSERT.L (DATA+2) != $FFFF\n\
+                      \x20       SIMHALT\n\
+                      DATA:   DC.W    1, 2";
+        let mut assembler = Assembler::new();
+        let program = assembler.assemble_source(source).unwrap();
+        // ASSERT erzeugt keinen Code und gilt vor der nächsten Instruktion
+        assert_eq!(assembler.labels["CHECK"], 2);
+        assert_eq!(assembler.labels["DATA"], 4);
+        let assertions = program.assertions_at(2);
+        assert_eq!(assertions.len(), 2);
+        assert_eq!(assertions[0].to_string(), "D0 == $5");
+        assert_eq!(assertions[1].line, 3);
+        assert_eq!(assertions[1].to_string(), "($000006).L != $FFFF");
+
+        let error = first_error("ASSERT D9 == 1");
+        assert_eq!(error.kind, AsmErrorKind::IllegalOperand);
+        assert!(error.message.starts_with("ASSERT: "), "{}", error.message);
+        assert_eq!(first_error("ASSERT D0").kind, AsmErrorKind::InvalidOperand);
+        assert_eq!(
+            first_error("ASSERT (NOWHERE) == 1").kind,
+            AsmErrorKind::UndefinedSymbol
+        );
     }
 }
@@ -0,0 +1,474 @@
+// Batch-Modus: alle Programme eines Verzeichnisses gegen erwartete Ergebnisse prüfen
+//
+// Zu jedem `name.s68` (oder `name.asm`) gehört eine `name.expect` im TOML-Format
+// (Untermenge: Schlüssel = Wert, Abschnitte, Ganzzahlen, Strings):
+//
+//     max_steps = 10000
+//
+//     [registers]
+//     D0 = 42
+//     A0 = 0x2000
+//
+//     [memory]
+//     "$0800" = "00 00 01 00"
+//
+//     [hash]
+//     "CRC32($0800,$0400)" = 0xDEADBEEF   # Verfahren(Start, Länge), auch FNV1A64
+
+use crate::emulator::{Emulator, RunOutcome};
+use crate::memory::HashAlgorithm;
+use std::fmt::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Schrittlimit, wenn die .expect-Datei keins angibt
+pub const DEFAULT_MAX_STEPS: u64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    Data(usize),
+    Address(usize),
+    Pc,
+    Ccr,
+    Sr,
+}
+
+impl Register {
+    pub fn from_name(name: &str) -> Option<Register> {
+        let name = name.to_uppercase();
+        match name.as_str() {
+            "PC" => return Some(Register::Pc),
+            "CCR" => return Some(Register::Ccr),
+            "SR" => return Some(Register::Sr),
+            "SP" => return Some(Register::Address(7)),
+            _ => {}
+        }
+        let index = name.get(1..)?.parse::<usize>().ok().filter(|&i| i < 8)?;
+        match name.as_bytes()[0] {
+            b'D' => Some(Register::Data(index)),
+            b'A' => Some(Register::Address(index)),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Register::Data(i) => format!("D{}", i),
+            Register::Address(i) => format!("A{}", i),
+            Register::Pc => "PC".to_string(),
+            Register::Ccr => "CCR".to_string(),
+            Register::Sr => "SR".to_string(),
+        }
+    }
+
+    fn read(self, emulator: &Emulator) -> u32 {
+        let cpu = emulator.cpu();
+        match self {
+            Register::Data(i) => cpu.get_data_register(i),
+            Register::Address(i) => cpu.get_address_register(i),
+            Register::Pc => cpu.get_pc(),
+            Register::Ccr => cpu.get_ccr() as u32,
+            Register::Sr => cpu.get_sr() as u32,
+        }
+    }
+}
+
+/// Inhalt einer .expect-Datei
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expectation {
+    pub max_steps: u64,
+    pub registers: Vec<(Register, u32)>,
+    pub memory: Vec<(u32, Vec<u8>)>,
+    /// Prüfsummen großer Bereiche statt ihres ganzen Inhalts
+    pub hashes: Vec<(HashAlgorithm, Range<u32>, u64)>,
+}
+
+impl Default for Expectation {
+    fn default() -> Self {
+        Expectation {
+            max_steps: DEFAULT_MAX_STEPS,
+            registers: Vec::new(),
+            memory: Vec::new(),
+            hashes: Vec::new(),
+        }
+    }
+}
+
+impl Expectation {
+    pub fn parse(text: &str) -> Result<Expectation, String> {
+        let mut expectation = Expectation::default();
+        let mut section = String::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", index + 1, message);
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_lowercase();
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected key = value".to_string()))?;
+            let key = unquote(key.trim());
+            let value = value.trim();
+
+            match section.as_str() {
+                "" if key == "max_steps" => {
+                    expectation.max_steps = parse_integer(value).map_err(&error)? as u64;
+                }
+                "registers" => {
+                    let register = Register::from_name(key)
+                        .ok_or_else(|| error(format!("unknown register {}", key)))?;
+                    let value = parse_integer(value).map_err(&error)?;
+                    expectation.registers.push((register, value as u32));
+                }
+                "memory" => {
+                    let address = parse_integer(key).map_err(&error)? as u32;
+                    let bytes = unquote(value)
+                        .split_whitespace()
+                        .map(|byte| u8::from_str_radix(byte, 16))
+                        .collect::<Result<Vec<u8>, _>>()
+                        .map_err(|_| error(format!("invalid byte list {}", value)))?;
+                    expectation.memory.push((address, bytes));
+                }
+                "hash" => {
+                    let (algorithm, range) = parse_hash_key(key).ok_or_else(|| {
+                        error(format!("expected CRC32(start,length), got {}", key))
+                    })?;
+                    let value = parse_integer(value).map_err(&error)?;
+                    expectation.hashes.push((algorithm, range, value as u64));
+                }
+                _ => return Err(error(format!("unexpected key {}", key))),
+            }
+        }
+        Ok(expectation)
+    }
+}
+
+/// `CRC32($800,$400)`: Verfahren, Startadresse und Länge
+fn parse_hash_key(key: &str) -> Option<(HashAlgorithm, Range<u32>)> {
+    let (name, arguments) = key.strip_suffix(')')?.split_once('(')?;
+    let algorithm = HashAlgorithm::from_name(name.trim())?;
+    let (start, length) = arguments.split_once(',')?;
+    let start = u32::try_from(parse_integer(start).ok()?).ok()?;
+    let length = u32::try_from(parse_integer(length).ok()?).ok()?;
+    Some((algorithm, start..start.checked_add(length)?))
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+/// Ganzzahl wie in TOML (42, -1, 0x2A, 0b101) oder mit $ wie im Assembler
+pub(crate) fn parse_integer(text: &str) -> Result<i64, String> {
+    let text = unquote(text.trim()).replace('_', "");
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.as_str()),
+    };
+    let parsed = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix('$'))
+    {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2)
+    } else {
+        digits.parse()
+    };
+    let value = parsed.map_err(|_| format!("invalid number {}", text))?;
+    Ok(if negative { -value } else { value })
+}
+
+/// Ergebnis eines Programms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseReport {
+    pub name: String,
+    pub steps: u64,
+    /// Leer = bestanden; sonst eine Zeile pro Abweichung
+    pub failures: Vec<String>,
+}
+
+impl CaseReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Assembliert, führt aus und vergleicht mit der Erwartung
+pub fn run_case(name: &str, source: &str, expectation: &Expectation) -> CaseReport {
+    let mut report = CaseReport {
+        name: name.to_string(),
+        steps: 0,
+        failures: Vec::new(),
+    };
+
+    let mut emulator = Emulator::new();
+    if let Err(errors) = emulator.load_source(source) {
+        report.failures.push("assembly failed:".to_string());
+        report
+            .failures
+            .extend(errors.iter().map(|error| format!("  {}", error)));
+        return report;
+    }
+
+    // Emulator::run bricht bei SIMHALT, Exception, Leerlaufschleife oder Daten am PC ab
+    let outcome = emulator.run(expectation.max_steps);
+    report.steps = emulator.steps();
+    match outcome {
+        RunOutcome::Halted => {}
+        RunOutcome::Exception(exception) => report.failures.push(format!("{}", exception)),
+        RunOutcome::StepLimit(steps) => report
+            .failures
+            .push(format!("did not halt within {} steps", steps)),
+        RunOutcome::DataExecution(hit) => report.failures.push(hit.to_string()),
+        RunOutcome::IdleLoopDetected { pcs } => report.failures.push(format!(
+            "did not halt: idle loop at {}",
+            pcs.iter()
+                .map(|pc| format!("${:06X}", pc))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+
+    for (register, expected) in &expectation.registers {
+        let actual = register.read(&emulator);
+        if actual != *expected {
+            report.failures.push(format!(
+                "{}: expected ${:08X}, got ${:08X}",
+                register.name(),
+                expected,
+                actual
+            ));
+        }
+    }
+    for (address, expected) in &expectation.memory {
+        let actual: Vec<u8> = (0..expected.len() as u32)
+            .map(|offset| emulator.memory().read_byte(address + offset))
+            .collect();
+        if actual != *expected {
+            report.failures.push(format!(
+                "memory ${:06X}: expected {}, got {}",
+                address,
+                hex_bytes(expected),
+                hex_bytes(&actual)
+            ));
+        }
+    }
+    for (algorithm, range, expected) in &expectation.hashes {
+        let actual = emulator.memory().hash_range(range.clone(), *algorithm);
+        if actual != *expected {
+            report.failures.push(format!(
+                "{}(${:06X},${:X}): expected ${:X}, got ${:X}",
+                algorithm.name(),
+                range.start,
+                range.len(),
+                expected,
+                actual
+            ));
+        }
+    }
+    report
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Ergebnisse eines Verzeichnisses, nach Namen sortiert
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub cases: Vec<CaseReport>,
+}
+
+impl BatchReport {
+    pub fn passed(&self) -> usize {
+        self.cases.iter().filter(|case| case.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.cases.len() - self.passed()
+    }
+
+    /// Tabelle mit einer Zeile pro Programm, danach die Abweichungen der Fehlschläge
+    pub fn summary(&self) -> String {
+        let width = self
+            .cases
+            .iter()
+            .map(|case| case.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("program".len());
+
+        let mut text = String::new();
+        let _ = writeln!(text, "{:<width$}  result  steps", "program");
+        for case in &self.cases {
+            let result = if case.passed() { "PASS" } else { "FAIL" };
+            let _ = writeln!(text, "{:<width$}  {:<6}  {}", case.name, result, case.steps);
+        }
+        for case in self.cases.iter().filter(|case| !case.passed()) {
+            let _ = writeln!(text, "\n--- {} ---", case.name);
+            for failure in &case.failures {
+                let _ = writeln!(text, "{}", failure);
+            }
+        }
+        let _ = writeln!(
+            text,
+            "\n{} programs: {} passed, {} failed",
+            self.cases.len(),
+            self.passed(),
+            self.failed()
+        );
+        text
+    }
+}
+
+/// Programme (.s68/.asm) in `directory`, sortiert
+pub fn find_programs(directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut programs: Vec<PathBuf> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| {
+                ext.eq_ignore_ascii_case("s68") || ext.eq_ignore_ascii_case("asm")
+            })
+        })
+        .collect();
+    programs.sort();
+    Ok(programs)
+}
+
+/// Ein Programm samt Erwartung von der Platte laden und prüfen
+pub fn run_file(path: &Path) -> CaseReport {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let failed = |message: String| CaseReport {
+        name: name.clone(),
+        steps: 0,
+        failures: vec![message],
+    };
+
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => return failed(format!("{}: {}", path.display(), error)),
+    };
+    let expect_path = path.with_extension("expect");
+    let expectation = match std::fs::read_to_string(&expect_path) {
+        Ok(text) => match Expectation::parse(&text) {
+            Ok(expectation) => expectation,
+            Err(error) => return failed(format!("{}: {}", expect_path.display(), error)),
+        },
+        Err(error) => return failed(format!("{}: {}", expect_path.display(), error)),
+    };
+    run_case(&name, &source, &expectation)
+}
+
+/// Prüft alle Programme eines Verzeichnisses parallel (jeder Emulator ist unabhängig)
+pub fn run_directory(directory: &Path) -> std::io::Result<BatchReport> {
+    let programs = find_programs(directory)?;
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(programs.len().max(1));
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(programs.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = programs.get(index) else {
+                    break;
+                };
+                let report = run_file(path);
+                results.lock().unwrap().push((index, report));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    Ok(BatchReport {
+        cases: results.into_iter().map(|(_, report)| report).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expectation() {
+        let expectation = Expectation::parse(
+            "max_steps = 500 # Kommentar
+
+[registers]
+D0 = 42
+a7 = 0x0001_0000
+CCR = 0b00100
+
+[memory]
+\"$0800\" = \"00 00 01 00\"
+",
+        )
+        .unwrap();
+        assert_eq!(expectation.max_steps, 500);
+        assert_eq!(
+            expectation.registers,
+            vec![
+                (Register::Data(0), 42),
+                (Register::Address(7), 0x10000),
+                (Register::Ccr, 4)
+            ]
+        );
+        assert_eq!(expectation.memory, vec![(0x0800, vec![0, 0, 1, 0])]);
+
+        assert_eq!(
+            Expectation::parse("[hash]\nCRC32($800) = 1").unwrap_err(),
+            "line 2: expected CRC32(start,length), got CRC32($800)"
+        );
+        assert_eq!(
+            Expectation::parse("[registers]\nD8 = 1").unwrap_err(),
+            "line 2: unknown register D8"
+        );
+    }
+
+    #[test]
+    fn test_run_case_reports_differences() {
+        let expectation = Expectation::parse("[registers]\nD0 = 7\nD1 = 3").unwrap();
+        let report = run_case("sum", "MOVEQ #7, D0\nMOVEQ #2, D1\nSIMHALT", &expectation);
+        assert_eq!(report.steps, 3);
+        assert_eq!(
+            report.failures,
+            vec!["D1: expected $00000003, got $00000002"]
+        );
+
+        // "123456789" hat die CRC-32 $CBF43926
+        let expectation =
+            Expectation::parse("[hash]\n\"CRC32($800,9)\" = 0xCBF43926\nFNV1A64($800,$400) = 1")
+                .unwrap();
+        let report = run_case(
+            "hash",
+            "ORG $800\nDC.B $31,$32,$33,$34,$35,$36,$37,$38,$39\nORG $1000\nSIMHALT",
+            &expectation,
+        );
+        assert_eq!(report.failures.len(), 1, "{:?}", report.failures);
+        assert!(report.failures[0].starts_with("FNV1A64($000800,$400): expected $1, got $"));
+
+        let report = run_case("broken", "MOVEQ #1", &expectation);
+        assert_eq!(report.failures[0], "assembly failed:");
+        assert!(report.failures[1].contains("line 1: MOVEQ"));
+    }
+}
@@ -0,0 +1,605 @@
+// Breakpoints, Memory- und Register-Watches
+// Breakpoints und Memory-Watches werden über eine Quellposition definiert und gegen das
+// geladene Programm aufgelöst, damit sie ein Neu-Assemblieren überleben.
+
+use crate::cpu::Registers;
+use crate::isa::Size;
+use crate::memory::Memory;
+use crate::program::Program;
+use std::fmt;
+
+/// Wo ein Breakpoint oder Watch definiert wurde
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Location {
+    Line(usize), // Quellzeile (1-basiert)
+    Symbol(String),
+    Address(u32),
+}
+
+impl Location {
+    pub fn resolve(&self, program: &Program) -> Option<u32> {
+        match self {
+            Location::Line(line) => program.address_for_line(*line),
+            Location::Symbol(name) => program.symbols.get(name).copied(),
+            Location::Address(address) => Some(*address),
+        }
+    }
+}
+
+/// Vergleich einer Breakpoint-Bedingung
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// Zerlegt "D0 == 5" in linke Seite, Vergleich und rechte Seite (beide ungetrimmt)
+    pub fn split(text: &str) -> Option<(&str, Comparison, &str)> {
+        const OPERATORS: [(&str, Comparison); 6] = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+        OPERATORS.iter().find_map(|(op, comparison)| {
+            let position = text.find(op)?;
+            Some((&text[..position], *comparison, &text[position + op.len()..]))
+        })
+    }
+
+    pub fn holds(self, actual: u32, value: u32) -> bool {
+        match self {
+            Comparison::Equal => actual == value,
+            Comparison::NotEqual => actual != value,
+            Comparison::Less => actual < value,
+            Comparison::LessOrEqual => actual <= value,
+            Comparison::Greater => actual > value,
+            Comparison::GreaterOrEqual => actual >= value,
+        }
+    }
+
+    pub fn operator(self) -> &'static str {
+        match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+/// Bedingung wie `D0 == 5` oder `A1 >= $2000`; verglichen wird vorzeichenlos
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    pub register: String, // D0-D7, A0-A7, PC oder CCR
+    pub comparison: Comparison,
+    pub value: u32,
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Condition, String> {
+        let (register, comparison, operand) = Comparison::split(text)
+            .ok_or_else(|| format!("expected a comparison in '{}'", text.trim()))?;
+
+        let register = parse_register(register)?;
+        let operand = operand.trim();
+        let value = parse_value(operand).ok_or_else(|| format!("invalid value '{}'", operand))?;
+        Ok(Condition {
+            register,
+            comparison,
+            value,
+        })
+    }
+
+    pub fn matches(&self, registers: &Registers) -> bool {
+        let actual = register_value(registers, &self.register);
+        self.comparison.holds(actual, self.value)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ${:X}",
+            self.register,
+            self.comparison.operator(),
+            self.value
+        )
+    }
+}
+
+/// Registername für Bedingungen, Watches und ASSERT (D0-D7, A0-A7, PC, CCR),
+/// großgeschrieben
+pub fn parse_register(text: &str) -> Result<String, String> {
+    let register = text.trim().to_uppercase();
+    let valid = register == "PC"
+        || register == "CCR"
+        || matches!(register.as_bytes(), [b'D' | b'A', b'0'..=b'7']);
+    if valid {
+        Ok(register)
+    } else {
+        Err(format!("unknown register '{}'", register))
+    }
+}
+
+/// Wert eines mit `parse_register` geprüften Registers
+pub fn register_value(registers: &Registers, register: &str) -> u32 {
+    let index = |r: &str| r.as_bytes()[1] as usize - b'0' as usize;
+    match register {
+        "PC" => registers.pc,
+        "CCR" => registers.ccr as u32,
+        _ if register.starts_with('D') => registers.d[index(register)],
+        _ => registers.a[index(register)],
+    }
+}
+
+/// $hex, 0xhex oder dezimal (auch negativ, als Zweierkomplement)
+fn parse_value(text: &str) -> Option<u32> {
+    if let Some(hex) = text.strip_prefix('$').or_else(|| text.strip_prefix("0x")) {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(negative) = text.strip_prefix('-') {
+        negative.parse::<u32>().ok().map(|v| v.wrapping_neg())
+    } else {
+        text.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub location: Location,
+    pub address: Option<u32>, // None = im aktuellen Programm nicht auflösbar
+    pub enabled: bool,
+    pub hits: u64,
+    pub condition: Option<Condition>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BreakpointStore {
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl BreakpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fügt einen Breakpoint hinzu; doppelte Locations werden ignoriert
+    pub fn add(&mut self, location: Location, program: Option<&Program>) -> bool {
+        if self.breakpoints.iter().any(|bp| bp.location == location) {
+            return false;
+        }
+        let address = program.and_then(|p| location.resolve(p));
+        self.breakpoints.push(Breakpoint {
+            location,
+            address,
+            enabled: true,
+            hits: 0,
+            condition: None,
+        });
+        true
+    }
+
+    /// Setzt oder entfernt den Breakpoint; true, wenn er danach gesetzt ist
+    pub fn toggle(&mut self, location: Location, program: Option<&Program>) -> bool {
+        !self.remove(&location) && self.add(location, program)
+    }
+
+    pub fn remove(&mut self, location: &Location) -> bool {
+        let before = self.breakpoints.len();
+        self.breakpoints.retain(|bp| &bp.location != location);
+        self.breakpoints.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn get_mut(&mut self, location: &Location) -> Option<&mut Breakpoint> {
+        self.breakpoints
+            .iter_mut()
+            .find(|bp| &bp.location == location)
+    }
+
+    pub fn set_enabled(&mut self, location: &Location, enabled: bool) -> bool {
+        self.get_mut(location)
+            .map(|bp| bp.enabled = enabled)
+            .is_some()
+    }
+
+    pub fn set_all_enabled(&mut self, enabled: bool) {
+        for bp in &mut self.breakpoints {
+            bp.enabled = enabled;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.breakpoints.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Breakpoint> {
+        self.breakpoints.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.breakpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty()
+    }
+
+    /// Löst alle Breakpoints gegen ein (neues) Programm auf
+    pub fn resolve(&mut self, program: &Program) {
+        for bp in &mut self.breakpoints {
+            bp.address = bp.location.resolve(program);
+        }
+    }
+
+    /// Aktiver Breakpoint an `address`, unabhängig von seiner Bedingung
+    pub fn is_breakpoint(&self, address: u32) -> bool {
+        self.breakpoints
+            .iter()
+            .any(|bp| bp.enabled && bp.address == Some(address))
+    }
+
+    /// Prüft die aktiven Breakpoints an `address` samt Bedingung und zählt die Treffer;
+    /// true, wenn mindestens einer auslöst
+    pub fn hit(&mut self, address: u32, registers: &Registers) -> bool {
+        let mut triggered = false;
+        for bp in &mut self.breakpoints {
+            let applies = bp.enabled
+                && bp.address == Some(address)
+                && bp.condition.as_ref().is_none_or(|c| c.matches(registers));
+            if applies {
+                bp.hits += 1;
+                triggered = true;
+            }
+        }
+        triggered
+    }
+}
+
+/// Beobachtet einen Speicherwert
+#[derive(Debug, Clone)]
+pub struct Watch {
+    pub location: Location,
+    pub size: Size,
+    pub address: Option<u32>,
+}
+
+impl Watch {
+    pub fn new(location: Location, size: Size) -> Self {
+        Watch {
+            location,
+            size,
+            address: None,
+        }
+    }
+
+    pub fn resolve(&mut self, program: &Program) {
+        self.address = self.location.resolve(program);
+    }
+
+    pub fn read(&self, memory: &Memory) -> Option<u32> {
+        let address = self.address?;
+        Some(match self.size {
+            Size::Byte => memory.read_byte(address) as u32,
+            Size::Word => memory.read_word(address) as u32,
+            Size::Long => memory.read_long(address),
+        })
+    }
+}
+
+/// Was eine ASSERT-Direktive prüft
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssertTarget {
+    Register(String), // wie bei Bedingungen
+    Memory { address: u32, size: Size },
+}
+
+/// Prüfung aus einer ASSERT-Direktive. Sie gilt für den Zustand, bevor die CPU die
+/// Instruktion an `address` ausführt (also nach der Instruktion davor).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assertion {
+    pub address: u32,
+    pub line: usize,
+    pub target: AssertTarget,
+    pub comparison: Comparison,
+    pub value: u32,
+}
+
+impl Assertion {
+    pub fn actual(&self, registers: &Registers, memory: &Memory) -> u32 {
+        match &self.target {
+            AssertTarget::Register(register) => register_value(registers, register),
+            AssertTarget::Memory { address, size } => match size {
+                Size::Byte => memory.read_byte(*address) as u32,
+                Size::Word => memory.read_word(*address) as u32,
+                Size::Long => memory.read_long(*address),
+            },
+        }
+    }
+
+    /// None, wenn die Prüfung gilt
+    pub fn check(&self, registers: &Registers, memory: &Memory) -> Option<AssertionFailure> {
+        let actual = self.actual(registers, memory);
+        (!self.comparison.holds(actual, self.value)).then(|| AssertionFailure {
+            assertion: self.clone(),
+            actual,
+        })
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.target {
+            AssertTarget::Register(register) => write!(f, "{}", register)?,
+            AssertTarget::Memory { address, size } => {
+                write!(f, "(${:06X}).{}", address, size.suffix())?
+            }
+        }
+        write!(f, " {} ${:X}", self.comparison.operator(), self.value)
+    }
+}
+
+/// Eine ASSERT-Direktive, die beim Erreichen nicht galt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssertionFailure {
+    pub assertion: Assertion,
+    pub actual: u32,
+}
+
+impl fmt::Display for AssertionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: ASSERT {} failed, actual ${:X}",
+            self.assertion.line, self.assertion, self.actual
+        )
+    }
+}
+
+/// Beobachtet ein Register nach jeder Instruktion: `D3` meldet jede Änderung,
+/// `A7 < $7F00` eine Änderung, nach der die Bedingung gilt. Ausgewertet wird nur, wenn sich
+/// das Register geändert hat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterWatch {
+    pub register: String,
+    pub condition: Option<Condition>,
+    /// Lauf anhalten; sonst nur melden
+    pub pause: bool,
+    pub enabled: bool,
+    pub hits: u64,
+}
+
+/// Ein ausgelöster Register-Watch mit altem und neuem Wert
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterWatchHit {
+    pub watch: String,
+    pub old: u32,
+    pub new: u32,
+    pub pause: bool,
+}
+
+impl fmt::Display for RegisterWatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "watch {}: ${:08X} → ${:08X}",
+            self.watch, self.old, self.new
+        )
+    }
+}
+
+impl RegisterWatch {
+    /// "D3" (jede Änderung) oder eine Bedingung wie bei Breakpoints ("A7 < $7F00")
+    pub fn parse(text: &str, pause: bool) -> Result<RegisterWatch, String> {
+        let condition = text
+            .contains(['=', '<', '>', '!'])
+            .then(|| Condition::parse(text))
+            .transpose()?;
+        let register = match &condition {
+            Some(condition) => condition.register.clone(),
+            None => parse_register(text)?,
+        };
+        Ok(RegisterWatch {
+            register,
+            condition,
+            pause,
+            enabled: true,
+            hits: 0,
+        })
+    }
+
+    /// Prüft einen Schritt von `before` nach `after`; zählt und meldet einen Treffer
+    pub fn check(&mut self, before: &Registers, after: &Registers) -> Option<RegisterWatchHit> {
+        let old = register_value(before, &self.register);
+        let new = register_value(after, &self.register);
+        if !self.enabled || old == new {
+            return None;
+        }
+        if self.condition.as_ref().is_some_and(|c| !c.matches(after)) {
+            return None;
+        }
+        self.hits += 1;
+        Some(RegisterWatchHit {
+            watch: self.to_string(),
+            old,
+            new,
+            pause: self.pause,
+        })
+    }
+}
+
+impl fmt::Display for RegisterWatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.condition {
+            Some(condition) => condition.fmt(f),
+            None => f.write_str(&self.register),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn program() -> Program {
+        Program {
+            symbols: HashMap::from([("LOOP".to_string(), 0x1004)]),
+            source_map: BTreeMap::from([(0x1000, 1), (0x1004, 2)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_locations() {
+        let mut store = BreakpointStore::new();
+        assert!(store.add(Location::Line(2), None));
+        assert!(store.add(Location::Symbol("LOOP".into()), None));
+        assert!(store.add(Location::Symbol("MISSING".into()), None));
+        assert!(
+            !store.add(Location::Line(2), None),
+            "duplicates are ignored"
+        );
+
+        store.resolve(&program());
+        let addresses: Vec<_> = store.iter().map(|bp| bp.address).collect();
+        assert_eq!(addresses, vec![Some(0x1004), Some(0x1004), None]);
+        assert!(store.is_breakpoint(0x1004));
+        assert!(!store.is_breakpoint(0x1000));
+
+        assert!(store.remove(&Location::Line(2)));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_toggle_and_disable() {
+        let mut store = BreakpointStore::new();
+        assert!(store.toggle(Location::Line(2), Some(&program())));
+        assert!(store.is_breakpoint(0x1004));
+        assert!(!store.toggle(Location::Line(2), Some(&program())));
+        assert!(store.is_empty());
+
+        store.toggle(Location::Line(1), Some(&program()));
+        store.toggle(Location::Line(2), Some(&program()));
+        assert!(store.set_enabled(&Location::Line(1), false));
+        assert!(!store.is_breakpoint(0x1000));
+        assert!(store.is_breakpoint(0x1004));
+
+        store.set_all_enabled(false);
+        assert!(!store.is_breakpoint(0x1004));
+        assert!(!store.hit(0x1004, &Registers::default()));
+        store.set_all_enabled(true);
+        assert!(store.is_breakpoint(0x1000));
+        assert!(!store.set_enabled(&Location::Line(9), false));
+    }
+
+    #[test]
+    fn test_hits_are_counted_when_condition_holds() {
+        let mut store = BreakpointStore::new();
+        store.add(Location::Symbol("LOOP".into()), Some(&program()));
+        store
+            .get_mut(&Location::Symbol("LOOP".into()))
+            .unwrap()
+            .condition = Some(Condition::parse("d1 >= $10").unwrap());
+
+        let mut registers = Registers::default();
+        assert!(!store.hit(0x1004, &registers));
+        registers.d[1] = 0x10;
+        assert!(store.hit(0x1004, &registers));
+        assert!(store.hit(0x1004, &registers));
+        assert!(!store.hit(0x1000, &registers));
+        assert_eq!(store.iter().next().unwrap().hits, 2);
+    }
+
+    #[test]
+    fn test_condition_parsing() {
+        let condition = Condition::parse("A0 != -1").unwrap();
+        assert_eq!(condition.register, "A0");
+        assert_eq!(condition.comparison, Comparison::NotEqual);
+        assert_eq!(condition.value, 0xFFFF_FFFF);
+        assert_eq!(condition.to_string(), "A0 != $FFFFFFFF");
+        assert_eq!(
+            Condition::parse("PC<0x1000").unwrap().comparison,
+            Comparison::Less
+        );
+        assert_eq!(
+            Condition::parse("D8 == 1").unwrap_err(),
+            "unknown register 'D8'"
+        );
+        assert_eq!(
+            Condition::parse("D0 = 1").unwrap_err(),
+            "expected a comparison in 'D0 = 1'"
+        );
+        assert_eq!(
+            Condition::parse("D0 == x").unwrap_err(),
+            "invalid value 'x'"
+        );
+    }
+
+    #[test]
+    fn test_register_watch_fires_on_change() {
+        let mut watch = RegisterWatch::parse("d3", true).unwrap();
+        assert_eq!(watch.to_string(), "D3");
+        let before = Registers::default();
+        let mut after = before;
+        after.d[3] = 7;
+        let hit = watch.check(&before, &after).unwrap();
+        assert_eq!((hit.old, hit.new), (0, 7));
+        assert_eq!(hit.to_string(), "watch D3: $00000000 → $00000007");
+        // Gleicher Wert: keine Änderung
+        assert!(watch.check(&after, &after).is_none());
+        assert_eq!(watch.hits, 1);
+
+        watch.enabled = false;
+        assert!(watch.check(&before, &after).is_none());
+    }
+
+    #[test]
+    fn test_register_watch_comparison() {
+        let mut watch = RegisterWatch::parse("A7 < $7F00", false).unwrap();
+        let mut before = Registers::default();
+        before.a[7] = 0x8000;
+        let mut after = before;
+        after.a[7] = 0x7F80;
+        assert!(watch.check(&before, &after).is_none(), "still above");
+        before = after;
+        after.a[7] = 0x7EFC;
+        let hit = watch.check(&before, &after).unwrap();
+        assert_eq!(hit.watch, "A7 < $7F00");
+        assert!(!hit.pause);
+    }
+
+    #[test]
+    fn test_register_watch_ignores_unrelated_registers() {
+        let mut watch = RegisterWatch::parse("D3", true).unwrap();
+        let mut conditional = RegisterWatch::parse("D3 == 0", true).unwrap();
+        let before = Registers::default();
+        let mut after = before;
+        after.d[2] = 1;
+        after.a[3] = 1;
+        after.pc = 0x1002;
+        after.ccr = 0x04;
+        assert!(watch.check(&before, &after).is_none());
+        // Die Bedingung gilt, aber D3 hat sich nicht geändert
+        assert!(conditional.check(&before, &after).is_none());
+        assert_eq!(watch.hits + conditional.hits, 0);
+
+        assert_eq!(
+            RegisterWatch::parse("D9", true).unwrap_err(),
+            "unknown register 'D9'"
+        );
+    }
+}
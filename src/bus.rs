@@ -0,0 +1,219 @@
+// Geräte am Systembus
+// Geräte laufen im Takt der CPU: nach jeder Instruktion bekommt jedes Gerät die dabei
+// verbrauchten Taktzyklen und kann einen Interrupt anfordern. Da nur gezählte Zyklen
+// eingehen (keine Wanduhr), liefert derselbe Ablauf immer dasselbe Interrupt-Timing.
+// Geräteregister liegen im normalen Speicher; Zugriffe der CPU darauf werden nach der
+// Instruktion an das Gerät gemeldet, das den Bereich belegt.
+
+use crate::cpu::MemAccess;
+use crate::memory::Memory;
+use std::ops::Range;
+
+/// Interrupt-Anforderung eines Geräts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IrqRequest {
+    /// Priorität 1-7; 7 ist nicht maskierbar
+    pub level: u8,
+    /// Vektornummer; None = Autovektor (24 + level)
+    pub vector: Option<u8>,
+}
+
+impl IrqRequest {
+    pub fn autovector(level: u8) -> Self {
+        IrqRequest {
+            level: level.clamp(1, 7),
+            vector: None,
+        }
+    }
+
+    /// Vektornummer, deren Adresse (Nummer * 4) den Handler enthält
+    pub fn vector_number(&self) -> u8 {
+        self.vector.unwrap_or(24 + self.level)
+    }
+}
+
+/// Ein Gerät, das mit der CPU mitläuft (Send, damit der Controller in einem Thread laufen kann)
+pub trait Device: Send {
+    fn name(&self) -> &str;
+
+    /// Adressbereich der Register; None = Gerät ohne Register
+    fn registers(&self) -> Option<Range<u32>> {
+        None
+    }
+
+    /// Die CPU hat auf ein Register zugegriffen (ein geschriebener Wert steht schon im Speicher)
+    fn access(&mut self, _access: &MemAccess, _memory: &mut Memory) {}
+
+    /// `cycles` Taktzyklen sind vergangen; optional einen Interrupt anfordern
+    fn tick(&mut self, cycles: u32, memory: &mut Memory) -> Option<IrqRequest>;
+
+    /// Eine Übertragung läuft (z.B. ein Byte auf der Leitung); Warten darauf ist kein
+    /// Leerlauf
+    fn busy(&self) -> bool {
+        false
+    }
+
+    /// CPU-Takt in Hz (beim Anschließen und bei jeder Änderung); Geräte mit Zeitverhalten
+    /// rechnen damit ihre Zyklen aus
+    fn set_clock_hz(&mut self, _hz: u32) {}
+
+    /// Zustand wie nach dem Einschalten (bei CPU-Reset und beim Anschließen); Register
+    /// im Speicher dürfen dabei neu belegt werden
+    fn reset(&mut self, _memory: &mut Memory) {}
+}
+
+/// Sammelt die Geräte und die noch nicht angenommene Interrupt-Anforderung
+#[derive(Default)]
+pub struct SystemBus {
+    devices: Vec<Box<dyn Device>>,
+    pending: Option<IrqRequest>,
+}
+
+impl SystemBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn attach(&mut self, device: Box<dyn Device>) {
+        self.devices.push(device);
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &dyn Device> {
+        self.devices.iter().map(|device| device.as_ref())
+    }
+
+    /// Meldet einen Registerzugriff dem Gerät, in dessen Bereich er liegt
+    pub fn access(&mut self, access: &MemAccess, memory: &mut Memory) {
+        let owner = self.devices.iter_mut().find(|device| {
+            device
+                .registers()
+                .is_some_and(|range| range.contains(&access.addr))
+        });
+        if let Some(device) = owner {
+            device.access(access, memory);
+        }
+    }
+
+    /// Reicht die Zyklen an alle Geräte weiter (in Anmeldereihenfolge) und liefert die
+    /// höchste dabei angeforderte Priorität. Sie bleibt anhängig, bis die CPU sie annimmt.
+    pub fn tick(&mut self, cycles: u32, memory: &mut Memory) -> Option<IrqRequest> {
+        let mut raised: Option<IrqRequest> = None;
+        for device in &mut self.devices {
+            if let Some(request) = device.tick(cycles, memory) {
+                if raised.is_none_or(|r| request.level > r.level) {
+                    raised = Some(request);
+                }
+            }
+        }
+        if let Some(request) = raised {
+            if self.pending.is_none_or(|p| request.level > p.level) {
+                self.pending = Some(request);
+            }
+        }
+        raised
+    }
+
+    /// Mindestens ein Gerät ist gerade beschäftigt (`Device::busy`)
+    pub fn busy(&self) -> bool {
+        self.devices.iter().any(|device| device.busy())
+    }
+
+    /// Neuen CPU-Takt an alle Geräte weitergeben
+    pub fn set_clock_hz(&mut self, hz: u32) {
+        for device in &mut self.devices {
+            device.set_clock_hz(hz);
+        }
+    }
+
+    pub fn pending(&self) -> Option<IrqRequest> {
+        self.pending
+    }
+
+    /// Die CPU hat den anhängigen Interrupt angenommen
+    pub fn acknowledge(&mut self) {
+        self.pending = None;
+    }
+
+    pub fn reset(&mut self, memory: &mut Memory) {
+        self.pending = None;
+        for device in &mut self.devices {
+            device.reset(memory);
+        }
+    }
+}
+
+/// Periodischer Timer: fordert alle `period` Taktzyklen einen Interrupt an
+#[derive(Debug, Clone)]
+pub struct Timer {
+    period: u32,
+    level: u8,
+    elapsed: u32,
+}
+
+impl Timer {
+    pub fn new(period: u32, level: u8) -> Self {
+        Timer {
+            period: period.max(1),
+            level,
+            elapsed: 0,
+        }
+    }
+
+    pub fn period(&self) -> u32 {
+        self.period
+    }
+}
+
+impl Device for Timer {
+    fn name(&self) -> &str {
+        "timer"
+    }
+
+    fn tick(&mut self, cycles: u32, _memory: &mut Memory) -> Option<IrqRequest> {
+        self.elapsed += cycles;
+        if self.elapsed < self.period {
+            return None;
+        }
+        // Überzählige Zyklen in die nächste Periode übernehmen, damit sie nicht driftet
+        self.elapsed %= self.period;
+        Some(IrqRequest::autovector(self.level))
+    }
+
+    fn reset(&mut self, _memory: &mut Memory) {
+        self.elapsed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_keeps_period_without_drift() {
+        let mut memory = Memory::new();
+        let mut timer = Timer::new(1000, 2);
+        let fired: Vec<u32> = (1..=300)
+            .filter(|_| timer.tick(14, &mut memory).is_some())
+            .collect();
+        // 14 * 72 = 1008, 14 * 143 = 2002, 14 * 215 = 3010, 14 * 286 = 4004
+        assert_eq!(fired, vec![72, 143, 215, 286]);
+    }
+
+    #[test]
+    fn test_bus_keeps_highest_pending_level() {
+        let mut memory = Memory::new();
+        let mut bus = SystemBus::new();
+        bus.attach(Box::new(Timer::new(10, 1)));
+        bus.attach(Box::new(Timer::new(20, 5)));
+
+        assert_eq!(bus.tick(10, &mut memory), Some(IrqRequest::autovector(1)));
+        assert_eq!(bus.tick(10, &mut memory), Some(IrqRequest::autovector(5)));
+        assert_eq!(bus.pending().map(|p| p.level), Some(5));
+        assert_eq!(bus.pending().unwrap().vector_number(), 29);
+
+        bus.acknowledge();
+        assert_eq!(bus.pending(), None);
+        bus.reset(&mut memory);
+        assert_eq!(bus.tick(19, &mut memory), Some(IrqRequest::autovector(1)));
+    }
+}
@@ -0,0 +1,286 @@
+// Vorschläge für die Eingabehilfe im Editor
+// Rein funktional: aus dem Zeilenanfang bis zum Cursor wird bestimmt, ob gerade ein
+// Mnemonic oder ein Operand getippt wird; passende Kandidaten kommen aus der
+// Instruktionstabelle, den Registernamen, den Direktiven und den bekannten Symbolen.
+
+use crate::isa::{self, OperandClass};
+
+/// Direktiven des Assemblers (mit Größenvarianten, wo es welche gibt)
+pub const DIRECTIVES: &[&str] = &[
+    "ORG", "EQU", "END", "SECTION", "STACK", "INITSP", "EVEN", "DC.B", "DC.W", "DC.L", "DS.B",
+    "DS.W", "DS.L", "XDEF", "XREF", "ASSERT",
+];
+
+/// Was an der Cursorposition steht
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Position {
+    /// Erstes Wort der Anweisung (nach einem eventuellen Label)
+    Mnemonic,
+    /// Operand Nummer `index` (0-basiert) von `mnemonic` (großgeschrieben, ohne Suffix)
+    Operand { mnemonic: String, index: usize },
+}
+
+/// Position im Quelltext und die bekannten Symbole (Labels und EQUs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionContext<'a> {
+    pub position: Position,
+    pub symbols: &'a [String],
+}
+
+impl<'a> CompletionContext<'a> {
+    /// Bestimmt Position und bisher getippten Präfix aus dem Zeilentext vor dem Cursor
+    pub fn at<'l>(before_cursor: &'l str, symbols: &'a [String]) -> (Self, &'l str) {
+        let code = before_cursor.split(';').next().unwrap_or("");
+        // "LOOP: MOVE ..." - das Label gehört nicht zur Anweisung
+        let code = match code.split_once(':') {
+            Some((label, rest)) if !label.trim().contains(char::is_whitespace) => rest,
+            _ => code,
+        };
+        let code = code.trim_start();
+
+        let position_and_prefix = match code.split_once(char::is_whitespace) {
+            None => (Position::Mnemonic, code),
+            Some((head, operands)) => {
+                let mnemonic = head.split('.').next().unwrap_or("").to_uppercase();
+                let index = operands.matches(',').count();
+                let prefix = operands.rsplit(',').next().unwrap_or("").trim_start();
+                (Position::Operand { mnemonic, index }, prefix)
+            }
+        };
+        let (position, prefix) = position_and_prefix;
+        (CompletionContext { position, symbols }, prefix)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionKind {
+    Mnemonic,
+    Directive,
+    Register,
+    Symbol,
+}
+
+/// Ein Kandidat; `text` wird anstelle des Präfixes eingesetzt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub text: String,
+    pub kind: SuggestionKind,
+}
+
+/// Kandidaten für `prefix` an der Position aus `context`, beste zuerst.
+///
+/// Groß-/Kleinschreibung zählt nicht. Zuerst kommen Kandidaten, die mit dem Präfix beginnen,
+/// dann solche, die ihn enthalten; innerhalb davon kürzere vor längeren.
+///
+/// ```
+/// use mc68000::completion::{suggest, CompletionContext};
+///
+/// let symbols = vec!["LOOP".to_string(), "LIMIT".to_string()];
+/// let (context, prefix) = CompletionContext::at("        BNE LO", &symbols);
+/// let first = &suggest(prefix, context)[0];
+/// assert_eq!(first.text, "LOOP");
+/// ```
+pub fn suggest(prefix: &str, context: CompletionContext) -> Vec<Suggestion> {
+    let candidates = match &context.position {
+        Position::Mnemonic => mnemonic_candidates(),
+        Position::Operand { mnemonic, index } => operand_candidates(mnemonic, *index, &context),
+    };
+    rank(prefix, candidates)
+}
+
+fn mnemonic_candidates() -> Vec<Suggestion> {
+    let mut candidates = Vec::new();
+    for spec in isa::INSTRUCTIONS {
+        candidates.push(Suggestion {
+            text: spec.mnemonic.to_string(),
+            kind: SuggestionKind::Mnemonic,
+        });
+        candidates.extend(spec.sizes.iter().map(|size| Suggestion {
+            text: format!("{}.{}", spec.mnemonic, size.suffix()),
+            kind: SuggestionKind::Mnemonic,
+        }));
+    }
+    candidates.extend(DIRECTIVES.iter().map(|directive| Suggestion {
+        text: directive.to_string(),
+        kind: SuggestionKind::Directive,
+    }));
+    candidates
+}
+
+/// Register nach den erlaubten Adressierungsarten, Symbole für Adressen und Immediates.
+/// Unbekannte Instruktionen und Direktiven bekommen alles angeboten.
+fn operand_candidates(
+    mnemonic: &str,
+    index: usize,
+    context: &CompletionContext,
+) -> Vec<Suggestion> {
+    const ALL: &[OperandClass] = &[
+        OperandClass::DataRegister,
+        OperandClass::AddressRegister,
+        OperandClass::Indirect,
+        OperandClass::PostIncrement,
+        OperandClass::PreDecrement,
+        OperandClass::Absolute,
+        OperandClass::Immediate,
+    ];
+    let classes = match isa::lookup(mnemonic) {
+        Some(spec) => spec.operands.get(index).copied().unwrap_or(&[]),
+        None => ALL,
+    };
+
+    let mut candidates = Vec::new();
+    let registers = |format: fn(u8) -> String| {
+        (0..8).map(move |n| Suggestion {
+            text: format(n),
+            kind: SuggestionKind::Register,
+        })
+    };
+    for class in classes {
+        match class {
+            OperandClass::DataRegister => candidates.extend(registers(|n| format!("D{}", n))),
+            OperandClass::AddressRegister => candidates.extend(registers(|n| format!("A{}", n))),
+            OperandClass::Indirect => candidates.extend(registers(|n| format!("(A{})", n))),
+            OperandClass::PostIncrement => candidates.extend(registers(|n| format!("(A{})+", n))),
+            OperandClass::PreDecrement => candidates.extend(registers(|n| format!("-(A{})", n))),
+            OperandClass::Displacement => {}
+            OperandClass::ConditionCodes => candidates.push(Suggestion {
+                text: "CCR".to_string(),
+                kind: SuggestionKind::Register,
+            }),
+            OperandClass::Absolute => {
+                candidates.extend(context.symbols.iter().map(|symbol| Suggestion {
+                    text: symbol.clone(),
+                    kind: SuggestionKind::Symbol,
+                }))
+            }
+            OperandClass::Immediate => {
+                candidates.extend(context.symbols.iter().map(|symbol| Suggestion {
+                    text: format!("#{}", symbol),
+                    kind: SuggestionKind::Symbol,
+                }))
+            }
+        }
+    }
+    candidates
+}
+
+fn rank(prefix: &str, candidates: Vec<Suggestion>) -> Vec<Suggestion> {
+    let prefix = prefix.to_uppercase();
+    let mut ranked: Vec<(u8, Suggestion)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let text = candidate.text.to_uppercase();
+            let score = if text.starts_with(&prefix) {
+                0
+            } else if text.contains(&prefix) {
+                1
+            } else {
+                return None;
+            };
+            Some((score, candidate))
+        })
+        .collect();
+    // Stabil: bei gleicher Länge bleibt die Reihenfolge der Tabelle
+    ranked.sort_by_key(|(score, candidate)| (*score, candidate.text.len()));
+    ranked.dedup_by(|a, b| a.1.text == b.1.text);
+    ranked.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+/// Labels und EQU-Namen aus dem Quelltext, auch wenn er (noch) nicht assembliert
+pub fn symbols_in_source(source: &str) -> Vec<String> {
+    let mut symbols: Vec<String> = source
+        .lines()
+        .filter_map(|line| {
+            let code = line.split(';').next().unwrap_or("");
+            let (label, _) = code.split_once(':')?;
+            let label = label.trim();
+            let valid = !label.is_empty()
+                && label.chars().all(|c| c.is_alphanumeric() || c == '_')
+                && !label.starts_with(|c: char| c.is_ascii_digit());
+            valid.then(|| label.to_string())
+        })
+        .collect();
+    symbols.sort();
+    symbols.dedup();
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(before_cursor: &str, symbols: &[String]) -> Vec<String> {
+        let (context, prefix) = CompletionContext::at(before_cursor, symbols);
+        suggest(prefix, context)
+            .into_iter()
+            .map(|suggestion| suggestion.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_position_from_line() {
+        let (context, prefix) = CompletionContext::at("LOOP: MO", &[]);
+        assert_eq!(context.position, Position::Mnemonic);
+        assert_eq!(prefix, "MO");
+
+        let (context, prefix) = CompletionContext::at("\tmove.l D0, (A", &[]);
+        assert_eq!(
+            context.position,
+            Position::Operand {
+                mnemonic: "MOVE".to_string(),
+                index: 1
+            }
+        );
+        assert_eq!(prefix, "(A");
+
+        let (context, prefix) = CompletionContext::at("   ", &[]);
+        assert_eq!(context.position, Position::Mnemonic);
+        assert_eq!(prefix, "");
+    }
+
+    #[test]
+    fn test_mnemonic_position_offers_mnemonics_and_directives() {
+        let suggestions = texts("  mov", &[]);
+        assert_eq!(suggestions[..3], ["MOVE", "MOVEQ", "MOVEA"]);
+        assert!(suggestions.contains(&"MOVE.L".to_string()));
+        assert!(!suggestions.contains(&"D0".to_string()));
+
+        let suggestions = texts("  dc", &[]);
+        assert_eq!(suggestions, ["DC.B", "DC.W", "DC.L"]);
+        // Enthält den Präfix, beginnt aber nicht damit: nach hinten
+        assert_eq!(texts("  EQ", &[])[..2], ["EQU", "BEQ"]);
+    }
+
+    #[test]
+    fn test_branch_operand_offers_labels() {
+        let symbols = vec![
+            "LOOP".to_string(),
+            "END_LOOP".to_string(),
+            "DONE".to_string(),
+        ];
+        assert_eq!(texts("  BNE ", &symbols), ["LOOP", "DONE", "END_LOOP"]);
+        assert_eq!(texts("  bne lo", &symbols), ["LOOP", "END_LOOP"]);
+        // DBRA: erst ein Datenregister, dann das Sprungziel
+        assert_eq!(texts("  DBRA D", &symbols)[0], "D0");
+        assert_eq!(texts("  DBRA D1, ", &symbols), ["LOOP", "DONE", "END_LOOP"]);
+    }
+
+    #[test]
+    fn test_operand_position_follows_instruction_table() {
+        let symbols = vec!["COUNT".to_string()];
+        let suggestions = texts("  MOVEQ ", &symbols);
+        assert_eq!(suggestions, ["#COUNT"]);
+        let suggestions = texts("  MOVEQ #1, ", &symbols);
+        assert_eq!(suggestions.len(), 8);
+        assert!(suggestions.iter().all(|s| s.starts_with('D')));
+        assert_eq!(texts("  MOVEA.L (A0", &symbols), ["(A0)", "(A0)+", "-(A0)"]);
+        // Kein weiterer Operand vorgesehen
+        assert!(texts("  NOP ", &symbols).is_empty());
+    }
+
+    #[test]
+    fn test_symbols_in_source() {
+        let source = "START: MOVEQ #1, D0\nLIMIT: EQU 5\n  BRA START ; x: y\n1: NOP";
+        assert_eq!(symbols_in_source(source), ["LIMIT", "START"]);
+    }
+}
@@ -0,0 +1,1187 @@
+// Ablaufsteuerung für die GUI
+// Führt lange Programme kooperativ aus: pro Frame nur ein begrenztes Kontingent an
+// Instruktionen, damit die Oberfläche (Stop/Pause) bedienbar bleibt.
+
+use crate::assembler::AsmError;
+use crate::breakpoints::{RegisterWatch, RegisterWatchHit};
+use crate::cpu::{CpuSnapshot, Exception, ExecResult};
+use crate::emulator::{DataExecution, Emulator, ReloadReport, StepBack};
+use crate::memory::{Memory, MemoryPatch};
+use crate::program::Program;
+use crate::trace::{explain_branch, format_trace, TraceLevel};
+use std::ops::Range;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_STEP_CAP: u64 = 10_000_000;
+/// So viele zuletzt ausgeführte Schritte bleiben für Erklärungen erhalten
+pub const HISTORY_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunState {
+    Idle,
+    Running,
+    Paused,
+    Halted,
+}
+
+/// Warum ein Lauf beendet wurde
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    Halted,
+    Exception(Exception),
+    Breakpoint(u32),
+    StepCap(u64),
+    OutOfProgram(u32), // PC zeigt nicht auf assemblierten Code
+    DataExecution(DataExecution),
+    IdleLoop(Vec<u32>), // PCs einer Endlosschleife ohne Wirkung
+    RegisterWatch(RegisterWatchHit),
+    Stopped, // vom Benutzer abgebrochen
+}
+
+impl StopReason {
+    /// Meldung für die Konsole; Adressen werden über `program` in Labels übersetzt
+    pub fn describe(&self, program: Option<&Program>) -> String {
+        match self {
+            StopReason::Halted => "✓ Programm regulär beendet (SIMHALT)".to_string(),
+            StopReason::Exception(Exception::BusError(fault)) => {
+                format!("⚠️ {}", fault.describe(program))
+            }
+            StopReason::Exception(exception) => format!("⚠️ Exception: {}", exception),
+            StopReason::Breakpoint(address) => {
+                format!("🔴 Breakpoint bei 0x{:06X}", address)
+            }
+            StopReason::StepCap(cap) => {
+                format!("⛔ Schrittlimit von {} Instruktionen erreicht", cap)
+            }
+            StopReason::OutOfProgram(pc) => {
+                format!("🛑 Programm beendet (PC 0x{:06X} außerhalb des Codes)", pc)
+            }
+            StopReason::DataExecution(hit) => format!(
+                "⚠️ {} (Start → \"Stop on data\" abschalten, um trotzdem fortzusetzen)",
+                hit
+            ),
+            StopReason::IdleLoop(pcs) => {
+                let at = pcs
+                    .iter()
+                    .map(|&pc| match program {
+                        Some(program) => program.symbolize(pc),
+                        None => format!("${:06X}", pc),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("🔁 program appears to be in an idle loop at {}", at)
+            }
+            StopReason::RegisterWatch(hit) => format!("👁 {}", hit),
+            StopReason::Stopped => "⏹️ Ausführung abgebrochen".to_string(),
+        }
+    }
+}
+
+/// Wie viel beim Laden eines neuen Programms zurückgesetzt wird
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Speicher leeren, Abbild laden, CPU zurücksetzen (Assemble)
+    Full,
+    /// Nur den Code ersetzen; Daten, Register und Quellzeile des PC bleiben
+    /// (siehe `Emulator::reload_code`)
+    CodeOnly,
+}
+
+/// Warum `Controller::load_image` nichts geladen hat
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// Während eines Laufs darf das Abbild nicht ausgetauscht werden; erst pausieren
+    Running,
+    Assembly(Vec<AsmError>),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Running => write!(f, "cannot load a program while it is running"),
+            LoadError::Assembly(errors) => write!(f, "{} assembly error(s)", errors.len()),
+        }
+    }
+}
+
+/// Hinweis, solange ein älteres Abbild läuft als der Quelltext im Editor
+pub const LAST_GOOD_BADGE: &str = "running last successful build (source has errors)";
+
+/// Das Programm, auf das Step und Run wirken. Wird nur durch ein erfolgreiches
+/// `load_image` als Ganzes ersetzt; ein fehlgeschlagener Versuch markiert es nur.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveProgram {
+    /// Quelltext, aus dem das geladene Abbild assembliert wurde
+    pub source: String,
+    /// Der zuletzt übersetzte Quelltext hatte Fehler; es läuft weiter dieses Abbild
+    pub source_has_errors: bool,
+}
+
+impl ActiveProgram {
+    pub fn badge(&self) -> Option<&'static str> {
+        self.source_has_errors.then_some(LAST_GOOD_BADGE)
+    }
+}
+
+/// Zustand nach einem Ausführungsabschnitt; die GUI zeichnet nur aus diesen Daten
+///
+/// Speicher wird nicht geteilt: jedes Update enthält Kopien der seit dem letzten Update
+/// beschriebenen Bereiche, immer an Instruktionsgrenzen. Ein Leser in einem anderen
+/// Thread hält damit einen eigenen Spiegel (`apply_to`) und sieht nie halb geschriebene
+/// Wörter, während die CPU ohne Locks auf `&mut Memory` arbeitet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameUpdate {
+    pub cpu: CpuSnapshot,
+    pub state: RunState,
+    /// Seit dem letzten Update beschriebene Speicherbereiche (ganze 4-KB-Seiten)
+    pub dirty: Vec<Range<u32>>,
+    /// Inhalt dieser Bereiche nach dem Abschnitt; leer für `subscribe_state`
+    pub memory: Vec<MemoryPatch>,
+    /// Angenommene Interrupts seit dem letzten Reset
+    pub interrupts: u64,
+}
+
+impl FrameUpdate {
+    /// Überträgt die geänderten Bereiche in einen Speicherspiegel
+    pub fn apply_to(&self, mirror: &mut Memory) {
+        for patch in &self.memory {
+            mirror.apply_patch(patch);
+        }
+    }
+}
+
+pub struct Controller {
+    emulator: Emulator,
+    state: RunState,
+    step_cap: u64,
+    total_steps: u64,
+    run_time: Duration,
+    /// Zyklenstand der CPU beim Start des Laufs (für die emulierte Zeit)
+    start_cycles: u64,
+    /// Breakpoint an der aktuellen Adresse beim Fortsetzen einmal überspringen
+    skip_breakpoint: bool,
+    trace_level: TraceLevel,
+    messages: Vec<String>,
+    updates: Option<Sender<FrameUpdate>>,
+    /// Der Empfänger will Kopien der Dirty-Bereiche (`subscribe`, nicht `subscribe_state`)
+    copy_memory: bool,
+    history: Vec<ExecResult>, // älteste zuerst
+    register_watches: Vec<RegisterWatch>,
+    active: Option<ActiveProgram>,
+}
+
+impl Default for Controller {
+    fn default() -> Self {
+        Self::new(Emulator::new())
+    }
+}
+
+impl Controller {
+    pub fn new(emulator: Emulator) -> Self {
+        Controller {
+            emulator,
+            state: RunState::Idle,
+            step_cap: DEFAULT_STEP_CAP,
+            total_steps: 0,
+            run_time: Duration::ZERO,
+            start_cycles: 0,
+            skip_breakpoint: false,
+            trace_level: TraceLevel::Off,
+            messages: Vec::new(),
+            updates: None,
+            copy_memory: false,
+            history: Vec::new(),
+            register_watches: Vec::new(),
+            active: None,
+        }
+    }
+
+    pub fn emulator(&self) -> &Emulator {
+        &self.emulator
+    }
+
+    pub fn emulator_mut(&mut self) -> &mut Emulator {
+        &mut self.emulator
+    }
+
+    /// Zuletzt erfolgreich über `load_image` geladenes Programm
+    pub fn active_program(&self) -> Option<&ActiveProgram> {
+        self.active.as_ref()
+    }
+
+    /// `LAST_GOOD_BADGE`, falls der Quelltext seit dem letzten Laden nicht übersetzbar ist
+    pub fn badge(&self) -> Option<&'static str> {
+        self.active.as_ref().and_then(ActiveProgram::badge)
+    }
+
+    pub fn state(&self) -> RunState {
+        self.state
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == RunState::Running
+    }
+
+    pub fn step_cap(&self) -> u64 {
+        self.step_cap
+    }
+
+    pub fn set_step_cap(&mut self, cap: u64) {
+        self.step_cap = cap.max(1);
+    }
+
+    pub fn trace_level(&self) -> TraceLevel {
+        self.trace_level
+    }
+
+    /// Detailgrad der Trace-Zeilen, die pro Schritt in die Meldungen geschrieben werden
+    pub fn set_trace_level(&mut self, level: TraceLevel) {
+        self.trace_level = level;
+        self.emulator
+            .cpu_mut()
+            .set_access_trace(level.needs_accesses());
+    }
+
+    /// Anzahl der Instruktionen im aktuellen (oder letzten) Lauf
+    pub fn total_steps(&self) -> u64 {
+        self.total_steps
+    }
+
+    /// Emulierte Laufzeit des aktuellen (oder letzten) Laufs: Zyklen geteilt durch den Takt
+    /// (`Emulator::clock_hz`, derselbe, mit dem die Geräte rechnen)
+    pub fn emulated_time(&self) -> Duration {
+        let cycles = self
+            .emulator
+            .cpu()
+            .cycles()
+            .saturating_sub(self.start_cycles);
+        Duration::from_secs_f64(cycles as f64 / self.emulator.clock_hz() as f64)
+    }
+
+    /// Startet einen neuen Lauf ab dem aktuellen PC
+    pub fn start(&mut self) {
+        self.total_steps = 0;
+        self.run_time = Duration::ZERO;
+        self.start_cycles = self.emulator.cpu().cycles();
+        self.resume();
+    }
+
+    pub fn pause(&mut self) {
+        if self.state == RunState::Running {
+            self.state = RunState::Paused;
+            self.messages.push(format!(
+                "⏸️ Pausiert nach {} Instruktionen",
+                self.total_steps
+            ));
+        }
+    }
+
+    pub fn resume(&mut self) {
+        self.state = RunState::Running;
+        self.skip_breakpoint = true;
+    }
+
+    pub fn stop(&mut self) {
+        if matches!(self.state, RunState::Running | RunState::Paused) {
+            self.finish(&StopReason::Stopped);
+        }
+    }
+
+    /// Zurück in den Ausgangszustand (z.B. nach Reset oder neuem Assemble)
+    pub fn reset(&mut self) {
+        self.emulator.reset();
+        self.history.clear();
+        self.state = RunState::Idle;
+        self.total_steps = 0;
+        self.run_time = Duration::ZERO;
+        self.start_cycles = self.emulator.cpu().cycles();
+    }
+
+    /// Assembliert `source` und tauscht das Programm aus. Nur in Idle, Paused oder Halted
+    /// erlaubt: zwischen zwei Frames steht die CPU zwar an einer Instruktionsgrenze, ein
+    /// laufendes Programm liefe aber ohne Pause einfach im neuen Code weiter.
+    ///
+    /// `Full` setzt den Controller zurück (Idle), `CodeOnly` lässt den Zustand stehen, damit
+    /// ein pausierter Lauf mit `resume` im neuen Code weitergehen kann. Bei Fehlern bleibt
+    /// alles unverändert: das bisherige Programm samt Symbolen, Quellzuordnung und
+    /// Breakpoints bleibt aktiv und wird nur als veraltet markiert (`badge`).
+    pub fn load_image(
+        &mut self,
+        source: &str,
+        mode: LoadMode,
+    ) -> Result<Option<ReloadReport>, LoadError> {
+        if self.state == RunState::Running {
+            return Err(LoadError::Running);
+        }
+        let loaded = match mode {
+            LoadMode::Full => self.emulator.load_source(source).map(|()| None),
+            LoadMode::CodeOnly => self.emulator.reload_code(source).map(Some),
+        };
+        let report = match loaded {
+            Ok(report) => report,
+            Err(errors) => {
+                if let Some(active) = &mut self.active {
+                    active.source_has_errors = true;
+                }
+                return Err(LoadError::Assembly(errors));
+            }
+        };
+        match mode {
+            LoadMode::Full => self.reset(),
+            // Erklärungen beziehen sich auf Zeilen des alten Quelltexts
+            LoadMode::CodeOnly => self.history.clear(),
+        }
+        self.active = Some(ActiveProgram {
+            source: source.to_string(),
+            source_has_errors: false,
+        });
+        self.publish();
+        Ok(report)
+    }
+
+    /// Führt bis zu `budget` Instruktionen aus. Gibt den Grund zurück, falls der Lauf in
+    /// diesem Frame endet.
+    pub fn on_frame(&mut self, budget: u32) -> Option<StopReason> {
+        if self.state != RunState::Running {
+            return None;
+        }
+        self.advance(budget)
+    }
+
+    /// Führt bis zu `n` Instruktionen aus: im Lauf das Kontingent eines Frames, im Step Mode 1.
+    /// Breakpoints, Geräte, Halt/Exception, Leerlauf-Erkennung und Meldungen sind in beiden
+    /// Fällen dieselben. Gibt den Grund zurück, falls die Ausführung dabei endet.
+    ///
+    /// Außerhalb eines Laufs wird die erste Instruktion auch auf einem Breakpoint ausgeführt,
+    /// sonst käme ein einzelner Schritt nie daran vorbei.
+    pub fn advance(&mut self, n: u32) -> Option<StopReason> {
+        if self.state != RunState::Running {
+            self.skip_breakpoint = true;
+        }
+
+        let frame_start = Instant::now();
+        let mut reason = None;
+
+        for _ in 0..n {
+            if let Some(stop) = self.check_before_step() {
+                reason = Some(stop);
+                break;
+            }
+
+            let result = self.emulator.step();
+            let watch_hit = self.check_register_watches(&result);
+            if let Some(irq) = self.emulator.last_interrupt() {
+                if self.trace_level != TraceLevel::Off {
+                    self.messages.push(format!(
+                        "⚡ Interrupt Level {} (Vektor {})",
+                        irq.level,
+                        irq.vector_number()
+                    ));
+                }
+            }
+            if let Some(line) =
+                format_trace(&result, self.trace_level, self.emulator.address_formatter())
+            {
+                self.messages.push(line);
+            }
+            self.remember(result);
+            self.total_steps += 1;
+            self.skip_breakpoint = false;
+
+            if let Some(exception) = self.emulator.cpu().exception() {
+                reason = Some(StopReason::Exception(exception));
+                break;
+            }
+            if self.emulator.cpu().is_halted() {
+                reason = Some(StopReason::Halted);
+                break;
+            }
+            if let Some(hit) = watch_hit {
+                reason = Some(StopReason::RegisterWatch(hit));
+                break;
+            }
+            if let Some(pcs) = self.emulator.idle_loop() {
+                reason = Some(StopReason::IdleLoop(pcs.to_vec()));
+                break;
+            }
+            if self.total_steps >= self.step_cap {
+                reason = Some(StopReason::StepCap(self.step_cap));
+                break;
+            }
+        }
+
+        self.run_time += frame_start.elapsed();
+        if let Some(reason) = &reason {
+            self.finish(reason);
+        }
+        self.publish();
+        reason
+    }
+
+    /// Einen Schritt zurück (siehe `Emulator::step_back`); nur außerhalb eines Laufs
+    pub fn step_back(&mut self) -> Option<StepBack> {
+        if self.state == RunState::Running {
+            return None;
+        }
+        let back = self.emulator.step_back()?;
+        self.history.pop();
+        self.total_steps = self.total_steps.saturating_sub(1);
+        self.publish();
+        Some(back)
+    }
+
+    pub fn register_watches(&self) -> &[RegisterWatch] {
+        &self.register_watches
+    }
+
+    pub fn register_watches_mut(&mut self) -> &mut Vec<RegisterWatch> {
+        &mut self.register_watches
+    }
+
+    /// Register-Watch aus Text wie "D3" oder "A7 < $7F00" hinzufügen (siehe `RegisterWatch`)
+    pub fn add_register_watch(&mut self, text: &str, pause: bool) -> Result<(), String> {
+        self.register_watches
+            .push(RegisterWatch::parse(text, pause)?);
+        Ok(())
+    }
+
+    /// Prüft alle Register-Watches nach einem Schritt. Nicht anhaltende Treffer werden nur
+    /// gemeldet; zurück kommt der erste, der anhalten soll.
+    fn check_register_watches(&mut self, result: &ExecResult) -> Option<RegisterWatchHit> {
+        if self.register_watches.is_empty() || result.before == result.after {
+            return None;
+        }
+        let mut pause = None;
+        for watch in &mut self.register_watches {
+            let Some(hit) = watch.check(&result.before, &result.after) else {
+                continue;
+            };
+            if hit.pause {
+                pause.get_or_insert(hit);
+            } else {
+                self.messages.push(format!("👁 {}", hit));
+            }
+        }
+        pause
+    }
+
+    /// Die letzten `HISTORY_LEN` Schritte, ältester zuerst
+    pub fn history(&self) -> &[ExecResult] {
+        &self.history
+    }
+
+    /// Warum der letzte Schritt gesprungen ist oder nicht (nur nach einem Bcc)
+    pub fn explain_last_branch(&self) -> Option<String> {
+        let (last, earlier) = self.history.split_last()?;
+        explain_branch(last, earlier, self.emulator.program())
+    }
+
+    fn remember(&mut self, result: ExecResult) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.remove(0);
+        }
+        self.history.push(result);
+    }
+
+    /// Liefert einen Empfänger für `FrameUpdate`s; ein früherer Empfänger wird abgehängt
+    pub fn subscribe(&mut self) -> Receiver<FrameUpdate> {
+        self.subscribe_with(true)
+    }
+
+    /// Wie `subscribe`, aber ohne Speicherkopien: für Empfänger im selben Thread, die den
+    /// Speicher direkt über `emulator()` lesen
+    pub fn subscribe_state(&mut self) -> Receiver<FrameUpdate> {
+        self.subscribe_with(false)
+    }
+
+    fn subscribe_with(&mut self, copy_memory: bool) -> Receiver<FrameUpdate> {
+        let (sender, receiver) = channel();
+        self.updates = Some(sender);
+        self.copy_memory = copy_memory;
+        self.publish();
+        receiver
+    }
+
+    /// Schickt den aktuellen Zustand an den Empfänger (nach Slices, Steps, Reset, ...)
+    pub fn publish(&mut self) {
+        let Some(sender) = &self.updates else {
+            return;
+        };
+        let dirty = self.emulator.memory_mut().take_dirty_ranges();
+        let memory = match self.copy_memory {
+            true => dirty
+                .iter()
+                .map(|range| self.emulator.memory().copy_range(range.clone()))
+                .collect(),
+            false => Vec::new(),
+        };
+        let update = FrameUpdate {
+            cpu: self.emulator.cpu().snapshot(),
+            state: self.state,
+            dirty,
+            memory,
+            interrupts: self.emulator.interrupts(),
+        };
+        if sender.send(update).is_err() {
+            self.updates = None;
+        }
+    }
+
+    fn check_before_step(&mut self) -> Option<StopReason> {
+        let pc = self.emulator.cpu().get_pc();
+
+        if let Some(hit) = self.emulator.pending_data_execution() {
+            return Some(StopReason::DataExecution(hit));
+        }
+        // Daten bei abgeschalteter Prüfung bewusst ausführen
+        if let Some(program) = self.emulator.program() {
+            if !program.source_map.contains_key(&pc) && !program.is_data(pc) {
+                return Some(StopReason::OutOfProgram(pc));
+            }
+        }
+
+        if !self.skip_breakpoint && self.emulator.breakpoint_hit() {
+            return Some(StopReason::Breakpoint(pc));
+        }
+
+        None
+    }
+
+    fn finish(&mut self, reason: &StopReason) {
+        self.state = match reason {
+            StopReason::Halted
+            | StopReason::Exception(_)
+            | StopReason::OutOfProgram(_)
+            | StopReason::IdleLoop(_) => RunState::Halted,
+            StopReason::Breakpoint(_)
+            | StopReason::DataExecution(_)
+            | StopReason::RegisterWatch(_) => RunState::Paused,
+            StopReason::StepCap(_) | StopReason::Stopped => RunState::Idle,
+        };
+        self.messages.push(format!(
+            "{} — {} Instruktionen in {:.2}s",
+            reason.describe(self.emulator.program()),
+            self.total_steps,
+            self.run_time.as_secs_f64()
+        ));
+    }
+
+    /// Fortschrittsanzeige während eines Laufs, z.B. "running… 1.2M instructions, 0.3s emulated"
+    pub fn progress_text(&self) -> Option<String> {
+        match self.state {
+            RunState::Running => Some(format!(
+                "running… {} instructions, {:.1}s emulated",
+                format_count(self.total_steps),
+                self.emulated_time().as_secs_f64()
+            )),
+            RunState::Paused => Some(format!(
+                "paused after {} instructions",
+                format_count(self.total_steps)
+            )),
+            _ => None,
+        }
+    }
+
+    /// Meldungen für die Konsole seit dem letzten Aufruf
+    pub fn take_messages(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.messages)
+    }
+}
+
+/// Kompakte Zahl: 950, 45.3K, 1.2M
+pub fn format_count(count: u64) -> String {
+    if count >= 1_000_000 {
+        format!("{:.1}M", count as f64 / 1_000_000.0)
+    } else if count >= 10_000 {
+        format!("{:.1}K", count as f64 / 1_000.0)
+    } else {
+        count.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breakpoints::{Condition, Location, RegisterWatchHit};
+    use crate::bus::Timer;
+    use crate::memory::Memory;
+
+    // 2 * 25000 Schleifendurchläufe + Initialisierung + SIMHALT
+    const LONG_LOOP: &str = "            ORG     $1000
+            MOVE.L  #25000, D1
+LOOP:       SUBQ.L  #1, D1
+            BNE     LOOP
+            SIMHALT
+";
+
+    fn controller(source: &str) -> Controller {
+        let mut emulator = Emulator::new();
+        emulator.load_source(source).unwrap();
+        Controller::new(emulator)
+    }
+
+    #[test]
+    fn test_long_run_spans_many_frames() {
+        let mut controller = controller(LONG_LOOP);
+        controller.start();
+
+        let mut frames = 0;
+        let reason = loop {
+            frames += 1;
+            if let Some(reason) = controller.on_frame(1000) {
+                break reason;
+            }
+            assert!(controller.is_running());
+            assert!(controller.progress_text().unwrap().starts_with("running…"));
+            assert!(frames < 100, "run should finish");
+        };
+
+        assert_eq!(reason, StopReason::Halted);
+        assert_eq!(controller.state(), RunState::Halted);
+        assert_eq!(controller.total_steps(), 50_002);
+        assert_eq!(frames, 51);
+        assert_eq!(controller.emulator().cpu().get_data_register(1), 0);
+
+        let messages = controller.take_messages();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("SIMHALT"));
+        assert!(messages[0].contains("50002 Instruktionen"));
+    }
+
+    #[test]
+    fn test_progress_shows_emulated_time() {
+        // Die Zeit kommt aus den Zyklen, nicht aus der Uhr des Hosts
+        let mut controller = controller(LONG_LOOP);
+        controller.emulator_mut().set_clock_hz(1000);
+        controller.start();
+        controller.on_frame(1000);
+        let cycles = controller.emulator().cpu().cycles();
+        assert_eq!(
+            controller.emulated_time().as_secs_f64(),
+            cycles as f64 / 1000.0
+        );
+        assert_eq!(
+            controller.progress_text().unwrap(),
+            "running… 1000 instructions, 7.0s emulated"
+        );
+
+        // Ein neuer Lauf zählt ab dem aktuellen Zyklenstand
+        controller.pause();
+        controller.start();
+        assert_eq!(controller.emulated_time(), Duration::ZERO);
+        controller.on_frame(1000);
+        assert_eq!(
+            controller.emulated_time().as_secs_f64(),
+            (controller.emulator().cpu().cycles() - cycles) as f64 / 1000.0
+        );
+    }
+
+    #[test]
+    fn test_step_cap_ends_run() {
+        let mut controller = controller("LOOP: BRA LOOP");
+        controller.emulator_mut().set_idle_loop_detection(false);
+        controller.set_step_cap(2500);
+        controller.start();
+
+        let mut reason = None;
+        for _ in 0..10 {
+            reason = reason.or(controller.on_frame(1000));
+        }
+
+        assert_eq!(reason, Some(StopReason::StepCap(2500)));
+        assert_eq!(controller.total_steps(), 2500);
+        assert!(!controller.is_running());
+    }
+
+    #[test]
+    fn test_breakpoint_pauses_and_resume_continues() {
+        let mut controller = controller(LONG_LOOP);
+        controller
+            .emulator_mut()
+            .add_breakpoint(Location::Symbol("LOOP".into()));
+        controller.start();
+
+        assert_eq!(
+            controller.on_frame(1000),
+            Some(StopReason::Breakpoint(0x1006))
+        );
+        assert_eq!(controller.state(), RunState::Paused);
+        assert_eq!(controller.total_steps(), 1);
+
+        controller.resume();
+        assert_eq!(
+            controller.on_frame(1000),
+            Some(StopReason::Breakpoint(0x1006))
+        );
+        assert_eq!(controller.total_steps(), 3, "one loop iteration");
+    }
+
+    #[test]
+    fn test_breakpoint_stops_run_inside_frame() {
+        // 601 Instruktionen bis MIDDLE, ohne Breakpoint über 1000
+        let source = "            ORG     $1000
+            MOVE.L  #300, D1
+LOOP:       SUBQ.L  #1, D1
+            BNE     LOOP
+MIDDLE:     MOVE.L  #300, D2
+LOOP2:      SUBQ.L  #1, D2
+            BNE     LOOP2
+            SIMHALT
+";
+        let mut run = controller(source);
+        run.emulator_mut()
+            .add_breakpoint(Location::Symbol("MIDDLE".into()));
+        run.start();
+
+        assert_eq!(run.advance(1000), Some(StopReason::Breakpoint(0x100A)));
+        assert_eq!(run.state(), RunState::Paused);
+        assert_eq!(run.total_steps(), 601);
+        assert_eq!(run.emulator().cpu().get_data_register(1), 0);
+        assert_eq!(run.emulator().cpu().get_data_register(2), 0);
+
+        // Einzelschritte kommen an derselben Stelle an und laufen dann über den Breakpoint
+        let mut stepped = controller(source);
+        stepped
+            .emulator_mut()
+            .add_breakpoint(Location::Symbol("MIDDLE".into()));
+        for _ in 0..601 {
+            assert_eq!(stepped.advance(1), None);
+        }
+        assert_eq!(stepped.emulator().cpu().get_pc(), 0x100A);
+        assert_eq!(stepped.advance(1), None);
+        assert_eq!(stepped.emulator().cpu().get_data_register(2), 300);
+
+        run.resume();
+        assert_eq!(run.advance(1000), Some(StopReason::Halted));
+        assert_eq!(run.total_steps(), 1203);
+    }
+
+    #[test]
+    fn test_timer_interrupt_same_in_run_and_steps() {
+        // Timer mit 1000 Zyklen Periode auf Level 1 (Autovektor 25 an $64)
+        const TIMER_PROGRAM: &str = "STACK       EQU     $8000
+            ORG     $64
+            DC.L    TICK
+            ORG     $1000
+START:      MOVEA.L #STACK, A7
+LOOP:       ADDQ.L  #1, D0
+            BRA     LOOP
+TICK:       ADDQ.L  #1, D1
+            RTE
+";
+        let timer_controller = || {
+            let mut controller = controller(TIMER_PROGRAM);
+            controller
+                .emulator_mut()
+                .attach_device(Box::new(Timer::new(1000, 1)));
+            controller.emulator_mut().cpu_mut().set_sr(0x2000);
+            controller.set_trace_level(TraceLevel::Pc);
+            controller
+        };
+
+        let mut run = timer_controller();
+        run.start();
+        assert_eq!(run.on_frame(500), None);
+
+        let mut steps = timer_controller();
+        for _ in 0..500 {
+            assert_eq!(steps.advance(1), None);
+        }
+
+        assert_eq!(run.emulator().interrupts(), 3);
+        assert_eq!(steps.emulator().interrupts(), 3);
+        assert_eq!(
+            run.emulator().cpu().snapshot(),
+            steps.emulator().cpu().snapshot()
+        );
+        assert_eq!(run.emulator().cpu().get_data_register(1), 3);
+        let messages = run.take_messages();
+        assert_eq!(messages.iter().filter(|m| m.starts_with("⚡")).count(), 3);
+        assert_eq!(messages, steps.take_messages());
+    }
+
+    #[test]
+    fn test_load_rejected_while_running() {
+        let mut controller = controller(LONG_LOOP);
+        controller.start();
+        assert_eq!(controller.on_frame(1000), None);
+
+        let result = controller.load_image("MOVEQ #1, D0\nSIMHALT", LoadMode::Full);
+        assert_eq!(result.unwrap_err(), LoadError::Running);
+        let result = controller.load_image(LONG_LOOP, LoadMode::CodeOnly);
+        assert_eq!(result.unwrap_err(), LoadError::Running);
+
+        // Lauf und Programm unverändert
+        assert!(controller.is_running());
+        assert_eq!(
+            controller.emulator().program().unwrap().entry_point(),
+            Some(0x1000)
+        );
+        assert_eq!(controller.on_frame(100_000), Some(StopReason::Halted));
+        assert_eq!(controller.total_steps(), 50_002);
+    }
+
+    #[test]
+    fn test_pause_swap_resume() {
+        let mut controller = controller(LONG_LOOP);
+        controller.start();
+        assert_eq!(controller.on_frame(1000), None);
+        controller.pause();
+        assert_eq!(controller.emulator().cpu().get_data_register(1), 24_500);
+
+        // Nur der Code ändert sich: D1 und die Position in der Schleife bleiben
+        let faster = LONG_LOOP.replace("SUBQ.L  #1", "SUBQ.L  #2");
+        let report = controller
+            .load_image(&faster, LoadMode::CodeOnly)
+            .unwrap()
+            .unwrap();
+        assert!(report.pc_preserved);
+        assert_eq!(controller.state(), RunState::Paused);
+
+        controller.resume();
+        assert_eq!(controller.on_frame(100_000), Some(StopReason::Halted));
+        // Erst das anstehende BNE, dann 12250 Durchläufe mit SUBQ #2 und SIMHALT
+        assert_eq!(controller.total_steps(), 1000 + 1 + 2 * 12_250 + 1);
+
+        // Fehlerhafter Quelltext lässt alles stehen
+        let result = controller.load_image("MOVEQ #1", LoadMode::Full);
+        assert!(matches!(result, Err(LoadError::Assembly(_))));
+        assert_eq!(controller.state(), RunState::Halted);
+
+        // Vollständiges Laden setzt zurück
+        assert!(controller
+            .load_image("MOVEQ #7, D2\nSIMHALT", LoadMode::Full)
+            .unwrap()
+            .is_none());
+        assert_eq!(controller.state(), RunState::Idle);
+        assert_eq!(controller.total_steps(), 0);
+        controller.start();
+        assert_eq!(controller.on_frame(10), Some(StopReason::Halted));
+        assert_eq!(controller.emulator().cpu().get_data_register(2), 7);
+    }
+
+    #[test]
+    fn test_bus_error_stop_names_instruction_and_section() {
+        let mut controller = Controller::default();
+        let source = "        ORG $1000\n        MOVE.L #$E10000, D1\n        MOVEA.L D1, A2\n        MOVE.L D0, (A2)\n        SIMHALT";
+        controller.load_image(source, LoadMode::Full).unwrap();
+        controller
+            .emulator_mut()
+            .memory_mut()
+            .unmap(0xE0_0000..0xF0_0000);
+        let reason = controller.advance(3).unwrap();
+        assert!(matches!(
+            reason,
+            StopReason::Exception(Exception::BusError(_))
+        ));
+        assert_eq!(
+            reason.describe(controller.emulator().program()),
+            "⚠️ bus error: write.l to $E10000 (unmapped) by MOVE.L D0, (A2) at CODE+$8"
+        );
+    }
+
+    #[test]
+    fn test_failed_load_keeps_last_good_program() {
+        let mut controller = Controller::default();
+        // Ohne Programm gibt es nichts, das weiterlaufen könnte
+        let result = controller.load_image("MOVEQ #1", LoadMode::Full);
+        assert!(matches!(result, Err(LoadError::Assembly(_))));
+        assert_eq!(controller.active_program(), None);
+        assert_eq!(controller.badge(), None);
+
+        let good = "MOVEQ #1, D0\nMOVEQ #2, D1\nMOVEQ #3, D2\nSIMHALT";
+        controller.load_image(good, LoadMode::Full).unwrap();
+        assert_eq!(controller.badge(), None);
+        assert_eq!(controller.advance(1), None);
+
+        let result = controller.load_image("MOVEQ #9, D0\nBOGUS", LoadMode::Full);
+        assert!(matches!(result, Err(LoadError::Assembly(_))));
+        assert_eq!(controller.badge(), Some(LAST_GOOD_BADGE));
+        let active = controller.active_program().unwrap();
+        assert_eq!(active.source, good);
+        assert!(active.source_has_errors);
+
+        // Step und Run arbeiten weiter mit dem alten Abbild, an derselben Stelle
+        assert_eq!(controller.advance(1), None);
+        assert_eq!(controller.emulator().cpu().get_data_register(1), 2);
+        controller.start();
+        assert_eq!(controller.on_frame(100), Some(StopReason::Halted));
+        assert_eq!(controller.emulator().cpu().get_data_register(0), 1);
+        assert_eq!(controller.emulator().cpu().get_data_register(2), 3);
+
+        // Auch ein fehlgeschlagenes Nachladen nur des Codes lässt das Programm stehen
+        let result = controller.load_image("BOGUS", LoadMode::CodeOnly);
+        assert!(matches!(result, Err(LoadError::Assembly(_))));
+        assert_eq!(controller.badge(), Some(LAST_GOOD_BADGE));
+        assert_eq!(controller.emulator().program().unwrap().image.len(), 4);
+
+        // Erst ein erfolgreicher Build ersetzt das Programm und entfernt den Hinweis
+        let fixed = "MOVEQ #9, D0\nSIMHALT";
+        controller.load_image(fixed, LoadMode::Full).unwrap();
+        assert_eq!(controller.badge(), None);
+        assert_eq!(controller.active_program().unwrap().source, fixed);
+        controller.start();
+        assert_eq!(controller.on_frame(100), Some(StopReason::Halted));
+        assert_eq!(controller.emulator().cpu().get_data_register(0), 9);
+    }
+
+    #[test]
+    fn test_conditional_breakpoint_counts_hits() {
+        let mut controller = controller(LONG_LOOP);
+        let loop_start = Location::Symbol("LOOP".into());
+        let emulator = controller.emulator_mut();
+        emulator.add_breakpoint(loop_start.clone());
+        emulator
+            .breakpoints_mut()
+            .get_mut(&loop_start)
+            .unwrap()
+            .condition = Some(Condition::parse("D1 == 24990").unwrap());
+        controller.start();
+
+        assert_eq!(
+            controller.on_frame(1000),
+            Some(StopReason::Breakpoint(0x1006))
+        );
+        assert_eq!(controller.emulator().cpu().get_data_register(1), 24990);
+        let bp = controller.emulator().breakpoints().iter().next().unwrap();
+        assert_eq!(bp.hits, 1, "only counted when the condition holds");
+
+        // Deaktiviert: läuft bis zum Step-Limit durch
+        controller
+            .emulator_mut()
+            .breakpoints_mut()
+            .set_all_enabled(false);
+        controller.resume();
+        assert_eq!(controller.on_frame(100), None);
+    }
+
+    #[test]
+    fn test_pause_and_stop() {
+        let mut controller = controller(LONG_LOOP);
+        controller.start();
+        controller.on_frame(10);
+        controller.pause();
+
+        assert_eq!(controller.on_frame(1000), None);
+        assert_eq!(controller.total_steps(), 10, "paused runs do not advance");
+        assert!(controller.progress_text().unwrap().starts_with("paused"));
+
+        controller.stop();
+        assert_eq!(controller.state(), RunState::Idle);
+        assert!(controller
+            .take_messages()
+            .last()
+            .unwrap()
+            .contains("abgebrochen"));
+    }
+
+    #[test]
+    fn test_idle_loop_stops_run() {
+        let mut controller = controller("START: MOVEQ #1, D0\nEND: BRA END");
+        controller.start();
+
+        let reason = (0..10).find_map(|_| controller.on_frame(1000));
+        assert_eq!(reason, Some(StopReason::IdleLoop(vec![0x0002])));
+        assert_eq!(controller.state(), RunState::Halted);
+        assert!(
+            controller.take_messages()[0].contains("program appears to be in an idle loop at END")
+        );
+    }
+
+    #[test]
+    fn test_trace_lines_per_step() {
+        let mut controller = controller("MOVEQ #1, D0\nSIMHALT");
+        controller.set_trace_level(TraceLevel::Registers);
+        controller.start();
+        controller.on_frame(10);
+
+        let messages = controller.take_messages();
+        assert_eq!(messages[0], "$000000  MOVEQ #1, D0  ; D0=$00000001");
+        assert_eq!(messages[1], "$000002  SIMHALT");
+        assert!(messages[2].contains("SIMHALT"), "stop message follows");
+    }
+
+    #[test]
+    fn test_frame_updates_after_slices() {
+        let mut controller = controller(LONG_LOOP);
+        let updates = controller.subscribe();
+        let initial = updates.try_recv().unwrap();
+        assert_eq!(initial.cpu.pc, 0x1000);
+
+        controller.start();
+        controller.on_frame(10);
+        let update = updates.try_recv().unwrap();
+        assert_eq!(update.state, RunState::Running);
+        assert_eq!(update.cpu, controller.emulator().cpu().snapshot());
+        assert!(update.cpu.cycles > initial.cpu.cycles);
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_state_updates_skip_memory_copies() {
+        let mut controller = controller(LONG_LOOP);
+        let updates = controller.subscribe_state();
+        let initial = updates.try_recv().unwrap();
+        assert_eq!(initial.dirty, vec![0x1000..0x2000], "program image");
+        assert!(initial.memory.is_empty());
+
+        // Neu assemblieren löscht den Speicher: dirty sind nur die belegten Seiten
+        controller.emulator_mut().load_source(LONG_LOOP).unwrap();
+        controller.publish();
+        let update = updates.try_recv().unwrap();
+        assert_eq!(update.dirty, vec![0x1000..0x2000]);
+        assert!(update.memory.is_empty());
+    }
+
+    #[test]
+    fn test_memory_mirror_in_reader_thread() {
+        // Schreibt abwechselnd $00000000 und $FFFFFFFF; ein Leser darf nie Mischwerte sehen
+        let mut controller = controller(
+            "            ORG     $1000
+            MOVEA.L #BUFFER, A0
+LOOP:       MOVEQ   #0, D0
+            MOVE.L  D0, (A0)
+            MOVEQ   #-1, D0
+            MOVE.L  D0, (A0)
+            BRA     LOOP
+BUFFER:     DS.L    1
+",
+        );
+        controller.set_step_cap(u64::MAX);
+        let buffer = controller.emulator().program().unwrap().symbols["BUFFER"];
+        let updates = controller.subscribe();
+        controller.start();
+
+        let worker = std::thread::spawn(move || {
+            for _ in 0..200 {
+                controller.on_frame(7);
+            }
+            controller
+        });
+
+        let mut mirror = Memory::new();
+        let mut seen = std::collections::HashSet::new();
+        let mut poll = |seen: &mut std::collections::HashSet<u32>| {
+            while let Ok(update) = updates.try_recv() {
+                update.apply_to(&mut mirror);
+                let word = mirror.read_word(buffer);
+                let long = mirror.read_long(buffer);
+                assert!(word == 0 || word == 0xFFFF, "torn word ${:04X}", word);
+                assert!(long == 0 || long == 0xFFFF_FFFF, "torn long ${:08X}", long);
+                seen.insert(long);
+            }
+        };
+        while !worker.is_finished() {
+            poll(&mut seen);
+        }
+        let controller = worker.join().unwrap();
+        poll(&mut seen);
+
+        assert_eq!(seen.len(), 2);
+        assert_eq!(
+            mirror.read_long(buffer),
+            controller.emulator().memory().read_long(buffer)
+        );
+    }
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(950), "950");
+        assert_eq!(format_count(45_300), "45.3K");
+        assert_eq!(format_count(1_200_000), "1.2M");
+    }
+
+    #[test]
+    fn test_step_explains_branch_from_history() {
+        let mut controller = controller("MOVEQ #0, D0\nBNE SKIP\nSKIP: SIMHALT");
+        assert_eq!(controller.advance(1), None);
+        assert_eq!(controller.explain_last_branch(), None);
+        assert_eq!(controller.advance(1), None);
+        assert_eq!(
+            controller.explain_last_branch().unwrap(),
+            "BNE SKIP — not taken (Z=1 from MOVEQ at line 1)"
+        );
+
+        controller.reset();
+        assert!(controller.history().is_empty());
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut controller = controller(LONG_LOOP);
+        controller.start();
+        controller.on_frame(1000);
+        assert_eq!(controller.history().len(), HISTORY_LEN);
+        assert_eq!(controller.history().last().unwrap().pc, 0x1006, "SUBQ");
+    }
+
+    #[test]
+    fn test_data_execution_pauses_run() {
+        let mut controller = controller(
+            "        SECTION CODE\n        MOVEQ #1, D0\n        SECTION VARS\n        DC.W $4E71, $A000",
+        );
+        controller.start();
+        let reason = controller.on_frame(100).unwrap();
+        assert_eq!(
+            reason,
+            StopReason::DataExecution(DataExecution {
+                pc: 2,
+                section: "VARS".to_string()
+            })
+        );
+        assert_eq!(controller.state(), RunState::Paused);
+        assert!(controller.take_messages()[0].contains("data section VARS at $000002"));
+
+        // Fortsetzen ohne Prüfung führt NOP und SIMHALT in den Daten aus
+        controller.emulator_mut().set_data_execution_check(false);
+        controller.resume();
+        assert_eq!(controller.on_frame(100), Some(StopReason::Halted));
+        assert_eq!(controller.total_steps(), 3);
+    }
+
+    #[test]
+    fn test_register_watch_pauses_run() {
+        let mut controller = controller(
+            "            ORG     $1000
+            MOVEQ   #5, D1
+            MOVEQ   #1, D3
+            MOVEQ   #2, D1
+            MOVEQ   #1, D3
+            MOVEQ   #9, D3
+            SIMHALT
+",
+        );
+        controller.add_register_watch("D3", true).unwrap();
+        controller.add_register_watch("D1 > 3", false).unwrap();
+        assert!(controller.add_register_watch("X1", true).is_err());
+
+        controller.start();
+        let reason = controller.on_frame(100).unwrap();
+        assert_eq!(
+            reason,
+            StopReason::RegisterWatch(RegisterWatchHit {
+                watch: "D3".to_string(),
+                old: 0,
+                new: 1,
+                pause: true,
+            })
+        );
+        assert_eq!(controller.state(), RunState::Paused);
+        assert_eq!(controller.total_steps(), 2);
+        let messages = controller.take_messages();
+        assert_eq!(messages[0], "👁 watch D1 > $3: $00000000 → $00000005");
+        assert!(messages[1].starts_with("👁 watch D3: $00000000 → $00000001"));
+
+        // D1 = 2 erfüllt die Bedingung nicht; MOVEQ #1, D3 ändert D3 nicht
+        controller.resume();
+        let reason = controller.on_frame(100).unwrap();
+        assert!(matches!(reason, StopReason::RegisterWatch(ref hit) if hit.new == 9));
+        assert_eq!(controller.total_steps(), 5);
+        assert_eq!(controller.register_watches()[0].hits, 2);
+        assert_eq!(controller.register_watches()[1].hits, 1);
+
+        controller.resume();
+        assert_eq!(controller.on_frame(100), Some(StopReason::Halted));
+    }
+}
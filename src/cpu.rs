@@ -16,7 +16,337 @@
 
 */
 
-use crate::memory::Memory;
+use crate::bus::IrqRequest;
+use crate::disassembler::disassemble;
+use crate::disassembler::{CONDITIONS, MAX_INSTRUCTION_WORDS};
+use crate::isa::{OperandClass, Size, LEGACY_SIMHALT_OPCODE, SIMHALT_OPCODE};
+use crate::memory::{BusFaultKind, Memory};
+use crate::program::Program;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Standardtakt (68000 mit 8 MHz), mit dem Zyklen in Zeit umgerechnet werden
+/// (`Emulator::set_clock_hz`)
+pub const CPU_CLOCK_HZ: u32 = 8_000_000;
+
+/// Konsolenausgabe von CPU und Assembler (Decode-Trace, Hinweise). `mc68000 dap` schaltet
+/// sie ab, weil stdout dort die Protokollnachrichten trägt.
+static CONSOLE_ECHO: AtomicBool = AtomicBool::new(true);
+
+pub fn set_console_echo(enabled: bool) {
+    CONSOLE_ECHO.store(enabled, Ordering::Relaxed);
+}
+
+pub fn console_echo() -> bool {
+    CONSOLE_ECHO.load(Ordering::Relaxed)
+}
+
+/// `println!`, solange die Konsolenausgabe eingeschaltet ist
+macro_rules! echo {
+    ($($arg:tt)*) => {
+        if $crate::cpu::console_echo() {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use echo;
+
+/// Registersatz des User Mode (für Traces und Vergleiche)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Registers {
+    pub d: [u32; 8],
+    pub a: [u32; 8],
+    pub pc: u32,
+    pub ccr: u8,
+}
+
+/// Ergebnis eines Schritts: was ausgeführt wurde und wie sich die Register geändert haben
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecResult {
+    pub pc: u32,
+    /// Opcode und die folgenden Wörter (für die Disassemblierung)
+    pub words: [u16; MAX_INSTRUCTION_WORDS],
+    pub before: Registers,
+    pub after: Registers,
+    /// Geschätzte Taktzyklen dieses Schritts (siehe `CPU::cycles`)
+    pub cycles: u32,
+    /// Datenzugriffe dieses Schritts; nur gefüllt, wenn `CPU::set_access_trace` aktiv ist
+    pub accesses: Vec<MemAccess>,
+    /// CCR-Bits (XNZVC), die die Instruktion geschrieben hat, auch wenn der Wert gleich blieb
+    pub flags_written: u8,
+    /// Entscheidung eines bedingten Sprungs (Bcc)
+    pub branch: Option<BranchInfo>,
+    /// Bus Error dieses Schritts; die Register stehen wieder wie vor der Instruktion
+    pub bus_fault: Option<BusFault>,
+}
+
+impl ExecResult {
+    pub fn opcode(&self) -> u16 {
+        self.words[0]
+    }
+
+    pub fn next_pc(&self) -> u32 {
+        self.after.pc
+    }
+
+    /// CCR vorher/nachher, falls die Instruktion Flags gesetzt hat
+    pub fn flag_change(&self) -> Option<(u8, u8)> {
+        (self.flags_written != 0).then_some((self.before.ccr, self.after.ccr))
+    }
+}
+
+/// Wie ein Bcc entschieden hat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BranchInfo {
+    /// Bedingungsfeld des Opcodes (0 = BRA ... 15 = BLE)
+    pub condition: u8,
+    pub taken: bool,
+    /// CCR zum Zeitpunkt der Entscheidung
+    pub flags_at_decision: u8,
+    pub target: u32,
+}
+
+impl BranchInfo {
+    pub fn mnemonic(&self) -> &'static str {
+        CONDITIONS[self.condition as usize & 0xF]
+    }
+
+    /// CCR-Bits, von denen die Bedingung abhängt
+    pub fn deciding_flags(&self) -> u8 {
+        const N: u8 = 0x08;
+        const Z: u8 = 0x04;
+        const V: u8 = 0x02;
+        const C: u8 = 0x01;
+        match self.condition {
+            0x2 | 0x3 => C | Z, // HI, LS
+            0x4 | 0x5 => C,     // CC, CS
+            0x6 | 0x7 => Z,     // NE, EQ
+            0x8 | 0x9 => V,     // VC, VS
+            0xA | 0xB => N,     // PL, MI
+            0xC | 0xD => N | V, // GE, LT
+            0xE | 0xF => N | V | Z,
+            _ => 0, // BRA, BSR
+        }
+    }
+}
+
+/// Wie `execute` eine Instruktion verstehen würde (siehe `CPU::decode`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub mnemonic: &'static str,
+    /// Operationsgröße; None bei Instruktionen ohne Größe (Bcc, JMP, DBRA, ...)
+    pub size: Option<Size>,
+    /// Adressierungsart je Operand; Sprungziele (Bcc, DBRA) zählen als Absolute
+    pub operands: Vec<OperandClass>,
+    /// Bytes inkl. Extension Words, um die der PC ohne Sprung weiterrückt
+    pub length: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// Wie die Adresse eines Speicheroperanden berechnet wurde
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EaDescription {
+    Indirect {
+        reg: u8,
+    }, // (An)
+    PostIncrement {
+        reg: u8,
+        updated: u32,
+    }, // (An)+, An danach
+    PreDecrement {
+        reg: u8,
+        updated: u32,
+    }, // -(An), An danach
+    Displacement {
+        reg: u8,
+        base: u32,
+        displacement: i16,
+    },
+    AbsoluteWord,
+    AbsoluteLong,
+}
+
+impl fmt::Display for EaDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EaDescription::Indirect { reg } => write!(f, "(A{})", reg),
+            EaDescription::PostIncrement { reg, .. } => write!(f, "(A{})+", reg),
+            EaDescription::PreDecrement { reg, .. } => write!(f, "-(A{})", reg),
+            EaDescription::Displacement {
+                reg,
+                base,
+                displacement,
+            } => write!(
+                f,
+                "{}(A{}) = ${:06X}{:+}",
+                displacement, reg, base, displacement
+            ),
+            EaDescription::AbsoluteWord => f.write_str("abs.W"),
+            EaDescription::AbsoluteLong => f.write_str("abs.L"),
+        }
+    }
+}
+
+/// Ein Datenzugriff einer Instruktion (ohne Instruktions-Fetch)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemAccess {
+    pub addr: u32,
+    pub size: Size,
+    pub kind: AccessKind,
+    pub value: u32,
+    pub ea_description: EaDescription,
+}
+
+impl fmt::Display for MemAccess {
+    /// z.B. "(A0)+ → read long @ $000800 = $00000005, A0 now $000804"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            AccessKind::Read => "read",
+            AccessKind::Write => "write",
+        };
+        let size = match self.size {
+            Size::Byte => "byte",
+            Size::Word => "word",
+            Size::Long => "long",
+        };
+        write!(
+            f,
+            "{} → {} {} @ ${:06X} = ${:0width$X}",
+            self.ea_description,
+            kind,
+            size,
+            self.addr,
+            self.value,
+            width = 2 * self.size.bytes() as usize
+        )?;
+        match self.ea_description {
+            EaDescription::PostIncrement { reg, updated }
+            | EaDescription::PreDecrement { reg, updated } => {
+                write!(f, ", A{} now ${:06X}", reg, updated)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Vektor des Bus Errors (Adresse $8)
+pub const BUS_ERROR_VECTOR: u32 = 2;
+/// Vektor des Address Errors (Adresse $C), z.B. MOVE.W auf eine ungerade Adresse
+pub const ADDRESS_ERROR_VECTOR: u32 = 3;
+
+/// Ein Datenzugriff, den der Speicher abgelehnt hat (siehe `Memory::fault`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusFault {
+    /// Adresse der auslösenden Instruktion
+    pub pc: u32,
+    /// Opcode und die folgenden Wörter (für die Disassemblierung)
+    pub words: [u16; MAX_INSTRUCTION_WORDS],
+    pub address: u32,
+    pub size: Size,
+    pub kind: AccessKind,
+    pub cause: BusFaultKind,
+    /// Bytes der Instruktion (Opcode und Extension Words), die beim Zugriff gelesen waren
+    pub consumed: u32,
+}
+
+impl BusFault {
+    /// Vektor 3 für ungerade Adressen, sonst 2
+    pub fn vector(&self) -> u32 {
+        match self.cause {
+            BusFaultKind::OddAddress => ADDRESS_ERROR_VECTOR,
+            BusFaultKind::Unmapped | BusFaultKind::ReadOnly => BUS_ERROR_VECTOR,
+        }
+    }
+
+    /// Wie `Display`, die Instruktion aber relativ zu ihrer Section ("CODE+$24")
+    pub fn describe(&self, program: Option<&Program>) -> String {
+        let section = program.and_then(|program| program.section_at(self.pc));
+        let location = match section {
+            Some(section) if section.start == self.pc => section.name.clone(),
+            Some(section) => format!("{}+${:X}", section.name, self.pc - section.start),
+            None => program
+                .and_then(|program| program.symbol_offset(self.pc))
+                .unwrap_or_else(|| format!("${:06X}", self.pc)),
+        };
+        format!("{} at {}", self.access(), location)
+    }
+
+    /// "bus error: write.l to $E10000 (unmapped) by MOVE.L D0, (A2)"
+    fn access(&self) -> String {
+        let (kind, direction) = match self.kind {
+            AccessKind::Read => ("read", "from"),
+            AccessKind::Write => ("write", "to"),
+        };
+        let error = match self.vector() {
+            ADDRESS_ERROR_VECTOR => "address error",
+            _ => "bus error",
+        };
+        format!(
+            "{}: {}.{} {} ${:06X} ({}) by {}",
+            error,
+            kind,
+            self.size.suffix().to_lowercase(),
+            direction,
+            self.address & 0x00FF_FFFF,
+            self.cause.name(),
+            disassemble(&self.words).text
+        )
+    }
+}
+
+impl fmt::Display for BusFault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at ${:06X}", self.access(), self.pc)
+    }
+}
+
+/// Ausnahme, die die CPU anhält, statt die Instruktion stillschweigend zu überspringen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exception {
+    /// Instruktion erkannt, aber (noch) nicht emuliert
+    NotImplemented {
+        pc: u32,
+        opcode: u16,
+        mnemonic: &'static str,
+    },
+    DivideByZero {
+        pc: u32,
+    },
+    /// Bus oder Address Error ohne Handler (Vektor 2 bzw. 3 ist 0)
+    BusError(BusFault),
+}
+
+impl Exception {
+    pub fn pc(&self) -> u32 {
+        match *self {
+            Exception::NotImplemented { pc, .. } | Exception::DivideByZero { pc } => pc,
+            Exception::BusError(fault) => fault.pc,
+        }
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Exception::NotImplemented {
+                pc,
+                opcode,
+                mnemonic,
+            } => write!(
+                f,
+                "{} (${:04X}) at ${:06X} is not implemented",
+                mnemonic, opcode, pc
+            ),
+            Exception::DivideByZero { pc } => write!(f, "division by zero at ${:06X}", pc),
+            Exception::BusError(fault) => write!(f, "{}", fault),
+        }
+    }
+}
 
 pub struct CPU {
     // Section User Mode S.28 Foliensatz 2
@@ -31,8 +361,71 @@ pub struct CPU {
     #[allow(dead_code)]
     vector_base_register: u32,
     status_register: u16,
+
+    halted: bool, // durch SIMHALT oder eine Exception gesetzt
+    exception: Option<Exception>,
+    stopped: bool,        // durch STOP angehalten, wartet auf einen Interrupt
+    legacy_simhalt: bool, // $4E72 als SIMHALT statt STOP ausführen
+    cycles: u64,
+    trace_accesses: bool,
+    accesses: Vec<MemAccess>,    // Zugriffe der laufenden Instruktion
+    io_accesses: Vec<MemAccess>, // Zugriffe auf Geräteregister (immer aufgezeichnet)
+    flags_written: u8,           // von der laufenden Instruktion geschriebene CCR-Bits
+    branch: Option<BranchInfo>,
+    instruction_pc: u32,         // Adresse der laufenden Instruktion
+    bus_fault: Option<BusFault>, // erster abgelehnter Zugriff der laufenden Instruktion
+    alignment_check: bool,       // .W/.L auf ungerade Adressen lösen einen Address Error aus
+    rom_write_faults: bool,      // Schreiben ins ROM löst einen Bus Error aus (sonst ignoriert)
 }
 
+/// Unveränderliche Momentaufnahme des CPU-Zustands für die Anzeige
+///
+/// Reiner Wertetyp ohne Heap-Anteile, damit er pro Frame billig kopiert und über einen
+/// Channel verschickt werden kann.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuSnapshot {
+    pub d: [u32; 8],
+    pub a: [u32; 8],
+    pub pc: u32,
+    pub sr: u16,
+    pub ccr: u8,
+    pub x: bool,
+    pub n: bool,
+    pub z: bool,
+    pub v: bool,
+    pub c: bool,
+    pub halted: bool,
+    pub cycles: u64,
+}
+
+/// Vergleicht, was `CPU::restore` übernimmt: Die Flag-Felder sind nur eine Ansicht von
+/// `ccr`, und im SR zählt nur das System-Byte (die CCR steht in `ccr`)
+impl PartialEq for CpuSnapshot {
+    fn eq(&self, other: &CpuSnapshot) -> bool {
+        self.d == other.d
+            && self.a == other.a
+            && self.pc == other.pc
+            && self.sr & 0xFF00 == other.sr & 0xFF00
+            && self.ccr == other.ccr
+            && self.halted == other.halted
+            && self.cycles == other.cycles
+    }
+}
+
+impl Eq for CpuSnapshot {}
+
+/// Vollständiger Ablaufzustand zum Zurückspulen: Register wie im Snapshot (inklusive
+/// halted) sowie STOP-Zustand und Exception
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuState {
+    pub registers: CpuSnapshot,
+    pub stopped: bool,
+    pub exception: Option<Exception>,
+}
+
+/// Verknüpfung von CCR und Immediate-Byte (ORI/ANDI/EORI to CCR)
+type CcrOperation = fn(u8, u8) -> u8;
+
 // Kernel ROM Mach ich mal nicht
 impl Default for CPU {
     fn default() -> Self {
@@ -50,6 +443,20 @@ impl CPU {
             supervisor_stack_pointer: 0,
             vector_base_register: 0,
             status_register: 0,
+            halted: false,
+            exception: None,
+            stopped: false,
+            legacy_simhalt: false,
+            cycles: 0,
+            trace_accesses: false,
+            accesses: Vec::new(),
+            io_accesses: Vec::new(),
+            flags_written: 0,
+            branch: None,
+            instruction_pc: 0,
+            bus_fault: None,
+            alignment_check: false,
+            rom_write_faults: true,
         }
     }
 
@@ -57,6 +464,91 @@ impl CPU {
         self.program_counter = 0;
         self.condition_code_register = 0;
         self.status_register = 0x2700; // Supervisor Mode, Interrupts enabled
+        self.halted = false;
+        self.exception = None;
+        self.stopped = false;
+        self.cycles = 0;
+    }
+
+    /// true, nachdem SIMHALT ausgeführt oder eine Exception ausgelöst wurde (bis zum
+    /// nächsten Reset)
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// true nach STOP; ohne Interrupt-Quelle bleibt die CPU dort stehen
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Kompatibilität für alte Programme: $4E72 hält die CPU an wie SIMHALT,
+    /// statt als STOP #imm ausgeführt zu werden
+    pub fn set_legacy_simhalt(&mut self, enabled: bool) {
+        self.legacy_simhalt = enabled;
+    }
+
+    /// Wort- und Langwortzugriffe auf ungerade Adressen wie der echte 68000 mit einem
+    /// Address Error (Vektor 3) abbrechen. Aus (Standard) wird einfach zugegriffen.
+    pub fn set_alignment_check(&mut self, enabled: bool) {
+        self.alignment_check = enabled;
+    }
+
+    pub fn alignment_check(&self) -> bool {
+        self.alignment_check
+    }
+
+    /// Schreibzugriffe auf ROM mit einem Bus Error abbrechen (Standard); aus werden sie
+    /// stillschweigend verworfen wie auf Boards ohne Schreibschutz-Logik
+    pub fn set_rom_write_faults(&mut self, enabled: bool) {
+        self.rom_write_faults = enabled;
+    }
+
+    pub fn rom_write_faults(&self) -> bool {
+        self.rom_write_faults
+    }
+
+    /// Datenzugriffe in `ExecResult::accesses` protokollieren. Aus (Standard) wird
+    /// nichts aufgezeichnet und nichts alloziert.
+    pub fn set_access_trace(&mut self, enabled: bool) {
+        self.trace_accesses = enabled;
+    }
+
+    /// Exception, die die CPU angehalten hat; PC zeigt auf die auslösende Instruktion
+    pub fn exception(&self) -> Option<Exception> {
+        self.exception
+    }
+
+    /// Interrupt-Maske I2-I0 aus dem SR
+    pub fn interrupt_mask(&self) -> u8 {
+        ((self.status_register >> 8) & 0x7) as u8
+    }
+
+    /// Nimmt einen Interrupt an, wenn sein Level über der Maske liegt (Level 7 immer).
+    ///
+    /// Legt PC und SR auf den Stack (A7), setzt S und die Maske auf das Level und springt
+    /// über die Vektortabelle. Beendet ein STOP. Zählt 44 Taktzyklen.
+    pub fn interrupt(&mut self, memory: &mut Memory, request: IrqRequest) -> bool {
+        if self.halted || (request.level <= self.interrupt_mask() && request.level < 7) {
+            return false;
+        }
+        let sr = (self.status_register & 0xFF00) | self.condition_code_register as u16;
+        let sp = self.address_registers[7].wrapping_sub(6);
+        memory.write_word(sp, sr);
+        memory.write_long(sp.wrapping_add(2), self.program_counter);
+        self.address_registers[7] = sp;
+
+        // Trace aus, Supervisor an, Maske = Level
+        self.status_register = (sr & 0x78FF) | 0x2000 | ((request.level as u16) << 8);
+        self.program_counter = memory.read_long(request.vector_number() as u32 * 4);
+        self.stopped = false;
+        self.cycles += 44;
+        echo!(
+            "Interrupt level {} -> vector {} at 0x{:06X}",
+            request.level,
+            request.vector_number(),
+            self.program_counter
+        );
+        true
     }
 
     // Getter methods for testing
@@ -80,6 +572,72 @@ impl CPU {
         }
     }
 
+    /// Taktzyklen seit dem letzten Reset.
+    ///
+    /// Näherung, kein exaktes 68000-Timing: 4 Takte pro gelesenem Instruktionswort,
+    /// 4 pro geschriebenem Speicherwort und 10 für jeden genommenen Sprung.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn snapshot(&self) -> CpuSnapshot {
+        let ccr = self.condition_code_register;
+        CpuSnapshot {
+            d: self.data_registers,
+            a: self.address_registers,
+            pc: self.program_counter,
+            sr: self.status_register,
+            ccr,
+            x: ccr & 0x10 != 0,
+            n: ccr & 0x08 != 0,
+            z: ccr & 0x04 != 0,
+            v: ccr & 0x02 != 0,
+            c: ccr & 0x01 != 0,
+            halted: self.halted,
+            cycles: self.cycles,
+        }
+    }
+
+    /// Übernimmt Register, PC, SR und Zykluszähler aus einer Momentaufnahme; die CPU läuft
+    /// danach (halted/stopped und eine Exception werden zurückgesetzt)
+    pub fn restore(&mut self, snapshot: &CpuSnapshot) {
+        self.data_registers = snapshot.d;
+        self.address_registers = snapshot.a;
+        self.supervisor_stack_pointer = snapshot.a[7];
+        self.program_counter = snapshot.pc;
+        self.status_register = snapshot.sr;
+        self.condition_code_register = snapshot.ccr;
+        self.cycles = snapshot.cycles;
+        self.halted = false;
+        self.stopped = false;
+        self.exception = None;
+    }
+
+    pub fn state(&self) -> CpuState {
+        CpuState {
+            registers: self.snapshot(),
+            stopped: self.stopped,
+            exception: self.exception,
+        }
+    }
+
+    /// Stellt einen mit `state` gesicherten Zustand exakt wieder her
+    pub fn restore_state(&mut self, state: &CpuState) {
+        self.restore(&state.registers);
+        self.halted = state.registers.halted;
+        self.stopped = state.stopped;
+        self.exception = state.exception;
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers {
+            d: self.data_registers,
+            a: self.address_registers,
+            pc: self.program_counter,
+            ccr: self.condition_code_register,
+        }
+    }
+
     // Hauptausführungsschleife
     #[allow(dead_code)]
     pub fn run(&mut self, memory: &mut Memory) {
@@ -88,17 +646,144 @@ impl CPU {
         }
     }
 
-    // Fetch-Decode-Execute Zyklus
-    pub fn execute_instruction(&mut self, memory: &mut Memory) {
+    /// Führt die Instruktion an PC aus (Fetch-Decode-Execute Zyklus)
+    ///
+    /// ```
+    /// use mc68000::{Memory, CPU};
+    ///
+    /// let mut memory = Memory::new();
+    /// memory.write_word(0x1000, 0x7405); // MOVEQ #5, D2
+    /// memory.write_word(0x1002, 0xA000); // SIMHALT
+    ///
+    /// let mut cpu = CPU::new();
+    /// cpu.set_pc(0x1000);
+    /// cpu.execute_instruction(&mut memory);
+    /// assert_eq!(cpu.get_data_register(2), 5);
+    /// assert_eq!(cpu.get_pc(), 0x1002);
+    ///
+    /// cpu.execute_instruction(&mut memory);
+    /// assert!(cpu.is_halted());
+    /// ```
+    pub fn execute_instruction(&mut self, memory: &mut Memory) -> ExecResult {
+        let before = self.registers();
+        let mut words = [0u16; MAX_INSTRUCTION_WORDS];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = memory.read_word(self.program_counter.wrapping_add(2 * i as u32) & 0x00FF_FFFE);
+        }
+
+        let writes = memory.write_count();
+        self.accesses.clear();
+        self.io_accesses.clear();
+        self.flags_written = 0;
+        self.branch = None;
+        self.instruction_pc = before.pc;
+        self.execute(memory);
+
+        let bus_fault = self
+            .bus_fault
+            .take()
+            .map(|fault| BusFault { words, ..fault });
+        if let Some(fault) = bus_fault {
+            self.bus_error(memory, before, fault);
+        }
+
+        let cycles = self.estimate_cycles(before.pc, memory.write_count() - writes);
+        self.cycles += cycles as u64;
+
+        ExecResult {
+            pc: before.pc,
+            words,
+            before,
+            after: self.registers(),
+            cycles,
+            accesses: if self.trace_accesses {
+                self.accesses.clone()
+            } else {
+                Vec::new()
+            },
+            flags_written: self.flags_written,
+            branch: self.branch.take(),
+            bus_fault,
+        }
+    }
+
+    /// Die Instruktion wird verworfen: Register und PC wie vor ihr, Zugriffe ab dem Fehler
+    /// fanden nicht statt. Mit Handler (Vektor 2, bei ungerader Adresse 3) wird der
+    /// Gruppe-0-Rahmen gestapelt:
+    ///
+    /// ```text
+    /// SP+0   Wort   Zugriffsart: R/W (Bit 4, 1 = Lesen), I/N (Bit 3), Function Code
+    /// SP+2   Lang   Zugriffsadresse
+    /// SP+6   Wort   Opcode
+    /// SP+8   Wort   SR
+    /// SP+10  Lang   PC der Instruktion
+    /// ```
+    ///
+    /// Ohne Handler hält die CPU mit `Exception::BusError` an.
+    fn bus_error(&mut self, memory: &mut Memory, before: Registers, fault: BusFault) {
+        self.data_registers = before.d;
+        self.address_registers = before.a;
+        self.program_counter = before.pc;
+        self.condition_code_register = before.ccr;
+        self.flags_written = 0;
+        self.branch = None;
+
+        let handler = memory.read_long(fault.vector() * 4);
+        if handler == 0 {
+            self.raise(Exception::BusError(fault));
+            return;
+        }
+        let sr = (self.status_register & 0xFF00) | self.condition_code_register as u16;
+        let supervisor = sr & 0x2000 != 0;
+        let function_code = if supervisor { 5 } else { 1 }; // Daten
+        let read = if fault.kind == AccessKind::Read {
+            0x10
+        } else {
+            0
+        };
+        let sp = self.address_registers[7].wrapping_sub(14);
+        memory.write_word(sp, 0x08 | read | function_code);
+        memory.write_long(sp.wrapping_add(2), fault.address);
+        memory.write_word(sp.wrapping_add(6), fault.words[0]);
+        memory.write_word(sp.wrapping_add(8), sr);
+        memory.write_long(sp.wrapping_add(10), fault.pc);
+        self.address_registers[7] = sp;
+
+        self.status_register = (sr & 0x7FFF) | 0x2000;
+        self.program_counter = handler;
+        self.cycles += 50;
+        echo!("{} -> handler at 0x{:06X}", fault, handler);
+    }
+
+    // Sequentieller Ablauf: PC-Differenz = gelesene Wörter; sonst Sprung
+    fn estimate_cycles(&self, pc: u32, writes: u64) -> u32 {
+        let advanced = self.program_counter.wrapping_sub(pc);
+        let fetch = if (2..=2 * MAX_INSTRUCTION_WORDS as u32).contains(&advanced) {
+            4 * advanced / 2
+        } else if self.halted || self.stopped {
+            4
+        } else {
+            10
+        };
+        fetch + 4 * writes as u32
+    }
+
+    fn execute(&mut self, memory: &mut Memory) {
+        if self.stopped {
+            return; // STOP: keine Instruktionen bis zum nächsten Interrupt
+        }
+
         // FETCH: Instruktion aus Speicher lesen (16-bit Wort)
         let instruction = memory.read_word(self.program_counter);
 
         // DECODE: Instruktion analysieren
         let opcode = (instruction >> 12) & 0xF; // Obere 4 Bits
 
-        println!(
+        echo!(
             "PC: 0x{:06X}, Instruction: 0x{:04X}, Opcode: 0x{:01X}",
-            self.program_counter, instruction, opcode
+            self.program_counter,
+            instruction,
+            opcode
         );
 
         // EXECUTE: Je nach Opcode entsprechende Funktion aufrufen
@@ -111,6 +796,10 @@ impl CPU {
             0x7 => self.moveq_instruction(instruction, memory),
             0x8 => self.or_instruction(instruction, memory),
             0x9 | 0xB => self.sub_cmp_instruction(instruction, memory),
+            0xA if instruction == SIMHALT_OPCODE => {
+                echo!("SIMHALT - Program stopped");
+                self.halted = true; // PC bleibt stehen
+            }
             0xA => self.unimplemented_instruction(instruction),
             0xC => self.and_instruction(instruction, memory),
             0xD => self.add_instruction(instruction, memory),
@@ -120,6 +809,178 @@ impl CPU {
         }
     }
 
+    /// Dekodiert die Instruktion am Anfang von `words` so, wie `execute` sie ausführen
+    /// würde, ohne Register oder Speicher anzufassen. None = die CPU führt sie nicht aus.
+    ///
+    /// Muss zu den Handlern unten passen; tests/encoding_consistency.rs vergleicht beides
+    /// mit Assembler und Disassembler.
+    pub fn decode(&self, words: &[u16]) -> Option<DecodedInstruction> {
+        use OperandClass::*;
+
+        let opcode = *words.first()?;
+        let reg = opcode & 0x7;
+        let mode = (opcode >> 3) & 0x7;
+        let opmode = (opcode >> 6) & 0x7;
+        let size_field = Self::decode_size((opcode >> 6) & 0x3);
+
+        // (Mnemonic, Größe, Operanden, Extension Words)
+        let (mnemonic, size, operands, extension): (&'static str, _, Vec<_>, _) = match opcode >> 12
+        {
+            0x0 if Self::ccr_operation(opcode).is_some() => {
+                let (name, _) = Self::ccr_operation(opcode)?;
+                (name, Some(Size::Byte), vec![Immediate, ConditionCodes], 1)
+            }
+            0x0 if opcode & 0xFF38 == 0x0C00 => {
+                let size = size_field?;
+                let (_, words) = Self::decode_ea(7, 4, size)?;
+                ("CMPI", Some(size), vec![Immediate, DataRegister], words)
+            }
+            0x1..=0x3 => return Self::decode_move(opcode),
+            0x4 => match opcode {
+                0x4E71 => ("NOP", None, vec![], 0),
+                0x4E73 => ("RTE", None, vec![], 0),
+                LEGACY_SIMHALT_OPCODE if self.legacy_simhalt => ("SIMHALT", None, vec![], 0),
+                0x4E72 => ("STOP", None, vec![Immediate], 1),
+                0x4EF8 => ("JMP", None, vec![Absolute], 1),
+                _ if opcode & 0xFFF8 == 0x4ED0 => ("JMP", None, vec![Indirect], 0),
+                _ if opcode & 0xFF00 == 0x4A00 => {
+                    let size = size_field?;
+                    let (class, words) = Self::decode_ea(mode, reg, size)?;
+                    ("TST", Some(size), vec![class], words)
+                }
+                _ => return None,
+            },
+            0x5 if opcode & 0xFFF8 == 0x51C8 => ("DBRA", None, vec![DataRegister, Absolute], 1),
+            0x5 => {
+                let size = size_field?;
+                let dest = match mode {
+                    0 => DataRegister,
+                    // Adressregister haben keinen Byte-Zugriff
+                    1 if size != Size::Byte => AddressRegister,
+                    _ => return None,
+                };
+                let name = if opcode & 0x0100 != 0 { "SUBQ" } else { "ADDQ" };
+                (name, Some(size), vec![Immediate, dest], 0)
+            }
+            0x6 => (
+                CONDITIONS[((opcode >> 8) & 0xF) as usize],
+                None,
+                vec![Absolute],
+                0,
+            ),
+            0x7 => ("MOVEQ", Some(Size::Long), vec![Immediate, DataRegister], 0),
+            0x8 => match size_field {
+                None => {
+                    let name = if opcode & 0x0100 != 0 { "DIVS" } else { "DIVU" };
+                    let (source, words) = Self::decode_ea(mode, reg, Size::Word)?;
+                    (name, Some(Size::Word), vec![source, DataRegister], words)
+                }
+                Some(_) if opmode == 4 && mode <= 1 => return None, // SBCD
+                Some(size) if opmode <= 2 => {
+                    let (source, words) = Self::decode_ea(mode, reg, size)?;
+                    ("OR", Some(size), vec![source, DataRegister], words)
+                }
+                Some(size) => {
+                    let (dest, words) = Self::decode_ea(mode, reg, size)?;
+                    if matches!(dest, DataRegister | AddressRegister | Immediate) {
+                        return None;
+                    }
+                    ("OR", Some(size), vec![DataRegister, dest], words)
+                }
+            },
+            0x9 | 0xD if opmode <= 2 && (mode == 0 || (mode == 1 && opmode != 0)) => {
+                let name = if opcode >> 12 == 0x9 { "SUB" } else { "ADD" };
+                let source = if mode == 1 {
+                    AddressRegister
+                } else {
+                    DataRegister
+                };
+                (name, size_field, vec![source, DataRegister], 0)
+            }
+            0xA if opcode == SIMHALT_OPCODE => ("SIMHALT", None, vec![], 0),
+            0xB => {
+                let (name, size, dest) = match opmode {
+                    0..=2 => ("CMP", size_field?, DataRegister),
+                    3 => ("CMPA", Size::Word, AddressRegister),
+                    7 => ("CMPA", Size::Long, AddressRegister),
+                    _ => return None,
+                };
+                let (source, words) = Self::decode_ea(mode, reg, size)?;
+                (name, Some(size), vec![source, dest], words)
+            }
+            0xC if opmode == 7 && (mode == 0 || (mode == 7 && reg == 4)) => {
+                let (source, words) = Self::decode_ea(mode, reg, Size::Word)?;
+                ("MULS", Some(Size::Word), vec![source, DataRegister], words)
+            }
+            0xE if opcode & 0x0138 == 0x0100 => {
+                ("ASL", Some(size_field?), vec![Immediate, DataRegister], 0)
+            }
+            _ => return None,
+        };
+
+        Some(DecodedInstruction {
+            mnemonic,
+            size,
+            operands,
+            length: 2 + 2 * extension,
+        })
+    }
+
+    // Gegenstück zu `move_instruction`
+    fn decode_move(opcode: u16) -> Option<DecodedInstruction> {
+        let size_bits = (opcode >> 12) & 0x3;
+        let dest_reg = (opcode >> 9) & 0x7;
+        let dest_mode = (opcode >> 6) & 0x7;
+        let src_mode = (opcode >> 3) & 0x7;
+        let src_reg = opcode & 0x7;
+        let size = match size_bits {
+            1 => Size::Byte,
+            3 => Size::Word,
+            _ => Size::Long,
+        };
+
+        let (source, source_words) = Self::decode_ea(src_mode, src_reg, size)?;
+        if size == Size::Byte && source == OperandClass::AddressRegister {
+            return None;
+        }
+        let (mnemonic, dest, dest_words) = if dest_mode == 1 {
+            if size == Size::Byte {
+                return None;
+            }
+            ("MOVEA", OperandClass::AddressRegister, 0)
+        } else {
+            let (dest, words) = Self::decode_ea(dest_mode, dest_reg, size)?;
+            if dest == OperandClass::Immediate {
+                return None;
+            }
+            ("MOVE", dest, words)
+        };
+        Some(DecodedInstruction {
+            mnemonic,
+            size: Some(size),
+            operands: vec![source, dest],
+            length: 2 + 2 * (source_words + dest_words),
+        })
+    }
+
+    /// Adressierungsart und Anzahl Extension Words einer EA, wie `read_ea` sie liest
+    fn decode_ea(mode: u16, reg: u16, size: Size) -> Option<(OperandClass, u32)> {
+        let decoded = match (mode, reg) {
+            (0, _) => (OperandClass::DataRegister, 0),
+            (1, _) => (OperandClass::AddressRegister, 0),
+            (2, _) => (OperandClass::Indirect, 0),
+            (3, _) => (OperandClass::PostIncrement, 0),
+            (4, _) => (OperandClass::PreDecrement, 0),
+            (5, _) => (OperandClass::Displacement, 1),
+            (7, 0) => (OperandClass::Absolute, 1),
+            (7, 1) => (OperandClass::Absolute, 2),
+            (7, 4) if size == Size::Long => (OperandClass::Immediate, 2),
+            (7, 4) => (OperandClass::Immediate, 1),
+            _ => return None,
+        };
+        Some(decoded)
+    }
+
     // Beispiel-Implementierungen für verschiedene Instruktionsgruppen
     fn move_instruction(&mut self, instruction: u16, memory: &mut Memory) {
         let size = (instruction >> 12) & 0x3; // 1=byte, 3=word, 2=long
@@ -128,71 +989,66 @@ impl CPU {
         let src_mode = (instruction >> 3) & 0x7;
         let src_reg = (instruction & 0x7) as usize;
 
-        println!(
+        echo!(
             "MOVE instruction: size={}, dest_reg={}, dest_mode={}, src_mode={}, src_reg={}",
-            size, dest_reg, dest_mode, src_mode, src_reg
+            size,
+            dest_reg,
+            dest_mode,
+            src_mode,
+            src_reg
         );
 
-        // MOVE.L #immediate, Dn: 0010 DDD 111 111 100
-        // size=2 (long), dest_mode=7, src_mode=7, src_reg=4
-        if size == 2 && dest_mode == 7 && src_mode == 7 && src_reg == 4 {
-            self.program_counter += 2;
-            let immediate = memory.read_word(self.program_counter) as u32;
-            self.program_counter += 2;
-            self.data_registers[dest_reg] = immediate;
-            println!("  MOVE.L #0x{:08X}, D{}", immediate, dest_reg);
-            return;
-        }
-
-        // MOVEA.L #immediate, An: 0010 AAA 001 111 100
-        // size=2, dest_mode=1 (for address register), src_mode=7, src_reg=4
-        if size == 2 && dest_mode == 1 && src_mode == 7 && src_reg == 4 {
-            self.program_counter += 2;
-            let immediate = memory.read_word(self.program_counter) as u32;
-            self.program_counter += 2;
-            self.address_registers[dest_reg] = immediate;
-            println!("  MOVEA.L #0x{:08X}, A{}", immediate, dest_reg);
-            return;
-        }
-
-        // MOVE.L (An), Dn: 0010 DDD 010 000 AAA
-        if size == 2 && dest_mode == 0 && src_mode == 2 {
-            let address = self.address_registers[src_reg];
-            let value = memory.read_long(address);
-            self.data_registers[dest_reg] = value;
-            println!(
-                "  MOVE.L (A{}=0x{:04X}), D{} -> 0x{:08X}",
-                src_reg, address, dest_reg, value
-            );
+        // MOVEA.s <ea>, An: 00SS AAA 001 MMM RRR (Word wird vorzeichenerweitert)
+        if dest_mode == 1 && (size == 2 || size == 3) {
+            let size = if size == 2 { Size::Long } else { Size::Word };
             self.program_counter += 2;
-            return;
+            if let Some(value) = self.read_ea(src_mode, src_reg, size, memory) {
+                self.address_registers[dest_reg] = Self::sign_extend(value, size);
+                echo!(
+                    "  MOVEA.{} -> A{} = 0x{:08X}",
+                    size.suffix(),
+                    dest_reg,
+                    self.address_registers[dest_reg]
+                );
+                return;
+            }
+            self.program_counter -= 2;
         }
 
-        // MOVE.L Dn, (An): 0010 AAA 010 000 RRR
-        if size == 2 && dest_mode == 2 && src_mode == 0 {
-            let address = self.address_registers[dest_reg];
-            let value = self.data_registers[src_reg];
-            memory.write_long(address, value);
-            println!(
-                "  MOVE.L D{}, (A{}=0x{:04X}) -> 0x{:08X}",
-                src_reg, dest_reg, address, value
-            );
-            self.program_counter += 2;
+        // MOVE.s <ea>, <ea>: Ziel Dn oder Speicher (Modi 2-5, 7/0, 7/1)
+        let size = match size {
+            1 => Size::Byte,
+            3 => Size::Word,
+            _ => Size::Long,
+        };
+        let writable = matches!(dest_mode, 0 | 2..=5) || (dest_mode == 7 && dest_reg <= 1);
+        // MOVE.B An gibt es nicht (Adressregister haben keinen Byte-Zugriff)
+        let readable = !(size == Size::Byte && src_mode == 1);
+        let start = self.program_counter;
+        self.program_counter += 2;
+        let value = if writable && readable {
+            self.read_ea(src_mode, src_reg, size, memory)
+        } else {
+            None
+        };
+        let Some(value) = value else {
+            self.program_counter = start;
+            self.raise_not_implemented(instruction, "MOVE");
             return;
-        }
+        };
 
-        // Vereinfachtes MOVE D0,D1 (0x3200)
-        if instruction == 0x3200 {
-            self.data_registers[1] = self.data_registers[0];
-            self.update_flags_for_result(self.data_registers[1] as i32);
+        if dest_mode == 0 {
+            self.write_data_register(dest_reg, value, size);
+        } else if let Some((address, ea)) = self.ea_address(dest_mode, dest_reg, size, memory) {
+            self.write_memory(memory, address, size, value, ea);
         }
-
-        self.program_counter += 2;
+        echo!("  MOVE.{} 0x{:X}", size.suffix(), value);
+        self.set_arithmetic_flags(value, size, false, false, false);
     }
 
-    fn addq_subq_instruction(&mut self, instruction: u16, _memory: &mut Memory) {
-        // SUBQ.L #imm, Dn: 0101 DDD 1 SS MMM RRR
-        // ADDQ.L #imm, Dn: 0101 DDD 0 SS MMM RRR
+    fn addq_subq_instruction(&mut self, instruction: u16, memory: &mut Memory) {
+        // SUBQ.s #imm, Dn: 0101 DDD 1 SS MMM RRR
+        // ADDQ.s #imm, Dn: 0101 DDD 0 SS MMM RRR
         // DDD = data (bits 9-11)
         // Bit 8 = 1 for SUBQ, 0 for ADDQ
         // SS = size (bits 6-7)
@@ -201,47 +1057,83 @@ impl CPU {
 
         let data = (instruction >> 9) & 0x7; // Extract bits 9-11
         let is_subq = (instruction & 0x0100) != 0; // Check bit 8
-        let _size = (instruction >> 6) & 0x3; // Extract bits 6-7
-        let _mode = (instruction >> 3) & 0x7; // Extract bits 3-5
+        let mode = (instruction >> 3) & 0x7; // Extract bits 3-5
         let reg = (instruction & 0x7) as usize; // Extract bits 0-2
+        let name = if is_subq { "SUBQ" } else { "ADDQ" };
 
         // Convert 0 to 8 (SUBQ/ADDQ use 0 to represent 8)
-        let immediate = if data == 0 { 8 } else { data as i32 };
+        let immediate = if data == 0 { 8 } else { data as u32 };
+
+        let Some(size) = Self::decode_size((instruction >> 6) & 0x3) else {
+            if instruction & 0xFFF8 == 0x51C8 {
+                self.dbra_instruction(instruction, memory);
+            } else {
+                self.unimplemented_instruction(instruction); // Scc/übrige DBcc
+            }
+            return;
+        };
+
+        match mode {
+            0 => {
+                let old_value = self.data_registers[reg];
+                let result = if is_subq {
+                    self.subtract(immediate, old_value, size, true)
+                } else {
+                    self.add(immediate, old_value, size)
+                };
+                self.write_data_register(reg, result, size);
+
+                echo!(
+                    "{}.{} #{}, D{} -> 0x{:08X}",
+                    name,
+                    size.suffix(),
+                    immediate,
+                    reg,
+                    self.data_registers[reg]
+                );
+            }
+            1 if size != Size::Byte => {
+                // Adressregister: immer 32 Bit, Flags bleiben unverändert
+                let old_value = self.address_registers[reg];
+                self.address_registers[reg] = if is_subq {
+                    old_value.wrapping_sub(immediate)
+                } else {
+                    old_value.wrapping_add(immediate)
+                };
+                echo!("{}.{} #{}, A{}", name, size.suffix(), immediate, reg);
+            }
+            _ => {
+                self.unimplemented_instruction(instruction);
+                return;
+            }
+        }
 
-        if is_subq {
-            // SUBQ
-            let old_value = self.data_registers[reg] as i32;
-            let new_value = old_value - immediate;
-            self.data_registers[reg] = new_value as u32;
+        self.program_counter += 2;
+    }
 
-            println!(
-                "SUBQ.L #{}, D{} -> {} - {} = {}",
-                immediate, reg, old_value, immediate, new_value
-            );
+    // DBRA Dn, disp: 0101 0001 1100 1RRR + 16-Bit-Displacement (relativ zu PC + 2)
+    // Dn.W herunterzählen und springen, solange es nicht -1 erreicht
+    fn dbra_instruction(&mut self, instruction: u16, memory: &mut Memory) {
+        let reg = (instruction & 0x7) as usize;
+        let displacement = memory.read_word(self.program_counter + 2) as i16;
+        let counter = (self.data_registers[reg] as u16).wrapping_sub(1);
+        self.write_data_register(reg, counter as u32, Size::Word);
 
-            self.update_flags_for_result(new_value);
-        } else {
-            // ADDQ
-            let old_value = self.data_registers[reg] as i32;
-            let new_value = old_value + immediate;
-            self.data_registers[reg] = new_value as u32;
-
-            println!(
-                "ADDQ.L #{}, D{} -> {} + {} = {}",
-                immediate, reg, old_value, immediate, new_value
-            );
+        echo!("DBRA D{}, {:+} -> {}", reg, displacement, counter as i16);
 
-            self.update_flags_for_result(new_value);
+        if counter == 0xFFFF {
+            self.program_counter += 4;
+        } else {
+            self.program_counter =
+                (self.program_counter + 2).wrapping_add(displacement as i32 as u32);
         }
-
-        self.program_counter += 2;
     }
 
     fn moveq_instruction(&mut self, instruction: u16, _memory: &mut Memory) {
         let register = (instruction >> 9) & 0x7; // Zielregister (D0-D7)
         let immediate = (instruction & 0xFF) as i8 as i32; // 8-bit signed immediate
 
-        println!("MOVEQ #0x{:02X}, D{}", immediate & 0xFF, register);
+        echo!("MOVEQ #0x{:02X}, D{}", immediate & 0xFF, register);
 
         self.data_registers[register as usize] = immediate as u32;
         self.update_flags_for_result(immediate);
@@ -252,27 +1144,38 @@ impl CPU {
         let condition = (instruction >> 8) & 0xF;
         let displacement = (instruction & 0xFF) as i8;
 
-        println!(
+        echo!(
             "Branch instruction, condition: 0x{:01X}, displacement: {}",
-            condition, displacement
+            condition,
+            displacement
         );
 
-        if self.check_condition(condition) {
-            self.program_counter =
-                ((self.program_counter as i32) + (displacement as i32) + 2) as u32;
+        let target = ((self.program_counter as i32) + (displacement as i32) + 2) as u32;
+        let taken = self.check_condition(condition);
+        self.branch = Some(BranchInfo {
+            condition: condition as u8,
+            taken,
+            flags_at_decision: self.condition_code_register,
+            target,
+        });
+        if taken {
+            self.program_counter = target;
         } else {
             self.program_counter += 2;
         }
     }
 
     fn unimplemented_instruction(&mut self, instruction: u16) {
-        println!("Unimplemented instruction: 0x{:04X}", instruction);
+        echo!("Unimplemented instruction: 0x{:04X}", instruction);
         self.program_counter += 2;
     }
 
     // Hilfsfunktionen
+    /// MOVEQ, MULS: N und Z nach dem Ergebnis, V und C gelöscht, X bleibt
     fn update_flags_for_result(&mut self, result: i32) {
-        // Zero Flag
+        self.flags_written |= 0x0F;
+        self.condition_code_register &= !0x03; // V und C löschen
+                                               // Zero Flag
         if result == 0 {
             self.condition_code_register |= 0x04; // Z-Flag setzen
         } else {
@@ -287,38 +1190,392 @@ impl CPU {
         }
     }
 
+    /// Setzt N, Z, V, C für `dest - source` in der angegebenen Größe (X bleibt unverändert)
+    fn compare(&mut self, source: u32, dest: u32, size: Size) {
+        self.subtract(source, dest, size, false);
+    }
+
+    /// `dest - source` in der angegebenen Größe; setzt N, Z, V, C und bei `set_x` auch X
+    fn subtract(&mut self, source: u32, dest: u32, size: Size, set_x: bool) -> u32 {
+        let mask = Self::size_mask(size);
+        let (source, dest) = (source & mask, dest & mask);
+        let result = dest.wrapping_sub(source) & mask;
+        let overflow = (source ^ dest) & (dest ^ result) & Self::size_msb(size) != 0;
+        self.set_arithmetic_flags(result, size, overflow, source > dest, set_x);
+        result
+    }
+
+    /// `dest + source` in der angegebenen Größe; setzt X, N, Z, V, C
+    fn add(&mut self, source: u32, dest: u32, size: Size) -> u32 {
+        let mask = Self::size_mask(size);
+        let (source, dest) = (source & mask, dest & mask);
+        let sum = source as u64 + dest as u64;
+        let result = sum as u32 & mask;
+        let overflow = !(source ^ dest) & (source ^ result) & Self::size_msb(size) != 0;
+        self.set_arithmetic_flags(result, size, overflow, sum > mask as u64, true);
+        result
+    }
+
+    fn set_arithmetic_flags(
+        &mut self,
+        result: u32,
+        size: Size,
+        overflow: bool,
+        carry: bool,
+        set_x: bool,
+    ) {
+        self.flags_written |= if set_x { 0x1F } else { 0x0F };
+        let mut ccr = self.condition_code_register & 0x10;
+        if result & Self::size_msb(size) != 0 {
+            ccr |= 0x08; // N
+        }
+        if result == 0 {
+            ccr |= 0x04; // Z
+        }
+        if overflow {
+            ccr |= 0x02; // V
+        }
+        if carry {
+            ccr |= 0x01; // C
+            if set_x {
+                ccr |= 0x10; // X
+            }
+        } else if set_x {
+            ccr &= !0x10;
+        }
+        self.condition_code_register = ccr;
+    }
+
+    /// Schreibt nur die unteren Bits von Dn (Byte/Word), der Rest bleibt erhalten
+    fn write_data_register(&mut self, reg: usize, value: u32, size: Size) {
+        let mask = Self::size_mask(size);
+        self.data_registers[reg] = (self.data_registers[reg] & !mask) | (value & mask);
+    }
+
+    /// Liest einen Quelloperanden. Der PC steht hinter dem Opcode und wird über
+    /// Extension Words hinweg weitergesetzt. None = Adressierungsart nicht unterstützt.
+    fn read_ea(&mut self, mode: u16, reg: usize, size: Size, memory: &mut Memory) -> Option<u32> {
+        let value = match mode {
+            0 => self.data_registers[reg],
+            1 => self.address_registers[reg],
+            7 if reg == 4 => self.read_immediate(size, memory),
+            _ => {
+                let (address, ea) = self.ea_address(mode, reg, size, memory)?;
+                self.read_memory(memory, address, size, ea)
+            }
+        };
+        Some(value & Self::size_mask(size))
+    }
+
+    /// Immediate-Daten an PC: .B/.W ein Wort, .L zwei Wörter
+    fn read_immediate(&mut self, size: Size, memory: &Memory) -> u32 {
+        match size {
+            Size::Byte | Size::Word => {
+                let value = memory.read_word(self.program_counter) as u32;
+                self.program_counter += 2;
+                value & Self::size_mask(size)
+            }
+            Size::Long => {
+                let value = memory.read_long(self.program_counter);
+                self.program_counter += 4;
+                value
+            }
+        }
+    }
+
+    /// Adresse eines Speicheroperanden (Modi 2-5, 7/0, 7/1), mit Postinkrement/Predekrement
+    fn ea_address(
+        &mut self,
+        mode: u16,
+        reg: usize,
+        size: Size,
+        memory: &Memory,
+    ) -> Option<(u32, EaDescription)> {
+        let register = reg as u8;
+        let result = match mode {
+            2 => (
+                self.address_registers[reg],
+                EaDescription::Indirect { reg: register },
+            ),
+            3 => {
+                let address = self.address_registers[reg];
+                self.address_registers[reg] = address.wrapping_add(size.bytes());
+                let updated = self.address_registers[reg];
+                (
+                    address,
+                    EaDescription::PostIncrement {
+                        reg: register,
+                        updated,
+                    },
+                )
+            }
+            4 => {
+                let address = self.address_registers[reg].wrapping_sub(size.bytes());
+                self.address_registers[reg] = address;
+                (
+                    address,
+                    EaDescription::PreDecrement {
+                        reg: register,
+                        updated: address,
+                    },
+                )
+            }
+            5 => {
+                let displacement = memory.read_word(self.program_counter) as i16;
+                self.program_counter += 2;
+                let base = self.address_registers[reg];
+                (
+                    base.wrapping_add(displacement as i32 as u32),
+                    EaDescription::Displacement {
+                        reg: register,
+                        base,
+                        displacement,
+                    },
+                )
+            }
+            7 if reg == 0 => {
+                let address = memory.read_word(self.program_counter) as i16 as i32 as u32;
+                self.program_counter += 2;
+                (address, EaDescription::AbsoluteWord)
+            }
+            7 if reg == 1 => {
+                let address = memory.read_long(self.program_counter);
+                self.program_counter += 4;
+                (address, EaDescription::AbsoluteLong)
+            }
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Zugriffe der letzten Instruktion auf Geräteregister (siehe `Memory::map_io`)
+    pub fn take_io_accesses(&mut self) -> Vec<MemAccess> {
+        std::mem::take(&mut self.io_accesses)
+    }
+
+    /// Datenzugriff über eine berechnete Adresse; wird bei aktivem Access-Trace protokolliert
+    fn read_memory(&mut self, memory: &Memory, address: u32, size: Size, ea: EaDescription) -> u32 {
+        if !self.check_access(memory, address, size, AccessKind::Read) {
+            return 0;
+        }
+        let value = Self::read_sized(memory, address, size);
+        self.record_access(address, size, AccessKind::Read, value, ea);
+        if memory.is_io(address) {
+            self.record_io(address, size, AccessKind::Read, value, ea);
+        }
+        value
+    }
+
+    fn write_memory(
+        &mut self,
+        memory: &mut Memory,
+        address: u32,
+        size: Size,
+        value: u32,
+        ea: EaDescription,
+    ) {
+        if !self.check_access(memory, address, size, AccessKind::Write) {
+            return;
+        }
+        Self::write_sized(memory, address, size, value);
+        self.record_access(address, size, AccessKind::Write, value, ea);
+        if memory.is_io(address) {
+            self.record_io(address, size, AccessKind::Write, value, ea);
+        }
+    }
+
+    /// false, wenn der Zugriff einen Bus Error auslöst oder schon einer aufgetreten ist;
+    /// der erste wird für `execute_instruction` festgehalten
+    fn check_access(
+        &mut self,
+        memory: &Memory,
+        address: u32,
+        size: Size,
+        kind: AccessKind,
+    ) -> bool {
+        if self.bus_fault.is_some() {
+            return false;
+        }
+        let cause = if self.alignment_check && size != Size::Byte && address & 1 != 0 {
+            Some(BusFaultKind::OddAddress)
+        } else {
+            memory.fault(address, size.bytes(), kind == AccessKind::Write)
+        };
+        let Some(cause) = cause else {
+            return true;
+        };
+        if cause == BusFaultKind::ReadOnly && !self.rom_write_faults {
+            return false; // Schreibzugriff verpufft
+        }
+        self.bus_fault = Some(BusFault {
+            pc: self.instruction_pc,
+            words: [0; MAX_INSTRUCTION_WORDS],
+            address,
+            size,
+            kind,
+            cause,
+            consumed: self.program_counter.wrapping_sub(self.instruction_pc),
+        });
+        false
+    }
+
+    fn record_io(
+        &mut self,
+        addr: u32,
+        size: Size,
+        kind: AccessKind,
+        value: u32,
+        ea_description: EaDescription,
+    ) {
+        self.io_accesses.push(MemAccess {
+            addr,
+            size,
+            kind,
+            value: value & Self::size_mask(size),
+            ea_description,
+        });
+    }
+
+    fn record_access(
+        &mut self,
+        addr: u32,
+        size: Size,
+        kind: AccessKind,
+        value: u32,
+        ea_description: EaDescription,
+    ) {
+        if self.trace_accesses {
+            self.accesses.push(MemAccess {
+                addr,
+                size,
+                kind,
+                value: value & Self::size_mask(size),
+                ea_description,
+            });
+        }
+    }
+
+    fn write_sized(memory: &mut Memory, address: u32, size: Size, value: u32) {
+        match size {
+            Size::Byte => memory.write_byte(address, value as u8),
+            Size::Word => memory.write_word(address, value as u16),
+            Size::Long => memory.write_long(address, value),
+        }
+    }
+
+    fn read_sized(memory: &Memory, address: u32, size: Size) -> u32 {
+        match size {
+            Size::Byte => memory.read_byte(address) as u32,
+            Size::Word => memory.read_word(address) as u32,
+            Size::Long => memory.read_long(address),
+        }
+    }
+
+    /// Größenfeld 00/01/10 (CMP, CMPI, TST, ...)
+    fn decode_size(bits: u16) -> Option<Size> {
+        match bits {
+            0 => Some(Size::Byte),
+            1 => Some(Size::Word),
+            2 => Some(Size::Long),
+            _ => None,
+        }
+    }
+
+    fn size_mask(size: Size) -> u32 {
+        match size {
+            Size::Byte => 0xFF,
+            Size::Word => 0xFFFF,
+            Size::Long => 0xFFFF_FFFF,
+        }
+    }
+
+    fn size_msb(size: Size) -> u32 {
+        1 << (size.bytes() * 8 - 1)
+    }
+
+    fn sign_extend(value: u32, size: Size) -> u32 {
+        match size {
+            Size::Byte => value as u8 as i8 as i32 as u32,
+            Size::Word => value as u16 as i16 as i32 as u32,
+            Size::Long => value,
+        }
+    }
+
     fn check_condition(&self, condition: u16) -> bool {
+        let ccr = self.condition_code_register;
+        let c = ccr & 0x01 != 0;
+        let v = ccr & 0x02 != 0;
+        let z = ccr & 0x04 != 0;
+        let n = ccr & 0x08 != 0;
+
         match condition {
-            0x0 => true,                                       // BRA - Always branch
-            0x1 => false, // BSR - Branch to subroutine (vereinfacht)
-            0x2 => (self.condition_code_register & 0x01) != 0, // BHI - Branch if higher
-            0x3 => (self.condition_code_register & 0x01) == 0, // BLS - Branch if lower or same
-            0x4 => (self.condition_code_register & 0x01) == 0, // BCC - Branch if carry clear
-            0x5 => (self.condition_code_register & 0x01) != 0, // BCS - Branch if carry set
-            0x6 => (self.condition_code_register & 0x04) == 0, // BNE - Branch if not equal
-            0x7 => (self.condition_code_register & 0x04) != 0, // BEQ - Branch if equal
+            0x0 => true,         // BRA - Always branch
+            0x1 => false,        // BSR - Branch to subroutine (vereinfacht)
+            0x2 => !c && !z,     // BHI - Branch if higher
+            0x3 => c || z,       // BLS - Branch if lower or same
+            0x4 => !c,           // BCC - Branch if carry clear
+            0x5 => c,            // BCS - Branch if carry set
+            0x6 => !z,           // BNE - Branch if not equal
+            0x7 => z,            // BEQ - Branch if equal
+            0x8 => !v,           // BVC - Branch if overflow clear
+            0x9 => v,            // BVS - Branch if overflow set
+            0xA => !n,           // BPL - Branch if plus
+            0xB => n,            // BMI - Branch if minus
+            0xC => n == v,       // BGE - Branch if greater or equal
+            0xD => n != v,       // BLT - Branch if less than
+            0xE => !z && n == v, // BGT - Branch if greater than
+            0xF => z || n != v,  // BLE - Branch if less or equal
             _ => false,
         }
     }
 
+    /// ORI/ANDI/EORI #imm, CCR: 0000 0000/0010/1010 0011 1100
+    fn ccr_operation(instruction: u16) -> Option<(&'static str, CcrOperation)> {
+        match instruction {
+            0x003C => Some(("ORI", |ccr, mask| ccr | mask)),
+            0x023C => Some(("ANDI", |ccr, mask| ccr & mask)),
+            0x0A3C => Some(("EORI", |ccr, mask| ccr ^ mask)),
+            _ => None,
+        }
+    }
+
     // Platzhalter für weitere Instruktionsgruppen
     fn miscellaneous_instruction(&mut self, instruction: u16, memory: &mut Memory) {
-        // Check for CMPI.L #imm, Dn: 0000 1100 1000 0RRR
-        if (instruction & 0xFFF8) == 0x0C80 {
-            let dest_reg = (instruction & 0x7) as usize;
-            self.program_counter += 2;
-            let immediate = memory.read_word(self.program_counter) as i32;
-            self.program_counter += 2;
+        // ORI/ANDI/EORI #imm, CCR: ein Extension Word, davon zählt das untere Byte.
+        // Nur das CCR ändert sich, das Systembyte des SR bleibt; nicht privilegiert.
+        if let Some((name, operation)) = Self::ccr_operation(instruction) {
+            let mask = memory.read_word(self.program_counter + 2) as u8;
+            echo!("{} #0x{:02X}, CCR", name, mask);
+            self.condition_code_register = operation(self.condition_code_register, mask) & 0x1F;
+            self.flags_written |= 0x1F;
+            self.program_counter += 4;
+            return;
+        }
 
-            let dest_value = self.data_registers[dest_reg] as i32;
-            let result = dest_value - immediate;
+        // CMPI.s #imm, Dn: 0000 1100 SS 000 RRR
+        // .B/.W: ein Extension Word, .L: zwei
+        if (instruction & 0xFF38) == 0x0C00 {
+            if let Some(size) = Self::decode_size((instruction >> 6) & 0x3) {
+                let dest_reg = (instruction & 0x7) as usize;
+                self.program_counter += 2;
+                let immediate = self.read_immediate(size, memory);
 
-            println!(
-                "CMPI.L #0x{:04X}, D{} -> {} - {} = {}",
-                immediate, dest_reg, dest_value, immediate, result
-            );
+                echo!("CMPI.{} #0x{:X}, D{}", size.suffix(), immediate, dest_reg);
 
-            self.update_flags_for_result(result);
+                self.compare(immediate, self.data_registers[dest_reg], size);
+                return;
+            }
+        }
+
+        // JMP (An): 0100 1110 1101 0AAA
+        if (instruction & 0xFFF8) == 0x4ED0 {
+            let target_address = self.address_registers[(instruction & 0x7) as usize];
+            echo!(
+                "JMP (A{}) to address: 0x{:06X}",
+                instruction & 0x7,
+                target_address
+            );
+            self.program_counter = target_address;
             return;
         }
 
@@ -327,59 +1584,214 @@ impl CPU {
             // JMP (xxx).W - Jump to absolute word address
             // The target address follows as the next word
             let target_address = memory.read_word(self.program_counter + 2) as u32;
-            println!("JMP to address: 0x{:06X}", target_address);
+            echo!("JMP to address: 0x{:06X}", target_address);
             self.program_counter = target_address;
         } else if instruction == 0x4E71 {
             // NOP
-            println!("NOP");
+            echo!("NOP");
             self.program_counter += 2;
+        } else if instruction == LEGACY_SIMHALT_OPCODE && self.legacy_simhalt {
+            // Alte SIMHALT-Kodierung (Kompatibilitätsmodus)
+            echo!("SIMHALT (legacy) - Program stopped");
+            self.halted = true;
+        } else if instruction == 0x4E73 {
+            // RTE: SR und PC vom Stack holen
+            let sp = self.address_registers[7];
+            let sr = memory.read_word(sp);
+            self.program_counter = memory.read_long(sp.wrapping_add(2));
+            self.address_registers[7] = sp.wrapping_add(6);
+            self.set_sr(sr);
+            echo!("RTE to 0x{:06X}", self.program_counter);
         } else if instruction == 0x4E72 {
-            // SIMHALT - Custom halt instruction
-            println!("SIMHALT - Program stopped");
-            // Don't increment PC - this signals the end
-            // The GUI should detect this by checking if PC hasn't changed
+            // STOP #imm: SR laden und auf einen Interrupt warten
+            let value = memory.read_word(self.program_counter + 2);
+            echo!("STOP #0x{:04X}", value);
+            self.set_sr(value);
+            self.program_counter += 4;
+            self.stopped = true;
+        } else if let (0x4A00, Some(size)) = (
+            instruction & 0xFF00,
+            Self::decode_size((instruction >> 6) & 0x3),
+        ) {
+            // TST.s <ea>: 0100 1010 SS MMM RRR
+            let start = self.program_counter;
+            self.program_counter += 2;
+            let mode = (instruction >> 3) & 0x7;
+            let Some(value) = self.read_ea(mode, (instruction & 0x7) as usize, size, memory) else {
+                self.program_counter = start;
+                self.raise_not_implemented(instruction, "TST");
+                return;
+            };
+            echo!("TST.{} 0x{:X}", size.suffix(), value);
+            self.set_arithmetic_flags(value, size, false, false, false);
         } else {
-            println!("Miscellaneous instruction: 0x{:04X}", instruction);
+            echo!("Miscellaneous instruction: 0x{:04X}", instruction);
             self.program_counter += 2;
         }
     }
 
-    fn or_instruction(&mut self, instruction: u16, _memory: &mut Memory) {
-        println!("OR instruction: 0x{:04X}", instruction);
-        self.program_counter += 2;
+    // Gruppe 0x8:
+    // OR <ea>, Dn:  1000 DDD 0SS MMM RRR
+    // OR Dn, <ea>:  1000 DDD 1SS MMM RRR (nur Speicherziele)
+    // DIVU.W:       1000 DDD 011 MMM RRR
+    // DIVS.W:       1000 DDD 111 MMM RRR
+    // SBCD:         1000 XXX 100 00M YYY
+    fn or_instruction(&mut self, instruction: u16, memory: &mut Memory) {
+        let reg = ((instruction >> 9) & 0x7) as usize;
+        let opmode = (instruction >> 6) & 0x7;
+        let mode = (instruction >> 3) & 0x7;
+        let ea_reg = (instruction & 0x7) as usize;
+
+        // Größenfeld 11 = DIVU (opmode 011) / DIVS (opmode 111)
+        let Some(size) = Self::decode_size(opmode & 0x3) else {
+            self.divide_instruction(instruction, memory);
+            return;
+        };
+
+        match opmode {
+            4 if mode <= 1 => self.raise_not_implemented(instruction, "SBCD"),
+            0..=2 => {
+                self.program_counter += 2;
+                let Some(source) = self.read_ea(mode, ea_reg, size, memory) else {
+                    self.program_counter -= 2;
+                    self.raise_not_implemented(instruction, "OR");
+                    return;
+                };
+                let result = (self.data_registers[reg] | source) & Self::size_mask(size);
+                echo!("OR.{} 0x{:X}, D{}", size.suffix(), source, reg);
+                self.write_data_register(reg, result, size);
+                self.set_arithmetic_flags(result, size, false, false, false);
+            }
+            _ => {
+                let start = self.program_counter;
+                self.program_counter += 2;
+                let address = match mode {
+                    0 | 1 => None, // PACK/UNPK erst ab 68020
+                    _ => self.ea_address(mode, ea_reg, size, memory),
+                };
+                let Some((address, ea)) = address else {
+                    self.program_counter = start;
+                    self.raise_not_implemented(instruction, "OR");
+                    return;
+                };
+                let result = self.read_memory(memory, address, size, ea) | self.data_registers[reg];
+                let result = result & Self::size_mask(size);
+                echo!("OR.{} D{}, 0x{:06X}", size.suffix(), reg, address);
+                self.write_memory(memory, address, size, result, ea);
+                self.set_arithmetic_flags(result, size, false, false, false);
+            }
+        }
     }
 
-    fn sub_cmp_instruction(&mut self, instruction: u16, _memory: &mut Memory) {
-        let opcode_high = (instruction >> 12) & 0xF;
-
-        if opcode_high == 0xB {
-            // CMP instruction: 1011 DDD SSS MMM RRR
-            let dest_reg = ((instruction >> 9) & 0x7) as usize;
-            let source_reg = (instruction & 0x7) as usize;
-
-            println!("CMP.W D{}, D{}", source_reg, dest_reg);
+    // DIVU/DIVS.W <ea>, Dn: Dn(32) / <ea>(16) -> Rest:Quotient
+    fn divide_instruction(&mut self, instruction: u16, memory: &mut Memory) {
+        let reg = ((instruction >> 9) & 0x7) as usize;
+        let signed = instruction & 0x0100 != 0;
+        let mode = (instruction >> 3) & 0x7;
+        let ea_reg = (instruction & 0x7) as usize;
+        let name = if signed { "DIVS" } else { "DIVU" };
 
-            let source_value = self.data_registers[source_reg] as i32;
-            let dest_value = self.data_registers[dest_reg] as i32;
-            let result = dest_value - source_value; // CMP subtrahiert aber speichert nicht
+        let start = self.program_counter;
+        self.program_counter += 2;
+        let Some(divisor) = self.read_ea(mode, ea_reg, Size::Word, memory) else {
+            self.program_counter = start;
+            self.raise_not_implemented(instruction, name);
+            return;
+        };
+        if divisor == 0 {
+            self.program_counter = start;
+            self.raise(Exception::DivideByZero { pc: start });
+            return;
+        }
 
-            self.update_flags_for_result(result);
+        let dividend = self.data_registers[reg];
+        let result = if signed {
+            let divisor = divisor as u16 as i16 as i32;
+            let dividend = dividend as i32;
+            let quotient = dividend.wrapping_div(divisor);
+            i16::try_from(quotient)
+                .ok()
+                .map(|q| (dividend.wrapping_rem(divisor) as u32, q as u16 as u32))
         } else {
-            // SUB instruction
-            let dest_reg = ((instruction >> 9) & 0x7) as usize;
-            let source_reg = (instruction & 0x7) as usize;
+            let quotient = dividend / divisor;
+            (quotient <= 0xFFFF).then_some((dividend % divisor, quotient))
+        };
+        echo!("{}.W 0x{:X}, D{}", name, divisor, reg);
+
+        match result {
+            Some((remainder, quotient)) => {
+                self.data_registers[reg] = (remainder << 16) | quotient;
+                self.set_arithmetic_flags(quotient, Size::Word, false, false, false);
+            }
+            None => {
+                // Überlauf: Dn bleibt unverändert, V gesetzt
+                self.condition_code_register = (self.condition_code_register & 0x10) | 0x02;
+                self.flags_written |= 0x0F;
+            }
+        }
+    }
 
-            println!("SUB.W D{}, D{}", source_reg, dest_reg);
+    /// Hält die CPU an der aktuellen Instruktion an
+    fn raise(&mut self, exception: Exception) {
+        echo!("Exception: {}", exception);
+        self.exception = Some(exception);
+        self.halted = true;
+    }
 
-            let source_value = self.data_registers[source_reg] as i32;
-            let dest_value = self.data_registers[dest_reg] as i32;
-            let result = dest_value - source_value;
+    fn raise_not_implemented(&mut self, instruction: u16, mnemonic: &'static str) {
+        self.raise(Exception::NotImplemented {
+            pc: self.program_counter,
+            opcode: instruction,
+            mnemonic,
+        });
+    }
 
-            self.data_registers[dest_reg] = result as u32;
-            self.update_flags_for_result(result);
+    fn sub_cmp_instruction(&mut self, instruction: u16, memory: &mut Memory) {
+        let opcode_high = (instruction >> 12) & 0xF;
+
+        if opcode_high == 0xB {
+            self.cmp_instruction(instruction, memory);
+            return;
         }
 
+        self.add_sub_instruction(instruction);
+    }
+
+    // CMP <ea>, Dn:  1011 DDD 0SS MMM RRR
+    // CMPA <ea>, An: 1011 AAA S11 MMM RRR
+    fn cmp_instruction(&mut self, instruction: u16, memory: &mut Memory) {
+        let reg = ((instruction >> 9) & 0x7) as usize;
+        let opmode = (instruction >> 6) & 0x7;
+        let mode = (instruction >> 3) & 0x7;
+        let ea_reg = (instruction & 0x7) as usize;
+
+        let (size, address_compare) = match opmode {
+            0..=2 => (Self::decode_size(opmode), false),
+            3 => (Some(Size::Word), true),
+            7 => (Some(Size::Long), true),
+            _ => (None, false), // EOR
+        };
+        let Some(size) = size else {
+            self.unimplemented_instruction(instruction);
+            return;
+        };
+
         self.program_counter += 2;
+        let Some(source) = self.read_ea(mode, ea_reg, size, memory) else {
+            self.program_counter -= 2;
+            self.unimplemented_instruction(instruction);
+            return;
+        };
+
+        if address_compare {
+            // CMPA vergleicht immer 32 Bit, Word-Quellen werden vorzeichenerweitert
+            let source = Self::sign_extend(source, size);
+            echo!("CMPA.{} 0x{:X}, A{}", size.suffix(), source, reg);
+            self.compare(source, self.address_registers[reg], Size::Long);
+        } else {
+            echo!("CMP.{} 0x{:X}, D{}", size.suffix(), source, reg);
+            self.compare(source, self.data_registers[reg], size);
+        }
     }
 
     fn and_instruction(&mut self, instruction: u16, memory: &mut Memory) {
@@ -400,9 +1812,13 @@ impl CPU {
             let dest_value = self.data_registers[dest_reg] as i16;
             let result = (dest_value as i32) * (immediate as i32);
 
-            println!(
+            echo!(
                 "MULS.W #{}, D{} -> {} * {} = {}",
-                immediate, dest_reg, dest_value, immediate, result
+                immediate,
+                dest_reg,
+                dest_value,
+                immediate,
+                result
             );
 
             self.data_registers[dest_reg] = result as u32;
@@ -415,53 +1831,125 @@ impl CPU {
             let dest_value = self.data_registers[dest_reg] as i16;
             let result = (source_value as i32) * (dest_value as i32);
 
-            println!(
+            echo!(
                 "MULS.W D{}, D{} -> {} * {} = {}",
-                src_reg, dest_reg, source_value, dest_value, result
+                src_reg,
+                dest_reg,
+                source_value,
+                dest_value,
+                result
             );
 
             self.data_registers[dest_reg] = result as u32;
             self.update_flags_for_result(result);
             self.program_counter += 2;
         } else {
-            println!("AND instruction: 0x{:04X}", instruction);
+            echo!("AND instruction: 0x{:04X}", instruction);
             self.program_counter += 2;
         }
     }
 
     fn add_instruction(&mut self, instruction: u16, _memory: &mut Memory) {
-        // ADD.W Dx,Dy: 1101 DDD 001 000 SSS
+        self.add_sub_instruction(instruction);
+    }
+
+    // ADD.s <ea>, Dy: 1101 DDD 0SS MMM RRR
+    // SUB.s <ea>, Dy: 1001 DDD 0SS MMM RRR
+    // Quelle Dn oder An (An nicht bei .B); andere Quellen und Speicherziele
+    // (ADDA/SUBA, ADDX/SUBX) sind nicht emuliert
+    fn add_sub_instruction(&mut self, instruction: u16) {
+        let is_sub = instruction >> 12 == 0x9;
         let dest_reg = ((instruction >> 9) & 0x7) as usize;
+        let opmode = (instruction >> 6) & 0x7;
+        let mode = (instruction >> 3) & 0x7;
         let source_reg = (instruction & 0x7) as usize;
 
-        println!("ADD.W D{}, D{}", source_reg, dest_reg);
-
-        let source_value = self.data_registers[source_reg] as i32;
-        let dest_value = self.data_registers[dest_reg] as i32;
-        let result = dest_value + source_value;
+        let size = Self::decode_size(opmode)
+            .filter(|&size| opmode <= 2 && (mode == 0 || (mode == 1 && size != Size::Byte)));
+        let Some(size) = size else {
+            self.unimplemented_instruction(instruction);
+            return;
+        };
 
-        self.data_registers[dest_reg] = result as u32;
-        self.update_flags_for_result(result);
+        let (source, source_name) = if mode == 1 {
+            (self.address_registers[source_reg], 'A')
+        } else {
+            (self.data_registers[source_reg], 'D')
+        };
+        let dest = self.data_registers[dest_reg];
+        let result = if is_sub {
+            self.subtract(source, dest, size, true)
+        } else {
+            self.add(source, dest, size)
+        };
+        self.write_data_register(dest_reg, result, size);
+
+        echo!(
+            "{}.{} {}{}, D{} -> 0x{:08X}",
+            if is_sub { "SUB" } else { "ADD" },
+            size.suffix(),
+            source_name,
+            source_reg,
+            dest_reg,
+            self.data_registers[dest_reg]
+        );
         self.program_counter += 2;
     }
 
+    // ASL.s #count, Dn: 1110 CCC 1SS 000 RRR (Count 0 = 8)
+    // V wird gesetzt, wenn sich das oberste Bit während des Schiebens irgendwann ändert
     fn shift_instruction(&mut self, instruction: u16, _memory: &mut Memory) {
-        println!("Shift instruction: 0x{:04X}", instruction);
+        let Some(size) =
+            Self::decode_size((instruction >> 6) & 0x3).filter(|_| instruction & 0x0138 == 0x0100)
+        else {
+            echo!("Shift instruction: 0x{:04X}", instruction);
+            self.program_counter += 2;
+            return;
+        };
+
+        let reg = (instruction & 0x7) as usize;
+        let count = match (instruction >> 9) & 0x7 {
+            0 => 8,
+            count => count,
+        };
+        let mask = Self::size_mask(size);
+        let msb = Self::size_msb(size);
+        let mut value = self.data_registers[reg] & mask;
+        let mut carry = false;
+        let mut overflow = false;
+        for _ in 0..count {
+            carry = value & msb != 0;
+            value = (value << 1) & mask;
+            overflow |= (value & msb != 0) != carry;
+        }
+        self.write_data_register(reg, value, size);
+        self.set_arithmetic_flags(value, size, overflow, carry, true);
+
+        echo!(
+            "ASL.{} #{}, D{} -> 0x{:08X}",
+            size.suffix(),
+            count,
+            reg,
+            self.data_registers[reg]
+        );
         self.program_counter += 2;
     }
 
     // Debug-Funktionen
     #[allow(dead_code)]
     pub fn print_registers(&self) {
-        println!("=== CPU State ===");
+        echo!("=== CPU State ===");
         for i in 0..8 {
-            println!(
+            echo!(
                 "D{}: 0x{:08X}  A{}: 0x{:08X}",
-                i, self.data_registers[i], i, self.address_registers[i]
+                i,
+                self.data_registers[i],
+                i,
+                self.address_registers[i]
             );
         }
-        println!("PC: 0x{:08X}", self.program_counter);
-        println!(
+        echo!("PC: 0x{:08X}", self.program_counter);
+        echo!(
             "CCR: 0x{:02X} (N:{} Z:{} V:{} C:{})",
             self.condition_code_register,
             (self.condition_code_register >> 3) & 1,
@@ -469,7 +1957,13 @@ impl CPU {
             (self.condition_code_register >> 1) & 1,
             self.condition_code_register & 1
         );
-        println!("SR: 0x{:04X}", self.status_register);
+        echo!("SR: 0x{:04X}", self.status_register);
+    }
+
+    /// Stackpointer beim Start: A7 und SSP (die CPU läuft im Supervisor Mode)
+    pub fn set_stack_pointer(&mut self, address: u32) {
+        self.address_registers[7] = address;
+        self.supervisor_stack_pointer = address;
     }
 
     pub fn set_pc(&mut self, address: u32) {
@@ -483,4 +1977,417 @@ impl CPU {
     pub fn get_sr(&self) -> u16 {
         self.status_register
     }
+
+    /// SR setzen (Systembyte und CCR), z.B. um Interrupts vor dem Start freizugeben
+    pub fn set_sr(&mut self, value: u16) {
+        self.status_register = value;
+        self.condition_code_register = (value & 0x1F) as u8;
+        self.flags_written |= 0x1F;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_matches_getters() {
+        let mut memory = Memory::new();
+        memory.write_word(0x1000, 0x70FF); // MOVEQ #-1, D0
+        memory.write_word(0x1002, 0xA000); // SIMHALT
+        let mut cpu = CPU::new();
+        cpu.set_pc(0x1000);
+        cpu.execute_instruction(&mut memory);
+        cpu.execute_instruction(&mut memory);
+
+        let snapshot = cpu.snapshot();
+        for i in 0..8 {
+            assert_eq!(snapshot.d[i], cpu.get_data_register(i));
+            assert_eq!(snapshot.a[i], cpu.get_address_register(i));
+        }
+        assert_eq!(snapshot.pc, cpu.get_pc());
+        assert_eq!(snapshot.sr, cpu.get_sr());
+        assert_eq!(snapshot.ccr, cpu.get_ccr());
+        assert!(snapshot.n && !snapshot.z && !snapshot.v && !snapshot.c);
+        assert_eq!(snapshot.halted, cpu.is_halted());
+        assert_eq!(snapshot.cycles, cpu.cycles());
+        assert_eq!(cpu.cycles(), 8);
+    }
+
+    /// Legt `words` ab $1000 ab und führt eine Instruktion aus
+    fn execute(cpu: &mut CPU, memory: &mut Memory, words: &[u16]) {
+        for (i, word) in words.iter().enumerate() {
+            memory.write_word(0x1000 + 2 * i as u32, *word);
+        }
+        cpu.set_pc(0x1000);
+        cpu.execute_instruction(memory);
+    }
+
+    #[test]
+    fn test_or_word_sets_logic_flags() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        cpu.data_registers[0] = 0x1234_0F00;
+        cpu.data_registers[1] = 0xFFFF_80F0;
+        cpu.condition_code_register = 0x10 | 0x02 | 0x01; // X, V, C
+
+        execute(&mut cpu, &mut memory, &[0x8041]); // OR.W D1, D0
+        assert_eq!(cpu.get_data_register(0), 0x1234_8FF0);
+        assert_eq!(cpu.get_ccr(), 0x10 | 0x08); // X bleibt, N gesetzt, V/C gelöscht
+        assert_eq!(cpu.get_pc(), 0x1002);
+
+        cpu.data_registers[0] = 0xFFFF_0000;
+        cpu.data_registers[1] = 0;
+        execute(&mut cpu, &mut memory, &[0x8041]);
+        assert_eq!(cpu.get_ccr(), 0x10 | 0x04);
+    }
+
+    #[test]
+    fn test_addq_subq_byte_on_address_register_is_not_decoded() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        cpu.address_registers[0] = 0x2000;
+
+        execute(&mut cpu, &mut memory, &[0x5148]); // SUBQ.W #8, A0
+        assert_eq!(cpu.get_address_register(0), 0x1FF8);
+        assert!(cpu.decode(&[0x5148]).is_some());
+
+        // SUBQ.B #8, A0 / ADDQ.B #1, A0: keine gültige Kodierung
+        for opcode in [0x5108, 0x5208] {
+            assert_eq!(cpu.decode(&[opcode]), None);
+            execute(&mut cpu, &mut memory, &[opcode]);
+            assert_eq!(cpu.get_address_register(0), 0x1FF8);
+            assert_eq!(cpu.get_pc(), 0x1002);
+        }
+    }
+
+    #[test]
+    fn test_or_to_memory() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        memory.write_word(0x2000, 0x00F0);
+        cpu.address_registers[0] = 0x2000;
+        cpu.data_registers[1] = 0x000F;
+
+        execute(&mut cpu, &mut memory, &[0x8358]); // OR.W D1, (A0)+
+        assert_eq!(memory.read_word(0x2000), 0x00FF);
+        assert_eq!(cpu.get_address_register(0), 0x2002);
+    }
+
+    #[test]
+    fn test_move_records_postincrement_access() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        cpu.set_access_trace(true);
+        memory.write_long(0x0800, 0xDEAD_BEEF);
+        cpu.address_registers[0] = 0x0800;
+
+        memory.write_word(0x1000, 0x2218); // MOVE.L (A0)+, D1
+        cpu.set_pc(0x1000);
+        let result = cpu.execute_instruction(&mut memory);
+        assert_eq!(cpu.get_data_register(1), 0xDEAD_BEEF);
+        assert_eq!(cpu.get_ccr() & 0x08, 0x08); // N
+        assert_eq!(
+            result.accesses,
+            vec![MemAccess {
+                addr: 0x0800,
+                size: Size::Long,
+                kind: AccessKind::Read,
+                value: 0xDEAD_BEEF,
+                ea_description: EaDescription::PostIncrement {
+                    reg: 0,
+                    updated: 0x0804
+                },
+            }]
+        );
+        assert_eq!(
+            result.accesses[0].to_string(),
+            "(A0)+ → read long @ $000800 = $DEADBEEF, A0 now $000804"
+        );
+
+        // Ohne Access-Trace wird nichts aufgezeichnet
+        cpu.set_access_trace(false);
+        cpu.set_pc(0x1000);
+        assert!(cpu.execute_instruction(&mut memory).accesses.is_empty());
+        assert_eq!(cpu.get_address_register(0), 0x0808);
+    }
+
+    #[test]
+    fn test_move_records_displacement_accesses() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        cpu.set_access_trace(true);
+        memory.write_word(0x2004, 0x1234);
+        cpu.address_registers[1] = 0x2000;
+        cpu.address_registers[2] = 0x3008;
+
+        memory.write_word(0x1000, 0x3569); // MOVE.W 4(A1), -8(A2)
+        memory.write_word(0x1002, 0x0004);
+        memory.write_word(0x1004, 0xFFF8);
+        cpu.set_pc(0x1000);
+        let result = cpu.execute_instruction(&mut memory);
+        assert_eq!(memory.read_word(0x3000), 0x1234);
+        assert_eq!(result.next_pc(), 0x1006);
+        assert_eq!(
+            result.accesses,
+            vec![
+                MemAccess {
+                    addr: 0x2004,
+                    size: Size::Word,
+                    kind: AccessKind::Read,
+                    value: 0x1234,
+                    ea_description: EaDescription::Displacement {
+                        reg: 1,
+                        base: 0x2000,
+                        displacement: 4
+                    },
+                },
+                MemAccess {
+                    addr: 0x3000,
+                    size: Size::Word,
+                    kind: AccessKind::Write,
+                    value: 0x1234,
+                    ea_description: EaDescription::Displacement {
+                        reg: 2,
+                        base: 0x3008,
+                        displacement: -8
+                    },
+                },
+            ]
+        );
+        let lines: Vec<String> = result.accesses.iter().map(|a| a.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "4(A1) = $002000+4 → read word @ $002004 = $1234",
+                "-8(A2) = $003008-8 → write word @ $003000 = $1234",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_divu_and_divs() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        cpu.data_registers[0] = 100;
+
+        execute(&mut cpu, &mut memory, &[0x80FC, 0x0007]); // DIVU.W #7, D0
+        assert_eq!(cpu.get_data_register(0), (2 << 16) | 14);
+        assert_eq!(cpu.get_pc(), 0x1004);
+
+        cpu.data_registers[1] = -100i32 as u32;
+        cpu.data_registers[2] = 7;
+        execute(&mut cpu, &mut memory, &[0x83C2]); // DIVS.W D2, D1
+        assert_eq!(cpu.get_data_register(1), (0xFFFE << 16) | 0xFFF2); // Rest -2, -14
+        assert_eq!(cpu.get_ccr() & 0x08, 0x08);
+
+        // Quotient passt nicht in 16 Bit: V gesetzt, D0 unverändert
+        cpu.data_registers[0] = 0x0010_0000;
+        execute(&mut cpu, &mut memory, &[0x80FC, 0x0001]);
+        assert_eq!(cpu.get_data_register(0), 0x0010_0000);
+        assert_eq!(cpu.get_ccr() & 0x02, 0x02);
+    }
+
+    #[test]
+    fn test_divide_by_zero_and_sbcd_raise_exceptions() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        execute(&mut cpu, &mut memory, &[0x80FC, 0x0000]); // DIVU.W #0, D0
+        assert_eq!(
+            cpu.exception(),
+            Some(Exception::DivideByZero { pc: 0x1000 })
+        );
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.get_pc(), 0x1000);
+
+        cpu.reset();
+        execute(&mut cpu, &mut memory, &[0x8109]); // SBCD -(A1), -(A0)
+        assert_eq!(
+            cpu.exception(),
+            Some(Exception::NotImplemented {
+                pc: 0x1000,
+                opcode: 0x8109,
+                mnemonic: "SBCD"
+            })
+        );
+        assert_eq!(cpu.get_pc(), 0x1000);
+    }
+
+    #[test]
+    fn test_bus_error_reports_faulting_access() {
+        let mut memory = Memory::new();
+        memory.unmap(0xE0_0000..0xF0_0000);
+        memory.map_rom(0x8000..0x9000);
+        let mut cpu = CPU::new();
+        cpu.address_registers[1] = 0xE0_0000;
+        execute(&mut cpu, &mut memory, &[0x2019]); // MOVE.L (A1)+, D0
+        let Some(Exception::BusError(fault)) = cpu.exception() else {
+            panic!("no bus error: {:?}", cpu.exception());
+        };
+        assert_eq!(fault.pc, 0x1000);
+        assert_eq!(fault.address, 0xE0_0000);
+        assert_eq!(fault.size, Size::Long);
+        assert_eq!(fault.kind, AccessKind::Read);
+        assert_eq!(fault.cause, BusFaultKind::Unmapped);
+        assert_eq!(fault.consumed, 2);
+        // Die Instruktion fand nicht statt: A1 ist nicht erhöht
+        assert_eq!(cpu.get_address_register(1), 0xE0_0000);
+        assert_eq!(cpu.get_pc(), 0x1000);
+        assert_eq!(
+            fault.to_string(),
+            "bus error: read.l from $E00000 (unmapped) by MOVE.L (A1)+, D0 at $001000"
+        );
+
+        cpu.reset();
+        cpu.data_registers[0] = 0x1234_5678;
+        cpu.address_registers[2] = 0x8010;
+        execute(&mut cpu, &mut memory, &[0x2540, 0x0004]); // MOVE.L D0, 4(A2)
+        let Some(Exception::BusError(fault)) = cpu.exception() else {
+            panic!("no bus error: {:?}", cpu.exception());
+        };
+        assert_eq!(fault.address, 0x8014);
+        assert_eq!(fault.kind, AccessKind::Write);
+        assert_eq!(fault.cause, BusFaultKind::ReadOnly);
+        assert_eq!(fault.consumed, 4);
+        assert_eq!(memory.read_long(0x8014), 0);
+        assert!(fault.to_string().contains("write.l to $008014 (ROM)"));
+    }
+
+    #[test]
+    fn test_snapshot_equality_ignores_derived_fields() {
+        let mut cpu = CPU::new();
+        cpu.reset();
+        cpu.condition_code_register = 0x04;
+        let snapshot = cpu.snapshot();
+        assert!(snapshot.z);
+
+        // Flag-Felder und SR-Low-Byte übernimmt `restore` nicht
+        let mut same = snapshot;
+        same.z = false;
+        same.sr |= 0x001F;
+        assert_eq!(same, snapshot);
+
+        let mut restored = CPU::new();
+        restored.restore(&same);
+        assert_eq!(restored.snapshot(), snapshot);
+
+        let mut different = snapshot;
+        different.ccr = 0;
+        assert_ne!(different, snapshot);
+        different = snapshot;
+        different.sr = 0x0700;
+        assert_ne!(different, snapshot);
+    }
+
+    #[test]
+    fn test_alignment_check_and_rom_write_policy() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        cpu.address_registers[0] = 0x2001;
+        execute(&mut cpu, &mut memory, &[0x3010]); // MOVE.W (A0), D0
+        assert_eq!(cpu.exception(), None, "odd accesses pass without the check");
+
+        cpu.reset();
+        cpu.set_alignment_check(true);
+        execute(&mut cpu, &mut memory, &[0x3010]);
+        let Some(Exception::BusError(fault)) = cpu.exception() else {
+            panic!("no address error: {:?}", cpu.exception());
+        };
+        assert_eq!(fault.cause, BusFaultKind::OddAddress);
+        assert_eq!(fault.vector(), ADDRESS_ERROR_VECTOR);
+        assert!(fault
+            .to_string()
+            .starts_with("address error: read.w from $002001 (odd address)"));
+        // Bytezugriffe dürfen ungerade sein
+        cpu.reset();
+        execute(&mut cpu, &mut memory, &[0x1010]); // MOVE.B (A0), D0
+        assert_eq!(cpu.exception(), None);
+
+        memory.map_rom(0x8000..0x9000);
+        cpu.reset();
+        cpu.set_rom_write_faults(false);
+        cpu.data_registers[0] = 0x1234_5678;
+        cpu.address_registers[2] = 0x8010;
+        execute(&mut cpu, &mut memory, &[0x2480]); // MOVE.L D0, (A2)
+        assert_eq!(cpu.exception(), None);
+        assert_eq!(memory.read_long(0x8010), 0);
+        assert_eq!(cpu.get_pc(), 0x1002);
+    }
+
+    #[test]
+    fn test_bus_error_with_handler_stacks_group0_frame() {
+        let mut memory = Memory::new();
+        memory.unmap(0xE0_0000..0xF0_0000);
+        memory.write_long(BUS_ERROR_VECTOR * 4, 0x2000);
+        let mut cpu = CPU::new();
+        cpu.address_registers[1] = 0xE0_0010;
+        cpu.address_registers[7] = 0x4000;
+        execute(&mut cpu, &mut memory, &[0x3011]); // MOVE.W (A1), D0
+        assert_eq!(cpu.exception(), None);
+        assert_eq!(cpu.get_pc(), 0x2000);
+        assert!(cpu.get_sr() & 0x2000 != 0);
+
+        let sp = cpu.get_address_register(7);
+        assert_eq!(sp, 0x4000 - 14);
+        assert_eq!(memory.read_word(sp), 0x19); // Lesen, Befehl, FC 1 (User-Daten)
+        assert_eq!(memory.read_long(sp + 2), 0xE0_0010);
+        assert_eq!(memory.read_word(sp + 6), 0x3011);
+        assert_eq!(memory.read_word(sp + 8), 0);
+        assert_eq!(memory.read_long(sp + 10), 0x1000);
+    }
+
+    #[test]
+    fn test_stop_and_legacy_simhalt() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        execute(&mut cpu, &mut memory, &[0x4E72, 0x2704]); // STOP #$2704
+        assert!(cpu.is_stopped() && !cpu.is_halted());
+        assert_eq!(cpu.get_sr(), 0x2704);
+        assert_eq!(cpu.get_ccr(), 0x04);
+        assert_eq!(cpu.get_pc(), 0x1004);
+        cpu.execute_instruction(&mut memory);
+        assert_eq!(cpu.get_pc(), 0x1004);
+
+        cpu.reset();
+        cpu.set_legacy_simhalt(true);
+        execute(&mut cpu, &mut memory, &[0x4E72]);
+        assert!(cpu.is_halted() && !cpu.is_stopped());
+        assert_eq!(cpu.get_pc(), 0x1000);
+    }
+
+    #[test]
+    fn test_interrupt_wakes_stop_and_rte_returns() {
+        let mut memory = Memory::new();
+        let mut cpu = CPU::new();
+        cpu.address_registers[7] = 0x8000;
+        memory.write_long(26 * 4, 0x2000); // Autovektor Level 2
+        memory.write_word(0x2000, 0x4E73); // RTE
+
+        execute(&mut cpu, &mut memory, &[0x4E72, 0x2104]); // STOP #$2104
+        assert!(
+            !cpu.interrupt(&mut memory, IrqRequest::autovector(1)),
+            "masked"
+        );
+        assert!(cpu.interrupt(&mut memory, IrqRequest::autovector(2)));
+        assert!(!cpu.is_stopped());
+        assert_eq!(cpu.get_pc(), 0x2000);
+        assert_eq!(cpu.get_sr() & 0x0700, 0x0200);
+        assert_eq!(cpu.get_address_register(7), 0x7FFA);
+        assert_eq!(memory.read_word(0x7FFA), 0x2104, "SR with CCR");
+        assert_eq!(memory.read_long(0x7FFC), 0x1004, "PC after STOP");
+
+        cpu.execute_instruction(&mut memory);
+        assert_eq!(cpu.get_pc(), 0x1004);
+        assert_eq!(cpu.get_sr(), 0x2104);
+        assert_eq!(cpu.get_ccr(), 0x04);
+        assert_eq!(cpu.get_address_register(7), 0x8000);
+    }
+
+    #[test]
+    fn test_snapshot_is_plain_value() {
+        // Copy und ohne Drop: keine Heap-Allokation pro Snapshot
+        fn assert_copy<T: Copy>() {}
+        assert_copy::<CpuSnapshot>();
+        assert!(!std::mem::needs_drop::<CpuSnapshot>());
+    }
 }
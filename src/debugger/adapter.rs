@@ -0,0 +1,491 @@
+// Führt Requests des Debugger-Protokolls auf einem Controller aus
+// Breakpoints, Quellzuordnung, Register-Snapshots und Disassembler kommen aus denselben
+// Modulen wie in der GUI. Läufe sind synchron: `continue` kehrt erst zurück, wenn der
+// Controller anhält (Breakpoint, SIMHALT, Exception, Schrittlimit).
+
+use super::json::Json;
+use super::protocol::{
+    memory_reference, Command, Event, Message, Request, Response, StoppedReason,
+    REGISTERS_REFERENCE, THREAD_ID, WATCHES_REFERENCE,
+};
+use super::transport::Transport;
+use crate::breakpoints::{register_value, Location};
+use crate::controller::{Controller, LoadError, LoadMode, StopReason};
+use crate::disassembler::{disassemble_at, disassemble_at_symbolic};
+use crate::program::Program;
+use std::io;
+
+/// Instruktionen pro `on_frame` während `continue`
+pub const RUN_SLICE: u32 = 100_000;
+
+pub struct Adapter {
+    controller: Controller,
+    /// Dateipfad aus `launch` für die `source` der Stack-Frames
+    source_path: Option<String>,
+    stop_on_entry: bool,
+    disconnected: bool,
+}
+
+impl Default for Adapter {
+    fn default() -> Self {
+        Self::new(Controller::default())
+    }
+}
+
+impl Adapter {
+    pub fn new(controller: Controller) -> Self {
+        Adapter {
+            controller,
+            source_path: None,
+            stop_on_entry: false,
+            disconnected: false,
+        }
+    }
+
+    pub fn controller(&self) -> &Controller {
+        &self.controller
+    }
+
+    pub fn controller_mut(&mut self) -> &mut Controller {
+        &mut self.controller
+    }
+
+    /// Nach `disconnect` nimmt `serve` keine Requests mehr an
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    /// Beantwortet einen Request: zuerst die Response, danach die ausgelösten Events
+    pub fn handle(&mut self, request: &Request) -> Vec<Message> {
+        let mut events = Vec::new();
+        let result = match &request.command {
+            Command::Initialize => Ok(Some(Json::object([
+                ("supportsConfigurationDoneRequest", true.into()),
+                ("supportsReadMemoryRequest", true.into()),
+                ("supportsDisassembleRequest", true.into()),
+                ("supportsSteppingGranularity", false.into()),
+            ]))),
+            Command::Launch {
+                program,
+                source,
+                stop_on_entry,
+            } => self
+                .launch(program.as_deref(), source.as_deref(), *stop_on_entry)
+                .map(|()| {
+                    // Erst jetzt sind Zeilen-Breakpoints auflösbar
+                    events.push(Event::Initialized);
+                    None
+                }),
+            Command::SetBreakpoints { lines } => Ok(Some(self.set_breakpoints(lines))),
+            Command::ConfigurationDone => {
+                if self.controller.emulator().program().is_some() {
+                    events = if self.stop_on_entry {
+                        self.stopped(StoppedReason::Entry, "entry".to_string())
+                    } else {
+                        self.run()
+                    };
+                }
+                Ok(None)
+            }
+            Command::Continue => match self.program() {
+                Err(message) => Err(message),
+                Ok(_) => {
+                    events = self.run();
+                    Ok(Some(Json::object([("allThreadsContinued", true.into())])))
+                }
+            },
+            Command::Next | Command::StepIn => match self.program() {
+                Err(message) => Err(message),
+                Ok(_) => {
+                    let reason = self.controller.advance(1);
+                    events = self.stop_events(reason);
+                    Ok(None)
+                }
+            },
+            // Zwischen zwei Requests läuft nichts; der Client bekommt trotzdem sein Event
+            Command::Pause => {
+                events = self.stopped(StoppedReason::Pause, "paused".to_string());
+                Ok(None)
+            }
+            Command::Threads => Ok(Some(Json::object([(
+                "threads",
+                vec![Json::object([
+                    ("id", THREAD_ID.into()),
+                    ("name", "MC68000".into()),
+                ])]
+                .into(),
+            )]))),
+            Command::StackTrace => self.stack_trace().map(Some),
+            Command::Scopes { frame_id } => self.scopes(*frame_id).map(Some),
+            Command::Variables { reference } => self.variables(*reference).map(Some),
+            Command::ReadMemory { address, count } => Ok(Some(self.read_memory(*address, *count))),
+            Command::Disassemble {
+                address,
+                offset,
+                count,
+            } => Ok(Some(self.disassemble(*address, *offset, *count))),
+            Command::Disconnect => {
+                self.disconnected = true;
+                Ok(None)
+            }
+        };
+
+        let response = match result {
+            Ok(body) => Response::ok(request, body),
+            Err(message) => Response::error(request.seq, request.command.name(), &message),
+        };
+        let mut messages = vec![Message::Response(response)];
+        messages.extend(events.into_iter().map(Message::Event));
+        messages
+    }
+
+    fn program(&self) -> Result<&Program, String> {
+        self.controller
+            .emulator()
+            .program()
+            .ok_or_else(|| "no program launched".to_string())
+    }
+
+    fn launch(
+        &mut self,
+        path: Option<&str>,
+        source: Option<&str>,
+        stop_on_entry: bool,
+    ) -> Result<(), String> {
+        let source = match (path, source) {
+            (_, Some(source)) => source.to_string(),
+            (Some(path), None) => {
+                std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?
+            }
+            (None, None) => return Err("launch needs 'program' or 'source'".to_string()),
+        };
+        match self.controller.load_image(&source, LoadMode::Full) {
+            Ok(_) => {}
+            Err(LoadError::Assembly(errors)) => {
+                return Err(errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            Err(error) => return Err(error.to_string()),
+        }
+        self.source_path = path.map(str::to_string);
+        self.stop_on_entry = stop_on_entry;
+        Ok(())
+    }
+
+    fn set_breakpoints(&mut self, lines: &[usize]) -> Json {
+        let emulator = self.controller.emulator_mut();
+        let old = emulator
+            .breakpoints()
+            .iter()
+            .filter(|bp| matches!(bp.location, Location::Line(_)))
+            .map(|bp| bp.location.clone())
+            .collect::<Vec<_>>();
+        for location in &old {
+            emulator.breakpoints_mut().remove(location);
+        }
+
+        let mut breakpoints = Vec::new();
+        for &line in lines {
+            emulator.add_breakpoint(Location::Line(line));
+            let address = emulator
+                .program()
+                .and_then(|program| program.address_for_line(line));
+            let mut fields = vec![
+                ("verified", Json::from(address.is_some())),
+                ("line", line.into()),
+            ];
+            match address {
+                Some(address) => {
+                    fields.push(("instructionReference", memory_reference(address).into()))
+                }
+                None => fields.push(("message", "no instruction on this line".into())),
+            }
+            breakpoints.push(Json::object(fields));
+        }
+        Json::object([("breakpoints", breakpoints.into())])
+    }
+
+    /// Läuft bis zum nächsten Halt und meldet ihn
+    fn run(&mut self) -> Vec<Event> {
+        self.controller.start();
+        let reason = loop {
+            if let Some(reason) = self.controller.on_frame(RUN_SLICE) {
+                break reason;
+            }
+        };
+        self.stop_events(Some(reason))
+    }
+
+    fn stopped(&mut self, reason: StoppedReason, description: String) -> Vec<Event> {
+        let mut events = self.output_events();
+        events.push(Event::Stopped {
+            reason,
+            description,
+        });
+        events
+    }
+
+    /// Meldungen des Controllers als Output-Events
+    fn output_events(&mut self) -> Vec<Event> {
+        self.controller
+            .take_messages()
+            .into_iter()
+            .map(Event::Output)
+            .collect()
+    }
+
+    /// Events nach einem Schritt (`None` = ohne besonderen Grund) oder Lauf
+    fn stop_events(&mut self, reason: Option<StopReason>) -> Vec<Event> {
+        let description = reason
+            .as_ref()
+            .map(|reason| reason.describe(self.controller.emulator().program()));
+        let kind = match reason {
+            None => StoppedReason::Step,
+            Some(StopReason::Halted | StopReason::OutOfProgram(_)) => {
+                let mut events = self.output_events();
+                events.push(Event::Exited(0));
+                events.push(Event::Terminated);
+                return events;
+            }
+            Some(StopReason::Breakpoint(_)) => StoppedReason::Breakpoint,
+            Some(StopReason::Exception(_) | StopReason::DataExecution(_)) => {
+                StoppedReason::Exception
+            }
+            Some(StopReason::RegisterWatch(_)) => StoppedReason::DataBreakpoint,
+            Some(StopReason::StepCap(_) | StopReason::IdleLoop(_) | StopReason::Stopped) => {
+                StoppedReason::Pause
+            }
+        };
+        self.stopped(kind, description.unwrap_or_else(|| "step".to_string()))
+    }
+
+    /// Ein einziger Frame am PC: der Befehlssatz kennt keine Unterprogrammaufrufe
+    fn stack_trace(&self) -> Result<Json, String> {
+        let program = self.program()?;
+        let pc = self.controller.emulator().cpu().get_pc();
+        let name = program
+            .symbol_offset(pc)
+            .unwrap_or_else(|| memory_reference(pc));
+        let mut frame = vec![
+            ("id", Json::from(0i64)),
+            ("name", name.into()),
+            (
+                "line",
+                program.line_for_address(pc).unwrap_or_default().into(),
+            ),
+            ("column", 1i64.into()),
+            ("instructionPointerReference", memory_reference(pc).into()),
+        ];
+        if let Some(path) = &self.source_path {
+            let name = std::path::Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone());
+            frame.push((
+                "source",
+                Json::object([("name", name.into()), ("path", path.as_str().into())]),
+            ));
+        }
+        Ok(Json::object([
+            ("stackFrames", vec![Json::object(frame)].into()),
+            ("totalFrames", 1i64.into()),
+        ]))
+    }
+
+    fn scopes(&self, frame_id: i64) -> Result<Json, String> {
+        if frame_id != 0 {
+            return Err(format!("unknown frame {}", frame_id));
+        }
+        let scope = |name: &str, reference: i64| {
+            Json::object([
+                ("name", name.into()),
+                ("variablesReference", reference.into()),
+                ("expensive", false.into()),
+            ])
+        };
+        Ok(Json::object([(
+            "scopes",
+            vec![
+                scope("Registers", REGISTERS_REFERENCE),
+                scope("Watches", WATCHES_REFERENCE),
+            ]
+            .into(),
+        )]))
+    }
+
+    fn variables(&self, reference: i64) -> Result<Json, String> {
+        let variable = |name: String, value: String| {
+            Json::object([
+                ("name", name.into()),
+                ("value", value.into()),
+                ("variablesReference", 0i64.into()),
+            ])
+        };
+        let emulator = self.controller.emulator();
+        let variables = match reference {
+            REGISTERS_REFERENCE => {
+                let cpu = emulator.cpu().snapshot();
+                let mut variables = Vec::new();
+                for (i, value) in cpu.d.iter().enumerate() {
+                    variables.push(variable(format!("D{}", i), format!("0x{:08X}", value)));
+                }
+                for (i, value) in cpu.a.iter().enumerate() {
+                    variables.push(variable(format!("A{}", i), format!("0x{:08X}", value)));
+                }
+                variables.push(variable("PC".into(), format!("0x{:06X}", cpu.pc)));
+                variables.push(variable("SR".into(), format!("0x{:04X}", cpu.sr)));
+                let flags = [
+                    (cpu.x, 'X'),
+                    (cpu.n, 'N'),
+                    (cpu.z, 'Z'),
+                    (cpu.v, 'V'),
+                    (cpu.c, 'C'),
+                ]
+                .iter()
+                .map(|&(set, flag)| if set { flag } else { '-' })
+                .collect::<String>();
+                variables.push(variable("CCR".into(), flags));
+                variables
+            }
+            WATCHES_REFERENCE => {
+                let registers = emulator.cpu().registers();
+                let memory = emulator.watches().iter().map(|watch| {
+                    let name = match &watch.location {
+                        Location::Line(line) => format!("line {}", line),
+                        Location::Symbol(name) => name.clone(),
+                        Location::Address(address) => format!("${:06X}", address),
+                    };
+                    let value = match watch.read(emulator.memory()) {
+                        Some(value) => format!("0x{:X}", value),
+                        None => "unresolved".to_string(),
+                    };
+                    variable(format!("{}.{}", name, watch.size.suffix()), value)
+                });
+                let registers = self.controller.register_watches().iter().map(|watch| {
+                    let value = register_value(&registers, &watch.register);
+                    variable(
+                        watch.to_string(),
+                        format!("0x{:08X} ({} hits)", value, watch.hits),
+                    )
+                });
+                memory.chain(registers).collect()
+            }
+            _ => return Err(format!("unknown variablesReference {}", reference)),
+        };
+        Ok(Json::object([("variables", Json::Array(variables))]))
+    }
+
+    fn read_memory(&self, address: u32, count: u32) -> Json {
+        // Nur innerhalb des 24-Bit-Adressraums
+        let count = count.min(0x0100_0000 - address);
+        let memory = self.controller.emulator().memory();
+        let bytes = (address..address + count)
+            .map(|address| memory.read_byte(address))
+            .collect::<Vec<_>>();
+        Json::object([
+            ("address", memory_reference(address).into()),
+            ("data", base64(&bytes).into()),
+        ])
+    }
+
+    fn disassemble(&self, address: u32, offset: i64, count: usize) -> Json {
+        let emulator = self.controller.emulator();
+        let program = emulator.program();
+        // Rückwärts nur über bekannte Instruktionsgrenzen aus der Quellzuordnung
+        let mut start = address;
+        if offset < 0 {
+            if let Some(program) = program {
+                let before = program.source_map.range(..address).rev();
+                if let Some((&first, _)) = before.take(offset.unsigned_abs() as usize).last() {
+                    start = first;
+                }
+            }
+        }
+        for _ in 0..offset.max(0) {
+            start = start.wrapping_add(disassemble_at(emulator.memory(), start).length);
+        }
+
+        let mut instructions = Vec::new();
+        let mut pc = start & 0x00FF_FFFE;
+        for _ in 0..count {
+            let disassembly =
+                disassemble_at_symbolic(emulator.memory(), pc, emulator.address_formatter());
+            let bytes = (0..disassembly.length)
+                .map(|i| format!("{:02X}", emulator.memory().read_byte(pc + i)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let mut fields = vec![
+                ("address", Json::from(memory_reference(pc))),
+                ("instructionBytes", bytes.into()),
+                ("instruction", disassembly.text.into()),
+            ];
+            if let Some(program) = program {
+                if let Some(symbol) = program.symbol_at(pc) {
+                    fields.push(("symbol", symbol.into()));
+                }
+                if let Some(line) = program.line_for_address(pc) {
+                    fields.push(("line", line.into()));
+                }
+            }
+            instructions.push(Json::object(fields));
+            pc = (pc + disassembly.length) & 0x00FF_FFFE;
+        }
+        Json::object([("instructions", instructions.into())])
+    }
+}
+
+/// Beantwortet Requests, bis die Gegenseite trennt oder `disconnect` schickt. Jede
+/// ausgehende Nachricht bekommt eine eigene, fortlaufende Sequenznummer.
+pub fn serve(adapter: &mut Adapter, transport: &mut dyn Transport) -> io::Result<()> {
+    let mut seq = 0;
+    while let Some(text) = transport.receive()? {
+        let messages = match Request::parse(&text) {
+            Ok(request) => adapter.handle(&request),
+            Err(error) => vec![Message::Response(error.response())],
+        };
+        for message in messages {
+            seq += 1;
+            transport.send(&message.to_json(seq).to_string())?;
+        }
+        if adapter.is_disconnected() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Standard-Base64 mit Padding (`data` von readMemory)
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(&[0xFF, 0x00, 0x10, 0x20]), "/wAQIA==");
+    }
+}
@@ -0,0 +1,10 @@
+// Debugger-Protokoll für externe Oberflächen (Web-Frontend, VS-Code-Erweiterung)
+// `protocol` beschreibt die DAP-ähnlichen Nachrichten, `adapter` führt sie über den
+// Controller aus, `transport` rahmt sie für stdio oder TCP.
+
+pub mod adapter;
+pub mod json;
+pub mod protocol;
+pub mod transport;
+
+pub use adapter::{serve, Adapter};
@@ -0,0 +1,492 @@
+// Nachrichten des Debugger-Protokolls
+// Angelehnt an das Debug Adapter Protocol (DAP): gleiche Befehlsnamen, Argumente und
+// Antwortfelder, soweit der Emulator sie braucht. Ein Client für DAP funktioniert damit
+// ohne Anpassung für die unterstützten Befehle.
+
+use super::json::Json;
+use crate::program::parse_address;
+use std::fmt;
+
+/// Der Emulator hat genau einen Thread
+pub const THREAD_ID: i64 = 1;
+/// `variablesReference` der Scopes aus `scopes`
+pub const REGISTERS_REFERENCE: i64 = 1;
+pub const WATCHES_REFERENCE: i64 = 2;
+
+/// Befehl eines Requests samt Argumenten
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Initialize,
+    /// Assembliert und lädt ein Programm: `program` ist ein Dateipfad, alternativ steht der
+    /// Quelltext direkt in `source` (z.B. aus einem Web-Editor)
+    Launch {
+        program: Option<String>,
+        source: Option<String>,
+        stop_on_entry: bool,
+    },
+    /// Ersetzt alle Zeilen-Breakpoints
+    SetBreakpoints {
+        lines: Vec<usize>,
+    },
+    ConfigurationDone,
+    Continue,
+    /// Eine Instruktion; ohne Unterprogrammaufrufe im Befehlssatz dasselbe wie `StepIn`
+    Next,
+    StepIn,
+    Pause,
+    Threads,
+    StackTrace,
+    Scopes {
+        frame_id: i64,
+    },
+    Variables {
+        reference: i64,
+    },
+    /// `memoryReference` + `offset` sind bereits verrechnet
+    ReadMemory {
+        address: u32,
+        count: u32,
+    },
+    Disassemble {
+        address: u32,
+        /// Instruktionen vor (negativ) oder hinter `address`
+        offset: i64,
+        count: usize,
+    },
+    Disconnect,
+}
+
+impl Command {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Initialize => "initialize",
+            Command::Launch { .. } => "launch",
+            Command::SetBreakpoints { .. } => "setBreakpoints",
+            Command::ConfigurationDone => "configurationDone",
+            Command::Continue => "continue",
+            Command::Next => "next",
+            Command::StepIn => "stepIn",
+            Command::Pause => "pause",
+            Command::Threads => "threads",
+            Command::StackTrace => "stackTrace",
+            Command::Scopes { .. } => "scopes",
+            Command::Variables { .. } => "variables",
+            Command::ReadMemory { .. } => "readMemory",
+            Command::Disassemble { .. } => "disassemble",
+            Command::Disconnect => "disconnect",
+        }
+    }
+
+    /// `arguments` des Requests, wie ein DAP-Client sie schickt
+    pub fn arguments(&self) -> Json {
+        let thread = || Json::object([("threadId", THREAD_ID.into())]);
+        match self {
+            Command::Initialize => Json::object([("adapterID", "mc68000".into())]),
+            Command::Launch {
+                program,
+                source,
+                stop_on_entry,
+            } => {
+                let mut fields = vec![("stopOnEntry", Json::from(*stop_on_entry))];
+                if let Some(program) = program {
+                    fields.push(("program", program.as_str().into()));
+                }
+                if let Some(source) = source {
+                    fields.push(("source", source.as_str().into()));
+                }
+                Json::object(fields)
+            }
+            Command::SetBreakpoints { lines } => Json::object([(
+                "breakpoints",
+                lines
+                    .iter()
+                    .map(|&line| Json::object([("line", line.into())]))
+                    .collect::<Vec<_>>()
+                    .into(),
+            )]),
+            Command::Continue | Command::Next | Command::StepIn | Command::Pause => thread(),
+            Command::StackTrace => thread(),
+            Command::Scopes { frame_id } => Json::object([("frameId", (*frame_id).into())]),
+            Command::Variables { reference } => {
+                Json::object([("variablesReference", (*reference).into())])
+            }
+            Command::ReadMemory { address, count } => Json::object([
+                ("memoryReference", memory_reference(*address).into()),
+                ("count", (*count).into()),
+            ]),
+            Command::Disassemble {
+                address,
+                offset,
+                count,
+            } => Json::object([
+                ("memoryReference", memory_reference(*address).into()),
+                ("instructionOffset", (*offset).into()),
+                ("instructionCount", (*count).into()),
+            ]),
+            Command::ConfigurationDone | Command::Threads | Command::Disconnect => {
+                Json::object::<&str>([])
+            }
+        }
+    }
+
+    /// Befehl aus Name und `arguments` eines Requests
+    pub fn from_json(name: &str, arguments: &Json) -> Result<Command, String> {
+        let int = |key: &str| arguments.get(key).and_then(Json::as_i64);
+        let text = |key: &str| {
+            arguments
+                .get(key)
+                .and_then(Json::as_str)
+                .map(str::to_string)
+        };
+        let address = || -> Result<u32, String> {
+            let reference = text("memoryReference").ok_or("missing memoryReference")?;
+            let base = parse_address(&reference)
+                .ok_or_else(|| format!("invalid memoryReference '{}'", reference))?;
+            Ok(base.wrapping_add(int("offset").unwrap_or(0) as u32) & 0x00FF_FFFF)
+        };
+        Ok(match name {
+            "initialize" => Command::Initialize,
+            "launch" => Command::Launch {
+                program: text("program"),
+                source: text("source"),
+                stop_on_entry: arguments
+                    .get("stopOnEntry")
+                    .and_then(Json::as_bool)
+                    .unwrap_or(false),
+            },
+            "setBreakpoints" => {
+                // `breakpoints: [{line}]`, ältere Clients schicken `lines: [..]`
+                let lines = match arguments.get("breakpoints").and_then(Json::as_array) {
+                    Some(breakpoints) => breakpoints
+                        .iter()
+                        .map(|bp| bp.get("line").and_then(Json::as_i64))
+                        .collect::<Option<Vec<_>>>(),
+                    None => arguments
+                        .get("lines")
+                        .and_then(Json::as_array)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(Json::as_i64)
+                        .collect(),
+                };
+                let lines = lines
+                    .filter(|lines| lines.iter().all(|&line| line > 0))
+                    .ok_or("breakpoint lines must be positive integers")?;
+                Command::SetBreakpoints {
+                    lines: lines.into_iter().map(|line| line as usize).collect(),
+                }
+            }
+            "configurationDone" => Command::ConfigurationDone,
+            "continue" => Command::Continue,
+            "next" => Command::Next,
+            "stepIn" => Command::StepIn,
+            "pause" => Command::Pause,
+            "threads" => Command::Threads,
+            "stackTrace" => Command::StackTrace,
+            "scopes" => Command::Scopes {
+                frame_id: int("frameId").ok_or("missing frameId")?,
+            },
+            "variables" => Command::Variables {
+                reference: int("variablesReference").ok_or("missing variablesReference")?,
+            },
+            "readMemory" => Command::ReadMemory {
+                address: address()?,
+                count: int("count")
+                    .and_then(|count| u32::try_from(count).ok())
+                    .ok_or("missing count")?,
+            },
+            "disassemble" => Command::Disassemble {
+                // `offset` ist hier ein Byte-Offset auf die Adresse, wie bei readMemory
+                address: address()?,
+                offset: int("instructionOffset").unwrap_or(0),
+                count: int("instructionCount")
+                    .and_then(|count| usize::try_from(count).ok())
+                    .ok_or("missing instructionCount")?,
+            },
+            "disconnect" => Command::Disconnect,
+            _ => return Err(format!("unsupported command '{}'", name)),
+        })
+    }
+}
+
+/// Adresse als `memoryReference` bzw. `instructionPointerReference`
+pub fn memory_reference(address: u32) -> String {
+    format!("0x{:06X}", address)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub seq: i64,
+    pub command: Command,
+}
+
+/// Ein Request, der sich nicht auswerten ließ; wird als fehlgeschlagene Response beantwortet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestError {
+    pub seq: i64,
+    pub command: String,
+    pub message: String,
+}
+
+impl RequestError {
+    pub fn response(&self) -> Response {
+        Response::error(self.seq, &self.command, &self.message)
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request {}: {}", self.seq, self.message)
+    }
+}
+
+impl Request {
+    pub fn parse(text: &str) -> Result<Request, RequestError> {
+        let error = |seq, command: &str, message: String| RequestError {
+            seq,
+            command: command.to_string(),
+            message,
+        };
+        let json = Json::parse(text).map_err(|message| error(0, "", message))?;
+        let seq = json.get("seq").and_then(Json::as_i64).unwrap_or(0);
+        if json.get("type").and_then(Json::as_str) != Some("request") {
+            return Err(error(
+                seq,
+                "",
+                "expected a message of type 'request'".into(),
+            ));
+        }
+        let name = json.get("command").and_then(Json::as_str).unwrap_or("");
+        let arguments = json.get("arguments").cloned().unwrap_or(Json::Null);
+        let command =
+            Command::from_json(name, &arguments).map_err(|message| error(seq, name, message))?;
+        Ok(Request { seq, command })
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("seq", self.seq.into()),
+            ("type", "request".into()),
+            ("command", self.command.name().into()),
+            ("arguments", self.command.arguments()),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub request_seq: i64,
+    pub command: String,
+    pub success: bool,
+    /// Fehlermeldung bei `success == false`
+    pub message: Option<String>,
+    pub body: Option<Json>,
+}
+
+impl Response {
+    pub fn ok(request: &Request, body: Option<Json>) -> Response {
+        Response {
+            request_seq: request.seq,
+            command: request.command.name().to_string(),
+            success: true,
+            message: None,
+            body,
+        }
+    }
+
+    pub fn error(request_seq: i64, command: &str, message: &str) -> Response {
+        Response {
+            request_seq,
+            command: command.to_string(),
+            success: false,
+            message: Some(message.to_string()),
+            body: None,
+        }
+    }
+}
+
+/// Warum die Ausführung angehalten hat (`reason` des stopped-Events)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoppedReason {
+    Entry,
+    Step,
+    Breakpoint,
+    Pause,
+    Exception,
+    DataBreakpoint,
+}
+
+impl StoppedReason {
+    pub fn name(self) -> &'static str {
+        match self {
+            StoppedReason::Entry => "entry",
+            StoppedReason::Step => "step",
+            StoppedReason::Breakpoint => "breakpoint",
+            StoppedReason::Pause => "pause",
+            StoppedReason::Exception => "exception",
+            StoppedReason::DataBreakpoint => "data breakpoint",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Bereit für setBreakpoints und configurationDone
+    Initialized,
+    Stopped {
+        reason: StoppedReason,
+        description: String,
+    },
+    /// Meldungen des Controllers (Trace, Laufende)
+    Output(String),
+    Exited(i64),
+    Terminated,
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Initialized => "initialized",
+            Event::Stopped { .. } => "stopped",
+            Event::Output(_) => "output",
+            Event::Exited(_) => "exited",
+            Event::Terminated => "terminated",
+        }
+    }
+
+    fn body(&self) -> Option<Json> {
+        match self {
+            Event::Initialized | Event::Terminated => None,
+            Event::Stopped {
+                reason,
+                description,
+            } => Some(Json::object([
+                ("reason", reason.name().into()),
+                ("description", description.as_str().into()),
+                ("threadId", THREAD_ID.into()),
+                ("allThreadsStopped", true.into()),
+            ])),
+            Event::Output(text) => Some(Json::object([
+                ("category", "console".into()),
+                ("output", format!("{}\n", text).into()),
+            ])),
+            Event::Exited(code) => Some(Json::object([("exitCode", (*code).into())])),
+        }
+    }
+}
+
+/// Ausgehende Nachricht des Adapters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Response(Response),
+    Event(Event),
+}
+
+impl Message {
+    /// JSON mit der Sequenznummer `seq` des Adapters
+    pub fn to_json(&self, seq: i64) -> Json {
+        let mut fields = vec![("seq", Json::from(seq))];
+        let body = match self {
+            Message::Response(response) => {
+                fields.push(("type", "response".into()));
+                fields.push(("request_seq", response.request_seq.into()));
+                fields.push(("success", response.success.into()));
+                fields.push(("command", response.command.as_str().into()));
+                if let Some(message) = &response.message {
+                    fields.push(("message", message.as_str().into()));
+                }
+                response.body.clone()
+            }
+            Message::Event(event) => {
+                fields.push(("type", "event".into()));
+                fields.push(("event", event.name().into()));
+                event.body()
+            }
+        };
+        if let Some(body) = body {
+            fields.push(("body", body));
+        }
+        Json::object(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_round_trip() {
+        let commands = [
+            Command::Launch {
+                program: Some("demo.asm".into()),
+                source: None,
+                stop_on_entry: true,
+            },
+            Command::SetBreakpoints { lines: vec![3, 7] },
+            Command::Scopes { frame_id: 0 },
+            Command::Variables {
+                reference: REGISTERS_REFERENCE,
+            },
+            Command::ReadMemory {
+                address: 0x2000,
+                count: 16,
+            },
+            Command::Disassemble {
+                address: 0x1000,
+                offset: -2,
+                count: 5,
+            },
+            Command::Continue,
+        ];
+        for (seq, command) in commands.into_iter().enumerate() {
+            let request = Request {
+                seq: seq as i64 + 1,
+                command,
+            };
+            let text = request.to_json().to_string();
+            assert_eq!(Request::parse(&text), Ok(request), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_dap_arguments() {
+        let request = Request::parse(
+            r#"{"seq":4,"type":"request","command":"readMemory",
+                "arguments":{"memoryReference":"$2000","offset":-16,"count":8}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request.command,
+            Command::ReadMemory {
+                address: 0x1FF0,
+                count: 8
+            }
+        );
+
+        let request = Request::parse(
+            r#"{"seq":5,"type":"request","command":"setBreakpoints",
+                "arguments":{"source":{"path":"a.asm"},"lines":[2,9]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request.command,
+            Command::SetBreakpoints { lines: vec![2, 9] }
+        );
+    }
+
+    #[test]
+    fn test_bad_requests_become_error_responses() {
+        let error =
+            Request::parse(r#"{"seq":9,"type":"request","command":"evaluate"}"#).unwrap_err();
+        let response = Message::Response(error.response()).to_json(1).to_string();
+        assert_eq!(
+            response,
+            r#"{"seq":1,"type":"response","request_seq":9,"success":false,"command":"evaluate","message":"unsupported command 'evaluate'"}"#
+        );
+
+        let error = Request::parse(r#"{"seq":3,"type":"request","command":"scopes"}"#).unwrap_err();
+        assert_eq!(error.message, "missing frameId");
+        assert!(Request::parse("{").is_err());
+        assert!(Request::parse(r#"{"seq":1,"type":"event","event":"x"}"#).is_err());
+    }
+}
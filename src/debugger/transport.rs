@@ -0,0 +1,108 @@
+// Transport für das Debugger-Protokoll
+// Nachrichten werden wie bei DAP mit einem `Content-Length`-Header gerahmt. Der Adapter
+// kennt nur `Transport`; stdio, TCP oder ein Kanal im selben Prozess sind austauschbar.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+
+/// Liefert eingehende und verschickt ausgehende Nachrichten (je ein JSON-Text)
+pub trait Transport {
+    /// Nächste Nachricht; None, wenn die Gegenseite die Verbindung beendet hat
+    fn receive(&mut self) -> io::Result<Option<String>>;
+    fn send(&mut self, message: &str) -> io::Result<()>;
+}
+
+/// `Content-Length`-gerahmte Nachrichten über einen beliebigen Byte-Strom
+pub struct StreamTransport<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R: BufRead, W: Write> StreamTransport<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        StreamTransport { reader, writer }
+    }
+}
+
+/// stdin/stdout, z.B. für eine VS-Code-Erweiterung, die den Adapter selbst startet
+pub fn stdio() -> StreamTransport<io::StdinLock<'static>, io::Stdout> {
+    StreamTransport::new(io::stdin().lock(), io::stdout())
+}
+
+/// Eine TCP-Verbindung, z.B. vom Web-Frontend
+pub fn tcp(stream: TcpStream) -> io::Result<StreamTransport<BufReader<TcpStream>, TcpStream>> {
+    Ok(StreamTransport::new(
+        BufReader::new(stream.try_clone()?),
+        stream,
+    ))
+}
+
+impl<R: BufRead, W: Write> Transport for StreamTransport<R, W> {
+    fn receive(&mut self) -> io::Result<Option<String>> {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return match length {
+                    None => Ok(None),
+                    Some(_) => Err(invalid("connection closed inside a header")),
+                };
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                if length.is_some() {
+                    break;
+                }
+                continue;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("Content-Length") {
+                    let value = value.trim().parse::<usize>();
+                    length = Some(value.map_err(|_| invalid("invalid Content-Length"))?);
+                }
+            }
+        }
+
+        let mut body = vec![0; length.unwrap_or(0)];
+        self.reader.read_exact(&mut body)?;
+        String::from_utf8(body)
+            .map(Some)
+            .map_err(|_| invalid("message is not UTF-8"))
+    }
+
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        write!(
+            self.writer,
+            "Content-Length: {}\r\n\r\n{}",
+            message.len(),
+            message
+        )?;
+        self.writer.flush()
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_framing_round_trip() {
+        let mut written = Vec::new();
+        let mut sender = StreamTransport::new(io::empty(), &mut written);
+        sender.send(r#"{"seq":1}"#).unwrap();
+        sender.send("{\"text\":\"ä\"}").unwrap();
+        assert!(written.starts_with(b"Content-Length: 9\r\n\r\n{\"seq\":1}"));
+
+        let mut receiver = StreamTransport::new(written.as_slice(), io::sink());
+        assert_eq!(receiver.receive().unwrap().unwrap(), r#"{"seq":1}"#);
+        assert_eq!(receiver.receive().unwrap().unwrap(), "{\"text\":\"ä\"}");
+        assert_eq!(receiver.receive().unwrap(), None);
+
+        let mut broken = StreamTransport::new(&b"Content-Length: x\r\n\r\n{}"[..], io::sink());
+        assert!(broken.receive().is_err());
+    }
+}
@@ -0,0 +1,405 @@
+// Disassembler für die vom Assembler erzeugten Instruktionen
+// Arbeitet auf einer Wortfolge (Opcode + Extension Words), damit er sowohl für den
+// Speicher als auch für Trace-Ausgaben nutzbar ist.
+
+use crate::address_format::AddressFormatter;
+use crate::isa::{Size, SIMHALT_OPCODE};
+use crate::memory::Memory;
+
+/// Eine disassemblierte Instruktion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    pub text: String,
+    pub length: u32, // Bytes inkl. Extension Words
+}
+
+/// Längste Instruktion in Wörtern (Opcode + 4 Extension Words)
+pub const MAX_INSTRUCTION_WORDS: usize = 5;
+
+/// Liest die Wörter ab `address` und disassembliert sie
+pub fn disassemble_at(memory: &Memory, address: u32) -> Disassembly {
+    let mut words = [0u16; MAX_INSTRUCTION_WORDS];
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = memory.read_word(address.wrapping_add(2 * i as u32) & 0x00FF_FFFE);
+    }
+    disassemble(&words)
+}
+
+/// Wie `disassemble_at`, Zieladressen aber relativ zu Labels ("BNE LOOP", "BUFFER+8")
+pub fn disassemble_at_symbolic(
+    memory: &Memory,
+    address: u32,
+    formatter: &AddressFormatter,
+) -> Disassembly {
+    let mut words = [0u16; MAX_INSTRUCTION_WORDS];
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = memory.read_word(address.wrapping_add(2 * i as u32) & 0x00FF_FFFE);
+    }
+    disassemble_symbolic(&words, address, formatter)
+}
+
+/// Disassembliert die Instruktion am Anfang von `words`. Fehlende Extension Words
+/// werden als 0 gelesen.
+pub fn disassemble(words: &[u16]) -> Disassembly {
+    disassemble_with(words, 0, None)
+}
+
+/// Wie `disassemble` für die Instruktion an `pc`; absolute und PC-relative Adressen sowie
+/// Sprungziele erscheinen über `formatter`, sofern ein Label passt
+pub fn disassemble_symbolic(words: &[u16], pc: u32, formatter: &AddressFormatter) -> Disassembly {
+    disassemble_with(words, pc, Some(formatter))
+}
+
+fn disassemble_with(words: &[u16], pc: u32, formatter: Option<&AddressFormatter>) -> Disassembly {
+    let mut reader = Reader {
+        words,
+        position: 1,
+        pc,
+        formatter,
+    };
+    let opcode = words.first().copied().unwrap_or(0);
+    let text = decode(opcode, &mut reader);
+    Disassembly {
+        text,
+        length: 2 * reader.position as u32,
+    }
+}
+
+struct Reader<'a> {
+    words: &'a [u16],
+    position: usize,
+    pc: u32,
+    formatter: Option<&'a AddressFormatter>,
+}
+
+impl Reader<'_> {
+    fn next(&mut self) -> u16 {
+        let word = self.words.get(self.position).copied().unwrap_or(0);
+        self.position += 1;
+        word
+    }
+
+    fn next_long(&mut self) -> u32 {
+        let high = self.next() as u32;
+        (high << 16) | self.next() as u32
+    }
+
+    /// Adresse des nächsten Extension Words
+    fn address(&self) -> u32 {
+        self.pc.wrapping_add(2 * self.position as u32)
+    }
+
+    /// Label für `address`, falls ein Formatter gesetzt ist und eines passt
+    fn symbol(&self, address: u32) -> Option<String> {
+        self.formatter?.format(address & 0x00FF_FFFF)
+    }
+
+    /// Absolute Adresse als Label oder wie bisher als $-Hexzahl
+    fn absolute(&self, address: u32, text: String) -> String {
+        self.symbol(address).unwrap_or(text)
+    }
+}
+
+fn decode(opcode: u16, reader: &mut Reader) -> String {
+    let reg = opcode & 0x7;
+    let mode = (opcode >> 3) & 0x7;
+    let upper_reg = (opcode >> 9) & 0x7;
+
+    match opcode >> 12 {
+        0x0 if matches!(opcode, 0x003C | 0x023C | 0x0A3C) => {
+            let name = match opcode {
+                0x003C => "ORI",
+                0x023C => "ANDI",
+                _ => "EORI",
+            };
+            format!("{} #${:X}, CCR", name, reader.next() & 0xFF)
+        }
+        0x0 if opcode & 0xFF00 == 0x0C00 => match size_field(opcode >> 6) {
+            Some(size) => {
+                let immediate = immediate(size, reader);
+                let ea = effective_address(mode, reg, size, reader);
+                format!("CMPI.{} {}, {}", size.suffix(), immediate, ea)
+            }
+            None => unknown(opcode),
+        },
+        0x1..=0x3 => decode_move(opcode, reader),
+        0x4 => decode_misc(opcode, reader),
+        0x5 if opcode & 0x00F8 == 0x00C8 => {
+            let base = reader.address();
+            let displacement = reader.next() as i16;
+            match reader.symbol(base.wrapping_add(displacement as u32)) {
+                Some(target) => format!("DBRA D{}, {}", reg, target),
+                None => format!("DBRA D{}, {:+}", reg, displacement),
+            }
+        }
+        0x5 => match size_field(opcode >> 6) {
+            // ADDQ.B/SUBQ.B auf ein Adressregister gibt es nicht
+            Some(Size::Byte) if mode == 1 => unknown(opcode),
+            Some(size) => {
+                let data = if upper_reg == 0 { 8 } else { upper_reg };
+                let name = if opcode & 0x0100 != 0 { "SUBQ" } else { "ADDQ" };
+                let ea = effective_address(mode, reg, size, reader);
+                format!("{}.{} #{}, {}", name, size.suffix(), data, ea)
+            }
+            None => unknown(opcode),
+        },
+        0x6 => {
+            let condition = (opcode >> 8) & 0xF;
+            let displacement = (opcode & 0xFF) as i8;
+            let target = reader.pc.wrapping_add(2).wrapping_add(displacement as u32);
+            match reader.symbol(target) {
+                Some(target) => format!("{} {}", CONDITIONS[condition as usize], target),
+                None => format!("{} {:+}", CONDITIONS[condition as usize], displacement),
+            }
+        }
+        0x7 => format!("MOVEQ #{}, D{}", (opcode & 0xFF) as i8, upper_reg),
+        0x8 => decode_or(opcode, reader),
+        0xA if opcode == SIMHALT_OPCODE => "SIMHALT".to_string(),
+        0xC if opcode & 0x01C0 == 0x01C0 => {
+            // MULS.W #imm liest wie die CPU nur ein Extension Word
+            let source = effective_address(mode, reg, Size::Word, reader);
+            format!("MULS.W {}, D{}", source, upper_reg)
+        }
+        0x9 | 0xB | 0xD => decode_arithmetic(opcode, reader),
+        0xE if opcode & 0x0118 == 0x0100 => match size_field(opcode >> 6) {
+            Some(size) => {
+                let count = if upper_reg == 0 { 8 } else { upper_reg };
+                format!("ASL.{} #{}, D{}", size.suffix(), count, reg)
+            }
+            None => unknown(opcode),
+        },
+        _ => unknown(opcode),
+    }
+}
+
+/// Bcc-Mnemonics nach Bedingungsfeld (Bits 11-8)
+pub const CONDITIONS: [&str; 16] = [
+    "BRA", "BSR", "BHI", "BLS", "BCC", "BCS", "BNE", "BEQ", "BVC", "BVS", "BPL", "BMI", "BGE",
+    "BLT", "BGT", "BLE",
+];
+
+fn decode_move(opcode: u16, reader: &mut Reader) -> String {
+    let size = match opcode >> 12 {
+        1 => Size::Byte,
+        3 => Size::Word,
+        _ => Size::Long,
+    };
+    let dest_reg = (opcode >> 9) & 0x7;
+    let dest_mode = (opcode >> 6) & 0x7;
+    let src_mode = (opcode >> 3) & 0x7;
+    let src_reg = opcode & 0x7;
+
+    let source = effective_address(src_mode, src_reg, size, reader);
+    let dest = effective_address(dest_mode, dest_reg, size, reader);
+    let name = if dest_mode == 1 { "MOVEA" } else { "MOVE" };
+    format!("{}.{} {}, {}", name, size.suffix(), source, dest)
+}
+
+fn decode_misc(opcode: u16, reader: &mut Reader) -> String {
+    let reg = opcode & 0x7;
+    let mode = (opcode >> 3) & 0x7;
+
+    match opcode {
+        0x4E71 => "NOP".to_string(),
+        0x4E72 => format!("STOP #${:04X}", reader.next()),
+        0x4E73 => "RTE".to_string(),
+        0x4EF8 => {
+            let address = reader.next();
+            let target = address as i16 as u32;
+            format!("JMP {}", reader.absolute(target, format!("${:X}", address)))
+        }
+        _ if opcode & 0xFFF8 == 0x4ED0 => format!("JMP (A{})", reg),
+        _ if opcode & 0xFF00 == 0x4A00 => match size_field(opcode >> 6) {
+            Some(size) => format!(
+                "TST.{} {}",
+                size.suffix(),
+                effective_address(mode, reg, size, reader)
+            ),
+            None => unknown(opcode),
+        },
+        _ => unknown(opcode),
+    }
+}
+
+// OR, DIVU/DIVS, SBCD (0x8)
+fn decode_or(opcode: u16, reader: &mut Reader) -> String {
+    let upper_reg = (opcode >> 9) & 0x7;
+    let opmode = (opcode >> 6) & 0x7;
+    let mode = (opcode >> 3) & 0x7;
+    let reg = opcode & 0x7;
+
+    match opmode {
+        3 | 7 => {
+            let name = if opmode == 7 { "DIVS" } else { "DIVU" };
+            let source = effective_address(mode, reg, Size::Word, reader);
+            format!("{}.W {}, D{}", name, source, upper_reg)
+        }
+        4 if mode == 0 => format!("SBCD D{}, D{}", reg, upper_reg),
+        4 if mode == 1 => format!("SBCD -(A{}), -(A{})", reg, upper_reg),
+        _ => {
+            let size = size_field(opmode).unwrap_or(Size::Word);
+            let ea = effective_address(mode, reg, size, reader);
+            if opmode < 3 {
+                format!("OR.{} {}, D{}", size.suffix(), ea, upper_reg)
+            } else {
+                format!("OR.{} D{}, {}", size.suffix(), upper_reg, ea)
+            }
+        }
+    }
+}
+
+// SUB (0x9), CMP/CMPA (0xB), ADD (0xD)
+fn decode_arithmetic(opcode: u16, reader: &mut Reader) -> String {
+    let upper_reg = (opcode >> 9) & 0x7;
+    let opmode = (opcode >> 6) & 0x7;
+    let mode = (opcode >> 3) & 0x7;
+    let reg = opcode & 0x7;
+    let name = match opcode >> 12 {
+        0x9 => "SUB",
+        0xB => "CMP",
+        _ => "ADD",
+    };
+
+    match opmode {
+        0..=2 => {
+            let size = size_field(opmode).unwrap_or(Size::Word);
+            let source = effective_address(mode, reg, size, reader);
+            format!("{}.{} {}, D{}", name, size.suffix(), source, upper_reg)
+        }
+        3 | 7 => {
+            let size = if opmode == 3 { Size::Word } else { Size::Long };
+            let source = effective_address(mode, reg, size, reader);
+            format!("{}A.{} {}, A{}", name, size.suffix(), source, upper_reg)
+        }
+        _ => unknown(opcode),
+    }
+}
+
+fn size_field(bits: u16) -> Option<Size> {
+    match bits & 0x3 {
+        0 => Some(Size::Byte),
+        1 => Some(Size::Word),
+        2 => Some(Size::Long),
+        _ => None,
+    }
+}
+
+fn immediate(size: Size, reader: &mut Reader) -> String {
+    match size {
+        Size::Byte => format!("#${:X}", reader.next() & 0xFF),
+        Size::Word => format!("#${:X}", reader.next()),
+        Size::Long => format!("#${:X}", reader.next_long()),
+    }
+}
+
+fn effective_address(mode: u16, reg: u16, size: Size, reader: &mut Reader) -> String {
+    match mode {
+        0 => format!("D{}", reg),
+        1 => format!("A{}", reg),
+        2 => format!("(A{})", reg),
+        3 => format!("(A{})+", reg),
+        4 => format!("-(A{})", reg),
+        5 => format!("{}(A{})", reader.next() as i16, reg),
+        6 => {
+            let extension = reader.next();
+            format!(
+                "{}(A{}, {}{})",
+                (extension & 0xFF) as i8,
+                reg,
+                if extension & 0x8000 != 0 { "A" } else { "D" },
+                (extension >> 12) & 0x7
+            )
+        }
+        _ => match reg {
+            0 => {
+                let address = reader.next();
+                reader.absolute(address as i16 as u32, format!("${:X}", address))
+            }
+            1 => {
+                let address = reader.next_long();
+                reader.absolute(address, format!("${:X}", address))
+            }
+            2 => {
+                let base = reader.address();
+                let displacement = reader.next() as i16;
+                match reader.symbol(base.wrapping_add(displacement as u32)) {
+                    Some(target) => format!("{}(PC)", target),
+                    None => format!("{}(PC)", displacement),
+                }
+            }
+            4 => immediate(size, reader),
+            _ => "?".to_string(),
+        },
+    }
+}
+
+fn unknown(opcode: u16) -> String {
+    format!("DC.W ${:04X}", opcode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble_assembler_output() {
+        let cases: &[(&[u16], &str, u32)] = &[
+            (&[0x702A], "MOVEQ #42, D0", 2),
+            (&[0x3200], "MOVE.W D0, D1", 2),
+            (&[0x21FC, 0x0000, 0x0010, 0x0800], "MOVE.L #$10, $800", 8),
+            (&[0x207C, 0x0000, 0x0800], "MOVEA.L #$800, A0", 6),
+            (&[0x227C, 0x1234, 0x5678], "MOVEA.L #$12345678, A1", 6),
+            (&[0x2250], "MOVEA.L (A0), A1", 2),
+            (&[0x3569, 0x0004, 0xFFF8], "MOVE.W 4(A1), -8(A2)", 6),
+            (&[0x0C80, 0x1234, 0x5678], "CMPI.L #$12345678, D0", 6),
+            (&[0xB3C8], "CMPA.L A0, A1", 2),
+            (&[0xB078, 0x0800], "CMP.W $800, D0", 4),
+            (&[0x5381], "SUBQ.L #1, D1", 2),
+            (&[0x66FC], "BNE -4", 2),
+            (&[0xA000], "SIMHALT", 2),
+            (&[0x4E72, 0x2700], "STOP #$2700", 4),
+            (&[0x4E73], "RTE", 2),
+            (&[0x4ED1], "JMP (A1)", 2),
+            (&[0xC1FC, 0x0002], "MULS.W #$2, D0", 4),
+            (&[0x8041], "OR.W D1, D0", 2),
+            (&[0x8350], "OR.W D1, (A0)", 2),
+            (&[0x80FC, 0x0007], "DIVU.W #$7, D0", 4),
+            (&[0x83C2], "DIVS.W D2, D1", 2),
+            (&[0x8109], "SBCD -(A1), -(A0)", 2),
+            (&[0x003C, 0x0004], "ORI #$4, CCR", 4),
+            (&[0x023C, 0x00FB], "ANDI #$FB, CCR", 4),
+            (&[0x0A3C, 0x0001], "EORI #$1, CCR", 4),
+            (&[0xFFFF], "DC.W $FFFF", 2),
+        ];
+
+        for (words, text, length) in cases {
+            let disassembly = disassemble(words);
+            assert_eq!(disassembly.text, *text, "{:04X?}", words);
+            assert_eq!(disassembly.length, *length, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_disassemble_symbolic_targets() {
+        let source = "        ORG     $1000
+LOOP:   MOVE.W  $1018, D0
+        DBRA    D0, LOOP
+        BNE     LOOP
+        JMP     $1100
+        SIMHALT
+BUFFER: DS.B    16";
+        let mut memory = Memory::new();
+        let program = crate::Assembler::new().assemble_source(source).unwrap();
+        for (address, word) in &program.image {
+            memory.write_word(*address, *word);
+        }
+        let formatter = AddressFormatter::new(&program);
+        let text = |address| disassemble_at_symbolic(&memory, address, &formatter).text;
+        assert_eq!(text(0x1000), "MOVE.W BUFFER+8, D0");
+        assert_eq!(text(0x1004), "DBRA D0, LOOP");
+        assert_eq!(text(0x1008), "BNE LOOP");
+        // Kein passendes Label: Hexzahl wie ohne Formatter
+        assert_eq!(text(0x100A), "JMP $1100");
+        assert_eq!(disassemble_at(&memory, 0x1008).text, "BNE -10");
+    }
+}
@@ -0,0 +1,328 @@
+// Einfaches Blockgerät (Disk) mit DMA-artiger Übertragung
+// Das Programm schreibt Blocknummer, Zieladresse und Anzahl in die Register und startet
+// mit einem Schreibzugriff auf COMMAND. Nach `latency` Zyklen pro Block kopiert das Gerät
+// die Blöcke direkt in den (bzw. aus dem) Speicher, setzt STATUS und fordert auf Wunsch
+// einen Interrupt an. Alle Register sind Langwörter.
+
+use crate::bus::{Device, IrqRequest};
+use crate::cpu::{AccessKind, MemAccess};
+use crate::memory::Memory;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+pub const BLOCK_SIZE: usize = 512;
+
+/// Registerabstände zur Basisadresse
+pub const REG_BLOCK: u32 = 0x00;
+pub const REG_ADDRESS: u32 = 0x04;
+pub const REG_COUNT: u32 = 0x08;
+pub const REG_COMMAND: u32 = 0x0C;
+pub const REG_STATUS: u32 = 0x10;
+pub const REG_CONTROL: u32 = 0x14;
+const REGISTER_BYTES: u32 = 0x18;
+
+pub const COMMAND_READ: u32 = 1;
+pub const COMMAND_WRITE: u32 = 2;
+
+/// STATUS-Bits; ein Schreibzugriff auf STATUS löscht DONE und ERROR
+pub const STATUS_BUSY: u32 = 0x1;
+pub const STATUS_DONE: u32 = 0x2;
+pub const STATUS_ERROR: u32 = 0x4;
+
+/// CONTROL-Bit: Interrupt bei Abschluss (auch bei Fehler)
+pub const CONTROL_IRQ: u32 = 0x1;
+
+/// Laufende Übertragung
+#[derive(Debug, Clone, Copy)]
+struct Transfer {
+    command: u32,
+    block: u32,
+    address: u32,
+    count: u32,
+    remaining: u32, // Zyklen bis zum Abschluss
+}
+
+pub struct Disk {
+    base: u32,
+    data: Vec<u8>,
+    file: Option<File>, // geschriebene Blöcke werden hierhin zurückgeschrieben
+    latency: u32,
+    level: u8,
+    transfer: Option<Transfer>,
+    irq: bool, // Abschluss gemeldet, Interrupt beim nächsten Tick anfordern
+}
+
+impl Disk {
+    /// Standard-Basisadresse; `Rng` und `Uart` folgen bei $E100 und $E200
+    pub const DEFAULT_BASE: u32 = 0xE000;
+
+    /// Leere Disk mit `blocks` Blöcken im Speicher
+    pub fn new(base: u32, blocks: usize) -> Self {
+        Self::with_data(base, vec![0; blocks * BLOCK_SIZE])
+    }
+
+    /// Disk mit vorgegebenem Inhalt; wird auf ganze Blöcke aufgefüllt
+    pub fn with_data(base: u32, mut data: Vec<u8>) -> Self {
+        data.resize(data.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+        Disk {
+            base,
+            data,
+            file: None,
+            latency: 1000,
+            level: 2,
+            transfer: None,
+            irq: false,
+        }
+    }
+
+    /// Disk aus einer Image-Datei; WRITE-Kommandos schreiben in die Datei zurück
+    pub fn open(base: u32, path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::options().read(true).write(true).open(path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        let mut disk = Self::with_data(base, data);
+        disk.file = Some(file);
+        Ok(disk)
+    }
+
+    /// Zyklen pro übertragenem Block (0 = Abschluss beim nächsten Tick)
+    pub fn set_latency(&mut self, cycles_per_block: u32) {
+        self.latency = cycles_per_block;
+    }
+
+    pub fn set_irq_level(&mut self, level: u8) {
+        self.level = level;
+    }
+
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    pub fn block_count(&self) -> u32 {
+        (self.data.len() / BLOCK_SIZE) as u32
+    }
+
+    pub fn block(&self, block: u32) -> Option<&[u8]> {
+        let start = block as usize * BLOCK_SIZE;
+        self.data.get(start..start + BLOCK_SIZE)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.transfer.is_some()
+    }
+
+    fn register(&self, memory: &Memory, offset: u32) -> u32 {
+        memory.read_long(self.base + offset)
+    }
+
+    fn set_status(&self, memory: &mut Memory, status: u32) {
+        memory.write_long(self.base + REG_STATUS, status);
+    }
+
+    fn hits(&self, access: &MemAccess, offset: u32) -> bool {
+        let start = self.base + offset;
+        let end = access.addr + access.size.bytes();
+        access.addr < start + 4 && start < end
+    }
+
+    /// COMMAND wurde geschrieben: Parameter prüfen und Übertragung starten
+    fn start(&mut self, memory: &mut Memory) {
+        if self.transfer.is_some() {
+            return; // läuft schon; Kommando wird ignoriert
+        }
+        let command = self.register(memory, REG_COMMAND);
+        let block = self.register(memory, REG_BLOCK);
+        let address = self.register(memory, REG_ADDRESS);
+        let count = self.register(memory, REG_COUNT);
+
+        let in_range = count > 0
+            && block
+                .checked_add(count)
+                .is_some_and(|end| end <= self.block_count());
+        if !matches!(command, COMMAND_READ | COMMAND_WRITE) || !in_range {
+            self.finish(memory, STATUS_ERROR);
+            return;
+        }
+
+        self.set_status(memory, STATUS_BUSY);
+        self.transfer = Some(Transfer {
+            command,
+            block,
+            address,
+            count,
+            remaining: self.latency.saturating_mul(count),
+        });
+    }
+
+    /// Kopiert die Blöcke; Fehler beim Zurückschreiben in die Datei melden ERROR
+    fn complete(&mut self, transfer: Transfer, memory: &mut Memory) -> u32 {
+        let start = transfer.block as usize * BLOCK_SIZE;
+        let len = transfer.count as usize * BLOCK_SIZE;
+        if transfer.command == COMMAND_READ {
+            for (i, byte) in self.data[start..start + len].iter().enumerate() {
+                memory.write_byte(transfer.address.wrapping_add(i as u32), *byte);
+            }
+            return STATUS_DONE;
+        }
+
+        for i in 0..len {
+            self.data[start + i] = memory.read_byte(transfer.address.wrapping_add(i as u32));
+        }
+        if let Some(file) = &mut self.file {
+            let written = file
+                .seek(SeekFrom::Start(start as u64))
+                .and_then(|_| file.write_all(&self.data[start..start + len]))
+                .and_then(|_| file.flush());
+            if written.is_err() {
+                return STATUS_DONE | STATUS_ERROR;
+            }
+        }
+        STATUS_DONE
+    }
+
+    fn finish(&mut self, memory: &mut Memory, status: u32) {
+        self.set_status(memory, status);
+        self.irq = self.register(memory, REG_CONTROL) & CONTROL_IRQ != 0;
+    }
+}
+
+impl Device for Disk {
+    fn name(&self) -> &str {
+        "disk"
+    }
+
+    fn registers(&self) -> Option<Range<u32>> {
+        Some(self.base..self.base + REGISTER_BYTES)
+    }
+
+    fn access(&mut self, access: &MemAccess, memory: &mut Memory) {
+        if access.kind != AccessKind::Write {
+            return;
+        }
+        if self.hits(access, REG_COMMAND) {
+            self.start(memory);
+        } else if self.hits(access, REG_STATUS) {
+            // Quittieren: nur BUSY bleibt stehen
+            let busy = if self.is_busy() { STATUS_BUSY } else { 0 };
+            self.set_status(memory, busy);
+        }
+    }
+
+    fn tick(&mut self, cycles: u32, memory: &mut Memory) -> Option<IrqRequest> {
+        if let Some(mut transfer) = self.transfer {
+            transfer.remaining = transfer.remaining.saturating_sub(cycles);
+            self.transfer = Some(transfer);
+            if transfer.remaining == 0 {
+                self.transfer = None;
+                let status = self.complete(transfer, memory);
+                self.finish(memory, status);
+            }
+        }
+        if std::mem::take(&mut self.irq) {
+            return Some(IrqRequest::autovector(self.level));
+        }
+        None
+    }
+
+    fn reset(&mut self, _memory: &mut Memory) {
+        self.transfer = None;
+        self.irq = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::EaDescription;
+    use crate::isa::Size;
+
+    const BASE: u32 = Disk::DEFAULT_BASE;
+
+    /// Register setzen und COMMAND schreiben, wie es die CPU täte
+    fn command(disk: &mut Disk, memory: &mut Memory, command: u32, block: u32, count: u32) {
+        memory.write_long(BASE + REG_BLOCK, block);
+        memory.write_long(BASE + REG_ADDRESS, 0x2000);
+        memory.write_long(BASE + REG_COUNT, count);
+        memory.write_long(BASE + REG_COMMAND, command);
+        let access = MemAccess {
+            addr: BASE + REG_COMMAND,
+            size: Size::Long,
+            kind: AccessKind::Write,
+            value: command,
+            ea_description: EaDescription::Indirect { reg: 0 },
+        };
+        disk.access(&access, memory);
+    }
+
+    #[test]
+    fn test_read_copies_blocks_after_latency() {
+        let mut image = vec![0u8; 3 * BLOCK_SIZE];
+        image[BLOCK_SIZE..BLOCK_SIZE + 4].copy_from_slice(b"M68K");
+        image[3 * BLOCK_SIZE - 1] = 0x55;
+        let mut disk = Disk::with_data(BASE, image);
+        disk.set_latency(100);
+        let mut memory = Memory::new();
+
+        command(&mut disk, &mut memory, COMMAND_READ, 1, 2);
+        assert_eq!(memory.read_long(BASE + REG_STATUS), STATUS_BUSY);
+        assert_eq!(disk.tick(150, &mut memory), None);
+        assert_eq!(memory.read_long(0x2000), 0, "copied before the latency");
+
+        disk.tick(50, &mut memory);
+        assert_eq!(memory.read_long(BASE + REG_STATUS), STATUS_DONE);
+        assert_eq!(memory.read_long(0x2000), 0x4D36_384B);
+        assert_eq!(memory.read_byte(0x2000 + 2 * BLOCK_SIZE as u32 - 1), 0x55);
+    }
+
+    #[test]
+    fn test_write_stores_memory_in_block() {
+        let mut disk = Disk::new(BASE, 4);
+        disk.set_latency(0);
+        let mut memory = Memory::new();
+        memory.write_long(0x2000, 0xCAFE_F00D);
+
+        command(&mut disk, &mut memory, COMMAND_WRITE, 3, 1);
+        disk.tick(4, &mut memory);
+        assert_eq!(memory.read_long(BASE + REG_STATUS), STATUS_DONE);
+        assert_eq!(&disk.block(3).unwrap()[..4], &[0xCA, 0xFE, 0xF0, 0x0D]);
+        assert_eq!(disk.block(2).unwrap()[0], 0);
+    }
+
+    #[test]
+    fn test_out_of_range_and_unknown_commands_fail() {
+        let mut disk = Disk::new(BASE, 4);
+        let mut memory = Memory::new();
+
+        command(&mut disk, &mut memory, COMMAND_READ, 3, 2);
+        assert_eq!(memory.read_long(BASE + REG_STATUS), STATUS_ERROR);
+        assert!(!disk.is_busy());
+        command(&mut disk, &mut memory, COMMAND_READ, u32::MAX, 1);
+        assert_eq!(memory.read_long(BASE + REG_STATUS), STATUS_ERROR);
+        command(&mut disk, &mut memory, 7, 0, 1);
+        assert_eq!(memory.read_long(BASE + REG_STATUS), STATUS_ERROR);
+        command(&mut disk, &mut memory, COMMAND_READ, 0, 0);
+        assert_eq!(memory.read_long(BASE + REG_STATUS), STATUS_ERROR);
+    }
+
+    #[test]
+    fn test_completion_raises_interrupt_when_enabled() {
+        let mut disk = Disk::new(BASE, 1);
+        disk.set_latency(10);
+        disk.set_irq_level(3);
+        let mut memory = Memory::new();
+
+        command(&mut disk, &mut memory, COMMAND_READ, 0, 1);
+        assert_eq!(disk.tick(10, &mut memory), None, "interrupts disabled");
+
+        memory.write_long(BASE + REG_CONTROL, CONTROL_IRQ);
+        command(&mut disk, &mut memory, COMMAND_READ, 0, 1);
+        assert_eq!(disk.tick(10, &mut memory), Some(IrqRequest::autovector(3)));
+        assert_eq!(disk.tick(10, &mut memory), None, "only once");
+
+        // Fehler werden ebenfalls gemeldet
+        command(&mut disk, &mut memory, COMMAND_READ, 5, 1);
+        assert_eq!(disk.tick(1, &mut memory), Some(IrqRequest::autovector(3)));
+    }
+}
@@ -0,0 +1,1304 @@
+// Emulator-Fassade: CPU, Speicher, Assembler und das geladene Programm an einem Ort
+
+use crate::address_format::AddressFormatter;
+use crate::address_map::{self, MapEntry};
+use crate::assembler::{AsmError, Assembler};
+use crate::breakpoints::{AssertionFailure, BreakpointStore, Location, Watch};
+use crate::bus::{Device, IrqRequest, SystemBus};
+use crate::cpu::{CpuSnapshot, Exception, ExecResult, Registers, CPU, CPU_CLOCK_HZ};
+use crate::journal::{StepRecord, WriteJournal};
+use crate::memory::Memory;
+use crate::profiler::{Profile, ProfileSample};
+use crate::program::{Program, SectionKind};
+use crate::rng::Rng;
+use crate::strictness::{Strictness, StrictnessProfile};
+use crate::uart::{Uart, UartHost};
+use std::fmt;
+
+/// CPU, Speicher und Assembler mit dem aktuell geladenen Programm
+///
+/// ```
+/// use mc68000::Emulator;
+///
+/// let mut emulator = Emulator::new();
+/// emulator
+///     .load_source("        ORG $1000\n        MOVEQ #3, D1\n        SIMHALT")
+///     .expect("program assembles");
+/// assert_eq!(emulator.cpu().get_pc(), 0x1000);
+///
+/// while !emulator.cpu().is_halted() {
+///     emulator.step();
+/// }
+/// assert_eq!(emulator.cpu().get_data_register(1), 3);
+///
+/// // Fehler kommen als Liste zurück, das geladene Programm bleibt erhalten
+/// assert!(emulator.load_source("MOVEQ #3").is_err());
+/// assert!(emulator.program().is_some());
+/// ```
+pub struct Emulator {
+    cpu: CPU,
+    memory: Memory,
+    assembler: Assembler,
+    program: Option<Program>,
+    formatter: AddressFormatter, // Symboltabelle von `program` für Disassembly und Trace
+    breakpoints: BreakpointStore,
+    watches: Vec<Watch>,
+    idle_detection: bool,
+    idle_detector: IdleLoopDetector,
+    idle_loop: Option<Vec<u32>>,
+    steps: u64, // ausgeführte Instruktionen seit dem letzten Reset
+    bus: SystemBus,
+    interrupts: u64, // angenommene Interrupts seit dem letzten Reset
+    last_interrupt: Option<IrqRequest>, // vor der letzten Instruktion angenommen
+    // Vorgaben aus GUI/Kommandozeile; haben Vorrang vor END und STACK im Quelltext
+    entry_override: Option<u32>,
+    stack_override: Option<u32>,
+    data_execution_check: bool, // vor Instruktionen in Data-Sections anhalten
+    rng_seed: Option<u32>,      // Seed des angeschlossenen Zufallsgenerators
+    clock_hz: u32,              // CPU-Takt für Geräte und emulierte Zeit
+    journal: WriteJournal,      // für step_back
+    profile: Profile,           // Zyklen pro Quellzeile seit dem letzten Reset
+    assertion_failures: Vec<AssertionFailure>, // seit dem letzten Reset
+}
+
+/// Schritte ohne neuen Zustand, nach denen eine Leerlaufschleife gemeldet wird
+pub const IDLE_LOOP_THRESHOLD: u32 = 1000;
+/// Höchstens so viele verschiedene (PC, Register)-Zustände bilden eine Leerlaufschleife
+const IDLE_LOOP_MAX_STATES: usize = 4;
+
+/// Scratch-Bereich für `Emulator::run_fragment`: die obersten 64 KB des Adressraums
+pub const FRAGMENT_BASE: u32 = 0x00FF_0000;
+/// Höchstens so viele Instruktionen führt ein Fragment aus
+pub const FRAGMENT_STEP_LIMIT: u64 = 10_000;
+
+/// Ergebnis von `Emulator::run_fragment`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FragmentResult {
+    /// Zustand nach dem letzten ausgeführten Schritt
+    pub registers: CpuSnapshot,
+    pub outcome: RunOutcome,
+    pub steps: u64,
+    /// true, wenn das Fragment bis zum angehängten Halt gelaufen ist
+    pub completed: bool,
+}
+
+/// Ergebnis von `Emulator::step_back`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepBack {
+    /// PC nach dem Zurückgehen (Adresse der rückgängig gemachten Instruktion)
+    pub pc: u32,
+    pub writes_undone: usize,
+    /// Geräte liefen in diesem Schritt mit; ihr Zustand ist nicht zurückgesetzt
+    pub devices_ticked: bool,
+}
+
+/// Ergebnis von `Emulator::run`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunOutcome {
+    Halted,
+    /// Die CPU wurde durch eine Exception angehalten
+    Exception(Exception),
+    StepLimit(u64),
+    /// Die CPU dreht sich ohne Speicherzugriffe oder Geräteaktivität im Kreis
+    IdleLoopDetected {
+        pcs: Vec<u32>,
+    },
+    /// Der PC steht in einer Data-Section (siehe `Emulator::set_data_execution_check`)
+    DataExecution(DataExecution),
+}
+
+/// Der PC ist in eine Data-Section gelaufen, meist fehlt ein SIMHALT davor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataExecution {
+    pub pc: u32,
+    pub section: String,
+}
+
+impl fmt::Display for DataExecution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "PC entered data section {} at ${:06X} — missing halt?",
+            self.section, self.pc
+        )
+    }
+}
+
+/// Erkennt enge Endlosschleifen wie `END: BRA END`: dieselben wenigen Zustände wiederholen
+/// sich, ohne dass Speicher geschrieben wird oder ein Gerät sich meldet.
+#[derive(Debug, Clone, Default)]
+struct IdleLoopDetector {
+    states: Vec<Registers>,
+    writes: u64,
+    steps: u32,
+}
+
+impl IdleLoopDetector {
+    fn reset(&mut self) {
+        self.states.clear();
+        self.steps = 0;
+    }
+
+    /// Nach jedem Schritt aufrufen; liefert die PCs der Schleife, sobald sie lange genug läuft
+    fn observe(&mut self, state: Registers, writes: u64) -> Option<Vec<u32>> {
+        let known = self.states.contains(&state);
+        if writes != self.writes || (!known && self.states.len() == IDLE_LOOP_MAX_STATES) {
+            self.reset();
+            self.writes = writes;
+        }
+        if !known || self.states.is_empty() {
+            self.states.push(state);
+        }
+        self.steps += 1;
+
+        if self.steps < IDLE_LOOP_THRESHOLD {
+            return None;
+        }
+        let mut pcs: Vec<u32> = self.states.iter().map(|state| state.pc).collect();
+        pcs.sort_unstable();
+        pcs.dedup();
+        Some(pcs)
+    }
+}
+
+/// Ergebnis von `Emulator::reload_code`
+#[derive(Debug, Clone, Default)]
+pub struct ReloadReport {
+    pub code_words_written: usize,
+    /// true, wenn die CPU an der entsprechenden Stelle im neuen Code weiterläuft
+    pub pc_preserved: bool,
+    pub warnings: Vec<String>,
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Emulator {
+    pub fn new() -> Self {
+        Emulator {
+            cpu: CPU::new(),
+            memory: Memory::new(),
+            assembler: Assembler::new(),
+            program: None,
+            formatter: AddressFormatter::default(),
+            breakpoints: BreakpointStore::new(),
+            watches: Vec::new(),
+            idle_detection: true,
+            idle_detector: IdleLoopDetector::default(),
+            idle_loop: None,
+            steps: 0,
+            bus: SystemBus::new(),
+            interrupts: 0,
+            last_interrupt: None,
+            entry_override: None,
+            stack_override: None,
+            data_execution_check: true,
+            rng_seed: None,
+            clock_hz: CPU_CLOCK_HZ,
+            journal: WriteJournal::default(),
+            profile: Profile::new(),
+            assertion_failures: Vec::new(),
+        }
+    }
+
+    pub fn cpu(&self) -> &CPU {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut CPU {
+        &mut self.cpu
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    pub fn assembler(&self) -> &Assembler {
+        &self.assembler
+    }
+
+    /// Alte SIMHALT-Kodierung $4E72 in Assembler und CPU (veraltet, siehe
+    /// `Assembler::set_legacy_simhalt`)
+    pub fn set_legacy_simhalt(&mut self, enabled: bool) {
+        self.assembler.set_legacy_simhalt(enabled);
+        self.cpu.set_legacy_simhalt(enabled);
+    }
+
+    /// Peephole-Optimierung beim Assemblieren (siehe `Assembler::set_optimize`)
+    pub fn set_optimize(&mut self, enabled: bool) {
+        self.assembler.set_optimize(enabled);
+    }
+
+    /// Setzt alle Schalter des Profils; jeder lässt sich danach einzeln wieder umstellen.
+    /// Die Assembler-Schalter wirken beim nächsten Laden.
+    ///
+    /// ```
+    /// use mc68000::{Emulator, StrictnessProfile};
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_profile(StrictnessProfile::Fast);
+    /// assert_eq!(emulator.strictness_profile(), Some(StrictnessProfile::Fast));
+    /// emulator.set_idle_loop_detection(true);
+    /// assert_eq!(emulator.strictness_profile(), None);
+    /// ```
+    pub fn set_profile(&mut self, profile: StrictnessProfile) {
+        let settings = profile.settings();
+        self.cpu.set_alignment_check(settings.alignment_check);
+        self.cpu.set_rom_write_faults(settings.rom_write_faults);
+        self.set_data_execution_check(settings.data_execution_check);
+        self.set_idle_loop_detection(settings.idle_loop_detection);
+        self.assembler.set_strict_moveq(settings.strict_moveq);
+    }
+
+    /// Aktueller Stand der Schalter, die ein Profil setzt
+    pub fn strictness(&self) -> Strictness {
+        Strictness {
+            alignment_check: self.cpu.alignment_check(),
+            rom_write_faults: self.cpu.rom_write_faults(),
+            data_execution_check: self.data_execution_check,
+            idle_loop_detection: self.idle_detection,
+            strict_moveq: self.assembler.strict_moveq(),
+        }
+    }
+
+    /// Profil, zu dem alle Schalter passen; None nach einzelnen Änderungen und mit den
+    /// Standardwerten (Prüfungen wie Teaching, aber ohne Alignment-Prüfung)
+    pub fn strictness_profile(&self) -> Option<StrictnessProfile> {
+        StrictnessProfile::matching(self.strictness())
+    }
+
+    /// MOVEQ #128..255 beim Assemblieren ablehnen (siehe `Assembler::set_strict_moveq`)
+    pub fn set_strict_moveq(&mut self, enabled: bool) {
+        self.assembler.set_strict_moveq(enabled);
+    }
+
+    /// CPU-Takt, mit dem Geräte wie der UART ihr Timing berechnen und Zyklen in Zeit
+    /// umgerechnet werden (Standard `CPU_CLOCK_HZ`); gilt auch für angeschlossene Geräte
+    pub fn set_clock_hz(&mut self, hz: u32) {
+        self.clock_hz = hz.max(1);
+        self.bus.set_clock_hz(self.clock_hz);
+    }
+
+    pub fn clock_hz(&self) -> u32 {
+        self.clock_hz
+    }
+
+    pub fn bus(&self) -> &SystemBus {
+        &self.bus
+    }
+
+    /// Gerät anschließen; es läuft ab dem nächsten Schritt im CPU-Takt mit und seine
+    /// Register werden in den Adressraum eingeblendet
+    pub fn attach_device(&mut self, mut device: Box<dyn Device>) {
+        device.set_clock_hz(self.clock_hz);
+        device.reset(&mut self.memory);
+        if let Some(registers) = device.registers() {
+            self.memory.map_io(registers);
+        }
+        self.bus.attach(device);
+    }
+
+    /// Zufallsgenerator an `Rng::DEFAULT_BASE` anschließen. Der Seed bleibt abrufbar, damit
+    /// sich ein Lauf mit derselben Zahlenfolge wiederholen lässt.
+    pub fn attach_rng(&mut self, seed: u32) {
+        self.rng_seed = Some(seed);
+        self.attach_device(Box::new(Rng::new(Rng::DEFAULT_BASE, seed)));
+    }
+
+    pub fn rng_seed(&self) -> Option<u32> {
+        self.rng_seed
+    }
+
+    /// UART an `Uart::DEFAULT_BASE` anschließen; über die Host-Seite gehen Ein- und Ausgabe
+    pub fn attach_uart(&mut self, baud: u32) -> UartHost {
+        let (uart, host) = Uart::new(Uart::DEFAULT_BASE, baud);
+        self.attach_device(Box::new(uart));
+        host
+    }
+
+    /// Lässt die Geräte `cycles` Taktzyklen weiterlaufen, ohne dass die CPU eine Instruktion
+    /// ausführt (z.B. ein Frame lang, solange sie steht). Ein angeforderter Interrupt bleibt
+    /// anhängig und wird vor dem nächsten `step` angenommen.
+    pub fn tick_devices(&mut self, cycles: u32) -> Option<IrqRequest> {
+        let request = self.bus.tick(cycles, &mut self.memory);
+        if request.is_some() {
+            self.note_activity();
+        }
+        request
+    }
+
+    pub fn program(&self) -> Option<&Program> {
+        self.program.as_ref()
+    }
+
+    /// Adressen relativ zu den Labels des geladenen Programms
+    pub fn address_formatter(&self) -> &AddressFormatter {
+        &self.formatter
+    }
+
+    pub fn breakpoints(&self) -> &BreakpointStore {
+        &self.breakpoints
+    }
+
+    pub fn breakpoints_mut(&mut self) -> &mut BreakpointStore {
+        &mut self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, location: Location) -> bool {
+        self.breakpoints.add(location, self.program.as_ref())
+    }
+
+    /// Setzt oder entfernt einen Breakpoint; true, wenn er danach gesetzt ist
+    pub fn toggle_breakpoint(&mut self, location: Location) -> bool {
+        self.breakpoints.toggle(location, self.program.as_ref())
+    }
+
+    /// Löst ein Breakpoint am aktuellen PC aus (Bedingung erfüllt)? Zählt die Treffer.
+    pub fn breakpoint_hit(&mut self) -> bool {
+        let registers = self.cpu.registers();
+        self.breakpoints.hit(registers.pc, &registers)
+    }
+
+    pub fn watches(&self) -> &[Watch] {
+        &self.watches
+    }
+
+    pub fn add_watch(&mut self, mut watch: Watch) {
+        if let Some(program) = &self.program {
+            watch.resolve(program);
+        }
+        self.watches.push(watch);
+    }
+
+    /// Assembliert `source`, lädt das Abbild in einen leeren Speicher und setzt die CPU zurück.
+    /// Bei Fehlern bleibt der bisherige Zustand unverändert.
+    pub fn load_source(&mut self, source: &str) -> Result<(), Vec<AsmError>> {
+        let program = self.assembler.assemble_source(source)?;
+        self.load_program_image(&program);
+        self.install(program);
+        self.reset();
+        Ok(())
+    }
+
+    /// Lädt ein fertiges Programm, z.B. aus [`Assembler::link`], wie `load_source`
+    pub fn load_program(&mut self, program: Program) {
+        self.load_program_image(&program);
+        self.install(program);
+        self.reset();
+    }
+
+    /// Was wo liegt: RAM, Vektortabelle, Sections, Geräteregister und Stack, nach Adresse
+    /// sortiert. Wird bei jedem Aufruf neu erstellt und passt daher auch nach `reload_code`.
+    pub fn memory_map(&self) -> Vec<MapEntry> {
+        address_map::memory_map(
+            self.program.as_ref(),
+            &self.bus,
+            self.initial_sp(),
+            self.cpu.get_address_register(7),
+        )
+    }
+
+    /// Entfernt das geladene Programm (Speicher bleibt unverändert)
+    pub fn unload(&mut self) {
+        self.program = None;
+        self.formatter = AddressFormatter::default();
+    }
+
+    /// Startadresse unabhängig vom Programm (None = `END`-Operand bzw. erste Instruktion);
+    /// wirkt beim nächsten Reset
+    pub fn set_entry_override(&mut self, entry: Option<u32>) {
+        self.entry_override = entry;
+    }
+
+    pub fn entry_override(&self) -> Option<u32> {
+        self.entry_override
+    }
+
+    /// Anfangswert für A7 unabhängig von `STACK` im Quelltext; wirkt beim nächsten Reset
+    pub fn set_initial_sp_override(&mut self, sp: Option<u32>) {
+        self.stack_override = sp;
+    }
+
+    pub fn initial_sp_override(&self) -> Option<u32> {
+        self.stack_override
+    }
+
+    /// Startadresse beim Reset: Vorgabe > `END label` > erste Instruktion ab $1000
+    pub fn entry_point(&self) -> Option<u32> {
+        self.entry_override
+            .or_else(|| self.program.as_ref().and_then(|p| p.entry_point()))
+    }
+
+    /// A7 beim Reset: Vorgabe > `STACK $addr`; None lässt A7 unverändert
+    pub fn initial_sp(&self) -> Option<u32> {
+        self.stack_override
+            .or_else(|| self.program.as_ref().and_then(|p| p.initial_sp))
+    }
+
+    /// CPU zurücksetzen, PC auf den Programmstart und A7 auf den Anfangsstack setzen
+    pub fn reset(&mut self) {
+        self.note_activity();
+        self.cpu.reset();
+        self.bus.reset(&mut self.memory);
+        self.steps = 0;
+        self.interrupts = 0;
+        self.last_interrupt = None;
+        self.journal.clear();
+        self.profile.clear();
+        self.assertion_failures.clear();
+        if let Some(entry) = self.entry_point() {
+            self.cpu.set_pc(entry);
+        }
+        if let Some(sp) = self.initial_sp() {
+            self.cpu.set_stack_pointer(sp);
+        }
+    }
+
+    /// Nimmt einen anhängigen Interrupt an und führt dann eine Instruktion aus; die dabei
+    /// verbrauchten Zyklen (inklusive Interrupt-Annahme) gehen an die Geräte.
+    /// ASSERT-Direktiven vor der Instruktion werden zuerst geprüft.
+    pub fn step(&mut self) -> ExecResult {
+        self.check_assertions();
+        let cycles = self.cpu.cycles();
+        let mut record = StepRecord {
+            cpu: self.cpu.state(),
+            writes: Vec::new(),
+            steps: self.steps,
+            interrupts: self.interrupts,
+            last_interrupt: self.last_interrupt,
+            devices_ticked: self.bus.devices().next().is_some(),
+            profile: ProfileSample::default(),
+        };
+        self.memory.start_journal();
+        self.last_interrupt = None;
+        if let Some(request) = self.bus.pending() {
+            if self.cpu.interrupt(&mut self.memory, request) {
+                self.bus.acknowledge();
+                self.interrupts += 1;
+                self.last_interrupt = Some(request);
+                self.note_activity();
+            }
+        }
+
+        let overhead = (self.cpu.cycles() - cycles) as u32;
+        let result = self.cpu.execute_instruction(&mut self.memory);
+        self.steps += 1;
+        record.profile = ProfileSample {
+            line: self.profile_line(result.pc),
+            cycles: result.cycles,
+            overhead,
+        };
+        self.profile.add(record.profile);
+        // Nur Schreibzugriffe der CPU; was Geräte schreiben, gehört zu ihrem Zustand
+        record.writes = self.memory.take_journal();
+        self.journal.push(record);
+        for access in self.cpu.take_io_accesses() {
+            self.bus.access(&access, &mut self.memory);
+        }
+        self.bus
+            .tick((self.cpu.cycles() - cycles) as u32, &mut self.memory);
+
+        if self.bus.busy() {
+            self.note_activity();
+        }
+        if self.idle_detection {
+            self.idle_loop = self
+                .idle_detector
+                .observe(result.after, self.memory.write_count());
+        }
+        result
+    }
+
+    /// Macht den letzten `step` rückgängig: schreibt die alten Speicherwerte zurück und stellt
+    /// den CPU-Zustand davor wieder her. None, wenn kein Schritt mehr im Journal steht
+    /// (nach Reset, Laden oder mehr als `JOURNAL_LEN` Schritten zurück).
+    ///
+    /// Geräte werden nicht zurückgesetzt; `StepBack::devices_ticked` meldet, wenn sie
+    /// mitliefen und ihr Zustand deshalb nicht mehr zum Speicher passen kann.
+    ///
+    /// ```
+    /// use mc68000::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator
+    ///     .load_source("        ORG $1000\n        MOVE.W #7, $2000\n        SIMHALT")
+    ///     .unwrap();
+    /// emulator.step();
+    /// assert_eq!(emulator.memory().read_word(0x2000), 7);
+    ///
+    /// let back = emulator.step_back().unwrap();
+    /// assert_eq!(back.pc, 0x1000);
+    /// assert_eq!(emulator.memory().read_word(0x2000), 0);
+    /// assert!(emulator.step_back().is_none());
+    /// ```
+    pub fn step_back(&mut self) -> Option<StepBack> {
+        let record = self.journal.pop()?;
+        self.memory.undo(&record.writes);
+        self.cpu.restore_state(&record.cpu);
+        self.profile.subtract(record.profile);
+        self.steps = record.steps;
+        self.interrupts = record.interrupts;
+        self.last_interrupt = record.last_interrupt;
+        self.note_activity();
+        Some(StepBack {
+            pc: record.cpu.registers.pc,
+            writes_undone: record.writes.len(),
+            devices_ticked: record.devices_ticked,
+        })
+    }
+
+    /// Quellzeile für den Profiler; Instruktionen in Data-Sections zählen als unbekannt
+    fn profile_line(&self, pc: u32) -> Option<usize> {
+        let program = self.program.as_ref()?;
+        if program.is_data(pc) {
+            return None;
+        }
+        program.line_for_address(pc)
+    }
+
+    /// Zyklen pro Quellzeile seit dem letzten Reset
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// ASSERT-Direktiven, die seit dem letzten Reset beim Erreichen nicht galten
+    pub fn assertion_failures(&self) -> &[AssertionFailure] {
+        &self.assertion_failures
+    }
+
+    fn check_assertions(&mut self) {
+        let Some(program) = &self.program else {
+            return;
+        };
+        let assertions = program.assertions_at(self.cpu.get_pc());
+        if assertions.is_empty() {
+            return;
+        }
+        let registers = self.cpu.registers();
+        self.assertion_failures.extend(
+            assertions
+                .iter()
+                .filter_map(|assertion| assertion.check(&registers, &self.memory)),
+        );
+    }
+
+    /// Wie viele Schritte `step_back` zurückgehen kann
+    pub fn steps_back_available(&self) -> usize {
+        self.journal.len()
+    }
+
+    /// Anzahl der Instruktionen seit dem letzten Reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Angenommene Interrupts seit dem letzten Reset
+    pub fn interrupts(&self) -> u64 {
+        self.interrupts
+    }
+
+    /// Interrupt, der vor der Instruktion des letzten `step` angenommen wurde
+    pub fn last_interrupt(&self) -> Option<IrqRequest> {
+        self.last_interrupt
+    }
+
+    pub fn data_execution_check(&self) -> bool {
+        self.data_execution_check
+    }
+
+    /// Vor Instruktionen in Data-Sections anhalten (Standard). Abschalten, um bewusst
+    /// Daten auszuführen, z.B. selbstmodifizierenden Code.
+    pub fn set_data_execution_check(&mut self, enabled: bool) {
+        self.data_execution_check = enabled;
+    }
+
+    /// Meldung, falls der PC in einer Data-Section steht und die Prüfung aktiv ist
+    pub fn pending_data_execution(&self) -> Option<DataExecution> {
+        if !self.data_execution_check {
+            return None;
+        }
+        let pc = self.cpu.get_pc();
+        let section = self.program.as_ref()?.section_at(pc)?;
+        (section.kind == SectionKind::Data).then(|| DataExecution {
+            pc,
+            section: section.name.clone(),
+        })
+    }
+
+    /// Führt ein Quelltextstück isoliert aus, z.B. eine Auswahl im Editor.
+    ///
+    /// Das Stück wird ab `FRAGMENT_BASE` assembliert (Labels des geladenen Programms sind
+    /// bekannt) und um ein SIMHALT ergänzt. Es läuft auf einer Kopie des Speichers mit einer
+    /// eigenen CPU, die mit `seed` startet; Programm, Speicher und CPU des Emulators bleiben
+    /// unverändert. Geräte laufen nicht mit.
+    ///
+    /// ```
+    /// use mc68000::{CpuSnapshot, Emulator};
+    ///
+    /// let emulator = Emulator::new();
+    /// let mut seed = CpuSnapshot::default();
+    /// seed.d[1] = 5;
+    /// let result = emulator
+    ///     .run_fragment("MOVEQ #2, D0\nADD.L D1, D0", seed)
+    ///     .unwrap();
+    /// assert!(result.completed);
+    /// assert_eq!(result.registers.d[0], 7);
+    /// ```
+    pub fn run_fragment(
+        &self,
+        source: &str,
+        seed: CpuSnapshot,
+    ) -> Result<FragmentResult, Vec<AsmError>> {
+        let source = format!("{}\n        SIMHALT", source);
+        let symbols = self
+            .program
+            .as_ref()
+            .map(|program| program.symbols.clone())
+            .unwrap_or_default();
+        let mut assembler = Assembler::new();
+        assembler.set_strict_moveq(self.assembler.strict_moveq());
+        let program = assembler.assemble_fragment(&source, FRAGMENT_BASE, symbols)?;
+        let halt = program.address_for_line(source.lines().count());
+
+        let mut memory = self.memory.clone();
+        Self::write_image(&mut memory, &program);
+        let mut cpu = CPU::new();
+        cpu.set_alignment_check(self.cpu.alignment_check());
+        cpu.set_rom_write_faults(self.cpu.rom_write_faults());
+        cpu.restore(&seed);
+        cpu.set_pc(program.entry_point().unwrap_or(FRAGMENT_BASE));
+
+        let mut outcome = RunOutcome::StepLimit(FRAGMENT_STEP_LIMIT);
+        let mut steps = 0;
+        while steps < FRAGMENT_STEP_LIMIT {
+            cpu.execute_instruction(&mut memory);
+            steps += 1;
+            if let Some(exception) = cpu.exception() {
+                outcome = RunOutcome::Exception(exception);
+                break;
+            }
+            if cpu.is_halted() {
+                outcome = RunOutcome::Halted;
+                break;
+            }
+        }
+
+        Ok(FragmentResult {
+            completed: outcome == RunOutcome::Halted && Some(cpu.get_pc()) == halt,
+            registers: cpu.snapshot(),
+            outcome,
+            steps,
+        })
+    }
+
+    /// Führt bis zu `max_steps` Instruktionen aus
+    pub fn run(&mut self, max_steps: u64) -> RunOutcome {
+        for _ in 0..max_steps {
+            if let Some(hit) = self.pending_data_execution() {
+                return RunOutcome::DataExecution(hit);
+            }
+            self.step();
+            if let Some(exception) = self.cpu.exception() {
+                return RunOutcome::Exception(exception);
+            }
+            if self.cpu.is_halted() {
+                return RunOutcome::Halted;
+            }
+            if let Some(pcs) = &self.idle_loop {
+                return RunOutcome::IdleLoopDetected { pcs: pcs.clone() };
+            }
+        }
+        RunOutcome::StepLimit(max_steps)
+    }
+
+    /// PCs einer erkannten Leerlaufschleife (nach dem letzten `step`)
+    pub fn idle_loop(&self) -> Option<&[u32]> {
+        self.idle_loop.as_deref()
+    }
+
+    pub fn idle_loop_detection(&self) -> bool {
+        self.idle_detection
+    }
+
+    /// Leerlauf-Erkennung abschalten, z.B. für Programme, die absichtlich warten
+    pub fn set_idle_loop_detection(&mut self, enabled: bool) {
+        self.idle_detection = enabled;
+        self.note_activity();
+    }
+
+    /// Von Geräten und Interrupt-Quellen aufzurufen: das Programm wartet nicht vergeblich
+    pub fn note_activity(&mut self) {
+        self.idle_detector.reset();
+        self.idle_loop = None;
+    }
+
+    /// Assembliert neu und ersetzt nur den Code im Speicher.
+    ///
+    /// Daten-Sections und von Hand geänderter Speicher bleiben erhalten, solange sich ihre
+    /// Adressen nicht geändert haben. Breakpoints und Watches werden über die neue
+    /// Symboltabelle aufgelöst, und der PC bleibt auf derselben Quellzeile stehen, wenn es
+    /// sie noch gibt.
+    pub fn reload_code(&mut self, source: &str) -> Result<ReloadReport, Vec<AsmError>> {
+        let new = self.assembler.assemble_source(source)?;
+        // Alte Schreibzugriffe könnten den neuen Code überschreiben
+        self.journal.clear();
+        // Zeilennummern passen nicht mehr zum neuen Quelltext
+        self.profile.clear();
+        let Some(old) = self.program.take() else {
+            self.load_program_image(&new);
+            self.install(new);
+            self.reset();
+            return Ok(ReloadReport {
+                warnings: vec!["no program loaded; performed a full load".to_string()],
+                ..Default::default()
+            });
+        };
+
+        let mut report = ReloadReport::default();
+
+        // Alten Code entfernen, der im neuen Programm zu keiner Section mehr gehört
+        for (address, _) in old.words_in(SectionKind::Code) {
+            if new.section_at(*address).is_none() {
+                self.memory.write_word(*address, 0);
+            }
+        }
+
+        for (address, word) in new.words_in(SectionKind::Code) {
+            self.memory.write_word(*address, *word);
+            report.code_words_written += 1;
+        }
+
+        // Daten nur anfassen, wenn sich die Section verschoben oder verändert hat
+        for section in new.sections.iter().filter(|s| s.kind == SectionKind::Data) {
+            let unchanged = old.sections.iter().any(|s| s == section);
+            if unchanged {
+                continue;
+            }
+            for (address, byte) in new.data.iter().filter(|(a, _)| section.contains(*a)) {
+                self.memory.write_byte(*address, *byte);
+            }
+            report.warnings.push(format!(
+                "data section {} at ${:06X}-${:06X} changed; reinitialized",
+                section.name, section.start, section.end
+            ));
+        }
+
+        let mut moved: Vec<_> = old
+            .symbols
+            .iter()
+            .filter(|(_, address)| {
+                old.section_at(**address)
+                    .is_some_and(|s| s.kind == SectionKind::Data)
+            })
+            .filter_map(|(name, address)| match new.symbols.get(name) {
+                Some(new_address) if new_address != address => Some(format!(
+                    "data label {} moved from ${:06X} to ${:06X}",
+                    name, address, new_address
+                )),
+                _ => None,
+            })
+            .collect();
+        moved.sort();
+        report.warnings.extend(moved);
+
+        // PC auf die entsprechende Quellzeile im neuen Programm umsetzen
+        let pc = self.cpu.get_pc();
+        match Self::equivalent_address(&old, &new, pc) {
+            Some(address) => {
+                self.cpu.set_pc(address);
+                report.pc_preserved = true;
+            }
+            None => {
+                let entry = new.entry_point().unwrap_or(0);
+                self.cpu.set_pc(entry);
+                report.warnings.push(format!(
+                    "source line at PC ${:06X} no longer exists; PC reset to ${:06X}",
+                    pc, entry
+                ));
+            }
+        }
+
+        self.install(new);
+        Ok(report)
+    }
+
+    /// Code wortweise, Daten byteweise laden, damit DC.B an ungeraden Adressen keine
+    /// Nachbarbytes überschreibt
+    fn load_program_image(&mut self, program: &Program) {
+        self.memory.clear();
+        Self::write_image(&mut self.memory, program);
+    }
+
+    fn write_image(memory: &mut Memory, program: &Program) {
+        for (address, word) in program.words_in(SectionKind::Code) {
+            memory.write_word(*address, *word);
+        }
+        for (address, byte) in &program.data {
+            memory.write_byte(*address, *byte);
+        }
+    }
+
+    fn install(&mut self, program: Program) {
+        self.breakpoints.resolve(&program);
+        for watch in &mut self.watches {
+            watch.resolve(&program);
+        }
+        self.formatter = AddressFormatter::new(&program);
+        self.program = Some(program);
+    }
+
+    /// Sucht die Zeile mit dem gleichen Text wie die Zeile an `pc`, möglichst nahe an der alten
+    fn equivalent_address(old: &Program, new: &Program, pc: u32) -> Option<u32> {
+        let old_line = old.line_for_address(pc)?;
+        let text = old.line_text(old_line)?.trim();
+
+        new.source_map
+            .iter()
+            .filter(|(_, &line)| new.line_text(line).is_some_and(|t| t.trim() == text))
+            .min_by_key(|(_, &line)| line.abs_diff(old_line))
+            .map(|(&address, _)| address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::isa::Size;
+
+    const PROGRAM: &str = "            ORG     $0800
+BUFFER:     DC.L    5
+
+            ORG     $1000
+START:      MOVEQ   #1, D0
+            MOVEQ   #2, D1
+LOOP:       ADD     D0, D1
+            BRA     LOOP
+";
+
+    #[test]
+    fn test_fragment_runs_isolated_from_main_image() {
+        let mut emulator = Emulator::new();
+        emulator.load_source(PROGRAM).unwrap();
+        emulator.step();
+        let before = emulator.cpu().snapshot();
+
+        // Drei Instruktionen; das Label BUFFER kommt aus dem geladenen Programm
+        let fragment = "MOVEA.L #BUFFER, A0\nADDQ.L  #4, D0\nMOVE.L  D0, (A0)";
+        let result = emulator.run_fragment(fragment, before).unwrap();
+
+        assert!(result.completed);
+        assert_eq!(result.outcome, RunOutcome::Halted);
+        assert_eq!(result.steps, 4); // mit dem angehängten SIMHALT
+        assert_eq!(result.registers.d[0], 5);
+        assert_eq!(result.registers.a[0], 0x0800);
+        assert_eq!(result.registers.pc, FRAGMENT_BASE + 10);
+
+        // Weder Speicher noch CPU des Emulators wurden angefasst
+        assert_eq!(emulator.memory().read_long(0x0800), 5);
+        assert_eq!(emulator.memory().read_word(FRAGMENT_BASE), 0);
+        assert_eq!(emulator.cpu().snapshot(), before);
+        assert_eq!(
+            emulator.program().unwrap().symbols.get("BUFFER"),
+            Some(&0x0800)
+        );
+    }
+
+    #[test]
+    fn test_fragment_errors_and_escapes() {
+        let emulator = Emulator::new();
+        let errors = emulator
+            .run_fragment("MOVEQ #1, D0\nMOVEQ #1", CpuSnapshot::default())
+            .unwrap_err();
+        assert_eq!(errors[0].line, 2, "line numbers refer to the fragment");
+
+        // Ein Sprung aus dem Fragment heraus erreicht den Halt nicht
+        let result = emulator
+            .run_fragment("HERE: BRA HERE", CpuSnapshot::default())
+            .unwrap();
+        assert!(!result.completed);
+        assert_eq!(result.outcome, RunOutcome::StepLimit(FRAGMENT_STEP_LIMIT));
+    }
+
+    #[test]
+    fn test_reload_keeps_data_and_breakpoints() {
+        let mut emulator = Emulator::new();
+        emulator.load_source(PROGRAM).unwrap();
+        emulator.add_breakpoint(Location::Line(7));
+        emulator.add_watch(Watch::new(Location::Symbol("BUFFER".into()), Size::Long));
+
+        // Daten von Hand ändern und zwei Instruktionen ausführen
+        emulator.memory_mut().write_long(0x0800, 0xCAFE);
+        emulator.step();
+        emulator.step();
+        assert_eq!(emulator.cpu().get_pc(), 0x1004);
+
+        let modified = PROGRAM.replace("MOVEQ   #2, D1", "MOVEQ   #3, D1");
+        let report = emulator.reload_code(&modified).unwrap();
+
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+        assert!(report.pc_preserved);
+        assert_eq!(emulator.cpu().get_pc(), 0x1004);
+        assert_eq!(
+            emulator.cpu().get_data_register(1),
+            2,
+            "registers untouched"
+        );
+        assert_eq!(
+            emulator.memory().read_word(0x1002),
+            0x7203,
+            "new code written"
+        );
+        assert_eq!(
+            emulator.memory().read_long(0x0800),
+            0xCAFE,
+            "data untouched"
+        );
+        assert_eq!(emulator.watches()[0].read(emulator.memory()), Some(0xCAFE));
+
+        let bp = emulator.breakpoints().iter().next().unwrap();
+        assert_eq!(bp.address, Some(0x1004), "breakpoint survives the reload");
+    }
+
+    #[test]
+    fn test_reload_warns_when_data_moves() {
+        let mut emulator = Emulator::new();
+        emulator.load_source(PROGRAM).unwrap();
+
+        let moved = PROGRAM.replace("ORG     $0800", "ORG     $0900");
+        let report = emulator.reload_code(&moved).unwrap();
+
+        assert!(report
+            .warnings
+            .iter()
+            .any(|w| w.contains("BUFFER moved from $000800 to $000900")));
+        assert_eq!(emulator.memory().read_long(0x0900), 5);
+    }
+
+    #[test]
+    fn test_reload_resets_pc_when_line_is_gone() {
+        let mut emulator = Emulator::new();
+        emulator.load_source(PROGRAM).unwrap();
+        emulator.step();
+        assert_eq!(emulator.cpu().get_pc(), 0x1002);
+
+        let modified = PROGRAM.replace("MOVEQ   #2, D1", "MOVEQ   #4, D1");
+        let report = emulator.reload_code(&modified).unwrap();
+
+        assert!(!report.pc_preserved);
+        assert_eq!(emulator.cpu().get_pc(), 0x1000);
+    }
+
+    #[test]
+    fn test_failed_reload_keeps_program() {
+        let mut emulator = Emulator::new();
+        emulator.load_source(PROGRAM).unwrap();
+
+        let broken = PROGRAM.replace("ADD     D0, D1", "ADD     D0, D1, D2");
+        assert!(emulator.reload_code(&broken).is_err());
+        assert!(emulator.program().is_some());
+        assert_eq!(emulator.memory().read_word(0x1004), 0xD240);
+    }
+
+    fn loaded(source: &str) -> Emulator {
+        let mut emulator = Emulator::new();
+        emulator.load_source(source).unwrap();
+        emulator
+    }
+
+    #[test]
+    fn test_idle_loop_detected() {
+        let mut emulator = loaded("START: MOVEQ #1, D0\nEND: BRA END");
+
+        let outcome = emulator.run(1_000_000);
+        assert_eq!(outcome, RunOutcome::IdleLoopDetected { pcs: vec![0x0002] });
+        assert_eq!(emulator.program().unwrap().symbolize(0x0002), "END");
+
+        // Zählschleifen ändern Register und sind kein Leerlauf
+        let mut emulator = loaded("MOVE.L #3000, D1\nLOOP: SUBQ.L #1, D1\nBNE LOOP\nSIMHALT");
+        assert_eq!(emulator.run(1_000_000), RunOutcome::Halted);
+    }
+
+    #[test]
+    fn test_idle_loop_suppressed_by_memory_write() {
+        let mut emulator = loaded(
+            "ORG $0800\nBUF: DS.L 1\nORG $1000\nMOVEA.L #BUF, A0\nLOOP: MOVE.L D0, (A0)\nBRA LOOP",
+        );
+        assert_eq!(emulator.run(10_000), RunOutcome::StepLimit(10_000));
+    }
+
+    #[test]
+    fn test_profiles_set_documented_toggles() {
+        let mut emulator = Emulator::new();
+        assert_eq!(emulator.strictness_profile(), None);
+
+        emulator.set_profile(StrictnessProfile::Teaching);
+        assert_eq!(
+            emulator.strictness(),
+            Strictness {
+                alignment_check: true,
+                rom_write_faults: true,
+                data_execution_check: true,
+                idle_loop_detection: true,
+                strict_moveq: true,
+            }
+        );
+        assert!(emulator.cpu().alignment_check());
+
+        emulator.set_profile(StrictnessProfile::Compatible);
+        assert_eq!(
+            emulator.strictness(),
+            Strictness {
+                alignment_check: true,
+                rom_write_faults: false,
+                data_execution_check: false,
+                idle_loop_detection: false,
+                strict_moveq: false,
+            }
+        );
+        assert!(!emulator.assembler().strict_moveq());
+
+        emulator.set_profile(StrictnessProfile::Fast);
+        assert_eq!(
+            emulator.strictness(),
+            Strictness {
+                alignment_check: false,
+                rom_write_faults: false,
+                data_execution_check: false,
+                idle_loop_detection: false,
+                strict_moveq: false,
+            }
+        );
+        assert_eq!(emulator.strictness_profile(), Some(StrictnessProfile::Fast));
+    }
+
+    #[test]
+    fn test_overrides_after_profile_stick() {
+        let mut emulator = Emulator::new();
+        emulator.set_profile(StrictnessProfile::Teaching);
+        emulator.set_idle_loop_detection(false);
+        emulator.set_strict_moveq(false);
+        assert_eq!(emulator.strictness_profile(), None);
+
+        // Laden und Reset lassen die Schalter stehen
+        emulator
+            .load_source("ORG $1000\nMOVEQ #200, D0\nEND: BRA END")
+            .unwrap();
+        assert_eq!(emulator.assembler().warnings().len(), 1);
+        emulator.reset();
+        assert_eq!(emulator.run(1_000_000), RunOutcome::StepLimit(1_000_000));
+        assert_eq!(emulator.cpu().get_data_register(0), 0xFFFF_FFC8);
+
+        let strictness = emulator.strictness();
+        assert!(strictness.alignment_check);
+        assert!(strictness.data_execution_check);
+        assert!(!strictness.idle_loop_detection);
+        assert!(!strictness.strict_moveq);
+
+        // Ein Profil setzt alles wieder
+        emulator.set_profile(StrictnessProfile::Teaching);
+        assert_eq!(
+            emulator.strictness_profile(),
+            Some(StrictnessProfile::Teaching)
+        );
+    }
+
+    #[test]
+    fn test_idle_loop_suppressed_by_activity() {
+        let mut emulator = loaded("END: BRA END");
+        for step in 0..10_000 {
+            if step % 500 == 0 {
+                emulator.note_activity(); // z.B. ein Interrupt
+            }
+            emulator.step();
+            assert!(emulator.idle_loop().is_none());
+        }
+
+        emulator.set_idle_loop_detection(false);
+        assert_eq!(emulator.run(10_000), RunOutcome::StepLimit(10_000));
+    }
+
+    #[test]
+    fn test_entry_and_stack_precedence() {
+        let source = "            ORG     $0800
+HELPER:     MOVEQ   #1, D0
+            ORG     $1000
+FIRST:      MOVEQ   #2, D0
+START:      MOVEQ   #3, D0
+            STACK   $8000
+            END     START
+";
+        // END-Operand vor der Heuristik (erste Instruktion ab $1000)
+        let mut emulator = loaded(source);
+        assert_eq!(emulator.cpu().get_pc(), 0x1002);
+        assert_eq!(emulator.cpu().get_address_register(7), 0x8000);
+
+        // Vorgaben aus GUI/CLI vor dem Quelltext
+        emulator.set_entry_override(Some(0x0800));
+        emulator.set_initial_sp_override(Some(0x4000));
+        emulator.reset();
+        assert_eq!(emulator.cpu().get_pc(), 0x0800);
+        assert_eq!(emulator.cpu().get_address_register(7), 0x4000);
+
+        // Vorgaben gelten auch für neu geladene Programme, bis sie entfernt werden
+        emulator
+            .load_source(&source.replace("END     START", ""))
+            .unwrap();
+        assert_eq!(emulator.cpu().get_pc(), 0x0800);
+        emulator.set_entry_override(None);
+        emulator.set_initial_sp_override(None);
+        emulator.reset();
+        assert_eq!(
+            emulator.cpu().get_pc(),
+            0x1000,
+            "first instruction at $1000"
+        );
+        assert_eq!(emulator.cpu().get_address_register(7), 0x8000);
+    }
+
+    #[test]
+    fn test_falling_into_data_is_reported() {
+        // Kein SIMHALT: nach dem zweiten MOVEQ folgen die Daten
+        let source = "            ORG     $1000
+START:      MOVEQ   #1, D0
+            MOVEQ   #2, D1
+TABLE:      DC.W    $4E71, $A000
+";
+        let mut emulator = loaded(source);
+        let outcome = emulator.run(1000);
+        assert_eq!(
+            outcome,
+            RunOutcome::DataExecution(DataExecution {
+                pc: 0x1004,
+                section: "DATA".to_string()
+            })
+        );
+        assert_eq!(emulator.steps(), 2);
+        assert_eq!(
+            emulator.pending_data_execution().unwrap().to_string(),
+            "PC entered data section DATA at $001004 — missing halt?"
+        );
+        assert_eq!(emulator.run(1000), outcome, "stays paused");
+
+        // Override: Daten bewusst ausführen (NOP, SIMHALT)
+        emulator.set_data_execution_check(false);
+        assert_eq!(emulator.run(1000), RunOutcome::Halted);
+        assert_eq!(emulator.cpu().get_pc(), 0x1006);
+    }
+
+    #[test]
+    fn test_step_back_restores_exact_state() {
+        let source = "            ORG     $1000
+START:      MOVEA.L #$0800, A0
+            MOVE.L  #$12345678, (A0)
+            MOVE.W  #$ABCD, $0806
+            MOVEQ   #-1, D3
+            SIMHALT
+";
+        let mut emulator = loaded(source);
+        emulator.memory_mut().write_long(0x0804, 0x1111_2222);
+        let initial = emulator.cpu().state();
+        let memory = emulator.memory().copy_range(0x0800..0x0810);
+
+        for _ in 0..5 {
+            emulator.step();
+        }
+        assert!(emulator.cpu().is_halted());
+        assert_eq!(emulator.memory().read_long(0x0800), 0x1234_5678);
+        assert_eq!(emulator.memory().read_word(0x0806), 0xABCD);
+        assert_eq!(emulator.steps_back_available(), 5);
+
+        let mut undone = Vec::new();
+        for _ in 0..5 {
+            let back = emulator.step_back().expect("step recorded");
+            assert!(!back.devices_ticked);
+            undone.push(back.writes_undone);
+        }
+        // MOVE.L schreibt zwei Wörter
+        assert_eq!(undone, [0, 0, 1, 2, 0]);
+        assert_eq!(emulator.cpu().state(), initial);
+        assert_eq!(emulator.memory().copy_range(0x0800..0x0810), memory);
+        assert_eq!(emulator.steps(), 0);
+        assert!(emulator.step_back().is_none());
+
+        // Danach läuft alles wieder genauso
+        assert_eq!(emulator.run(10), RunOutcome::Halted);
+        assert_eq!(emulator.memory().read_long(0x0804), 0x1111_ABCD);
+    }
+
+    #[test]
+    fn test_step_back_reports_devices() {
+        let mut emulator = loaded(PROGRAM);
+        emulator.attach_device(Box::new(crate::bus::Timer::new(1000, 2)));
+        emulator.step();
+        assert!(emulator.step_back().unwrap().devices_ticked);
+
+        emulator.step();
+        emulator.reset();
+        assert_eq!(emulator.steps_back_available(), 0);
+    }
+
+    #[test]
+    fn test_profile_attributes_cycles_to_lines() {
+        let mut emulator = loaded(
+            "        ORG     $1000
+START:  MOVEQ   #9, D0
+SHORT:  ADDQ.L  #1, D2
+        DBRA    D0, SHORT
+        MOVEQ   #99, D1
+LONG:   ADDQ.L  #1, D3
+        DBRA    D1, LONG
+        SIMHALT",
+        );
+        assert_eq!(emulator.run(1000), RunOutcome::Halted);
+
+        let profile = emulator.profile();
+        assert_eq!(profile.line(3).count, 10);
+        assert_eq!(profile.line(6).count, 100);
+        // Die lange Schleife kostet etwa zehnmal so viel
+        assert!(profile.line(6).cycles > 5 * profile.line(3).cycles);
+        // Oben stehen die beiden Zeilen der langen Schleife
+        let mut hot: Vec<Option<usize>> = profile.hot_lines().iter().map(|h| h.line).collect();
+        hot[..2].sort();
+        assert_eq!(hot[..2], [Some(6), Some(7)]);
+        assert!(profile.hot_lines()[0].percent > profile.hot_lines()[2].percent);
+        assert_eq!(profile.total_cycles(), emulator.cpu().cycles());
+
+        // Zurückgehen nimmt den Schritt aus dem Profil, Reset leert es
+        emulator.step_back();
+        assert_eq!(emulator.profile().line(8).count, 0);
+        emulator.reset();
+        assert_eq!(emulator.profile().total_cycles(), 0);
+        assert!(emulator.profile().hot_lines().is_empty());
+    }
+
+    #[test]
+    fn test_failed_assertion_is_recorded() {
+        let mut emulator = loaded(
+            "        ORG     $1000
+        MOVEQ   #3, D0
+        ASSERT  D0 == 3
+        ASSERT  D0 > 3
+        ADDQ.L  #1, D0
+        ASSERT  D0 > 3
+        SIMHALT
+",
+        );
+        assert!(matches!(emulator.run(100), RunOutcome::Halted));
+        let failures = emulator.assertion_failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].to_string(),
+            "line 4: ASSERT D0 > $3 failed, actual $3"
+        );
+
+        emulator.reset();
+        assert!(emulator.assertion_failures().is_empty());
+    }
+}
@@ -1,34 +1,95 @@
 // MC68000 Emulator GUI mit egui
-use crate::{assembler, cpu, memory};
+use crate::address_map::{self, MapScale, RegionKind};
+use crate::assembler::AsmError;
+use crate::breakpoints::{Condition, Location};
+use crate::completion::{self, CompletionContext, Suggestion};
+use crate::controller::{Controller, FrameUpdate, LoadError, LoadMode, RunState};
+use crate::cpu::CpuSnapshot;
+use crate::disassembler::disassemble_at_symbolic;
+use crate::emulator::RunOutcome;
+use crate::log_ring::LogRing;
+use crate::program::parse_address;
+use crate::reference::{card_for, Card};
+use crate::replay::RunRecord;
+use crate::rng::Rng;
+use crate::strictness::StrictnessProfile;
+use crate::theme::{LineTokenCache, SyntaxTheme, TokenClass};
+use crate::trace::TraceLevel;
+use crate::uart::{Uart, UartHost};
 use eframe::egui;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::mpsc::Receiver;
 
 pub struct EmulatorApp {
     // Assembly Code Editor
     assembly_code: String,
 
     // Emulator State
-    cpu: cpu::CPU,
-    memory: memory::Memory,
-    assembler: assembler::Assembler,
+    controller: Controller,
+    updates: Receiver<FrameUpdate>,
+    cpu_view: CpuSnapshot, // letzter empfangener Zustand, nur daraus wird gezeichnet
+    previous_view: CpuSnapshot, // Zustand davor, für geänderte Register
 
     // GUI State
-    is_running: bool,
     step_mode: bool,
+    instructions_per_frame: u32, // Geschwindigkeit im Run-Modus
+    resume_after_assemble: bool, // Assemble während eines Laufs: danach weiterlaufen
     current_step: usize,
-    machine_code: Vec<(u32, u16)>,
+    entry_text: String, // Start-Vorgaben, leer = aus dem Quelltext
+    stack_text: String,
+    condition_texts: HashMap<Location, String>, // Eingabefelder im Breakpoints-Panel
+    memory_address: u32,                        // erste Adresse im Speicher-Panel
+    memory_address_text: String,
+    register_watch_text: String, // Eingabe für einen neuen Register-Watch
+    register_watch_pause: bool,
+    register_watch_error: Option<String>,
 
     // Output/Logs
-    output_log: String,
+    output_log: LogRing,
+    log_file_text: String,          // Pfad für "Save full log to file…"
+    log_file_prompt: bool,          // Eingabezeile für den Pfad offen
+    log_file_error: Option<String>, // Fehler beim Öffnen oder Schreiben
     error_message: String,
+    asm_errors: Vec<AsmError>, // für die Markierungen in der hervorgehobenen Ansicht
+    serial_tab: bool,          // Konsole zeigt den UART statt des Logs
+    uart: Option<UartHost>,    // None, bis "Connect UART" geklickt wurde
+    serial_log: LogRing,       // Ausgabe des Programms über den UART
+    serial_input: String,
 
     // Layout State
+    theme: SyntaxTheme,
+    highlight_cache: LineTokenCache, // zerlegte Zeilen für die Hervorhebung
+    completion: Option<CompletionPopup>, // Vorschläge nach Ctrl+Space
+    reference_cards: HashMap<String, Option<Card>>, // Karten nach Mnemonic, einmal erzeugt
+    reference_card: Option<Card>,    // mit F1 geöffnete Karte
     show_compare_view: bool,
     bottom_panel_height: f32,
     side_panel_width: f32,
 }
 
+/// Offene Vorschlagsliste im Editor
+struct CompletionPopup {
+    suggestions: Vec<Suggestion>,
+    selected: usize,
+    start: usize,  // Zeichenindex, ab dem der getippte Präfix ersetzt wird
+    cursor: usize, // Cursor beim Öffnen; bewegt er sich, schließt die Liste
+}
+
+/// So viele Vorschläge zeigt die Liste höchstens
+const COMPLETION_ROWS: usize = 12;
+/// So viele Zeilen zeigt der Profiler
+const PROFILE_ROWS: usize = 15;
+/// So viele Langwörter ab SP zeigt die Stack-Ansicht
+const STACK_SLOTS: u32 = 8;
+
 impl Default for EmulatorApp {
     fn default() -> Self {
+        let mut controller = Controller::default();
+        let seed = Rng::seed_from_clock();
+        controller.emulator_mut().attach_rng(seed);
+        // Die GUI liest den Speicher selbst, Kopien wären verschwendet
+        let updates = controller.subscribe_state();
         let mut app = Self {
             assembly_code: String::from(
                 "MOVEQ #42, D0    ; Lade 42 in D0
@@ -45,17 +106,43 @@ end:             ; Label für Ende
 NOP              ; No Operation
 BRA end          ; Endlos-Loop",
             ),
-            cpu: cpu::CPU::new(),
-            memory: memory::Memory::new(),
-            assembler: assembler::Assembler::new(),
-            is_running: false,
+            controller,
+            updates,
+            cpu_view: CpuSnapshot::default(),
+            previous_view: CpuSnapshot::default(),
             step_mode: true,
+            instructions_per_frame: 10_000,
+            resume_after_assemble: false,
             current_step: 0,
-            machine_code: Vec::new(),
-            output_log: String::new(),
+            entry_text: String::new(),
+            stack_text: String::new(),
+            condition_texts: HashMap::new(),
+            memory_address: 0x1000,
+            memory_address_text: String::from("$1000"),
+            register_watch_text: String::new(),
+            register_watch_pause: true,
+            register_watch_error: None,
+            output_log: {
+                let log = LogRing::default();
+                log.push_str(&format!("🎲 RNG an $E100, seed {}\n", seed));
+                log
+            },
+            log_file_text: String::from("mc68000.log"),
+            log_file_prompt: false,
+            log_file_error: None,
             error_message: String::new(),
+            asm_errors: Vec::new(),
+            serial_tab: false,
+            uart: None,
+            serial_log: LogRing::default(),
+            serial_input: String::new(),
 
             // Layout State
+            theme: SyntaxTheme::default(),
+            highlight_cache: LineTokenCache::default(),
+            completion: None,
+            reference_cards: HashMap::new(),
+            reference_card: None,
             show_compare_view: false,
             bottom_panel_height: 150.0,
             side_panel_width: 300.0,
@@ -68,8 +155,111 @@ BRA end          ; Endlos-Loop",
     }
 }
 
+/// Schlüssel der gespeicherten Einstellungen
+const THEME_KEY: &str = "syntax_theme";
+
+impl EmulatorApp {
+    /// App mit den gespeicherten Einstellungen (Farbschema)
+    pub fn new(cc: &eframe::CreationContext) -> Self {
+        let mut app = Self::default();
+        if let Some(theme) = cc
+            .storage
+            .and_then(|storage| storage.get_string(THEME_KEY))
+            .and_then(|name| SyntaxTheme::by_name(&name))
+        {
+            app.theme = theme;
+        }
+        app.apply_theme(&cc.egui_ctx);
+        app
+    }
+
+    /// Popover für Startadresse und Stack; die Felder überschreiben END/STACK im Quelltext
+    fn start_settings(&mut self, ui: &mut egui::Ui) {
+        let emulator = self.controller.emulator();
+        let source_entry = emulator.program().and_then(|p| p.entry_point());
+        let source_sp = emulator.program().and_then(|p| p.initial_sp);
+        let hint = |value: Option<u32>| match value {
+            Some(address) => format!("source: ${:06X}", address),
+            None => "source: none".to_string(),
+        };
+
+        let mut valid = true;
+        egui::Grid::new("start_settings").show(ui, |ui| {
+            for (label, text, source) in [
+                ("Entry:", &mut self.entry_text, source_entry),
+                ("Initial SP:", &mut self.stack_text, source_sp),
+            ] {
+                ui.label(label);
+                ui.add(
+                    egui::TextEdit::singleline(text)
+                        .hint_text(hint(source))
+                        .desired_width(120.0),
+                );
+                if !text.trim().is_empty() && parse_address(text).is_none() {
+                    ui.colored_label(self.theme.color(TokenClass::Error), "invalid address");
+                    valid = false;
+                }
+                ui.end_row();
+            }
+        });
+
+        let mut check = self.controller.emulator().data_execution_check();
+        if ui
+            .checkbox(&mut check, "Stop on data")
+            .on_hover_text("Anhalten, wenn der PC in eine Data-Section läuft")
+            .changed()
+        {
+            self.controller
+                .emulator_mut()
+                .set_data_execution_check(check);
+        }
+
+        if ui
+            .add_enabled(valid, egui::Button::new("Apply & Reset"))
+            .clicked()
+        {
+            let entry = parse_address(&self.entry_text);
+            let sp = parse_address(&self.stack_text);
+            let emulator = self.controller.emulator_mut();
+            emulator.set_entry_override(entry);
+            emulator.set_initial_sp_override(sp);
+            self.reset_emulator();
+            ui.close();
+        }
+    }
+
+    fn apply_theme(&self, ctx: &egui::Context) {
+        ctx.set_visuals(if self.theme.dark {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        });
+    }
+}
+
 impl eframe::App for EmulatorApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(THEME_KEY, self.theme.name.to_string());
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Laufendes Programm: ein Kontingent Instruktionen pro Frame
+        if self.controller.is_running() {
+            self.controller.on_frame(self.instructions_per_frame);
+        }
+        for message in self.controller.take_messages() {
+            self.output_log.push_str(&format!("{}\n", message));
+        }
+        if let Some(host) = &self.uart {
+            let output: String = host
+                .receive()
+                .into_iter()
+                .filter(|&byte| byte != b'\r')
+                .map(char::from)
+                .collect();
+            self.serial_log.push_str(&output);
+        }
+
         // VS Code Style Layout
 
         // Top Panel - Toolbar (smaller height, buttons right-aligned)
@@ -80,12 +270,105 @@ impl eframe::App for EmulatorApp {
                     // Title links
                     ui.heading("🖥️ MC68000 Emulator");
 
+                    ui.menu_button("View", |ui| {
+                        for theme in SyntaxTheme::PRESETS {
+                            if ui
+                                .selectable_label(self.theme == theme, theme.name)
+                                .clicked()
+                            {
+                                self.theme = theme;
+                                self.apply_theme(ctx);
+                                ui.close();
+                            }
+                        }
+                    });
+
+                    ui.menu_button("Start", |ui| self.start_settings(ui));
+
                     // Push buttons to the right
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         ui.checkbox(&mut self.step_mode, "Step Mode");
 
+                        let mut step_cap = self.controller.step_cap();
+                        if ui
+                            .add(egui::DragValue::new(&mut step_cap).prefix("Limit: "))
+                            .on_hover_text("Maximale Instruktionen pro Lauf")
+                            .changed()
+                        {
+                            self.controller.set_step_cap(step_cap);
+                        }
+
+                        let mut idle_detection =
+                            self.controller.emulator().idle_loop_detection();
+                        if ui
+                            .checkbox(&mut idle_detection, "Idle-Erkennung")
+                            .on_hover_text("Lauf beenden, wenn das Programm in einer wirkungslosen Endlosschleife hängt")
+                            .changed()
+                        {
+                            self.controller
+                                .emulator_mut()
+                                .set_idle_loop_detection(idle_detection);
+                        }
+
+                        // Einzelne Schalter danach bleiben möglich; dann steht hier "Custom"
+                        let current = self.controller.emulator().strictness_profile();
+                        let mut selected = current;
+                        egui::ComboBox::from_id_salt("strictness_profile")
+                            .selected_text(format!(
+                                "Checks: {}",
+                                current.map_or("Custom", StrictnessProfile::label)
+                            ))
+                            .show_ui(ui, |ui| {
+                                for profile in StrictnessProfile::ALL {
+                                    ui.selectable_value(&mut selected, Some(profile), profile.label());
+                                }
+                            })
+                            .response
+                            .on_hover_text(
+                                "Teaching: alle Prüfungen; Compatible: wie ein echter 68000; \
+                                 Fast: keine Prüfungen",
+                            );
+                        if let Some(profile) = selected.filter(|_| selected != current) {
+                            self.controller.emulator_mut().set_profile(profile);
+                        }
+
+                        let mut trace_level = self.controller.trace_level();
+                        egui::ComboBox::from_id_salt("trace_level")
+                            .selected_text(format!("Trace: {}", trace_level.label()))
+                            .show_ui(ui, |ui| {
+                                for level in TraceLevel::ALL {
+                                    ui.selectable_value(&mut trace_level, level, level.label());
+                                }
+                            });
+                        self.controller.set_trace_level(trace_level);
+
+                        ui.add(
+                            egui::Slider::new(&mut self.instructions_per_frame, 1..=100_000)
+                                .logarithmic(true)
+                                .text("Instr/Frame"),
+                        );
+                        ui.checkbox(&mut self.resume_after_assemble, "Resume")
+                            .on_hover_text(
+                                "Assemble während eines Laufs pausiert, tauscht das Programm \
+                                 und läuft danach weiter",
+                            );
+
                         ui.separator();
 
+                        if matches!(
+                            self.controller.state(),
+                            RunState::Running | RunState::Paused
+                        ) {
+                            if ui.button("⏹️ Stop").on_hover_text("Stop run").clicked() {
+                                self.controller.stop();
+                            }
+                            if self.controller.is_running()
+                                && ui.button("⏸️ Pause").on_hover_text("Pause run").clicked()
+                            {
+                                self.controller.pause();
+                            }
+                        }
+
                         if ui
                             .button("🔄 Reset")
                             .on_hover_text("Reset CPU (Ctrl+R)")
@@ -94,11 +377,22 @@ impl eframe::App for EmulatorApp {
                             self.reset_emulator();
                         }
 
+                        if ui
+                            .add_enabled(
+                                self.controller.emulator().steps_back_available() > 0,
+                                egui::Button::new("⏮ Back"),
+                            )
+                            .on_hover_text("Undo the last instruction")
+                            .clicked()
+                        {
+                            self.step_back();
+                        }
+
                         if ui
                             .button("⏸️ Step")
                             .on_hover_text("Step one instruction (F10)")
                             .clicked()
-                            && !self.machine_code.is_empty()
+                            && !self.machine_code().is_empty()
                         {
                             self.step_program();
                         }
@@ -107,7 +401,7 @@ impl eframe::App for EmulatorApp {
                             .button("▶️ Run")
                             .on_hover_text("Run program (F5)")
                             .clicked()
-                            && !self.machine_code.is_empty()
+                            && !self.machine_code().is_empty()
                         {
                             self.run_program();
                         }
@@ -120,6 +414,22 @@ impl eframe::App for EmulatorApp {
                             self.assemble_code();
                             self.show_compare_view = true; // Show compare view after assembly
                         }
+
+                        if ui
+                            .button("♻️ Reload Code")
+                            .on_hover_text("Reload code only, keep data and breakpoints")
+                            .clicked()
+                        {
+                            self.reload_code();
+                        }
+
+                        if let Some(badge) = self.controller.badge() {
+                            ui.separator();
+                            ui.colored_label(self.theme.color(TokenClass::Error), badge)
+                                .on_hover_text(
+                                    "Step und Run verwenden das zuletzt fehlerfrei assemblierte Programm",
+                                );
+                        }
                     });
                 });
             });
@@ -134,38 +444,103 @@ impl eframe::App for EmulatorApp {
                     ui.separator();
 
                     // Console tabs (like VS Code)
-                    if ui.selectable_label(true, "Terminal").clicked() {
-                        // Future: multiple console tabs
+                    ui.selectable_value(&mut self.serial_tab, false, "Terminal");
+                    ui.selectable_value(&mut self.serial_tab, true, "Serial");
+
+                    // Der Seed bleibt sichtbar, auch wenn die Startmeldung aus dem Log fällt
+                    let record = RunRecord::capture(self.controller.emulator());
+                    if let Some(seed) = record.rng_seed {
+                        ui.separator();
+                        let copy = ui.button(format!("🎲 {}", seed)).on_hover_text(format!(
+                            "RNG-Seed; Klick kopiert den Run-Record (mc68000 --replay FILE)\n{}",
+                            record
+                        ));
+                        if copy.clicked() {
+                            ui.ctx().copy_text(record.to_string());
+                        }
+                    }
+
+                    if let Some(progress) = self.controller.progress_text() {
+                        ui.separator();
+                        if self.controller.is_running() {
+                            ui.spinner();
+                        }
+                        ui.label(progress);
                     }
 
+                    if self.serial_tab {
+                        return;
+                    }
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         if ui.button("🗑️").on_hover_text("Clear").clicked() {
                             self.output_log.clear();
                         }
+                        if ui.button("📋").on_hover_text("Copy").clicked() {
+                            ui.ctx().copy_text(self.output_log.text());
+                        }
+                        match self.output_log.file_path() {
+                            Some(path) => {
+                                if ui
+                                    .button("⏹ Stop saving")
+                                    .on_hover_text(path.display().to_string())
+                                    .clicked()
+                                {
+                                    if let Err(error) = self.output_log.stop_file() {
+                                        self.log_file_error = Some(error.to_string());
+                                    }
+                                }
+                            }
+                            None => {
+                                if ui.button("💾 Save full log to file…").clicked() {
+                                    self.log_file_prompt = !self.log_file_prompt;
+                                }
+                            }
+                        }
+                        let dropped = self.output_log.dropped();
+                        if dropped > 0 {
+                            ui.colored_label(
+                                self.theme.color(TokenClass::Error),
+                                format!("log full, older lines dropped ({})", dropped),
+                            )
+                            .on_hover_text(format!(
+                                "Die Konsole hält die letzten {} Zeilen",
+                                self.output_log.capacity()
+                            ));
+                        }
                     });
                 });
+                if self.serial_tab {
+                    self.show_serial(ui);
+                    return;
+                }
+                self.show_log_file_prompt(ui);
 
                 ui.separator();
 
                 // Error Messages
                 if !self.error_message.is_empty() {
-                    ui.colored_label(egui::Color32::RED, &self.error_message);
+                    ui.colored_label(self.theme.color(TokenClass::Error), &self.error_message);
                     ui.separator();
                 }
 
-                // Output Console
-                egui::ScrollArea::vertical()
+                // Output Console: nur die sichtbaren Zeilen werden gezeichnet
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::both()
                     .auto_shrink([false; 2])
                     .stick_to_bottom(true)
-                    .show(ui, |ui| {
-                        ui.add(
-                            egui::TextEdit::multiline(&mut self.output_log)
-                                .font(egui::TextStyle::Monospace)
-                                .desired_width(f32::INFINITY),
-                        );
+                    .show_rows(ui, row_height, self.output_log.len(), |ui, rows| {
+                        for line in self.output_log.lines(rows) {
+                            ui.add(
+                                egui::Label::new(egui::RichText::new(line).monospace()).extend(),
+                            );
+                        }
                     });
             });
 
+        // Toolbar-Aktionen (Step, Reset, ...) sofort im Register-Panel anzeigen
+        self.controller.publish();
+        self.receive_updates();
+
         // Right Panel - CPU Registers (collapsible)
         egui::SidePanel::right("cpu_panel")
             .resizable(true)
@@ -179,7 +554,8 @@ impl eframe::App for EmulatorApp {
                         egui::Grid::new("data_regs").show(ui, |ui| {
                             for i in 0..8 {
                                 ui.label(format!("D{}:", i));
-                                ui.monospace(format!("0x{:08X}", self.cpu.get_data_register(i)));
+                                let changed = self.cpu_view.d[i] != self.previous_view.d[i];
+                                ui.label(self.register_text(self.cpu_view.d[i], changed));
                                 ui.end_row();
                             }
                         });
@@ -190,7 +566,8 @@ impl eframe::App for EmulatorApp {
                         egui::Grid::new("addr_regs").show(ui, |ui| {
                             for i in 0..8 {
                                 ui.label(format!("A{}:", i));
-                                ui.monospace(format!("0x{:08X}", self.cpu.get_address_register(i)));
+                                let changed = self.cpu_view.a[i] != self.previous_view.a[i];
+                                ui.label(self.register_text(self.cpu_view.a[i], changed));
                                 ui.end_row();
                             }
                         });
@@ -200,32 +577,39 @@ impl eframe::App for EmulatorApp {
                     ui.collapsing("Special Registers", |ui| {
                         egui::Grid::new("special_regs").show(ui, |ui| {
                             ui.label("PC:");
-                            ui.monospace(format!("0x{:08X}", self.cpu.get_pc()));
+                            ui.monospace(format!("0x{:08X}", self.cpu_view.pc));
                             ui.end_row();
 
                             ui.label("CCR:");
-                            let ccr = self.cpu.get_ccr();
+                            let cpu = &self.cpu_view;
                             ui.monospace(format!(
                                 "0x{:02X} (N:{} Z:{} V:{} C:{})",
-                                ccr,
-                                (ccr >> 3) & 1,
-                                (ccr >> 2) & 1,
-                                (ccr >> 1) & 1,
-                                ccr & 1
+                                cpu.ccr, cpu.n as u8, cpu.z as u8, cpu.v as u8, cpu.c as u8
                             ));
                             ui.end_row();
 
                             ui.label("SR:");
-                            ui.monospace(format!("0x{:04X}", self.cpu.get_sr()));
+                            ui.monospace(format!("0x{:04X}", self.cpu_view.sr));
+                            ui.end_row();
+
+                            ui.label("Cycles:");
+                            ui.monospace(self.cpu_view.cycles.to_string());
                             ui.end_row();
                         });
                     });
+
+                    ui.collapsing("Breakpoints", |ui| self.show_breakpoints(ui));
+                    ui.collapsing("Register Watches", |ui| self.show_register_watches(ui));
+                    ui.collapsing("Profiler", |ui| self.show_profile(ui));
+                    ui.collapsing("Address Map", |ui| self.show_address_map(ui));
+                    ui.collapsing("Stack", |ui| self.show_stack(ui));
+                    ui.collapsing("Memory", |ui| self.show_memory(ui));
                 });
             });
 
         // Central Panel - Main Editor Area
         egui::CentralPanel::default().show(ctx, |ui| {
-            if self.show_compare_view && !self.machine_code.is_empty() {
+            if self.show_compare_view && !self.machine_code().is_empty() {
                 // Compare View (Assembly vs Bytecode) - VS Code merge style
                 self.show_compare_editor(ui);
             } else {
@@ -234,13 +618,15 @@ impl eframe::App for EmulatorApp {
             }
         });
 
+        self.show_reference_card(ctx);
+
         // Keyboard shortcuts
         ctx.input(|i| {
             if i.key_pressed(egui::Key::F5) {
                 // F5 - Assemble & Run
                 self.assemble_code();
                 self.show_compare_view = true;
-                if !self.machine_code.is_empty() {
+                if !self.machine_code().is_empty() {
                     self.run_program();
                 }
             }
@@ -253,7 +639,7 @@ impl eframe::App for EmulatorApp {
 
             if i.key_pressed(egui::Key::F10) {
                 // F10 - Step
-                if !self.machine_code.is_empty() {
+                if !self.machine_code().is_empty() {
                     self.step_program();
                 }
             }
@@ -264,159 +650,206 @@ impl eframe::App for EmulatorApp {
             }
         });
 
+        // Ctrl+B - Breakpoint auf der Zeile mit dem Textcursor (F9 ist Assemble)
+        if ctx.input(|i| i.modifiers.ctrl && i.key_pressed(egui::Key::B)) {
+            if let Some(line) = self.cursor_line(ctx) {
+                self.toggle_breakpoint(line);
+            }
+        }
+
+        // Änderungen durch Tastenkürzel im nächsten Frame zeichnen
+        self.controller.publish();
+        if self.receive_updates() {
+            ctx.request_repaint();
+        }
+
         // Auto-refresh während Emulation
-        if self.is_running {
+        if self.controller.is_running() {
             ctx.request_repaint();
         }
     }
 }
 
 impl EmulatorApp {
-    fn assemble_initial_code(&mut self) {
-        // Initial assembly ohne Output-Meldungen für saubere Initialisierung
-        let lines: Vec<&str> = self
-            .assembly_code
-            .lines()
-            .map(|line| {
-                // Remove comments (everything after ';')
-                let line = line.split(';').next().unwrap_or("").trim();
-                // Remove line numbers if present (e.g., "1 ORG $1000" -> "ORG $1000")
-                if let Some(first_char) = line.chars().next() {
-                    if first_char.is_ascii_digit() {
-                        // Find first non-digit, non-whitespace character
-                        if let Some(pos) =
-                            line.find(|c: char| !c.is_ascii_digit() && !c.is_whitespace())
-                        {
-                            return line[pos..].trim();
-                        }
-                    }
-                }
-                line
-            })
-            .filter(|line| !line.is_empty())
-            .collect();
+    /// Übernimmt den neuesten `FrameUpdate`; true, falls sich die CPU geändert hat
+    fn receive_updates(&mut self) -> bool {
+        let previous = self.cpu_view;
+        while let Ok(update) = self.updates.try_recv() {
+            self.cpu_view = update.cpu;
+        }
+        if self.cpu_view == previous {
+            return false;
+        }
+        self.previous_view = previous;
+        true
+    }
 
-        self.machine_code = self.assembler.assemble(&lines);
+    /// Registerwert; im letzten Update geänderte Werte in der Theme-Farbe
+    fn register_text(&self, value: u32, changed: bool) -> egui::RichText {
+        let text = egui::RichText::new(format!("0x{:08X}", value)).monospace();
+        if changed {
+            text.color(self.theme.color(TokenClass::ChangedRegister))
+        } else {
+            text
+        }
+    }
 
-        if !self.machine_code.is_empty() {
-            for (address, instruction) in &self.machine_code {
-                self.memory.write_word(*address, *instruction);
-            }
+    /// Quelltext für den Assembler: Kommentare und führende Zeilennummern entfernen.
+    /// Leere Zeilen bleiben erhalten, damit Fehler die richtige Zeilennummer haben.
+    fn preprocessed_source(&self) -> String {
+        preprocess(&self.assembly_code)
+    }
 
-            // Setze PC auf die erste INSTRUCTION (skip data)
-            let first_instruction = self
-                .machine_code
-                .iter()
-                .find(|(addr, _)| *addr >= 0x1000)
-                .or_else(|| self.machine_code.first());
+    fn machine_code(&self) -> &[(u32, u16)] {
+        self.controller
+            .emulator()
+            .program()
+            .map(|program| program.image.as_slice())
+            .unwrap_or(&[])
+    }
 
-            if let Some((first_address, _)) = first_instruction {
-                self.cpu.set_pc(*first_address);
-            }
+    /// Nach einem fehlgeschlagenen Assemble läuft das letzte gute Programm weiter
+    fn log_kept_program(&mut self) {
+        if self.controller.badge().is_some() {
+            self.output_log
+                .push_str("↩️ Letzter erfolgreicher Build bleibt aktiv (Step/Run)\n");
         }
     }
 
+    fn show_assembly_errors(&mut self, errors: &[AsmError]) {
+        self.error_message = errors
+            .iter()
+            .map(|error| format!("❌ {}", error))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.asm_errors = errors.to_vec();
+    }
+
+    fn assemble_initial_code(&mut self) {
+        // Initial assembly ohne Output-Meldungen für saubere Initialisierung
+        let source = self.preprocessed_source();
+        let _ = self.controller.load_image(&source, LoadMode::Full);
+    }
+
     fn assemble_code(&mut self) {
+        let was_running = self.pause_for_load();
         self.output_log.clear();
         self.error_message.clear();
+        self.asm_errors.clear();
+
+        let source = self.preprocessed_source();
+        match self.controller.load_image(&source, LoadMode::Full) {
+            Ok(_) => {}
+            Err(LoadError::Assembly(errors)) => {
+                self.show_assembly_errors(&errors);
+                self.log_kept_program();
+                return;
+            }
+            Err(error) => {
+                self.error_message = error.to_string();
+                return;
+            }
+        }
 
-        // Speicher löschen für neuen Code
-        self.memory.clear();
-
-        // Assembly-Code in Zeilen aufteilen und assemblieren
-        let lines: Vec<&str> = self
-            .assembly_code
-            .lines()
-            .map(|line| {
-                // Remove comments (everything after ';')
-                let line = line.split(';').next().unwrap_or("").trim();
-                // Remove line numbers if present (e.g., "1 ORG $1000" -> "ORG $1000")
-                if let Some(first_char) = line.chars().next() {
-                    if first_char.is_ascii_digit() {
-                        // Find first non-digit, non-whitespace character
-                        if let Some(pos) =
-                            line.find(|c: char| !c.is_ascii_digit() && !c.is_whitespace())
-                        {
-                            return line[pos..].trim();
-                        }
-                    }
-                }
-                line
-            })
-            .filter(|line| !line.is_empty())
-            .collect();
-
-        self.machine_code = self.assembler.assemble(&lines);
-
-        if self.machine_code.is_empty() {
+        if self.machine_code().is_empty() {
             self.error_message =
                 "Assembly fehlgeschlagen! Keine Instruktionen generiert.".to_string();
             return;
         }
 
-        // Maschinenbefehle in Speicher laden
-        for (address, instruction) in &self.machine_code {
-            self.memory.write_word(*address, *instruction);
-        }
-
         self.output_log.push_str("✅ Assembly erfolgreich!\n");
+        for warning in self.controller.emulator().assembler().warnings() {
+            self.output_log.push_str(&format!("⚠️ {}\n", warning));
+        }
+        for note in self.controller.emulator().assembler().notes() {
+            self.output_log.push_str(&format!("ℹ️ {}\n", note));
+        }
         self.output_log.push_str(&format!(
             "📊 {} Instruktionen generiert\n\n",
-            self.machine_code.len()
+            self.machine_code().len()
         ));
 
         // Assembly Listing anzeigen
-        self.assembler
-            .print_assembly_to_string(&mut self.output_log);
+        let mut listing = String::new();
+        self.controller
+            .emulator()
+            .assembler()
+            .print_assembly_to_string(&mut listing);
+        self.output_log.push_str(&listing);
 
         // CPU zurücksetzen und PC auf erste Instruktion setzen
         self.reset_emulator();
+        self.output_log.push_str(&format!(
+            "🎯 PC auf Startadresse 0x{:06X} gesetzt\n",
+            self.controller.emulator().cpu().get_pc()
+        ));
+        if was_running && self.resume_after_assemble {
+            self.controller.start();
+            self.output_log
+                .push_str("▶️ Lauf mit dem neuen Programm neu gestartet\n");
+        }
+    }
 
-        // Setze PC auf die Adresse der ersten INSTRUCTION (skip data section at $0800)
-        // Find first address >= $1000 (typical code section start)
-        let first_instruction = self
-            .machine_code
-            .iter()
-            .find(|(addr, _)| *addr >= 0x1000)
-            .or_else(|| self.machine_code.first());
-
-        if let Some((first_address, _)) = first_instruction {
-            self.cpu.set_pc(*first_address);
-            self.output_log.push_str(&format!(
-                "🎯 PC auf Startadresse 0x{:06X} gesetzt\n",
-                first_address
-            ));
+    /// Das Abbild wird nur angehalten getauscht: ein laufendes Programm wird vorher an der
+    /// nächsten Instruktionsgrenze pausiert. Gibt zurück, ob es lief.
+    fn pause_for_load(&mut self) -> bool {
+        let running = self.controller.is_running();
+        if running {
+            self.controller.pause();
         }
+        running
     }
 
-    fn run_program(&mut self) {
-        if !self.step_mode {
-            self.is_running = true;
-            // Kontinuierliche Ausführung (würde in echtem Code begrenzt werden)
-            for _ in 0..1000 {
-                // Maximal 1000 Schritte zur Sicherheit
-                let old_pc = self.cpu.get_pc();
-
-                // Prüfe ob PC noch innerhalb des Code-Bereichs ist
-                let in_range = self.machine_code.iter().any(|(addr, _)| *addr == old_pc);
-                if !in_range {
+    /// Nur den Code neu laden; Daten, Register und Breakpoints bleiben erhalten
+    fn reload_code(&mut self) {
+        let was_running = self.pause_for_load();
+        self.error_message.clear();
+        self.asm_errors.clear();
+
+        let source = self.preprocessed_source();
+        match self.controller.load_image(&source, LoadMode::CodeOnly) {
+            Ok(report) => {
+                let report = report.expect("code-only load reports");
+                self.output_log.push_str(&format!(
+                    "♻️ Code neu geladen ({} Wörter), Daten und Breakpoints erhalten\n",
+                    report.code_words_written
+                ));
+                for warning in &report.warnings {
+                    self.output_log.push_str(&format!("⚠️ {}\n", warning));
+                }
+                if report.pc_preserved {
                     self.output_log.push_str(&format!(
-                        "🛑 Programm beendet (PC 0x{:06X} außerhalb des Codes)\n",
-                        old_pc
+                        "🎯 PC bleibt bei 0x{:06X}\n",
+                        self.controller.emulator().cpu().get_pc()
                     ));
-                    break;
                 }
-
-                self.step_program();
-
-                // Prüfe ob PC sich geändert hat (SIMHALT hält PC an)
-                if self.cpu.get_pc() == old_pc {
+                if was_running && self.resume_after_assemble {
+                    self.controller.resume();
                     self.output_log
-                        .push_str("✓ Programm regulär beendet (SIMHALT)\n");
-                    break;
+                        .push_str("▶️ Lauf im neuen Code fortgesetzt\n");
                 }
             }
-            self.is_running = false;
+            Err(LoadError::Assembly(errors)) => {
+                self.show_assembly_errors(&errors);
+                self.log_kept_program();
+            }
+            Err(error) => self.error_message = error.to_string(),
+        }
+    }
+
+    fn run_program(&mut self) {
+        if !self.step_mode {
+            // Kontinuierliche Ausführung: der Controller arbeitet pro Frame ein Kontingent ab
+            if self.controller.state() == RunState::Paused {
+                self.controller.resume();
+            } else {
+                self.controller.start();
+            }
+            self.output_log.push_str(&format!(
+                "▶️ Ausführung gestartet ({} Instruktionen/Frame, Limit {})\n",
+                self.instructions_per_frame,
+                self.controller.step_cap()
+            ));
         } else {
             // Im Step Mode nur einen Schritt ausführen
             self.step_program();
@@ -424,46 +857,624 @@ impl EmulatorApp {
     }
 
     fn step_program(&mut self) {
-        let pc = self.cpu.get_pc();
+        // Derselbe Weg wie ein Lauf, nur mit einer Instruktion: Datenprüfung, Programmende,
+        // Breakpoints, Geräte und Meldungen kommen aus dem Controller
+        let steps = self.controller.total_steps();
+        self.controller.advance(1);
+        let executed = self.controller.total_steps() > steps;
+        if executed {
+            self.current_step += 1;
+            // Mit Trace kommt die Zeile schon als Meldung des Controllers
+            if self.controller.trace_level() == TraceLevel::Off {
+                let result = self.controller.history().last().expect("just executed");
+                let line = format!("PC 0x{:06X} → 0x{:06X}", result.pc, result.next_pc());
+                self.output_log
+                    .push_str(&format!("Step {}: {}\n", self.current_step, line));
+            }
+        }
+        for message in self.controller.take_messages() {
+            self.output_log.push_str(&format!("{}\n", message));
+        }
+        if executed {
+            if let Some(why) = self.controller.explain_last_branch() {
+                self.output_log.push_str(&format!("    ↳ {}\n", why));
+            }
+        }
+    }
 
-        // Prüfe ob PC auf eine assemblierte Instruktion zeigt
-        let instruction_exists = self.machine_code.iter().any(|(addr, _)| *addr == pc);
+    fn step_back(&mut self) {
+        let Some(back) = self.controller.step_back() else {
+            return;
+        };
+        self.current_step = self.current_step.saturating_sub(1);
+        self.output_log.push_str(&format!(
+            "⏮ Zurück zu 0x{:06X} ({} Schreibzugriffe rückgängig)\n",
+            back.pc, back.writes_undone
+        ));
+        if back.devices_ticked {
+            self.output_log
+                .push_str("⚠️ Gerätezustand wird nicht zurückgesetzt\n");
+        }
+    }
 
-        if !instruction_exists {
-            self.output_log.push_str(&format!(
-                "🛑 Programm beendet (PC 0x{:06X} ist außerhalb des assemblierten Codes)\n",
-                pc
-            ));
+    /// Vorschläge für die Cursorposition (Zeichenindex im Editor) sammeln
+    fn open_completion(&mut self, cursor: usize) {
+        let before: String = self.assembly_code.chars().take(cursor).collect();
+        let line = before.rsplit('\n').next().unwrap_or("");
+
+        // Symbole aus dem Quelltext, auch wenn er gerade nicht assembliert, plus die des
+        // geladenen Programms
+        let mut symbols = completion::symbols_in_source(&self.assembly_code);
+        if let Some(program) = self.controller.emulator().program() {
+            symbols.extend(program.symbols.keys().cloned());
+        }
+        symbols.sort();
+        symbols.dedup();
+
+        let (context, prefix) = CompletionContext::at(line, &symbols);
+        let start = cursor - prefix.chars().count();
+        let suggestions = completion::suggest(prefix, context);
+        self.completion = (!suggestions.is_empty()).then_some(CompletionPopup {
+            suggestions,
+            selected: 0,
+            start,
+            cursor,
+        });
+    }
+
+    /// Tasten für die offene Liste; liefert den mit Enter/Tab gewählten Eintrag
+    fn completion_keys(&mut self, ctx: &egui::Context) -> Option<usize> {
+        let popup = self.completion.as_mut()?;
+        let rows = popup.suggestions.len().min(COMPLETION_ROWS);
+        let consume = |key| ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, key));
+        if consume(egui::Key::Escape) {
+            self.completion = None;
+            return None;
+        }
+        if consume(egui::Key::ArrowDown) {
+            popup.selected = (popup.selected + 1) % rows;
+        }
+        if consume(egui::Key::ArrowUp) {
+            popup.selected = (popup.selected + rows - 1) % rows;
+        }
+        (consume(egui::Key::Enter) || consume(egui::Key::Tab)).then_some(popup.selected)
+    }
+
+    /// Liste unter dem Cursor; liefert den angeklickten Eintrag
+    fn show_completion(
+        ctx: &egui::Context,
+        popup: &CompletionPopup,
+        position: egui::Pos2,
+    ) -> Option<usize> {
+        let mut clicked = None;
+        egui::Area::new(egui::Id::new("completion_popup"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(position)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for (index, suggestion) in
+                        popup.suggestions.iter().take(COMPLETION_ROWS).enumerate()
+                    {
+                        let text = egui::RichText::new(&suggestion.text).monospace();
+                        let label = ui
+                            .selectable_label(index == popup.selected, text)
+                            .on_hover_text(format!("{:?}", suggestion.kind));
+                        if label.clicked() {
+                            clicked = Some(index);
+                        }
+                    }
+                });
+            });
+        clicked
+    }
+
+    /// Ersetzt den getippten Präfix durch den Vorschlag und setzt den Cursor dahinter
+    fn insert_completion(&mut self, ctx: &egui::Context, index: usize) {
+        let Some(popup) = self.completion.take() else {
+            return;
+        };
+        let Some(suggestion) = popup.suggestions.get(index) else {
+            return;
+        };
+        let byte = |chars: usize| {
+            self.assembly_code
+                .char_indices()
+                .nth(chars)
+                .map_or(self.assembly_code.len(), |(byte, _)| byte)
+        };
+        let range = byte(popup.start)..byte(popup.cursor);
+        self.assembly_code.replace_range(range, &suggestion.text);
+
+        let cursor = egui::text::CCursor::new(popup.start + suggestion.text.chars().count());
+        let mut state =
+            egui::text_edit::TextEditState::load(ctx, Self::editor_id()).unwrap_or_default();
+        state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+        state.store(ctx, Self::editor_id());
+        ctx.memory_mut(|memory| memory.request_focus(Self::editor_id()));
+    }
+
+    /// Referenzkarte zum Wort am Zeichenindex `index`, falls es ein Mnemonic ist
+    fn card_at(&mut self, index: usize) -> Option<Card> {
+        let chars: Vec<char> = self.assembly_code.chars().collect();
+        let is_word = |c: &char| c.is_ascii_alphanumeric() || *c == '.' || *c == '_';
+        let start = chars[..index.min(chars.len())]
+            .iter()
+            .rposition(|c| !is_word(c))
+            .map_or(0, |i| i + 1);
+        let word: String = chars[start..].iter().take_while(|c| is_word(c)).collect();
+        let mnemonic = word.split('.').next()?.to_uppercase();
+        if mnemonic.is_empty() {
+            return None;
+        }
+        self.reference_cards
+            .entry(mnemonic)
+            .or_insert_with_key(|mnemonic| card_for(mnemonic))
+            .clone()
+    }
+
+    /// Mit F1 geöffnete Karte, bis sie geschlossen wird
+    fn show_reference_card(&mut self, ctx: &egui::Context) {
+        let Some(card) = &self.reference_card else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new(format!("📖 {}", card.mnemonic))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.monospace(card.to_string());
+            });
+        if !open {
+            self.reference_card = None;
+        }
+    }
+
+    /// Quellzeile (1-basiert) des Textcursors im Editor
+    fn cursor_line(&self, ctx: &egui::Context) -> Option<usize> {
+        let state = egui::text_edit::TextEditState::load(ctx, Self::editor_id())?;
+        let index = state.cursor.char_range()?.primary.index;
+        let before = self.assembly_code.chars().take(index);
+        Some(1 + before.filter(|&c| c == '\n').count())
+    }
+
+    /// Setzt den Textcursor an den Anfang von `line` und fokussiert den Editor
+    fn jump_to_line(&mut self, ctx: &egui::Context, line: usize) {
+        let index: usize = self
+            .assembly_code
+            .split('\n')
+            .take(line.saturating_sub(1))
+            .map(|text| text.chars().count() + 1)
+            .sum();
+        let mut state =
+            egui::text_edit::TextEditState::load(ctx, Self::editor_id()).unwrap_or_default();
+        let cursor = egui::text::CCursor::new(index);
+        state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+        state.store(ctx, Self::editor_id());
+        ctx.memory_mut(|memory| memory.request_focus(Self::editor_id()));
+        self.show_compare_view = false;
+    }
+
+    /// Markierter Text im Editor mit seiner ersten Zeile (1-basiert); ohne Auswahl die
+    /// Zeile des Cursors
+    fn selection(&self, ctx: &egui::Context) -> Option<(usize, String)> {
+        let state = egui::text_edit::TextEditState::load(ctx, Self::editor_id())?;
+        let range = state.cursor.char_range()?;
+        let [start, end] = range.sorted_cursors().map(|cursor| cursor.index);
+        let first_line = 1 + self
+            .assembly_code
+            .chars()
+            .take(start)
+            .filter(|&c| c == '\n')
+            .count();
+        if start == end {
+            let line = self.assembly_code.lines().nth(first_line - 1)?;
+            return Some((first_line, line.to_string()));
+        }
+        let text = self.assembly_code.chars().skip(start).take(end - start);
+        Some((first_line, text.collect()))
+    }
+
+    /// Führt die Auswahl im Scratch-Bereich aus und schreibt die Endregister in die Konsole
+    fn run_selection(&mut self, ctx: &egui::Context, seed_current: bool) {
+        let Some((first_line, text)) = self.selection(ctx) else {
+            return;
+        };
+        let seed = if seed_current {
+            self.controller.emulator().cpu().snapshot()
+        } else {
+            CpuSnapshot::default()
+        };
+        let last_line = first_line + text.trim_end().lines().count().max(1) - 1;
+
+        match self
+            .controller
+            .emulator()
+            .run_fragment(&preprocess(&text), seed)
+        {
+            Ok(result) => {
+                let status = match (&result.outcome, result.completed) {
+                    (_, true) => "fertig".to_string(),
+                    (RunOutcome::Exception(exception), _) => exception.to_string(),
+                    (RunOutcome::StepLimit(limit), _) => {
+                        format!("nach {} Schritten abgebrochen", limit)
+                    }
+                    _ => format!("angehalten bei ${:06X}", result.registers.pc),
+                };
+                self.output_log.push_str(&format!(
+                    "▶️ Auswahl Zeilen {}-{}: {} Schritte, {}\n",
+                    first_line, last_line, result.steps, status
+                ));
+                let registers = &result.registers;
+                for (prefix, values) in [("D", &registers.d), ("A", &registers.a)] {
+                    let line: Vec<String> = values
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| format!("{}{}={:08X}", prefix, i, value))
+                        .collect();
+                    self.output_log
+                        .push_str(&format!("    {}\n", line.join(" ")));
+                }
+                self.output_log.push_str(&format!(
+                    "    PC={:06X} CCR={:05b} (XNZVC)\n",
+                    registers.pc, registers.ccr
+                ));
+            }
+            Err(errors) => {
+                for error in errors {
+                    self.output_log.push_str(&format!(
+                        "❌ Auswahl: line {}: {}\n",
+                        first_line + error.line - 1,
+                        error.message
+                    ));
+                }
+            }
+        }
+    }
+
+    fn editor_id() -> egui::Id {
+        egui::Id::new("assembly_text_editor")
+    }
+
+    fn toggle_breakpoint(&mut self, line: usize) {
+        let emulator = self.controller.emulator_mut();
+        let set = emulator.toggle_breakpoint(Location::Line(line));
+        let resolved = emulator.program().and_then(|p| p.address_for_line(line));
+        self.output_log.push_str(&match (set, resolved) {
+            (true, Some(address)) => format!("🔴 Breakpoint Zeile {} (${:06X})\n", line, address),
+            (true, None) => format!("🔴 Breakpoint Zeile {} (noch keine Instruktion)\n", line),
+            (false, _) => format!("⚪ Breakpoint Zeile {} entfernt\n", line),
+        });
+    }
+
+    /// Liste aller Breakpoints: Adresse, Label+Offset, Aktiv, Treffer und Bedingung
+    fn show_breakpoints(&mut self, ui: &mut egui::Ui) {
+        let emulator = self.controller.emulator();
+        if emulator.breakpoints().is_empty() {
+            ui.label("Keine Breakpoints (Ctrl+B setzt einen auf der Cursorzeile)");
             return;
         }
 
-        let old_pc = self.cpu.get_pc();
-        self.cpu.execute_instruction(&mut self.memory);
-        self.current_step += 1;
+        let program = emulator.program();
+        let rows: Vec<_> = emulator
+            .breakpoints()
+            .iter()
+            .map(|bp| {
+                let line = match &bp.location {
+                    Location::Line(line) => Some(*line),
+                    _ => bp.address.and_then(|a| program?.line_for_address(a)),
+                };
+                let symbol = bp.address.and_then(|a| program?.symbol_offset(a));
+                (bp.clone(), line, symbol)
+            })
+            .collect();
+
+        let mut jump = None;
+        let mut remove = None;
+        egui::Grid::new("breakpoint_list")
+            .striped(true)
+            .show(ui, |ui| {
+                for (bp, line, symbol) in rows {
+                    let mut enabled = bp.enabled;
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        self.controller
+                            .emulator_mut()
+                            .breakpoints_mut()
+                            .set_enabled(&bp.location, enabled);
+                    }
+
+                    let address = match bp.address {
+                        Some(address) => format!("${:06X}", address),
+                        None => "——".to_string(),
+                    };
+                    let label = format!("{} {}", address, symbol.unwrap_or_default());
+                    let response = ui.link(self.theme.text(label, TokenClass::Address));
+                    if let Some(line) = line {
+                        if response.on_hover_text(format!("Zeile {}", line)).clicked() {
+                            jump = Some(line);
+                        }
+                    }
+
+                    ui.monospace(format!("{}×", bp.hits));
+
+                    let text = self
+                        .condition_texts
+                        .entry(bp.location.clone())
+                        .or_insert_with(|| {
+                            bp.condition
+                                .as_ref()
+                                .map_or(String::new(), |c| c.to_string())
+                        });
+                    let edit = ui.add(
+                        egui::TextEdit::singleline(text)
+                            .hint_text("Bedingung, z.B. D0 == 5")
+                            .desired_width(120.0),
+                    );
+                    let parsed = (!text.trim().is_empty()).then(|| Condition::parse(text));
+                    if let Some(Err(message)) = &parsed {
+                        edit.on_hover_text(message);
+                        ui.colored_label(self.theme.color(TokenClass::Error), "⚠");
+                    } else {
+                        if edit.changed() {
+                            if let Some(target) = self
+                                .controller
+                                .emulator_mut()
+                                .breakpoints_mut()
+                                .get_mut(&bp.location)
+                            {
+                                target.condition = parsed.and_then(Result::ok);
+                            }
+                        }
+                        ui.label("");
+                    }
+
+                    if ui.small_button("🗑").on_hover_text("Entfernen").clicked() {
+                        remove = Some(bp.location.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.horizontal(|ui| {
+            if ui.button("Disable all").clicked() {
+                self.controller
+                    .emulator_mut()
+                    .breakpoints_mut()
+                    .set_all_enabled(false);
+            }
+            if ui.button("Remove all").clicked() {
+                self.controller.emulator_mut().breakpoints_mut().clear();
+                self.condition_texts.clear();
+            }
+        });
+
+        if let Some(location) = remove {
+            self.controller
+                .emulator_mut()
+                .breakpoints_mut()
+                .remove(&location);
+            self.condition_texts.remove(&location);
+        }
+        if let Some(line) = jump {
+            self.jump_to_line(ui.ctx(), line);
+        }
+    }
+
+    /// Eingabe für neue Register-Watches und die Liste mit Treffern
+    fn show_register_watches(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let edit = ui.add(
+                egui::TextEdit::singleline(&mut self.register_watch_text)
+                    .hint_text("D3 oder A7 < $7F00")
+                    .desired_width(120.0),
+            );
+            ui.checkbox(&mut self.register_watch_pause, "Pause on change");
+            let submitted = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Add").clicked() || submitted {
+                let text = self.register_watch_text.trim().to_string();
+                match self
+                    .controller
+                    .add_register_watch(&text, self.register_watch_pause)
+                {
+                    Ok(()) => {
+                        self.register_watch_text.clear();
+                        self.register_watch_error = None;
+                    }
+                    Err(message) => self.register_watch_error = Some(message),
+                }
+            }
+        });
+        if let Some(message) = &self.register_watch_error {
+            ui.colored_label(
+                self.theme.color(TokenClass::Error),
+                format!("⚠ {}", message),
+            );
+        }
+
+        let mut remove = None;
+        egui::Grid::new("register_watch_list")
+            .striped(true)
+            .show(ui, |ui| {
+                let watches = self.controller.register_watches_mut();
+                for (index, watch) in watches.iter_mut().enumerate() {
+                    ui.checkbox(&mut watch.enabled, "");
+                    ui.monospace(watch.to_string());
+                    ui.label(if watch.pause { "⏸" } else { "📝" })
+                        .on_hover_text(if watch.pause {
+                            "hält an"
+                        } else {
+                            "nur melden"
+                        });
+                    ui.monospace(format!("{}×", watch.hits));
+                    if ui.small_button("🗑").on_hover_text("Entfernen").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(index) = remove {
+            self.controller.register_watches_mut().remove(index);
+        }
+    }
+
+    /// Pfadeingabe für die Dateiausgabe des Logs
+    fn show_log_file_prompt(&mut self, ui: &mut egui::Ui) {
+        if let Some(error) = self.output_log.take_file_error() {
+            self.log_file_error = Some(error);
+        }
+        if self.log_file_prompt {
+            ui.horizontal(|ui| {
+                ui.label("Datei:");
+                let edit = ui
+                    .add(egui::TextEdit::singleline(&mut self.log_file_text).desired_width(240.0));
+                let submitted = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if ui.button("Start").clicked() || submitted {
+                    let path = std::path::PathBuf::from(self.log_file_text.trim());
+                    match self.output_log.start_file(&path) {
+                        Ok(()) => {
+                            self.log_file_prompt = false;
+                            self.log_file_error = None;
+                        }
+                        Err(error) => {
+                            self.log_file_error = Some(format!("{}: {}", path.display(), error))
+                        }
+                    }
+                }
+                ui.label("schreibt die Konsole und ab jetzt jede neue Zeile in die Datei");
+            });
+        }
+        if let Some(message) = &self.log_file_error {
+            ui.colored_label(
+                self.theme.color(TokenClass::Error),
+                format!("⚠ {}", message),
+            );
+        }
+    }
 
+    /// UART anschließen; ab dann zeigt der Serial-Tab seine Ausgabe
+    fn connect_uart(&mut self) {
+        let emulator = self.controller.emulator_mut();
+        self.uart = Some(emulator.attach_uart(Uart::DEFAULT_BAUD));
         self.output_log.push_str(&format!(
-            "Step {}: PC 0x{:06X} → 0x{:06X}\n",
-            self.current_step,
-            old_pc,
-            self.cpu.get_pc()
+            "🔌 UART an ${:04X}, {} Baud\n",
+            Uart::DEFAULT_BASE,
+            Uart::DEFAULT_BAUD
         ));
     }
 
-    fn reset_emulator(&mut self) {
-        self.cpu.reset();
-        self.current_step = 0;
-        self.is_running = false;
+    /// Serial-Tab: Ausgabe des Programms und eine Eingabezeile, die mit '\n' gesendet wird
+    fn show_serial(&mut self, ui: &mut egui::Ui) {
+        let Some(host) = &self.uart else {
+            ui.horizontal(|ui| {
+                if ui.button("🔌 Connect UART").clicked() {
+                    self.connect_uart();
+                }
+                ui.label(format!(
+                    "UART an ${:04X} mit {} Baud (siehe uart_echo.asm)",
+                    Uart::DEFAULT_BASE,
+                    Uart::DEFAULT_BAUD
+                ));
+            });
+            return;
+        };
+        ui.horizontal(|ui| {
+            let edit = ui.add(
+                egui::TextEdit::singleline(&mut self.serial_input)
+                    .hint_text("Eingabe für das Programm")
+                    .desired_width(320.0),
+            );
+            let submitted = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Send").clicked() || submitted {
+                let mut line = std::mem::take(&mut self.serial_input);
+                line.push('\n');
+                host.send(line.as_bytes());
+                edit.request_focus();
+            }
+            if ui.button("🗑️").on_hover_text("Clear").clicked() {
+                self.serial_log.clear();
+            }
+        });
+        ui.separator();
 
-        // Setze PC auf die erste INSTRUCTION zurück (skip data at $0800)
-        let first_instruction = self
-            .machine_code
-            .iter()
-            .find(|(addr, _)| *addr >= 0x1000)
-            .or_else(|| self.machine_code.first());
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::both()
+            .id_salt("serial_scroll")
+            .auto_shrink([false; 2])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, self.serial_log.len(), |ui, rows| {
+                for line in self.serial_log.lines(rows) {
+                    ui.add(egui::Label::new(egui::RichText::new(line).monospace()).extend());
+                }
+            });
+    }
 
-        if let Some((first_address, _)) = first_instruction {
-            self.cpu.set_pc(*first_address);
+    /// Hot Lines: Zyklen pro Quellzeile seit dem letzten Reset, teuerste zuerst
+    fn show_profile(&mut self, ui: &mut egui::Ui) {
+        let emulator = self.controller.emulator();
+        let profile = emulator.profile();
+        if profile.total_cycles() == 0 {
+            ui.label("Noch nichts ausgeführt");
+            return;
         }
+        let program = emulator.program();
+        let rows: Vec<_> = profile
+            .hot_lines()
+            .into_iter()
+            .take(PROFILE_ROWS)
+            .map(|hot| {
+                let text = hot
+                    .line
+                    .and_then(|line| program?.line_text(line))
+                    .unwrap_or("")
+                    .trim()
+                    .to_string();
+                (hot, text)
+            })
+            .collect();
+        ui.label(format!("{} Zyklen gesamt", profile.total_cycles()));
+
+        let mut jump = None;
+        egui::Grid::new("profile_list")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Zeile");
+                ui.strong("Zyklen");
+                ui.strong("%");
+                ui.strong("Anzahl");
+                ui.end_row();
+                for (hot, text) in rows {
+                    match hot.line {
+                        Some(line) => {
+                            let response = ui
+                                .link(self.theme.text(format!("{:>4}", line), TokenClass::Address));
+                            if response.on_hover_text(text).clicked() {
+                                jump = Some(line);
+                            }
+                        }
+                        None => {
+                            ui.label("unknown").on_hover_text(
+                                "Adressen ohne Quellzeile, Data-Sections und Interrupt-Annahme",
+                            );
+                        }
+                    }
+                    ui.monospace(hot.cycles.to_string());
+                    ui.monospace(format!("{:5.1}", hot.percent));
+                    ui.monospace(hot.count.to_string());
+                    ui.end_row();
+                }
+            });
+        if let Some(line) = jump {
+            self.jump_to_line(ui.ctx(), line);
+        }
+    }
+
+    fn reset_emulator(&mut self) {
+        // Setzt auch den PC auf die erste INSTRUCTION zurück (skip data at $0800)
+        self.controller.reset();
+        self.current_step = 0;
 
         self.output_log.push_str("🔄 Emulator zurückgesetzt\n");
     }
@@ -496,14 +1507,15 @@ impl EmulatorApp {
                     // Verwende fast die gesamte verfügbare Höhe
                     let content_height = ui.available_height() - 10.0;
 
+                    // Nur die sichtbaren Zeilen zeichnen (große Quelltexte)
+                    let line_count = self.assembly_code.lines().count();
                     egui::ScrollArea::both()
                         .id_salt("editor_view_scroll")
                         .auto_shrink([false; 2])
                         .min_scrolled_height(content_height)
                         .max_height(content_height)
-                        .show(ui, |ui| {
-                            // Syntax-highlighted Assembly anzeigen
-                            self.show_assembly_with_highlighting(ui);
+                        .show_rows(ui, Self::row_height(ui), line_count, |ui, rows| {
+                            self.show_assembly_with_highlighting(ui, rows);
                         });
                 },
             );
@@ -524,15 +1536,81 @@ impl EmulatorApp {
                     .min_scrolled_height(content_height)
                     .max_height(content_height)
                     .show(ui, |ui| {
-                        ui.add_sized(
-                            [ui.available_width(), content_height],
-                            egui::TextEdit::multiline(&mut self.assembly_code)
-                                .id(egui::Id::new("assembly_text_editor"))
-                                .font(egui::TextStyle::Monospace)
-                                .code_editor()
-                                .desired_width(f32::INFINITY)
-                                .desired_rows(50),
-                        );
+                        // Pfeiltasten und Enter gehören der Vorschlagsliste, solange sie offen ist
+                        let mut accepted = self.completion_keys(ui.ctx());
+                        let output = egui::TextEdit::multiline(&mut self.assembly_code)
+                            .id(Self::editor_id())
+                            .font(egui::TextStyle::Monospace)
+                            .code_editor()
+                            .desired_width(f32::INFINITY)
+                            .desired_rows(50)
+                            .min_size(egui::vec2(ui.available_width(), content_height))
+                            .show(ui);
+                        let response = output.response.clone();
+
+                        // Referenzkarte: Hover über einem Mnemonic, F1 auf dem Wort am Cursor
+                        if let Some(pointer) = response.hover_pos() {
+                            let index = output.galley.cursor_from_pos(pointer - output.galley_pos);
+                            if let Some(card) = self.card_at(index.index) {
+                                response.clone().on_hover_ui_at_pointer(|ui| {
+                                    ui.monospace(card.to_string());
+                                });
+                            }
+                        }
+                        if response.has_focus()
+                            && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::F1))
+                        {
+                            let card = output
+                                .cursor_range
+                                .and_then(|range| self.card_at(range.primary.index));
+                            self.reference_card = card;
+                        }
+
+                        let cursor = output.cursor_range.map(|range| range.primary.index);
+                        if response.has_focus()
+                            && ui.input_mut(|i| {
+                                i.consume_key(egui::Modifiers::COMMAND, egui::Key::Space)
+                            })
+                        {
+                            if let Some(cursor) = cursor {
+                                self.open_completion(cursor);
+                            }
+                        }
+                        if self
+                            .completion
+                            .as_ref()
+                            .is_some_and(|popup| Some(popup.cursor) != cursor)
+                        {
+                            self.completion = None;
+                        }
+                        if let Some(popup) = &self.completion {
+                            let anchor = output
+                                .galley
+                                .pos_from_cursor(egui::text::CCursor::new(popup.cursor));
+                            let position = output.galley_pos + anchor.left_bottom().to_vec2();
+                            if let Some(index) = Self::show_completion(ui.ctx(), popup, position) {
+                                accepted = Some(index);
+                            }
+                        }
+                        if let Some(index) = accepted {
+                            self.insert_completion(ui.ctx(), index);
+                        }
+
+                        // Rechtsklick: Auswahl isoliert ausführen (mit oder ohne aktuelle Register)
+                        let mut run_selection = None;
+                        response.context_menu(|ui| {
+                            if ui.button("▶ Run selection").clicked() {
+                                run_selection = Some(true);
+                                ui.close();
+                            }
+                            if ui.button("▶ Run selection (clean registers)").clicked() {
+                                run_selection = Some(false);
+                                ui.close();
+                            }
+                        });
+                        if let Some(seed_current) = run_selection {
+                            self.run_selection(ui.ctx(), seed_current);
+                        }
                     });
             });
         });
@@ -570,14 +1648,15 @@ impl EmulatorApp {
                     // Verwende fast die gesamte verfügbare Höhe
                     let content_height = ui.available_height() - 10.0;
 
+                    let line_count = self.assembly_code.lines().count();
                     egui::ScrollArea::vertical()
                         .id_salt("assembly_compare_scroll")
                         .auto_shrink([false; 2])
                         .min_scrolled_height(content_height)
                         .max_height(content_height)
-                        .show(ui, |ui| {
+                        .show_rows(ui, Self::row_height(ui), line_count, |ui, rows| {
                             // Show assembly with line numbers and syntax highlighting
-                            self.show_assembly_with_highlighting(ui);
+                            self.show_assembly_with_highlighting(ui, rows);
                         });
                 },
             );
@@ -592,318 +1671,320 @@ impl EmulatorApp {
                 // Verwende fast die gesamte verfügbare Höhe
                 let content_height = ui.available_height() - 10.0;
 
+                ui.label(self.theme.text(
+                    format!(
+                        "  {:<10}{:<8}{:<18}Instruction",
+                        "Address", "Code", "Binary"
+                    ),
+                    TokenClass::Punctuation,
+                ));
+                let word_count = self.machine_code().len();
                 egui::ScrollArea::vertical()
                     .id_salt("machine_code_scroll")
                     .auto_shrink([false; 2])
                     .min_scrolled_height(content_height)
                     .max_height(content_height)
-                    .show(ui, |ui| {
-                        self.show_machine_code_detailed(ui);
+                    .show_rows(ui, Self::row_height(ui), word_count, |ui, rows| {
+                        self.show_machine_code_detailed(ui, rows);
                     });
             });
         });
     }
 
-    fn show_assembly_with_highlighting(&mut self, ui: &mut egui::Ui) {
-        let lines: Vec<&str> = self.assembly_code.lines().collect();
+    /// Höhe einer Zeile in den virtualisierten Listen (ohne Abstand)
+    fn row_height(ui: &egui::Ui) -> f32 {
+        ui.text_style_height(&egui::TextStyle::Monospace)
+    }
 
-        // Use a Grid to ensure proper layout with unique IDs
-        egui::Grid::new("assembly_highlight_grid")
-            .num_columns(2)
-            .spacing([5.0, 2.0])
-            .striped(false)
-            .show(ui, |ui| {
-                for (line_num, line) in lines.iter().enumerate() {
-                    // Line number (VS Code style)
-                    ui.label(
-                        egui::RichText::new(format!("{:3}", line_num + 1))
-                            .color(egui::Color32::GRAY)
-                            .monospace(),
-                    );
+    /// Zeilen `rows` mit Zeilennummer und Hervorhebung; Tokens kommen aus dem Cache
+    fn show_assembly_with_highlighting(&mut self, ui: &mut egui::Ui, rows: Range<usize>) {
+        let breakpoint_lines: Vec<usize> = self
+            .controller
+            .emulator()
+            .breakpoints()
+            .iter()
+            .filter_map(|bp| match bp.location {
+                Location::Line(line) => Some(line),
+                _ => None,
+            })
+            .collect();
+        self.highlight_cache
+            .truncate(self.assembly_code.lines().count());
 
-                    // Assembly line with improved syntax highlighting
-                    if line.trim().is_empty() {
-                        ui.label(" ");
-                    } else if line.trim_start().starts_with(';') {
-                        // Comment - green
-                        ui.label(
-                            egui::RichText::new(*line)
-                                .color(egui::Color32::from_rgb(106, 153, 85))
-                                .monospace(),
-                        );
-                    } else if line.contains(':') && !line.trim_start().starts_with(' ') {
-                        // Label - bright yellow (VS Code style)
-                        ui.label(
-                            egui::RichText::new(*line)
-                                .color(egui::Color32::from_rgb(255, 215, 0))
-                                .monospace(),
-                        );
-                    } else {
-                        // Check for instruction highlighting
-                        self.highlight_instruction_improved(ui, line);
-                    }
+        let mut toggled = None;
+        let visible = self
+            .assembly_code
+            .lines()
+            .enumerate()
+            .skip(rows.start)
+            .take(rows.len());
+        for (line_num, line) in visible {
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                // Line number (VS Code style); Klick setzt/entfernt einen Breakpoint
+                let marker = if breakpoint_lines.contains(&(line_num + 1)) {
+                    "●"
+                } else {
+                    " "
+                };
+                let gutter = egui::Label::new(self.theme.text(
+                    format!("{}{:3}  ", marker, line_num + 1),
+                    TokenClass::LineNumber,
+                ))
+                .sense(egui::Sense::click());
+                if ui
+                    .add(gutter)
+                    .on_hover_text("Breakpoint (Ctrl+B)")
+                    .clicked()
+                {
+                    toggled = Some(line_num + 1);
+                }
 
-                    ui.end_row();
+                let errors: Vec<&AsmError> = self
+                    .asm_errors
+                    .iter()
+                    .filter(|error| error.line == line_num + 1)
+                    .collect();
+                for token in self.highlight_cache.tokens(line_num, line) {
+                    let text = self.theme.text(token.text.as_str(), token.class);
+                    let response = ui.label(if token.strong { text.strong() } else { text });
+                    // Fehler ohne Spalten markieren die ganze Zeile
+                    let hit = errors.iter().find(|error| {
+                        error
+                            .columns
+                            .as_ref()
+                            .is_none_or(|columns| token.overlaps(columns))
+                    });
+                    if let Some(error) = hit {
+                        draw_squiggle(ui, response.rect);
+                        response.on_hover_text(&error.message);
+                    }
                 }
             });
+        }
+        if let Some(line) = toggled {
+            self.toggle_breakpoint(line);
+        }
     }
 
-    fn highlight_instruction_improved(&self, ui: &mut egui::Ui, line: &str) {
-        // Split line into instruction and operands, preserving comments
-        let comment_pos = line.find(';');
-        let (code_part, comment_part) = if let Some(pos) = comment_pos {
-            (&line[..pos], Some(&line[pos..]))
-        } else {
-            (line, None)
+    /// Balken über den ganzen Adressraum mit Sections, Geräten, Stack und Markern für PC/SP;
+    /// ein Klick öffnet das Speicher-Panel an der Stelle
+    fn show_address_map(&mut self, ui: &mut egui::Ui) {
+        let emulator = self.controller.emulator();
+        let regions = address_map::regions(
+            emulator.program(),
+            emulator.bus(),
+            emulator.initial_sp(),
+            self.cpu_view.a[7],
+        );
+        let scale = MapScale::new(&regions);
+
+        let size = egui::vec2(ui.available_width(), 24.0);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+        let x_range = |range: &std::ops::Range<u32>| {
+            let span = scale.span(range, rect.width());
+            // Mindestens ein Pixel, damit auch ein einzelnes Register sichtbar ist
+            rect.left() + span.start..rect.left() + span.end.max(span.start + 1.0)
         };
+        for region in &regions {
+            let class = match region.kind {
+                RegionKind::Ram | RegionKind::Vectors => TokenClass::Comment,
+                RegionKind::Code => TokenClass::MoveMnemonic,
+                RegionKind::Data => TokenClass::Immediate,
+                RegionKind::Device => TokenClass::Label,
+                RegionKind::Stack => TokenClass::Register,
+            };
+            let x = x_range(&region.range);
+            let span = egui::Rect::from_x_y_ranges(x.start..=x.end, rect.y_range());
+            painter.rect_filled(
+                span.shrink2(egui::vec2(0.0, 3.0)),
+                1.0,
+                self.theme.color(class),
+            );
+        }
+        for (address, class) in [
+            (self.cpu_view.pc, TokenClass::CurrentLine),
+            (self.cpu_view.a[7], TokenClass::ChangedRegister),
+        ] {
+            let x = rect.left() + scale.position(address) * rect.width();
+            painter.vline(
+                x,
+                rect.y_range(),
+                egui::Stroke::new(2.0, self.theme.color(class)),
+            );
+        }
 
-        let trimmed_code = code_part.trim();
-        if trimmed_code.is_empty() {
-            ui.label(" ");
-            return;
+        // Tooltip und Klick: der kleinste Bereich unter der Maus, sonst die Adresse selbst
+        let pointer = response.hover_pos();
+        let hovered = pointer.map(|pos| {
+            let region = regions
+                .iter()
+                .filter(|region| x_range(&region.range).contains(&pos.x))
+                .min_by_key(|region| region.len());
+            let address = scale.address((pos.x - rect.left()) / rect.width());
+            (region.cloned(), address)
+        });
+        if let Some((region, address)) = &hovered {
+            let text = match region {
+                Some(region) => region.describe(),
+                None => format!("frei: ${:06X}", address),
+            };
+            response.clone().on_hover_text(format!(
+                "{}\nPC ${:06X}, SP ${:06X}",
+                text, self.cpu_view.pc, self.cpu_view.a[7]
+            ));
+        }
+        if response.clicked() {
+            if let Some((region, address)) = hovered {
+                let address = region.map_or(address, |region| region.range.start);
+                self.memory_address = address & !0xF;
+                self.memory_address_text = format!("${:06X}", self.memory_address);
+            }
         }
+        ui.small("Striche: PC und SP; Klick öffnet das Speicher-Panel");
+    }
 
-        // Use horizontal layout for better control
+    /// Hexdump ab `memory_address`
+    fn show_memory(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
-            let parts: Vec<&str> = trimmed_code.split_whitespace().collect();
-
-            if !parts.is_empty() {
-                let instruction = parts[0].to_uppercase();
-
-                // Instruction mnemonic with improved colors
-                let instr_color = match instruction.as_str() {
-                    "MOVEQ" | "MOVE" => egui::Color32::from_rgb(86, 156, 214), // Blue
-                    "ADD" | "SUB" | "CMP" => egui::Color32::from_rgb(78, 201, 176), // Cyan
-                    "BRA" | "BEQ" | "BNE" | "BCC" | "BCS" | "BPL" | "BMI" | "BGE" | "BLT"
-                    | "BGT" | "BLE" => egui::Color32::from_rgb(197, 134, 192), // Purple
-                    "JMP" | "JUMP" => egui::Color32::from_rgb(255, 165, 0), // Orange for jump instructions
-                    "NOP" => egui::Color32::from_rgb(156, 220, 254),        // Light blue
-                    _ => egui::Color32::from_rgb(220, 220, 220),            // Default light gray
-                };
-
-                ui.label(
-                    egui::RichText::new(&instruction)
-                        .color(instr_color)
-                        .monospace()
-                        .strong(),
-                );
-
-                // Operands with improved highlighting
-                if parts.len() > 1 {
-                    let operands = parts[1..].join(" ");
-                    self.highlight_operands_improved(ui, &operands);
+            ui.label("Adresse:");
+            let response = ui
+                .add(egui::TextEdit::singleline(&mut self.memory_address_text).desired_width(80.0));
+            if response.changed() {
+                if let Some(address) = parse_address(&self.memory_address_text) {
+                    self.memory_address = address;
                 }
             }
+        });
 
-            // Comment - green (VS Code comment color)
-            if let Some(comment) = comment_part {
-                ui.label(
-                    egui::RichText::new(comment)
-                        .color(egui::Color32::from_rgb(106, 153, 85))
-                        .monospace(),
-                );
+        let memory = self.controller.emulator().memory();
+        let formatter = self.controller.emulator().address_formatter();
+        let mut dump = String::new();
+        for row in 0..16u32 {
+            let address = self.memory_address.wrapping_add(row * 8) & 0x00FF_FFFF;
+            let bytes: Vec<u8> = (0..8).map(|i| memory.read_byte(address + i)).collect();
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let ascii: String = bytes
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            // Label der Zeile, z.B. "BUFFER+8"
+            let label = formatter.format(address).unwrap_or_default();
+            let line = format!("{:06X}  {}  {}  {}", address, hex.join(" "), ascii, label);
+            dump.push_str(line.trim_end());
+            dump.push('\n');
+        }
+        ui.label(self.theme.text(dump, TokenClass::MachineCode).monospace());
+    }
+
+    /// Langwörter ab SP; Werte, die auf ein Label zeigen (Rücksprungadressen, Puffer),
+    /// erscheinen zusätzlich symbolisch
+    fn show_stack(&self, ui: &mut egui::Ui) {
+        let emulator = self.controller.emulator();
+        let formatter = emulator.address_formatter();
+        let sp = self.cpu_view.a[7];
+        egui::Grid::new("stack").show(ui, |ui| {
+            for slot in 0..STACK_SLOTS {
+                let address = sp.wrapping_add(4 * slot) & 0x00FF_FFFF;
+                let value = emulator.memory().read_long(address);
+                ui.label(self.theme.text(
+                    format!("SP+{:<2} {:06X}", 4 * slot, address),
+                    TokenClass::Address,
+                ));
+                ui.monospace(format!("{:08X}", value));
+                ui.label(self.theme.text(
+                    formatter.format(value & 0x00FF_FFFF).unwrap_or_default(),
+                    TokenClass::Label,
+                ));
+                ui.end_row();
             }
         });
     }
 
-    fn highlight_operands_improved(&self, ui: &mut egui::Ui, operands: &str) {
-        ui.label(egui::RichText::new(" ").monospace()); // Space between instruction and operands
-
-        // Improved operand highlighting with better parsing
-        let parts: Vec<&str> = operands.split(',').collect();
-
-        for (i, part) in parts.iter().enumerate() {
-            let part = part.trim();
-
-            let color = if part.starts_with('#') {
-                // Immediate values - orange/green
-                egui::Color32::from_rgb(181, 206, 168)
-            } else if part.starts_with('D')
-                && part.chars().nth(1).is_some_and(|c| c.is_ascii_digit())
-            {
-                // Data registers - light blue
-                egui::Color32::from_rgb(156, 220, 254)
-            } else if part.starts_with('A')
-                && part.chars().nth(1).is_some_and(|c| c.is_ascii_digit())
-            {
-                // Address registers - light blue
-                egui::Color32::from_rgb(156, 220, 254)
-            } else {
-                // Labels or other - yellow
-                egui::Color32::from_rgb(255, 215, 0)
-            };
-
-            ui.label(egui::RichText::new(part).color(color).monospace());
-
-            // Add comma if not the last part
-            if i < parts.len() - 1 {
+    /// Wörter `rows` des Abbilds; nur sichtbare Zeilen werden disassembliert
+    fn show_machine_code_detailed(&self, ui: &mut egui::Ui, rows: Range<usize>) {
+        for (address, instruction) in &self.machine_code()[rows] {
+            let current = *address == self.cpu_view.pc;
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                // Address with current PC marker
+                let (marker, class) = if current {
+                    ("►", TokenClass::CurrentLine)
+                } else {
+                    (" ", TokenClass::Address)
+                };
                 ui.label(
-                    egui::RichText::new(", ")
-                        .color(egui::Color32::WHITE)
-                        .monospace(),
+                    self.theme
+                        .text(format!("{} 0x{:06X}  ", marker, address), class),
                 );
-            }
+                ui.label(
+                    self.theme
+                        .text(format!("0x{:04X}  ", instruction), TokenClass::MachineCode),
+                );
+                ui.label(
+                    self.theme
+                        .text(format!("{:016b}  ", instruction), TokenClass::Binary),
+                );
+                let emulator = self.controller.emulator();
+                let text = disassemble_at_symbolic(
+                    emulator.memory(),
+                    *address,
+                    emulator.address_formatter(),
+                )
+                .text;
+                ui.label(self.theme.text(text, TokenClass::Disassembly));
+            });
         }
     }
+}
 
-    fn show_machine_code_detailed(&self, ui: &mut egui::Ui) {
-        egui::Grid::new("machine_code_detailed_grid")
-            .striped(true)
-            .spacing([8.0, 4.0])
-            .show(ui, |ui| {
-                // Header
-                ui.strong("Address");
-                ui.strong("Machine Code");
-                ui.strong("Binary");
-                ui.strong("Instruction");
-                ui.end_row();
-
-                for (address, instruction) in self.machine_code.iter() {
-                    let current_marker = if *address == self.cpu.get_pc() {
-                        "►"
-                    } else {
-                        " "
-                    };
-
-                    // Address with current PC marker
-                    ui.label(
-                        egui::RichText::new(format!("{} 0x{:06X}", current_marker, address))
-                            .monospace()
-                            .color(if *address == self.cpu.get_pc() {
-                                egui::Color32::YELLOW
-                            } else {
-                                egui::Color32::WHITE
-                            }),
-                    );
-
-                    // Machine code
-                    ui.label(
-                        egui::RichText::new(format!("0x{:04X}", instruction))
-                            .monospace()
-                            .color(egui::Color32::from_rgb(181, 206, 168)),
-                    );
-
-                    // Binary representation
-                    ui.label(
-                        egui::RichText::new(format!("{:016b}", instruction))
-                            .monospace()
-                            .color(egui::Color32::GRAY),
-                    );
-
-                    // Decoded instruction (if available)
-                    ui.label(
-                        egui::RichText::new(self.decode_instruction(*instruction))
-                            .monospace()
-                            .color(egui::Color32::from_rgb(206, 145, 120)),
+/// Quelltext für den Assembler: Kommentare und führende Zeilennummern entfernen.
+/// Leere Zeilen bleiben erhalten, damit Fehler die richtige Zeilennummer haben.
+/// Spalten bleiben erhalten, damit die Fehlerspalten des Assemblers zum Editor passen
+fn preprocess(source: &str) -> String {
+    source
+        .lines()
+        .map(|line| {
+            // Remove comments (everything after ';')
+            let line = line.split(';').next().unwrap_or("").trim_end();
+            // Replace line numbers if present (e.g., "1 ORG $1000" -> "  ORG $1000")
+            if line.trim_start().starts_with(|c: char| c.is_ascii_digit()) {
+                // Find first non-digit, non-whitespace character
+                if let Some(pos) = line.find(|c: char| !c.is_ascii_digit() && !c.is_whitespace()) {
+                    return format!(
+                        "{}{}",
+                        " ".repeat(line[..pos].chars().count()),
+                        &line[pos..]
                     );
-
-                    ui.end_row();
-                }
-            });
-    }
-
-    fn decode_instruction(&self, instruction: u16) -> String {
-        let opcode = (instruction >> 12) & 0xF;
-
-        match opcode {
-            0x7 => {
-                let reg = (instruction >> 9) & 0x7;
-                let immediate = (instruction & 0xFF) as i8;
-                format!("MOVEQ #{}, D{}", immediate, reg)
-            }
-            0x2 => {
-                // MOVE.L variants
-                if (instruction & 0xFFF8) == 0x2078 {
-                    let reg = (instruction >> 9) & 0x7;
-                    format!("MOVE.L (xxx).W, D{}", reg)
-                } else if (instruction & 0xFFF8) == 0x23C0 {
-                    let reg = instruction & 0x7;
-                    format!("MOVE.L D{}, (xxx).W", reg)
-                } else {
-                    format!("MOVE 0x{:04X}", instruction)
-                }
-            }
-            0x3 => {
-                let dest_reg = (instruction >> 9) & 0x7;
-                let src_reg = instruction & 0x7;
-                format!("MOVE D{}, D{}", src_reg, dest_reg)
-            }
-            0x4 => {
-                if instruction == 0x4E71 {
-                    "NOP".to_string()
-                } else if instruction == 0x4E72 {
-                    "SIMHALT".to_string()
-                } else if instruction == 0x4EF8 {
-                    "JMP (xxx).W".to_string()
-                } else if (instruction & 0xFFC0) == 0x4A80 {
-                    // TST.L Dn
-                    let reg = instruction & 0x7;
-                    format!("TST.L D{}", reg)
-                } else {
-                    format!("MISC 0x{:04X}", instruction)
                 }
             }
-            0x5 => {
-                if (instruction & 0xF1C0) == 0x5180 {
-                    // SUBQ.L
-                    let data = (instruction >> 9) & 0x7;
-                    let reg = instruction & 0x7;
-                    let immediate = if data == 0 { 8 } else { data };
-                    format!("SUBQ.L #{}, D{}", immediate, reg)
-                } else if (instruction & 0xFFF8) == 0x51C8 {
-                    // DBRA
-                    let reg = instruction & 0x7;
-                    format!("DBRA D{}, (disp)", reg)
-                } else {
-                    format!("Scc/DBcc 0x{:04X}", instruction)
-                }
-            }
-            0x6 => {
-                let condition = (instruction >> 8) & 0xF;
-                let displacement = (instruction & 0xFF) as i8;
-                let condition_name = match condition {
-                    0x0 => "BRA", // Always
-                    0x1 => "BSR", // Branch to subroutine
-                    0x2 => "BHI", // Branch if higher
-                    0x3 => "BLS", // Branch if lower or same
-                    0x4 => "BCC", // Branch if carry clear
-                    0x5 => "BCS", // Branch if carry set
-                    0x6 => "BNE", // Branch if not equal
-                    0x7 => "BEQ", // Branch if equal
-                    0x8 => "BVC", // Branch if overflow clear
-                    0x9 => "BVS", // Branch if overflow set
-                    0xA => "BPL", // Branch if plus
-                    0xB => "BMI", // Branch if minus
-                    0xC => "BGE", // Branch if greater or equal
-                    0xD => "BLT", // Branch if less than
-                    0xE => "BGT", // Branch if greater than
-                    0xF => "BLE", // Branch if less or equal
-                    _ => "Bcc",
-                };
-                format!("{} {:+}", condition_name, displacement)
-            }
-            0xD => {
-                let dest_reg = (instruction >> 9) & 0x7;
-                let src_reg = instruction & 0x7;
-                format!("ADD D{}, D{}", src_reg, dest_reg)
-            }
-            0xB => {
-                let dest_reg = (instruction >> 9) & 0x7;
-                let src_reg = instruction & 0x7;
-                format!("CMP D{}, D{}", src_reg, dest_reg)
-            }
-            0xE => {
-                if (instruction & 0xF1C0) == 0xE180 {
-                    // ASL.L #imm, Dn
-                    let count = (instruction >> 9) & 0x7;
-                    let reg = instruction & 0x7;
-                    let shift = if count == 0 { 8 } else { count };
-                    format!("ASL.L #{}, D{}", shift, reg)
-                } else {
-                    format!("SHIFT 0x{:04X}", instruction)
-                }
-            }
-            _ => format!("UNK 0x{:04X}", instruction),
-        }
-    }
+            line.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Rote Zickzacklinie unter `rect`, wie Rechtschreibfehler im Editor
+fn draw_squiggle(ui: &egui::Ui, rect: egui::Rect) {
+    const STEP: f32 = 3.0;
+    let y = rect.bottom() - 1.0;
+    let points: Vec<egui::Pos2> = (0..=((rect.width() / STEP) as usize))
+        .map(|i| {
+            let x = rect.left() + i as f32 * STEP;
+            egui::pos2(x, if i % 2 == 0 { y } else { y - 2.0 })
+        })
+        .collect();
+    ui.painter().add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.0, egui::Color32::RED),
+    ));
 }
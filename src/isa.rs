@@ -0,0 +1,409 @@
+// Statische Instruktionstabelle
+// Beschreibt, welche Operanden und Größen jede unterstützte Instruktion akzeptiert.
+
+use std::fmt;
+
+/// SIMHALT: Line-A-Wort, das auf dem 68000 keiner echten Instruktion entspricht
+pub const SIMHALT_OPCODE: u16 = 0xA000;
+/// Frühere SIMHALT-Kodierung; eigentlich STOP ohne Immediate Word
+pub const LEGACY_SIMHALT_OPCODE: u16 = 0x4E72;
+
+/// Operandengröße (Suffix .B, .W, .L)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Size {
+    Byte,
+    Word,
+    Long,
+}
+
+impl Size {
+    /// Parst ein Größen-Suffix ohne Punkt ("B", "W", "L"; "S" = Short Branch)
+    pub fn from_suffix(suffix: &str) -> Option<Size> {
+        match suffix.to_uppercase().as_str() {
+            "B" | "S" => Some(Size::Byte),
+            "W" => Some(Size::Word),
+            "L" => Some(Size::Long),
+            _ => None,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Size::Byte => "B",
+            Size::Word => "W",
+            Size::Long => "L",
+        }
+    }
+
+    pub fn bytes(self) -> u32 {
+        match self {
+            Size::Byte => 1,
+            Size::Word => 2,
+            Size::Long => 4,
+        }
+    }
+}
+
+/// Syntaktische Operandenklasse (Adressierungsart aus Sicht des Assemblers)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperandClass {
+    DataRegister,    // Dn
+    AddressRegister, // An
+    Indirect,        // (An)
+    PostIncrement,   // (An)+
+    PreDecrement,    // -(An)
+    Displacement,    // d(An)
+    Absolute,        // Label oder Adresse
+    Immediate,       // #wert
+    ConditionCodes,  // CCR
+}
+
+impl OperandClass {
+    pub fn description(self) -> &'static str {
+        match self {
+            OperandClass::DataRegister => "data register",
+            OperandClass::AddressRegister => "address register",
+            OperandClass::Indirect => "address register indirect",
+            OperandClass::PostIncrement => "postincrement",
+            OperandClass::PreDecrement => "predecrement",
+            OperandClass::Displacement => "displacement",
+            OperandClass::Absolute => "absolute/label",
+            OperandClass::Immediate => "immediate",
+            OperandClass::ConditionCodes => "condition code register",
+        }
+    }
+}
+
+impl fmt::Display for OperandClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// Ein Eintrag der Instruktionstabelle
+#[derive(Debug)]
+pub struct InstructionSpec {
+    pub mnemonic: &'static str,
+    /// Erlaubte Operandenklassen pro Position (Länge = erwartete Operandenanzahl)
+    pub operands: &'static [&'static [OperandClass]],
+    /// Erlaubte Größen-Suffixe (leer = kein Suffix erlaubt)
+    pub sizes: &'static [Size],
+    /// Einzeiler für die Referenzkarte (`reference::card_for`)
+    pub description: &'static str,
+    /// Wirkung auf X N Z V C wie im Programmer's Reference Manual: `*` nach dem Ergebnis,
+    /// `-` unverändert, `0` gelöscht
+    pub flags: &'static str,
+}
+
+impl InstructionSpec {
+    pub fn operand_count(&self) -> usize {
+        self.operands.len()
+    }
+
+    pub fn allows_size(&self, size: Size) -> bool {
+        self.sizes.contains(&size)
+    }
+}
+
+use OperandClass::*;
+
+const BWL: &[Size] = &[Size::Byte, Size::Word, Size::Long];
+const SHORT: &[Size] = &[Size::Byte];
+
+const DREG: &[OperandClass] = &[DataRegister];
+const AREG: &[OperandClass] = &[AddressRegister];
+const IMM: &[OperandClass] = &[Immediate];
+const ABS: &[OperandClass] = &[Absolute];
+const CCR: &[OperandClass] = &[ConditionCodes];
+
+const BRANCH: &[&[OperandClass]] = &[ABS];
+
+/// Alle vom Assembler unterstützten Instruktionen
+pub static INSTRUCTIONS: &[InstructionSpec] = &[
+    InstructionSpec {
+        mnemonic: "MOVEQ",
+        operands: &[IMM, DREG],
+        sizes: &[Size::Long],
+        description: "move a sign-extended 8-bit immediate into a data register",
+        flags: "-**00",
+    },
+    InstructionSpec {
+        mnemonic: "MOVE",
+        operands: &[
+            &[DataRegister, AddressRegister, Indirect, Absolute, Immediate],
+            &[DataRegister, Indirect, Absolute],
+        ],
+        sizes: BWL,
+        description: "copy a value between registers and memory",
+        flags: "-**00",
+    },
+    InstructionSpec {
+        mnemonic: "MOVEA",
+        operands: &[
+            &[
+                Immediate,
+                DataRegister,
+                AddressRegister,
+                Indirect,
+                PostIncrement,
+                PreDecrement,
+            ],
+            AREG,
+        ],
+        sizes: &[Size::Word, Size::Long],
+        description: "load an address register; .W sources are sign-extended",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "MULS",
+        operands: &[&[DataRegister, Immediate], DREG],
+        sizes: &[Size::Word],
+        description: "signed 16 × 16 → 32-bit multiply into a data register",
+        flags: "-**00",
+    },
+    InstructionSpec {
+        mnemonic: "TST",
+        operands: &[DREG],
+        sizes: BWL,
+        description: "compare a value against zero",
+        flags: "-**00",
+    },
+    InstructionSpec {
+        mnemonic: "ADDQ",
+        operands: &[IMM, &[DataRegister, AddressRegister]],
+        sizes: BWL,
+        description: "add an immediate 1-8",
+        flags: "*****",
+    },
+    InstructionSpec {
+        mnemonic: "SUBQ",
+        operands: &[IMM, &[DataRegister, AddressRegister]],
+        sizes: BWL,
+        description: "subtract an immediate 1-8",
+        flags: "*****",
+    },
+    InstructionSpec {
+        mnemonic: "ASL",
+        operands: &[IMM, DREG],
+        sizes: BWL,
+        description: "arithmetic shift left by 1-8 bits",
+        flags: "*****",
+    },
+    InstructionSpec {
+        mnemonic: "DBRA",
+        operands: &[DREG, ABS],
+        sizes: &[],
+        description: "decrement Dn.W and branch unless it becomes -1",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BRA",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch always",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BEQ",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if equal (Z set)",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BNE",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if not equal (Z clear)",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BCC",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if carry clear",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BCS",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if carry set",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BPL",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if plus (N clear)",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BMI",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if minus (N set)",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BGE",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if greater or equal (signed)",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BLT",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if less than (signed)",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BGT",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if greater than (signed)",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "BLE",
+        operands: BRANCH,
+        sizes: SHORT,
+        description: "branch if less or equal (signed)",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "NOP",
+        operands: &[],
+        sizes: &[],
+        description: "no operation",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "RTE",
+        operands: &[],
+        sizes: &[],
+        description: "return from exception: restore SR and PC from the stack",
+        flags: "*****",
+    },
+    InstructionSpec {
+        mnemonic: "SIMHALT",
+        operands: &[],
+        sizes: &[],
+        description: "stop the simulation (emulator extension)",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "ADD",
+        operands: &[&[DataRegister, AddressRegister], DREG],
+        sizes: BWL,
+        description: "add source to a data register",
+        flags: "*****",
+    },
+    InstructionSpec {
+        mnemonic: "SUB",
+        operands: &[&[DataRegister, AddressRegister], DREG],
+        sizes: BWL,
+        description: "subtract source from a data register",
+        flags: "*****",
+    },
+    InstructionSpec {
+        mnemonic: "CMP",
+        operands: &[
+            &[DataRegister, AddressRegister, Absolute, Immediate],
+            &[DataRegister, AddressRegister],
+        ],
+        sizes: BWL,
+        description: "compare: set flags from destination - source",
+        flags: "-****",
+    },
+    InstructionSpec {
+        mnemonic: "CMPA",
+        operands: &[&[DataRegister, AddressRegister, Absolute, Immediate], AREG],
+        sizes: &[Size::Word, Size::Long],
+        description: "compare with all 32 bits of an address register",
+        flags: "-****",
+    },
+    InstructionSpec {
+        mnemonic: "CMPI",
+        operands: &[IMM, DREG],
+        sizes: BWL,
+        description: "compare a data register with an immediate",
+        flags: "-****",
+    },
+    InstructionSpec {
+        mnemonic: "ORI",
+        operands: &[IMM, CCR],
+        sizes: SHORT,
+        description: "OR an immediate into the condition codes",
+        flags: "*****",
+    },
+    InstructionSpec {
+        mnemonic: "ANDI",
+        operands: &[IMM, CCR],
+        sizes: SHORT,
+        description: "AND an immediate into the condition codes",
+        flags: "*****",
+    },
+    InstructionSpec {
+        mnemonic: "EORI",
+        operands: &[IMM, CCR],
+        sizes: SHORT,
+        description: "exclusive-OR an immediate into the condition codes",
+        flags: "*****",
+    },
+    InstructionSpec {
+        mnemonic: "JMP",
+        operands: &[&[Absolute, Indirect]],
+        sizes: &[],
+        description: "jump to an address",
+        flags: "-----",
+    },
+    InstructionSpec {
+        mnemonic: "JUMP",
+        operands: &[&[Absolute, Indirect]],
+        sizes: &[],
+        description: "alias of JMP",
+        flags: "-----",
+    },
+];
+
+/// Sucht den Tabelleneintrag zu einem (bereits großgeschriebenen) Mnemonic
+pub fn lookup(mnemonic: &str) -> Option<&'static InstructionSpec> {
+    INSTRUCTIONS.iter().find(|spec| spec.mnemonic == mnemonic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_known_and_unknown() {
+        assert_eq!(lookup("MOVEQ").map(|s| s.operand_count()), Some(2));
+        assert_eq!(lookup("NOP").map(|s| s.operand_count()), Some(0));
+        assert!(lookup("FOO").is_none());
+    }
+
+    #[test]
+    fn test_mnemonics_are_unique() {
+        for (i, spec) in INSTRUCTIONS.iter().enumerate() {
+            assert!(
+                INSTRUCTIONS[i + 1..]
+                    .iter()
+                    .all(|other| other.mnemonic != spec.mnemonic),
+                "{} appears twice in the instruction table",
+                spec.mnemonic
+            );
+        }
+    }
+
+    #[test]
+    fn test_size_suffix_roundtrip() {
+        for size in [Size::Byte, Size::Word, Size::Long] {
+            assert_eq!(Size::from_suffix(size.suffix()), Some(size));
+        }
+        assert_eq!(Size::from_suffix("s"), Some(Size::Byte));
+        assert_eq!(Size::from_suffix("X"), None);
+    }
+}
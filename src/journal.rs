@@ -0,0 +1,110 @@
+// Schreib-Journal für das schrittweise Zurückspulen
+// Statt vor jedem Schritt den ganzen Speicher zu kopieren, merkt sich jeder Schritt nur den
+// CPU-Zustand davor und die alten Werte der Speicherzellen, die die CPU beschrieben hat.
+// Rückwärts werden die Werte in umgekehrter Reihenfolge zurückgeschrieben.
+// Gerätezustand wird nicht gesichert; ein Schritt merkt sich nur, ob Geräte mitliefen.
+
+use crate::bus::IrqRequest;
+use crate::cpu::CpuState;
+use crate::memory::JournalEntry;
+use crate::profiler::ProfileSample;
+use std::collections::VecDeque;
+
+/// So viele Schritte lassen sich höchstens zurückgehen
+pub const JOURNAL_LEN: usize = 10_000;
+
+/// Alles, was nötig ist, um einen Schritt von `Emulator::step` rückgängig zu machen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepRecord {
+    pub cpu: CpuState,
+    /// Schreibzugriffe der CPU (Interrupt-Stackframe und Instruktion) in Schreibreihenfolge
+    pub writes: Vec<JournalEntry>,
+    pub steps: u64,
+    pub interrupts: u64,
+    pub last_interrupt: Option<IrqRequest>,
+    /// Geräte haben Zyklen bekommen; ihr Zustand wird beim Zurückgehen nicht zurückgesetzt
+    pub devices_ticked: bool,
+    /// Beitrag zum Profil, wird beim Zurückgehen abgezogen
+    pub profile: ProfileSample,
+}
+
+/// Begrenzte Liste der letzten Schritte; der älteste fällt heraus
+#[derive(Debug, Clone)]
+pub struct WriteJournal {
+    records: VecDeque<StepRecord>,
+    capacity: usize,
+}
+
+impl Default for WriteJournal {
+    fn default() -> Self {
+        Self::new(JOURNAL_LEN)
+    }
+}
+
+impl WriteJournal {
+    pub fn new(capacity: usize) -> Self {
+        WriteJournal {
+            records: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, record: StepRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// Jüngster Schritt
+    pub fn pop(&mut self) -> Option<StepRecord> {
+        self.records.pop_back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::CPU;
+
+    fn record(steps: u64) -> StepRecord {
+        StepRecord {
+            cpu: CPU::new().state(),
+            writes: Vec::new(),
+            steps,
+            interrupts: 0,
+            last_interrupt: None,
+            devices_ticked: false,
+            profile: ProfileSample::default(),
+        }
+    }
+
+    #[test]
+    fn test_journal_drops_oldest() {
+        let mut journal = WriteJournal::new(3);
+        for steps in 0..5 {
+            journal.push(record(steps));
+        }
+        assert_eq!(journal.len(), 3);
+        let order: Vec<u64> = std::iter::from_fn(|| journal.pop())
+            .map(|r| r.steps)
+            .collect();
+        assert_eq!(order, [4, 3, 2]);
+        assert!(journal.is_empty());
+    }
+}
@@ -1,12 +1,48 @@
+pub mod address_format;
+pub mod address_map;
 pub mod assembler;
+pub mod batch;
+pub mod breakpoints;
+pub mod bus;
+pub mod completion;
+pub mod controller;
 pub mod cpu;
+pub mod debugger;
+pub mod disassembler;
+pub mod disk;
+pub mod emulator;
 pub mod gui;
+pub mod isa;
+pub mod journal;
+pub mod log_ring;
 pub mod memory;
+pub mod profiler;
+pub mod program;
+pub mod reference;
+pub mod replay;
+pub mod rng;
+pub mod rom;
+pub mod strictness;
+pub mod testgen;
+#[cfg(feature = "test-util")]
+pub mod testutil;
+pub mod theme;
+pub mod trace;
+pub mod uart;
 
 // Re-export main types for easier access in tests
-pub use assembler::Assembler;
-pub use cpu::CPU;
-pub use memory::Memory;
+pub use assembler::{AsmError, AsmErrorKind, Assembler};
+pub use bus::{Device, IrqRequest, SystemBus, Timer};
+pub use cpu::{
+    AccessKind, CpuSnapshot, CpuState, DecodedInstruction, EaDescription, Exception, MemAccess, CPU,
+};
+pub use disk::Disk;
+pub use emulator::{DataExecution, Emulator, FragmentResult, RunOutcome, StepBack};
+pub use memory::{BusFaultKind, DiffRange, HashAlgorithm, JournalEntry, Memory, MemoryPatch};
+pub use replay::RunRecord;
+pub use rng::Rng;
+pub use strictness::{Strictness, StrictnessProfile};
+pub use uart::{Uart, UartHost};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,252 @@
+// Begrenztes Ausgabe-Log für die Konsole der GUI
+// Lange Läufe mit Trace erzeugen Millionen Zeilen; gehalten werden nur die letzten
+// `capacity` Zeilen, ältere fallen heraus und werden gezählt. Wer alles braucht, schaltet
+// die Dateiausgabe ein: ab dann geht jeder Text zusätzlich durch einen BufWriter auf die
+// Platte, ohne dass der Ring wächst. Der Ring ist intern gesperrt und kann daher von einem
+// Worker-Thread aus beschrieben werden, während die GUI liest.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// So viele Zeilen hält die Konsole der GUI
+pub const LOG_LINES: usize = 5_000;
+
+struct Inner {
+    lines: VecDeque<String>,
+    /// Die letzte Zeile hat noch kein '\n' und wird beim nächsten Text fortgesetzt
+    open: bool,
+    dropped: u64,
+    file: Option<(PathBuf, BufWriter<File>)>,
+    file_error: Option<String>,
+}
+
+pub struct LogRing {
+    inner: Mutex<Inner>,
+    capacity: usize,
+}
+
+impl Default for LogRing {
+    fn default() -> Self {
+        Self::new(LOG_LINES)
+    }
+}
+
+impl LogRing {
+    pub fn new(capacity: usize) -> Self {
+        LogRing {
+            inner: Mutex::new(Inner {
+                lines: VecDeque::new(),
+                open: false,
+                dropped: 0,
+                file: None,
+                file_error: None,
+            }),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Ein Thread, der beim Schreiben abstürzt, soll das Log nicht unbrauchbar machen
+    fn inner(&self) -> MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Hängt Text an wie `String::push_str`; Zeilen enden mit '\n'
+    pub fn push_str(&self, text: &str) {
+        let mut inner = self.inner();
+        let inner = &mut *inner;
+        for piece in text.split_inclusive('\n') {
+            let (content, ends_line) = match piece.strip_suffix('\n') {
+                Some(content) => (content, true),
+                None => (piece, false),
+            };
+            match inner.lines.back_mut() {
+                Some(last) if inner.open => last.push_str(content),
+                _ => inner.lines.push_back(content.to_string()),
+            }
+            inner.open = !ends_line;
+        }
+        while inner.lines.len() > self.capacity {
+            inner.lines.pop_front();
+            inner.dropped += 1;
+        }
+
+        if let Some((path, writer)) = &mut inner.file {
+            if let Err(error) = writer.write_all(text.as_bytes()) {
+                inner.file_error = Some(format!("{}: {}", path.display(), error));
+                inner.file = None;
+            }
+        }
+    }
+
+    /// Leert die Anzeige samt Zähler; eine laufende Dateiausgabe bleibt aktiv
+    pub fn clear(&self) {
+        let mut inner = self.inner();
+        inner.lines.clear();
+        inner.open = false;
+        inner.dropped = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner().lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner().lines.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Seit dem letzten `clear` herausgefallene Zeilen
+    pub fn dropped(&self) -> u64 {
+        self.inner().dropped
+    }
+
+    /// Kopie der Zeilen `rows` (für `ScrollArea::show_rows`)
+    pub fn lines(&self, rows: Range<usize>) -> Vec<String> {
+        let inner = self.inner();
+        let end = rows.end.min(inner.lines.len());
+        let start = rows.start.min(end);
+        inner.lines.range(start..end).cloned().collect()
+    }
+
+    /// Alle gehaltenen Zeilen als ein Text
+    pub fn text(&self) -> String {
+        let inner = self.inner();
+        let mut text = String::new();
+        for line in &inner.lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Schreibt die gehaltenen Zeilen nach `path` und ab jetzt jeden neuen Text dazu
+    pub fn start_file(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let mut inner = self.inner();
+        if inner.dropped > 0 {
+            writeln!(
+                writer,
+                "[{} earlier lines were dropped before saving]",
+                inner.dropped
+            )?;
+        }
+        for (index, line) in inner.lines.iter().enumerate() {
+            writer.write_all(line.as_bytes())?;
+            if index + 1 < inner.lines.len() || !inner.open {
+                writer.write_all(b"\n")?;
+            }
+        }
+        inner.file = Some((path.to_path_buf(), writer));
+        inner.file_error = None;
+        Ok(())
+    }
+
+    /// Beendet die Dateiausgabe und schreibt den Puffer auf die Platte
+    pub fn stop_file(&self) -> io::Result<()> {
+        match self.inner().file.take() {
+            Some((_, mut writer)) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn file_path(&self) -> Option<PathBuf> {
+        self.inner().file.as_ref().map(|(path, _)| path.clone())
+    }
+
+    /// Fehler beim Schreiben in die Datei; die Dateiausgabe ist dann schon beendet
+    pub fn take_file_error(&self) -> Option<String> {
+        self.inner().file_error.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_wraps_and_counts_dropped_lines() {
+        let log = LogRing::new(3);
+        log.push_str("one\ntwo\n");
+        log.push_str("thr");
+        log.push_str("ee\nfour\n");
+        assert_eq!(log.lines(0..10), ["two", "three", "four"]);
+        assert_eq!(log.dropped(), 1);
+
+        for n in 0..10 {
+            log.push_str(&format!("line {}\n", n));
+        }
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.dropped(), 11);
+        assert_eq!(log.lines(1..3), ["line 8", "line 9"]);
+        assert_eq!(log.text(), "line 7\nline 8\nline 9\n");
+
+        log.clear();
+        assert!(log.is_empty());
+        assert_eq!(log.dropped(), 0);
+    }
+
+    #[test]
+    fn test_file_receives_everything() {
+        let path = std::env::temp_dir().join(format!("mc68000-log-{}.txt", std::process::id()));
+        let log = LogRing::new(2);
+        log.push_str("a\nb\nc\n");
+        log.start_file(&path).unwrap();
+        for n in 0..100 {
+            log.push_str(&format!("{}\n", n));
+        }
+        log.stop_file().unwrap();
+        log.push_str("after\n");
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = saved.lines().collect();
+        assert_eq!(lines.len(), 1 + 2 + 100);
+        assert!(lines[0].contains("1 earlier lines"));
+        assert_eq!(lines[1..3], ["b", "c"]);
+        assert_eq!(lines[102], "99");
+        assert_eq!(log.len(), 2);
+    }
+
+    #[test]
+    fn test_concurrent_append() {
+        const THREADS: usize = 4;
+        const PER_THREAD: usize = 1000;
+        let log = Arc::new(LogRing::new(500));
+        let workers: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let log = Arc::clone(&log);
+                std::thread::spawn(move || {
+                    for n in 0..PER_THREAD {
+                        log.push_str(&format!("worker {} step {}\n", thread, n));
+                    }
+                })
+            })
+            .collect();
+        // Die GUI liest, während geschrieben wird
+        while workers.iter().any(|w| !w.is_finished()) {
+            assert!(log.len() <= 500);
+            let _ = log.lines(0..20);
+        }
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(log.len(), 500);
+        assert_eq!(log.dropped(), (THREADS * PER_THREAD - 500) as u64);
+        // Keine Zeile wurde zerrissen oder mit einer anderen verschmolzen
+        assert!(log
+            .lines(0..500)
+            .iter()
+            .all(|line| line.starts_with("worker ") && line.matches("step").count() == 1));
+    }
+}
@@ -1,14 +1,580 @@
-mod assembler;
-mod cpu;
-pub mod gui;
-mod memory;
+use mc68000::address_format::AddressFormatter;
+use mc68000::address_map::MapTable;
+use mc68000::controller::DEFAULT_STEP_CAP;
+use mc68000::debugger::{self, transport, Adapter};
+use mc68000::program::parse_address;
+use mc68000::rom::{self, RomLayout};
+use mc68000::testgen;
+use mc68000::trace::{format_trace, TraceLevel};
+use mc68000::{
+    assembler, batch, cpu, isa, memory, reference, Assembler, Emulator, Exception, Rng, RunRecord,
+    StrictnessProfile, Uart,
+};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::ops::Range;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str =
+    "usage: mc68000 [--trace[=off|pc|full|regs|mem]] [--legacy-simhalt] [--optimize]
+               [--entry=ADDR] [--sp=ADDR] [--hash START:LENGTH]... [--rng-seed=N]
+               [--record FILE] [--replay FILE]
+               [--profile] [--strictness teaching|compatible|fast]
+               [--uart[=BAUD]] [--print-map]
+               [--rom=START:LENGTH]... [--unmap=START:LENGTH]... [program.asm]
+       mc68000 batch <directory>
+       mc68000 build program.asm --rom-size SIZE --rom-base ADDR -o FILE
+               [--fill BYTE] [--with-vectors] [--legacy-simhalt] [--optimize]
+       mc68000 gen-exerciser [-o FILE]
+       mc68000 dap [--port PORT]
+       mc68000 help [MNEMONIC]";
+
+/// Einstellungen für `run_file` aus der Kommandozeile
+#[derive(Default)]
+struct Options {
+    trace: TraceLevel,
+    legacy_simhalt: bool,
+    optimize: bool,
+    entry: Option<u32>,                    // überschreibt END im Quelltext
+    initial_sp: Option<u32>,               // überschreibt STACK im Quelltext
+    hashes: Vec<Range<u32>>,               // nach dem Lauf CRC-32 und FNV-1a ausgeben
+    rng_seed: Option<u32>,                 // None = aus der Uhrzeit, wird dann ausgegeben
+    record: Option<String>,                // Run-Record nach dem Lauf in diese Datei schreiben
+    profile: bool,                         // nach dem Lauf die teuersten Quellzeilen ausgeben
+    strictness: Option<StrictnessProfile>, // --strictness teaching|compatible|fast
+    uart: Option<u32>,                     // Baudrate; stdin/stdout laufen über den UART
+    print_map: bool,                       // nach dem Laden die Speicherkarte ausgeben
+    rom: Vec<Range<u32>>,                  // Schreibzugriffe lösen einen Bus Error aus
+    unmapped: Vec<Range<u32>>,             // jeder Zugriff löst einen Bus Error aus
+}
+
+/// So viele Zeilen zeigt `--profile`
+const PROFILE_LINES: usize = 20;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "batch") {
+        match args.get(1) {
+            Some(directory) if args.len() == 2 => run_batch(directory),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    if args.first().is_some_and(|arg| arg == "build") {
+        run_build(&args[1..]);
+    }
+    if args.first().is_some_and(|arg| arg == "gen-exerciser") {
+        run_gen_exerciser(&args[1..]);
+    }
+    if args.first().is_some_and(|arg| arg == "dap") {
+        run_dap(&args[1..]);
+    }
+    if args.first().is_some_and(|arg| arg == "help") {
+        run_help(&args[1..]);
+    }
+
+    let mut options = Options::default();
+    let mut source_file = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--trace" {
+            options.trace = TraceLevel::Disassembly;
+        } else if let Some(name) = arg.strip_prefix("--trace=") {
+            options.trace = TraceLevel::from_name(name).unwrap_or_else(|| {
+                eprintln!("unknown trace level '{}'\n{}", name, USAGE);
+                process::exit(2);
+            });
+        } else if arg == "--legacy-simhalt" {
+            options.legacy_simhalt = true;
+        } else if arg == "--optimize" {
+            options.optimize = true;
+        } else if let Some(address) = arg.strip_prefix("--entry=") {
+            options.entry = Some(address_argument(address));
+        } else if let Some(address) = arg.strip_prefix("--sp=") {
+            options.initial_sp = Some(address_argument(address));
+        } else if arg == "--hash" {
+            let range = args.next().unwrap_or_default();
+            options.hashes.push(hash_argument(&range));
+        } else if let Some(range) = arg.strip_prefix("--hash=") {
+            options.hashes.push(hash_argument(range));
+        } else if let Some(range) = arg.strip_prefix("--rom=") {
+            options.rom.push(hash_argument(range));
+        } else if let Some(range) = arg.strip_prefix("--unmap=") {
+            options.unmapped.push(hash_argument(range));
+        } else if let Some(seed) = arg.strip_prefix("--rng-seed=") {
+            options.rng_seed = Some(seed_argument(seed));
+        } else if arg == "--rng-seed" {
+            options.rng_seed = Some(seed_argument(&args.next().unwrap_or_default()));
+        } else if let Some(path) = option_value(&arg, "--record", &mut args) {
+            options.record = Some(path);
+        } else if let Some(path) = option_value(&arg, "--replay", &mut args) {
+            // Spätere Optionen überschreiben die Werte aus dem Record
+            let record = replay_argument(&path);
+            options.rng_seed = record.rng_seed.or(options.rng_seed);
+            options.entry = record.entry.or(options.entry);
+            options.initial_sp = record.initial_sp.or(options.initial_sp);
+            options.strictness = record.strictness.or(options.strictness);
+        } else if let Some(name) = option_value(&arg, "--strictness", &mut args) {
+            options.strictness = Some(StrictnessProfile::from_name(&name).unwrap_or_else(|| {
+                eprintln!("unknown strictness profile '{}'\n{}", name, USAGE);
+                process::exit(2);
+            }));
+        } else if arg == "--profile" {
+            options.profile = true;
+        } else if arg == "--print-map" {
+            options.print_map = true;
+        } else if arg == "--uart" {
+            options.uart = Some(Uart::DEFAULT_BAUD);
+        } else if let Some(baud) = arg.strip_prefix("--uart=") {
+            options.uart = Some(baud_argument(baud));
+        } else if arg.starts_with('-') {
+            eprintln!("unknown option '{}'\n{}", arg, USAGE);
+            process::exit(2);
+        } else {
+            source_file = Some(arg);
+        }
+    }
+
+    match source_file {
+        Some(path) => run_file(&path, &options),
+        None => run_demo(options.trace),
+    }
+}
+
+fn address_argument(text: &str) -> u32 {
+    parse_address(text).unwrap_or_else(|| {
+        eprintln!("invalid address '{}'\n{}", text, USAGE);
+        process::exit(2);
+    })
+}
+
+fn seed_argument(text: &str) -> u32 {
+    let seed = match text.strip_prefix("0x").or_else(|| text.strip_prefix('$')) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    };
+    seed.unwrap_or_else(|| {
+        eprintln!("invalid seed '{}'\n{}", text, USAGE);
+        process::exit(2);
+    })
+}
+
+/// Füllbyte für `build --fill`: 0..255, dezimal oder hex mit $ bzw. 0x
+fn fill_argument(text: &str) -> u8 {
+    let fill = match text.strip_prefix("0x").or_else(|| text.strip_prefix('$')) {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    };
+    fill.unwrap_or_else(|| {
+        eprintln!("invalid fill byte '{}'\n{}", text, USAGE);
+        process::exit(2);
+    })
+}
+
+/// Liest einen Run-Record, den `--record` geschrieben hat
+fn replay_argument(path: &str) -> RunRecord {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|error| {
+        eprintln!("{}: {}", path, error);
+        process::exit(2);
+    });
+    RunRecord::parse(&text).unwrap_or_else(|error| {
+        eprintln!("{}: {}", path, error);
+        process::exit(2);
+    })
+}
+
+fn baud_argument(text: &str) -> u32 {
+    match text.parse() {
+        Ok(baud) if baud > 0 => baud,
+        _ => {
+            eprintln!("invalid baud rate '{}'\n{}", text, USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+/// `START:LENGTH`, z.B. `0x800:0x1000` für 4 KB ab $800 (auch für --rom und --unmap)
+fn hash_argument(text: &str) -> Range<u32> {
+    let range = text.split_once(':').and_then(|(start, length)| {
+        let start = parse_address(start)?;
+        let length = parse_address(length)?;
+        // Bereiche enden spätestens am Ende des 24-Bit-Adressraums
+        let end = start
+            .checked_add(length)
+            .filter(|&end| end <= 0x0100_0000)?;
+        Some(start..end)
+    });
+    range.unwrap_or_else(|| {
+        eprintln!("invalid range '{}', expected START:LENGTH\n{}", text, USAGE);
+        process::exit(2);
+    })
+}
+
+/// Alle Programme eines Verzeichnisses gegen ihre .expect-Dateien prüfen
+fn run_batch(directory: &str) -> ! {
+    // stdout gehört der Zusammenfassung; die Worker-Threads würden sonst jede Instruktion melden
+    cpu::set_console_echo(false);
+    let report = batch::run_directory(Path::new(directory)).unwrap_or_else(|error| {
+        eprintln!("{}: {}", directory, error);
+        process::exit(2);
+    });
+    print!("{}", report.summary());
+    process::exit(if report.failed() == 0 { 0 } else { 1 });
+}
+
+/// Wert einer Option, als `--name=WERT` oder `--name WERT`
+fn option_value(arg: &str, name: &str, args: &mut impl Iterator<Item = String>) -> Option<String> {
+    if arg == name {
+        Some(args.next().unwrap_or_else(|| {
+            eprintln!("missing value for {}\n{}", name, USAGE);
+            process::exit(2);
+        }))
+    } else {
+        arg.strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='))
+            .map(str::to_string)
+    }
+}
+
+/// Assembliert eine Datei und schreibt sie als flaches ROM-Abbild
+fn run_build(args: &[String]) -> ! {
+    let mut source_file = None;
+    let mut output = None;
+    let mut size = None;
+    let mut base = None;
+    let mut fill = 0xFF;
+    let mut with_vectors = false;
+    let mut assembler = Assembler::new();
+
+    let mut args = args.iter().cloned();
+    while let Some(arg) = args.next() {
+        if let Some(text) = option_value(&arg, "--rom-size", &mut args) {
+            size = Some(rom::parse_size(&text).unwrap_or_else(|| {
+                eprintln!("invalid ROM size '{}'\n{}", text, USAGE);
+                process::exit(2);
+            }));
+        } else if let Some(text) = option_value(&arg, "--rom-base", &mut args) {
+            base = Some(address_argument(&text));
+        } else if let Some(text) = option_value(&arg, "--fill", &mut args) {
+            fill = fill_argument(&text);
+        } else if let Some(path) = option_value(&arg, "-o", &mut args) {
+            output = Some(path);
+        } else if arg == "--with-vectors" {
+            with_vectors = true;
+        } else if arg == "--legacy-simhalt" {
+            assembler.set_legacy_simhalt(true);
+        } else if arg == "--optimize" {
+            assembler.set_optimize(true);
+        } else if arg.starts_with('-') {
+            eprintln!("unknown option '{}'\n{}", arg, USAGE);
+            process::exit(2);
+        } else {
+            source_file = Some(arg);
+        }
+    }
+    let (Some(path), Some(output), Some(size), Some(base)) = (source_file, output, size, base)
+    else {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    };
+
+    let source = std::fs::read_to_string(&path).unwrap_or_else(|error| {
+        eprintln!("{}: {}", path, error);
+        process::exit(1);
+    });
+    let program = assembler.assemble_source(&source).unwrap_or_else(|errors| {
+        for error in errors {
+            eprintln!("{}: {}", path, error);
+        }
+        process::exit(1);
+    });
+    for warning in assembler.warnings() {
+        eprintln!("{}: warning: {}", path, warning);
+    }
+
+    let layout = RomLayout {
+        fill,
+        with_vectors,
+        ..RomLayout::new(base, size)
+    };
+    let image = rom::build_rom(&program, &layout).unwrap_or_else(|error| {
+        eprintln!("{}: {}", path, error);
+        process::exit(1);
+    });
+    if let Err(error) = std::fs::write(&output, &image) {
+        eprintln!("{}: {}", output, error);
+        process::exit(1);
+    }
+    println!(
+        "{}: {} bytes, ${:06X}-${:06X}",
+        output,
+        image.len(),
+        base,
+        base as u64 + size as u64 - 1
+    );
+    process::exit(0);
+}
+
+/// Referenzkarte einer Instruktion; ohne Argument die Liste aller Mnemonics
+fn run_help(args: &[String]) -> ! {
+    match args {
+        [] => {
+            let mnemonics: Vec<&str> = isa::INSTRUCTIONS.iter().map(|s| s.mnemonic).collect();
+            println!("{}", mnemonics.join(" "));
+            process::exit(0);
+        }
+        [mnemonic] => match reference::card_for(mnemonic) {
+            Some(card) => {
+                println!("{}", card);
+                process::exit(0);
+            }
+            None => {
+                eprintln!("unknown instruction '{}'", mnemonic);
+                process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+/// Schreibt das Selbsttest-Programm auf stdout oder in eine Datei
+fn run_gen_exerciser(args: &[String]) -> ! {
+    let mut output = None;
+    let mut args = args.iter().cloned();
+    while let Some(arg) = args.next() {
+        if let Some(path) = option_value(&arg, "-o", &mut args) {
+            output = Some(path);
+        } else {
+            eprintln!("unknown option '{}'\n{}", arg, USAGE);
+            process::exit(2);
+        }
+    }
+
+    let exerciser = testgen::exerciser();
+    match output {
+        Some(path) => {
+            if let Err(error) = std::fs::write(&path, &exerciser.source) {
+                eprintln!("{}: {}", path, error);
+                process::exit(1);
+            }
+            println!(
+                "{}: {} cases, {} instructions",
+                path,
+                exerciser.cases,
+                exerciser.covered.len()
+            );
+        }
+        None => print!("{}", exerciser.source),
+    }
+    process::exit(0);
+}
+
+/// Debugger-Protokoll über stdio oder, mit `--port`, für eine TCP-Verbindung auf localhost
+fn run_dap(args: &[String]) -> ! {
+    let mut port = None;
+    let mut args = args.iter().cloned();
+    while let Some(arg) = args.next() {
+        if let Some(value) = option_value(&arg, "--port", &mut args) {
+            port = Some(value.parse::<u16>().unwrap_or_else(|_| {
+                eprintln!("invalid port '{}'\n{}", value, USAGE);
+                process::exit(2);
+            }));
+        } else {
+            eprintln!("unknown option '{}'\n{}", arg, USAGE);
+            process::exit(2);
+        }
+    }
+
+    // stdout gehört dem Protokoll; Meldungen gehen als Output-Events an den Client
+    cpu::set_console_echo(false);
+    let mut adapter = Adapter::default();
+    let result = match port {
+        None => debugger::serve(&mut adapter, &mut transport::stdio()),
+        Some(port) => TcpListener::bind(("127.0.0.1", port)).and_then(|listener| {
+            eprintln!("dap: listening on {}", listener.local_addr()?);
+            let (stream, peer) = listener.accept()?;
+            eprintln!("dap: connection from {}", peer);
+            debugger::serve(&mut adapter, &mut transport::tcp(stream)?)
+        }),
+    };
+    if let Err(error) = result {
+        eprintln!("dap: {}", error);
+        process::exit(1);
+    }
+    process::exit(0);
+}
+
+/// Assembliert eine Datei und führt sie bis SIMHALT (oder bis zum Schrittlimit) aus
+fn run_file(path: &str, options: &Options) {
+    let source = std::fs::read_to_string(path).unwrap_or_else(|error| {
+        eprintln!("{}: {}", path, error);
+        process::exit(1);
+    });
+
+    // Mit UART gehört stdout dem Programm: kein Echo (auch keine Register am Ende),
+    // Trace, Speicherkarte, Hashes und Profil gehen nach stderr
+    let guest_stdout = options.uart.is_some();
+    if guest_stdout {
+        cpu::set_console_echo(false);
+    }
+
+    let mut emulator = Emulator::new();
+    emulator.set_legacy_simhalt(options.legacy_simhalt);
+    emulator.set_optimize(options.optimize);
+    emulator.set_entry_override(options.entry);
+    emulator.set_initial_sp_override(options.initial_sp);
+    if let Some(strictness) = options.strictness {
+        emulator.set_profile(strictness);
+    }
+    let seed = options.rng_seed.unwrap_or_else(Rng::seed_from_clock);
+    emulator.attach_rng(seed);
+    let uart = options.uart.map(|baud| {
+        let host = emulator.attach_uart(baud);
+        // stdin im Hintergrund lesen; die Bytes kommen im Takt der Baudrate beim Programm an
+        let sender = host.sender();
+        std::thread::spawn(move || {
+            for byte in std::io::stdin().lock().bytes() {
+                let Ok(byte) = byte else { break };
+                if sender.send(byte).is_err() {
+                    break;
+                }
+            }
+        });
+        host
+    });
+    emulator
+        .cpu_mut()
+        .set_access_trace(options.trace.needs_accesses());
+    if let Err(errors) = emulator.load_source(&source) {
+        for error in errors {
+            eprintln!("{}: {}", path, error);
+        }
+        process::exit(1);
+    }
+    for range in &options.rom {
+        emulator.memory_mut().map_rom(range.clone());
+    }
+    for range in &options.unmapped {
+        emulator.memory_mut().unmap(range.clone());
+    }
+    let first_random = emulator.memory().read_long(Rng::DEFAULT_BASE);
+    for warning in emulator.assembler().warnings() {
+        eprintln!("{}: warning: {}", path, warning);
+    }
+    for note in emulator.assembler().notes() {
+        eprintln!("{}: note: {}", path, note);
+    }
+    if options.print_map {
+        report(
+            guest_stdout,
+            format_args!("{}", MapTable(&emulator.memory_map())),
+        );
+    }
+
+    for _ in 0..DEFAULT_STEP_CAP {
+        if let Some(hit) = emulator.pending_data_execution() {
+            eprintln!("{}: warning: {}", path, hit);
+            break;
+        }
+        let pc = emulator.cpu().get_pc();
+        if emulator
+            .program()
+            .is_some_and(|program| !program.source_map.contains_key(&pc))
+        {
+            break;
+        }
+
+        let result = emulator.step();
+        if let Some(line) = format_trace(&result, options.trace, emulator.address_formatter()) {
+            report(guest_stdout, format_args!("{}\n", line));
+        }
+        if let Some(host) = &uart {
+            let output = host.receive();
+            if !output.is_empty() {
+                let mut stdout = std::io::stdout().lock();
+                let _ = stdout.write_all(&output).and_then(|_| stdout.flush());
+            }
+        }
+        if emulator.cpu().is_halted() {
+            break;
+        }
+    }
+
+    match emulator.cpu().exception() {
+        Some(Exception::BusError(fault)) => {
+            eprintln!("{}: {}", path, fault.describe(emulator.program()))
+        }
+        Some(exception) => eprintln!("{}: {}", path, exception),
+        None => {}
+    }
+    // Zufallszahlen benutzt: Seed ausgeben, damit sich der Lauf wiederholen lässt
+    if options.rng_seed.is_none() && emulator.memory().read_long(Rng::DEFAULT_BASE) != first_random
+    {
+        eprintln!(
+            "{}: rng seed {} (repeat with --rng-seed={})",
+            path, seed, seed
+        );
+    }
+    if let Some(record) = &options.record {
+        if let Err(error) = std::fs::write(record, RunRecord::capture(&emulator).to_string()) {
+            eprintln!("{}: {}", record, error);
+        }
+    }
+    emulator.cpu().print_registers();
+    for range in &options.hashes {
+        let memory = emulator.memory();
+        report(
+            guest_stdout,
+            format_args!(
+                "${:06X}:${:X}  CRC32 ${:08X}  FNV1A64 ${:016X}\n",
+                range.start,
+                range.len(),
+                memory.crc32(range.clone()),
+                memory.fnv1a64(range.clone())
+            ),
+        );
+    }
+    if options.profile {
+        report(
+            guest_stdout,
+            format_args!(
+                "{}",
+                emulator.profile().report(emulator.program(), PROFILE_LINES)
+            ),
+        );
+    }
+    let failures = emulator.assertion_failures();
+    if !failures.is_empty() {
+        for failure in failures {
+            eprintln!("{}: {}", path, failure);
+        }
+        process::exit(1);
+    }
+}
+
+/// Ausgabe des Emulators selbst; nach stderr, wenn stdout dem Programm gehört
+fn report(to_stderr: bool, text: std::fmt::Arguments) {
+    if to_stderr {
+        eprint!("{}", text);
+    } else {
+        print!("{}", text);
+    }
+}
+
+fn run_demo(trace: TraceLevel) {
     println!("Starting MC68000 Emulator...");
     let mut cpu = cpu::CPU::new();
     let mut memory = memory::Memory::new();
 
     cpu.reset();
+    cpu.set_access_trace(trace.needs_accesses());
     println!("CPU and Memory initialized.");
 
     // Assembly-Code definieren
@@ -56,7 +622,10 @@ fn main() {
     // Schrittweise Ausführung (5 Instruktionen)
     for step in 1..=6 {
         println!("--- Schritt {} ---", step);
-        cpu.execute_instruction(&mut memory);
+        let result = cpu.execute_instruction(&mut memory);
+        if let Some(line) = format_trace(&result, trace, &AddressFormatter::default()) {
+            println!("{}", line);
+        }
         cpu.print_registers();
         println!();
 
@@ -1,8 +1,5 @@
 // MC68000 Emulator - GUI Version
-mod assembler;
-mod cpu;
-mod gui;
-mod memory;
+use mc68000::gui;
 
 fn main() -> eframe::Result {
     env_logger::init(); // Log to stderr (if you want to see it, run with `RUST_LOG=debug`).
@@ -18,6 +15,6 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "MC68000 Emulator",
         options,
-        Box::new(|_cc| Ok(Box::new(gui::EmulatorApp::default()))),
+        Box::new(|cc| Ok(Box::new(gui::EmulatorApp::new(cc)))),
     )
 }
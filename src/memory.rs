@@ -2,8 +2,157 @@
 /*
    24 Bit Adressraum = 16 MB
 */
+
+use std::fmt;
+use std::ops::Range;
+
+/// Kopie eines Speicherbereichs, z.B. für Anzeigen in einem anderen Thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryPatch {
+    pub address: u32,
+    pub bytes: Vec<u8>,
+}
+
+/// Warum ein Zugriff der CPU einen Bus Error auslöst
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusFaultKind {
+    /// Kein Speicher an der Adresse (`Memory::unmap`)
+    Unmapped,
+    /// Schreibzugriff auf ROM (`Memory::map_rom`)
+    ReadOnly,
+    /// Wort- oder Langwortzugriff auf eine ungerade Adresse; meldet die CPU selbst, wenn
+    /// `CPU::set_alignment_check` an ist (Address Error statt Bus Error)
+    OddAddress,
+}
+
+impl BusFaultKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            BusFaultKind::Unmapped => "unmapped",
+            BusFaultKind::ReadOnly => "ROM",
+            BusFaultKind::OddAddress => "odd address",
+        }
+    }
+}
+
+/// Prüfsummenverfahren für `Memory::hash_range`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// CRC-32 (IEEE 802.3, wie zlib/PNG)
+    Crc32,
+    /// FNV-1a mit 64 Bit
+    Fnv1a64,
+}
+
+impl HashAlgorithm {
+    pub fn from_name(name: &str) -> Option<HashAlgorithm> {
+        match name.to_uppercase().as_str() {
+            "CRC32" => Some(HashAlgorithm::Crc32),
+            "FNV1A64" | "FNV" => Some(HashAlgorithm::Fnv1a64),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Crc32 => "CRC32",
+            HashAlgorithm::Fnv1a64 => "FNV1A64",
+        }
+    }
+}
+
+/// Tabelle für CRC-32 (reflektiertes Polynom $EDB88320), zur Compile-Zeit berechnet
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Der 68000 legt nur 24 Adressbits an den Bus; höhere Bits werden ignoriert
+const ADDRESS_MASK: u32 = 0x00FF_FFFF;
+
+/// Größe der Seiten, in denen der Speicher abgelegt wird; nie beschriebene Seiten belegen
+/// keinen Platz und lesen sich als 0
+pub const PAGE_SIZE: u32 = 0x1000;
+const PAGE_COUNT: usize = (ADDRESS_MASK as usize + 1) / PAGE_SIZE as usize;
+
+type Page = Box<[u8; PAGE_SIZE as usize]>;
+
+/// Ein Dirty-Bit pro Seite, 64 Seiten pro Wort
+const DIRTY_WORDS: usize = PAGE_COUNT / 64;
+
+/// Big-Endian Hauptspeicher über den vollen 24-Bit-Adressraum, dünn besetzt in Seiten
+/// zu `PAGE_SIZE` Bytes
+///
+/// ```
+/// use mc68000::{Memory, CPU};
+///
+/// let mut memory = Memory::new();
+/// memory.write_long(0x0800, 0x1234_5678);
+/// assert_eq!(memory.read_word(0x0800), 0x1234);
+/// assert_eq!(memory.read_byte(0x0803), 0x78);
+///
+/// // Programm direkt als Wörter ablegen und ausführen
+/// memory.write_word(0x1000, 0x70FF); // MOVEQ #-1, D0
+/// memory.write_word(0x1002, 0xA000); // SIMHALT
+/// let mut cpu = CPU::new();
+/// cpu.set_pc(0x1000);
+/// while !cpu.is_halted() {
+///     cpu.execute_instruction(&mut memory);
+/// }
+/// assert_eq!(cpu.get_data_register(0), 0xFFFF_FFFF);
+/// ```
+#[derive(Clone)]
 pub struct Memory {
-    data: Vec<u8>,
+    pages: Vec<Option<Page>>, // dünn besetzt, Seite erst beim ersten Schreiben ungleich 0
+    writes: u64,              // Anzahl Schreibzugriffe (für Leerlauf-Erkennung)
+    dirty: Vec<u64>,          // ein Bit pro Seite, beschrieben seit take_dirty_ranges
+    io: Vec<Range<u32>>,      // Register-Fenster von Geräten
+    unmapped: Vec<Range<u32>>, // Zugriffe der CPU lösen einen Bus Error aus
+    rom: Vec<Range<u32>>,     // Schreibzugriffe der CPU lösen einen Bus Error aus
+    journal: Option<Vec<JournalEntry>>, // alte Werte, solange ein Journal offen ist
+}
+
+/// Zusammenhängender Bereich, in dem sich zwei Speicher unterscheiden (`Memory::diff`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffRange {
+    pub start: u32,
+    /// exklusiv
+    pub end: u32,
+}
+
+impl DiffRange {
+    pub fn range(&self) -> Range<u32> {
+        self.start..self.end
+    }
+}
+
+impl fmt::Display for DiffRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${:06X}..${:06X}", self.start, self.end)
+    }
+}
+
+/// Ein Schreibzugriff mit dem überschriebenen Wert (Wort oder Byte, Big-Endian)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub address: u32,
+    pub old: u16,
+    pub word: bool, // false = Byte
 }
 
 impl Default for Memory {
@@ -15,44 +164,570 @@ impl Default for Memory {
 impl Memory {
     pub fn new() -> Self {
         Memory {
-            data: vec![0; 16 * 1024 * 1024], // 16 MB Adressraum
+            pages: (0..PAGE_COUNT).map(|_| None).collect(), // 16 MB Adressraum
+            writes: 0,
+            dirty: vec![0; DIRTY_WORDS],
+            io: Vec::new(),
+            unmapped: Vec::new(),
+            rom: Vec::new(),
+            journal: None,
         }
     }
 
+    fn index(address: u32) -> usize {
+        (address & ADDRESS_MASK) as usize
+    }
+
+    fn byte(&self, address: u32) -> u8 {
+        let index = Self::index(address);
+        self.pages[index / PAGE_SIZE as usize]
+            .as_ref()
+            .map_or(0, |page| page[index % PAGE_SIZE as usize])
+    }
+
+    /// Legt die Seite erst an, wenn dort etwas anderes als 0 stehen soll
+    fn set_byte(&mut self, address: u32, value: u8) {
+        let index = Self::index(address);
+        let page = &mut self.pages[index / PAGE_SIZE as usize];
+        if page.is_none() && value == 0 {
+            return;
+        }
+        let page = page.get_or_insert_with(|| Box::new([0; PAGE_SIZE as usize]));
+        page[index % PAGE_SIZE as usize] = value;
+    }
+
     #[allow(dead_code)]
     pub fn read_byte(&self, address: u32) -> u8 {
-        self.data[address as usize]
+        self.byte(address)
     }
 
     #[allow(dead_code)]
     pub fn write_byte(&mut self, address: u32, value: u8) {
-        self.data[address as usize] = value;
+        let old = self.byte(address) as u16;
+        if let Some(journal) = &mut self.journal {
+            journal.push(JournalEntry {
+                address,
+                old,
+                word: false,
+            });
+        }
+        self.set_byte(address, value);
+        self.writes += 1;
+        self.mark_dirty(address & ADDRESS_MASK, 1);
     }
 
     // MC68000 ist Big-Endian
     pub fn read_word(&self, address: u32) -> u16 {
-        let high_byte = self.data[address as usize] as u16;
-        let low_byte = self.data[(address + 1) as usize] as u16;
+        let high_byte = self.byte(address) as u16;
+        let low_byte = self.byte(address.wrapping_add(1)) as u16;
         (high_byte << 8) | low_byte
     }
 
     pub fn write_word(&mut self, address: u32, value: u16) {
-        self.data[address as usize] = (value >> 8) as u8; // High Byte
-        self.data[(address + 1) as usize] = (value & 0xFF) as u8; // Low Byte
+        if self.journal.is_some() {
+            let old = self.read_word(address);
+            if let Some(journal) = &mut self.journal {
+                journal.push(JournalEntry {
+                    address,
+                    old,
+                    word: true,
+                });
+            }
+        }
+        self.set_byte(address, (value >> 8) as u8); // High Byte
+        self.set_byte(address.wrapping_add(1), (value & 0xFF) as u8); // Low Byte
+        self.writes += 1;
+        self.mark_dirty(address & ADDRESS_MASK, 2);
     }
 
     pub fn read_long(&self, address: u32) -> u32 {
         let high_word = self.read_word(address) as u32;
-        let low_word = self.read_word(address + 2) as u32;
+        let low_word = self.read_word(address.wrapping_add(2)) as u32;
         (high_word << 16) | low_word
     }
 
     pub fn write_long(&mut self, address: u32, value: u32) {
         self.write_word(address, (value >> 16) as u16); // High Word
-        self.write_word(address + 2, (value & 0xFFFF) as u16); // Low Word
+        self.write_word(address.wrapping_add(2), (value & 0xFFFF) as u16); // Low Word
+    }
+
+    /// Ab jetzt zu jedem Schreibzugriff den alten Wert festhalten (bis `take_journal`)
+    pub fn start_journal(&mut self) {
+        self.journal = Some(Vec::new());
+    }
+
+    /// Seit `start_journal` überschriebene Werte in Schreibreihenfolge; beendet das Journal
+    pub fn take_journal(&mut self) -> Vec<JournalEntry> {
+        self.journal.take().unwrap_or_default()
+    }
+
+    /// Macht die Schreibzugriffe eines Journals rückgängig (neueste zuerst)
+    pub fn undo(&mut self, entries: &[JournalEntry]) {
+        for entry in entries.iter().rev() {
+            if entry.word {
+                self.write_word(entry.address, entry.old);
+            } else {
+                self.write_byte(entry.address, entry.old as u8);
+            }
+        }
+    }
+
+    /// Zähler aller bisherigen Schreibzugriffe
+    pub fn write_count(&self) -> u64 {
+        self.writes
+    }
+
+    /// Seit dem letzten Aufruf beschriebene Adressbereiche (für die Anzeige), auf ganze
+    /// Seiten gerundet; benachbarte Seiten ergeben einen Bereich
+    pub fn take_dirty_ranges(&mut self) -> Vec<Range<u32>> {
+        let mut ranges: Vec<Range<u32>> = Vec::new();
+        for (index, word) in self.dirty.iter_mut().enumerate() {
+            let mut bits = std::mem::take(word);
+            while bits != 0 {
+                let page = (index * 64) as u32 + bits.trailing_zeros();
+                bits &= bits - 1;
+                let start = page * PAGE_SIZE;
+                match ranges.last_mut() {
+                    Some(last) if last.end == start => last.end += PAGE_SIZE,
+                    _ => ranges.push(start..start + PAGE_SIZE),
+                }
+            }
+        }
+        ranges
+    }
+
+    /// Kopiert `range` (auf den Adressraum begrenzt)
+    pub fn copy_range(&self, range: Range<u32>) -> MemoryPatch {
+        let range = Self::clamp(range);
+        // Seitenweise: angelegte Seiten als Slice, nie beschriebene als Nullen
+        let mut bytes = Vec::with_capacity(range.len());
+        let mut address = range.start;
+        while address < range.end {
+            let end = range.end.min((address / PAGE_SIZE + 1) * PAGE_SIZE);
+            let offset = (address % PAGE_SIZE) as usize;
+            let len = (end - address) as usize;
+            match &self.pages[(address / PAGE_SIZE) as usize] {
+                Some(page) => bytes.extend_from_slice(&page[offset..offset + len]),
+                None => bytes.resize(bytes.len() + len, 0),
+            }
+            address = end;
+        }
+        MemoryPatch {
+            address: range.start,
+            bytes,
+        }
+    }
+
+    /// Übernimmt eine Kopie in einen Spiegel; zählt nicht als Schreibzugriff
+    pub fn apply_patch(&mut self, patch: &MemoryPatch) {
+        for (address, byte) in (patch.address..).zip(&patch.bytes) {
+            self.set_byte(address, *byte);
+        }
+    }
+
+    /// Neuer Speicher nur mit den Bytes aus `range` (alles andere 0) und denselben
+    /// Geräte-, ROM- und ausgeblendeten Bereichen; z.B. um in Tests nur einen Ausschnitt
+    /// zu vergleichen
+    pub fn clone_region(&self, range: Range<u32>) -> Memory {
+        let mut region = Memory {
+            io: self.io.clone(),
+            unmapped: self.unmapped.clone(),
+            rom: self.rom.clone(),
+            ..Memory::new()
+        };
+        region.apply_patch(&self.copy_range(range));
+        region
+    }
+
+    /// Anzahl der angelegten Seiten (`PAGE_SIZE`); ein frischer Speicher hat keine
+    pub fn allocated_pages(&self) -> usize {
+        self.pages.iter().filter(|page| page.is_some()).count()
+    }
+
+    /// Bereiche mit unterschiedlichem Inhalt, nach Adresse sortiert und zusammengefasst.
+    /// Nie angelegte Seiten zählen als 0; gleiche Seiten werden als Ganzes verglichen,
+    /// nur abweichende byteweise.
+    ///
+    /// ```
+    /// use mc68000::memory::{DiffRange, Memory};
+    ///
+    /// let before = Memory::new();
+    /// let mut after = before.clone();
+    /// after.write_long(0x0FFE, 0x1234_5678); // über eine Seitengrenze
+    /// assert_eq!(before.diff(&after), vec![DiffRange { start: 0x0FFE, end: 0x1002 }]);
+    /// ```
+    pub fn diff(&self, other: &Memory) -> Vec<DiffRange> {
+        const ZERO: [u8; PAGE_SIZE as usize] = [0; PAGE_SIZE as usize];
+        let mut ranges: Vec<DiffRange> = Vec::new();
+        for (number, (left, right)) in self.pages.iter().zip(&other.pages).enumerate() {
+            let (left, right) = match (left, right) {
+                (None, None) => continue,
+                (Some(left), Some(right)) => (&**left, &**right),
+                (Some(left), None) => (&**left, &ZERO),
+                (None, Some(right)) => (&ZERO, &**right),
+            };
+            if left == right {
+                continue;
+            }
+            let base = number as u32 * PAGE_SIZE;
+            for (address, (a, b)) in (base..).zip(left.iter().zip(right)) {
+                if a == b {
+                    continue;
+                }
+                match ranges.last_mut() {
+                    Some(last) if last.end == address => last.end += 1,
+                    _ => ranges.push(DiffRange {
+                        start: address,
+                        end: address + 1,
+                    }),
+                }
+            }
+        }
+        ranges
+    }
+
+    /// Auf den Adressraum begrenzt
+    fn clamp(range: Range<u32>) -> Range<u32> {
+        let end = range.end.min(ADDRESS_MASK + 1);
+        range.start.min(end)..end
+    }
+
+    /// Bytes von `range` in Adressreihenfolge (wie `read_byte`, also mit 24-Bit-Umlauf)
+    fn range_bytes(&self, range: Range<u32>) -> impl Iterator<Item = u8> + '_ {
+        range.map(|address| self.byte(address))
+    }
+
+    /// CRC-32 über `range`
+    ///
+    /// Die Werte sind über Versionen stabil: jedes Byte des Bereichs zählt, nie
+    /// beschriebene Bytes als 0, unabhängig davon, wie der Speicher intern abgelegt ist.
+    ///
+    /// ```
+    /// use mc68000::Memory;
+    ///
+    /// let mut memory = Memory::new();
+    /// for (i, byte) in b"123456789".iter().enumerate() {
+    ///     memory.write_byte(0x800 + i as u32, *byte);
+    /// }
+    /// assert_eq!(memory.crc32(0x800..0x809), 0xCBF4_3926);
+    /// ```
+    pub fn crc32(&self, range: Range<u32>) -> u32 {
+        !self.range_bytes(range).fold(!0u32, |crc, byte| {
+            CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+        })
+    }
+
+    /// FNV-1a (64 Bit) über `range`; stabil wie `crc32`
+    pub fn fnv1a64(&self, range: Range<u32>) -> u64 {
+        self.range_bytes(range)
+            .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+            })
     }
 
+    /// Prüfsumme nach `algorithm`; CRC-32 belegt nur die unteren 32 Bit
+    pub fn hash_range(&self, range: Range<u32>, algorithm: HashAlgorithm) -> u64 {
+        match algorithm {
+            HashAlgorithm::Crc32 => self.crc32(range) as u64,
+            HashAlgorithm::Fnv1a64 => self.fnv1a64(range),
+        }
+    }
+
+    // Seite des ersten und des letzten Bytes markieren (ein Wort kann über die Grenze reichen)
+    fn mark_dirty(&mut self, address: u32, len: u32) {
+        for byte in [address, address.wrapping_add(len - 1) & ADDRESS_MASK] {
+            let page = (byte / PAGE_SIZE) as usize;
+            self.dirty[page / 64] |= 1 << (page % 64);
+        }
+    }
+
+    /// Markiert `range` als Geräteregister; Zugriffe der CPU darauf werden dem Bus gemeldet
+    pub fn map_io(&mut self, range: Range<u32>) {
+        self.io.push(range);
+    }
+
+    pub fn is_io(&self, address: u32) -> bool {
+        let address = address & ADDRESS_MASK;
+        self.io.iter().any(|range| range.contains(&address))
+    }
+
+    /// Nimmt `range` aus dem Adressraum: jeder Datenzugriff der CPU dorthin ist ein Bus Error.
+    /// Laden und Anzeigen (`read_*`/`write_*` direkt) sind davon nicht betroffen.
+    pub fn unmap(&mut self, range: Range<u32>) {
+        self.unmapped.push(range);
+    }
+
+    /// Markiert `range` als ROM: Schreibzugriffe der CPU sind ein Bus Error, das Programm
+    /// selbst wird trotzdem hineingeladen
+    pub fn map_rom(&mut self, range: Range<u32>) {
+        self.rom.push(range);
+    }
+
+    /// Bus Error für einen Zugriff der CPU auf `len` Bytes ab `address`? Unmapped geht vor ROM.
+    pub fn fault(&self, address: u32, len: u32, write: bool) -> Option<BusFaultKind> {
+        let address = address & ADDRESS_MASK;
+        let hits = |ranges: &[Range<u32>]| {
+            ranges
+                .iter()
+                .any(|range| range.start < address + len && address < range.end)
+        };
+        if hits(&self.unmapped) {
+            Some(BusFaultKind::Unmapped)
+        } else if write && hits(&self.rom) {
+            Some(BusFaultKind::ReadOnly)
+        } else {
+            None
+        }
+    }
+
+    /// Löscht den Inhalt; Register-Fenster, ROM und ausgeblendete Bereiche bleiben.
+    /// Dirty werden nur die Seiten, die angelegt waren; alle anderen waren schon 0.
     pub fn clear(&mut self) {
-        self.data.fill(0);
+        for (index, page) in self.pages.iter_mut().enumerate() {
+            if page.take().is_some() {
+                self.dirty[index / 64] |= 1 << (index % 64);
+            }
+        }
+    }
+}
+
+/// Gleich bei gleichem Inhalt und gleichen Geräte-, ROM- und ausgeblendeten Bereichen;
+/// Schreibzähler, Dirty Ranges und Journal zählen nicht
+impl PartialEq for Memory {
+    fn eq(&self, other: &Memory) -> bool {
+        self.io == other.io
+            && self.unmapped == other.unmapped
+            && self.rom == other.rom
+            && self.diff(other).is_empty()
+    }
+}
+
+impl Eq for Memory {}
+
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Memory")
+            .field("allocated_pages", &self.allocated_pages())
+            .field("writes", &self.writes)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dirty_ranges_merge_sequential_writes() {
+        let mut memory = Memory::new();
+        memory.write_long(0x1000, 0x1234_5678);
+        memory.write_word(0x1004, 0xABCD);
+        memory.write_byte(0x5001, 0x42);
+        assert_eq!(
+            memory.take_dirty_ranges(),
+            vec![0x1000..0x2000, 0x5000..0x6000]
+        );
+        assert!(memory.take_dirty_ranges().is_empty());
+
+        // Ein Wort über die Seitengrenze markiert beide Seiten
+        memory.write_word(0x2FFF, 0xABCD);
+        assert_eq!(memory.take_dirty_ranges(), vec![0x2000..0x4000]);
+    }
+
+    #[test]
+    fn test_scattered_writes_stay_separate() {
+        // Weit verstreute Schreibzugriffe fallen nicht zu einem großen Bereich zusammen
+        let mut memory = Memory::new();
+        for i in 0..100 {
+            memory.write_byte(0x2_0000 * i + 0x10, 1);
+        }
+        let dirty = memory.take_dirty_ranges();
+        assert_eq!(dirty.len(), 100);
+        assert!(dirty.iter().all(|range| range.len() == PAGE_SIZE as usize));
+        assert_eq!(dirty[99], 0xC6_0000..0xC6_1000);
+    }
+
+    #[test]
+    fn test_clear_dirties_only_allocated_pages() {
+        let mut memory = Memory::new();
+        memory.write_word(0x1FFF, 0x1234);
+        memory.write_byte(0x8000, 0); // Null legt keine Seite an
+        memory.take_dirty_ranges();
+
+        memory.clear();
+        assert_eq!(memory.take_dirty_ranges(), vec![0x1000..0x3000]);
+
+        // Kopie über angelegte und leere Seiten hinweg
+        let mut memory = Memory::new();
+        memory.write_word(0x1FFF, 0x1234);
+        let patch = memory.copy_range(0x1FFE..0x3002);
+        assert_eq!(patch.bytes.len(), 0x1004);
+        assert_eq!(patch.bytes[..3], [0x00, 0x12, 0x34]);
+        assert!(patch.bytes[3..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_journal_undo_restores_old_values() {
+        let mut memory = Memory::new();
+        memory.write_long(0x800, 0x1122_3344);
+        memory.start_journal();
+        memory.write_byte(0x801, 0xAA);
+        memory.write_long(0x800, 0xDEAD_BEEF);
+        memory.write_word(0x802, 0x5566);
+        let journal = memory.take_journal();
+        assert_eq!(journal.len(), 4); // Langwort = zwei Wörter
+        assert_eq!(journal[0].old, 0x22);
+
+        // Ohne offenes Journal wird nichts mehr festgehalten
+        memory.write_byte(0x900, 1);
+        assert!(memory.take_journal().is_empty());
+
+        memory.undo(&journal);
+        assert_eq!(memory.read_long(0x800), 0x1122_3344);
+    }
+
+    #[test]
+    fn test_bus_fault_regions() {
+        let mut memory = Memory::new();
+        memory.unmap(0xE0_0000..0xF0_0000);
+        memory.map_rom(0x10_0000..0x10_1000);
+        memory.write_long(0x10_0000, 0x1234_5678); // Laden geht immer
+
+        assert_eq!(
+            memory.fault(0xE1_0000, 4, false),
+            Some(BusFaultKind::Unmapped)
+        );
+        assert_eq!(
+            memory.fault(0xDF_FFFE, 4, true),
+            Some(BusFaultKind::Unmapped)
+        );
+        assert_eq!(memory.fault(0xDF_FFFC, 4, true), None);
+        assert_eq!(memory.fault(0x10_0FFE, 2, false), None);
+        assert_eq!(
+            memory.fault(0x10_0FFE, 2, true),
+            Some(BusFaultKind::ReadOnly)
+        );
+        // Nur 24 Adressbits
+        assert_eq!(
+            memory.fault(0xFF10_0000, 1, true),
+            Some(BusFaultKind::ReadOnly)
+        );
+        assert_eq!(memory.read_long(0x10_0000), 0x1234_5678);
+    }
+
+    #[test]
+    fn test_hashes_of_fixed_contents() {
+        let mut memory = Memory::new();
+        // Leerer Bereich: Startwerte der Verfahren
+        assert_eq!(memory.crc32(0x800..0x800), 0);
+        assert_eq!(memory.fnv1a64(0x800..0x800), 0xCBF2_9CE4_8422_2325);
+        // Nie beschriebene Bytes zählen als 0
+        assert_eq!(memory.crc32(0x800..0x804), 0x2144_DF1C);
+
+        memory.write_byte(0x800, b'a');
+        assert_eq!(memory.crc32(0x800..0x801), 0xE8B7_BE43);
+        assert_eq!(memory.fnv1a64(0x800..0x801), 0xAF63_DC4C_8601_EC8C);
+
+        for (i, byte) in b"123456789".iter().enumerate() {
+            memory.write_byte(0x1000 + i as u32, *byte);
+        }
+        assert_eq!(memory.crc32(0x1000..0x1009), 0xCBF4_3926);
+        assert_eq!(
+            memory.hash_range(0x1000..0x1009, HashAlgorithm::Crc32),
+            0xCBF4_3926
+        );
+        assert_eq!(
+            memory.hash_range(0x1000..0x1009, HashAlgorithm::Fnv1a64),
+            0x06D5_5739_23C6_CDFC
+        );
+    }
+
+    #[test]
+    fn test_pages_allocated_on_first_nonzero_write() {
+        let mut memory = Memory::new();
+        assert_eq!(memory.allocated_pages(), 0);
+        memory.write_long(0x2000, 0);
+        assert_eq!(memory.allocated_pages(), 0, "zeros need no page");
+        memory.write_word(0x2FFF, 0xABCD); // über die Seitengrenze
+        assert_eq!(memory.allocated_pages(), 2);
+        assert_eq!(memory.read_word(0x2FFF), 0xABCD);
+        // 24-Bit-Umlauf wie bisher
+        memory.write_byte(0xFF00_0010, 7);
+        assert_eq!(memory.read_byte(0x10), 7);
+
+        memory.clear();
+        assert_eq!(memory.allocated_pages(), 0);
+        assert_eq!(memory.read_word(0x2FFF), 0);
+    }
+
+    #[test]
+    fn test_diff_over_allocated_and_unallocated_pages() {
+        let empty = Memory::new();
+        // Beide Seiten fehlen
+        assert!(empty.diff(&Memory::new()).is_empty());
+
+        // Angelegt, aber wieder 0, gegen nie angelegt
+        let mut zeroed = Memory::new();
+        zeroed.write_long(0x4000, 0x1111_1111);
+        zeroed.write_long(0x4000, 0);
+        assert_eq!(zeroed.allocated_pages(), 1);
+        assert!(zeroed.diff(&empty).is_empty());
+        assert!(empty.diff(&zeroed).is_empty());
+        assert_eq!(zeroed, empty);
+
+        // Angelegt gegen nie angelegt, in beide Richtungen
+        let mut written = Memory::new();
+        written.write_word(0x4010, 0x00FF);
+        written.write_byte(0x4013, 1);
+        let expected = vec![
+            DiffRange {
+                start: 0x4011,
+                end: 0x4012,
+            },
+            DiffRange {
+                start: 0x4013,
+                end: 0x4014,
+            },
+        ];
+        assert_eq!(written.diff(&empty), expected);
+        assert_eq!(empty.diff(&written), expected);
+        assert_ne!(written, empty);
+
+        // Beide angelegt: gleich, verschieden, und zusammenhängend über die Seitengrenze
+        let mut copy = written.clone();
+        assert!(copy.diff(&written).is_empty());
+        copy.write_long(0x4FFE, 0xDEAD_BEEF);
+        copy.write_byte(0x4011, 0xFF); // wie vorher
+        assert_eq!(
+            written.diff(&copy),
+            vec![DiffRange {
+                start: 0x4FFE,
+                end: 0x5002
+            }]
+        );
+        assert_eq!(written.diff(&copy)[0].to_string(), "$004FFE..$005002");
+    }
+
+    #[test]
+    fn test_clone_region_and_equality() {
+        let mut memory = Memory::new();
+        memory.write_long(0x0800, 0x0102_0304);
+        memory.write_long(0x9000, 5);
+        memory.map_rom(0x8000..0xA000);
+
+        let region = memory.clone_region(0x0802..0x0804);
+        assert_eq!(region.allocated_pages(), 1);
+        assert_eq!(region.read_long(0x0800), 0x0000_0304);
+        assert_eq!(region.read_long(0x9000), 0);
+        assert_eq!(region, memory.clone_region(0x0802..0x0804));
+        assert_eq!(region.fault(0x8000, 2, true), Some(BusFaultKind::ReadOnly));
+
+        // Zähler zählen nicht, Bereiche schon
+        let mut other = memory.clone_region(0..0x0100_0000);
+        assert_eq!(other.write_count(), 0);
+        assert_eq!(other, memory);
+        other.unmap(0xE0_0000..0xF0_0000);
+        assert_ne!(other, memory);
+        assert!(other.diff(&memory).is_empty());
     }
 }
@@ -0,0 +1,192 @@
+// Profiler: Taktzyklen pro Quellzeile
+// Jede ausgeführte Instruktion wird mit ihren geschätzten Zyklen der Zeile zugerechnet, zu der
+// ihre Adresse in der Source-Map gehört. Adressen ohne Zeile und Instruktionen in
+// Data-Sections landen im Sammeltopf "unknown", ebenso die Zyklen der Interrupt-Annahme.
+
+use crate::program::Program;
+use std::collections::HashMap;
+
+/// Zyklen und Ausführungen einer Zeile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineCost {
+    pub cycles: u64,
+    pub count: u64,
+}
+
+/// Was ein Schritt zum Profil beigetragen hat (für `Emulator::step_back`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProfileSample {
+    /// None = unbekannt
+    pub line: Option<usize>,
+    pub cycles: u32,
+    /// Zyklen der Interrupt-Annahme vor der Instruktion
+    pub overhead: u32,
+}
+
+/// Eine Zeile der Hot-Lines-Tabelle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HotLine {
+    /// None = unbekannt
+    pub line: Option<usize>,
+    pub cycles: u64,
+    pub count: u64,
+    /// Anteil an allen Zyklen in Prozent
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    lines: HashMap<usize, LineCost>,
+    unknown: LineCost,
+    total_cycles: u64,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, sample: ProfileSample) {
+        let cost = self.cost_mut(sample.line);
+        cost.cycles += sample.cycles as u64;
+        cost.count += 1;
+        self.unknown.cycles += sample.overhead as u64;
+        self.total_cycles += (sample.cycles + sample.overhead) as u64;
+    }
+
+    /// Nimmt einen mit `add` gezählten Schritt wieder heraus
+    pub fn subtract(&mut self, sample: ProfileSample) {
+        let cost = self.cost_mut(sample.line);
+        cost.cycles = cost.cycles.saturating_sub(sample.cycles as u64);
+        cost.count = cost.count.saturating_sub(1);
+        if let Some(line) = sample.line {
+            if self.lines[&line].count == 0 {
+                self.lines.remove(&line);
+            }
+        }
+        self.unknown.cycles = self.unknown.cycles.saturating_sub(sample.overhead as u64);
+        self.total_cycles = self
+            .total_cycles
+            .saturating_sub((sample.cycles + sample.overhead) as u64);
+    }
+
+    fn cost_mut(&mut self, line: Option<usize>) -> &mut LineCost {
+        match line {
+            Some(line) => self.lines.entry(line).or_default(),
+            None => &mut self.unknown,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    pub fn line(&self, line: usize) -> LineCost {
+        self.lines.get(&line).copied().unwrap_or_default()
+    }
+
+    pub fn unknown(&self) -> LineCost {
+        self.unknown
+    }
+
+    /// Alle Zeilen mit Zyklen, teuerste zuerst; "unknown" steht dazwischen, falls nicht leer
+    pub fn hot_lines(&self) -> Vec<HotLine> {
+        let percent = |cycles: u64| {
+            if self.total_cycles == 0 {
+                0.0
+            } else {
+                cycles as f64 * 100.0 / self.total_cycles as f64
+            }
+        };
+        let mut hot: Vec<HotLine> = self
+            .lines
+            .iter()
+            .map(|(&line, cost)| (Some(line), *cost))
+            .chain((self.unknown != LineCost::default()).then_some((None, self.unknown)))
+            .map(|(line, cost)| HotLine {
+                line,
+                cycles: cost.cycles,
+                count: cost.count,
+                percent: percent(cost.cycles),
+            })
+            .collect();
+        // Gleiche Zyklen: nach Zeilennummer, "unknown" zuletzt
+        hot.sort_by_key(|h| (std::cmp::Reverse(h.cycles), h.line.unwrap_or(usize::MAX)));
+        hot
+    }
+
+    /// Textausgabe für `mc68000 --profile`: die `limit` teuersten Zeilen mit Quelltext
+    pub fn report(&self, program: Option<&Program>, limit: usize) -> String {
+        let mut out = format!(
+            "{:>7}  {:>10}  {:>6}  {:>8}  source\n",
+            "line", "cycles", "%", "count"
+        );
+        for hot in self.hot_lines().into_iter().take(limit) {
+            let (line, text) = match hot.line {
+                Some(line) => (
+                    line.to_string(),
+                    program.and_then(|p| p.line_text(line)).unwrap_or("").trim(),
+                ),
+                None => ("unknown".to_string(), ""),
+            };
+            out.push_str(&format!(
+                "{:>7}  {:>10}  {:>5.1}%  {:>8}  {}\n",
+                line, hot.cycles, hot.percent, hot.count, text
+            ));
+        }
+        out.push_str(&format!("  total  {:>10}\n", self.total_cycles));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(line: Option<usize>, cycles: u32) -> ProfileSample {
+        ProfileSample {
+            line,
+            cycles,
+            overhead: 0,
+        }
+    }
+
+    #[test]
+    fn test_hot_lines_order_and_percent() {
+        let mut profile = Profile::new();
+        for _ in 0..3 {
+            profile.add(sample(Some(5), 10));
+        }
+        profile.add(sample(Some(2), 30));
+        profile.add(sample(None, 20));
+        profile.add(ProfileSample {
+            line: Some(7),
+            cycles: 4,
+            overhead: 16,
+        });
+
+        let hot = profile.hot_lines();
+        let order: Vec<Option<usize>> = hot.iter().map(|h| h.line).collect();
+        // Zeile 2 und 5 je 30 Zyklen, unknown 20 + 16
+        assert_eq!(order, [None, Some(2), Some(5), Some(7)]);
+        assert_eq!(profile.total_cycles(), 100);
+        assert_eq!(hot[0].cycles, 36);
+        assert_eq!(hot[0].count, 1);
+        assert_eq!(hot[2].count, 3);
+        assert!((hot[2].percent - 30.0).abs() < 1e-9);
+
+        profile.subtract(ProfileSample {
+            line: Some(7),
+            cycles: 4,
+            overhead: 16,
+        });
+        assert_eq!(profile.line(7), LineCost::default());
+        assert_eq!(profile.unknown().cycles, 20);
+        assert_eq!(profile.total_cycles(), 80);
+        assert_eq!(profile.hot_lines().len(), 3);
+    }
+}
@@ -0,0 +1,213 @@
+// Assembliertes Programm: Speicherabbild plus Metadaten (Sections, Symbole, Source Map)
+
+use crate::breakpoints::Assertion;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind {
+    Code,
+    Data,
+}
+
+/// Zusammenhängender Adressbereich gleicher Art
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub kind: SectionKind,
+    pub start: u32,
+    pub end: u32, // exklusiv
+    /// Modul, aus dem die Section stammt (Module Mode); None bei einer einzelnen Quelle
+    pub module: Option<String>,
+}
+
+impl Section {
+    pub fn contains(&self, address: u32) -> bool {
+        (self.start..self.end).contains(&address)
+    }
+
+    pub fn len(&self) -> u32 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    /// (Adresse, Wort) wie von `Assembler::assemble` geliefert
+    pub image: Vec<(u32, u16)>,
+    /// Bytes aus DC-Direktiven, exakt adressiert (auch ungerade Adressen); DS erzeugt
+    /// keine Einträge. Im `image` stecken sie zusätzlich zu Wörtern zusammengefasst.
+    pub data: Vec<(u32, u8)>,
+    pub sections: Vec<Section>,
+    pub symbols: HashMap<String, u32>,
+    /// Startadresse aus `END label`
+    pub entry: Option<u32>,
+    /// Anfangswert für A7 aus `STACK $addr` (oder `INITSP`)
+    pub initial_sp: Option<u32>,
+    /// Instruktionsadresse -> Quellzeile (1-basiert)
+    pub source_map: BTreeMap<u32, usize>,
+    /// Quelltext, aus dem das Programm erzeugt wurde
+    pub source_lines: Vec<String>,
+    /// Prüfungen aus ASSERT-Direktiven, nach Adresse sortiert
+    pub assertions: Vec<Assertion>,
+    /// Bytes, die ein Label mit DC/DS belegt (auch über mehrere Zeilen ohne eigenes Label)
+    pub object_sizes: HashMap<String, u32>,
+}
+
+impl Program {
+    /// Ordnet alle Sections dem Modul `name` zu
+    pub fn set_module(&mut self, name: &str) {
+        for section in &mut self.sections {
+            section.module = Some(name.to_string());
+        }
+    }
+
+    pub fn section_at(&self, address: u32) -> Option<&Section> {
+        self.sections.iter().find(|s| s.contains(address))
+    }
+
+    pub fn is_code(&self, address: u32) -> bool {
+        self.section_at(address)
+            .is_some_and(|s| s.kind == SectionKind::Code)
+    }
+
+    pub fn is_data(&self, address: u32) -> bool {
+        self.section_at(address)
+            .is_some_and(|s| s.kind == SectionKind::Data)
+    }
+
+    /// ASSERT-Direktiven, die vor der Instruktion an `address` geprüft werden
+    pub fn assertions_at(&self, address: u32) -> &[Assertion] {
+        let start = self.assertions.partition_point(|a| a.address < address);
+        let end = self.assertions.partition_point(|a| a.address <= address);
+        &self.assertions[start..end]
+    }
+
+    /// Quellzeile der Instruktion an `address`
+    pub fn line_for_address(&self, address: u32) -> Option<usize> {
+        self.source_map.get(&address).copied()
+    }
+
+    /// Adresse der ersten Instruktion auf Quellzeile `line`
+    pub fn address_for_line(&self, line: usize) -> Option<u32> {
+        self.source_map
+            .iter()
+            .find(|(_, &l)| l == line)
+            .map(|(&address, _)| address)
+    }
+
+    /// Label genau an `address` (bei mehreren das alphabetisch erste)
+    pub fn symbol_at(&self, address: u32) -> Option<&str> {
+        self.symbols
+            .iter()
+            .filter(|(_, &a)| a == address)
+            .map(|(name, _)| name.as_str())
+            .min()
+    }
+
+    /// `address` als Label, falls vorhanden, sonst als $-Hexzahl
+    pub fn symbolize(&self, address: u32) -> String {
+        match self.symbol_at(address) {
+            Some(name) => name.to_string(),
+            None => format!("${:06X}", address),
+        }
+    }
+
+    /// `address` relativ zum nächsten Label darunter ("LOOP", "LOOP+4"); None ohne Label
+    pub fn symbol_offset(&self, address: u32) -> Option<String> {
+        let (name, base) = self
+            .symbols
+            .iter()
+            .filter(|(_, &a)| a <= address)
+            .max_by(|(n1, a1), (n2, a2)| a1.cmp(a2).then(n2.cmp(n1)))?;
+        Some(match address - base {
+            0 => name.clone(),
+            offset => format!("{}+{}", name, offset),
+        })
+    }
+
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        self.source_lines
+            .get(line.checked_sub(1)?)
+            .map(|s| s.as_str())
+    }
+
+    /// Startadresse: `END label`, sonst die erste Instruktion ab $1000, sonst die erste
+    /// Instruktion überhaupt, sonst das erste Wort des Abbilds
+    pub fn entry_point(&self) -> Option<u32> {
+        if self.entry.is_some() {
+            return self.entry;
+        }
+        // Instruktionsadressen, damit DC-Daten vor dem Code nicht als Start gelten
+        self.source_map
+            .range(0x1000..)
+            .chain(self.source_map.iter())
+            .next()
+            .map(|(addr, _)| *addr)
+            .or_else(|| self.image.first().map(|(addr, _)| *addr))
+    }
+
+    /// Wörter des Abbilds, die in Sections der Art `kind` liegen
+    pub fn words_in(&self, kind: SectionKind) -> impl Iterator<Item = &(u32, u16)> {
+        self.image
+            .iter()
+            .filter(move |(addr, _)| self.section_at(*addr).is_some_and(|s| s.kind == kind))
+    }
+}
+
+/// Adresse aus einer Benutzereingabe ($8000, 0x8000 oder dezimal), z.B. für die
+/// Start-Felder der GUI und die Kommandozeile; nur im 24-Bit-Adressraum
+pub fn parse_address(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let value = match text.strip_prefix('$').or_else(|| text.strip_prefix("0x")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => text.parse().ok()?,
+    };
+    (value <= 0x00FF_FFFF).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_and_address_lookup() {
+        let program = Program {
+            source_map: BTreeMap::from([(0x1000, 3), (0x1002, 4), (0x1006, 6)]),
+            source_lines: vec!["a".into(), "b".into(), "c".into()],
+            ..Default::default()
+        };
+
+        assert_eq!(program.line_for_address(0x1002), Some(4));
+        assert_eq!(program.line_for_address(0x1004), None);
+        assert_eq!(program.address_for_line(6), Some(0x1006));
+        assert_eq!(program.address_for_line(5), None);
+        assert_eq!(program.line_text(3), Some("c"));
+        assert_eq!(program.line_text(0), None);
+    }
+
+    #[test]
+    fn test_symbol_offset() {
+        let program = Program {
+            symbols: HashMap::from([("START".into(), 0x1000), ("LOOP".into(), 0x1004)]),
+            ..Default::default()
+        };
+        assert_eq!(program.symbol_offset(0x1000).as_deref(), Some("START"));
+        assert_eq!(program.symbol_offset(0x1002).as_deref(), Some("START+2"));
+        assert_eq!(program.symbol_offset(0x100A).as_deref(), Some("LOOP+6"));
+        assert_eq!(program.symbol_offset(0x0800), None);
+    }
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(parse_address("$8000"), Some(0x8000));
+        assert_eq!(parse_address(" 0x1000 "), Some(0x1000));
+        assert_eq!(parse_address("4096"), Some(0x1000));
+        assert_eq!(parse_address("$1000000"), None);
+        assert_eq!(parse_address("START"), None);
+        assert_eq!(parse_address(""), None);
+    }
+}
@@ -0,0 +1,247 @@
+// Referenzmodell der emulierten Instruktionen
+// Kleine Funktionen, die die beabsichtigte Semantik nach dem 68000 Programmer's Reference
+// Manual nachrechnen, bewusst unabhängig von cpu.rs: Überlauf und Übertrag kommen aus
+// vorzeichenbehafteter bzw. breiterer Arithmetik statt aus Bit-Tricks. `testgen` berechnet
+// damit die erwarteten Werte des Exercisers; zugleich steht hier, welche Instruktion welches
+// Flag wie setzt.
+//
+// Flags: X N Z V C = Bits 4..0 des CCR. "X bleibt" heißt, das Bit wird unverändert aus
+// dem CCR davor übernommen.
+
+use crate::isa::Size;
+
+mod card;
+pub use card::{card_for, Card, Form};
+
+pub const X: u8 = 0x10;
+pub const N: u8 = 0x08;
+pub const Z: u8 = 0x04;
+pub const V: u8 = 0x02;
+pub const C: u8 = 0x01;
+
+/// Wert in der Operandengröße und das CCR danach
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub value: u32,
+    pub ccr: u8,
+}
+
+pub fn mask(size: Size) -> u32 {
+    match size {
+        Size::Byte => 0xFF,
+        Size::Word => 0xFFFF,
+        Size::Long => 0xFFFF_FFFF,
+    }
+}
+
+fn bits(size: Size) -> u32 {
+    size.bytes() * 8
+}
+
+/// Die unteren Bits von `value` als vorzeichenbehaftete Zahl
+pub fn signed(value: u32, size: Size) -> i64 {
+    match size {
+        Size::Byte => value as u8 as i8 as i64,
+        Size::Word => value as u16 as i16 as i64,
+        Size::Long => value as i32 as i64,
+    }
+}
+
+/// Vorzeichenerweiterung auf 32 Bit (MOVEA.W, CMPA.W)
+pub fn sign_extend(value: u32, size: Size) -> u32 {
+    signed(value, size) as u32
+}
+
+/// Passt `value` vorzeichenbehaftet in die Operandengröße?
+fn fits(value: i64, size: Size) -> bool {
+    let half = 1i64 << (bits(size) - 1);
+    (-half..half).contains(&value)
+}
+
+/// Ein Datenregister nach einem Schreibzugriff der Größe `size`: die oberen Bits bleiben
+pub fn merge(old: u32, value: u32, size: Size) -> u32 {
+    (old & !mask(size)) | (value & mask(size))
+}
+
+/// N und Z nach dem Ergebnis
+fn nz(value: u32, size: Size) -> u8 {
+    let mut ccr = 0;
+    if signed(value, size) < 0 {
+        ccr |= N;
+    }
+    if value & mask(size) == 0 {
+        ccr |= Z;
+    }
+    ccr
+}
+
+/// MOVE, TST: N und Z nach dem Wert, V und C gelöscht, X bleibt
+pub fn move_flags(value: u32, size: Size, ccr: u8) -> u8 {
+    (ccr & X) | nz(value, size)
+}
+
+/// MOVEQ: Byte vorzeichenerweitert nach Long, Flags wie MOVE.L
+pub fn moveq(data: i8, ccr: u8) -> Outcome {
+    let value = data as i32 as u32;
+    Outcome {
+        value,
+        ccr: move_flags(value, Size::Long, ccr),
+    }
+}
+
+/// ADD, ADDQ (auf Dn): X und C = Übertrag aus dem obersten Bit, V = Ergebnis passt
+/// vorzeichenbehaftet nicht in die Größe
+pub fn add(source: u32, dest: u32, size: Size) -> Outcome {
+    let sum = (source & mask(size)) as u64 + (dest & mask(size)) as u64;
+    let value = sum as u32 & mask(size);
+    let mut ccr = nz(value, size);
+    if !fits(signed(source, size) + signed(dest, size), size) {
+        ccr |= V;
+    }
+    if sum > mask(size) as u64 {
+        ccr |= X | C;
+    }
+    Outcome { value, ccr }
+}
+
+/// SUB, SUBQ (auf Dn): `dest - source`; X und C = Borgen, V wie bei ADD
+pub fn sub(source: u32, dest: u32, size: Size) -> Outcome {
+    let value = dest.wrapping_sub(source) & mask(size);
+    let mut ccr = nz(value, size);
+    if !fits(signed(dest, size) - signed(source, size), size) {
+        ccr |= V;
+    }
+    if source & mask(size) > dest & mask(size) {
+        ccr |= X | C;
+    }
+    Outcome { value, ccr }
+}
+
+/// CMP, CMPI: Flags wie SUB, aber X bleibt und das Ziel ändert sich nicht
+pub fn cmp(source: u32, dest: u32, size: Size, ccr: u8) -> u8 {
+    (ccr & X) | (sub(source, dest, size).ccr & !X)
+}
+
+/// CMPA: Quelle vorzeichenerweitert, verglichen wird immer mit allen 32 Bit von An
+pub fn cmpa(source: u32, size: Size, dest: u32, ccr: u8) -> u8 {
+    cmp(sign_extend(source, size), dest, Size::Long, ccr)
+}
+
+/// MULS.W: 16 × 16 → 32 Bit vorzeichenbehaftet; N und Z nach dem Produkt, V und C
+/// gelöscht, X bleibt
+pub fn muls(source: u32, dest: u32, ccr: u8) -> Outcome {
+    let value = (signed(source, Size::Word) * signed(dest, Size::Word)) as u32;
+    Outcome {
+        value,
+        ccr: move_flags(value, Size::Long, ccr),
+    }
+}
+
+/// ASL #count (1-8): X und C = zuletzt herausgeschobenes Bit; V, wenn sich das oberste
+/// Bit irgendwann ändert, d.h. wenn `value * 2^count` vorzeichenbehaftet nicht mehr passt
+pub fn asl(value: u32, count: u32, size: Size) -> Outcome {
+    let shifted = (value as u64) << count;
+    let result = shifted as u32 & mask(size);
+    let mut ccr = nz(result, size);
+    if !fits(signed(value, size) << count, size) {
+        ccr |= V;
+    }
+    if (value & mask(size)) >> (bits(size) - count) & 1 != 0 {
+        ccr |= X | C;
+    }
+    Outcome { value: result, ccr }
+}
+
+/// ORI/ANDI/EORI #data, CCR; nur die fünf Flag-Bits existieren
+pub fn ccr_operation(mnemonic: &str, ccr: u8, data: u8) -> Option<u8> {
+    let result = match mnemonic {
+        "ORI" => ccr | data,
+        "ANDI" => ccr & data,
+        "EORI" => ccr ^ data,
+        _ => return None,
+    };
+    Some(result & 0x1F)
+}
+
+/// Springt Bcc bei diesem CCR? None für unbekannte Mnemonics
+pub fn branch_taken(mnemonic: &str, ccr: u8) -> Option<bool> {
+    let flag = |bit: u8| ccr & bit != 0;
+    let (n, z, v, c) = (flag(N), flag(Z), flag(V), flag(C));
+    Some(match mnemonic {
+        "BRA" => true,
+        "BHI" => !c && !z,
+        "BLS" => c || z,
+        "BCC" => !c,
+        "BCS" => c,
+        "BNE" => !z,
+        "BEQ" => z,
+        "BVC" => !v,
+        "BVS" => v,
+        "BPL" => !n,
+        "BMI" => n,
+        "BGE" => n == v,
+        "BLT" => n != v,
+        "BGT" => !z && n == v,
+        "BLE" => z || n != v,
+        _ => return None,
+    })
+}
+
+/// DBRA: Dn.W herunterzählen (die oberen 16 Bit bleiben); springt, solange nicht -1
+/// erreicht ist. Flags bleiben.
+pub fn dbra(register: u32) -> (u32, bool) {
+    let counter = (register as u16).wrapping_sub(1);
+    (
+        merge(register, counter as u32, Size::Word),
+        counter != 0xFFFF,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_sub_flags() {
+        // Byte: $7F + 1 läuft vorzeichenbehaftet über, $FF + 1 vorzeichenlos
+        assert_eq!(add(1, 0x7F, Size::Byte).ccr, N | V);
+        assert_eq!(
+            add(1, 0xFF, Size::Byte),
+            Outcome {
+                value: 0,
+                ccr: X | Z | C
+            }
+        );
+        assert_eq!(sub(1, 0x80, Size::Byte).ccr, V);
+        assert_eq!(sub(1, 0, Size::Word).ccr, X | N | C);
+        assert_eq!(cmp(1, 0, Size::Word, 0), N | C);
+        assert_eq!(cmp(5, 5, Size::Long, X), X | Z);
+        assert_eq!(cmpa(0xFFFF, Size::Word, 0xFFFF_FFFF, 0), Z);
+    }
+
+    #[test]
+    fn test_asl_overflow_and_carry() {
+        assert_eq!(asl(0x40, 1, Size::Byte).ccr, N | V);
+        assert_eq!(asl(0xC0, 1, Size::Byte).ccr, X | N | C);
+        // Das oberste Bit wechselt unterwegs (1, 0, 1) und ist am Ende wieder gesetzt
+        assert_eq!(asl(0xA0, 2, Size::Byte).ccr, N | V);
+        assert_eq!(
+            asl(0x0101, 8, Size::Word),
+            Outcome {
+                value: 0x0100,
+                ccr: X | V | C
+            }
+        );
+    }
+
+    #[test]
+    fn test_moveq_muls_and_dbra() {
+        assert_eq!(moveq(-1, X | V | C).ccr, X | N);
+        assert_eq!(muls(0x8000, 0x8000, 0).value, 0x4000_0000);
+        assert_eq!(muls(0xFFFF, 3, 0).ccr, N);
+        assert_eq!(dbra(0x1234_0000), (0x1234_FFFF, false));
+        assert_eq!(dbra(0x0000_0002), (0x0000_0001, true));
+        assert_eq!(branch_taken("BGT", N | V), Some(true));
+        assert_eq!(branch_taken("BXX", 0), None);
+    }
+}
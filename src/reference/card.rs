@@ -0,0 +1,306 @@
+// Referenzkarten für den Editor (Hover/F1) und `mc68000 help`
+// Alles kommt aus der Instruktionstabelle in `isa.rs`: Jede Kombination erlaubter Operanden
+// wird mit einem Beispiel assembliert und einmal ausgeführt. Nur was beides übersteht,
+// erscheint als Syntaxform, und die Zyklen sind die der Emulation.
+
+use crate::assembler::Assembler;
+use crate::cpu::{self, Exception, CPU};
+use crate::isa::{self, InstructionSpec, OperandClass, Size};
+use crate::memory::Memory;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Hier wird jedes Beispiel assembliert
+const ORIGIN: u32 = 0x1000;
+
+/// Eine Syntaxform mit den Größen, die der Assembler dafür annimmt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Form {
+    /// z.B. "ADD.W/L An, Dn"
+    pub syntax: String,
+    /// Kleinste und größte Zyklenzahl über Größen und Sprung/kein Sprung
+    pub cycles: RangeInclusive<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub mnemonic: &'static str,
+    pub description: &'static str,
+    pub forms: Vec<Form>,
+    /// Adressierungsarten pro Operand, die in mindestens einer Form vorkommen
+    pub operands: Vec<Vec<OperandClass>>,
+    /// X N Z V C wie in `InstructionSpec::flags`
+    pub flags: &'static str,
+}
+
+/// Karte zu einem Mnemonic (Groß-/Kleinschreibung und Größen-Suffix egal, "move.l" geht);
+/// None, wenn die Instruktion nicht unterstützt wird
+pub fn card_for(mnemonic: &str) -> Option<Card> {
+    let name = mnemonic.split('.').next().unwrap_or("").to_uppercase();
+    let spec = isa::lookup(&name)?;
+
+    // Die Beispiele sollen nichts in die Konsole schreiben
+    let echo = cpu::console_echo();
+    cpu::set_console_echo(false);
+    let mut forms = Vec::new();
+    let mut operands = vec![Vec::new(); spec.operand_count()];
+    for combination in combinations(spec.operands) {
+        let mut sizes = Vec::new();
+        let mut cycles: Option<RangeInclusive<u32>> = None;
+        let candidates: Vec<Option<Size>> = if spec.sizes.len() > 1 {
+            spec.sizes.iter().copied().map(Some).collect()
+        } else {
+            vec![None]
+        };
+        for size in candidates {
+            let Some(measured) = measure(spec, size, &combination) else {
+                continue;
+            };
+            sizes.extend(size);
+            cycles = Some(match cycles {
+                Some(known) => {
+                    (*known.start()).min(*measured.start())..=(*known.end()).max(*measured.end())
+                }
+                None => measured,
+            });
+        }
+        let Some(cycles) = cycles else {
+            continue;
+        };
+        for (classes, class) in operands.iter_mut().zip(&combination) {
+            if !classes.contains(class) {
+                classes.push(*class);
+            }
+        }
+        forms.push(Form {
+            syntax: syntax(spec.mnemonic, &sizes, &combination),
+            cycles,
+        });
+    }
+
+    cpu::set_console_echo(echo);
+
+    Some(Card {
+        mnemonic: spec.mnemonic,
+        description: spec.description,
+        forms,
+        operands,
+        flags: spec.flags,
+    })
+}
+
+/// Alle Kombinationen aus je einer Operandenklasse pro Position
+fn combinations(positions: &[&[OperandClass]]) -> Vec<Vec<OperandClass>> {
+    positions.iter().fold(vec![Vec::new()], |done, classes| {
+        done.iter()
+            .flat_map(|prefix| {
+                classes.iter().map(move |class| {
+                    let mut combination = prefix.clone();
+                    combination.push(*class);
+                    combination
+                })
+            })
+            .collect()
+    })
+}
+
+/// Assembliert ein Beispiel der Form und führt es aus; Zyklen ohne und mit gesetzten Flags
+/// (Bcc springt einmal und einmal nicht). None, wenn Assembler oder CPU es ablehnen.
+fn measure(
+    spec: &InstructionSpec,
+    size: Option<Size>,
+    combination: &[OperandClass],
+) -> Option<RangeInclusive<u32>> {
+    let suffix = size.map_or(String::new(), |size| format!(".{}", size.suffix()));
+    let operands: Vec<String> = combination
+        .iter()
+        .enumerate()
+        .map(|(position, class)| example(*class, position as u32 + 1))
+        .collect();
+    let source = format!(
+        "        ORG     ${:X}\nHERE:   {}{} {}",
+        ORIGIN,
+        spec.mnemonic,
+        suffix,
+        operands.join(", ")
+    );
+    let program = Assembler::new().assemble_source(&source).ok()?;
+
+    let mut cycles = Vec::new();
+    for (registers, ccr) in [(0, 0x00), (2, 0x1F)] {
+        let mut memory = Memory::new();
+        for (address, word) in &program.image {
+            memory.write_word(*address, *word);
+        }
+        let mut cpu = CPU::new();
+        let mut snapshot = cpu.snapshot();
+        snapshot.d = [registers; 8];
+        snapshot.pc = ORIGIN;
+        snapshot.ccr = ccr;
+        cpu.restore(&snapshot);
+        let result = cpu.execute_instruction(&mut memory);
+        if matches!(cpu.exception(), Some(Exception::NotImplemented { .. })) {
+            return None;
+        }
+        cycles.push(result.cycles);
+    }
+    Some(*cycles.iter().min()?..=*cycles.iter().max()?)
+}
+
+/// Beispieloperand; `register` unterscheidet Quelle und Ziel
+fn example(class: OperandClass, register: u32) -> String {
+    match class {
+        OperandClass::DataRegister => format!("D{}", register),
+        OperandClass::AddressRegister => format!("A{}", register),
+        OperandClass::Indirect => format!("(A{})", register),
+        OperandClass::PostIncrement => format!("(A{})+", register),
+        OperandClass::PreDecrement => format!("-(A{})", register),
+        OperandClass::Displacement => format!("4(A{})", register),
+        // Die Instruktion selbst, damit es auch als Sprungziel taugt
+        OperandClass::Absolute => "HERE".to_string(),
+        OperandClass::Immediate => "#1".to_string(),
+        OperandClass::ConditionCodes => "CCR".to_string(),
+    }
+}
+
+/// Kurzname in Syntaxformen und Matrix
+fn notation(class: OperandClass) -> &'static str {
+    match class {
+        OperandClass::DataRegister => "Dn",
+        OperandClass::AddressRegister => "An",
+        OperandClass::Indirect => "(An)",
+        OperandClass::PostIncrement => "(An)+",
+        OperandClass::PreDecrement => "-(An)",
+        OperandClass::Displacement => "d(An)",
+        OperandClass::Absolute => "addr",
+        OperandClass::Immediate => "#imm",
+        OperandClass::ConditionCodes => "CCR",
+    }
+}
+
+fn syntax(mnemonic: &str, sizes: &[Size], combination: &[OperandClass]) -> String {
+    let mut text = mnemonic.to_string();
+    if !sizes.is_empty() {
+        let suffixes: Vec<&str> = sizes.iter().map(|size| size.suffix()).collect();
+        text.push('.');
+        text.push_str(&suffixes.join("/"));
+    }
+    if !combination.is_empty() {
+        let operands: Vec<&str> = combination.iter().map(|class| notation(*class)).collect();
+        text.push(' ');
+        text.push_str(&operands.join(", "));
+    }
+    text
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} — {}", self.mnemonic, self.description)?;
+
+        writeln!(f)?;
+        let width = self.forms.iter().map(|form| form.syntax.len()).max();
+        for form in &self.forms {
+            let (low, high) = (*form.cycles.start(), *form.cycles.end());
+            let cycles = if low == high {
+                low.to_string()
+            } else {
+                format!("{}-{}", low, high)
+            };
+            writeln!(
+                f,
+                "  {:<width$}  {} cycles",
+                form.syntax,
+                cycles,
+                width = width.unwrap_or(0)
+            )?;
+        }
+
+        // Matrix nur mit den Adressierungsarten, die überhaupt vorkommen
+        let mut columns: Vec<OperandClass> = Vec::new();
+        for class in self.operands.iter().flatten() {
+            if !columns.contains(class) {
+                columns.push(*class);
+            }
+        }
+        if !columns.is_empty() {
+            let names: &[&str] = match self.operands.len() {
+                1 => &["operand"],
+                _ => &["source", "destination"],
+            };
+            writeln!(f)?;
+            let header = columns
+                .iter()
+                .map(|class| format!(" {:<5}", notation(*class)));
+            writeln!(
+                f,
+                "{}",
+                format!("  {:<12}{}", "", header.collect::<String>()).trim_end()
+            )?;
+            for (name, classes) in names.iter().zip(&self.operands) {
+                let marks = columns.iter().map(|class| {
+                    let mark = if classes.contains(class) { "✓" } else { "·" };
+                    format!(" {:<5}", mark)
+                });
+                writeln!(
+                    f,
+                    "{}",
+                    format!("  {:<12}{}", name, marks.collect::<String>()).trim_end()
+                )?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "  X N Z V C")?;
+        let flags: Vec<String> = self.flags.chars().map(String::from).collect();
+        writeln!(f, "  {}", flags.join(" "))?;
+        write!(f, "  (* set from the result, - unchanged, 0 cleared)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_card_for_moveq() {
+        let card = card_for("moveq").unwrap();
+        assert_eq!(card.mnemonic, "MOVEQ");
+        assert_eq!(
+            card.forms,
+            vec![Form {
+                syntax: "MOVEQ #imm, Dn".to_string(),
+                cycles: 4..=4
+            }]
+        );
+        let text = card.to_string();
+        assert!(text.starts_with("MOVEQ — move a sign-extended"));
+        assert!(text.contains("  - * * 0 0\n"));
+        assert_eq!(card_for("MOVEQ.L"), Some(card));
+        assert_eq!(card_for("BOGUS"), None);
+    }
+
+    #[test]
+    fn test_forms_follow_the_assembler() {
+        // Adressregister als Quelle gibt es bei ADD nur für .W und .L
+        let add = card_for("ADD").unwrap();
+        let syntax: Vec<&str> = add.forms.iter().map(|form| form.syntax.as_str()).collect();
+        assert_eq!(syntax, ["ADD.B/W/L Dn, Dn", "ADD.W/L An, Dn"]);
+
+        // Bcc: nicht gesprungen 4, gesprungen 10 Zyklen (wie `CPU::estimate_cycles`)
+        let beq = card_for("BEQ").unwrap();
+        assert_eq!(beq.forms[0].syntax, "BEQ addr");
+        assert_eq!(beq.forms[0].cycles, 4..=10);
+        assert!(beq.to_string().contains("operand"));
+    }
+
+    #[test]
+    fn test_every_instruction_has_a_card() {
+        for spec in isa::INSTRUCTIONS {
+            let card = card_for(spec.mnemonic).unwrap();
+            assert!(!card.forms.is_empty(), "{} has no form", spec.mnemonic);
+            assert_eq!(spec.flags.len(), 5, "{}", spec.mnemonic);
+            assert!(spec.flags.chars().all(|c| "*-01".contains(c)));
+            assert!(!spec.description.is_empty());
+        }
+    }
+}
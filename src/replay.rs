@@ -0,0 +1,163 @@
+// Run-Record: alles, was ein Lauf ohne Eingaben zum Wiederholen braucht
+// Der Record hält die Einstellungen fest, die das Ergebnis bestimmen: den Seed des
+// Zufallsgenerators, Start- und Stack-Vorgabe und das Prüfprofil. Gespeichert wird er als
+// Textdatei im selben TOML-Format wie die .expect-Dateien des Batch-Modus:
+//
+//     rng_seed = 42
+//     entry = 0x1000
+//     strictness = "teaching"
+//
+// `mc68000 --record FILE` schreibt ihn nach dem Lauf, `mc68000 --replay FILE` stellt die
+// Einstellungen vor dem Laden wieder her.
+
+use crate::batch::parse_integer;
+use crate::emulator::Emulator;
+use crate::strictness::StrictnessProfile;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunRecord {
+    /// None = kein Zufallsgenerator angeschlossen
+    pub rng_seed: Option<u32>,
+    pub entry: Option<u32>,
+    pub initial_sp: Option<u32>,
+    /// None = einzeln umgestellte Schalter, die kein Profil ergeben
+    pub strictness: Option<StrictnessProfile>,
+}
+
+impl RunRecord {
+    /// Einstellungen des Emulators, wie sie gerade sind
+    pub fn capture(emulator: &Emulator) -> RunRecord {
+        RunRecord {
+            rng_seed: emulator.rng_seed(),
+            entry: emulator.entry_override(),
+            initial_sp: emulator.initial_sp_override(),
+            strictness: emulator.strictness_profile(),
+        }
+    }
+
+    /// Setzt die Einstellungen vor dem Laden; schließt den Zufallsgenerator mit dem
+    /// festgehaltenen Seed an
+    pub fn apply(&self, emulator: &mut Emulator) {
+        emulator.set_entry_override(self.entry);
+        emulator.set_initial_sp_override(self.initial_sp);
+        if let Some(profile) = self.strictness {
+            emulator.set_profile(profile);
+        }
+        if let Some(seed) = self.rng_seed {
+            emulator.attach_rng(seed);
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<RunRecord, String> {
+        let mut record = RunRecord::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", index + 1, message);
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected key = value".to_string()))?;
+            let value = value.trim();
+            let number = || -> Result<u32, String> {
+                let value = parse_integer(value).map_err(&error)?;
+                u32::try_from(value).map_err(|_| error(format!("{} is out of range", value)))
+            };
+            match key.trim() {
+                "rng_seed" => record.rng_seed = Some(number()?),
+                "entry" => record.entry = Some(number()?),
+                "initial_sp" => record.initial_sp = Some(number()?),
+                "strictness" => {
+                    let name = value.trim_matches('"');
+                    let profile = StrictnessProfile::from_name(name)
+                        .ok_or_else(|| error(format!("unknown strictness profile {}", name)))?;
+                    record.strictness = Some(profile);
+                }
+                key => return Err(error(format!("unexpected key {}", key))),
+            }
+        }
+        Ok(record)
+    }
+}
+
+/// Eine Zeile pro gesetztem Wert; `parse` liest das Ergebnis wieder ein
+impl fmt::Display for RunRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(seed) = self.rng_seed {
+            writeln!(f, "rng_seed = {}", seed)?;
+        }
+        if let Some(entry) = self.entry {
+            writeln!(f, "entry = 0x{:X}", entry)?;
+        }
+        if let Some(sp) = self.initial_sp {
+            writeln!(f, "initial_sp = 0x{:X}", sp)?;
+        }
+        if let Some(profile) = self.strictness {
+            writeln!(f, "strictness = \"{}\"", profile.name())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_record_round_trip() {
+        let record = RunRecord {
+            rng_seed: Some(0xDEAD_BEEF),
+            entry: Some(0x2000),
+            initial_sp: Some(0x8000),
+            strictness: Some(StrictnessProfile::Teaching),
+        };
+        assert_eq!(RunRecord::parse(&record.to_string()), Ok(record));
+        assert_eq!(RunRecord::parse(""), Ok(RunRecord::default()));
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        assert_eq!(
+            RunRecord::parse("rng_seed = 1\nspeed = 3"),
+            Err("line 2: unexpected key speed".to_string())
+        );
+        assert!(RunRecord::parse("rng_seed = -1").is_err());
+        assert!(RunRecord::parse("strictness = \"lax\"").is_err());
+    }
+
+    #[test]
+    fn test_applied_record_repeats_the_numbers() {
+        let source = "
+            ORG     $1000
+            MOVE.L  $E100, D0
+            MOVE.L  $E100, D1
+            SIMHALT
+        ";
+        let run = |emulator: &mut Emulator| {
+            emulator.load_source(source).unwrap();
+            while !emulator.cpu().is_halted() {
+                emulator.step();
+            }
+            (
+                emulator.cpu().get_data_register(0),
+                emulator.cpu().get_data_register(1),
+            )
+        };
+
+        let mut first = Emulator::new();
+        first.attach_rng(Rng::seed_from_clock());
+        let numbers = run(&mut first);
+        let record = RunRecord::capture(&first);
+        assert_eq!(record.rng_seed, first.rng_seed());
+
+        let mut second = Emulator::new();
+        RunRecord::parse(&record.to_string())
+            .unwrap()
+            .apply(&mut second);
+        assert_eq!(run(&mut second), numbers);
+    }
+}
@@ -0,0 +1,195 @@
+// Zufallszahlen-Gerät (xorshift32) für Spiele und Simulationen
+// DATA enthält immer die aktuelle Zufallszahl als Langwort; nach jedem Lesezugriff auf DATA
+// legt das Gerät die nächste Zahl ab. Ein Byte- oder Wortzugriff liest also die oberen
+// 8 bzw. 16 Bit einer neuen Zahl. Ein Schreibzugriff auf SEED setzt den Generator neu.
+// Derselbe Seed liefert immer dieselbe Folge; beim Reset beginnt sie wieder am Seed.
+
+use crate::bus::{Device, IrqRequest};
+use crate::cpu::{AccessKind, MemAccess};
+use crate::memory::Memory;
+use std::ops::Range;
+
+/// Registerabstände zur Basisadresse
+pub const REG_DATA: u32 = 0x00;
+pub const REG_SEED: u32 = 0x04;
+const REGISTER_BYTES: u32 = 0x08;
+
+/// xorshift kann den Zustand 0 nicht verlassen; Seed 0 wird durch diesen Wert ersetzt
+const ZERO_SEED_REPLACEMENT: u32 = 0x2545_F491;
+
+pub struct Rng {
+    base: u32,
+    seed: u32,  // Seed beim Reset
+    state: u32, // zuletzt abgelegte Zahl
+}
+
+impl Rng {
+    /// Standard-Basisadresse, direkt hinter den Disk-Registern
+    pub const DEFAULT_BASE: u32 = 0xE100;
+
+    pub fn new(base: u32, seed: u32) -> Self {
+        Rng {
+            base,
+            seed,
+            state: Self::start_state(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Seed aus der Uhrzeit, wenn keiner vorgegeben ist (der Aufrufer sollte ihn ausgeben)
+    pub fn seed_from_clock() -> u32 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(1, |elapsed| {
+                elapsed.subsec_nanos() ^ elapsed.as_secs() as u32
+            })
+    }
+
+    fn start_state(seed: u32) -> u32 {
+        if seed == 0 {
+            ZERO_SEED_REPLACEMENT
+        } else {
+            seed
+        }
+    }
+
+    /// Nächste Zahl der Folge (xorshift32, Marsaglia 13/17/5)
+    fn next(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Legt die nächste Zahl in DATA ab
+    fn publish(&mut self, memory: &mut Memory) {
+        let value = self.next();
+        memory.write_long(self.base + REG_DATA, value);
+    }
+
+    fn hits(&self, access: &MemAccess, offset: u32) -> bool {
+        let start = self.base + offset;
+        let end = access.addr + access.size.bytes();
+        access.addr < start + 4 && start < end
+    }
+}
+
+impl Device for Rng {
+    fn name(&self) -> &str {
+        "rng"
+    }
+
+    fn registers(&self) -> Option<Range<u32>> {
+        Some(self.base..self.base + REGISTER_BYTES)
+    }
+
+    fn access(&mut self, access: &MemAccess, memory: &mut Memory) {
+        match access.kind {
+            AccessKind::Read if self.hits(access, REG_DATA) => self.publish(memory),
+            AccessKind::Write if self.hits(access, REG_SEED) => {
+                // Neuer Seed gilt nur bis zum Reset; dann zählt wieder der konfigurierte
+                self.state = Self::start_state(memory.read_long(self.base + REG_SEED));
+                self.publish(memory);
+            }
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, _cycles: u32, _memory: &mut Memory) -> Option<IrqRequest> {
+        None
+    }
+
+    fn reset(&mut self, memory: &mut Memory) {
+        self.state = Self::start_state(self.seed);
+        memory.write_long(self.base + REG_SEED, self.seed);
+        self.publish(memory);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::EaDescription;
+    use crate::isa::Size;
+
+    const BASE: u32 = Rng::DEFAULT_BASE;
+
+    fn access(rng: &mut Rng, memory: &mut Memory, kind: AccessKind, offset: u32, size: Size) {
+        let access = MemAccess {
+            addr: BASE + offset,
+            size,
+            kind,
+            value: 0,
+            ea_description: EaDescription::Indirect { reg: 0 },
+        };
+        rng.access(&access, memory);
+    }
+
+    /// Liest DATA wie die CPU: Wert aus dem Speicher, danach meldet der Bus den Zugriff
+    fn read_byte(rng: &mut Rng, memory: &mut Memory) -> u8 {
+        let value = memory.read_byte(BASE + REG_DATA);
+        access(rng, memory, AccessKind::Read, REG_DATA, Size::Byte);
+        value
+    }
+
+    #[test]
+    fn test_fixed_seed_gives_fixed_sequence() {
+        let mut memory = Memory::new();
+        let mut rng = Rng::new(BASE, 1);
+        rng.reset(&mut memory);
+        // xorshift32 ab 1: $00042021, $04080601, $9DCCA8C5, ...
+        assert_eq!(memory.read_long(BASE + REG_DATA), 0x0004_2021);
+        let bytes: Vec<u8> = (0..4).map(|_| read_byte(&mut rng, &mut memory)).collect();
+        assert_eq!(bytes, vec![0x00, 0x04, 0x9D, 0x12]);
+
+        // Reset beginnt die Folge von vorn
+        rng.reset(&mut memory);
+        let again: Vec<u8> = (0..4).map(|_| read_byte(&mut rng, &mut memory)).collect();
+        assert_eq!(again, bytes);
+    }
+
+    #[test]
+    fn test_seed_register_reseeds() {
+        let mut memory = Memory::new();
+        let mut rng = Rng::new(BASE, 7);
+        rng.reset(&mut memory);
+        assert_eq!(memory.read_long(BASE + REG_SEED), 7);
+
+        memory.write_long(BASE + REG_SEED, 1);
+        access(
+            &mut rng,
+            &mut memory,
+            AccessKind::Write,
+            REG_SEED,
+            Size::Long,
+        );
+        assert_eq!(memory.read_long(BASE + REG_DATA), 0x0004_2021);
+
+        // Seed 0 bleibt nicht bei 0 hängen
+        memory.write_long(BASE + REG_SEED, 0);
+        access(
+            &mut rng,
+            &mut memory,
+            AccessKind::Write,
+            REG_SEED,
+            Size::Long,
+        );
+        assert_ne!(memory.read_long(BASE + REG_DATA), 0);
+
+        // Andere Zugriffe ändern nichts
+        let before = memory.read_long(BASE + REG_DATA);
+        access(
+            &mut rng,
+            &mut memory,
+            AccessKind::Read,
+            REG_SEED,
+            Size::Long,
+        );
+        assert_eq!(memory.read_long(BASE + REG_DATA), before);
+    }
+}
@@ -0,0 +1,269 @@
+// Flaches ROM-Abbild für andere Emulatoren und EPROM-Programmiergeräte
+// Das Programm wird in ein Fenster [base, base+size) gelegt; Lücken und der Rest werden
+// mit einem Füllbyte (Standard $FF, wie ein gelöschtes EPROM) aufgefüllt. Optional stehen
+// die Reset-Vektoren (Anfangs-SP und Start-PC) am Anfang des ROMs.
+
+use crate::address_map::ADDRESS_SPACE;
+use crate::program::{Program, SectionKind};
+use std::fmt;
+
+/// Lage und Inhalt des ROM-Fensters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RomLayout {
+    pub base: u32,
+    pub size: u32,
+    pub fill: u8,
+    /// Anfangs-SP und Start-PC als Langwörter an Offset 0 und 4
+    pub with_vectors: bool,
+}
+
+impl RomLayout {
+    pub fn new(base: u32, size: u32) -> Self {
+        RomLayout {
+            base,
+            size,
+            fill: 0xFF,
+            with_vectors: false,
+        }
+    }
+
+    fn end(&self) -> u64 {
+        self.base as u64 + self.size as u64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RomError {
+    /// Das Fenster passt nicht in den 24-Bit-Adressraum
+    WindowOutsideAddressSpace {
+        base: u32,
+        size: u32,
+    },
+    /// Eine Section liegt (teilweise) vor dem Fensteranfang
+    SectionBeforeBase {
+        section: String,
+        start: u32,
+        overflow: u32,
+    },
+    /// Eine Section reicht über das Fensterende hinaus
+    SectionPastEnd {
+        section: String,
+        end: u32,
+        overflow: u32,
+    },
+    /// Eine Section belegt den Platz der Reset-Vektoren
+    SectionOverlapsVectors {
+        section: String,
+        start: u32,
+    },
+    /// Für die Vektoren fehlt `STACK` bzw. ein Startpunkt
+    MissingStack,
+    MissingEntry,
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RomError::WindowOutsideAddressSpace { base, size } => write!(
+                f,
+                "ROM window ${:06X} + ${:X} does not fit in the 24-bit address space",
+                base, size
+            ),
+            RomError::SectionBeforeBase {
+                section,
+                start,
+                overflow,
+            } => write!(
+                f,
+                "section {} starts at ${:06X}, ${:X} bytes before the ROM base",
+                section, start, overflow
+            ),
+            RomError::SectionPastEnd {
+                section,
+                end,
+                overflow,
+            } => write!(
+                f,
+                "section {} ends at ${:06X}, ${:X} bytes past the end of the ROM",
+                section, end, overflow
+            ),
+            RomError::SectionOverlapsVectors { section, start } => write!(
+                f,
+                "section {} at ${:06X} overlaps the reset vectors in the first 8 bytes",
+                section, start
+            ),
+            RomError::MissingStack => {
+                write!(f, "--with-vectors needs an initial stack pointer (STACK)")
+            }
+            RomError::MissingEntry => write!(f, "--with-vectors needs an entry point"),
+        }
+    }
+}
+
+/// Größenangabe wie "64K", "1M", "$8000" oder "512"
+pub fn parse_size(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let (number, factor) = match text.char_indices().last()? {
+        (i, 'K' | 'k') => (&text[..i], 1024),
+        (i, 'M' | 'm') => (&text[..i], 1024 * 1024),
+        _ => (text, 1),
+    };
+    let value = match number
+        .strip_prefix('$')
+        .or_else(|| number.strip_prefix("0x"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => number.parse().ok()?,
+    };
+    value.checked_mul(factor).filter(|&size| size > 0)
+}
+
+/// Legt das Programm in das ROM-Fenster; jede Section muss vollständig darin liegen.
+///
+/// ```
+/// use mc68000::rom::{build_rom, RomLayout};
+/// use mc68000::Assembler;
+///
+/// let program = Assembler::new()
+///     .assemble_source("        ORG $F80010\n        NOP")
+///     .unwrap();
+/// let rom = build_rom(&program, &RomLayout::new(0xF80000, 0x20)).unwrap();
+/// assert_eq!(rom.len(), 0x20);
+/// assert_eq!(rom[0x10..0x12], [0x4E, 0x71]);
+/// assert_eq!(rom[0x12], 0xFF);
+/// ```
+pub fn build_rom(program: &Program, layout: &RomLayout) -> Result<Vec<u8>, RomError> {
+    if layout.end() > ADDRESS_SPACE as u64 {
+        return Err(RomError::WindowOutsideAddressSpace {
+            base: layout.base,
+            size: layout.size,
+        });
+    }
+    for section in program.sections.iter().filter(|s| !s.is_empty()) {
+        if section.start < layout.base {
+            return Err(RomError::SectionBeforeBase {
+                section: section.name.clone(),
+                start: section.start,
+                overflow: layout.base - section.start,
+            });
+        }
+        if section.end as u64 > layout.end() {
+            return Err(RomError::SectionPastEnd {
+                section: section.name.clone(),
+                end: section.end,
+                overflow: (section.end as u64 - layout.end()) as u32,
+            });
+        }
+        if layout.with_vectors && section.start < layout.base + 8 {
+            return Err(RomError::SectionOverlapsVectors {
+                section: section.name.clone(),
+                start: section.start,
+            });
+        }
+    }
+
+    let mut rom = vec![layout.fill; layout.size as usize];
+    let offset = |address: u32| (address - layout.base) as usize;
+    // Wie beim Laden in den Emulator: Code als Wörter, Daten bytegenau
+    for &(address, word) in program.words_in(SectionKind::Code) {
+        rom[offset(address)..offset(address) + 2].copy_from_slice(&word.to_be_bytes());
+    }
+    for &(address, byte) in &program.data {
+        rom[offset(address)] = byte;
+    }
+
+    if layout.with_vectors {
+        let sp = program.initial_sp.ok_or(RomError::MissingStack)?;
+        let entry = program.entry_point().ok_or(RomError::MissingEntry)?;
+        rom[0..4].copy_from_slice(&sp.to_be_bytes());
+        rom[4..8].copy_from_slice(&entry.to_be_bytes());
+    }
+    Ok(rom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::Assembler;
+
+    const BASE: u32 = 0xF8_0000;
+
+    fn assemble(source: &str) -> Program {
+        Assembler::new().assemble_source(source).unwrap()
+    }
+
+    #[test]
+    fn test_rom_with_vectors() {
+        let program = assemble(
+            "        STACK   $8000
+        ORG     $F80100
+START:  MOVEQ   #1, D0
+        SIMHALT
+        ORG     $F80200
+TABLE:  DC.B    $12, $34, $56
+        END     START
+",
+        );
+        let layout = RomLayout {
+            fill: 0xEA,
+            with_vectors: true,
+            ..RomLayout::new(BASE, 0x1000)
+        };
+        let rom = build_rom(&program, &layout).unwrap();
+        assert_eq!(rom.len(), 0x1000);
+        // Reset-Vektoren: SP, dann PC
+        assert_eq!(rom[0..4], [0x00, 0x00, 0x80, 0x00]);
+        assert_eq!(rom[4..8], [0x00, 0xF8, 0x01, 0x00]);
+        // Code an Adresse - Basis, Daten bytegenau
+        assert_eq!(rom[0x100..0x104], [0x70, 0x01, 0xA0, 0x00]);
+        assert_eq!(rom[0x200..0x203], [0x12, 0x34, 0x56]);
+        // Lücken und Rest mit dem Füllbyte
+        assert!(rom[8..0x100].iter().all(|&b| b == 0xEA));
+        assert_eq!(rom[0x203], 0xEA);
+        assert_eq!(rom[0xFFF], 0xEA);
+    }
+
+    #[test]
+    fn test_sections_outside_window() {
+        let program = assemble("        ORG $F80FFC\n        NOP\n        NOP\n        NOP");
+        let error = build_rom(&program, &RomLayout::new(BASE, 0x1000)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "section CODE ends at $F81002, $2 bytes past the end of the ROM"
+        );
+
+        let program = assemble("        ORG $1000\n        NOP");
+        let error = build_rom(&program, &RomLayout::new(BASE, 0x1000)).unwrap_err();
+        assert_eq!(
+            error,
+            RomError::SectionBeforeBase {
+                section: "CODE".to_string(),
+                start: 0x1000,
+                overflow: BASE - 0x1000,
+            }
+        );
+
+        let program = assemble("        ORG $F80004\n        NOP");
+        let layout = RomLayout {
+            with_vectors: true,
+            ..RomLayout::new(BASE, 0x1000)
+        };
+        assert!(matches!(
+            build_rom(&program, &layout),
+            Err(RomError::SectionOverlapsVectors { .. })
+        ));
+        let program = assemble("        ORG $F80100\n        NOP");
+        assert_eq!(build_rom(&program, &layout), Err(RomError::MissingStack));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("64K"), Some(0x10000));
+        assert_eq!(parse_size("1m"), Some(0x10_0000));
+        assert_eq!(parse_size("$8000"), Some(0x8000));
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("0"), None);
+        assert_eq!(parse_size("K"), None);
+        assert_eq!(parse_size("lots"), None);
+    }
+}
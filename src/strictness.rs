@@ -0,0 +1,126 @@
+// Voreinstellungen für die Prüfungen von Assembler, CPU und Emulator
+// Ein Profil setzt nur die einzelnen Schalter; danach lässt sich jeder davon wieder einzeln
+// umstellen. `Emulator::strictness_profile` meldet das Profil nur, solange alle Schalter dazu passen.
+// Nicht enthalten, weil es sie nicht als Schalter gibt: nicht implementierte Instruktionen
+// halten immer an (`Exception::NotImplemented`), und der Stack wird nicht auf Grenzen geprüft.
+
+/// Stand aller Schalter, die ein Profil setzt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Strictness {
+    /// .W/.L auf ungerade Adressen lösen einen Address Error aus (`CPU::set_alignment_check`)
+    pub alignment_check: bool,
+    /// Schreiben ins ROM löst einen Bus Error aus, sonst verpufft es
+    /// (`CPU::set_rom_write_faults`)
+    pub rom_write_faults: bool,
+    /// Vor Instruktionen in Data-Sections anhalten (`Emulator::set_data_execution_check`)
+    pub data_execution_check: bool,
+    /// Leerlaufschleifen melden (`Emulator::set_idle_loop_detection`)
+    pub idle_loop_detection: bool,
+    /// MOVEQ #128..255 ablehnen statt mit Warnung kodieren (`Assembler::set_strict_moveq`)
+    pub strict_moveq: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrictnessProfile {
+    /// Für Übungen: alles prüfen und bei allem Verdächtigen anhalten
+    Teaching,
+    /// Wie ein echter 68000 mit einem nachsichtigen Assembler
+    Compatible,
+    /// Alle Prüfungen aus, z.B. für Benchmarks
+    Fast,
+}
+
+impl StrictnessProfile {
+    pub const ALL: [StrictnessProfile; 3] = [
+        StrictnessProfile::Teaching,
+        StrictnessProfile::Compatible,
+        StrictnessProfile::Fast,
+    ];
+
+    /// Name für Kommandozeile und GUI: teaching, compatible, fast
+    pub fn name(self) -> &'static str {
+        match self {
+            StrictnessProfile::Teaching => "teaching",
+            StrictnessProfile::Compatible => "compatible",
+            StrictnessProfile::Fast => "fast",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<StrictnessProfile> {
+        Self::ALL
+            .into_iter()
+            .find(|profile| profile.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StrictnessProfile::Teaching => "Teaching",
+            StrictnessProfile::Compatible => "Compatible",
+            StrictnessProfile::Fast => "Fast",
+        }
+    }
+
+    /// Die Schalter des Profils:
+    ///
+    /// | Schalter             | Teaching | Compatible | Fast |
+    /// |----------------------|----------|------------|------|
+    /// | alignment_check      | an       | an         | aus  |
+    /// | rom_write_faults     | an       | aus        | aus  |
+    /// | data_execution_check | an       | aus        | aus  |
+    /// | idle_loop_detection  | an       | aus        | aus  |
+    /// | strict_moveq         | an       | aus        | aus  |
+    pub fn settings(self) -> Strictness {
+        match self {
+            StrictnessProfile::Teaching => Strictness {
+                alignment_check: true,
+                rom_write_faults: true,
+                data_execution_check: true,
+                idle_loop_detection: true,
+                strict_moveq: true,
+            },
+            // Der echte Prozessor kennt Address Errors, aber keine Data-Sections
+            StrictnessProfile::Compatible => Strictness {
+                alignment_check: true,
+                rom_write_faults: false,
+                data_execution_check: false,
+                idle_loop_detection: false,
+                strict_moveq: false,
+            },
+            StrictnessProfile::Fast => Strictness {
+                alignment_check: false,
+                rom_write_faults: false,
+                data_execution_check: false,
+                idle_loop_detection: false,
+                strict_moveq: false,
+            },
+        }
+    }
+
+    /// Profil, dessen Schalter genau `settings` entsprechen
+    pub fn matching(settings: Strictness) -> Option<StrictnessProfile> {
+        Self::ALL
+            .into_iter()
+            .find(|profile| profile.settings() == settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_roundtrip() {
+        for profile in StrictnessProfile::ALL {
+            assert_eq!(StrictnessProfile::from_name(profile.name()), Some(profile));
+            assert_eq!(
+                StrictnessProfile::matching(profile.settings()),
+                Some(profile)
+            );
+        }
+        assert_eq!(
+            StrictnessProfile::from_name("Teaching"),
+            Some(StrictnessProfile::Teaching)
+        );
+        assert_eq!(StrictnessProfile::from_name("paranoid"), None);
+    }
+}
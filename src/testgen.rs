@@ -0,0 +1,536 @@
+// Generator für ein Selbsttest-Programm ("Exerciser")
+// Erzeugt Assembly, das jede Instruktion aus `isa::INSTRUCTIONS` mit typischen Operanden
+// (0, 1, Vorzeichengrenzen, alle Bits gesetzt) ausführt. Hinter jeder Instruktion prüfen
+// ASSERT-Direktiven Register, Flags und Speicher gegen die Werte aus dem Referenzmodell
+// (`reference`). Vorher wird das CCR auf ein bekanntes Muster gesetzt, damit auch
+// unveränderte Flags geprüft werden.
+//
+// Kommt eine Instruktion hinzu, braucht sie hier einen Fall; `covered` meldet, welche
+// Mnemonics das Programm enthält.
+
+use crate::isa::{self, Size};
+use crate::reference::{self, C, N, V, X, Z};
+
+/// Zielzelle für MOVE in den Speicher (unterhalb des Codes)
+pub const RESULT: u32 = 0x0800;
+/// Stackframe für RTE
+const FRAME: u32 = 0x0900;
+/// Füllwert, an dem man erhaltene Registerteile erkennt
+const FILL: u32 = 0xA5A5_A5A5;
+
+/// Registerinhalte für Operanden; die unteren Bytes und Words decken dieselben Grenzen ab
+const VALUES: &[u32] = &[
+    0x0000_0000,
+    0x0000_0001,
+    0x0000_007F,
+    0x0000_0080,
+    0x0000_7FFF,
+    0x0000_8000,
+    0x7FFF_FFFF,
+    0x8000_0000,
+    0xFFFF_FFFF,
+];
+
+/// Werte für Instruktionen mit einem Operanden
+const SINGLE: &[u32] = &[
+    0x0000_0000,
+    0x0000_0001,
+    0x0000_0040,
+    0x0000_00C0,
+    0x0000_4080,
+    0x0000_C000,
+    0x4000_8001,
+    0x8000_0000,
+    0xFFFF_FFFF,
+    0x1234_5678,
+];
+
+/// CCR vor der geprüften Instruktion, reihum
+const INITIAL_CCR: &[u8] = &[0x00, 0x1F, X, N | Z | V | C];
+
+const SIZES: [Size; 3] = [Size::Byte, Size::Word, Size::Long];
+
+/// Werte, die sich in der Operandengröße unterscheiden (jeweils der erste, damit die oberen
+/// Bits nicht immer 0 sind)
+fn distinct(values: &[u32], size: Size) -> Vec<u32> {
+    let mut seen = Vec::new();
+    let mut result = Vec::new();
+    for &value in values {
+        let low = value & reference::mask(size);
+        if !seen.contains(&low) {
+            seen.push(low);
+            result.push(value);
+        }
+    }
+    result
+}
+
+/// Das erzeugte Programm und was es abdeckt
+pub struct Exerciser {
+    pub source: String,
+    /// Anzahl der geprüften Instruktionsinstanzen
+    pub cases: usize,
+    /// Vorkommende Mnemonics (großgeschrieben, ohne Suffix)
+    pub covered: Vec<&'static str>,
+}
+
+/// Erzeugt das Exerciser-Programm
+pub fn exerciser() -> Exerciser {
+    let mut generator = Generator::default();
+    generator.line("; Exerciser: erzeugt mit `mc68000 gen-exerciser`, nicht von Hand ändern");
+    generator.line(&format!("RESULT      EQU     ${:X}", RESULT));
+    generator.line("            ORG     $1000");
+    // Absolute Sprungziele ((xxx).W) müssen unter $8000 liegen, deshalb kommen JMP und RTE
+    // vor den großen Blöcken; Bcc und DBRA springen relativ
+    generator.jumps();
+    generator.rte();
+    generator.moves();
+    generator.moves_from_address_register();
+    generator.address_moves();
+    generator.tst();
+    generator.quick();
+    generator.add_sub_cmp();
+    generator.cmpi();
+    generator.cmpa();
+    generator.muls();
+    generator.asl();
+    generator.ccr_operations();
+    generator.branches();
+    generator.dbra();
+    generator.nop();
+    generator.line("            SIMHALT");
+    generator.covered.push("SIMHALT");
+
+    let mut covered = generator.covered;
+    covered.sort();
+    covered.dedup();
+    Exerciser {
+        source: generator.source,
+        cases: generator.cases,
+        covered,
+    }
+}
+
+#[derive(Default)]
+struct Generator {
+    source: String,
+    cases: usize,
+    labels: usize,
+    covered: Vec<&'static str>,
+}
+
+impl Generator {
+    fn line(&mut self, text: &str) {
+        self.source.push_str(text);
+        self.source.push('\n');
+    }
+
+    fn op(&mut self, text: &str) {
+        self.line(&format!("            {}", text));
+    }
+
+    fn assert(&mut self, target: &str, value: u32) {
+        self.op(&format!("ASSERT  {} == ${:X}", target, value));
+    }
+
+    fn label(&mut self) -> String {
+        self.labels += 1;
+        format!("X{}", self.labels)
+    }
+
+    /// Beginnt einen Fall: Kommentar und das CCR-Muster für diesen Fall
+    fn case(&mut self, mnemonic: &'static str, comment: &str) -> u8 {
+        let ccr = INITIAL_CCR[self.cases % INITIAL_CCR.len()];
+        self.cases += 1;
+        self.covered.push(mnemonic);
+        self.line(&format!("; {}", comment));
+        ccr
+    }
+
+    fn set_ccr(&mut self, ccr: u8) {
+        self.op("ANDI    #0, CCR");
+        if ccr != 0 {
+            self.op(&format!("ORI     #${:02X}, CCR", ccr));
+        }
+    }
+
+    fn set_data(&mut self, register: usize, value: u32) {
+        self.op(&format!("MOVE.L  #${:X}, D{}", value, register));
+    }
+
+    fn set_address(&mut self, register: usize, value: u32) {
+        self.op(&format!("MOVEA.L #${:X}, A{}", value, register));
+    }
+
+    fn moves(&mut self) {
+        for data in [0i8, 1, 127, -128, -1] {
+            let ccr = self.case("MOVEQ", &format!("MOVEQ #{}", data));
+            self.set_data(0, FILL);
+            self.set_ccr(ccr);
+            self.op(&format!("MOVEQ   #{}, D0", data));
+            let expected = reference::moveq(data, ccr);
+            self.assert("D0", expected.value);
+            self.assert("CCR", expected.ccr as u32);
+        }
+
+        for size in SIZES {
+            let s = size.suffix();
+            for &value in VALUES {
+                let value = value & reference::mask(size);
+                let ccr = self.case("MOVE", &format!("MOVE.{} #${:X}, Dn", s, value));
+                self.set_data(1, FILL);
+                self.set_ccr(ccr);
+                self.op(&format!("MOVE.{}  #${:X}, D1", s, value));
+                self.assert("D1", reference::merge(FILL, value, size));
+                self.assert("CCR", reference::move_flags(value, size, ccr) as u32);
+
+                // Register in den Speicher und zurück
+                let ccr = self.case("MOVE", &format!("MOVE.{} ${:X} via memory", s, value));
+                self.op(&format!("MOVE.L  #${:X}, RESULT", FILL));
+                self.set_data(2, value);
+                self.set_data(3, FILL);
+                self.set_ccr(ccr);
+                self.op(&format!("MOVE.{}  D2, RESULT", s));
+                let shift = 32 - 8 * size.bytes();
+                let long = if shift == 0 {
+                    value
+                } else {
+                    (FILL & ((1 << shift) - 1)) | (value << shift)
+                };
+                self.line(&format!("            ASSERT.L (RESULT) == ${:X}", long));
+                self.assert("CCR", reference::move_flags(value, size, ccr) as u32);
+                self.op(&format!("MOVE.{}  RESULT, D3", s));
+                self.assert("D3", reference::merge(FILL, value, size));
+
+                // Ziel über ein Adressregister
+                self.set_address(0, RESULT + 4);
+                self.op(&format!("MOVE.{}  D2, (A0)", s));
+                self.line(&format!(
+                    "            ASSERT.{} (RESULT+4) == ${:X}",
+                    s, value
+                ));
+            }
+        }
+    }
+
+    /// MOVE.W/L An, Dn: wie MOVE aus einem Datenregister, An bleibt unverändert
+    fn moves_from_address_register(&mut self) {
+        for size in [Size::Word, Size::Long] {
+            for &value in VALUES {
+                let text = format!("MOVE.{}  A5, D3", size.suffix());
+                let ccr = self.case("MOVE", &format!("{} = ${:X}", text, value));
+                self.set_address(5, value);
+                self.set_data(3, FILL);
+                self.set_ccr(ccr);
+                self.op(&text);
+                self.assert("D3", reference::merge(FILL, value, size));
+                self.assert("CCR", reference::move_flags(value, size, ccr) as u32);
+                self.assert("A5", value);
+            }
+        }
+    }
+
+    fn address_moves(&mut self) {
+        for size in [Size::Word, Size::Long] {
+            let s = size.suffix();
+            for &value in VALUES {
+                let ccr = self.case("MOVEA", &format!("MOVEA.{} D4 = ${:X}", s, value));
+                self.set_data(4, value);
+                self.set_address(1, FILL & 0xFFFF);
+                self.set_ccr(ccr);
+                self.op(&format!("MOVEA.{} D4, A1", s));
+                self.assert("A1", reference::sign_extend(value, size));
+                // MOVEA ändert keine Flags
+                self.assert("CCR", ccr as u32);
+            }
+        }
+        for value in [0u32, 0x7FFF, 0x8000, 0xFFFF] {
+            let ccr = self.case("MOVEA", &format!("MOVEA.W #${:X}", value));
+            self.set_ccr(ccr);
+            self.op(&format!("MOVEA.W #${:X}, A2", value));
+            self.assert("A2", reference::sign_extend(value, Size::Word));
+            self.assert("CCR", ccr as u32);
+        }
+    }
+
+    fn tst(&mut self) {
+        for size in SIZES {
+            for &value in SINGLE {
+                let ccr = self.case("TST", &format!("TST.{} ${:X}", size.suffix(), value));
+                self.set_data(5, value);
+                self.set_ccr(ccr);
+                self.op(&format!("TST.{}   D5", size.suffix()));
+                self.assert("D5", value);
+                self.assert("CCR", reference::move_flags(value, size, ccr) as u32);
+            }
+        }
+    }
+
+    fn quick(&mut self) {
+        for mnemonic in ["ADDQ", "SUBQ"] {
+            for size in SIZES {
+                for data in [1u32, 3, 8] {
+                    for &value in VALUES {
+                        let text = format!("{}.{} #{}, D6", mnemonic, size.suffix(), data);
+                        let ccr = self.case(mnemonic, &format!("{} = ${:X}", text, value));
+                        self.set_data(6, value);
+                        self.set_ccr(ccr);
+                        self.op(&text);
+                        let expected = if mnemonic == "ADDQ" {
+                            reference::add(data, value, size)
+                        } else {
+                            reference::sub(data, value, size)
+                        };
+                        self.assert("D6", reference::merge(value, expected.value, size));
+                        self.assert("CCR", expected.ccr as u32);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Quelle Dn und An (An nicht bei .B)
+    fn add_sub_cmp(&mut self) {
+        for mnemonic in ["ADD", "SUB", "CMP"] {
+            for size in SIZES {
+                for register in ["D0", "A0"] {
+                    if size == Size::Byte && register == "A0" {
+                        continue;
+                    }
+                    self.add_sub_cmp_values(mnemonic, size, register);
+                }
+            }
+        }
+    }
+
+    fn add_sub_cmp_values(&mut self, mnemonic: &'static str, size: Size, register: &str) {
+        // Für An reichen die Vorzeichengrenzen
+        let sources = if register == "A0" {
+            distinct(&[0x0000_0001, 0x0000_8000, 0x7FFF_FFFF, 0xFFFF_FFFF], size)
+        } else {
+            distinct(VALUES, size)
+        };
+        for &source in &sources {
+            for &dest in &distinct(VALUES, size) {
+                let text = format!("{}.{} {}, D1", mnemonic, size.suffix(), register);
+                let ccr = self.case(
+                    mnemonic,
+                    &format!("{} with ${:X}, ${:X}", text, source, dest),
+                );
+                if register == "A0" {
+                    self.set_address(0, source);
+                } else {
+                    self.set_data(0, source);
+                }
+                self.set_data(1, dest);
+                self.set_ccr(ccr);
+                self.op(&text);
+                let (value, flags) = match mnemonic {
+                    "ADD" => {
+                        let outcome = reference::add(source, dest, size);
+                        (reference::merge(dest, outcome.value, size), outcome.ccr)
+                    }
+                    "SUB" => {
+                        let outcome = reference::sub(source, dest, size);
+                        (reference::merge(dest, outcome.value, size), outcome.ccr)
+                    }
+                    _ => (dest, reference::cmp(source, dest, size, ccr)),
+                };
+                self.assert("D1", value);
+                self.assert("CCR", flags as u32);
+            }
+        }
+    }
+
+    fn cmpi(&mut self) {
+        for size in SIZES {
+            for &source in &VALUES[..6] {
+                for &dest in VALUES {
+                    let source = source & reference::mask(size);
+                    let text = format!("CMPI.{} #${:X}, D2", size.suffix(), source);
+                    let ccr = self.case("CMPI", &format!("{} = ${:X}", text, dest));
+                    self.set_data(2, dest);
+                    self.set_ccr(ccr);
+                    self.op(&text);
+                    self.assert("D2", dest);
+                    self.assert("CCR", reference::cmp(source, dest, size, ccr) as u32);
+                }
+            }
+        }
+    }
+
+    fn cmpa(&mut self) {
+        for size in [Size::Word, Size::Long] {
+            for &source in VALUES {
+                for &dest in &[0u32, 0x7FFF, 0xFFFF_8000, 0xFFFF_FFFF] {
+                    let text = format!("CMPA.{} D3, A3", size.suffix());
+                    let ccr =
+                        self.case("CMPA", &format!("{} with ${:X}, ${:X}", text, source, dest));
+                    self.set_data(3, source);
+                    self.set_address(3, dest);
+                    self.set_ccr(ccr);
+                    self.op(&text);
+                    self.assert("A3", dest);
+                    self.assert("CCR", reference::cmpa(source, size, dest, ccr) as u32);
+                }
+            }
+        }
+    }
+
+    fn muls(&mut self) {
+        const FACTORS: [u32; 6] = [0, 1, 3, 0x7FFF, 0x8000, 0xFFFF];
+        for source in FACTORS {
+            for dest in FACTORS {
+                let ccr = self.case("MULS", &format!("MULS.W ${:X} * ${:X}", source, dest));
+                self.set_data(4, source | 0x5A5A_0000);
+                self.set_data(5, dest | 0x5A5A_0000);
+                self.set_ccr(ccr);
+                self.op("MULS.W  D4, D5");
+                let expected = reference::muls(source, dest, ccr);
+                self.assert("D5", expected.value);
+                self.assert("CCR", expected.ccr as u32);
+            }
+        }
+        for immediate in [2u32, 0xFFFE] {
+            let ccr = self.case("MULS", &format!("MULS.W #${:X}", immediate));
+            self.set_data(5, 0x4000);
+            self.set_ccr(ccr);
+            self.op(&format!("MULS.W  #${:X}, D5", immediate));
+            let expected = reference::muls(immediate, 0x4000, ccr);
+            self.assert("D5", expected.value);
+            self.assert("CCR", expected.ccr as u32);
+        }
+    }
+
+    fn asl(&mut self) {
+        for size in SIZES {
+            for count in [1u32, 2, 7, 8] {
+                for &value in SINGLE {
+                    let text = format!("ASL.{} #{}, D6", size.suffix(), count);
+                    let ccr = self.case("ASL", &format!("{} = ${:X}", text, value));
+                    self.set_data(6, value);
+                    self.set_ccr(ccr);
+                    self.op(&text);
+                    let expected = reference::asl(value, count, size);
+                    self.assert("D6", reference::merge(value, expected.value, size));
+                    self.assert("CCR", expected.ccr as u32);
+                }
+            }
+        }
+    }
+
+    fn ccr_operations(&mut self) {
+        for mnemonic in ["ORI", "ANDI", "EORI"] {
+            for &ccr in INITIAL_CCR {
+                for data in [0x00u8, 0x0A, 0x15, 0xFF] {
+                    self.case(
+                        mnemonic,
+                        &format!("{} #${:02X} on ${:02X}", mnemonic, data, ccr),
+                    );
+                    self.set_ccr(ccr);
+                    self.op(&format!("{:<7} #${:02X}, CCR", mnemonic, data));
+                    let expected = reference::ccr_operation(mnemonic, ccr, data).unwrap_or(0);
+                    self.assert("CCR", expected as u32);
+                }
+            }
+        }
+    }
+
+    /// Jede Bedingung aus der Instruktionstabelle mit allen 16 Kombinationen von N, Z, V, C
+    fn branches(&mut self) {
+        for spec in isa::INSTRUCTIONS {
+            if reference::branch_taken(spec.mnemonic, 0).is_none() {
+                continue;
+            }
+            for flags in 0..16u8 {
+                let taken = reference::branch_taken(spec.mnemonic, flags) == Some(true);
+                self.case(
+                    spec.mnemonic,
+                    &format!("{} with CCR ${:02X}", spec.mnemonic, flags),
+                );
+                let target = self.label();
+                self.op("MOVEQ   #0, D7");
+                self.set_ccr(flags);
+                self.op(&format!("{:<7} {}", spec.mnemonic, target));
+                self.op("MOVEQ   #1, D7");
+                self.line(&format!("{}:", target));
+                self.assert("D7", if taken { 0 } else { 1 });
+            }
+        }
+    }
+
+    fn dbra(&mut self) {
+        for count in [0u32, 1, 5, 0x0001_0002] {
+            self.case("DBRA", &format!("DBRA with D6 = ${:X}", count));
+            let loop_label = self.label();
+            self.set_data(6, count);
+            self.op("MOVEQ   #0, D5");
+            self.line(&format!("{}:", loop_label));
+            self.op("ADDQ.L  #1, D5");
+            self.op(&format!("DBRA    D6, {}", loop_label));
+            // Schleife läuft (Dn.W + 1)-mal und endet bei Dn.W = $FFFF
+            let mut register = count;
+            let mut iterations = 0;
+            loop {
+                iterations += 1;
+                let (next, taken) = reference::dbra(register);
+                register = next;
+                if !taken {
+                    break;
+                }
+            }
+            self.assert("D5", iterations);
+            self.assert("D6", register);
+        }
+    }
+
+    fn jumps(&mut self) {
+        for mnemonic in ["JMP", "JUMP"] {
+            self.case(mnemonic, &format!("{} to a label", mnemonic));
+            let target = self.label();
+            self.op("MOVEQ   #0, D7");
+            self.op(&format!("{:<7} {}", mnemonic, target));
+            self.op("MOVEQ   #1, D7");
+            self.line(&format!("{}:", target));
+            self.assert("D7", 0);
+        }
+
+        self.case("JMP", "JMP (A4)");
+        let target = self.label();
+        self.op("MOVEQ   #0, D7");
+        self.op(&format!("MOVEA.L #{}, A4", target));
+        self.op("JMP     (A4)");
+        self.op("MOVEQ   #1, D7");
+        self.line(&format!("{}:", target));
+        self.assert("D7", 0);
+        // Die Adresse des Labels kennt erst der Assembler
+        self.op(&format!("ASSERT  A4 == {}", target));
+    }
+
+    /// RTE mit einem von Hand gebauten Stackframe (SR, dann PC)
+    fn rte(&mut self) {
+        for &ccr in INITIAL_CCR {
+            self.case("RTE", &format!("RTE to SR $27{:02X}", ccr));
+            let target = self.label();
+            self.op(&format!("MOVE.W  #$27{:02X}, ${:X}", ccr, FRAME));
+            self.op(&format!("MOVE.L  #{}, ${:X}", target, FRAME + 2));
+            self.set_address(7, FRAME);
+            self.op("MOVEQ   #0, D7");
+            self.op("RTE");
+            self.op("MOVEQ   #1, D7");
+            self.line(&format!("{}:", target));
+            self.assert("D7", 0);
+            self.assert("A7", FRAME + 6);
+            self.assert("CCR", ccr as u32);
+        }
+    }
+
+    fn nop(&mut self) {
+        for &ccr in INITIAL_CCR {
+            self.case("NOP", "NOP keeps the flags");
+            self.set_ccr(ccr);
+            self.op("NOP");
+            self.assert("CCR", ccr as u32);
+        }
+    }
+}
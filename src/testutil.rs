@@ -0,0 +1,212 @@
+// Zusicherungen für Tests gegen den Emulator (Feature `test-util`)
+// Fehlschläge nennen die abweichenden Bereiche bzw. Register statt zwei 16-MB-Speicher
+// oder ganze Snapshots auszugeben.
+//
+// ```ignore
+// let before = emulator.memory().clone();
+// emulator.run(1000);
+// assert_memory_eq!(before, emulator.memory(), except: 0x0804..0x0808);
+// ```
+
+use crate::cpu::CpuSnapshot;
+use crate::memory::{DiffRange, Memory};
+use crate::trace::format_ccr;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// So viele Bereiche bzw. Bytes pro Bereich nennt eine Fehlermeldung höchstens
+const MAX_REPORTED: usize = 8;
+const MAX_BYTES: u32 = 16;
+
+/// Panik mit den abweichenden Bereichen, falls sich die Speicher unterscheiden
+#[track_caller]
+pub fn assert_memory_eq(left: &Memory, right: &Memory) {
+    assert_memory_eq_except(left, right, &[]);
+}
+
+/// Wie `assert_memory_eq`, Abweichungen innerhalb von `allowed` sind aber erlaubt
+#[track_caller]
+pub fn assert_memory_eq_except(left: &Memory, right: &Memory, allowed: &[Range<u32>]) {
+    let unexpected: Vec<DiffRange> = left
+        .diff(right)
+        .into_iter()
+        .flat_map(|diff| outside(diff, allowed))
+        .collect();
+    if !unexpected.is_empty() {
+        panic!(
+            "memory differs in {} range(s):\n{}",
+            unexpected.len(),
+            describe_diff(left, right, &unexpected)
+        );
+    }
+}
+
+/// Eine Zeile pro Bereich: "$000804..$000808: 00 00 01 00 != 00 00 00 00"
+pub fn describe_diff(left: &Memory, right: &Memory, diffs: &[DiffRange]) -> String {
+    let bytes = |memory: &Memory, diff: &DiffRange| {
+        let end = diff.end.min(diff.start + MAX_BYTES);
+        let mut text: Vec<String> = (diff.start..end)
+            .map(|address| format!("{:02X}", memory.read_byte(address)))
+            .collect();
+        if end < diff.end {
+            text.push("...".to_string());
+        }
+        text.join(" ")
+    };
+    let mut report = String::new();
+    for diff in diffs.iter().take(MAX_REPORTED) {
+        let _ = writeln!(
+            report,
+            "  {}: {} != {}",
+            diff,
+            bytes(left, diff),
+            bytes(right, diff)
+        );
+    }
+    if diffs.len() > MAX_REPORTED {
+        let _ = writeln!(report, "  ... and {} more", diffs.len() - MAX_REPORTED);
+    }
+    report
+}
+
+/// Teile von `diff`, die in keinem der erlaubten Bereiche liegen
+fn outside(diff: DiffRange, allowed: &[Range<u32>]) -> Vec<DiffRange> {
+    let mut pieces = vec![diff];
+    for range in allowed {
+        pieces = pieces
+            .into_iter()
+            .flat_map(|piece| {
+                let before = DiffRange {
+                    start: piece.start,
+                    end: piece.end.min(range.start),
+                };
+                let after = DiffRange {
+                    start: piece.start.max(range.end),
+                    end: piece.end,
+                };
+                [before, after]
+                    .into_iter()
+                    .filter(|part| part.start < part.end)
+            })
+            .collect();
+    }
+    pieces
+}
+
+/// Panik mit den abweichenden Registern, falls die Snapshots ungleich sind
+/// (Vergleich wie `CpuSnapshot::eq`)
+#[track_caller]
+pub fn assert_snapshot_eq(left: &CpuSnapshot, right: &CpuSnapshot) {
+    if left == right {
+        return;
+    }
+    let mut differences = Vec::new();
+    let registers = [("D", &left.d, &right.d), ("A", &left.a, &right.a)];
+    for (prefix, old, new) in registers {
+        for i in 0..8 {
+            if old[i] != new[i] {
+                differences.push(format!(
+                    "{}{}: ${:08X} != ${:08X}",
+                    prefix, i, old[i], new[i]
+                ));
+            }
+        }
+    }
+    if left.pc != right.pc {
+        differences.push(format!("PC: ${:06X} != ${:06X}", left.pc, right.pc));
+    }
+    if left.sr & 0xFF00 != right.sr & 0xFF00 {
+        differences.push(format!("SR: ${:04X} != ${:04X}", left.sr, right.sr));
+    }
+    if left.ccr != right.ccr {
+        differences.push(format!(
+            "CCR: {} != {}",
+            format_ccr(left.ccr),
+            format_ccr(right.ccr)
+        ));
+    }
+    if left.halted != right.halted {
+        differences.push(format!("halted: {} != {}", left.halted, right.halted));
+    }
+    if left.cycles != right.cycles {
+        differences.push(format!("cycles: {} != {}", left.cycles, right.cycles));
+    }
+    panic!("CPU state differs:\n  {}", differences.join("\n  "));
+}
+
+/// `assert_memory_eq!(a, b)` oder `assert_memory_eq!(a, b, except: 0x800..0x804, ...)`;
+/// nimmt `Memory` oder `&Memory`
+#[macro_export]
+macro_rules! assert_memory_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testutil::assert_memory_eq(&$left, &$right)
+    };
+    ($left:expr, $right:expr, except: $($allowed:expr),+ $(,)?) => {
+        $crate::testutil::assert_memory_eq_except(&$left, &$right, &[$($allowed),+])
+    };
+}
+
+/// `assert_snapshot_eq!(a, b)` für zwei `CpuSnapshot`
+#[macro_export]
+macro_rules! assert_snapshot_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testutil::assert_snapshot_eq(&$left, &$right)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_ranges_cut_differences() {
+        let diff = DiffRange {
+            start: 0x800,
+            end: 0x810,
+        };
+        assert!(outside(diff, &[0x700..0x804, 0x804..0x810]).is_empty());
+        assert_eq!(
+            outside(diff, &[0x804..0x808, 0x80C..0x900]),
+            vec![
+                DiffRange {
+                    start: 0x800,
+                    end: 0x804
+                },
+                DiffRange {
+                    start: 0x808,
+                    end: 0x80C
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failure_names_ranges_and_registers() {
+        let before = Memory::new();
+        let mut after = before.clone();
+        after.write_long(0x0804, 0x100);
+        crate::assert_memory_eq!(before, after, except: 0x0804..0x0808);
+
+        let message = std::panic::catch_unwind(|| assert_memory_eq(&before, &after))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert_eq!(
+            *message,
+            "memory differs in 1 range(s):\n  $000806..$000807: 00 != 01\n"
+        );
+
+        let left = CpuSnapshot::default();
+        let mut right = left;
+        right.d[3] = 7;
+        right.ccr = 0x04;
+        let message = std::panic::catch_unwind(|| assert_snapshot_eq(&left, &right))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert_eq!(
+            *message,
+            "CPU state differs:\n  D3: $00000000 != $00000007\n  CCR: ----- != --Z--"
+        );
+    }
+}
@@ -0,0 +1,409 @@
+// Farbschemata für die Syntaxhervorhebung der GUI
+// Die Zuordnung Token-Klasse -> Farbe ist eine Tabelle pro Preset; eine neue Klasse
+// braucht nur einen Eintrag in `TokenClass::ALL` und je eine Farbe in den Presets.
+
+use crate::assembler::char_columns;
+use egui::{Color32, RichText};
+use std::ops::Range;
+
+/// Was ein Stück Text in Editor, Maschinencode-Ansicht oder Register-Panel darstellt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    MoveMnemonic,
+    ArithmeticMnemonic,
+    BranchMnemonic,
+    JumpMnemonic,
+    NopMnemonic,
+    OtherMnemonic,
+    Register,
+    Immediate,
+    Label,
+    Comment,
+    Punctuation,
+    LineNumber,
+    CurrentLine,     // Zeile/Adresse am PC
+    ChangedRegister, // im letzten Schritt geändert
+    Address,
+    MachineCode,
+    Binary,
+    Disassembly,
+    Error,
+}
+
+impl TokenClass {
+    pub const ALL: [TokenClass; 19] = [
+        TokenClass::MoveMnemonic,
+        TokenClass::ArithmeticMnemonic,
+        TokenClass::BranchMnemonic,
+        TokenClass::JumpMnemonic,
+        TokenClass::NopMnemonic,
+        TokenClass::OtherMnemonic,
+        TokenClass::Register,
+        TokenClass::Immediate,
+        TokenClass::Label,
+        TokenClass::Comment,
+        TokenClass::Punctuation,
+        TokenClass::LineNumber,
+        TokenClass::CurrentLine,
+        TokenClass::ChangedRegister,
+        TokenClass::Address,
+        TokenClass::MachineCode,
+        TokenClass::Binary,
+        TokenClass::Disassembly,
+        TokenClass::Error,
+    ];
+
+    /// Klasse eines (großgeschriebenen) Mnemonics
+    pub fn of_mnemonic(mnemonic: &str) -> TokenClass {
+        match mnemonic {
+            "MOVEQ" | "MOVE" | "MOVEA" => TokenClass::MoveMnemonic,
+            "ADD" | "SUB" | "CMP" | "CMPA" | "CMPI" | "ADDQ" | "SUBQ" | "MULS" | "ASL" | "TST"
+            | "ORI" | "ANDI" | "EORI" => TokenClass::ArithmeticMnemonic,
+            "BRA" | "BEQ" | "BNE" | "BCC" | "BCS" | "BPL" | "BMI" | "BGE" | "BLT" | "BGT"
+            | "BLE" | "DBRA" => TokenClass::BranchMnemonic,
+            "JMP" | "JUMP" | "RTE" => TokenClass::JumpMnemonic,
+            "NOP" => TokenClass::NopMnemonic,
+            _ => TokenClass::OtherMnemonic,
+        }
+    }
+
+    /// Klasse eines Operanden ("#5", "D0", "(A1)+", "LOOP")
+    pub fn of_operand(operand: &str) -> TokenClass {
+        let register = operand.trim_start_matches(['-', '(']).as_bytes();
+        if operand.starts_with('#') {
+            TokenClass::Immediate
+        } else if matches!(register, [b'D' | b'A' | b'd' | b'a', b'0'..=b'7', ..])
+            || operand.eq_ignore_ascii_case("CCR")
+        {
+            TokenClass::Register
+        } else {
+            TokenClass::Label
+        }
+    }
+}
+
+/// Eingefärbtes Stück einer Quellzeile; `strong` für das Mnemonic.
+/// `columns` sind die Zeichen der Quellzeile, aus denen der Token stammt (für Fehlermarkierungen);
+/// eingefügte Trenner haben einen leeren Bereich.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    pub class: TokenClass,
+    pub strong: bool,
+    pub columns: Range<usize>,
+}
+
+impl Token {
+    fn new(text: impl Into<String>, class: TokenClass, columns: Range<usize>) -> Token {
+        Token {
+            text: text.into(),
+            class,
+            strong: false,
+            columns,
+        }
+    }
+
+    /// Überlappt der Token die Quellspalten `columns`?
+    pub fn overlaps(&self, columns: &Range<usize>) -> bool {
+        self.columns.start < columns.end && columns.start < self.columns.end
+    }
+}
+
+/// Zerlegt eine Quellzeile für die Hervorhebung: Kommentarzeile, Label-Zeile oder
+/// Mnemonic, Operanden (durch ", " getrennt) und Kommentar
+pub fn tokenize_line(line: &str) -> Vec<Token> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return Vec::new();
+    }
+    let whole = char_columns(line, line);
+    if line.trim_start().starts_with(';') {
+        return vec![Token::new(line, TokenClass::Comment, whole)];
+    }
+    if line.contains(':') && !line.starts_with(' ') {
+        return vec![Token::new(line, TokenClass::Label, whole)];
+    }
+
+    let (code, comment) = match line.find(';') {
+        Some(pos) => (&line[..pos], Some(&line[pos..])),
+        None => (line, None),
+    };
+    let mut tokens = Vec::new();
+    let code = code.trim();
+    if let Some((head, operands)) = code
+        .split_once(char::is_whitespace)
+        .or((!code.is_empty()).then_some((code, "")))
+    {
+        // Mnemonic ohne Größen-Suffix klassifizieren
+        let instruction = head.to_uppercase();
+        let mnemonic = instruction.split('.').next().unwrap_or("");
+        let class = TokenClass::of_mnemonic(mnemonic);
+        tokens.push(Token {
+            text: instruction,
+            class,
+            strong: true,
+            columns: char_columns(line, head),
+        });
+        let operands = operands.trim();
+        if !operands.is_empty() {
+            let gap = |at: usize| at..at;
+            tokens.push(Token::new(
+                " ",
+                TokenClass::Punctuation,
+                gap(char_columns(line, operands).start),
+            ));
+            for (i, operand) in operands.split(',').enumerate() {
+                let operand = operand.trim();
+                let columns = char_columns(line, operand);
+                if i > 0 {
+                    tokens.push(Token::new(
+                        ", ",
+                        TokenClass::Punctuation,
+                        gap(columns.start),
+                    ));
+                }
+                tokens.push(Token::new(
+                    operand,
+                    TokenClass::of_operand(operand),
+                    columns,
+                ));
+            }
+        }
+    }
+    if let Some(comment) = comment {
+        tokens.push(Token::new(
+            comment,
+            TokenClass::Comment,
+            char_columns(line, comment),
+        ));
+    }
+    tokens
+}
+
+/// Zerlegte Zeilen nach Zeilennummer; eine Zeile wird nur neu zerlegt, wenn sich ihr
+/// Text geändert hat. So kostet ein Frame nur die sichtbaren, geänderten Zeilen.
+#[derive(Debug, Default)]
+pub struct LineTokenCache {
+    lines: Vec<Option<(String, Vec<Token>)>>,
+    misses: usize,
+}
+
+impl LineTokenCache {
+    /// Tokens der Zeile `index` mit dem aktuellen Text `line`
+    pub fn tokens(&mut self, index: usize, line: &str) -> &[Token] {
+        if index >= self.lines.len() {
+            self.lines.resize(index + 1, None);
+        }
+        let entry = &mut self.lines[index];
+        if entry.as_ref().is_none_or(|(text, _)| text != line) {
+            self.misses += 1;
+            *entry = Some((line.to_string(), tokenize_line(line)));
+        }
+        entry.as_ref().map_or(&[], |(_, tokens)| tokens)
+    }
+
+    /// Vergisst Zeilen hinter `len` (Quelltext wurde kürzer)
+    pub fn truncate(&mut self, len: usize) {
+        self.lines.truncate(len);
+    }
+
+    /// Wie oft bisher neu zerlegt wurde
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+}
+
+/// Ein Farbschema; `dark` wählt die passenden egui-Visuals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntaxTheme {
+    pub name: &'static str,
+    pub dark: bool,
+    colors: &'static [(TokenClass, Color32)],
+}
+
+impl SyntaxTheme {
+    pub const DARK: SyntaxTheme = SyntaxTheme {
+        name: "Dark",
+        dark: true,
+        colors: &[
+            (TokenClass::MoveMnemonic, Color32::from_rgb(86, 156, 214)),
+            (
+                TokenClass::ArithmeticMnemonic,
+                Color32::from_rgb(78, 201, 176),
+            ),
+            (TokenClass::BranchMnemonic, Color32::from_rgb(197, 134, 192)),
+            (TokenClass::JumpMnemonic, Color32::from_rgb(255, 165, 0)),
+            (TokenClass::NopMnemonic, Color32::from_rgb(156, 220, 254)),
+            (TokenClass::OtherMnemonic, Color32::from_rgb(220, 220, 220)),
+            (TokenClass::Register, Color32::from_rgb(156, 220, 254)),
+            (TokenClass::Immediate, Color32::from_rgb(181, 206, 168)),
+            (TokenClass::Label, Color32::from_rgb(255, 215, 0)),
+            (TokenClass::Comment, Color32::from_rgb(106, 153, 85)),
+            (TokenClass::Punctuation, Color32::WHITE),
+            (TokenClass::LineNumber, Color32::GRAY),
+            (TokenClass::CurrentLine, Color32::YELLOW),
+            (
+                TokenClass::ChangedRegister,
+                Color32::from_rgb(255, 140, 105),
+            ),
+            (TokenClass::Address, Color32::WHITE),
+            (TokenClass::MachineCode, Color32::from_rgb(181, 206, 168)),
+            (TokenClass::Binary, Color32::GRAY),
+            (TokenClass::Disassembly, Color32::from_rgb(206, 145, 120)),
+            (TokenClass::Error, Color32::RED),
+        ],
+    };
+
+    pub const LIGHT: SyntaxTheme = SyntaxTheme {
+        name: "Light",
+        dark: false,
+        colors: &[
+            (TokenClass::MoveMnemonic, Color32::from_rgb(0, 80, 170)),
+            (
+                TokenClass::ArithmeticMnemonic,
+                Color32::from_rgb(0, 120, 110),
+            ),
+            (TokenClass::BranchMnemonic, Color32::from_rgb(135, 40, 140)),
+            (TokenClass::JumpMnemonic, Color32::from_rgb(190, 90, 0)),
+            (TokenClass::NopMnemonic, Color32::from_rgb(40, 110, 160)),
+            (TokenClass::OtherMnemonic, Color32::from_rgb(40, 40, 40)),
+            (TokenClass::Register, Color32::from_rgb(0, 95, 150)),
+            (TokenClass::Immediate, Color32::from_rgb(30, 120, 40)),
+            (TokenClass::Label, Color32::from_rgb(150, 100, 0)),
+            (TokenClass::Comment, Color32::from_rgb(80, 130, 60)),
+            (TokenClass::Punctuation, Color32::from_rgb(60, 60, 60)),
+            (TokenClass::LineNumber, Color32::from_rgb(130, 130, 130)),
+            (TokenClass::CurrentLine, Color32::from_rgb(200, 80, 0)),
+            (TokenClass::ChangedRegister, Color32::from_rgb(200, 30, 30)),
+            (TokenClass::Address, Color32::from_rgb(40, 40, 40)),
+            (TokenClass::MachineCode, Color32::from_rgb(30, 120, 40)),
+            (TokenClass::Binary, Color32::from_rgb(120, 120, 120)),
+            (TokenClass::Disassembly, Color32::from_rgb(150, 70, 40)),
+            (TokenClass::Error, Color32::from_rgb(190, 0, 0)),
+        ],
+    };
+
+    pub const PRESETS: [SyntaxTheme; 2] = [SyntaxTheme::DARK, SyntaxTheme::LIGHT];
+
+    /// Preset zu einem gespeicherten Namen
+    pub fn by_name(name: &str) -> Option<SyntaxTheme> {
+        Self::PRESETS
+            .into_iter()
+            .find(|theme| theme.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Farbe einer Token-Klasse; Magenta macht fehlende Tabelleneinträge sofort sichtbar
+    pub fn color(&self, class: TokenClass) -> Color32 {
+        self.lookup(class).unwrap_or(Color32::from_rgb(255, 0, 255))
+    }
+
+    /// Monospace-Text in der Farbe von `class`
+    pub fn text(&self, text: impl Into<String>, class: TokenClass) -> RichText {
+        RichText::new(text).color(self.color(class)).monospace()
+    }
+
+    fn lookup(&self, class: TokenClass) -> Option<Color32> {
+        self.colors
+            .iter()
+            .find(|(token, _)| *token == class)
+            .map(|(_, color)| *color)
+    }
+}
+
+impl Default for SyntaxTheme {
+    fn default() -> Self {
+        SyntaxTheme::DARK
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_class_has_a_color_in_every_preset() {
+        for theme in SyntaxTheme::PRESETS {
+            for class in TokenClass::ALL {
+                assert!(
+                    theme.lookup(class).is_some(),
+                    "{:?} has no color in {}",
+                    class,
+                    theme.name
+                );
+            }
+            assert_eq!(theme.colors.len(), TokenClass::ALL.len(), "{}", theme.name);
+        }
+    }
+
+    #[test]
+    fn test_classification_and_names() {
+        assert_eq!(TokenClass::of_mnemonic("BNE"), TokenClass::BranchMnemonic);
+        assert_eq!(TokenClass::of_mnemonic("DIVU"), TokenClass::OtherMnemonic);
+        assert_eq!(TokenClass::of_operand("#$10"), TokenClass::Immediate);
+        assert_eq!(TokenClass::of_operand("-(A7)"), TokenClass::Register);
+        assert_eq!(TokenClass::of_operand("d3"), TokenClass::Register);
+        assert_eq!(TokenClass::of_operand("DATA"), TokenClass::Label);
+        assert_eq!(SyntaxTheme::by_name("light"), Some(SyntaxTheme::LIGHT));
+        assert_eq!(SyntaxTheme::by_name("Solarized"), None);
+    }
+
+    #[test]
+    fn test_tokenize_line() {
+        let texts = |line: &str| -> Vec<(String, TokenClass)> {
+            tokenize_line(line)
+                .into_iter()
+                .map(|token| (token.text, token.class))
+                .collect()
+        };
+        assert_eq!(
+            texts("  move.l #5, (A1)+ ; Zähler"),
+            vec![
+                ("MOVE.L".to_string(), TokenClass::MoveMnemonic),
+                (" ".to_string(), TokenClass::Punctuation),
+                ("#5".to_string(), TokenClass::Immediate),
+                (", ".to_string(), TokenClass::Punctuation),
+                ("(A1)+".to_string(), TokenClass::Register),
+                ("; Zähler".to_string(), TokenClass::Comment),
+            ]
+        );
+        assert_eq!(texts("NOP").len(), 1);
+        assert!(texts("   ").is_empty());
+        assert_eq!(texts("; nur Kommentar")[0].1, TokenClass::Comment);
+        assert_eq!(texts("LOOP: BRA LOOP")[0].1, TokenClass::Label);
+        assert!(tokenize_line("NOP")[0].strong);
+
+        // Quellspalten trotz normalisierter Abstände
+        let columns: Vec<_> = tokenize_line("\tmove.l  #5,D1 ; x")
+            .into_iter()
+            .map(|token| token.columns)
+            .collect();
+        assert_eq!(columns, [1..7, 9..9, 9..11, 12..12, 12..14, 15..18]);
+    }
+
+    #[test]
+    fn test_token_cache_only_retokenizes_changed_lines() {
+        let mut cache = LineTokenCache::default();
+        for (i, line) in ["NOP", "MOVEQ #1, D0"].iter().enumerate() {
+            cache.tokens(i, line);
+        }
+        assert_eq!(cache.misses(), 2);
+
+        // Unverändert: keine neue Zerlegung
+        assert_eq!(cache.tokens(1, "MOVEQ #1, D0")[0].text, "MOVEQ");
+        assert_eq!(cache.misses(), 2);
+
+        // Geänderte Zeile wird neu zerlegt, die andere nicht
+        assert_eq!(
+            cache.tokens(1, "BRA LOOP")[0].class,
+            TokenClass::BranchMnemonic
+        );
+        cache.tokens(0, "NOP");
+        assert_eq!(cache.misses(), 3);
+
+        // Sprung nach vorn (nur sichtbare Zeilen) und Kürzen
+        assert_eq!(cache.tokens(40_000, "TST D0")[0].text, "TST");
+        cache.truncate(1);
+        cache.tokens(1, "BRA LOOP");
+        assert_eq!(cache.misses(), 5);
+    }
+}
@@ -0,0 +1,318 @@
+// Trace-Ausgabe pro Instruktion, gemeinsam für GUI-Konsole und CLI (--trace)
+
+use crate::address_format::AddressFormatter;
+use crate::cpu::{ExecResult, Registers};
+use crate::disassembler::{disassemble, disassemble_symbolic};
+use crate::program::Program;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceLevel {
+    #[default]
+    Off,
+    Pc,          // nur PC-Wechsel
+    Disassembly, // Adresse + disassemblierte Instruktion
+    Registers,   // zusätzlich geänderte Register
+    Accesses,    // zusätzlich Speicherzugriffe mit Adressberechnung
+}
+
+impl TraceLevel {
+    pub const ALL: [TraceLevel; 5] = [
+        TraceLevel::Off,
+        TraceLevel::Pc,
+        TraceLevel::Disassembly,
+        TraceLevel::Registers,
+        TraceLevel::Accesses,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TraceLevel::Off => "Off",
+            TraceLevel::Pc => "PC only",
+            TraceLevel::Disassembly => "Full disassembly",
+            TraceLevel::Registers => "Full + register deltas",
+            TraceLevel::Accesses => "Full + memory accesses",
+        }
+    }
+
+    /// Ob die CPU dafür Speicherzugriffe aufzeichnen muss
+    pub fn needs_accesses(self) -> bool {
+        self == TraceLevel::Accesses
+    }
+
+    /// Name für die Kommandozeile: off, pc, full, regs, mem
+    pub fn from_name(name: &str) -> Option<TraceLevel> {
+        match name.to_lowercase().as_str() {
+            "off" => Some(TraceLevel::Off),
+            "pc" => Some(TraceLevel::Pc),
+            "full" | "disassembly" => Some(TraceLevel::Disassembly),
+            "regs" | "registers" => Some(TraceLevel::Registers),
+            "mem" | "accesses" => Some(TraceLevel::Accesses),
+            _ => None,
+        }
+    }
+}
+
+/// Formatiert einen ausgeführten Schritt; None bei `TraceLevel::Off`. Operanden und
+/// Sprungziele erscheinen relativ zu den Labels aus `formatter`.
+pub fn format_trace(
+    result: &ExecResult,
+    level: TraceLevel,
+    formatter: &AddressFormatter,
+) -> Option<String> {
+    let mut line = match level {
+        TraceLevel::Off => return None,
+        TraceLevel::Pc => {
+            return Some(format!("PC ${:06X} → ${:06X}", result.pc, result.next_pc()))
+        }
+        TraceLevel::Disassembly | TraceLevel::Registers | TraceLevel::Accesses => {
+            let disassembly = disassemble_symbolic(&result.words, result.pc, formatter);
+            format!("${:06X}  {}", result.pc, disassembly.text)
+        }
+    };
+
+    if matches!(level, TraceLevel::Registers | TraceLevel::Accesses) {
+        let deltas = register_deltas(&result.before, &result.after);
+        if !deltas.is_empty() {
+            let _ = write!(line, "  ; {}", deltas.join(", "));
+        }
+    }
+    // Zugriffe als eigene Spalte: "  | (A0)+ → read long @ ..."
+    if level == TraceLevel::Accesses {
+        for access in &result.accesses {
+            let _ = write!(line, "  | {}", access);
+        }
+    }
+    Some(line)
+}
+
+/// Geänderte Register (ohne PC) als "D0=$00000001"
+fn register_deltas(before: &Registers, after: &Registers) -> Vec<String> {
+    let mut deltas = Vec::new();
+    for (prefix, old, new) in [("D", &before.d, &after.d), ("A", &before.a, &after.a)] {
+        for i in 0..8 {
+            if old[i] != new[i] {
+                deltas.push(format!("{}{}=${:08X}", prefix, i, new[i]));
+            }
+        }
+    }
+    if before.ccr != after.ccr {
+        deltas.push(format!("CCR={}", format_ccr(after.ccr)));
+    }
+    deltas
+}
+
+/// CCR als Flag-Leiste "XNZVC", gelöschte Bits als '-'
+pub fn format_ccr(ccr: u8) -> String {
+    "XNZVC"
+        .chars()
+        .enumerate()
+        .map(|(i, flag)| if ccr & (0x10 >> i) != 0 { flag } else { '-' })
+        .collect()
+}
+
+/// Begründet die Entscheidung eines Bcc mit den Instruktionen, die die entscheidenden
+/// Flags zuletzt geschrieben haben, z.B. "BNE LOOP — not taken (Z=1 from SUBQ at line 14)".
+///
+/// `earlier` sind die davor ausgeführten Schritte, der älteste zuerst. None, wenn
+/// `result` kein Bcc ist.
+pub fn explain_branch(
+    result: &ExecResult,
+    earlier: &[ExecResult],
+    program: Option<&Program>,
+) -> Option<String> {
+    let branch = result.branch?;
+    let target = match program {
+        Some(program) => program.symbolize(branch.target),
+        None => format!("${:06X}", branch.target),
+    };
+    let head = format!("{} {}", branch.mnemonic(), target);
+    let deciding = branch.deciding_flags();
+    if deciding == 0 {
+        return Some(format!("{} — always taken", head));
+    }
+
+    // Flags nach der Instruktion gruppieren, die sie zuletzt geschrieben hat
+    let mut groups: Vec<(Option<usize>, Vec<String>)> = Vec::new();
+    for (name, bit) in [('N', 0x08), ('Z', 0x04), ('V', 0x02), ('C', 0x01)] {
+        if deciding & bit == 0 {
+            continue;
+        }
+        let writer = earlier
+            .iter()
+            .rposition(|step| step.flags_written & bit != 0);
+        let flag = format!("{}={}", name, u8::from(branch.flags_at_decision & bit != 0));
+        match groups.iter_mut().find(|(index, _)| *index == writer) {
+            Some((_, flags)) => flags.push(flag),
+            None => groups.push((writer, vec![flag])),
+        }
+    }
+
+    let reasons: Vec<String> = groups
+        .into_iter()
+        .map(|(writer, flags)| {
+            let source = match writer {
+                Some(index) => {
+                    let step = &earlier[index];
+                    let text = disassemble(&step.words).text;
+                    let mnemonic = text.split([' ', '.']).next().unwrap_or_default();
+                    match program.and_then(|p| p.line_for_address(step.pc)) {
+                        Some(line) => format!("from {} at line {}", mnemonic, line),
+                        None => format!("from {} at ${:06X}", mnemonic, step.pc),
+                    }
+                }
+                None => "not set by any recorded instruction".to_string(),
+            };
+            format!("{} {}", flags.join(", "), source)
+        })
+        .collect();
+
+    let decision = if branch.taken { "taken" } else { "not taken" };
+    Some(format!("{} — {} ({})", head, decision, reasons.join("; ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::{AccessKind, EaDescription, MemAccess};
+    use crate::emulator::Emulator;
+    use crate::isa::Size;
+
+    fn result() -> ExecResult {
+        let before = Registers {
+            pc: 0x1000,
+            ..Default::default()
+        };
+        let mut after = before;
+        after.pc = 0x1002;
+        after.d[3] = 7;
+        ExecResult {
+            pc: 0x1000,
+            words: [0x7607, 0, 0, 0, 0], // MOVEQ #7, D3
+            before,
+            after,
+            cycles: 4,
+            accesses: Vec::new(),
+            flags_written: 0x0C,
+            branch: None,
+            bus_fault: None,
+        }
+    }
+
+    #[test]
+    fn test_trace_levels() {
+        let result = result();
+        assert_eq!(
+            format_trace(&result, TraceLevel::Off, &AddressFormatter::default()),
+            None
+        );
+        assert_eq!(
+            format_trace(&result, TraceLevel::Pc, &AddressFormatter::default()).unwrap(),
+            "PC $001000 → $001002"
+        );
+        assert_eq!(
+            format_trace(
+                &result,
+                TraceLevel::Disassembly,
+                &AddressFormatter::default()
+            )
+            .unwrap(),
+            "$001000  MOVEQ #7, D3"
+        );
+        assert_eq!(
+            format_trace(&result, TraceLevel::Registers, &AddressFormatter::default()).unwrap(),
+            "$001000  MOVEQ #7, D3  ; D3=$00000007"
+        );
+    }
+
+    #[test]
+    fn test_ccr_delta_and_names() {
+        let mut result = result();
+        result.after.ccr = 0x04 | 0x01;
+        assert!(
+            format_trace(&result, TraceLevel::Registers, &AddressFormatter::default())
+                .unwrap()
+                .ends_with("D3=$00000007, CCR=--Z-C")
+        );
+
+        result.accesses.push(MemAccess {
+            addr: 0x0800,
+            size: Size::Byte,
+            kind: AccessKind::Write,
+            value: 7,
+            ea_description: EaDescription::Indirect { reg: 2 },
+        });
+        assert!(
+            format_trace(&result, TraceLevel::Accesses, &AddressFormatter::default())
+                .unwrap()
+                .ends_with("CCR=--Z-C  | (A2) → write byte @ $000800 = $07")
+        );
+
+        assert_eq!(TraceLevel::from_name("regs"), Some(TraceLevel::Registers));
+        assert_eq!(TraceLevel::from_name("FULL"), Some(TraceLevel::Disassembly));
+        assert_eq!(TraceLevel::from_name("mem"), Some(TraceLevel::Accesses));
+        assert_eq!(TraceLevel::from_name("verbose"), None);
+    }
+
+    /// Führt `source` bis SIMHALT aus und erklärt alle Bcc-Entscheidungen
+    fn explain_all(source: &str) -> Vec<String> {
+        let mut emulator = Emulator::new();
+        emulator.load_source(source).unwrap();
+        let mut history = Vec::new();
+        let mut explanations = Vec::new();
+        while !emulator.cpu().is_halted() {
+            let result = emulator.step();
+            explanations.extend(explain_branch(&result, &history, emulator.program()));
+            history.push(result);
+        }
+        explanations
+    }
+
+    #[test]
+    fn test_explain_zero_flag() {
+        let source = "MOVE.L #2, D1\nLOOP: SUBQ.L #1, D1\nBNE LOOP\nSIMHALT";
+        assert_eq!(
+            explain_all(source),
+            vec![
+                "BNE LOOP — taken (Z=0 from SUBQ at line 2)",
+                "BNE LOOP — not taken (Z=1 from SUBQ at line 2)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_negative_flag() {
+        let source = "MOVEQ #1, D0\nSUBQ.L #5, D0\nBPL DONE\nBMI DONE\nNOP\nDONE: SIMHALT";
+        assert_eq!(
+            explain_all(source),
+            vec![
+                "BPL DONE — not taken (N=1 from SUBQ at line 2)",
+                "BMI DONE — taken (N=1 from SUBQ at line 2)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_signed_conditions_across_instructions() {
+        // CMP setzt NZVC, MOVEQ danach ebenfalls (V und C gelöscht): alles stammt vom MOVEQ
+        let source = "MOVEQ #5, D1\nMOVEQ #3, D0\nCMP.L D0, D1\nMOVEQ #-1, D2\n\
+                      BGT DONE\nBLT DONE\nNOP\nDONE: SIMHALT";
+        assert_eq!(
+            explain_all(source),
+            vec![
+                "BGT DONE — not taken (N=1, Z=0, V=0 from MOVEQ at line 4)",
+                "BLT DONE — taken (N=1, V=0 from MOVEQ at line 4)",
+            ]
+        );
+
+        // Ohne vorherige Instruktion und für BRA
+        let explanations = explain_all("BGE DONE\nDONE: BRA END\nEND: SIMHALT");
+        assert_eq!(
+            explanations,
+            vec![
+                "BGE DONE — taken (N=0, V=0 not set by any recorded instruction)",
+                "BRA END — always taken",
+            ]
+        );
+    }
+}
@@ -0,0 +1,402 @@
+// Serielle Schnittstelle (UART) mit Baudraten-Timing im CPU-Takt
+// Ein Byte braucht auf der Leitung 10 Bit (Start, 8 Daten, Stop); bei 8 MHz CPU-Takt und
+// gegebener Baudrate sind das `cycles_per_byte` Zyklen. Ein nach DATA geschriebenes Byte
+// wird so lange hinausgeschoben, erst danach ist TX_EMPTY wieder gesetzt und das Byte beim
+// Host. In der Gegenrichtung kommen die Bytes des Hosts im selben Abstand an; holt das
+// Programm ein Byte nicht ab, bevor das nächste fertig ist, geht das neue verloren (OVERRUN).
+// Der Host (CLI oder GUI) ist über zwei Kanäle angebunden, siehe `UartHost`.
+// Alle Register sind Bytes an geraden Adressen, damit MOVE.B (xxx).W sie erreicht.
+
+use crate::bus::{Device, IrqRequest};
+use crate::cpu::{AccessKind, MemAccess, CPU_CLOCK_HZ};
+use crate::memory::Memory;
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Registerabstände zur Basisadresse
+pub const REG_DATA: u32 = 0x00;
+pub const REG_STATUS: u32 = 0x02;
+pub const REG_CONTROL: u32 = 0x04;
+const REGISTER_BYTES: u32 = 0x06;
+
+/// STATUS-Bits. RX_FULL liegt in Bit 7, damit MOVE.B/TST.B es direkt ins N-Flag bringt.
+/// Lesen von DATA löscht RX_FULL, ein Schreibzugriff auf STATUS löscht OVERRUN.
+pub const STATUS_RX_FULL: u8 = 0x80;
+pub const STATUS_TX_EMPTY: u8 = 0x40;
+pub const STATUS_OVERRUN: u8 = 0x20;
+
+/// CONTROL-Bits: Interrupt bei empfangenem Byte bzw. wenn der Sender wieder frei ist
+pub const CONTROL_RX_IRQ: u8 = 0x01;
+pub const CONTROL_TX_IRQ: u8 = 0x02;
+
+/// Startbit, 8 Datenbits, Stopbit
+const BITS_PER_BYTE: u32 = 10;
+
+/// Host-Seite der Schnittstelle: Bytes zum Programm schicken und seine Ausgabe abholen
+pub struct UartHost {
+    to_guest: Sender<u8>,
+    from_guest: Receiver<u8>,
+}
+
+impl UartHost {
+    /// Reiht Bytes für den Empfänger ein; sie kommen im Takt der Baudrate an
+    pub fn send(&self, bytes: &[u8]) {
+        for &byte in bytes {
+            // Gerät entfernt: es gibt niemanden mehr, der die Bytes lesen könnte
+            let _ = self.to_guest.send(byte);
+        }
+    }
+
+    /// Eigener Sender, z.B. für einen Thread, der stdin liest
+    pub fn sender(&self) -> Sender<u8> {
+        self.to_guest.clone()
+    }
+
+    /// Alle seit dem letzten Aufruf vollständig gesendeten Bytes des Programms
+    pub fn receive(&self) -> Vec<u8> {
+        self.from_guest.try_iter().collect()
+    }
+}
+
+pub struct Uart {
+    base: u32,
+    baud: u32,
+    cycles_per_byte: u32,
+    level: u8,
+    to_host: Sender<u8>,
+    from_host: Receiver<u8>,
+    tx: Option<(u8, u32)>, // Byte im Sende-Schieberegister, restliche Zyklen
+    rx: Option<(u8, u32)>, // Byte, das gerade ankommt
+    rx_data: u8,           // zuletzt empfangenes Byte, steht in DATA
+    status: u8,
+    irq: bool, // Ereignis gemeldet, Interrupt beim Tick anfordern
+}
+
+impl Uart {
+    /// Standard-Basisadresse, hinter Disk und Zufallsgenerator
+    pub const DEFAULT_BASE: u32 = 0xE200;
+    pub const DEFAULT_BAUD: u32 = 9600;
+
+    /// UART mit `baud` Bit pro Sekunde und die dazugehörige Host-Seite. Die Zyklen pro Byte
+    /// gelten für `CPU_CLOCK_HZ`, bis der Bus einen anderen Takt meldet.
+    pub fn new(base: u32, baud: u32) -> (Self, UartHost) {
+        let (to_host, from_guest) = mpsc::channel();
+        let (to_guest, from_host) = mpsc::channel();
+        let uart = Uart {
+            base,
+            baud: baud.max(1),
+            cycles_per_byte: Self::cycles_per_byte_at(CPU_CLOCK_HZ, baud.max(1)),
+            level: 4,
+            to_host,
+            from_host,
+            tx: None,
+            rx: None,
+            rx_data: 0,
+            status: STATUS_TX_EMPTY,
+            irq: false,
+        };
+        (
+            uart,
+            UartHost {
+                to_guest,
+                from_guest,
+            },
+        )
+    }
+
+    fn cycles_per_byte_at(clock_hz: u32, baud: u32) -> u32 {
+        let cycles = u64::from(clock_hz) * u64::from(BITS_PER_BYTE) / u64::from(baud);
+        u32::try_from(cycles).unwrap_or(u32::MAX).max(1)
+    }
+
+    pub fn cycles_per_byte(&self) -> u32 {
+        self.cycles_per_byte
+    }
+
+    pub fn set_irq_level(&mut self, level: u8) {
+        self.level = level;
+    }
+
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    fn hits(&self, access: &MemAccess, offset: u32) -> bool {
+        let register = self.base + offset;
+        (access.addr..access.addr + access.size.bytes()).contains(&register)
+    }
+
+    fn control(&self, memory: &Memory) -> u8 {
+        memory.read_byte(self.base + REG_CONTROL)
+    }
+
+    /// STATUS nur bei Änderung schreiben: jeder Schreibzugriff zählt für die
+    /// Leerlauf-Erkennung als Aktivität
+    fn publish_status(&self, memory: &mut Memory) {
+        let register = self.base + REG_STATUS;
+        if memory.read_byte(register) != self.status {
+            memory.write_byte(register, self.status);
+        }
+    }
+
+    /// Ein Byte ist vollständig angekommen
+    fn deliver(&mut self, byte: u8, memory: &mut Memory) {
+        if self.status & STATUS_RX_FULL != 0 {
+            // Das alte Byte bleibt, das neue ist verloren
+            self.status |= STATUS_OVERRUN;
+            return;
+        }
+        self.rx_data = byte;
+        memory.write_byte(self.base + REG_DATA, byte);
+        self.status |= STATUS_RX_FULL;
+        if self.control(memory) & CONTROL_RX_IRQ != 0 {
+            self.irq = true;
+        }
+    }
+
+    /// Empfänger: nimmt Bytes vom Host nacheinander an, jedes nach `cycles_per_byte`
+    fn receive(&mut self, cycles: u32, memory: &mut Memory) {
+        let mut budget = cycles;
+        loop {
+            let (byte, remaining) = match self.rx {
+                Some(shifting) => shifting,
+                None => match self.from_host.try_recv() {
+                    Ok(byte) => (byte, self.cycles_per_byte),
+                    Err(_) => break,
+                },
+            };
+            if remaining > budget {
+                self.rx = Some((byte, remaining - budget));
+                break;
+            }
+            budget -= remaining;
+            self.rx = None;
+            self.deliver(byte, memory);
+        }
+    }
+}
+
+impl Device for Uart {
+    fn name(&self) -> &str {
+        "uart"
+    }
+
+    fn registers(&self) -> Option<Range<u32>> {
+        Some(self.base..self.base + REGISTER_BYTES)
+    }
+
+    fn access(&mut self, access: &MemAccess, memory: &mut Memory) {
+        match access.kind {
+            AccessKind::Write if self.hits(access, REG_DATA) => {
+                // Sender belegt: das Byte geht verloren, wie bei einem echten Baustein
+                // ohne Puffer; das Programm muss vorher auf TX_EMPTY warten
+                if self.tx.is_none() {
+                    let byte = memory.read_byte(self.base + REG_DATA);
+                    self.tx = Some((byte, self.cycles_per_byte));
+                    self.status &= !STATUS_TX_EMPTY;
+                }
+                // DATA zeigt beim Lesen weiter das empfangene Byte
+                memory.write_byte(self.base + REG_DATA, self.rx_data);
+            }
+            AccessKind::Read if self.hits(access, REG_DATA) => {
+                self.status &= !STATUS_RX_FULL;
+            }
+            AccessKind::Write if self.hits(access, REG_STATUS) => {
+                self.status &= !STATUS_OVERRUN;
+            }
+            _ => {}
+        }
+        // Auch nach einem Schreibzugriff auf STATUS gilt der Zustand des Geräts
+        self.publish_status(memory);
+    }
+
+    fn busy(&self) -> bool {
+        self.tx.is_some() || self.rx.is_some()
+    }
+
+    /// Ein Byte, das schon unterwegs ist, behält seine restlichen Zyklen
+    fn set_clock_hz(&mut self, hz: u32) {
+        self.cycles_per_byte = Self::cycles_per_byte_at(hz, self.baud);
+    }
+
+    fn tick(&mut self, cycles: u32, memory: &mut Memory) -> Option<IrqRequest> {
+        if let Some((byte, remaining)) = self.tx {
+            if remaining > cycles {
+                self.tx = Some((byte, remaining - cycles));
+            } else {
+                self.tx = None;
+                let _ = self.to_host.send(byte); // Host weg: Ausgabe verwerfen
+                self.status |= STATUS_TX_EMPTY;
+                if self.control(memory) & CONTROL_TX_IRQ != 0 {
+                    self.irq = true;
+                }
+            }
+        }
+        self.receive(cycles, memory);
+        self.publish_status(memory);
+
+        if std::mem::take(&mut self.irq) {
+            return Some(IrqRequest::autovector(self.level));
+        }
+        None
+    }
+
+    /// Schieberegister leeren; schon vom Host geschickte, noch nicht begonnene Bytes bleiben
+    fn reset(&mut self, memory: &mut Memory) {
+        self.tx = None;
+        self.rx = None;
+        self.rx_data = 0;
+        self.status = STATUS_TX_EMPTY;
+        self.irq = false;
+        memory.write_byte(self.base + REG_DATA, 0);
+        memory.write_byte(self.base + REG_STATUS, self.status);
+        memory.write_byte(self.base + REG_CONTROL, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::EaDescription;
+    use crate::isa::Size;
+
+    const BASE: u32 = Uart::DEFAULT_BASE;
+    /// 80000 Baud: 1000 Zyklen pro Byte
+    const BAUD: u32 = 80_000;
+
+    fn setup() -> (Uart, UartHost, Memory) {
+        let (mut uart, host) = Uart::new(BASE, BAUD);
+        let mut memory = Memory::new();
+        uart.reset(&mut memory);
+        (uart, host, memory)
+    }
+
+    fn access(uart: &mut Uart, memory: &mut Memory, kind: AccessKind, offset: u32) {
+        let access = MemAccess {
+            addr: BASE + offset,
+            size: Size::Byte,
+            kind,
+            value: 0,
+            ea_description: EaDescription::Indirect { reg: 0 },
+        };
+        uart.access(&access, memory);
+    }
+
+    /// Wie die CPU: erst der Speicherzugriff, danach meldet der Bus ihn dem Gerät
+    fn write(uart: &mut Uart, memory: &mut Memory, offset: u32, value: u8) {
+        memory.write_byte(BASE + offset, value);
+        access(uart, memory, AccessKind::Write, offset);
+    }
+
+    fn read(uart: &mut Uart, memory: &mut Memory, offset: u32) -> u8 {
+        let value = memory.read_byte(BASE + offset);
+        access(uart, memory, AccessKind::Read, offset);
+        value
+    }
+
+    fn status(memory: &Memory) -> u8 {
+        memory.read_byte(BASE + REG_STATUS)
+    }
+
+    #[test]
+    fn test_transmit_takes_one_byte_time() {
+        let (mut uart, host, mut memory) = setup();
+        assert_eq!(uart.cycles_per_byte(), 1000);
+        assert_eq!(status(&memory), STATUS_TX_EMPTY);
+
+        write(&mut uart, &mut memory, REG_DATA, b'H');
+        assert_eq!(status(&memory), 0);
+        // Zweites Byte bei belegtem Sender geht verloren
+        write(&mut uart, &mut memory, REG_DATA, b'X');
+        assert_eq!(
+            memory.read_byte(BASE + REG_DATA),
+            0,
+            "DATA shows the receiver"
+        );
+
+        uart.tick(999, &mut memory);
+        assert_eq!(status(&memory), 0);
+        assert!(host.receive().is_empty());
+        uart.tick(1, &mut memory);
+        assert_eq!(status(&memory), STATUS_TX_EMPTY);
+        assert_eq!(host.receive(), b"H");
+
+        write(&mut uart, &mut memory, REG_DATA, b'i');
+        uart.tick(1000, &mut memory);
+        assert_eq!(host.receive(), b"i");
+    }
+
+    #[test]
+    fn test_unchanged_status_is_not_rewritten() {
+        let (mut uart, host, mut memory) = setup();
+        let writes = memory.write_count();
+        uart.tick(5000, &mut memory);
+        assert_eq!(memory.write_count(), writes);
+        assert!(!uart.busy());
+
+        // Ein ankommendes Byte hält das Gerät beschäftigt, geschrieben wird erst bei RX_FULL
+        host.send(b"a");
+        uart.tick(500, &mut memory);
+        assert!(uart.busy());
+        assert_eq!(memory.write_count(), writes);
+        uart.tick(500, &mut memory);
+        assert!(!uart.busy());
+        assert_eq!(status(&memory), STATUS_TX_EMPTY | STATUS_RX_FULL);
+        assert!(memory.write_count() > writes);
+    }
+
+    #[test]
+    fn test_receive_paces_bytes_and_flags_overrun() {
+        let (mut uart, host, mut memory) = setup();
+        host.send(b"abc");
+
+        // Byte n ist nach (n + 1) * 1000 Zyklen da
+        uart.tick(600, &mut memory);
+        assert_eq!(status(&memory) & STATUS_RX_FULL, 0);
+        uart.tick(400, &mut memory);
+        assert_eq!(status(&memory), STATUS_TX_EMPTY | STATUS_RX_FULL);
+        assert_eq!(read(&mut uart, &mut memory, REG_DATA), b'a');
+        assert_eq!(status(&memory), STATUS_TX_EMPTY);
+
+        // 'b' bei 2000, 'c' bei 3000 kommt an, während 'b' noch nicht gelesen ist
+        uart.tick(1999, &mut memory);
+        assert_eq!(status(&memory) & STATUS_OVERRUN, 0);
+        uart.tick(1, &mut memory);
+        assert_eq!(
+            status(&memory),
+            STATUS_TX_EMPTY | STATUS_RX_FULL | STATUS_OVERRUN
+        );
+        assert_eq!(read(&mut uart, &mut memory, REG_DATA), b'b');
+        assert_eq!(status(&memory), STATUS_TX_EMPTY | STATUS_OVERRUN);
+
+        // Quittieren löscht OVERRUN; der Wert im Speicher ist wieder der des Geräts
+        write(&mut uart, &mut memory, REG_STATUS, 0xFF);
+        assert_eq!(status(&memory), STATUS_TX_EMPTY);
+    }
+
+    #[test]
+    fn test_interrupts_when_enabled() {
+        let (mut uart, host, mut memory) = setup();
+        uart.set_irq_level(5);
+        host.send(b"x");
+        assert_eq!(uart.tick(1000, &mut memory), None, "interrupts disabled");
+        read(&mut uart, &mut memory, REG_DATA);
+
+        write(
+            &mut uart,
+            &mut memory,
+            REG_CONTROL,
+            CONTROL_RX_IRQ | CONTROL_TX_IRQ,
+        );
+        host.send(b"y");
+        assert_eq!(
+            uart.tick(1000, &mut memory),
+            Some(IrqRequest::autovector(5))
+        );
+        assert_eq!(uart.tick(1000, &mut memory), None, "only once");
+
+        write(&mut uart, &mut memory, REG_DATA, b'z');
+        assert_eq!(uart.tick(999, &mut memory), None);
+        assert_eq!(uart.tick(1, &mut memory), Some(IrqRequest::autovector(5)));
+    }
+}
@@ -0,0 +1,129 @@
+// Robustheit des Assemblers: beliebiger Quelltext darf nur Fehler liefern, nie paniken
+// Zufallsquelltexte mit festem Seed plus ein Korpus von Eingaben, die früher abgestürzt sind
+
+use mc68000::{Assembler, Emulator};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Xorshift-Generator mit festem Seed, damit Fehlschläge reproduzierbar sind
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+// Bausteine, aus denen zufällige Zeilen zusammengesetzt werden
+#[rustfmt::skip]
+const TOKENS: &[&str] = &[
+    "MOVE", "MOVE.L", "MOVE.W", "MOVE.B", "MOVEQ", "MOVEA", "MOVEA.L", "ADD", "SUB", "SUBQ.L",
+    "ADDQ", "CMP", "CMPI.L", "CMPA", "MULS", "TST", "ASL", "DBRA", "BRA", "BNE", "BEQ.S", "JMP",
+    "NOP", "SIMHALT", "ORG", "END", "DC.B", "DC.W", "DC.L", "DS.W", "DS", "DC", "EQU",
+    "SECTION", "XDEF", "XREF", "EVEN", "OPT", "#", "#$", "#0x", "#-", "#$FFFFFFFFFF", "$", "0x",
+    "%", "D", "A", "D0", "D8", "A7", "SP", "(", ")", "(A0)", "(A0)+", "-(A1)", "4(A2)", "(A",
+    "-(", ")+", ",", ", ", ":", "::", ";", "*", "'", "\"", "+", "-", "LABEL", "LABEL:", "1",
+    "128", "-129", "65536", "$1000", "$FFFFFE", "$FFFFFF", "$7FFFFFFFFFFFFFFF", "$80000000",
+    "ä", "ß", "→", "🦀", "\t", " ", "  ", ".", ".L", ".X",
+];
+
+fn random_source(rng: &mut Rng) -> String {
+    let mut source = String::new();
+    for _ in 0..1 + rng.below(12) {
+        for _ in 0..rng.below(7) {
+            if rng.below(10) == 0 {
+                // Beliebiges Unicode-Zeichen
+                let c = char::from_u32(rng.next() as u32 % 0x11_0000).unwrap_or('\u{FFFD}');
+                source.push(c);
+            } else {
+                source.push_str(TOKENS[rng.below(TOKENS.len())]);
+            }
+            if rng.below(3) == 0 {
+                source.push(' ');
+            }
+        }
+        source.push('\n');
+    }
+    source
+}
+
+#[test]
+fn test_random_sources_never_panic() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    let mut assembler = Assembler::new();
+    for _ in 0..5000 {
+        let source = random_source(&mut rng);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let _ = assembler.assemble_source(&source);
+        }));
+        assert!(result.is_ok(), "assembler panicked on:\n{}", source);
+    }
+}
+
+/// Eingaben, die Assembler oder Laden einmal zum Absturz gebracht haben (oder es leicht
+/// könnten): leere Präfixe, Überläufe, Adressen am Ende des 24-Bit-Raums, Nicht-ASCII
+const CORPUS: &[&str] = &[
+    "MOVEQ #, D0",
+    "MOVE.L #",
+    "CMPI.L #$, D0",
+    "#",
+    "ORG $7FFFFFFFFFFFFFFF+$7FFFFFFFFFFFFFFF",
+    "ORG -$7FFFFFFFFFFFFFFF-$7FFFFFFFFFFFFFFF",
+    "FAR EQU $80000000\nBRA FAR",
+    "FAR EQU $7FFFFFFF\nBRA FAR",
+    "ORG $FFFFFE\nNOP\nNOP",
+    "ORG $FFFFFF\nDC.L 1",
+    "ORG $FFFFFF\nDS.L $FFFFFF\nDS.L $FFFFFF\nNOP",
+    "DS.L $FFFFFF\nDS.L $FFFFFF\nDS.L $FFFFFF\nDS.L $FFFFFF\nDS.L $FFFFFF",
+    "DC.B ä",
+    "MOVEA.L #, A0",
+    "JMP $",
+    "JMP (",
+    "MOVE.L ä, D0",
+    "DBRA D0, 🦀",
+    ":",
+    "LABEL: EQU",
+    "SECTION ,",
+    "END $",
+    "DC.W %",
+    "SUBQ.L #ä, D1",
+    "ORG $FFFFFC\nLOOP: BRA LOOP",
+];
+
+#[test]
+fn test_corpus_never_panics() {
+    let mut emulator = Emulator::new();
+    for source in CORPUS {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let _ = emulator.load_source(source);
+            emulator.run(10);
+        }));
+        assert!(result.is_ok(), "panicked on {:?}", source);
+    }
+}
+
+#[test]
+fn test_corpus_errors_are_reported() {
+    let mut assembler = Assembler::new();
+    let errors = assembler
+        .assemble_source("ORG $7FFFFFFFFFFFFFFF+$7FFFFFFFFFFFFFFF")
+        .unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "line 1: ORG: $7FFFFFFFFFFFFFFF+$7FFFFFFFFFFFFFFF overflows"
+    );
+
+    let errors = assembler
+        .assemble_source("ORG $FFFFFE\nNOP\nNOP")
+        .unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "line 3: 2 bytes at $1000000 do not fit into the 24-bit address space"
+    );
+}
@@ -0,0 +1,127 @@
+// Aufrufe des Kommandozeilenprogramms mc68000
+use std::process::Command;
+
+fn mc68000(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_mc68000"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_ranges_past_address_space_are_rejected() {
+    for option in [
+        "--hash=$FFFFFF:$10",
+        "--rom=$F00000:$200000",
+        "--unmap=0:$1000001",
+    ] {
+        let output = mc68000(&[option, "a1.asm"]);
+        assert_eq!(output.status.code(), Some(2), "{}", option);
+        assert!(
+            String::from_utf8_lossy(&output.stderr).starts_with("invalid range"),
+            "{}",
+            option
+        );
+    }
+
+    // Bis genau zum Ende des Adressraums ist erlaubt
+    let output = mc68000(&["--hash=$FFFF00:$100", "a1.asm"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("$FFFF00:$100  CRC32"));
+}
+
+#[test]
+fn test_strictness_and_profiler_are_separate_options() {
+    // --profile ist nur noch der Profiler, die Prüfprofile kommen über --strictness
+    let output = mc68000(&["--strictness", "fast", "--profile", "a1.asm"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("  total"));
+
+    let output = mc68000(&["--strictness=teaching", "a1.asm"]);
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("  total"));
+
+    let output = mc68000(&["--strictness=paranoid", "a1.asm"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("unknown strictness profile 'paranoid'"));
+}
+
+#[test]
+fn test_printed_rng_seed_replays_run() {
+    // Ohne --rng-seed kommt der Seed aus der Uhr und steht auf stderr; mit ihm wiederholt
+    // sich der Lauf: gleiche Würfe ($2000, 10 Bytes) und gleiche Register
+    let first = mc68000(&["--hash=$2000:10", "dice.asm"]);
+    assert!(first.status.success());
+    let stderr = String::from_utf8_lossy(&first.stderr);
+    let seed = stderr
+        .split_once("(repeat with --rng-seed=")
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(seed, _)| seed.to_string())
+        .unwrap_or_else(|| panic!("no seed in {:?}", stderr));
+
+    assert!(String::from_utf8_lossy(&first.stdout).contains("$002000:$A  CRC32"));
+
+    let replay = mc68000(&[
+        &format!("--rng-seed={}", seed),
+        "--hash=$2000:10",
+        "dice.asm",
+    ]);
+    assert!(replay.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&replay.stdout),
+        String::from_utf8_lossy(&first.stdout)
+    );
+    // Mit festem Seed gibt es nichts zu melden
+    assert!(!String::from_utf8_lossy(&replay.stderr).contains("rng seed"));
+}
+
+#[test]
+fn test_invalid_fill_byte_is_named() {
+    for fill in ["$1FF", "256", "-1", "x"] {
+        let output = mc68000(&["build", "--fill", fill]);
+        assert_eq!(output.status.code(), Some(2));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.starts_with("invalid fill byte"), "{}", stderr);
+    }
+}
+
+#[test]
+fn test_run_record_replays_run() {
+    // --record hält den Seed aus der Uhr in einer Datei fest, --replay wiederholt den Lauf
+    let path = std::env::temp_dir().join(format!("mc68000-cli-record-{}", std::process::id()));
+    let path = path.to_str().unwrap();
+    let first = mc68000(&["--record", path, "--hash=$2000:10", "dice.asm"]);
+    assert!(first.status.success());
+    let record = std::fs::read_to_string(path).unwrap();
+    assert!(record.starts_with("rng_seed = "), "{}", record);
+
+    let replay = mc68000(&["--replay", path, "--hash=$2000:10", "dice.asm"]);
+    std::fs::remove_file(path).unwrap();
+    assert!(replay.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&replay.stdout),
+        String::from_utf8_lossy(&first.stdout)
+    );
+}
+
+#[test]
+fn test_batch_prints_only_the_summary() {
+    let directory = std::env::temp_dir().join(format!("mc68000-cli-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("pass.s68"), "MOVEQ #42, D0\nSIMHALT\n").unwrap();
+    std::fs::write(directory.join("pass.expect"), "[registers]\nD0 = 42\n").unwrap();
+
+    let output = mc68000(&["batch", directory.to_str().unwrap()]);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("PC:"), "{}", stdout);
+    assert!(
+        stdout.contains("1 programs: 1 passed, 0 failed"),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,133 @@
+// Durchsatz der beiden Wege, Speicher mit einem Leser in einem anderen Thread zu teilen,
+// gemessen an einer Schleife mit 1M Durchläufen. Läuft nur auf Wunsch, optimiert:
+//     cargo test --release --test concurrency_bench -- --ignored --nocapture
+//
+// - Message Passing (gewählt, `Controller::subscribe`): die CPU arbeitet auf `&mut Memory`,
+//   nach jedem Abschnitt gehen Kopien der Dirty-Seiten an den Leser
+// - Geteilter Speicher: `Arc<RwLock<Memory>>`, die CPU nimmt pro Instruktion einen Write-Lock
+
+use mc68000::{cpu, Assembler, Memory, MemoryPatch, CPU};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LOOP: &str = "
+            ORG     $1000
+START:      MOVEA.L #COUNTER, A0
+            MOVE.L  #1000000, D0
+LOOP:       MOVE.L  D0, (A0)
+            SUBQ.L  #1, D0
+            BNE     LOOP
+            SIMHALT
+COUNTER:    DS.L    1
+";
+
+/// Instruktionen pro Abschnitt, etwa ein Frame der GUI
+const SLICE: u32 = 10_000;
+/// Bester von so vielen Läufen, gegen Rauschen durch andere Prozesse
+const RUNS: usize = 5;
+
+fn load() -> (CPU, Memory) {
+    cpu::set_console_echo(false);
+    let program = Assembler::new().assemble_source(LOOP).unwrap();
+    let mut memory = Memory::new();
+    for (address, word) in &program.image {
+        memory.write_word(*address, *word);
+    }
+    let mut cpu = CPU::new();
+    cpu.set_pc(program.symbols["START"]);
+    (cpu, memory)
+}
+
+fn best_of(run: fn() -> Duration) -> Duration {
+    (0..RUNS).map(|_| run()).min().unwrap()
+}
+
+fn single_threaded() -> Duration {
+    let (mut cpu, mut memory) = load();
+    let start = Instant::now();
+    while !cpu.is_halted() {
+        cpu.execute_instruction(&mut memory);
+    }
+    start.elapsed()
+}
+
+fn message_passing() -> Duration {
+    let (mut cpu, mut memory) = load();
+    let (sender, receiver) = channel::<Vec<MemoryPatch>>();
+    let reader = thread::spawn(move || {
+        let mut mirror = Memory::new();
+        for patches in receiver {
+            patches.iter().for_each(|patch| mirror.apply_patch(patch));
+        }
+        mirror
+    });
+
+    let start = Instant::now();
+    while !cpu.is_halted() {
+        for _ in 0..SLICE {
+            if cpu.is_halted() {
+                break;
+            }
+            cpu.execute_instruction(&mut memory);
+        }
+        let patches = memory
+            .take_dirty_ranges()
+            .into_iter()
+            .map(|range| memory.copy_range(range))
+            .collect();
+        sender.send(patches).unwrap();
+    }
+    let elapsed = start.elapsed();
+    drop(sender);
+    let mirror = reader.join().unwrap();
+    assert_eq!(mirror.read_long(0x1000), memory.read_long(0x1000));
+    elapsed
+}
+
+fn shared_lock() -> Duration {
+    let (mut cpu, memory) = load();
+    let memory = Arc::new(RwLock::new(memory));
+    let done = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let memory = Arc::clone(&memory);
+        let done = Arc::clone(&done);
+        thread::spawn(move || {
+            let mut reads = 0u64;
+            while !done.load(Ordering::Relaxed) {
+                let _ = memory.read().unwrap().read_word(0x1000);
+                reads += 1;
+            }
+            reads
+        })
+    };
+
+    let start = Instant::now();
+    while !cpu.is_halted() {
+        cpu.execute_instruction(&mut memory.write().unwrap());
+    }
+    let elapsed = start.elapsed();
+    done.store(true, Ordering::Relaxed);
+    assert!(reader.join().unwrap() > 0);
+    elapsed
+}
+
+#[test]
+#[ignore = "performance, run with --release -- --ignored"]
+fn test_message_passing_keeps_throughput() {
+    let plain = best_of(single_threaded);
+    let messages = best_of(message_passing);
+    let locked = best_of(shared_lock);
+    let share = |elapsed: Duration| plain.as_secs_f64() / elapsed.as_secs_f64() * 100.0;
+    println!("single-threaded  {:?}", plain);
+    println!("message passing  {:?} ({:.0}%)", messages, share(messages));
+    println!("RwLock per step  {:?} ({:.0}%)", locked, share(locked));
+
+    assert!(
+        share(messages) >= 90.0,
+        "message passing at {:.0}%",
+        share(messages)
+    );
+}
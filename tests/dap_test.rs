@@ -0,0 +1,259 @@
+// Debugger-Protokoll: ein geskripteter Client spricht über TCP mit dem Adapter
+use mc68000::debugger::json::Json;
+use mc68000::debugger::protocol::{Command, Request, REGISTERS_REFERENCE};
+use mc68000::debugger::transport::{self, StreamTransport, Transport};
+use mc68000::debugger::{serve, Adapter};
+use std::collections::VecDeque;
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+const PROGRAM: &str = "            ORG     $1000
+START:      MOVEQ   #7, D0
+            ADDQ.L  #3, D0
+            MOVE.L  D0, D1
+            SIMHALT
+";
+
+struct Client {
+    transport: StreamTransport<BufReader<TcpStream>, TcpStream>,
+    seq: i64,
+    /// Nachrichten, die schon gelesen, aber noch nicht abgefragt wurden
+    pending: VecDeque<Json>,
+}
+
+impl Client {
+    fn request(&mut self, command: Command) -> Json {
+        self.seq += 1;
+        let request = Request {
+            seq: self.seq,
+            command,
+        };
+        self.transport.send(&request.to_json().to_string()).unwrap();
+        self.response(self.seq)
+    }
+
+    fn next_message(&mut self) -> Json {
+        let text = self.transport.receive().unwrap().expect("adapter closed");
+        Json::parse(&text).unwrap()
+    }
+
+    fn response(&mut self, seq: i64) -> Json {
+        loop {
+            let message = self.next_message();
+            if message.get("request_seq").and_then(Json::as_i64) == Some(seq) {
+                return message;
+            }
+            self.pending.push_back(message);
+        }
+    }
+
+    /// Nächstes Event `name`; andere Events (z.B. output) werden übersprungen
+    fn event(&mut self, name: &str) -> Json {
+        let is_event = |message: &Json| message.get("event").and_then(Json::as_str) == Some(name);
+        if let Some(index) = self.pending.iter().position(is_event) {
+            return self.pending.remove(index).unwrap();
+        }
+        loop {
+            let message = self.next_message();
+            if is_event(&message) {
+                return message;
+            }
+        }
+    }
+}
+
+fn body(message: &Json) -> &Json {
+    assert_eq!(
+        message.get("success").and_then(Json::as_bool),
+        Some(true),
+        "{}",
+        message
+    );
+    message.get("body").unwrap_or(&Json::Null)
+}
+
+fn event_body(event: &Json) -> &Json {
+    event.get("body").unwrap()
+}
+
+fn str_field<'a>(json: &'a Json, key: &str) -> &'a str {
+    json.get(key).and_then(Json::as_str).unwrap()
+}
+
+fn register(variables: &Json, name: &str) -> String {
+    variables
+        .get("variables")
+        .and_then(Json::as_array)
+        .unwrap()
+        .iter()
+        .find(|variable| str_field(variable, "name") == name)
+        .map(|variable| str_field(variable, "value").to_string())
+        .unwrap()
+}
+
+#[test]
+fn test_scripted_session_over_tcp() {
+    let path = std::env::temp_dir().join(format!("mc68000-dap-{}.asm", std::process::id()));
+    std::fs::write(&path, PROGRAM).unwrap();
+    let path = path.to_string_lossy().into_owned();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut adapter = Adapter::default();
+        serve(&mut adapter, &mut transport::tcp(stream).unwrap()).unwrap();
+    });
+    let mut client = Client {
+        transport: transport::tcp(TcpStream::connect(address).unwrap()).unwrap(),
+        seq: 0,
+        pending: VecDeque::new(),
+    };
+
+    let capabilities = client.request(Command::Initialize);
+    assert_eq!(
+        body(&capabilities)
+            .get("supportsReadMemoryRequest")
+            .and_then(Json::as_bool),
+        Some(true)
+    );
+
+    let launch = client.request(Command::Launch {
+        program: Some(path.clone()),
+        source: None,
+        stop_on_entry: true,
+    });
+    body(&launch);
+    client.event("initialized");
+
+    // Wie ein DAP-Client: Breakpoint über Quelle und Zeile
+    client.seq += 1;
+    let request = format!(
+        r#"{{"seq":{},"type":"request","command":"setBreakpoints","arguments":{{"source":{{"path":{}}},"breakpoints":[{{"line":4}},{{"line":1}}]}}}}"#,
+        client.seq,
+        Json::from(path.as_str())
+    );
+    client.transport.send(&request).unwrap();
+    let breakpoints = client.response(client.seq);
+    let breakpoints = body(&breakpoints)
+        .get("breakpoints")
+        .and_then(Json::as_array)
+        .unwrap()
+        .to_vec();
+    assert_eq!(breakpoints[0].get("verified"), Some(&Json::Bool(true)));
+    assert_eq!(
+        str_field(&breakpoints[0], "instructionReference"),
+        "0x001004"
+    );
+    assert_eq!(breakpoints[1].get("verified"), Some(&Json::Bool(false)));
+
+    body(&client.request(Command::ConfigurationDone));
+    let stopped = client.event("stopped");
+    assert_eq!(str_field(event_body(&stopped), "reason"), "entry");
+
+    body(&client.request(Command::Continue));
+    let stopped = client.event("stopped");
+    assert_eq!(str_field(event_body(&stopped), "reason"), "breakpoint");
+
+    let trace = client.request(Command::StackTrace);
+    let frame = &body(&trace)
+        .get("stackFrames")
+        .and_then(Json::as_array)
+        .unwrap()[0];
+    assert_eq!(frame.get("line").and_then(Json::as_i64), Some(4));
+    assert_eq!(str_field(frame, "name"), "START+4");
+    assert_eq!(
+        str_field(frame.get("source").unwrap(), "path"),
+        path.as_str()
+    );
+
+    let scopes = client.request(Command::Scopes { frame_id: 0 });
+    let registers = &body(&scopes)
+        .get("scopes")
+        .and_then(Json::as_array)
+        .unwrap()[0];
+    assert_eq!(
+        registers.get("variablesReference").and_then(Json::as_i64),
+        Some(REGISTERS_REFERENCE)
+    );
+    let variables = client.request(Command::Variables {
+        reference: REGISTERS_REFERENCE,
+    });
+    assert_eq!(register(body(&variables), "D0"), "0x0000000A");
+    assert_eq!(register(body(&variables), "D1"), "0x00000000");
+    assert_eq!(register(body(&variables), "PC"), "0x001004");
+
+    // MOVEQ #7, D0 = $7007
+    let memory = client.request(Command::ReadMemory {
+        address: 0x1000,
+        count: 2,
+    });
+    assert_eq!(str_field(body(&memory), "data"), "cAc=");
+
+    let disassembly = client.request(Command::Disassemble {
+        address: 0x1004,
+        offset: -2,
+        count: 3,
+    });
+    let instructions = body(&disassembly)
+        .get("instructions")
+        .and_then(Json::as_array)
+        .unwrap()
+        .to_vec();
+    assert_eq!(str_field(&instructions[0], "address"), "0x001000");
+    assert_eq!(str_field(&instructions[0], "symbol"), "START");
+    assert_eq!(str_field(&instructions[2], "address"), "0x001004");
+    assert!(str_field(&instructions[2], "instruction").starts_with("MOVE.L"));
+
+    let step = client.request(Command::Next);
+    body(&step);
+    assert_eq!(
+        str_field(event_body(&client.event("stopped")), "reason"),
+        "step"
+    );
+    let variables = client.request(Command::Variables {
+        reference: REGISTERS_REFERENCE,
+    });
+    assert_eq!(register(body(&variables), "D1"), "0x0000000A");
+
+    body(&client.request(Command::Continue));
+    let exited = client.event("exited");
+    assert_eq!(event_body(&exited).get("exitCode"), Some(&Json::Number(0)));
+    client.event("terminated");
+
+    // Fehler kommen als Response mit success=false
+    let error = client.request(Command::Scopes { frame_id: 3 });
+    assert_eq!(error.get("success"), Some(&Json::Bool(false)));
+    assert_eq!(str_field(&error, "message"), "unknown frame 3");
+
+    body(&client.request(Command::Disconnect));
+    server.join().unwrap();
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_launch_reports_assembly_errors() {
+    let mut adapter = Adapter::default();
+    let request = Request {
+        seq: 1,
+        command: Command::Launch {
+            program: None,
+            source: Some("MOVEQ #1\nSIMHALT".to_string()),
+            stop_on_entry: false,
+        },
+    };
+    let messages = adapter.handle(&request);
+    assert_eq!(messages.len(), 1);
+    let response = messages[0].to_json(1);
+    assert_eq!(response.get("success"), Some(&Json::Bool(false)));
+    assert!(str_field(&response, "message").contains("line 1"));
+
+    // Ohne Programm lehnen die Ausführungsbefehle ab
+    let messages = adapter.handle(&Request {
+        seq: 2,
+        command: Command::Continue,
+    });
+    let response = messages[0].to_json(2);
+    assert_eq!(str_field(&response, "message"), "no program launched");
+}
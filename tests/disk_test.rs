@@ -0,0 +1,115 @@
+// Disk am Systembus: Beispielprogramm, Schreiben in ein Image und Abschluss-Interrupt
+use mc68000::disk::{BLOCK_SIZE, CONTROL_IRQ, REG_CONTROL, STATUS_DONE};
+use mc68000::{Disk, Emulator, RunOutcome};
+
+const DISK_READ: &str = include_str!("../disk_read.asm");
+
+fn signed_image() -> Vec<u8> {
+    let mut image = vec![0u8; 4 * BLOCK_SIZE];
+    image[..4].copy_from_slice(b"M68K");
+    image
+}
+
+fn run_with(disk: Disk, source: &str) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.attach_device(Box::new(disk));
+    emulator.load_source(source).unwrap();
+    assert_eq!(emulator.run(10_000), RunOutcome::Halted);
+    emulator
+}
+
+#[test]
+fn test_example_reads_block_zero_and_checks_signature() {
+    let emulator = run_with(
+        Disk::with_data(Disk::DEFAULT_BASE, signed_image()),
+        DISK_READ,
+    );
+    assert_eq!(emulator.cpu().get_data_register(7), 1);
+    assert_eq!(emulator.memory().read_long(0x2000), 0x4D36_384B);
+    // Gewartet wurde über die Latenz hinweg (1000 Zyklen pro Block)
+    assert!(emulator.cpu().cycles() > 1000);
+}
+
+#[test]
+fn test_example_detects_wrong_signature_and_missing_block() {
+    let emulator = run_with(Disk::new(Disk::DEFAULT_BASE, 1), DISK_READ);
+    assert_eq!(emulator.cpu().get_data_register(7), 0xFFFF_FFFF);
+
+    // Disk ohne Blöcke: Block 0 liegt außerhalb, STATUS meldet ERROR
+    let emulator = run_with(Disk::new(Disk::DEFAULT_BASE, 0), DISK_READ);
+    assert_eq!(emulator.cpu().get_data_register(7), 0xFFFF_FFFF);
+    assert_eq!(emulator.cpu().get_data_register(1), 4);
+}
+
+#[test]
+fn test_write_command_persists_to_image_file() {
+    let path = std::env::temp_dir().join(format!("mc68000-disk-{}.img", std::process::id()));
+    std::fs::write(&path, vec![0u8; 2 * BLOCK_SIZE]).unwrap();
+
+    let source = "DISK_BLOCK:   EQU     $E000
+DISK_ADDRESS: EQU     $E004
+DISK_COUNT:   EQU     $E008
+DISK_COMMAND: EQU     $E00C
+DISK_STATUS:  EQU     $E010
+            ORG     $2000
+DATA:       DC.L    $CAFEF00D
+            ORG     $1000
+START:      MOVEQ   #1, D0
+            MOVEA.L #DISK_BLOCK, A0
+            MOVE.L  D0, (A0)
+            MOVEA.L #DISK_COUNT, A0
+            MOVE.L  D0, (A0)
+            MOVE.L  #$2000, D0
+            MOVEA.L #DISK_ADDRESS, A0
+            MOVE.L  D0, (A0)
+            MOVEQ   #2, D0
+            MOVEA.L #DISK_COMMAND, A0
+            MOVE.L  D0, (A0)
+            MOVEA.L #DISK_STATUS, A0
+WAIT:       MOVE.L  (A0), D1
+            CMP.L   #1, D1
+            BEQ     WAIT
+            SIMHALT
+            END     START
+";
+    let emulator = run_with(Disk::open(Disk::DEFAULT_BASE, &path).unwrap(), source);
+    assert_eq!(emulator.cpu().get_data_register(1), STATUS_DONE);
+
+    let image = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(image.len(), 2 * BLOCK_SIZE);
+    assert_eq!(
+        &image[BLOCK_SIZE..BLOCK_SIZE + 4],
+        &[0xCA, 0xFE, 0xF0, 0x0D]
+    );
+    assert!(image[..BLOCK_SIZE].iter().all(|&b| b == 0));
+}
+
+#[test]
+fn test_completion_interrupt_runs_handler() {
+    // Wie das Beispiel, aber mit CONTROL_IRQ; der Handler (Autovektor Level 2) setzt D6
+    let source = DISK_READ
+        .replace(
+            "            ORG     $2000",
+            "            ORG     $68\n            DC.L    DONE\n            ORG     $2000",
+        )
+        .replace(
+            "FAIL:",
+            "DONE:       MOVEQ   #1, D6\n            RTE\nFAIL:",
+        );
+    let mut disk = Disk::with_data(Disk::DEFAULT_BASE, signed_image());
+    disk.set_latency(200);
+
+    let mut emulator = Emulator::new();
+    emulator.attach_device(Box::new(disk));
+    emulator.load_source(&source).unwrap();
+    emulator
+        .memory_mut()
+        .write_long(Disk::DEFAULT_BASE + REG_CONTROL, CONTROL_IRQ);
+    emulator.cpu_mut().set_sr(0x2000);
+
+    assert_eq!(emulator.run(10_000), RunOutcome::Halted);
+    assert_eq!(emulator.interrupts(), 1);
+    assert_eq!(emulator.cpu().get_data_register(6), 1);
+    assert_eq!(emulator.cpu().get_data_register(7), 1);
+}
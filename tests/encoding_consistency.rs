@@ -0,0 +1,264 @@
+// Konsistenz von Assembler, Disassembler und CPU-Dekodierung
+// Für jeden Eintrag der Instruktionstabelle werden Operandenkombinationen erzeugt, assembliert,
+// disassembliert und von der CPU dekodiert (und einmal ausgeführt). Alle drei müssen bei
+// Mnemonic, Größe, Operanden und Länge übereinstimmen; eine Instruktion, die nur in einer
+// Komponente ergänzt wird, fällt hier sofort auf.
+
+use mc68000::disassembler::disassemble;
+use mc68000::isa::{self, InstructionSpec, OperandClass, Size, INSTRUCTIONS};
+use mc68000::{Assembler, Memory, CPU};
+
+const START: u32 = 0x1000;
+
+/// Kombinationen, die die Tabelle zulässt, der Assembler aber wie der echte 68000 ablehnt
+const REJECTED: &[(&str, &str)] = &[
+    ("MOVE.B A1, D2", "address registers have no byte access"),
+    ("MOVE.B A1, (A4)", "address registers have no byte access"),
+    ("MOVE.B A1, DATA", "address registers have no byte access"),
+    ("ADD.B A1, D2", "address registers have no byte access"),
+    ("SUB.B A1, D2", "address registers have no byte access"),
+    ("CMP.B A1, D2", "address registers have no byte access"),
+    ("CMP.B D1, A3", "CMPA has no byte size"),
+    ("CMP.B A1, A3", "CMPA has no byte size"),
+    ("CMP.B DATA, A3", "CMPA has no byte size"),
+    ("CMP.B #5, A3", "CMPA has no byte size"),
+    ("ADDQ.B #5, A3", "address registers have no byte access"),
+    ("SUBQ.B #5, A3", "address registers have no byte access"),
+];
+
+/// Mnemonics, die den PC nicht einfach hinter die Instruktion setzen
+const FLOW: &[&str] = &["DBRA", "JMP", "RTE", "SIMHALT", "STOP"];
+
+/// Beispieloperand einer Klasse; Register unterscheiden sich je Position
+fn sample(class: OperandClass, position: usize, branch: bool) -> &'static str {
+    let [first, second] = match class {
+        OperandClass::DataRegister => ["D1", "D2"],
+        OperandClass::AddressRegister => ["A1", "A3"],
+        OperandClass::Indirect => ["(A2)", "(A4)"],
+        OperandClass::PostIncrement => ["(A3)+", "(A5)+"],
+        OperandClass::PreDecrement => ["-(A4)", "-(A6)"],
+        OperandClass::Displacement => ["4(A2)", "-8(A4)"],
+        OperandClass::Absolute if branch => ["START", "START"],
+        OperandClass::Absolute => ["DATA", "DATA"],
+        OperandClass::Immediate => ["#5", "#5"],
+        OperandClass::ConditionCodes => ["CCR", "CCR"],
+    };
+    if position == 0 {
+        first
+    } else {
+        second
+    }
+}
+
+/// Wie Sprungziele und Adressen im Disassembler erscheinen: Displacement bzw. $800,
+/// Immediates dezimal ohne führendes #$ (der Disassembler schreibt #5 und #$5)
+fn expected_operand(operand: &str) -> String {
+    match operand {
+        "START" => "-2".to_string(), // START - (START + 2)
+        "DATA" => "$800".to_string(),
+        _ => normalize(operand),
+    }
+}
+
+fn normalize(operand: &str) -> String {
+    match operand.strip_prefix("#$") {
+        Some(hex) => format!("#{}", i64::from_str_radix(hex, 16).unwrap()),
+        None => operand.to_string(),
+    }
+}
+
+/// Mnemonic, unter dem die Instruktion dekodiert wird (CMP #imm ist CMPI usw.)
+fn canonical_mnemonic(spec: &InstructionSpec, operands: &[OperandClass]) -> &'static str {
+    match (spec.mnemonic, operands) {
+        ("JUMP", _) => "JMP",
+        ("CMP", [_, OperandClass::AddressRegister]) => "CMPA",
+        ("CMP", [OperandClass::Immediate, _]) => "CMPI",
+        (mnemonic, _) => mnemonic,
+    }
+}
+
+/// Größe, die ein Mnemonic ohne Suffix trägt (MOVEQ ist immer .L, Bcc immer kurz)
+fn implied_size(mnemonic: &str) -> Option<Size> {
+    isa::lookup(mnemonic)
+        .filter(|spec| spec.sizes.len() == 1)
+        .map(|spec| spec.sizes[0])
+}
+
+/// Alle Kombinationen eines Eintrags: jede Klasse je Position, jede erlaubte Größe
+fn combinations(spec: &InstructionSpec) -> Vec<(Option<Size>, Vec<OperandClass>)> {
+    let mut operand_lists = vec![vec![]];
+    for allowed in spec.operands {
+        operand_lists = operand_lists
+            .into_iter()
+            .flat_map(|prefix: Vec<OperandClass>| {
+                allowed.iter().map(move |&class| {
+                    let mut list = prefix.clone();
+                    list.push(class);
+                    list
+                })
+            })
+            .collect();
+    }
+    let sizes: Vec<Option<Size>> = if spec.sizes.len() > 1 {
+        spec.sizes.iter().copied().map(Some).collect()
+    } else {
+        vec![None]
+    };
+    sizes
+        .iter()
+        .flat_map(|&size| operand_lists.iter().map(move |ops| (size, ops.clone())))
+        .collect()
+}
+
+struct Parsed {
+    mnemonic: String,
+    size: Option<Size>,
+    operands: Vec<String>,
+}
+
+fn parse_disassembly(text: &str) -> Parsed {
+    let (head, rest) = text.split_once(' ').unwrap_or((text, ""));
+    let (mnemonic, suffix) = head.split_once('.').unwrap_or((head, ""));
+    Parsed {
+        mnemonic: mnemonic.to_string(),
+        size: Size::from_suffix(suffix),
+        operands: rest
+            .split(", ")
+            .filter(|op| !op.is_empty())
+            .map(normalize)
+            .collect(),
+    }
+}
+
+/// Assembliert eine Zeile bei START; liefert die Wörter und die reservierte Länge
+fn assemble(line: &str) -> Result<(Vec<u16>, u32), String> {
+    let source = format!(
+        "DATA:   EQU     $800\n        ORG     ${:X}\nSTART:  {}\nNEXT:\n",
+        START, line
+    );
+    let mut assembler = Assembler::new();
+    let program = assembler
+        .assemble_source(&source)
+        .map_err(|errors| format!("{:?}", errors))?;
+    let mut image = program.image.clone();
+    image.sort();
+    let words = image.iter().map(|&(_, word)| word).collect();
+    Ok((words, program.symbols["NEXT"] - START))
+}
+
+/// Prüft eine Zeile; Fehler als Text, damit alle Abweichungen auf einmal gemeldet werden
+fn check(
+    line: &str,
+    mnemonic: &str,
+    size: Option<Size>,
+    operands: &[OperandClass],
+    sources: &[&str],
+) -> Result<(), String> {
+    let (words, reserved) = assemble(line)?;
+    let length = 2 * words.len() as u32;
+    if reserved != length {
+        return Err(format!(
+            "reserved {} bytes, emitted {:04X?}",
+            reserved, words
+        ));
+    }
+
+    // Disassembler
+    let disassembly = disassemble(&words);
+    let parsed = parse_disassembly(&disassembly.text);
+    let expected_operands: Vec<String> = sources.iter().map(|op| expected_operand(op)).collect();
+    let implied = implied_size(mnemonic);
+    if parsed.mnemonic != mnemonic
+        || parsed.size.or(implied) != size.or(implied)
+        || parsed.operands != expected_operands
+        || disassembly.length != length
+    {
+        return Err(format!(
+            "disassembler: {:04X?} -> \"{}\" ({} bytes)",
+            words, disassembly.text, disassembly.length
+        ));
+    }
+
+    // CPU: Dekodierung
+    let cpu = CPU::new();
+    let Some(decoded) = cpu.decode(&words) else {
+        return Err(format!("CPU does not decode {:04X?}", words));
+    };
+    if decoded.mnemonic != mnemonic
+        || decoded.size.or(implied) != size.or(implied)
+        || decoded.operands != operands
+        || decoded.length != length
+    {
+        return Err(format!("CPU decode: {:04X?} -> {:?}", words, decoded));
+    }
+
+    // CPU: Ausführung muss zur Dekodierung passen
+    let mut cpu = CPU::new();
+    let mut memory = Memory::new();
+    for (i, &word) in words.iter().enumerate() {
+        memory.write_word(START + 2 * i as u32, word);
+    }
+    cpu.set_stack_pointer(0x8000);
+    cpu.set_pc(START);
+    cpu.execute_instruction(&mut memory);
+    if let Some(exception) = cpu.exception() {
+        return Err(format!("CPU execute: {}", exception));
+    }
+    let flow = FLOW.contains(&mnemonic) || mnemonic.starts_with('B');
+    if !flow && cpu.get_pc() != START + length {
+        return Err(format!(
+            "CPU execute: PC ${:X}, expected ${:X}",
+            cpu.get_pc(),
+            START + length
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_table_encoder_decoder_agree() {
+    let mut failures = Vec::new();
+    let mut checked = 0;
+    let mut rejected = Vec::new();
+
+    for spec in INSTRUCTIONS {
+        let checked_before = checked;
+        let branch = spec.mnemonic.starts_with('B') || spec.mnemonic == "DBRA";
+        for (size, operands) in combinations(spec) {
+            let sources: Vec<&str> = operands
+                .iter()
+                .enumerate()
+                .map(|(i, &class)| sample(class, i, branch))
+                .collect();
+            let mut line = spec.mnemonic.to_string();
+            if let Some(size) = size {
+                line = format!("{}.{}", line, size.suffix());
+            }
+            if !sources.is_empty() {
+                line = format!("{} {}", line, sources.join(", "));
+            }
+
+            if REJECTED.iter().any(|(rejected, _)| *rejected == line) {
+                assert!(assemble(&line).is_err(), "{} should be rejected", line);
+                rejected.push(line);
+                continue;
+            }
+
+            let size = size.or(implied_size(spec.mnemonic));
+            let mnemonic = canonical_mnemonic(spec, &operands);
+            if let Err(message) = check(&line, mnemonic, size, &operands, &sources) {
+                failures.push(format!("{}: {}", line, message));
+            }
+            checked += 1;
+        }
+        assert!(
+            checked > checked_before,
+            "{}: nothing checked",
+            spec.mnemonic
+        );
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+    assert_eq!(rejected.len(), REJECTED.len(), "stale entries in REJECTED");
+    assert!(checked > 100, "only {} combinations checked", checked);
+}
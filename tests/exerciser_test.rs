@@ -0,0 +1,45 @@
+// Der erzeugte Exerciser muss mit dem Referenzmodell übereinstimmen
+// Jede Abweichung zwischen CPU und `reference` erscheint als fehlgeschlagenes ASSERT
+
+use mc68000::isa;
+use mc68000::testgen;
+use mc68000::{Emulator, RunOutcome};
+
+#[test]
+fn test_exerciser_passes() {
+    let exerciser = testgen::exerciser();
+    let mut emulator = Emulator::new();
+    if let Err(errors) = emulator.load_source(&exerciser.source) {
+        panic!(
+            "exerciser does not assemble: {:?}",
+            &errors[..errors.len().min(5)]
+        );
+    }
+    assert!(emulator.program().unwrap().assertions.len() > 1000);
+
+    let outcome = emulator.run(1_000_000);
+    let failures: Vec<String> = emulator
+        .assertion_failures()
+        .iter()
+        .map(|failure| failure.to_string())
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "{} failures:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+    assert!(matches!(outcome, RunOutcome::Halted), "{:?}", outcome);
+}
+
+#[test]
+fn test_exerciser_covers_instruction_table() {
+    let exerciser = testgen::exerciser();
+    let missing: Vec<&str> = isa::INSTRUCTIONS
+        .iter()
+        .map(|spec| spec.mnemonic)
+        .filter(|mnemonic| !exerciser.covered.contains(mnemonic))
+        .collect();
+    assert!(missing.is_empty(), "not exercised: {:?}", missing);
+    assert!(exerciser.cases > 500);
+}
@@ -1,5 +1,5 @@
 // Integration tests for MC68000 emulator
-use mc68000::{Assembler, Memory, CPU};
+use mc68000::{assert_memory_eq, Assembler, Emulator, Memory, RunOutcome, Timer, CPU};
 
 #[test]
 fn test_power_of_two_calculation() {
@@ -33,6 +33,7 @@ DONE:       MOVEA.L #RESULT, A1
 
     // Memory at $0800 should contain 8
     assert_eq!(memory.read_long(0x0800), 8, "N_VALUE should be 8");
+    let before = memory.clone();
 
     // Run program
     run_until_halt(&mut cpu, &mut memory, 1000);
@@ -41,8 +42,9 @@ DONE:       MOVEA.L #RESULT, A1
     assert_eq!(cpu.get_data_register(0), 256, "D0 should contain 256 (2^8)");
     assert_eq!(cpu.get_data_register(1), 0, "D1 should be 0 after loop");
 
-    // Memory at $0804 (RESULT) should contain 256
+    // Memory at $0804 (RESULT) should contain 256, nothing else was written
     assert_eq!(memory.read_long(0x0804), 256, "RESULT should be 256");
+    assert_memory_eq!(before, memory, except: 0x0804..0x0808);
 }
 
 #[test]
@@ -82,6 +84,46 @@ DATA:       DC.L    123
     assert_eq!(cpu.get_data_register(0), 123, "D0 should be 123");
 }
 
+#[test]
+fn test_movea_long_immediate() {
+    // MOVEA.L #imm hat zwei Extension Words, auch über $FFFF
+    let assembly = r#"
+            ORG     $1000
+            MOVEA.L #$12345678, A0
+            MOVEA.L #$80000000, A1
+            MOVE.L  A0, D0
+            SIMHALT
+    "#;
+
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    assert_eq!(memory.read_word(0x1000), 0x207C);
+    assert_eq!(memory.read_long(0x1002), 0x1234_5678);
+    run_until_halt(&mut cpu, &mut memory, 10);
+
+    assert_eq!(cpu.get_address_register(0), 0x1234_5678);
+    assert_eq!(cpu.get_address_register(1), 0x8000_0000);
+    assert_eq!(cpu.get_data_register(0), 0x1234_5678);
+    assert_eq!(cpu.get_pc(), 0x100E, "stopped at SIMHALT");
+}
+
+#[test]
+fn test_movea_negative_immediate() {
+    // #-1 ist bei .L $FFFFFFFF; MOVEA.W erweitert das Vorzeichen auf das ganze An
+    let assembly = r#"
+            ORG     $1000
+            MOVEA.L #-1, A0
+            MOVEA.L #0, A2
+            MOVEA.W #-1, A2
+            SIMHALT
+    "#;
+
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    run_until_halt(&mut cpu, &mut memory, 10);
+
+    assert_eq!(cpu.get_address_register(0), 0xFFFF_FFFF);
+    assert_eq!(cpu.get_address_register(2), 0xFFFF_FFFF);
+}
+
 #[test]
 fn test_muls_multiplication() {
     let assembly = r#"
@@ -113,6 +155,135 @@ fn test_subq_operation() {
     assert_eq!(cpu.get_data_register(1), 7, "10 - 1 - 2 = 7");
 }
 
+#[test]
+fn test_subq_sizes_keep_upper_bits() {
+    let assembly = r#"
+            ORG     $1000
+            MOVE.L  #$100, D0
+            SUBQ.B  #1, D0
+            SIMHALT
+    "#;
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    run_until_halt(&mut cpu, &mut memory, 10);
+    assert_eq!(
+        cpu.get_data_register(0),
+        0x0000_01FF,
+        "only the low byte wraps"
+    );
+    assert_eq!(cpu.get_ccr(), 0x10 | 0x08 | 0x01, "X, N and C set");
+
+    let assembly = r#"
+            ORG     $1000
+            MOVE.L  #$8000, D1
+            SUBQ.W  #1, D1
+            MOVEQ   #2, D2
+            SUBQ.L  #3, D2
+            SIMHALT
+    "#;
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    run_until_halt(&mut cpu, &mut memory, 10);
+    assert_eq!(cpu.get_data_register(1), 0x7FFF);
+    assert_eq!(cpu.get_data_register(2), 0xFFFF_FFFF);
+    assert_eq!(cpu.get_ccr(), 0x10 | 0x08 | 0x01, "2 - 3 borrows");
+}
+
+#[test]
+fn test_negative_immediates_execute() {
+    let assembly = r#"
+            ORG     $1000
+            MOVE.L  #-1, D0
+            MOVE.W  #-2, D1
+            CMP.L   #-1, D0
+            BNE     WRONG
+            MOVE.L  #-100000, D2
+            SIMHALT
+WRONG:      MOVEQ   #0, D0
+            SIMHALT
+    "#;
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    run_until_halt(&mut cpu, &mut memory, 10);
+    assert_eq!(cpu.get_data_register(0), 0xFFFF_FFFF);
+    assert_eq!(cpu.get_data_register(1) & 0xFFFF, 0xFFFE);
+    assert_eq!(cpu.get_data_register(2), (-100000i32) as u32);
+}
+
+#[test]
+fn test_address_register_sources_execute() {
+    let assembly = r#"
+            ORG     $1000
+            MOVE.L  #$12345678, D7
+            MOVEA.L D7, A3
+            MOVEA.W #$FFF0, A0
+            MOVEQ   #$20, D0
+            ADD.L   A0, D0          ; $20 + $FFFFFFF0
+            MOVE.L  A3, D1
+            MOVE.L  #$AAAA0100, D2
+            SUB.W   A3, D2          ; nur das untere Wort von A3
+            MOVE.W  A0, $0800
+            SIMHALT
+    "#;
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    run_until_halt(&mut cpu, &mut memory, 20);
+    assert_eq!(cpu.get_data_register(0), 0x10);
+    assert_eq!(cpu.get_data_register(1), 0x1234_5678);
+    assert_eq!(
+        cpu.get_data_register(2),
+        0xAAAA_AA88,
+        "$0100 - $5678, upper word kept"
+    );
+    assert_eq!(memory.read_word(0x0800), 0xFFF0);
+    // Quelle bleibt unverändert
+    assert_eq!(cpu.get_address_register(3), 0x1234_5678);
+}
+
+#[test]
+fn test_movea_sizes_execute() {
+    // .W erweitert das Vorzeichen auf 32 Bit, .L übernimmt den Wert unverändert
+    let assembly = r#"
+            ORG     $1000
+            MOVEA.W #$FFF0, A0
+            MOVEA.L #$FFF0, A1
+            MOVEA.L #$12345678, A2
+            MOVE.L  #$12348000, D7
+            MOVEA.W D7, A3
+            MOVEA.L D7, A4
+            SIMHALT
+    "#;
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    run_until_halt(&mut cpu, &mut memory, 20);
+    assert_eq!(cpu.get_address_register(0), 0xFFFF_FFF0);
+    assert_eq!(cpu.get_address_register(1), 0x0000_FFF0);
+    assert_eq!(cpu.get_address_register(2), 0x1234_5678);
+    assert_eq!(cpu.get_address_register(3), 0xFFFF_8000);
+    assert_eq!(cpu.get_address_register(4), 0x1234_8000);
+}
+
+#[test]
+fn test_dbra_asl_tst_execute() {
+    // Summe 1..5 mit DBRA, dann ASL mit Übertrag und TST auf ein Byte
+    let assembly = r#"
+            ORG     $1000
+            MOVEQ   #4, D0
+            MOVEQ   #0, D1
+            MOVEQ   #1, D2
+LOOP:       ADD.L   D2, D1
+            ADDQ.L  #1, D2
+            DBRA    D0, LOOP
+            MOVE.L  #$40000001, D3
+            ASL.L   #2, D3
+            MOVE.L  #$180, D4
+            TST.B   D4
+            SIMHALT
+    "#;
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    run_until_halt(&mut cpu, &mut memory, 100);
+    assert_eq!(cpu.get_data_register(1), 15);
+    assert_eq!(cpu.get_data_register(0) & 0xFFFF, 0xFFFF);
+    assert_eq!(cpu.get_data_register(3), 0x0000_0004);
+    // TST.B sieht $80: N gesetzt, Z/V/C gelöscht; X (1 aus ASL) bleibt
+    assert_eq!(cpu.get_ccr(), 0x18);
+}
+
 #[test]
 fn test_cmpi_beq_branch() {
     let assembly = r#"
@@ -163,9 +334,282 @@ BUFFER:     DS.L    1
     "#;
 
     let (mut cpu, mut memory) = assemble_and_load(assembly);
+    let before = memory.clone();
     run_until_halt(&mut cpu, &mut memory, 10);
 
     assert_eq!(memory.read_long(0x0800), 777, "BUFFER should contain 777");
+    assert_memory_eq!(before, memory, except: 0x0800..0x0804);
+}
+
+// CCR nach einem einzelnen Vergleich mit D0 = `d0`
+fn ccr_after_compare(d0: i8, compare: &str) -> u8 {
+    let assembly = format!(
+        "            ORG     $1000\n            MOVEQ   #{}, D0\n            {}\n            SIMHALT\n",
+        d0, compare
+    );
+    let (mut cpu, mut memory) = assemble_and_load(&assembly);
+    run_until_halt(&mut cpu, &mut memory, 10);
+    cpu.get_ccr() & 0x0F
+}
+
+#[test]
+fn test_cmp_flags_for_all_sizes() {
+    const N: u8 = 0x08;
+    const Z: u8 = 0x04;
+    const V: u8 = 0x02;
+    const C: u8 = 0x01;
+
+    // D0 = $FFFFFFFF: als Byte/Word gleich, als Long größer
+    assert_eq!(ccr_after_compare(-1, "CMP.B   #$FF, D0"), Z);
+    assert_eq!(ccr_after_compare(-1, "CMP.W   #$FFFF, D0"), Z);
+    assert_eq!(ccr_after_compare(-1, "CMP.L   #$FFFF, D0"), N);
+
+    // Unsigned kleiner -> Borrow
+    assert_eq!(ccr_after_compare(1, "CMP.B   #2, D0"), N | C);
+    assert_eq!(ccr_after_compare(1, "CMP.W   #2, D0"), N | C);
+    assert_eq!(ccr_after_compare(1, "CMP.L   #2, D0"), N | C);
+
+    // $7F - (-1) läuft als Byte über, als Word nicht
+    assert_eq!(ccr_after_compare(127, "CMP.B   #$FF, D0"), N | V | C);
+    assert_eq!(ccr_after_compare(127, "CMP.W   #$FF, D0"), N | C);
+
+    // Register-Register
+    assert_eq!(ccr_after_compare(5, "CMP.W   D0, D0"), Z);
+    assert_eq!(ccr_after_compare(-128, "CMP.B   D1, D0"), N);
+}
+
+// Nimmt `branch` nach den Zeilen `setup` den Sprung? MOVEQ davor setzt Z und löscht N, V, C
+fn branch_taken(setup: &[&str], branch: &str) -> bool {
+    let mut assembly = "            ORG     $1000\n            MOVEQ   #0, D1\n".to_string();
+    for line in setup {
+        assembly.push_str(&format!("            {}\n", line));
+    }
+    assembly.push_str(&format!(
+        "            {} TAKEN\n            SIMHALT\nTAKEN:      MOVEQ   #1, D1\n            SIMHALT\n",
+        branch
+    ));
+    let (mut cpu, mut memory) = assemble_and_load(&assembly);
+    run_until_halt(&mut cpu, &mut memory, 10);
+    cpu.get_data_register(1) == 1
+}
+
+#[test]
+fn test_ccr_immediates_set_clear_and_toggle_flags() {
+    // Z: ANDI löscht, ORI setzt, EORI kippt
+    assert!(branch_taken(&[], "BEQ"));
+    assert!(branch_taken(&["ANDI    #%11111011, CCR"], "BNE"));
+    assert!(branch_taken(
+        &["ANDI    #%11111011, CCR", "ORI #%00000100, CCR"],
+        "BEQ"
+    ));
+    assert!(branch_taken(&["EORI    #%00000100, CCR"], "BNE"));
+
+    // C
+    assert!(branch_taken(&[], "BCC"));
+    assert!(branch_taken(&["ORI     #%00000001, CCR"], "BCS"));
+    assert!(branch_taken(&["EORI    #%00000001, CCR"], "BCS"));
+    assert!(branch_taken(
+        &["ORI     #1, CCR", "EORI    #%00000001, CCR"],
+        "BCC"
+    ));
+
+    // N
+    assert!(branch_taken(&["ORI     #%00001000, CCR"], "BMI"));
+    assert!(branch_taken(
+        &["ORI.B   #$08, CCR", "ANDI.B  #$F7, CCR"],
+        "BPL"
+    ));
+
+    // V: N != V ergibt "kleiner"
+    assert!(branch_taken(&["ORI     #%00000010, CCR"], "BLT"));
+    assert!(branch_taken(&["ORI     #%00001010, CCR"], "BGE"));
+}
+
+#[test]
+fn test_ccr_immediates_leave_system_byte() {
+    let assembly = "            ORG     $1000
+            ORI     #$FF, CCR
+            EORI    #%00010000, CCR
+            SIMHALT
+";
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    let system_byte = cpu.get_sr() & 0xFF00;
+    run_until_halt(&mut cpu, &mut memory, 10);
+    assert_eq!(cpu.get_ccr(), 0x0F, "only XNZVC exist, X toggled back");
+    assert_eq!(cpu.get_sr() & 0xFF00, system_byte);
+    assert!(cpu.exception().is_none());
+
+    // Der Immediate muss in ein Byte passen
+    let mut assembler = Assembler::new();
+    let errors = assembler
+        .assemble_source("        ORI #$100, CCR")
+        .unwrap_err();
+    assert!(errors[0].message.contains("does not fit in a byte"));
+}
+
+#[test]
+fn test_cmpa_loop_bound() {
+    let assembly = r#"
+            ORG     $0800
+TABLE:      DC.L    1
+TABLE_END:  DS.L    1
+
+            ORG     $1000
+            MOVEA.L #TABLE_END, A1
+            MOVEA.L #TABLE, A0
+            MOVEQ   #0, D0
+CHECK:      CMPA.L  A0, A1
+            BNE     NEXT
+            SIMHALT
+NEXT:       MOVEQ   #1, D0
+            MOVEA.L #TABLE_END, A0
+            BRA     CHECK
+    "#;
+
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    run_until_halt(&mut cpu, &mut memory, 20);
+
+    assert_eq!(cpu.get_data_register(0), 1, "one pass before A0 reaches A1");
+    assert_eq!(cpu.get_ccr() & 0x04, 0x04, "A0 == A1 at the end");
+}
+
+#[test]
+fn test_cmp_word_label() {
+    let assembly = r#"
+            ORG     $0800
+LENGTH:     DS.L    1
+
+            ORG     $1000
+            MOVEQ   #3, D0
+            CMP.W   LENGTH, D0
+            BEQ     SAME
+            MOVEQ   #0, D1
+            SIMHALT
+SAME:       MOVEQ   #1, D1
+            SIMHALT
+    "#;
+
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    memory.write_word(0x0800, 3);
+    run_until_halt(&mut cpu, &mut memory, 10);
+
+    assert_eq!(cpu.get_data_register(1), 1, "LENGTH.W == D0.W");
+}
+
+#[test]
+fn test_jump_table_dispatch() {
+    let assembly = r#"
+            ORG     $0800
+TABLE:      DC.L    HANDLER1, HANDLER2
+
+            ORG     $1000
+            MOVEQ   #2, D0          ; Index 1 -> zwei Einträge lesen
+            MOVEA.L #TABLE, A0
+NEXT:       MOVEA.L (A0)+, A1
+            SUBQ.L  #1, D0
+            BNE     NEXT
+            JMP     (A1)
+
+HANDLER1:   MOVEQ   #1, D7
+            SIMHALT
+HANDLER2:   MOVEQ   #2, D7
+            SIMHALT
+    "#;
+
+    let (mut cpu, mut memory) = assemble_and_load(assembly);
+    assert_ne!(memory.read_long(0x0800), 0, "table filled with addresses");
+
+    run_until_halt(&mut cpu, &mut memory, 20);
+
+    assert_eq!(cpu.get_data_register(7), 2, "dispatched to HANDLER2");
+    assert_eq!(cpu.get_address_register(0), 0x0808);
+}
+
+const OS_MODULE: &str = r#"
+            ORG     $4000
+            XDEF    OS_DOUBLE
+OS_DOUBLE:  ADD     D0, D0
+            JMP     (A6)        ; Rücksprung über A6
+"#;
+
+const USER_MODULE: &str = r#"
+            ORG     $1000
+            XREF    OS_DOUBLE
+START:      MOVEQ   #21, D0
+            MOVEA.L #RETURN, A6
+            MOVEA.L #OS_DOUBLE, A0
+            JMP     (A0)
+RETURN:     SIMHALT
+"#;
+
+fn run_linked(program: &mc68000::program::Program) -> CPU {
+    let mut memory = Memory::new();
+    for (address, word) in &program.image {
+        memory.write_word(*address, *word);
+    }
+    let mut cpu = CPU::new();
+    cpu.set_pc(program.symbols["START"]);
+    run_until_halt(&mut cpu, &mut memory, 20);
+    cpu
+}
+
+#[test]
+fn test_modules_call_exported_routine() {
+    let mut assembler = Assembler::new();
+    assembler.assemble_module("os", OS_MODULE).unwrap();
+    let user = assembler.assemble_module("user", USER_MODULE).unwrap();
+    assert_eq!(
+        user.symbols["OS_DOUBLE"], 0x4000,
+        "export visible to later module"
+    );
+
+    let linked = assembler.link().unwrap();
+    assert_eq!(run_linked(&linked).get_data_register(0), 42);
+}
+
+#[test]
+fn test_link_resolves_forward_externals() {
+    // Das aufrufende Modul kommt zuerst; erst link() kennt OS_DOUBLE
+    let mut assembler = Assembler::new();
+    assembler.assemble_module("user", USER_MODULE).unwrap();
+    assembler.assemble_module("os", OS_MODULE).unwrap();
+
+    let linked = assembler.link().unwrap();
+    assert_eq!(run_linked(&linked).get_data_register(0), 42);
+    assert_eq!(
+        linked
+            .line_text(linked.line_for_address(0x4000).unwrap())
+            .map(str::trim),
+        Some("OS_DOUBLE:  ADD     D0, D0")
+    );
+}
+
+#[test]
+fn test_reassembling_module_keeps_its_exports() {
+    // Erneutes Assemblieren ersetzt das Modul; nur andere Module zählen als Duplikat
+    let source = "        ORG     $4000\n        XDEF    PUTC\nPUTC:   JMP     (A6)";
+    let mut assembler = Assembler::new();
+    assembler.assemble_module("os", source).unwrap();
+    assembler.assemble_module("os", source).unwrap();
+
+    let errors = assembler.assemble_module("os2", source).unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "line 2: XDEF PUTC: already exported by module os"
+    );
+}
+
+#[test]
+fn test_link_reports_missing_external() {
+    let mut assembler = Assembler::new();
+    assembler.assemble_module("user", USER_MODULE).unwrap();
+
+    let errors = assembler.link().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].to_string(),
+        "line 3: undefined external OS_DOUBLE (required by module user)"
+    );
 }
 
 // Helper functions
@@ -218,3 +662,137 @@ fn run_until_halt(cpu: &mut CPU, memory: &mut Memory, max_steps: usize) {
         }
     }
 }
+
+#[test]
+fn test_legacy_simhalt_programs_still_halt() {
+    let source = "            ORG     $1000
+            MOVEQ   #5, D0
+            SIMHALT
+";
+    let mut emulator = Emulator::new();
+    emulator.set_legacy_simhalt(true);
+    emulator.load_source(source).unwrap();
+    assert_eq!(emulator.memory().read_word(0x1002), 0x4E72);
+    assert_eq!(emulator.assembler().warnings().len(), 1);
+    assert_eq!(emulator.run(100), RunOutcome::Halted);
+    assert_eq!(emulator.cpu().get_data_register(0), 5);
+}
+
+#[test]
+fn test_optimized_loop_runs_like_the_original() {
+    let source = "            ORG     $1000
+            MOVE.L  #10, D0
+            MOVEQ   #0, D1
+LOOP:       ADD.L   #2, D1
+            SUB.L   #1, D0
+            BNE     LOOP
+            SIMHALT
+";
+    let mut emulator = Emulator::new();
+    emulator.set_optimize(true);
+    emulator.load_source(source).unwrap();
+    assert_eq!(emulator.assembler().notes().len(), 3);
+    assert_eq!(emulator.run(1000), RunOutcome::Halted);
+    assert_eq!(emulator.cpu().get_data_register(0), 0);
+    assert_eq!(emulator.cpu().get_data_register(1), 20);
+}
+
+#[test]
+fn test_data_bytes_loaded_exactly() {
+    let source = "            ORG     $0800
+BYTES:      DC.B    1, 2, 3
+WORD:       DC.W    $1234
+            ORG     $1000
+            MOVEQ   #1, D0
+            SIMHALT
+";
+    let mut emulator = Emulator::new();
+    emulator.load_source(source).unwrap();
+
+    let bytes: Vec<u8> = (0x0800..0x0806)
+        .map(|address| emulator.memory().read_byte(address))
+        .collect();
+    // DC.W wird auf die nächste gerade Adresse ausgerichtet, das Füllbyte bleibt 0
+    assert_eq!(bytes, vec![1, 2, 3, 0, 0x12, 0x34]);
+
+    assert_eq!(emulator.memory().read_word(0x1000), 0x7001);
+    assert_eq!(emulator.run(100), RunOutcome::Halted);
+}
+
+#[test]
+fn test_batch_directory_summary() {
+    let directory = std::env::temp_dir().join(format!("mc68000-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let files = [
+        ("pass.s68", "MOVEQ #42, D0\nSIMHALT\n"),
+        ("pass.expect", "[registers]\nD0 = 42\n"),
+        ("wrong.s68", "MOVEQ #7, D1\nSIMHALT\n"),
+        ("wrong.expect", "[registers]\nD1 = 8\n"),
+        ("spin.s68", "LOOP: BRA LOOP\n"),
+        ("spin.expect", "max_steps = 5000\n"),
+    ];
+    for (name, content) in files {
+        std::fs::write(directory.join(name), content).unwrap();
+    }
+
+    let report = mc68000::batch::run_directory(&directory).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(report.cases.len(), 3);
+    assert_eq!(report.passed(), 1);
+    assert_eq!(report.failed(), 2);
+    let summary = report.summary();
+    assert!(
+        summary.contains("3 programs: 1 passed, 2 failed"),
+        "{}",
+        summary
+    );
+    assert!(summary.contains("D1: expected $00000008, got $00000007"));
+    assert!(summary.contains("did not halt"));
+}
+
+// Timer mit 1000 Zyklen Periode auf Level 1 (Autovektor 25 an $64)
+const TIMER_PROGRAM: &str = "STACK       EQU     $8000
+            ORG     $64
+            DC.L    TICK
+            ORG     $1000
+START:      MOVEA.L #STACK, A7
+LOOP:       ADDQ.L  #1, D0
+            BRA     LOOP
+TICK:       ADDQ.L  #1, D1
+            RTE
+";
+
+/// Schrittnummern, vor denen ein Interrupt angenommen wurde
+fn timer_interrupt_steps() -> Vec<u64> {
+    let mut emulator = Emulator::new();
+    emulator.attach_device(Box::new(Timer::new(1000, 1)));
+    emulator.load_source(TIMER_PROGRAM).unwrap();
+    emulator.cpu_mut().set_sr(0x2000); // Maske 0: Interrupts freigeben
+
+    let mut steps = Vec::new();
+    for step in 1..=500 {
+        emulator.step();
+        if emulator.last_interrupt().is_some() {
+            steps.push(step);
+            assert_eq!(emulator.cpu().get_sr() & 0x0700, 0x0100, "mask raised");
+        }
+    }
+    assert_eq!(emulator.interrupts(), steps.len() as u64);
+    assert_eq!(emulator.cpu().get_data_register(1), steps.len() as u32);
+    steps
+}
+
+#[test]
+fn test_timer_interrupt_timing() {
+    // MOVEA.L (12 Zyklen), danach ADDQ (4) und BRA (10) im Wechsel:
+    // nach 143 Instruktionen sind 12 + 70 * 14 + 4 + 10 = 1006 Zyklen vergangen
+    let steps = timer_interrupt_steps();
+    assert_eq!(steps[0], 144, "handler runs right after instruction 143");
+
+    // Danach einer pro 1000 Zyklen, die Annahme (44 Zyklen) eingerechnet
+    assert_eq!(steps.len(), 3);
+
+    // Gleiche Konfiguration, gleiches Timing
+    assert_eq!(timer_interrupt_steps(), steps);
+}
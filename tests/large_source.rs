@@ -0,0 +1,99 @@
+// Laufzeit bei sehr großen Quelltexten (generierte Tabellen)
+// Läuft nur auf Wunsch, am besten optimiert:
+//     cargo test --release --test large_source -- --ignored
+
+use mc68000::theme::LineTokenCache;
+use mc68000::{Assembler, Emulator};
+use std::time::{Duration, Instant};
+
+const LINES: usize = 50_000;
+
+/// Programm mit einer Datentabelle aus `lines` DC.W-Zeilen
+fn data_table(lines: usize) -> String {
+    let mut source = String::from("        ORG     $1000\n        SIMHALT\nTABLE:\n");
+    for i in 0..lines {
+        source.push_str(&format!(
+            "        DC.W    ${:04X}   ; Eintrag {}\n",
+            i & 0xFFFF,
+            i
+        ));
+    }
+    source
+}
+
+/// Programm aus `lines` Instruktionen
+fn straight_code(lines: usize) -> String {
+    let mut source = String::from("        ORG     $1000\n");
+    for i in 0..lines {
+        source.push_str(&format!("        ADDQ.W  #{}, D{}\n", i % 8 + 1, i % 8));
+    }
+    source.push_str("        SIMHALT\n");
+    source
+}
+
+#[test]
+#[ignore = "performance, run with --release -- --ignored"]
+fn test_large_data_table_assembles_quickly() {
+    let source = data_table(LINES);
+    let start = Instant::now();
+    let program = Assembler::new().assemble_source(&source).unwrap();
+    let elapsed = start.elapsed();
+
+    assert_eq!(program.data.len(), 2 * LINES);
+    assert!(
+        elapsed < Duration::from_secs(1),
+        "{} DC.W lines took {:?}",
+        LINES,
+        elapsed
+    );
+
+    let mut emulator = Emulator::new();
+    let start = Instant::now();
+    emulator.load_source(&source).unwrap();
+    assert!(
+        start.elapsed() < Duration::from_secs(1),
+        "load took {:?}",
+        start.elapsed()
+    );
+    assert_eq!(emulator.memory().read_word(program.symbols["TABLE"] + 2), 1);
+}
+
+#[test]
+#[ignore = "performance, run with --release -- --ignored"]
+fn test_large_code_assembles_quickly() {
+    let source = straight_code(LINES);
+    let start = Instant::now();
+    let program = Assembler::new().assemble_source(&source).unwrap();
+    let elapsed = start.elapsed();
+
+    assert_eq!(program.source_map.len(), LINES + 1);
+    assert!(
+        elapsed < Duration::from_secs(1),
+        "{} instructions took {:?}",
+        LINES,
+        elapsed
+    );
+}
+
+#[test]
+#[ignore = "performance, run with --release -- --ignored"]
+fn test_highlighting_one_screen_stays_cheap() {
+    let source = data_table(LINES);
+    let mut cache = LineTokenCache::default();
+
+    // Ein Frame zeichnet etwa 60 Zeilen; 1000 Frames beim Scrollen durch die Tabelle
+    let start = Instant::now();
+    for frame in 0..1000 {
+        let first = frame * 37 % (LINES - 60);
+        let line_count = source.lines().count();
+        cache.truncate(line_count);
+        for (index, line) in source.lines().enumerate().skip(first).take(60) {
+            cache.tokens(index, line);
+        }
+    }
+    assert!(
+        start.elapsed() < Duration::from_secs(2),
+        "1000 frames took {:?}",
+        start.elapsed()
+    );
+}
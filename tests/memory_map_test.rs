@@ -0,0 +1,113 @@
+// Speicherkarte: Sections, Geräte und Stack in einer sortierten Liste
+use mc68000::address_map::{MapEntry, MapTable, RegionKind};
+use mc68000::{Assembler, Disk, Emulator, Rng};
+
+const PROGRAM: &str = "
+            ORG     $1000
+START:      MOVEA.L #TABLE, A0
+            MOVE.W  (A0), D0
+            SIMHALT
+            SECTION TABLES
+            ORG     $3000
+TABLE:      DC.W    1, 2, 3, 4
+";
+
+fn entry(kind: RegionKind, name: &str, start: u32, end: u32) -> MapEntry {
+    MapEntry {
+        name: name.to_string(),
+        kind,
+        range: start..end,
+        source: None,
+    }
+}
+
+/// Geräte an eigenen Adressen und ein vorgegebener Stack
+fn custom_layout() -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.attach_device(Box::new(Disk::new(0xC000, 2)));
+    emulator.attach_device(Box::new(Rng::new(0xA000, 1)));
+    emulator.set_initial_sp_override(Some(0x9000));
+    emulator
+}
+
+#[test]
+fn test_two_sections_on_custom_layout() {
+    let mut emulator = custom_layout();
+    emulator.load_source(PROGRAM).unwrap();
+
+    let map = emulator.memory_map();
+    assert_eq!(
+        map,
+        [
+            entry(RegionKind::Ram, "RAM", 0, 0x0100_0000),
+            entry(RegionKind::Vectors, "Vektortabelle", 0, 0x400),
+            entry(RegionKind::Code, "CODE", 0x1000, 0x100A),
+            entry(RegionKind::Data, "TABLES", 0x3000, 0x3008),
+            entry(RegionKind::Stack, "Stack", 0x8F00, 0x9000),
+            entry(RegionKind::Device, "rng", 0xA000, 0xA008),
+            entry(RegionKind::Device, "disk", 0xC000, 0xC018),
+        ]
+    );
+
+    let table = MapTable(&map).to_string();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 8);
+    assert_eq!(
+        lines[4],
+        "$003000  $003007         8  data     TABLES         -"
+    );
+    // Die letzte Spalte beginnt in jeder Zeile an derselben Stelle
+    let source_column = |line: &str| line.rfind("  ");
+    assert!(lines
+        .iter()
+        .all(|line| source_column(line) == source_column(lines[0])));
+
+    // Nach dem Hot Reload stimmt die Karte mit dem neuen Code überein
+    let longer = PROGRAM.replace("SIMHALT", "NOP\n            SIMHALT");
+    emulator.reload_code(&longer).unwrap();
+    let code = &emulator.memory_map()[2];
+    assert_eq!(code.range, 0x1000..0x100C);
+
+    // Ohne Stack-Vorgabe und ohne STACK im Quelltext gibt es keinen Stack-Eintrag
+    let mut emulator = Emulator::new();
+    emulator.load_source(PROGRAM).unwrap();
+    assert!(emulator
+        .memory_map()
+        .iter()
+        .all(|entry| entry.kind != RegionKind::Stack));
+}
+
+#[test]
+fn test_linked_modules_name_their_source() {
+    let mut assembler = Assembler::new();
+    assembler
+        .assemble_module(
+            "os",
+            "        ORG $4000\n        XDEF OS\nOS:     JMP (A6)\n",
+        )
+        .unwrap();
+    assembler
+        .assemble_module(
+            "user",
+            "        ORG $1000\n        XREF OS\nSTART:  JMP OS\n        SECTION VARS\n        ORG $2000\n        DS.W 1\n        DC.W 7\n",
+        )
+        .unwrap();
+
+    let mut emulator = custom_layout();
+    emulator.load_program(assembler.link().unwrap());
+    let map = emulator.memory_map();
+    let sections: Vec<(&str, Option<&str>)> = map
+        .iter()
+        .filter(|e| matches!(e.kind, RegionKind::Code | RegionKind::Data))
+        .map(|e| (e.name.as_str(), e.source.as_deref()))
+        .collect();
+    assert_eq!(
+        sections,
+        [
+            ("CODE", Some("user")),
+            ("VARS", Some("user")),
+            ("CODE", Some("os"))
+        ]
+    );
+    assert_eq!(emulator.cpu().get_pc(), 0x1000);
+}
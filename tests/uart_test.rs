@@ -0,0 +1,137 @@
+// UART am Systembus: Echo-Beispiel, Timing aus Sicht des Programms und Empfangs-Interrupt
+use mc68000::uart::{
+    CONTROL_RX_IRQ, REG_CONTROL, REG_STATUS, STATUS_OVERRUN, STATUS_RX_FULL, STATUS_TX_EMPTY,
+};
+use mc68000::{Emulator, RunOutcome, Uart};
+
+const UART_ECHO: &str = include_str!("../uart_echo.asm");
+/// 1000 Zyklen pro Byte
+const BAUD: u32 = 80_000;
+
+#[test]
+fn test_echo_example_returns_input() {
+    let mut emulator = Emulator::new();
+    let host = emulator.attach_uart(BAUD);
+    emulator.load_source(UART_ECHO).unwrap();
+    host.send(b"hallo\n");
+
+    assert_eq!(emulator.run(100_000), RunOutcome::Halted);
+    assert_eq!(host.receive(), b"hallo\n");
+    assert_eq!(emulator.cpu().get_data_register(7), 6);
+    // Das letzte Byte kommt nach 6000 Zyklen an und braucht 1000 zum Hinausschieben;
+    // das Programm wartet darauf, bevor es anhält
+    assert!(emulator.cpu().cycles() >= 7000);
+}
+
+#[test]
+fn test_uart_follows_emulator_clock() {
+    // 1 MHz statt 8 MHz: ein Byte dauert 125 statt 1000 Zyklen, auch für einen UART, der
+    // schon vor der Umstellung angeschlossen war
+    let mut emulator = Emulator::new();
+    let host = emulator.attach_uart(BAUD);
+    emulator.set_clock_hz(1_000_000);
+    host.send(b"a");
+    emulator.tick_devices(124);
+    let status = Uart::DEFAULT_BASE + REG_STATUS;
+    assert_eq!(emulator.memory().read_byte(status) & STATUS_RX_FULL, 0);
+    emulator.tick_devices(1);
+    assert_ne!(emulator.memory().read_byte(status) & STATUS_RX_FULL, 0);
+}
+
+#[test]
+fn test_idle_loop_detection_with_uart() {
+    // Bei 9600 Baud dauert ein Byte über 1000 Schritte der Warteschleifen; solange es
+    // unterwegs ist, ist das kein Leerlauf
+    let mut emulator = Emulator::new();
+    let host = emulator.attach_uart(Uart::DEFAULT_BAUD);
+    emulator.load_source(UART_ECHO).unwrap();
+    assert!(emulator.idle_loop_detection());
+    host.send(b"ok\n");
+    assert_eq!(emulator.run(1_000_000), RunOutcome::Halted);
+    assert_eq!(host.receive(), b"ok\n");
+
+    // Ohne Eingabe wartet das Programm vergeblich
+    emulator.reset();
+    assert!(matches!(
+        emulator.run(1_000_000),
+        RunOutcome::IdleLoopDetected { .. }
+    ));
+}
+
+#[test]
+fn test_program_sees_flags_at_byte_boundaries() {
+    let mut emulator = Emulator::new();
+    let host = emulator.attach_uart(BAUD);
+    emulator
+        .load_source(
+            "        ORG     $1000
+LOOP:   BRA     LOOP",
+        )
+        .unwrap();
+    host.send(b"AB");
+    let status = |emulator: &Emulator| emulator.memory().read_byte(Uart::DEFAULT_BASE + REG_STATUS);
+
+    // Geräte ohne CPU weiterlaufen lassen: 'A' ist bei Zyklus 1000 fertig
+    emulator.tick_devices(999);
+    assert_eq!(status(&emulator), STATUS_TX_EMPTY);
+    emulator.tick_devices(1);
+    assert_eq!(status(&emulator), STATUS_TX_EMPTY | STATUS_RX_FULL);
+    assert_eq!(emulator.memory().read_byte(Uart::DEFAULT_BASE), b'A');
+
+    // Programm holt nichts ab: 'B' bei Zyklus 2000 läuft über, 'A' bleibt
+    emulator.tick_devices(1000);
+    assert_eq!(status(&emulator) & STATUS_OVERRUN, STATUS_OVERRUN);
+    assert_eq!(emulator.memory().read_byte(Uart::DEFAULT_BASE), b'A');
+}
+
+#[test]
+fn test_receive_interrupt_runs_handler() {
+    // Handler auf Autovektor Level 4 holt das Byte nach D0 und hält an
+    let source = "UART_DATA:  EQU     $E200
+            ORG     $70
+            DC.L    HANDLER
+            ORG     $1000
+START:      BRA     START
+HANDLER:    MOVEA.L #UART_DATA, A0
+            MOVE.B  (A0), D0
+            SIMHALT
+            END     START
+";
+    let mut emulator = Emulator::new();
+    let host = emulator.attach_uart(BAUD);
+    emulator.load_source(source).unwrap();
+    emulator
+        .memory_mut()
+        .write_byte(Uart::DEFAULT_BASE + REG_CONTROL, CONTROL_RX_IRQ);
+    emulator.cpu_mut().set_sr(0x2000);
+    host.send(b"!");
+
+    assert_eq!(emulator.run(10_000), RunOutcome::Halted);
+    assert_eq!(emulator.interrupts(), 1);
+    assert_eq!(emulator.cpu().get_data_register(0), b'!' as u32);
+    // Angenommen wird erst nach einem vollen Byte
+    assert!(emulator.cpu().cycles() >= 1000);
+}
+
+#[test]
+fn test_cli_stdout_carries_only_guest_bytes() {
+    // echo hallo | mc68000 --uart uart_echo.asm
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mc68000"))
+        .args(["--uart", "--trace", "uart_echo.asm"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"hallo\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hallo\n");
+    // Der Trace landet auf stderr
+    assert!(String::from_utf8_lossy(&output.stderr).contains("MOVEA.L"));
+}
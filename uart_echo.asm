@@ -0,0 +1,36 @@
+; ============================================================
+; Programm: Echo über die serielle Schnittstelle
+; Erwartet den UART an $E200 (siehe src/uart.rs). Jedes empfangene
+; Byte wird zurückgeschickt, bis ein Zeilenende ($0A) kam.
+; Anzahl der Bytes in D7
+; Ausprobieren mit: echo hallo | mc68000 --uart uart_echo.asm
+; ============================================================
+
+UART_DATA:   EQU     $E200
+UART_STATUS: EQU     $E202
+
+            ORG     $1000
+
+START:      MOVEA.L #UART_DATA, A0
+            MOVEA.L #UART_STATUS, A1
+            MOVEQ   #0, D7
+
+WAIT_RX:    MOVE.B  (A1), D1        ; Bit 7 = RX_FULL landet im N-Flag
+            BPL     WAIT_RX
+            MOVE.B  (A0), D0        ; Byte abholen, löscht RX_FULL
+            ADDQ.L  #1, D7
+
+WAIT_TX:    MOVE.B  (A1), D1
+            ASL.B   #1, D1          ; Bit 6 = TX_EMPTY ins N-Flag
+            BPL     WAIT_TX
+            MOVE.B  D0, (A0)        ; senden
+
+            CMPI.B  #$0A, D0
+            BNE     WAIT_RX
+
+DRAIN:      MOVE.B  (A1), D1        ; letztes Byte ganz hinausschieben lassen
+            ASL.B   #1, D1
+            BPL     DRAIN
+            SIMHALT
+
+            END     START